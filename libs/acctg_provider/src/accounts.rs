@@ -3,6 +3,8 @@
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
+use provider_error::ProviderError;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AccountType {
     pub account_type_id: i16,
@@ -43,55 +45,55 @@ pub struct AccountNode {
 pub trait AccountsProvider {
     fn account_types_fetch(
         &self,
-    ) -> impl Future<Output = Result<Vec<AccountType>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<AccountType>, ProviderError>> + Send;
 
     fn account_categories_fetch(
         &self,
-    ) -> impl Future<Output = Result<Vec<AccountCategory>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<AccountCategory>, ProviderError>> + Send;
 
     fn accounts_fetch_all(
         &self,
         tenant_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Vec<Account>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Account>, ProviderError>> + Send;
 
     fn accounts_fetch_by_type(
         &self,
         tenant_id: &uuid::Uuid,
         type_id: &i16,
-    ) -> impl Future<Output = Result<Vec<Account>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Account>, ProviderError>> + Send;
 
     fn accounts_fetch(
         &self,
         tenant_id: &uuid::Uuid,
         account_type_id: &i16,
         filter: &str,
-    ) -> impl Future<Output = Result<Vec<Account>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Account>, ProviderError>> + Send;
 
     fn accounts_fetch_tree(
         &self,
         tenant_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Vec<AccountNode>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<AccountNode>, ProviderError>> + Send;
 
     fn account_fetch(
         &self,
         account_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Account, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Account, ProviderError>> + Send;
 
     fn account_fetch_by_name(
         &self,
         tenant_id: &uuid::Uuid,
         name: &str,
-    ) -> impl Future<Output = Result<Account, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Account, ProviderError>> + Send;
 
     fn account_fetch_children(
         &self,
         account_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Vec<Account>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Account>, ProviderError>> + Send;
 
     fn account_save(
         &self,
         tenant_id: &uuid::Uuid,
         account: &Account,
         parent_account_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 }
@@ -3,6 +3,8 @@
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
+use provider_error::ProviderError;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InvoiceType {
     pub id: i16,
@@ -44,28 +46,28 @@ pub struct Invoice {
 pub trait InvoiceProvider {
     fn invoice_types_fetch(
         &self,
-    ) -> impl Future<Output = Result<Vec<InvoiceType>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<InvoiceType>, ProviderError>> + Send;
 
     fn invoices_fetch(
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> impl Future<Output = Result<Vec<Invoice>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Invoice>, ProviderError>> + Send;
 
     fn invoice_fetch(
         &self,
         invoice_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Invoice, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Invoice, ProviderError>> + Send;
 
     fn invoice_save(
         &self,
         tenant_id: &uuid::Uuid,
         invoice: &Invoice,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     // fn invoice_items_save(
     //     &self,
     //     invoice_id: &uuid::Uuid,
     //     items: &Vec<InvoiceItem>,
-    // ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    // ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 }
@@ -0,0 +1,658 @@
+-- chart of accounts and invoices
+
+create schema if not exists acctg;
+
+
+create table acctg.account_types (
+    account_type_id smallint not null,
+    name text not null,
+    constraint pk_account_types primary key (account_type_id)
+);
+
+insert into acctg.account_types (account_type_id, name) values
+    (1, 'asset'),
+    (2, 'liability'),
+    (3, 'equity'),
+    (4, 'revenue'),
+    (5, 'expense');
+
+create table acctg.account_categories (
+    acct_category_id smallint not null,
+    account_type_id smallint not null,
+    name text not null,
+    sub_name text not null default '',
+    constraint pk_account_categories primary key (acct_category_id),
+    constraint fk_account_categories_type foreign key (account_type_id) references acctg.account_types (account_type_id)
+);
+
+insert into acctg.account_categories (acct_category_id, account_type_id, name, sub_name) values
+    (1, 1, 'asset', 'current'),
+    (2, 1, 'asset', 'non-current'),
+    (3, 2, 'liability', 'current'),
+    (4, 2, 'liability', 'non-current'),
+    (5, 3, 'equity', ''),
+    (6, 4, 'revenue', 'operating'),
+    (7, 4, 'revenue', 'non-operating'),
+    (8, 5, 'expense', 'operating'),
+    (9, 5, 'expense', 'non-operating');
+
+create table acctg.accounts (
+    account_id uuid not null,
+    tenant_id uuid not null,
+    active boolean not null default true,
+    created_ts timestamptz not null default now(),
+    updated_ts timestamptz not null default now(),
+    account_type_id smallint not null,
+    account_category_id smallint not null,
+    code text not null default '',
+    name text not null,
+    description text not null default '',
+    constraint pk_accounts primary key (account_id),
+    constraint fk_accounts_tenant foreign key (tenant_id) references tenants.tenants (tenant_id),
+    constraint fk_accounts_type foreign key (account_type_id) references acctg.account_types (account_type_id),
+    constraint fk_accounts_category foreign key (account_category_id) references acctg.account_categories (acct_category_id)
+);
+
+create index i_accounts_tenant on acctg.accounts (tenant_id);
+
+-- a null parent marks a top level account
+create table acctg.account_hierarchy (
+    account_id uuid not null,
+    tenant_id uuid not null,
+    parent_account_id uuid,
+    constraint pk_account_hierarchy primary key (account_id),
+    constraint fk_account_hierarchy_account foreign key (account_id) references acctg.accounts (account_id) on delete cascade,
+    constraint fk_account_hierarchy_parent foreign key (parent_account_id) references acctg.accounts (account_id),
+    constraint fk_account_hierarchy_tenant foreign key (tenant_id) references tenants.tenants (tenant_id)
+);
+
+create index i_account_hierarchy_parent on acctg.account_hierarchy (parent_account_id);
+
+
+create procedure acctg.account_save(
+    p_tenant_id uuid,
+    p_account_id uuid,
+    p_account_type_id smallint,
+    p_account_category_id smallint,
+    p_code text,
+    p_name text,
+    p_description text
+)
+language sql
+as $$
+    insert into acctg.accounts as a (
+        account_id,
+        tenant_id,
+        account_type_id,
+        account_category_id,
+        code,
+        name,
+        description
+    ) values (
+        p_account_id,
+        p_tenant_id,
+        p_account_type_id,
+        p_account_category_id,
+        p_code,
+        p_name,
+        p_description
+    )
+    on conflict (account_id) do update set
+        account_type_id = excluded.account_type_id,
+        account_category_id = excluded.account_category_id,
+        code = excluded.code,
+        name = excluded.name,
+        description = excluded.description,
+        updated_ts = now()
+    where a.tenant_id = excluded.tenant_id;
+$$;
+
+-- the nil uuid as parent makes the account a top level account
+create procedure acctg.account_hierarchy_save(
+    p_tenant_id uuid,
+    p_account_id uuid,
+    p_parent_account_id uuid
+)
+language sql
+as $$
+    insert into acctg.account_hierarchy as h (
+        account_id,
+        tenant_id,
+        parent_account_id
+    ) values (
+        p_account_id,
+        p_tenant_id,
+        nullif(p_parent_account_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    )
+    on conflict (account_id) do update set
+        parent_account_id = excluded.parent_account_id
+    where h.tenant_id = excluded.tenant_id;
+$$;
+
+
+create function acctg.account_types_fetch()
+returns table (
+    account_type_id smallint,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        t.account_type_id,
+        t.name
+    from acctg.account_types t
+    order by t.account_type_id;
+$$;
+
+create function acctg.account_categories_fetch()
+returns table (
+    acct_category_id smallint,
+    name text,
+    sub_name text
+)
+language sql
+stable
+as $$
+    select
+        c.acct_category_id,
+        c.name,
+        c.sub_name
+    from acctg.account_categories c
+    order by c.acct_category_id;
+$$;
+
+create function acctg.accounts_fetch_all(
+    p_tenant_id uuid
+)
+returns table (
+    account_id uuid,
+    active boolean,
+    account_type_id smallint,
+    account_category_id smallint,
+    name text,
+    code text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        a.account_id,
+        a.active,
+        a.account_type_id,
+        a.account_category_id,
+        a.name,
+        a.code,
+        a.description
+    from acctg.accounts a
+    where a.tenant_id = p_tenant_id
+    order by a.code, a.name;
+$$;
+
+create function acctg.accounts_fetch_by_type(
+    p_tenant_id uuid,
+    p_account_type_id smallint
+)
+returns table (
+    account_id uuid,
+    active boolean,
+    account_type_id smallint,
+    account_category_id smallint,
+    name text,
+    code text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        a.account_id,
+        a.active,
+        a.account_type_id,
+        a.account_category_id,
+        a.name,
+        a.code,
+        a.description
+    from acctg.accounts a
+    where a.tenant_id = p_tenant_id
+        and a.account_type_id = p_account_type_id
+    order by a.code, a.name;
+$$;
+
+create function acctg.accounts_fetch(
+    p_tenant_id uuid,
+    p_account_type_id smallint,
+    p_filter text
+)
+returns table (
+    account_id uuid,
+    active boolean,
+    account_type_id smallint,
+    account_category_id smallint,
+    name text,
+    code text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        a.account_id,
+        a.active,
+        a.account_type_id,
+        a.account_category_id,
+        a.name,
+        a.code,
+        a.description
+    from acctg.accounts a
+    where a.tenant_id = p_tenant_id
+        and a.account_type_id = p_account_type_id
+        and (
+            a.name ilike '%' || p_filter || '%'
+            or a.code ilike '%' || p_filter || '%'
+        )
+    order by a.code, a.name;
+$$;
+
+create function acctg.account_fetch(
+    p_account_id uuid
+)
+returns table (
+    account_id uuid,
+    active boolean,
+    account_type_id smallint,
+    account_category_id smallint,
+    name text,
+    code text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        a.account_id,
+        a.active,
+        a.account_type_id,
+        a.account_category_id,
+        a.name,
+        a.code,
+        a.description
+    from acctg.accounts a
+    where a.account_id = p_account_id;
+$$;
+
+create function acctg.account_fetch_by_name(
+    p_tenant_id uuid,
+    p_name text
+)
+returns table (
+    account_id uuid,
+    active boolean,
+    account_type_id smallint,
+    account_category_id smallint,
+    name text,
+    code text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        a.account_id,
+        a.active,
+        a.account_type_id,
+        a.account_category_id,
+        a.name,
+        a.code,
+        a.description
+    from acctg.accounts a
+    where a.tenant_id = p_tenant_id
+        and lower(a.name) = lower(p_name);
+$$;
+
+create function acctg.account_fetch_children(
+    p_account_id uuid
+)
+returns table (
+    account_id uuid,
+    active boolean,
+    account_type_id smallint,
+    account_category_id smallint,
+    name text,
+    code text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        a.account_id,
+        a.active,
+        a.account_type_id,
+        a.account_category_id,
+        a.name,
+        a.code,
+        a.description
+    from acctg.account_hierarchy h
+        join acctg.accounts a on a.account_id = h.account_id
+    where h.parent_account_id = p_account_id
+    order by a.code, a.name;
+$$;
+
+-- top level accounts are returned with the nil uuid as parent
+create function acctg.accounts_fetch_tree(
+    p_tenant_id uuid
+)
+returns table (
+    account_id uuid,
+    parent_account_id uuid,
+    name text,
+    level int,
+    path text
+)
+language sql
+stable
+as $$
+    with recursive tree as (
+        select
+            a.account_id,
+            '00000000-0000-0000-0000-000000000000'::uuid as parent_account_id,
+            a.name,
+            0 as level,
+            a.name as path
+        from acctg.accounts a
+            left join acctg.account_hierarchy h on h.account_id = a.account_id
+        where a.tenant_id = p_tenant_id
+            and h.parent_account_id is null
+        union all
+        select
+            a.account_id,
+            h.parent_account_id,
+            a.name,
+            t.level + 1,
+            t.path || '/' || a.name
+        from acctg.account_hierarchy h
+            join acctg.accounts a on a.account_id = h.account_id
+            join tree t on t.account_id = h.parent_account_id
+    )
+    select
+        t.account_id,
+        t.parent_account_id,
+        t.name,
+        t.level,
+        t.path
+    from tree t
+    order by t.path;
+$$;
+
+
+create table acctg.invoice_types (
+    invoice_type_id smallint not null,
+    name text not null,
+    constraint pk_invoice_types primary key (invoice_type_id)
+);
+
+insert into acctg.invoice_types (invoice_type_id, name) values
+    (1, 'sales'),
+    (2, 'purchase');
+
+create sequence acctg.invoice_id_seq as int;
+
+-- org_id is not constrained, a tenant without organizations invoices as itself
+create table acctg.invoices (
+    invoice_id uuid not null,
+    tenant_id uuid not null,
+    invoice_type_id smallint not null,
+    invoice_id_seq int not null default nextval('acctg.invoice_id_seq'),
+    account_id uuid not null,
+    org_id uuid not null,
+    partner_id uuid not null,
+    active boolean not null default true,
+    version int not null default 0,
+    created_ts timestamptz not null default now(),
+    updated_ts timestamptz not null default now(),
+    due_date_ts timestamptz,
+    description text not null default '',
+    constraint pk_invoices primary key (invoice_id),
+    constraint u_invoices_seq unique (invoice_id_seq),
+    constraint fk_invoices_tenant foreign key (tenant_id) references tenants.tenants (tenant_id),
+    constraint fk_invoices_type foreign key (invoice_type_id) references acctg.invoice_types (invoice_type_id),
+    constraint fk_invoices_account foreign key (account_id) references acctg.accounts (account_id),
+    constraint fk_invoices_partner foreign key (partner_id) references crm.partners (partner_id)
+);
+
+alter sequence acctg.invoice_id_seq owned by acctg.invoices.invoice_id_seq;
+
+create index i_invoices_tenant on acctg.invoices (tenant_id);
+
+create table acctg.invoice_items (
+    invoice_item_id uuid not null,
+    invoice_id uuid not null,
+    version int not null default 0,
+    description text not null default '',
+    quantity numeric not null,
+    unit_price numeric not null,
+    currency_id int not null,
+    constraint pk_invoice_items primary key (invoice_item_id),
+    constraint fk_invoice_items_invoice foreign key (invoice_id) references acctg.invoices (invoice_id) on delete cascade
+);
+
+create index i_invoice_items_invoice on acctg.invoice_items (invoice_id);
+
+create type acctg.invoice_item_type as (
+    invoice_item_id uuid,
+    version int,
+    description text,
+    quantity numeric,
+    unit_price numeric,
+    currency_id int
+);
+
+
+create procedure acctg.invoice_save(
+    p_tenant_id uuid,
+    p_invoice_id uuid,
+    p_invoice_type_id smallint,
+    p_account_id uuid,
+    p_org_id uuid,
+    p_partner_id uuid,
+    p_description text,
+    p_due_date timestamptz,
+    p_version int
+)
+language sql
+as $$
+    insert into acctg.invoices as i (
+        invoice_id,
+        tenant_id,
+        invoice_type_id,
+        account_id,
+        org_id,
+        partner_id,
+        description,
+        due_date_ts,
+        version
+    ) values (
+        p_invoice_id,
+        p_tenant_id,
+        p_invoice_type_id,
+        p_account_id,
+        p_org_id,
+        p_partner_id,
+        p_description,
+        p_due_date,
+        p_version
+    )
+    on conflict (invoice_id) do update set
+        invoice_type_id = excluded.invoice_type_id,
+        account_id = excluded.account_id,
+        org_id = excluded.org_id,
+        partner_id = excluded.partner_id,
+        description = excluded.description,
+        due_date_ts = excluded.due_date_ts,
+        version = i.version + 1,
+        updated_ts = now()
+    where i.tenant_id = excluded.tenant_id;
+$$;
+
+-- replaces the items of an invoice with the given items
+create procedure acctg.invoice_items_save(
+    p_invoice_id uuid,
+    p_items acctg.invoice_item_type[]
+)
+language sql
+as $$
+    delete from acctg.invoice_items
+    where invoice_id = p_invoice_id
+        and invoice_item_id not in (
+            select t.invoice_item_id from unnest(p_items) t
+        );
+
+    insert into acctg.invoice_items as ii (
+        invoice_item_id,
+        invoice_id,
+        version,
+        description,
+        quantity,
+        unit_price,
+        currency_id
+    )
+    select
+        t.invoice_item_id,
+        p_invoice_id,
+        t.version,
+        t.description,
+        t.quantity,
+        t.unit_price,
+        t.currency_id
+    from unnest(p_items) t
+    on conflict (invoice_item_id) do update set
+        version = ii.version + 1,
+        description = excluded.description,
+        quantity = excluded.quantity,
+        unit_price = excluded.unit_price,
+        currency_id = excluded.currency_id
+    where ii.invoice_id = excluded.invoice_id;
+$$;
+
+
+create function acctg.invoice_types_fetch()
+returns table (
+    invoice_type_id smallint,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        t.invoice_type_id,
+        t.name
+    from acctg.invoice_types t
+    order by t.invoice_type_id;
+$$;
+
+create function acctg.invoices_fetch(
+    p_tenant_id uuid,
+    p_filter text
+)
+returns table (
+    invoice_id uuid,
+    invoice_type_id smallint,
+    invoice_id_seq int,
+    account_id uuid,
+    org_id uuid,
+    partner_id uuid,
+    active boolean,
+    version int,
+    created_ts timestamptz,
+    updated_ts timestamptz,
+    due_date_ts timestamptz,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        i.invoice_id,
+        i.invoice_type_id,
+        i.invoice_id_seq,
+        i.account_id,
+        i.org_id,
+        i.partner_id,
+        i.active,
+        i.version,
+        i.created_ts,
+        i.updated_ts,
+        i.due_date_ts,
+        i.description
+    from acctg.invoices i
+    where i.tenant_id = p_tenant_id
+        and (
+            i.description ilike '%' || p_filter || '%'
+            or i.invoice_id_seq::text like '%' || p_filter || '%'
+        )
+    order by i.invoice_id_seq desc;
+$$;
+
+create function acctg.invoice_fetch(
+    p_invoice_id uuid
+)
+returns table (
+    invoice_id uuid,
+    invoice_type_id smallint,
+    invoice_id_seq int,
+    account_id uuid,
+    org_id uuid,
+    partner_id uuid,
+    active boolean,
+    version int,
+    created_ts timestamptz,
+    updated_ts timestamptz,
+    due_date_ts timestamptz,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        i.invoice_id,
+        i.invoice_type_id,
+        i.invoice_id_seq,
+        i.account_id,
+        i.org_id,
+        i.partner_id,
+        i.active,
+        i.version,
+        i.created_ts,
+        i.updated_ts,
+        i.due_date_ts,
+        i.description
+    from acctg.invoices i
+    where i.invoice_id = p_invoice_id;
+$$;
+
+create function acctg.invoice_items_fetch(
+    p_invoice_id uuid
+)
+returns table (
+    invoice_item_id uuid,
+    version int,
+    description text,
+    quantity numeric,
+    unit_price numeric,
+    currency_id int
+)
+language sql
+stable
+as $$
+    select
+        ii.invoice_item_id,
+        ii.version,
+        ii.description,
+        ii.quantity,
+        ii.unit_price,
+        ii.currency_id
+    from acctg.invoice_items ii
+    where ii.invoice_id = p_invoice_id;
+$$;
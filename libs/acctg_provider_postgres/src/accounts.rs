@@ -10,6 +10,8 @@ use acctg_provider::accounts::{
     Account, AccountCategory, AccountNode, AccountType, AccountsProvider,
 };
 
+use provider_error::ProviderError;
+
 pub struct AccountTypeItem(pub AccountType);
 
 impl<'r> FromRow<'r, PgRow> for AccountTypeItem {
@@ -143,7 +145,7 @@ impl AccountsProviderPostgres {
 }
 
 impl AccountsProvider for AccountsProviderPostgres {
-    async fn account_types_fetch(&self) -> Result<Vec<AccountType>, &'static str> {
+    async fn account_types_fetch(&self) -> Result<Vec<AccountType>, ProviderError> {
         info!("account_types_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -153,7 +155,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching account types: {:?}", e);
-                    return Err("Error fetching account types");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     return Ok(rows.into_iter().map(|r| r.0).collect());
@@ -161,13 +163,13 @@ impl AccountsProvider for AccountsProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn account_categories_fetch(
         &self,
-    ) -> Result<Vec<acctg_provider::accounts::AccountCategory>, &'static str> {
+    ) -> Result<Vec<acctg_provider::accounts::AccountCategory>, ProviderError> {
         info!("account_categories_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -179,7 +181,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching account categories: {:?}", e);
-                    return Err("Error fetching account categories");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     return Ok(rows.iter().map(|r| r.0.clone()).collect());
@@ -187,14 +189,14 @@ impl AccountsProvider for AccountsProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn accounts_fetch_all(
         &self,
         tenant_id: &uuid::Uuid,
-    ) -> Result<Vec<Account>, &'static str> {
+    ) -> Result<Vec<Account>, ProviderError> {
         info!("accounts_fetch_all");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -207,7 +209,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching accounts: {:?}", e);
-                    return Err("Error fetching accounts");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let accounts: Vec<Account> = rows.iter().map(|r| r.0.clone()).collect();
@@ -217,7 +219,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -225,7 +227,7 @@ impl AccountsProvider for AccountsProviderPostgres {
         &self,
         tenant_id: &uuid::Uuid,
         type_id: &i16,
-    ) -> Result<Vec<Account>, &'static str> {
+    ) -> Result<Vec<Account>, ProviderError> {
         info!("accounts_fetch_by_type");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -239,7 +241,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching accounts: {:?}", e);
-                    return Err("Error fetching accounts");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let accounts: Vec<Account> = rows.iter().map(|r| r.0.clone()).collect();
@@ -249,7 +251,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -258,7 +260,7 @@ impl AccountsProvider for AccountsProviderPostgres {
         tenant_id: &uuid::Uuid,
         account_type_id: &i16,
         filter: &str,
-    ) -> Result<Vec<Account>, &'static str> {
+    ) -> Result<Vec<Account>, ProviderError> {
         info!("accounts_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -273,7 +275,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching accounts: {:?}", e);
-                    return Err("Error fetching accounts");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let accounts: Vec<Account> = rows.iter().map(|r| r.0.clone()).collect();
@@ -282,14 +284,14 @@ impl AccountsProvider for AccountsProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn accounts_fetch_tree(
         &self,
         tenant_id: &uuid::Uuid,
-    ) -> Result<Vec<AccountNode>, &'static str> {
+    ) -> Result<Vec<AccountNode>, ProviderError> {
         info!("accounts_fetch_tree");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -300,7 +302,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching accounts: {:?}", e);
-                    return Err("Error fetching accounts");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(accounts) => {
                     // debug!("before accounts_fetch_tree: {:?}", accounts);
@@ -312,11 +314,11 @@ impl AccountsProvider for AccountsProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn account_fetch(&self, account_id: &uuid::Uuid) -> Result<Account, &'static str> {
+    async fn account_fetch(&self, account_id: &uuid::Uuid) -> Result<Account, ProviderError> {
         info!("account_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -327,7 +329,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching account: {:?}", e);
-                    return Err("Error fetching account");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(r) => {
                     return Ok(r.0);
@@ -335,7 +337,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -343,7 +345,7 @@ impl AccountsProvider for AccountsProviderPostgres {
         &self,
         tenant_id: &uuid::Uuid,
         name: &str,
-    ) -> Result<Account, &'static str> {
+    ) -> Result<Account, ProviderError> {
         info!("account_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -357,7 +359,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching account: {:?}", e);
-                    return Err("Error fetching account");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(r) => {
                     return Ok(r.0);
@@ -365,14 +367,14 @@ impl AccountsProvider for AccountsProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn account_fetch_children(
         &self,
         account_id: &uuid::Uuid,
-    ) -> Result<Vec<Account>, &'static str> {
+    ) -> Result<Vec<Account>, ProviderError> {
         info!("accounts_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -385,7 +387,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching accounts: {:?}", e);
-                    return Err("Error fetching accounts");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let accounts: Vec<Account> = rows.iter().map(|r| r.0.clone()).collect();
@@ -394,7 +396,7 @@ impl AccountsProvider for AccountsProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -403,7 +405,7 @@ impl AccountsProvider for AccountsProviderPostgres {
         tenant_id: &uuid::Uuid,
         account: &Account,
         parent_account_id: &uuid::Uuid,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("account_save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -430,7 +432,7 @@ impl AccountsProvider for AccountsProviderPostgres {
                     {
                         Err(e) => {
                             error!("Error setting parent account: {:?}", e);
-                            return Err("Error setting parent account");
+                            return Err(database_provider::from_sqlx(&e));
                         }
                         Ok(_) => {}
                     }
@@ -439,12 +441,12 @@ impl AccountsProvider for AccountsProviderPostgres {
                 }
                 Err(e) => {
                     error!("Error saving accounts: {:?}", e);
-                    return Err("Error saving accounts");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
@@ -478,12 +480,12 @@ mod tests {
             .tenant_id();
 
         if let Err(e) = app.account_types_fetch().await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch account types");
         }
 
         if let Err(e) = app.account_categories_fetch().await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch account categories");
         }
 
@@ -512,37 +514,37 @@ mod tests {
             )
             .await
         {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to save account");
         }
 
         if let Err(e) = app.account_fetch(&account_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch account");
         }
 
         if let Err(e) = app.account_fetch_children(&account_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch account");
         }
 
         if let Err(e) = app.accounts_fetch_all(&tenant_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch accounts");
         }
 
         if let Err(e) = app.accounts_fetch_by_type(&tenant_id, &1).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch accounts by type");
         }
 
         if let Err(e) = app.accounts_fetch(&tenant_id, &1, &"%").await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch accounts by filter");
         }
 
         if let Err(e) = app.accounts_fetch_tree(&tenant_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch accounts tree");
         }
 
@@ -11,6 +11,8 @@ use tracing::{debug, error, info};
 
 use acctg_provider::invoice::{Invoice, InvoiceItem, InvoiceProvider, InvoiceType};
 
+use provider_error::ProviderError;
+
 // #[derive(Debug, Serialize, Deserialize, Type)]
 // #[sqlx(type_name = "acctg.invoice_item_type")]
 // struct InvoiceItemDerived(pub InvoiceItem);
@@ -91,7 +93,7 @@ impl InvoiceProviderPostgres {
 }
 
 impl InvoiceProvider for InvoiceProviderPostgres {
-    async fn invoice_types_fetch(&self) -> Result<Vec<InvoiceType>, &'static str> {
+    async fn invoice_types_fetch(&self) -> Result<Vec<InvoiceType>, ProviderError> {
         info!("invoice_types_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -101,7 +103,7 @@ impl InvoiceProvider for InvoiceProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching invoice types: {:?}", e);
-                    return Err("Error fetching invoice types");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let types: Vec<InvoiceType> = rows.iter().map(|r| r.0.clone()).collect();
@@ -110,7 +112,7 @@ impl InvoiceProvider for InvoiceProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -118,7 +120,7 @@ impl InvoiceProvider for InvoiceProviderPostgres {
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> Result<Vec<Invoice>, &'static str> {
+    ) -> Result<Vec<Invoice>, ProviderError> {
         info!("invoices_fetch");
         // debug!("tenant_id: {:?}, filter: {}", tenant_id, filter);
 
@@ -136,16 +138,16 @@ impl InvoiceProvider for InvoiceProviderPostgres {
                 }
                 Err(e) => {
                     error!("Error fetching invoices: {:?}", e);
-                    return Err("Error fetching invoices");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn invoice_fetch(&self, invoice_id: &uuid::Uuid) -> Result<Invoice, &'static str> {
+    async fn invoice_fetch(&self, invoice_id: &uuid::Uuid) -> Result<Invoice, ProviderError> {
         info!("invoice_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -156,7 +158,7 @@ impl InvoiceProvider for InvoiceProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching invoice: {:?}", e);
-                    return Err("Error fetching invoice");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(row) => {
                     let invoice_items = match sqlx::query_as::<_, InvoiceItemDerived>(
@@ -182,7 +184,7 @@ impl InvoiceProvider for InvoiceProviderPostgres {
                         }
                         Err(e) => {
                             error!("Error fetching invoice: {:?}", e);
-                            // return Err("Error fetching invoice");
+                            // return Err(database_provider::from_sqlx(&e));
                             Vec::new()
                         }
                     };
@@ -206,7 +208,7 @@ impl InvoiceProvider for InvoiceProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -214,7 +216,7 @@ impl InvoiceProvider for InvoiceProviderPostgres {
         &self,
         tenant_id: &uuid::Uuid,
         invoice: &Invoice,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("invoice_save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -234,7 +236,7 @@ impl InvoiceProvider for InvoiceProviderPostgres {
             match pool.begin().await {
                 Err(e) => {
                     error!("Error starting transaction: {:?}", e);
-                    return Err("Error starting transaction");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(mut tx) => {
                     match sqlx::query("call acctg.invoice_save($1,$2,$3,$4,$5,$6,$7,$8,$9);")
@@ -253,7 +255,7 @@ impl InvoiceProvider for InvoiceProviderPostgres {
                     {
                         Err(e) => {
                             error!("Error saving invoice: {:?}", e);
-                            return Err("Error saving invoice");
+                            return Err(database_provider::from_sqlx(&e));
                         }
                         Ok(_) => {
                             match sqlx::query("call acctg.invoice_items_save($1,$2);")
@@ -264,12 +266,12 @@ impl InvoiceProvider for InvoiceProviderPostgres {
                             {
                                 Err(e) => {
                                     error!("Error saving invoice items: {:?}", e);
-                                    return Err("Error saving invoice items");
+                                    return Err(database_provider::from_sqlx(&e));
                                 }
                                 Ok(_) => {
                                     if let Err(e) = tx.commit().await {
                                         error!("Error committing transaction: {:?}", e);
-                                        return Err("Error committing transaction");
+                                        return Err(database_provider::from_sqlx(&e));
                                     }
                                     return Ok(());
                                 }
@@ -280,7 +282,7 @@ impl InvoiceProvider for InvoiceProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
@@ -309,13 +311,13 @@ mod tests {
         let tp = tenants_provider_postgres::PostgresTenantsProvider::new(&dp);
         // let opp = tenants_provider_postgres::organizations::OrganizationsProviderPostgres::new(&dp);
 
-        let cpp = crm_provider_postgres::PostgresCrmProvider::new(&dp);
+        let cpp = crm_provider_postgres::CrmProviderPostgres::new(&dp);
 
         let app = crate::accounts::AccountsProviderPostgres::new(&dp);
         let ipp = InvoiceProviderPostgres::new(&dp);
 
         if let Err(e) = ipp.invoice_types_fetch().await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch invoice types");
         }
 
@@ -377,17 +379,17 @@ mod tests {
         };
 
         if let Err(e) = ipp.invoice_save(&tenant_id, &invoice).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to save invoice");
         }
 
         if let Err(e) = ipp.invoice_fetch(&invoice_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch invoice");
         }
 
         if let Err(e) = ipp.invoices_fetch(&tenant_id, &"%").await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch invoices");
         }
     }
@@ -1,2 +1,16 @@
 pub mod accounts;
 pub mod invoice;
+
+/// schema migrations for the `acctg` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "acctg",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        }
+    ]
+};
+
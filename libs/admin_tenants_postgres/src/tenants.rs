@@ -193,22 +193,22 @@ mod tests {
         let tp = PostgresAdminTenantsProvider::new(&dp);
 
         if let Err(e) = tp.tenant_save(&tenant_id, &name, &description).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to save tenant record");
         }
 
         if let Err(e) = tp.tenant_set_active(&tenant_id, true).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to set tenant active state");
         }
 
         if let Err(e) = tp.tenants_fetch_by_id(&tenant_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch tenant");
         }
 
         if let Err(e) = tp.tenants_fetch("%test%").await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch tenants");
         }
     }
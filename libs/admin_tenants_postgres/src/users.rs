@@ -127,12 +127,12 @@ mod tests {
         let tp = crate::tenants::PostgresAdminTenantsProvider::new(&dp);
 
         if let Err(e) = tp.tenant_save(&tenant_id, &name, &description).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to save tenant record");
         }
 
         if let Err(e) = tp.tenant_set_active(&tenant_id, true).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to set tenant active state");
         }
 
@@ -153,7 +153,7 @@ mod tests {
             &name,
             &name
         ).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to add user");
         }
 
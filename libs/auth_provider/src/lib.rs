@@ -1,3 +1,11 @@
+#![allow(clippy::needless_return)]
+
+pub mod password;
+pub mod reset;
+pub mod tokens;
+
+use provider_error::ProviderError;
+
 pub enum AuthenticationType {
     Password
 }
@@ -13,27 +21,37 @@ pub struct User {
 
 pub trait AuthProvider {
 
+    /// passwords have to follow the password policy of the provider, in
+    /// this and `user_auth_password_set`
     fn add_user_auth_password(
         &self,
         user_id: &uuid::Uuid,
         email: &str,
         pw: &str
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// replace the password of an existing password sign in, fails with
+    /// `NotFound` if the user has none
+    fn user_auth_password_set(
+        &self,
+        user_id: &uuid::Uuid,
+        pw: &str
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn user_auth_password_set_active(
         &self,
         user_id: &uuid::Uuid,
         active: bool
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn authenticate_by_password(
         &self,
         email: &str,
         pw: &str
-    ) -> impl Future<Output = Result<bool, &'static str>> + Send;
+    ) -> impl Future<Output = Result<bool, ProviderError>> + Send;
 
     fn fetch_user_by_id(
         &self,
         user_id: &uuid::Uuid
-    ) -> impl Future<Output = Result<User, &'static str>> + Send;
+    ) -> impl Future<Output = Result<User, ProviderError>> + Send;
 }
\ No newline at end of file
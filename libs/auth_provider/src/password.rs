@@ -0,0 +1,205 @@
+//! password hashes as PHC strings,
+//! `$argon2id$v=19$m=<memory>,t=<time>,p=<parallelism>$<salt>$<hash>`
+//! with unpadded base64 salt and hash, made and checked by the argon2 crate
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Version};
+
+use provider_error::ProviderError;
+
+const SALT_SIZE: usize = 16;
+const HASH_SIZE: usize = 32;
+
+
+/// argon2id cost parameters, `memory_cost` in KiB; the defaults follow the
+/// OWASP recommendation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Params {
+    pub memory_cost: u32,
+    pub time_cost: u32,
+    pub parallelism: u32
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        return Self {
+            memory_cost: 19 * 1024,
+            time_cost: 2,
+            parallelism: 1
+        };
+    }
+}
+
+
+/// rules a new password has to follow, lengths count characters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Policy {
+    pub min_length: usize,
+    pub max_length: usize
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        return Self {
+            min_length: 8,
+            max_length: 128
+        };
+    }
+}
+
+impl Policy {
+
+    pub fn check(&self, pw: &str) -> Result<(), ProviderError> {
+        let length = pw.chars().count();
+        if length < self.min_length {
+            return Err(ProviderError::validation(
+                "pw",
+                &format!("password must have at least {} characters", self.min_length)
+            ));
+        }
+        if length > self.max_length {
+            return Err(ProviderError::validation(
+                "pw",
+                &format!("password must have at most {} characters", self.max_length)
+            ));
+        }
+        return Ok(());
+    }
+}
+
+
+/// an argon2id v1.3 hash, hashes of other algorithms or versions are not
+/// accepted
+fn parse(phc: &str) -> Option<PasswordHash<'_>> {
+    let stored = PasswordHash::new(phc).ok()?;
+    if stored.algorithm != argon2::ARGON2ID_IDENT || stored.version != Some(Version::V0x13.into()) {
+        return None;
+    }
+    return Some(stored);
+}
+
+
+/// hash of `pw` with a random salt, this takes a while by design and
+/// should not run on an async worker thread
+pub fn hash(params: &Params, pw: &str) -> Result<String, ProviderError> {
+    let params = argon2::Params::new(params.memory_cost, params.time_cost, params.parallelism, Some(HASH_SIZE))
+        .map_err(|_| ProviderError::validation("password_hash", "unsupported argon2id parameters"))?;
+
+    let mut salt = [0; SALT_SIZE];
+    openssl::rand::rand_bytes(&mut salt).map_err(|_| ProviderError::Internal)?;
+    let salt = SaltString::encode_b64(&salt).map_err(|_| ProviderError::Internal)?;
+
+    return match Argon2::new(Algorithm::Argon2id, Version::V0x13, params).hash_password(pw.as_bytes(), &salt) {
+        Ok(hash) => Ok(hash.to_string()),
+        Err(_) => Err(ProviderError::Internal)
+    };
+}
+
+/// whether `pw` matches the argon2id hash `phc`, compared in constant time;
+/// hashes in other formats or with unusable parameters never match
+pub fn verify(pw: &str, phc: &str) -> Result<bool, ProviderError> {
+    let Some(stored) = parse(phc) else {
+        return Ok(false);
+    };
+
+    // the algorithm, version and parameters are taken from the stored hash
+    return Ok(Argon2::default().verify_password(pw.as_bytes(), &stored).is_ok());
+}
+
+/// whether the stored hash should be replaced by a hash with `params`, as
+/// it is not an argon2id hash or was made with other parameters
+pub fn needs_rehash(params: &Params, phc: &str) -> bool {
+    let Some(stored) = parse(phc) else {
+        return true;
+    };
+    let Ok(stored_params) = argon2::Params::try_from(&stored) else {
+        return true;
+    };
+
+    let mut salt = [0; 64];
+    let salt_size = stored.salt.and_then(|s| s.decode_b64(&mut salt).ok()).map(<[u8]>::len);
+    let hash_size = stored.hash.map(|h| h.len());
+    return stored_params.m_cost() != params.memory_cost
+        || stored_params.t_cost() != params.time_cost
+        || stored_params.p_cost() != params.parallelism
+        || salt_size != Some(SALT_SIZE)
+        || hash_size != Some(HASH_SIZE);
+}
+
+/// whether the stored hash was made by pgcrypto's `crypt()` before
+/// passwords were hashed here
+pub fn is_legacy(stored: &str) -> bool {
+    return ["$2a$", "$2b$", "$2y$", "$1$"].iter().any(|prefix| stored.starts_with(prefix));
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// cheap parameters, tests do not need to be slow
+    fn params() -> Params {
+        return Params { memory_cost: 64, time_cost: 1, parallelism: 1 };
+    }
+
+    #[test]
+    fn test_hash() {
+        let phc = hash(&params(), "correct horse").unwrap();
+        assert!(phc.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+        assert!(verify("correct horse", &phc).unwrap());
+        assert!(!verify("correct horse ", &phc).unwrap());
+
+        // every hash gets its own salt
+        assert_ne!(phc, hash(&params(), "correct horse").unwrap());
+    }
+
+    #[test]
+    fn test_known_hash() {
+        let phc = "$argon2id$v=19$m=64,t=2,p=1$c29tZXNhbHQ$FqGkmHNGCd0BRW2kBt6fPZ2pPmyGwwChL8FGUhTOSSI";
+        assert!(verify("password", phc).unwrap());
+        assert!(!verify("Password", phc).unwrap());
+    }
+
+    #[test]
+    fn test_needs_rehash() {
+        let phc = hash(&params(), "pw").unwrap();
+        assert!(!needs_rehash(&params(), &phc));
+        assert!(needs_rehash(&Params { time_cost: 3, ..params() }, &phc));
+        assert!(needs_rehash(&params(), "$2a$06$DCq7YPn5Rq63x1Lad4cll.TV4S6ytwfsfvkgY8jIucDrjc8deX1s."));
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = Policy { min_length: 4, max_length: 6 };
+        assert!(policy.check("abcd").is_ok());
+        assert!(policy.check("äöüß").is_ok());
+        assert!(policy.check("abcdef").is_ok());
+        assert_eq!(
+            policy.check("abc"),
+            Err(ProviderError::validation("pw", "password must have at least 4 characters"))
+        );
+        assert_eq!(
+            policy.check("abcdefg"),
+            Err(ProviderError::validation("pw", "password must have at most 6 characters"))
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        for phc in [
+            "",
+            "$2a$06$DCq7YPn5Rq63x1Lad4cll.TV4S6ytwfsfvkgY8jIucDrjc8deX1s.",
+            "$argon2i$v=19$m=64,t=1,p=1$c29tZXNhbHQ$aGFzaA",
+            "$argon2id$v=16$m=64,t=1,p=1$c29tZXNhbHQ$aGFzaA",
+            "$argon2id$v=19$m=64,t=0,p=1$c29tZXNhbHQ$aGFzaA",
+            "$argon2id$v=19$m=64,t=1,p=1,x=2$c29tZXNhbHQ$aGFzaA",
+            "$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ$",
+            "$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ$aGFzaA$"
+        ] {
+            assert!(!verify("password", phc).unwrap(), "{phc}");
+        }
+        assert!(is_legacy("$2a$06$DCq7YPn5Rq63x1Lad4cll.TV4S6ytwfsfvkgY8jIucDrjc8deX1s."));
+        assert!(!is_legacy("$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ$aGFzaA"));
+    }
+}
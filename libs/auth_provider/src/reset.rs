@@ -0,0 +1,29 @@
+use provider_error::ProviderError;
+
+
+
+pub trait PasswordResetProvider {
+
+    /// store a reset token for the active password sign in of `email` and
+    /// queue `notification` in the same transaction, replacing earlier
+    /// tokens of the user. Nothing is stored or queued for an unknown
+    /// address, the result tells whether a token was stored. Fails with
+    /// `Throttled` once `email` or `client` asked too often, whether the
+    /// address is known or not
+    fn password_reset_request(
+        &self,
+        email: &str,
+        client: &str,
+        token: &str,
+        expires: &chrono::DateTime<chrono::Utc>,
+        notification: &outbox_provider::OutboxMessage
+    ) -> impl Future<Output = Result<bool, ProviderError>> + Send;
+
+    /// use a reset token and return the user it was issued to, a token can
+    /// only be used once. Fails with `NotFound` for an unknown, expired or
+    /// used token
+    fn password_reset_consume(
+        &self,
+        token: &str
+    ) -> impl Future<Output = Result<uuid::Uuid, ProviderError>> + Send;
+}
@@ -0,0 +1,69 @@
+use provider_error::ProviderError;
+
+
+/// session a refresh token was issued for, every rotation of a refresh
+/// token stays in the same family
+#[derive(Debug, Clone)]
+pub struct RefreshSession {
+    pub family_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub tenant_id: uuid::Uuid
+}
+
+
+
+pub trait TokensProvider {
+
+    /// persist a refresh token starting a new family
+    fn refresh_token_add(
+        &self,
+        family_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        tenant_id: &uuid::Uuid,
+        token: &str,
+        expires: &chrono::DateTime<chrono::Utc>
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// exchange a refresh token for `new_token` in the same family, the old
+    /// token can only be used once. Presenting a token that was already
+    /// used revokes the whole family and fails with `NotFound`, as does an
+    /// unknown, expired or revoked token
+    fn refresh_token_rotate(
+        &self,
+        token: &str,
+        new_token: &str,
+        new_expires: &chrono::DateTime<chrono::Utc>
+    ) -> impl Future<Output = Result<RefreshSession, ProviderError>> + Send;
+
+    /// revoke the family the refresh token belongs to, limited to tokens
+    /// issued to `user_id`
+    fn refresh_family_revoke(
+        &self,
+        user_id: &uuid::Uuid,
+        token: &str
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// deny an access token until it expires
+    fn access_token_deny(
+        &self,
+        token_id: &uuid::Uuid,
+        expires: &chrono::DateTime<chrono::Utc>
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// revoke every refresh token of `user_id` and deny the access tokens
+    /// issued to the user so far. Returns the revocation time, access tokens
+    /// issued at or after it are not denied
+    fn user_tokens_revoke(
+        &self,
+        user_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<chrono::DateTime<chrono::Utc>, ProviderError>> + Send;
+
+    /// whether the access token was denied, or issued to `user_id` before
+    /// all of the user's tokens were revoked
+    fn access_token_is_denied(
+        &self,
+        token_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        issued: &chrono::DateTime<chrono::Utc>
+    ) -> impl Future<Output = Result<bool, ProviderError>> + Send;
+}
@@ -0,0 +1 @@
+drop schema if exists auth cascade;
@@ -0,0 +1,91 @@
+-- password authentication
+
+create extension if not exists pgcrypto;
+
+create schema if not exists auth;
+
+
+create table auth.user_auth_password (
+    user_id uuid not null,
+    active boolean not null default true,
+    created timestamptz not null default now(),
+    updated timestamptz not null default now(),
+    email text not null,
+    pw_hash text not null,
+    constraint pk_user_auth_password primary key (user_id),
+    constraint fk_user_auth_password_user foreign key (user_id) references users.users (user_id) on delete cascade
+);
+
+create unique index u_user_auth_password_email on auth.user_auth_password (lower(email));
+
+
+create procedure auth.user_auth_password_save(
+    p_user_id uuid,
+    p_email text,
+    p_pw text
+)
+language sql
+as $$
+    insert into auth.user_auth_password as a (
+        user_id,
+        email,
+        pw_hash
+    ) values (
+        p_user_id,
+        p_email,
+        public.crypt(p_pw, public.gen_salt('bf'))
+    )
+    on conflict (user_id) do update set
+        email = excluded.email,
+        pw_hash = excluded.pw_hash,
+        updated = now();
+$$;
+
+create procedure auth.user_auth_password_set_active(
+    p_user_id uuid,
+    p_active boolean
+)
+language sql
+as $$
+    update auth.user_auth_password set
+        active = p_active,
+        updated = now()
+    where user_id = p_user_id;
+$$;
+
+create function auth.user_auth_password_authenticate(
+    p_email text,
+    p_pw text
+)
+returns boolean
+language sql
+stable
+as $$
+    select exists (
+        select 1
+        from auth.user_auth_password a
+            join users.users u on u.user_id = a.user_id
+        where
+            lower(a.email) = lower(p_email)
+            and a.active
+            and u.active
+            and a.pw_hash = public.crypt(p_pw, a.pw_hash)
+    );
+$$;
+
+create function auth.user_auth_password_fetch(
+    p_user_id uuid
+)
+returns table (
+    user_id uuid,
+    email text
+)
+language sql
+stable
+as $$
+    select
+        a.user_id,
+        a.email
+    from auth.user_auth_password a
+    where a.user_id = p_user_id;
+$$;
@@ -0,0 +1,10 @@
+drop function if exists auth.access_token_is_denied(uuid);
+drop procedure if exists auth.access_token_deny(uuid, timestamptz);
+drop procedure if exists auth.refresh_family_revoke(uuid, text);
+drop function if exists auth.refresh_token_rotate(text, text, timestamptz);
+drop procedure if exists auth.refresh_token_add(uuid, uuid, uuid, text, timestamptz);
+
+drop table if exists auth.access_token_denylist;
+drop table if exists auth.refresh_tokens;
+
+drop function if exists auth.token_hash(text);
@@ -0,0 +1,189 @@
+-- refresh tokens and access token revocation
+
+create function auth.token_hash(
+    p_token text
+)
+returns text
+language sql
+immutable
+as $$
+    select encode(public.digest(p_token, 'sha256'), 'hex');
+$$;
+
+
+-- refresh tokens are stored hashed, every rotation of a token stays in the
+-- family of the sign-in that started it
+create table auth.refresh_tokens (
+    token_hash text not null,
+    family_id uuid not null,
+    user_id uuid not null,
+    tenant_id uuid not null,
+    created timestamptz not null default now(),
+    expires timestamptz not null,
+    used timestamptz,
+    revoked timestamptz,
+    constraint pk_refresh_tokens primary key (token_hash),
+    constraint fk_refresh_tokens_user foreign key (user_id) references users.users (user_id) on delete cascade
+);
+
+create index idx_refresh_tokens_family on auth.refresh_tokens (family_id);
+create index idx_refresh_tokens_user on auth.refresh_tokens (user_id);
+
+
+-- access tokens revoked before their expiry, rows are only kept until then
+create table auth.access_token_denylist (
+    token_id uuid not null,
+    expires timestamptz not null,
+    constraint pk_access_token_denylist primary key (token_id)
+);
+
+
+create procedure auth.refresh_token_add(
+    p_family_id uuid,
+    p_user_id uuid,
+    p_tenant_id uuid,
+    p_token text,
+    p_expires timestamptz
+)
+language sql
+as $$
+    delete from auth.refresh_tokens
+    where
+        user_id = p_user_id
+        and expires < now();
+
+    insert into auth.refresh_tokens (
+        token_hash,
+        family_id,
+        user_id,
+        tenant_id,
+        expires
+    ) values (
+        auth.token_hash(p_token),
+        p_family_id,
+        p_user_id,
+        p_tenant_id,
+        p_expires
+    );
+$$;
+
+-- returns no rows if the token cannot be used, reusing a token that was
+-- already rotated revokes its whole family
+create function auth.refresh_token_rotate(
+    p_token text,
+    p_new_token text,
+    p_expires timestamptz
+)
+returns table (
+    family_id uuid,
+    user_id uuid,
+    tenant_id uuid
+)
+language plpgsql
+as $$
+declare
+    r auth.refresh_tokens%rowtype;
+begin
+    select t.* into r
+    from auth.refresh_tokens t
+    where t.token_hash = auth.token_hash(p_token)
+    for update;
+
+    if not found or r.revoked is not null or r.expires <= now() then
+        return;
+    end if;
+
+    if r.used is not null then
+        update auth.refresh_tokens t set
+            revoked = now()
+        where
+            t.family_id = r.family_id
+            and t.revoked is null;
+        return;
+    end if;
+
+    if not exists (
+        select 1
+        from users.users u
+        where
+            u.user_id = r.user_id
+            and u.active
+    ) then
+        return;
+    end if;
+
+    update auth.refresh_tokens t set
+        used = now()
+    where t.token_hash = r.token_hash;
+
+    insert into auth.refresh_tokens (
+        token_hash,
+        family_id,
+        user_id,
+        tenant_id,
+        expires
+    ) values (
+        auth.token_hash(p_new_token),
+        r.family_id,
+        r.user_id,
+        r.tenant_id,
+        p_expires
+    );
+
+    return query select r.family_id, r.user_id, r.tenant_id;
+end;
+$$;
+
+create procedure auth.refresh_family_revoke(
+    p_user_id uuid,
+    p_token text
+)
+language sql
+as $$
+    update auth.refresh_tokens t set
+        revoked = now()
+    where
+        t.family_id in (
+            select f.family_id
+            from auth.refresh_tokens f
+            where
+                f.token_hash = auth.token_hash(p_token)
+                and f.user_id = p_user_id
+        )
+        and t.revoked is null;
+$$;
+
+create procedure auth.access_token_deny(
+    p_token_id uuid,
+    p_expires timestamptz
+)
+language sql
+as $$
+    delete from auth.access_token_denylist
+    where expires < now();
+
+    insert into auth.access_token_denylist (
+        token_id,
+        expires
+    ) values (
+        p_token_id,
+        p_expires
+    )
+    on conflict (token_id) do nothing;
+$$;
+
+create function auth.access_token_is_denied(
+    p_token_id uuid
+)
+returns boolean
+language sql
+stable
+as $$
+    select exists (
+        select 1
+        from auth.access_token_denylist d
+        where
+            d.token_id = p_token_id
+            and d.expires >= now()
+    );
+$$;
@@ -0,0 +1,50 @@
+-- argon2id hashes cannot be checked by crypt(), their users have to reset
+-- their password
+
+drop function auth.user_auth_password_legacy_verify(uuid, text);
+drop procedure auth.user_auth_password_rehash(uuid, text, text);
+drop function auth.user_auth_password_hash_fetch(text);
+drop procedure auth.user_auth_password_save(uuid, text, text);
+
+
+create procedure auth.user_auth_password_save(
+    p_user_id uuid,
+    p_email text,
+    p_pw text
+)
+language sql
+as $$
+    insert into auth.user_auth_password as a (
+        user_id,
+        email,
+        pw_hash
+    ) values (
+        p_user_id,
+        p_email,
+        public.crypt(p_pw, public.gen_salt('bf'))
+    )
+    on conflict (user_id) do update set
+        email = excluded.email,
+        pw_hash = excluded.pw_hash,
+        updated = now();
+$$;
+
+create function auth.user_auth_password_authenticate(
+    p_email text,
+    p_pw text
+)
+returns boolean
+language sql
+stable
+as $$
+    select exists (
+        select 1
+        from auth.user_auth_password a
+            join users.users u on u.user_id = a.user_id
+        where
+            lower(a.email) = lower(p_email)
+            and a.active
+            and u.active
+            and a.pw_hash = public.crypt(p_pw, a.pw_hash)
+    );
+$$;
@@ -0,0 +1,86 @@
+-- password hashes are made and checked by the application, passwords only
+-- reach the database once more to check a hash made by crypt() before,
+-- which is then replaced
+
+drop function auth.user_auth_password_authenticate(text, text);
+drop procedure auth.user_auth_password_save(uuid, text, text);
+
+
+create procedure auth.user_auth_password_save(
+    p_user_id uuid,
+    p_email text,
+    p_pw_hash text
+)
+language sql
+as $$
+    insert into auth.user_auth_password as a (
+        user_id,
+        email,
+        pw_hash
+    ) values (
+        p_user_id,
+        p_email,
+        p_pw_hash
+    )
+    on conflict (user_id) do update set
+        email = excluded.email,
+        pw_hash = excluded.pw_hash,
+        updated = now();
+$$;
+
+-- the hash to check a password against, only for active users
+create function auth.user_auth_password_hash_fetch(
+    p_email text
+)
+returns table (
+    user_id uuid,
+    pw_hash text
+)
+language sql
+stable
+as $$
+    select
+        a.user_id,
+        a.pw_hash
+    from auth.user_auth_password a
+        join users.users u on u.user_id = a.user_id
+    where
+        lower(a.email) = lower(p_email)
+        and a.active
+        and u.active;
+$$;
+
+-- replaces the hash after a successful sign in, unless the password was
+-- changed in the meantime
+create procedure auth.user_auth_password_rehash(
+    p_user_id uuid,
+    p_old_hash text,
+    p_new_hash text
+)
+language sql
+as $$
+    update auth.user_auth_password set
+        pw_hash = p_new_hash
+    where
+        user_id = p_user_id
+        and pw_hash = p_old_hash;
+$$;
+
+-- checks a password against a hash made by crypt()
+create function auth.user_auth_password_legacy_verify(
+    p_user_id uuid,
+    p_pw text
+)
+returns boolean
+language sql
+stable
+as $$
+    select exists (
+        select 1
+        from auth.user_auth_password a
+        where
+            a.user_id = p_user_id
+            and a.pw_hash not like '$argon2%'
+            and a.pw_hash = public.crypt(p_pw, a.pw_hash)
+    );
+$$;
@@ -0,0 +1,25 @@
+drop function if exists auth.access_token_is_denied(uuid, uuid, timestamptz);
+
+create function auth.access_token_is_denied(
+    p_token_id uuid
+)
+returns boolean
+language sql
+stable
+as $$
+    select exists (
+        select 1
+        from auth.access_token_denylist d
+        where
+            d.token_id = p_token_id
+            and d.expires >= now()
+    );
+$$;
+
+drop procedure if exists auth.user_tokens_revoke(uuid);
+drop function if exists auth.user_auth_password_set(uuid, text);
+drop function if exists auth.password_reset_consume(text);
+drop function if exists auth.password_reset_request(text, text, timestamptz);
+
+drop table if exists auth.user_token_revocations;
+drop table if exists auth.password_reset_tokens;
@@ -0,0 +1,159 @@
+-- password reset tokens and revocation of every session of a user
+
+-- reset tokens are stored hashed and can only be used once
+create table auth.password_reset_tokens (
+    token_hash text not null,
+    user_id uuid not null,
+    created timestamptz not null default now(),
+    expires timestamptz not null,
+    used timestamptz,
+    constraint pk_password_reset_tokens primary key (token_hash),
+    constraint fk_password_reset_tokens_user foreign key (user_id) references users.users (user_id) on delete cascade
+);
+
+create index idx_password_reset_tokens_user on auth.password_reset_tokens (user_id);
+
+
+-- access tokens of a user issued before `revoked` are denied, tokens carry
+-- their issue time in whole seconds
+create table auth.user_token_revocations (
+    user_id uuid not null,
+    revoked timestamptz not null,
+    constraint pk_user_token_revocations primary key (user_id),
+    constraint fk_user_token_revocations_user foreign key (user_id) references users.users (user_id) on delete cascade
+);
+
+
+-- returns the user the token was stored for, no rows for an address
+-- without an active password sign in
+create function auth.password_reset_request(
+    p_email text,
+    p_token text,
+    p_expires timestamptz
+)
+returns table (
+    user_id uuid
+)
+language plpgsql
+as $$
+declare
+    v_user_id uuid;
+begin
+    select a.user_id into v_user_id
+    from auth.user_auth_password a
+        join users.users u on u.user_id = a.user_id
+    where
+        lower(a.email) = lower(p_email)
+        and a.active
+        and u.active;
+
+    if not found then
+        return;
+    end if;
+
+    delete from auth.password_reset_tokens t
+    where t.user_id = v_user_id;
+
+    insert into auth.password_reset_tokens (
+        token_hash,
+        user_id,
+        expires
+    ) values (
+        auth.token_hash(p_token),
+        v_user_id,
+        p_expires
+    );
+
+    return query select v_user_id;
+end;
+$$;
+
+-- returns no rows if the token cannot be used
+create function auth.password_reset_consume(
+    p_token text
+)
+returns table (
+    user_id uuid
+)
+language sql
+as $$
+    update auth.password_reset_tokens t set
+        used = now()
+    from auth.user_auth_password a
+        join users.users u on u.user_id = a.user_id
+    where
+        t.token_hash = auth.token_hash(p_token)
+        and t.used is null
+        and t.expires > now()
+        and a.user_id = t.user_id
+        and a.active
+        and u.active
+    returning t.user_id;
+$$;
+
+create function auth.user_auth_password_set(
+    p_user_id uuid,
+    p_pw_hash text
+)
+returns boolean
+language sql
+as $$
+    with updated as (
+        update auth.user_auth_password set
+            pw_hash = p_pw_hash,
+            updated = now()
+        where user_id = p_user_id
+        returning user_id
+    )
+    select exists (select 1 from updated);
+$$;
+
+create procedure auth.user_tokens_revoke(
+    p_user_id uuid
+)
+language sql
+as $$
+    update auth.refresh_tokens set
+        revoked = now()
+    where
+        user_id = p_user_id
+        and revoked is null;
+
+    insert into auth.user_token_revocations (
+        user_id,
+        revoked
+    ) values (
+        p_user_id,
+        date_trunc('second', now())
+    )
+    on conflict (user_id) do update set
+        revoked = excluded.revoked;
+$$;
+
+
+drop function auth.access_token_is_denied(uuid);
+
+create function auth.access_token_is_denied(
+    p_token_id uuid,
+    p_user_id uuid,
+    p_issued timestamptz
+)
+returns boolean
+language sql
+stable
+as $$
+    select exists (
+        select 1
+        from auth.access_token_denylist d
+        where
+            d.token_id = p_token_id
+            and d.expires >= now()
+    )
+    or exists (
+        select 1
+        from auth.user_token_revocations r
+        where
+            r.user_id = p_user_id
+            and r.revoked > p_issued
+    );
+$$;
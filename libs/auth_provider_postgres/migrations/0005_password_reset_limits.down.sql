@@ -0,0 +1,47 @@
+drop function if exists auth.password_reset_request(text, text, text, timestamptz);
+
+-- returns the user the token was stored for, no rows for an address
+-- without an active password sign in
+create function auth.password_reset_request(
+    p_email text,
+    p_token text,
+    p_expires timestamptz
+)
+returns table (
+    user_id uuid
+)
+language plpgsql
+as $$
+declare
+    v_user_id uuid;
+begin
+    select a.user_id into v_user_id
+    from auth.user_auth_password a
+        join users.users u on u.user_id = a.user_id
+    where
+        lower(a.email) = lower(p_email)
+        and a.active
+        and u.active;
+
+    if not found then
+        return;
+    end if;
+
+    delete from auth.password_reset_tokens t
+    where t.user_id = v_user_id;
+
+    insert into auth.password_reset_tokens (
+        token_hash,
+        user_id,
+        expires
+    ) values (
+        auth.token_hash(p_token),
+        v_user_id,
+        p_expires
+    );
+
+    return query select v_user_id;
+end;
+$$;
+
+drop table if exists auth.password_reset_attempts;
@@ -0,0 +1,87 @@
+-- password reset requests are limited per address and per client, known and
+-- unknown addresses count alike
+
+create table auth.password_reset_attempts (
+    email text not null,
+    client text not null,
+    created timestamptz not null default now()
+);
+
+create index idx_password_reset_attempts_email on auth.password_reset_attempts (email, created);
+create index idx_password_reset_attempts_client on auth.password_reset_attempts (client, created);
+create index idx_password_reset_attempts_created on auth.password_reset_attempts (created);
+
+
+drop function if exists auth.password_reset_request(text, text, timestamptz);
+
+-- `throttled` is set when the address was asked for 5 times or the client
+-- asked 20 times within the last hour, nothing is stored then. `user_id` is
+-- null for an address without an active password sign in
+create function auth.password_reset_request(
+    p_email text,
+    p_client text,
+    p_token text,
+    p_expires timestamptz
+)
+returns table (
+    user_id uuid,
+    throttled boolean
+)
+language plpgsql
+as $$
+declare
+    v_user_id uuid;
+begin
+    delete from auth.password_reset_attempts a
+    where a.created < now() - interval '1 hour';
+
+    if (
+        select count(*)
+        from auth.password_reset_attempts a
+        where a.email = lower(p_email)
+    ) >= 5 or (
+        select count(*)
+        from auth.password_reset_attempts a
+        where a.client = p_client
+    ) >= 20 then
+        return query select null::uuid, true;
+        return;
+    end if;
+
+    insert into auth.password_reset_attempts (
+        email,
+        client
+    ) values (
+        lower(p_email),
+        p_client
+    );
+
+    select a.user_id into v_user_id
+    from auth.user_auth_password a
+        join users.users u on u.user_id = a.user_id
+    where
+        lower(a.email) = lower(p_email)
+        and a.active
+        and u.active;
+
+    if not found then
+        return query select null::uuid, false;
+        return;
+    end if;
+
+    delete from auth.password_reset_tokens t
+    where t.user_id = v_user_id;
+
+    insert into auth.password_reset_tokens (
+        token_hash,
+        user_id,
+        expires
+    ) values (
+        auth.token_hash(p_token),
+        v_user_id,
+        p_expires
+    );
+
+    return query select v_user_id, false;
+end;
+$$;
@@ -0,0 +1,23 @@
+drop function if exists auth.user_tokens_revoke(uuid);
+
+create procedure auth.user_tokens_revoke(
+    p_user_id uuid
+)
+language sql
+as $$
+    update auth.refresh_tokens set
+        revoked = now()
+    where
+        user_id = p_user_id
+        and revoked is null;
+
+    insert into auth.user_token_revocations (
+        user_id,
+        revoked
+    ) values (
+        p_user_id,
+        date_trunc('second', now())
+    )
+    on conflict (user_id) do update set
+        revoked = excluded.revoked;
+$$;
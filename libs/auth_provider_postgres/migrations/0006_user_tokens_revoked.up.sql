@@ -0,0 +1,29 @@
+-- revoking the tokens of a user returns the revocation time, tokens issued
+-- right after the revocation claim it as their issue time so they are not
+-- denied when the application clock lags behind the database
+
+drop procedure if exists auth.user_tokens_revoke(uuid);
+
+create function auth.user_tokens_revoke(
+    p_user_id uuid
+)
+returns timestamptz
+language sql
+as $$
+    update auth.refresh_tokens set
+        revoked = now()
+    where
+        user_id = p_user_id
+        and revoked is null;
+
+    insert into auth.user_token_revocations (
+        user_id,
+        revoked
+    ) values (
+        p_user_id,
+        date_trunc('second', now())
+    )
+    on conflict (user_id) do update set
+        revoked = excluded.revoked
+    returning revoked;
+$$;
@@ -1,19 +1,190 @@
 #![allow(clippy::needless_return)]
 
+pub mod reset;
+pub mod tokens;
+
 use tracing::{debug, error, info};
 
 use sqlx::Row;
 
+use auth_provider::password;
+use provider_error::ProviderError;
+
 const AUTH_TYPE_PW: i32 = 1;
 
+/// schema migrations for the `auth` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "auth",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 2,
+            name: "tokens",
+            up: include_str!("../migrations/0002_tokens.up.sql"),
+            down: include_str!("../migrations/0002_tokens.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 3,
+            name: "password_hashes",
+            up: include_str!("../migrations/0003_password_hashes.up.sql"),
+            down: include_str!("../migrations/0003_password_hashes.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 4,
+            name: "password_reset",
+            up: include_str!("../migrations/0004_password_reset.up.sql"),
+            down: include_str!("../migrations/0004_password_reset.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 5,
+            name: "password_reset_limits",
+            up: include_str!("../migrations/0005_password_reset_limits.up.sql"),
+            down: include_str!("../migrations/0005_password_reset_limits.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 6,
+            name: "user_tokens_revoked",
+            up: include_str!("../migrations/0006_user_tokens_revoked.up.sql"),
+            down: include_str!("../migrations/0006_user_tokens_revoked.down.sql")
+        }
+    ]
+};
+
+
 pub struct PostgresAuthProvider {
     dp: database_provider::DatabaseProvider,
+    password: password::Params,
+    policy: password::Policy,
 }
 
 impl PostgresAuthProvider {
     pub fn new(dp: &database_provider::DatabaseProvider) -> Self {
-        return Self { dp: dp.clone() };
+        return Self {
+            dp: dp.clone(),
+            password: password::Params::default(),
+            policy: password::Policy::default(),
+        };
+    }
+
+    /// parameters new password hashes are made with, hashes made with
+    /// other parameters are replaced on the next sign in
+    pub fn with_password_params(mut self, params: password::Params) -> Self {
+        self.password = params;
+        return self;
+    }
+
+    /// rules new passwords have to follow
+    pub fn with_password_policy(mut self, policy: password::Policy) -> Self {
+        self.policy = policy;
+        return self;
     }
+
+    /// user and stored hash of an active user
+    async fn password_hash_fetch(&self, email: &str) -> Result<Option<(uuid::Uuid, String)>, ProviderError> {
+        info!("password_hash_fetch");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from auth.user_auth_password_hash_fetch($1);")
+                .bind(email)
+                .fetch_optional(&pool)
+                .await
+            {
+                Ok(row) => {
+                    return Ok(row.map(|row| (row.get("user_id"), row.get("pw_hash"))));
+                }
+                Err(e) => {
+                    error!("Error fetching password hash: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    /// checks the password against a hash made by pgcrypto's `crypt()`
+    async fn password_legacy_verify(&self, user_id: &uuid::Uuid, pw: &str) -> Result<bool, ProviderError> {
+        info!("password_legacy_verify");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from auth.user_auth_password_legacy_verify($1,$2);")
+                .bind(user_id)
+                .bind(pw)
+                .fetch_one(&pool)
+                .await
+            {
+                Ok(row) => {
+                    return Ok(row.get("user_auth_password_legacy_verify"));
+                }
+                Err(e) => {
+                    error!("Error verifying legacy password hash: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn password_rehash(
+        &self,
+        user_id: &uuid::Uuid,
+        old_hash: &str,
+        new_hash: &str,
+    ) -> Result<(), ProviderError> {
+        info!("password_rehash");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call auth.user_auth_password_rehash($1,$2,$3);")
+                .bind(user_id)
+                .bind(old_hash)
+                .bind(new_hash)
+                .execute(&pool)
+                .await
+            {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("Error replacing password hash: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+}
+
+
+/// hashing is slow by design and runs on the blocking thread pool
+async fn hash_password(params: password::Params, pw: &str) -> Result<String, ProviderError> {
+    let pw = String::from(pw);
+    return tokio::task::spawn_blocking(move || password::hash(&params, &pw))
+        .await
+        .map_err(|e| {
+            error!("password hashing failed: {}", e);
+            return ProviderError::Internal;
+        })?;
+}
+
+async fn verify_password(pw: &str, pw_hash: &str) -> Result<bool, ProviderError> {
+    let pw = String::from(pw);
+    let pw_hash = String::from(pw_hash);
+    return tokio::task::spawn_blocking(move || password::verify(&pw, &pw_hash))
+        .await
+        .map_err(|e| {
+            error!("password verification failed: {}", e);
+            return ProviderError::Internal;
+        })?;
 }
 
 impl auth_provider::AuthProvider for PostgresAuthProvider {
@@ -22,14 +193,17 @@ impl auth_provider::AuthProvider for PostgresAuthProvider {
         user_id: &uuid::Uuid,
         email: &str,
         pw: &str,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("add_user_auth_password");
 
+        self.policy.check(pw)?;
+        let pw_hash = hash_password(self.password, pw).await?;
+
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
             match sqlx::query("call auth.user_auth_password_save($1,$2,$3);")
                 .bind(user_id)
                 .bind(email)
-                .bind(pw)
+                .bind(&pw_hash)
                 .execute(&pool)
                 .await
             {
@@ -38,12 +212,42 @@ impl auth_provider::AuthProvider for PostgresAuthProvider {
                 }
                 Err(e) => {
                     error!("Error adding user authentication using password: {:?}", e);
-                    return Err("Error adding user authentication using password");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn user_auth_password_set(&self, user_id: &uuid::Uuid, pw: &str) -> Result<(), ProviderError> {
+        info!("user_auth_password_set");
+
+        self.policy.check(pw)?;
+        let pw_hash = hash_password(self.password, pw).await?;
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from auth.user_auth_password_set($1,$2);")
+                .bind(user_id)
+                .bind(&pw_hash)
+                .fetch_one(&pool)
+                .await
+            {
+                Ok(row) => {
+                    if !row.get::<bool, _>("user_auth_password_set") {
+                        return Err(ProviderError::NotFound);
+                    }
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("Error setting user password: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -51,7 +255,7 @@ impl auth_provider::AuthProvider for PostgresAuthProvider {
         &self,
         user_id: &uuid::Uuid,
         active: bool,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("user_auth_password_set_active");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -69,46 +273,50 @@ impl auth_provider::AuthProvider for PostgresAuthProvider {
                         "Error setting user authentication using password active: {:?}",
                         e
                     );
-                    return Err("Error setting user authentication using password active");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn authenticate_by_password(&self, email: &str, pw: &str) -> Result<bool, &'static str> {
+    async fn authenticate_by_password(&self, email: &str, pw: &str) -> Result<bool, ProviderError> {
         info!("authenticate");
 
-        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
-            match sqlx::query("select * from auth.user_auth_password_authenticate($1,$2);")
-                .bind(email)
-                .bind(pw)
-                .fetch_one(&pool)
-                .await
-            {
-                Ok(row) => {
-                    debug!("{:?}", row);
+        let Some((user_id, pw_hash)) = self.password_hash_fetch(email).await? else {
+            // unknown addresses take as long as wrong passwords
+            let _ = hash_password(self.password, pw).await;
+            return Ok(false);
+        };
 
-                    let authentic = row.get("user_auth_password_authenticate");
-                    return Ok(authentic);
-                }
-                Err(e) => {
-                    error!("Error user authentication using password: {:?}", e);
-                    return Err("Error user authentication using password");
-                }
-            }
+        let authentic = if password::is_legacy(&pw_hash) {
+            self.password_legacy_verify(&user_id, pw).await?
         } else {
-            error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            verify_password(pw, &pw_hash).await?
+        };
+
+        if authentic && password::needs_rehash(&self.password, &pw_hash) {
+            debug!("replacing password hash of user {}", user_id);
+
+            // the sign in succeeds even if the old hash stays
+            let rehashed = match hash_password(self.password, pw).await {
+                Ok(new_hash) => self.password_rehash(&user_id, &pw_hash, &new_hash).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = rehashed {
+                error!("unable to replace password hash of user {}: {}", user_id, e);
+            }
         }
+
+        return Ok(authentic);
     }
 
     async fn fetch_user_by_id(
         &self,
         user_id: &uuid::Uuid,
-    ) -> Result<auth_provider::User, &'static str> {
+    ) -> Result<auth_provider::User, ProviderError> {
         info!("fetch_user_by_id");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -127,12 +335,12 @@ impl auth_provider::AuthProvider for PostgresAuthProvider {
                 }
                 Err(e) => {
                     error!("Error user authentication using password: {:?}", e);
-                    return Err("Error user authentication using password");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
@@ -145,6 +353,26 @@ mod tests {
     use users_provider::UsersProvider;
     use users_provider_postgres::PostgresUsersProvider;
 
+    /// cheap parameters, tests do not need to be slow
+    fn test_params() -> password::Params {
+        return password::Params {
+            memory_cost: 64,
+            time_cost: 1,
+            parallelism: 1,
+        };
+    }
+
+    async fn stored_hash(dp: &database_provider::DatabaseProvider, user_id: &uuid::Uuid) -> String {
+        let Some(database_provider::DatabaseType::Postgres(pool)) = dp.get_pool("main") else {
+            panic!("no Postgres pool found for 'main'");
+        };
+        return sqlx::query_scalar("select pw_hash from auth.user_auth_password where user_id = $1;")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    }
+
     #[actix_web::test]
     async fn test_user_auth_password() {
         if let Err(e) = tracing_subscriber::fmt::try_init() {
@@ -162,33 +390,78 @@ mod tests {
         let up = PostgresUsersProvider::new(&dp);
 
         if let Err(e) = up.save(&user_id, &"", &"", &"", &"", &"", &0).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to add user authentication using password");
         }
 
-        let ap = PostgresAuthProvider::new(&dp);
+        let ap = PostgresAuthProvider::new(&dp).with_password_params(test_params());
 
         if let Err(e) = ap.add_user_auth_password(&user_id, &email, &pw).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to add user authentication using password");
         }
 
         if let Err(e) = ap.user_auth_password_set_active(&user_id, true).await {
-            error!(e);
+            error!("{}", e);
             assert!(
                 false,
                 "unable to set user authentication using password active"
             );
         }
 
-        if let Err(e) = ap.authenticate_by_password(&email, &pw).await {
-            error!(e);
-            assert!(false, "unable to authenticate using password");
-        }
+        assert_eq!(ap.authenticate_by_password(&email, &pw).await, Ok(true));
+        assert_eq!(ap.authenticate_by_password(&email, "test1tesT").await, Ok(false));
+        assert_eq!(ap.authenticate_by_password("unknown@test.com", &pw).await, Ok(false));
 
         if let Err(e) = ap.fetch_user_by_id(&user_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch user by id");
         }
     }
+
+    #[actix_web::test]
+    async fn test_rehash() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let user_id = uuid::Uuid::new_v4();
+        let email = format!("test_{}@test.com", user_id.simple());
+        let pw = "test1test";
+
+        let up = PostgresUsersProvider::new(&dp);
+        assert!(up.save(&user_id, &"", &"", &"", &"", &"", &0).await.is_ok());
+
+        // a hash made by crypt() before passwords were hashed here
+        let Some(database_provider::DatabaseType::Postgres(pool)) = dp.get_pool("main") else {
+            panic!("no Postgres pool found for 'main'");
+        };
+        sqlx::query("insert into auth.user_auth_password (user_id, email, pw_hash) values ($1, $2, public.crypt($3, public.gen_salt('bf', 4)));")
+            .bind(user_id)
+            .bind(&email)
+            .bind(pw)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let ap = PostgresAuthProvider::new(&dp).with_password_params(test_params());
+        assert_eq!(ap.authenticate_by_password(&email, "wrong").await, Ok(false));
+        assert!(stored_hash(&dp, &user_id).await.starts_with("$2"));
+
+        assert_eq!(ap.authenticate_by_password(&email, pw).await, Ok(true));
+        let rehashed = stored_hash(&dp, &user_id).await;
+        assert!(rehashed.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+        assert_eq!(ap.authenticate_by_password(&email, pw).await, Ok(true));
+        assert_eq!(stored_hash(&dp, &user_id).await, rehashed);
+
+        // changed parameters replace the hash as well
+        let ap = PostgresAuthProvider::new(&dp).with_password_params(password::Params {
+            time_cost: 2,
+            ..test_params()
+        });
+        assert_eq!(ap.authenticate_by_password(&email, pw).await, Ok(true));
+        assert!(stored_hash(&dp, &user_id).await.starts_with("$argon2id$v=19$m=64,t=2,p=1$"));
+
+        assert!(ap.user_auth_password_set_active(&user_id, false).await.is_ok());
+        assert_eq!(ap.authenticate_by_password(&email, pw).await, Ok(false));
+    }
 }
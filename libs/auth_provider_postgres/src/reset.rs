@@ -0,0 +1,205 @@
+use tracing::{error, info};
+
+use sqlx::Row;
+
+use auth_provider::reset::PasswordResetProvider;
+
+use provider_error::ProviderError;
+
+pub struct PostgresPasswordResetProvider {
+    dp: database_provider::DatabaseProvider,
+}
+
+impl PostgresPasswordResetProvider {
+    pub fn new(dp: &database_provider::DatabaseProvider) -> Self {
+        return Self { dp: dp.clone() };
+    }
+}
+
+impl PasswordResetProvider for PostgresPasswordResetProvider {
+    async fn password_reset_request(
+        &self,
+        email: &str,
+        client: &str,
+        token: &str,
+        expires: &chrono::DateTime<chrono::Utc>,
+        notification: &outbox_provider::OutboxMessage,
+    ) -> Result<bool, ProviderError> {
+        info!("password_reset_request");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            let mut tx = match pool.begin().await {
+                Ok(tx) => tx,
+                Err(e) => {
+                    error!("Error starting transaction: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            };
+
+            let stored = match sqlx::query("select * from auth.password_reset_request($1,$2,$3,$4);")
+                .bind(email)
+                .bind(client)
+                .bind(token)
+                .bind(expires)
+                .fetch_one(&mut *tx)
+                .await
+            {
+                Ok(row) if row.get::<bool, _>("throttled") => {
+                    return Err(ProviderError::Throttled);
+                }
+                Ok(row) => row.get::<Option<uuid::Uuid>, _>("user_id").is_some(),
+                Err(e) => {
+                    error!("Error storing password reset token: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            };
+
+            if stored && let Err(e) = outbox_provider_postgres::enqueue_with(&mut *tx, notification).await {
+                error!("Error queueing password reset mail: {:?}", e);
+                return Err(database_provider::from_sqlx(&e));
+            }
+
+            match tx.commit().await {
+                Ok(_) => {
+                    return Ok(stored);
+                }
+                Err(e) => {
+                    error!("Error storing password reset token: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn password_reset_consume(&self, token: &str) -> Result<uuid::Uuid, ProviderError> {
+        info!("password_reset_consume");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from auth.password_reset_consume($1);")
+                .bind(token)
+                .fetch_optional(&pool)
+                .await
+            {
+                Ok(Some(row)) => {
+                    return Ok(row.get("user_id"));
+                }
+                Ok(None) => {
+                    return Err(ProviderError::NotFound);
+                }
+                Err(e) => {
+                    error!("Error using password reset token: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use auth_provider::AuthProvider;
+    use users_provider::UsersProvider;
+    use users_provider_postgres::PostgresUsersProvider;
+
+    async fn queued(dp: &database_provider::DatabaseProvider, email: &str) -> i64 {
+        let Some(database_provider::DatabaseType::Postgres(pool)) = dp.get_pool("main") else {
+            panic!("no Postgres pool found for 'main'");
+        };
+        return sqlx::query_scalar("select count(*) from outbox.messages where $1 = any(mail_to);")
+            .bind(email)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_password_reset() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let user_id = uuid::Uuid::new_v4();
+        let email = format!("test_{}@test.com", user_id.simple());
+
+        let up = PostgresUsersProvider::new(&dp);
+        assert!(up.save(&user_id, &"", &"", &"", &"", &"", &0).await.is_ok());
+
+        let ap = crate::PostgresAuthProvider::new(&dp).with_password_params(auth_provider::password::Params {
+            memory_cost: 64,
+            time_cost: 1,
+            parallelism: 1,
+        });
+        assert!(ap.add_user_auth_password(&user_id, &email, "test1test").await.is_ok());
+
+        let rp = PostgresPasswordResetProvider::new(&dp);
+        let client = format!("test_{}", user_id.simple());
+        let expires = chrono::Utc::now() + chrono::TimeDelta::hours(1);
+        let notification = outbox_provider::OutboxMessage::new(&email, "Reset your password", "link");
+
+        // nothing is stored or sent for unknown addresses
+        let unknown = format!("unknown_{}@test.com", user_id.simple());
+        let message = outbox_provider::OutboxMessage::new(&unknown, "Reset your password", "link");
+        assert_eq!(rp.password_reset_request(&unknown, &client, "unknown", &expires, &message).await, Ok(false));
+        assert_eq!(queued(&dp, &unknown).await, 0);
+
+        let first = uuid::Uuid::new_v4().to_string();
+        let second = uuid::Uuid::new_v4().to_string();
+        assert_eq!(rp.password_reset_request(&email.to_uppercase(), &client, &first, &expires, &notification).await, Ok(true));
+        assert_eq!(rp.password_reset_request(&email, &client, &second, &expires, &notification).await, Ok(true));
+        assert_eq!(queued(&dp, &email).await, 2);
+
+        // a new request replaces the earlier token, a token is used once
+        assert_eq!(rp.password_reset_consume(&first).await, Err(ProviderError::NotFound));
+        assert_eq!(rp.password_reset_consume(&second).await, Ok(user_id));
+        assert_eq!(rp.password_reset_consume(&second).await, Err(ProviderError::NotFound));
+
+        let expired = uuid::Uuid::new_v4().to_string();
+        let past = chrono::Utc::now() - chrono::TimeDelta::seconds(1);
+        assert_eq!(rp.password_reset_request(&email, &client, &expired, &past, &notification).await, Ok(true));
+        assert_eq!(rp.password_reset_consume(&expired).await, Err(ProviderError::NotFound));
+
+        // the password is set through the auth provider, following its policy
+        assert!(matches!(
+            ap.user_auth_password_set(&user_id, "short").await,
+            Err(ProviderError::Validation { .. })
+        ));
+        assert!(ap.user_auth_password_set(&user_id, "test2test").await.is_ok());
+        assert_eq!(ap.authenticate_by_password(&email, "test1test").await, Ok(false));
+        assert_eq!(ap.authenticate_by_password(&email, "test2test").await, Ok(true));
+        assert_eq!(
+            ap.user_auth_password_set(&uuid::Uuid::new_v4(), "test2test").await,
+            Err(ProviderError::NotFound)
+        );
+
+        // inactive sign ins cannot be reset
+        let token = uuid::Uuid::new_v4().to_string();
+        assert_eq!(rp.password_reset_request(&email, &client, &token, &expires, &notification).await, Ok(true));
+        assert!(ap.user_auth_password_set_active(&user_id, false).await.is_ok());
+        assert_eq!(rp.password_reset_consume(&token).await, Err(ProviderError::NotFound));
+        assert_eq!(rp.password_reset_request(&email, &client, &token, &expires, &notification).await, Ok(false));
+
+        // the address was asked for 5 times within the hour
+        assert_eq!(
+            rp.password_reset_request(&email, &client, &token, &expires, &notification).await,
+            Err(ProviderError::Throttled)
+        );
+
+        // the client asked for 6 addresses so far, unknown ones count as well
+        for n in 0..14 {
+            let unknown = format!("unknown_{}_{}@test.com", n, user_id.simple());
+            assert_eq!(rp.password_reset_request(&unknown, &client, "unknown", &expires, &message).await, Ok(false));
+        }
+        assert_eq!(
+            rp.password_reset_request(&unknown, &client, "unknown", &expires, &message).await,
+            Err(ProviderError::Throttled)
+        );
+        assert_eq!(queued(&dp, &unknown).await, 0);
+    }
+}
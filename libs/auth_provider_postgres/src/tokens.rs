@@ -0,0 +1,374 @@
+#![allow(clippy::needless_return)]
+
+use tracing::{debug, error, info};
+
+use sqlx::Row;
+
+use auth_provider::tokens::{RefreshSession, TokensProvider};
+
+use provider_error::ProviderError;
+
+pub struct PostgresTokensProvider {
+    dp: database_provider::DatabaseProvider,
+}
+
+impl PostgresTokensProvider {
+    pub fn new(dp: &database_provider::DatabaseProvider) -> Self {
+        return Self { dp: dp.clone() };
+    }
+}
+
+impl TokensProvider for PostgresTokensProvider {
+    async fn refresh_token_add(
+        &self,
+        family_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        tenant_id: &uuid::Uuid,
+        token: &str,
+        expires: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), ProviderError> {
+        info!("refresh_token_add");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call auth.refresh_token_add($1,$2,$3,$4,$5);")
+                .bind(family_id)
+                .bind(user_id)
+                .bind(tenant_id)
+                .bind(token)
+                .bind(expires)
+                .execute(&pool)
+                .await
+            {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("Error adding refresh token: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn refresh_token_rotate(
+        &self,
+        token: &str,
+        new_token: &str,
+        new_expires: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<RefreshSession, ProviderError> {
+        info!("refresh_token_rotate");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from auth.refresh_token_rotate($1,$2,$3);")
+                .bind(token)
+                .bind(new_token)
+                .bind(new_expires)
+                .fetch_optional(&pool)
+                .await
+            {
+                Ok(Some(row)) => {
+                    debug!("{:?}", row);
+
+                    return Ok(RefreshSession {
+                        family_id: row.get("family_id"),
+                        user_id: row.get("user_id"),
+                        tenant_id: row.get("tenant_id"),
+                    });
+                }
+                Ok(None) => {
+                    return Err(ProviderError::NotFound);
+                }
+                Err(e) => {
+                    error!("Error rotating refresh token: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn refresh_family_revoke(
+        &self,
+        user_id: &uuid::Uuid,
+        token: &str,
+    ) -> Result<(), ProviderError> {
+        info!("refresh_family_revoke");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call auth.refresh_family_revoke($1,$2);")
+                .bind(user_id)
+                .bind(token)
+                .execute(&pool)
+                .await
+            {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("Error revoking refresh token family: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn access_token_deny(
+        &self,
+        token_id: &uuid::Uuid,
+        expires: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), ProviderError> {
+        info!("access_token_deny");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call auth.access_token_deny($1,$2);")
+                .bind(token_id)
+                .bind(expires)
+                .execute(&pool)
+                .await
+            {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("Error denying access token: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn user_tokens_revoke(
+        &self,
+        user_id: &uuid::Uuid
+    ) -> Result<chrono::DateTime<chrono::Utc>, ProviderError> {
+        info!("user_tokens_revoke");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query_scalar("select auth.user_tokens_revoke($1);")
+                .bind(user_id)
+                .fetch_one(&pool)
+                .await
+            {
+                Ok(revoked) => {
+                    return Ok(revoked);
+                }
+                Err(e) => {
+                    error!("Error revoking user tokens: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn access_token_is_denied(
+        &self,
+        token_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        issued: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool, ProviderError> {
+        info!("access_token_is_denied");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from auth.access_token_is_denied($1,$2,$3);")
+                .bind(token_id)
+                .bind(user_id)
+                .bind(issued)
+                .fetch_one(&pool)
+                .await
+            {
+                Ok(row) => {
+                    let denied: bool = row.get("access_token_is_denied");
+                    return Ok(denied);
+                }
+                Err(e) => {
+                    error!("Error checking access token deny list: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use users_provider::UsersProvider;
+    use users_provider_postgres::PostgresUsersProvider;
+
+    #[actix_web::test]
+    async fn test_refresh_token_rotation() {
+        if let Err(e) = tracing_subscriber::fmt::try_init() {
+            println!("error: {:?}", e);
+        }
+
+        let cfg = config::Config::from_env();
+        let db_provider = database_provider::DatabaseProvider::new(&cfg);
+        let dp = actix_web::web::Data::new(std::sync::Arc::new(db_provider));
+
+        let user_id = uuid::Uuid::new_v4();
+        let tenant_id = uuid::Uuid::new_v4();
+        let family_id = uuid::Uuid::new_v4();
+        let expires = chrono::Utc::now() + chrono::TimeDelta::hours(1);
+
+        let up = PostgresUsersProvider::new(&dp);
+        if let Err(e) = up.save(&user_id, &"", &"", &"", &"", &"", &0).await {
+            error!("{}", e);
+            assert!(false, "unable to add user");
+        }
+
+        let tp = PostgresTokensProvider::new(&dp);
+
+        let first = uuid::Uuid::new_v4().to_string();
+        let second = uuid::Uuid::new_v4().to_string();
+        let third = uuid::Uuid::new_v4().to_string();
+
+        if let Err(e) = tp
+            .refresh_token_add(&family_id, &user_id, &tenant_id, &first, &expires)
+            .await
+        {
+            error!("{}", e);
+            assert!(false, "unable to add refresh token");
+        }
+
+        match tp.refresh_token_rotate(&first, &second, &expires).await {
+            Err(e) => {
+                error!("{}", e);
+                assert!(false, "unable to rotate refresh token");
+            }
+            Ok(session) => {
+                assert_eq!(session.family_id, family_id);
+                assert_eq!(session.user_id, user_id);
+                assert_eq!(session.tenant_id, tenant_id);
+            }
+        }
+
+        // reusing a rotated token revokes the family, including the token
+        // issued by the rotation
+        assert_eq!(
+            tp.refresh_token_rotate(&first, &third, &expires).await.err(),
+            Some(ProviderError::NotFound)
+        );
+        assert_eq!(
+            tp.refresh_token_rotate(&second, &third, &expires).await.err(),
+            Some(ProviderError::NotFound)
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_refresh_family_revoke() {
+        if let Err(e) = tracing_subscriber::fmt::try_init() {
+            println!("error: {:?}", e);
+        }
+
+        let cfg = config::Config::from_env();
+        let db_provider = database_provider::DatabaseProvider::new(&cfg);
+        let dp = actix_web::web::Data::new(std::sync::Arc::new(db_provider));
+
+        let user_id = uuid::Uuid::new_v4();
+        let family_id = uuid::Uuid::new_v4();
+        let expires = chrono::Utc::now() + chrono::TimeDelta::hours(1);
+
+        let up = PostgresUsersProvider::new(&dp);
+        if let Err(e) = up.save(&user_id, &"", &"", &"", &"", &"", &0).await {
+            error!("{}", e);
+            assert!(false, "unable to add user");
+        }
+
+        let tp = PostgresTokensProvider::new(&dp);
+
+        let token = uuid::Uuid::new_v4().to_string();
+        let next = uuid::Uuid::new_v4().to_string();
+
+        if let Err(e) = tp
+            .refresh_token_add(&family_id, &user_id, &uuid::Uuid::nil(), &token, &expires)
+            .await
+        {
+            error!("{}", e);
+            assert!(false, "unable to add refresh token");
+        }
+
+        if let Err(e) = tp.refresh_family_revoke(&user_id, &token).await {
+            error!("{}", e);
+            assert!(false, "unable to revoke refresh token family");
+        }
+
+        assert_eq!(
+            tp.refresh_token_rotate(&token, &next, &expires).await.err(),
+            Some(ProviderError::NotFound)
+        );
+
+        let token_id = uuid::Uuid::new_v4();
+        let issued = chrono::Utc::now() - chrono::TimeDelta::minutes(1);
+        assert_eq!(tp.access_token_is_denied(&token_id, &user_id, &issued).await, Ok(false));
+
+        if let Err(e) = tp.access_token_deny(&token_id, &expires).await {
+            error!("{}", e);
+            assert!(false, "unable to deny access token");
+        }
+
+        assert_eq!(tp.access_token_is_denied(&token_id, &user_id, &issued).await, Ok(true));
+    }
+
+    #[actix_web::test]
+    async fn test_user_tokens_revoke() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let user_id = uuid::Uuid::new_v4();
+        let expires = chrono::Utc::now() + chrono::TimeDelta::hours(1);
+
+        let up = PostgresUsersProvider::new(&dp);
+        assert!(up.save(&user_id, &"", &"", &"", &"", &"", &0).await.is_ok());
+
+        let tp = PostgresTokensProvider::new(&dp);
+
+        let first = uuid::Uuid::new_v4().to_string();
+        let second = uuid::Uuid::new_v4().to_string();
+        let next = uuid::Uuid::new_v4().to_string();
+        for token in [&first, &second] {
+            assert!(tp.refresh_token_add(&uuid::Uuid::new_v4(), &user_id, &uuid::Uuid::nil(), token, &expires).await.is_ok());
+        }
+
+        let token_id = uuid::Uuid::new_v4();
+        let issued = chrono::Utc::now() - chrono::TimeDelta::minutes(1);
+        assert_eq!(tp.access_token_is_denied(&token_id, &user_id, &issued).await, Ok(false));
+
+        let revoked = tp.user_tokens_revoke(&user_id).await;
+        assert!(revoked.is_ok());
+        let revoked = revoked.unwrap_or_default();
+
+        // every family is revoked, access tokens issued so far are denied
+        for token in [&first, &second] {
+            assert_eq!(
+                tp.refresh_token_rotate(token, &next, &expires).await.err(),
+                Some(ProviderError::NotFound)
+            );
+        }
+        assert_eq!(tp.access_token_is_denied(&token_id, &user_id, &issued).await, Ok(true));
+
+        // tokens issued at the revocation or afterwards are not, nor are
+        // those of other users
+        assert_eq!(tp.access_token_is_denied(&token_id, &user_id, &revoked).await, Ok(false));
+        let later = chrono::Utc::now() + chrono::TimeDelta::seconds(1);
+        assert_eq!(tp.access_token_is_denied(&token_id, &user_id, &later).await, Ok(false));
+        assert_eq!(tp.access_token_is_denied(&token_id, &uuid::Uuid::new_v4(), &issued).await, Ok(false));
+    }
+}
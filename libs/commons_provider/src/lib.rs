@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use provider_error::ProviderError;
+
 #[derive(Serialize)]
 pub struct Country {
     pub country_id: i32,
@@ -37,19 +39,19 @@ pub struct Uom {
 }
 
 pub trait CommonsProvider {
-    fn fetch_countries(&self) -> impl Future<Output = Result<Vec<Country>, &'static str>> + Send;
+    fn fetch_countries(&self) -> impl Future<Output = Result<Vec<Country>, ProviderError>> + Send;
 
-    fn fetch_currencies(&self) -> impl Future<Output = Result<Vec<Currency>, &'static str>> + Send;
+    fn fetch_currencies(&self) -> impl Future<Output = Result<Vec<Currency>, ProviderError>> + Send;
 
-    fn fetch_genders(&self) -> impl Future<Output = Result<Vec<Gender>, &'static str>> + Send;
+    fn fetch_genders(&self) -> impl Future<Output = Result<Vec<Gender>, ProviderError>> + Send;
 
     fn fetch_dimensions(&self)
-    -> impl Future<Output = Result<Vec<Dimension>, &'static str>> + Send;
+    -> impl Future<Output = Result<Vec<Dimension>, ProviderError>> + Send;
 
-    fn fetch_uoms(&self) -> impl Future<Output = Result<Vec<Uom>, &'static str>> + Send;
+    fn fetch_uoms(&self) -> impl Future<Output = Result<Vec<Uom>, ProviderError>> + Send;
 
     fn fetch_uoms_by_dimension_id(
         &self,
         dimension_id: &i16,
-    ) -> impl Future<Output = Result<Vec<Uom>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Uom>, ProviderError>> + Send;
 }
@@ -0,0 +1 @@
+drop schema if exists common cascade;
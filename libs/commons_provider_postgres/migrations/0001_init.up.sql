@@ -0,0 +1,235 @@
+-- reference data shared by all modules
+
+create schema if not exists common;
+
+
+create table common.countries (
+    iso_3166_1_numeric int not null,
+    official_name_en text not null,
+    iso_3166_1_alpha_2 char(2) not null,
+    iso_3166_1_alpha_3 char(3) not null,
+    constraint pk_countries primary key (iso_3166_1_numeric),
+    constraint u_countries_alpha_2 unique (iso_3166_1_alpha_2),
+    constraint u_countries_alpha_3 unique (iso_3166_1_alpha_3)
+);
+
+create table common.currencies (
+    currency_id int not null,
+    currency text not null,
+    unit_text text not null,
+    symbol text,
+    constraint pk_currencies primary key (currency_id),
+    constraint u_currencies_unit_text unique (unit_text)
+);
+
+create table common.genders (
+    id smallint not null,
+    name text not null,
+    constraint pk_genders primary key (id),
+    constraint u_genders_name unique (name)
+);
+
+create table common.dimensions (
+    dimension_id smallint not null,
+    name text not null,
+    constraint pk_dimensions primary key (dimension_id),
+    constraint u_dimensions_name unique (name)
+);
+
+create table common.uom (
+    uom_id bigint not null,
+    dimension_id smallint not null,
+    name text not null,
+    symbol text,
+    constraint pk_uom primary key (uom_id),
+    constraint fk_uom_dimension foreign key (dimension_id) references common.dimensions (dimension_id),
+    constraint u_uom_name unique (dimension_id, name)
+);
+
+
+insert into common.countries (iso_3166_1_numeric, official_name_en, iso_3166_1_alpha_2, iso_3166_1_alpha_3) values
+    (36, 'Australia', 'AU', 'AUS'),
+    (76, 'Brazil', 'BR', 'BRA'),
+    (124, 'Canada', 'CA', 'CAN'),
+    (156, 'China', 'CN', 'CHN'),
+    (250, 'France', 'FR', 'FRA'),
+    (276, 'Germany', 'DE', 'DEU'),
+    (344, 'China, Hong Kong Special Administrative Region', 'HK', 'HKG'),
+    (356, 'India', 'IN', 'IND'),
+    (360, 'Indonesia', 'ID', 'IDN'),
+    (392, 'Japan', 'JP', 'JPN'),
+    (410, 'Republic of Korea', 'KR', 'KOR'),
+    (458, 'Malaysia', 'MY', 'MYS'),
+    (484, 'Mexico', 'MX', 'MEX'),
+    (554, 'New Zealand', 'NZ', 'NZL'),
+    (608, 'Philippines', 'PH', 'PHL'),
+    (702, 'Singapore', 'SG', 'SGP'),
+    (724, 'Spain', 'ES', 'ESP'),
+    (764, 'Thailand', 'TH', 'THA'),
+    (826, 'United Kingdom of Great Britain and Northern Ireland', 'GB', 'GBR'),
+    (840, 'United States of America', 'US', 'USA'),
+    (704, 'Viet Nam', 'VN', 'VNM');
+
+insert into common.currencies (currency_id, currency, unit_text, symbol) values
+    (36, 'Australian Dollar', 'AUD', '$'),
+    (124, 'Canadian Dollar', 'CAD', '$'),
+    (156, 'Yuan Renminbi', 'CNY', '¥'),
+    (344, 'Hong Kong Dollar', 'HKD', '$'),
+    (356, 'Indian Rupee', 'INR', '₹'),
+    (392, 'Yen', 'JPY', '¥'),
+    (410, 'Won', 'KRW', '₩'),
+    (458, 'Malaysian Ringgit', 'MYR', 'RM'),
+    (554, 'New Zealand Dollar', 'NZD', '$'),
+    (608, 'Philippine Peso', 'PHP', '₱'),
+    (702, 'Singapore Dollar', 'SGD', '$'),
+    (764, 'Baht', 'THB', '฿'),
+    (826, 'Pound Sterling', 'GBP', '£'),
+    (840, 'US Dollar', 'USD', '$'),
+    (978, 'Euro', 'EUR', '€');
+
+insert into common.genders (id, name) values
+    (1, 'male'),
+    (2, 'female'),
+    (3, 'unspecified');
+
+insert into common.dimensions (dimension_id, name) values
+    (1, 'quantity'),
+    (2, 'length'),
+    (3, 'mass'),
+    (4, 'volume'),
+    (5, 'area'),
+    (6, 'time');
+
+insert into common.uom (uom_id, dimension_id, name, symbol) values
+    (1, 1, 'each', 'ea'),
+    (2, 1, 'dozen', 'dz'),
+    (3, 1, 'box', null),
+    (4, 1, 'pallet', null),
+    (10, 2, 'millimeter', 'mm'),
+    (11, 2, 'centimeter', 'cm'),
+    (12, 2, 'meter', 'm'),
+    (13, 2, 'kilometer', 'km'),
+    (14, 2, 'inch', 'in'),
+    (15, 2, 'foot', 'ft'),
+    (20, 3, 'gram', 'g'),
+    (21, 3, 'kilogram', 'kg'),
+    (22, 3, 'metric ton', 't'),
+    (23, 3, 'pound', 'lb'),
+    (30, 4, 'milliliter', 'ml'),
+    (31, 4, 'liter', 'l'),
+    (32, 4, 'cubic meter', 'm³'),
+    (33, 4, 'gallon', 'gal'),
+    (40, 5, 'square meter', 'm²'),
+    (41, 5, 'square foot', 'ft²'),
+    (50, 6, 'hour', 'h'),
+    (51, 6, 'day', 'd');
+
+
+create function common.countries_fetch_all()
+returns table (
+    iso_3166_1_numeric int,
+    official_name_en text,
+    iso_3166_1_alpha_2 text,
+    iso_3166_1_alpha_3 text
+)
+language sql
+stable
+as $$
+    select
+        c.iso_3166_1_numeric,
+        c.official_name_en,
+        c.iso_3166_1_alpha_2::text,
+        c.iso_3166_1_alpha_3::text
+    from common.countries c
+    order by c.official_name_en;
+$$;
+
+create function common.currencies_fetch_all()
+returns table (
+    currency_id int,
+    currency text,
+    unit_text text,
+    symbol text
+)
+language sql
+stable
+as $$
+    select
+        c.currency_id,
+        c.currency,
+        c.unit_text,
+        c.symbol
+    from common.currencies c
+    order by c.currency;
+$$;
+
+create function common.genders_fetch_all()
+returns table (
+    id smallint,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        g.id,
+        g.name
+    from common.genders g
+    order by g.id;
+$$;
+
+create function common.dimensions_fetch_all()
+returns table (
+    dimension_id smallint,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        d.dimension_id,
+        d.name
+    from common.dimensions d
+    order by d.dimension_id;
+$$;
+
+create function common.uom_fetch_all()
+returns table (
+    uom_id bigint,
+    dimension_id smallint,
+    name text,
+    symbol text
+)
+language sql
+stable
+as $$
+    select
+        u.uom_id,
+        u.dimension_id,
+        u.name,
+        u.symbol
+    from common.uom u
+    order by u.dimension_id, u.uom_id;
+$$;
+
+create function common.uom_fetch_all_by_dimension_id(
+    p_dimension_id smallint
+)
+returns table (
+    uom_id bigint,
+    dimension_id smallint,
+    name text,
+    symbol text
+)
+language sql
+stable
+as $$
+    select
+        u.uom_id,
+        u.dimension_id,
+        u.name,
+        u.symbol
+    from common.uom u
+    where u.dimension_id = p_dimension_id
+    order by u.uom_id;
+$$;
@@ -4,6 +4,22 @@ use tracing::{debug, error, info};
 
 use sqlx::Row;
 
+use provider_error::ProviderError;
+
+/// schema migrations for the `commons` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "commons",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        }
+    ]
+};
+
+
 pub struct PostgresCommonsProvider {
     dp: database_provider::DatabaseProvider,
 }
@@ -15,7 +31,7 @@ impl PostgresCommonsProvider {
 }
 
 impl commons_provider::CommonsProvider for PostgresCommonsProvider {
-    async fn fetch_countries(&self) -> Result<Vec<commons_provider::Country>, &'static str> {
+    async fn fetch_countries(&self) -> Result<Vec<commons_provider::Country>, ProviderError> {
         info!("fetch_countries");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -45,16 +61,16 @@ impl commons_provider::CommonsProvider for PostgresCommonsProvider {
                 }
                 Err(e) => {
                     error!("Error fetching countries: {:?}", e);
-                    return Err("Error fetching countries");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn fetch_currencies(&self) -> Result<Vec<commons_provider::Currency>, &'static str> {
+    async fn fetch_currencies(&self) -> Result<Vec<commons_provider::Currency>, ProviderError> {
         info!("fetch_currencies");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -84,16 +100,16 @@ impl commons_provider::CommonsProvider for PostgresCommonsProvider {
                 }
                 Err(e) => {
                     error!("Error fetching currencies: {:?}", e);
-                    return Err("Error fetching currencies");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn fetch_genders(&self) -> Result<Vec<commons_provider::Gender>, &'static str> {
+    async fn fetch_genders(&self) -> Result<Vec<commons_provider::Gender>, ProviderError> {
         info!("fetch_genders");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -119,16 +135,16 @@ impl commons_provider::CommonsProvider for PostgresCommonsProvider {
                 }
                 Err(e) => {
                     error!("Error fetching genders: {:?}", e);
-                    return Err("Error fetching genders");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn fetch_dimensions(&self) -> Result<Vec<commons_provider::Dimension>, &'static str> {
+    async fn fetch_dimensions(&self) -> Result<Vec<commons_provider::Dimension>, ProviderError> {
         info!("fetch_dimensions");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -154,16 +170,16 @@ impl commons_provider::CommonsProvider for PostgresCommonsProvider {
                 }
                 Err(e) => {
                     error!("Error fetching dimensions: {:?}", e);
-                    return Err("Error fetching dimensions");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn fetch_uoms(&self) -> Result<Vec<commons_provider::Uom>, &'static str> {
+    async fn fetch_uoms(&self) -> Result<Vec<commons_provider::Uom>, ProviderError> {
         info!("fetch_uoms");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -193,19 +209,19 @@ impl commons_provider::CommonsProvider for PostgresCommonsProvider {
                 }
                 Err(e) => {
                     error!("Error fetching uoms: {:?}", e);
-                    return Err("Error fetching uoms");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn fetch_uoms_by_dimension_id(
         &self,
         dimension_id: &i16,
-    ) -> Result<Vec<commons_provider::Uom>, &'static str> {
+    ) -> Result<Vec<commons_provider::Uom>, ProviderError> {
         info!("fetch_uoms_by_dimension_id");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -236,12 +252,12 @@ impl commons_provider::CommonsProvider for PostgresCommonsProvider {
                 }
                 Err(e) => {
                     error!("Error fetching uoms: {:?}", e);
-                    return Err("Error fetching uoms");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
@@ -265,32 +281,32 @@ mod tests {
         let cp = PostgresCommonsProvider::new(&dp);
 
         if let Err(e) = cp.fetch_countries().await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch countries");
         }
 
         if let Err(e) = cp.fetch_currencies().await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch currencies");
         }
 
         if let Err(e) = cp.fetch_genders().await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch genders");
         }
 
         if let Err(e) = cp.fetch_dimensions().await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch dimensions");
         }
 
         if let Err(e) = cp.fetch_uoms().await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch uoms");
         }
 
         if let Err(e) = cp.fetch_uoms_by_dimension_id(&1).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch uoms by dimension id");
         }
     }
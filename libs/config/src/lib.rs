@@ -1,92 +1,1131 @@
 #![allow(clippy::needless_return)]
 
 use tracing::{
-    info,
     error,
     debug
 };
 
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
 
 use serde::Deserialize;
 
 
 
 
-const DEFAULT_HTTP_PORT: u16 = 80;
+const DEFAULT_BIND_ADDRESS: &str = "localhost";
+const DEFAULT_HTTP_PORT: u16 = 8080;
+const DEFAULT_PUBLIC_URL: &str = "http://localhost:8080";
+const DEFAULT_WORKERS: usize = 2;
+const DEFAULT_POOL_MAX_CONNECTIONS: u32 = 5;
+const DEFAULT_POOL_MIN_CONNECTIONS: u32 = 0;
+const DEFAULT_CORS_ORIGIN: &str = "*";
 const DEFAULT_TOKEN_SECRET: &str = "replace_me";
+const DEFAULT_ACCESS_TOKEN_LIFETIME: i64 = 60 * 60;
+const DEFAULT_REFRESH_TOKEN_LIFETIME: i64 = 60 * 60 * 24 * 14;
+const DEFAULT_MAIL_FROM: &str = "nexus@localhost";
+const DEFAULT_SMTP_PORT: u16 = 587;
+const DEFAULT_SENDMAIL_PATH: &str = "/usr/sbin/sendmail";
+const DEFAULT_MAIL_FILE_PATH: &str = "/var/tmp/nexus/mail";
+const DEFAULT_FILE_STORE_ROOT: &str = "/var/tmp/nexus/files";
+const DEFAULT_FILE_MAX_SIZE: u64 = 100 * 1024 * 1024;
+const DEFAULT_OUTBOX_POLL_INTERVAL: i64 = 10;
+const DEFAULT_OUTBOX_MAX_ATTEMPTS: i32 = 8;
+const DEFAULT_OUTBOX_BACKOFF: i64 = 30;
+const DEFAULT_OUTBOX_MAX_BACKOFF: i64 = 60 * 60 * 6;
+const DEFAULT_PREVIEW_SIZE: u32 = 256;
+const DEFAULT_PREVIEW_TIMEOUT: i64 = 30;
+const DEFAULT_PREVIEW_POLL_INTERVAL: i64 = 10;
+const DEFAULT_SEARCH_MAX_SIZE: u64 = 50 * 1024 * 1024;
+const DEFAULT_SEARCH_POLL_INTERVAL: i64 = 10;
+const DEFAULT_PASSWORD_MEMORY_COST: u32 = 19 * 1024;
+const DEFAULT_PASSWORD_TIME_COST: u32 = 2;
+const DEFAULT_PASSWORD_PARALLELISM: u32 = 1;
+const DEFAULT_PASSWORD_RESET_LIFETIME: i64 = 60 * 60;
+const DEFAULT_PASSWORD_MIN_LENGTH: usize = 8;
+const DEFAULT_PASSWORD_MAX_LENGTH: usize = 128;
+
+const MAIN_CONNECTION: &str = "main";
+
+
+#[derive(Debug)]
+pub enum ConfigError {
+    File(String),
+    Environment(String),
+    MalformedConnection(String),
+    DefaultSecret,
+    Invalid(String)
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::File(e) => write!(f, "unable to read configuration file: {}", e),
+            ConfigError::Environment(e) => write!(f, "unable to read configuration from environment: {}", e),
+            ConfigError::MalformedConnection(e) => write!(f, "malformed connection entry: {}", e),
+            ConfigError::DefaultSecret => write!(f, "token secret is empty or still set to the default value"),
+            ConfigError::Invalid(e) => write!(f, "invalid configuration: {}", e)
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+
+/// wrapper that keeps secrets out of debug output
+#[derive(Clone, Default)]
+struct Secret(String);
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "\"\"");
+        }
+        return write!(f, "\"********\"");
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MailTransport {
+    Log,
+    Smtp,
+    Sendmail,
+    File
+}
+
+impl FromStr for MailTransport {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "log" => Ok(MailTransport::Log),
+            "smtp" => Ok(MailTransport::Smtp),
+            "sendmail" => Ok(MailTransport::Sendmail),
+            "file" => Ok(MailTransport::File),
+            other => Err(ConfigError::Invalid(format!("unknown mail transport '{}'", other)))
+        }
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct MailConfig {
+    pub transport: MailTransport,
+    pub from: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    smtp_password: Secret,
+    pub smtp_starttls: bool,
+    pub sendmail_path: String,
+    pub file_path: String
+}
+
+impl MailConfig {
+    pub fn smtp_password(&self) -> String {
+        return self.smtp_password.0.clone();
+    }
+}
+
+
+/// delivery of queued mail, intervals in seconds; a failed message is
+/// retried after `backoff * 2^(attempts - 1)` seconds, capped at
+/// `max_backoff`, and dead-lettered after `max_attempts`
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    pub poll_interval: i64,
+    pub max_attempts: i32,
+    pub backoff: i64,
+    pub max_backoff: i64
+}
+
+
+/// limits applied to uploaded content, sizes in bytes; a `default_quota`
+/// of 0 leaves tenants without a quota of their own unlimited and an empty
+/// `allowed_types` allows any content type
+#[derive(Debug, Clone)]
+pub struct UploadConfig {
+    pub max_size: u64,
+    pub default_quota: u64,
+    pub allowed_types: Vec<String>
+}
+
+impl UploadConfig {
+
+    /// whether `content_type` matches one of the allowed types, `image/*`
+    /// matches every image type
+    pub fn allows(&self, content_type: &str) -> bool {
+        if self.allowed_types.is_empty() {
+            return true;
+        }
+        let content_type = content_type.to_ascii_lowercase();
+        return self.allowed_types.iter().any(|allowed| {
+            let allowed = allowed.to_ascii_lowercase();
+            return match allowed.strip_suffix("/*") {
+                Some(kind) => content_type.split('/').next() == Some(kind),
+                None => content_type == allowed
+            };
+        });
+    }
+}
+
+
+/// rendering of previews, intervals in seconds; images are scaled down to
+/// at most `size` pixels in width and height by the worker, for other
+/// content `command` is run as `command <size> <content type>` with the
+/// content on stdin and writes such an image to stdout, only images get a
+/// preview while it is empty
+#[derive(Debug, Clone)]
+pub struct PreviewConfig {
+    pub command: String,
+    pub size: u32,
+    pub timeout: i64,
+    pub poll_interval: i64
+}
+
+
+/// extraction of document text for the search index, interval in seconds;
+/// text is not extracted from content larger than `max_size` bytes
+#[derive(Debug, Clone)]
+pub struct SearchConfig {
+    pub max_size: u64,
+    pub poll_interval: i64
+}
+
+
+/// argon2id cost of password hashes, `memory_cost` in KiB; hashes made
+/// with other values are replaced on the next sign in
+#[derive(Debug, Clone)]
+pub struct PasswordConfig {
+    pub memory_cost: u32,
+    pub time_cost: u32,
+    pub parallelism: u32,
+    /// seconds a password reset link can be used
+    pub reset_lifetime: i64,
+    /// length of new passwords in characters
+    pub min_length: usize,
+    pub max_length: usize
+}
+
+
+/// asymmetric token signing key, `path` points to a PEM encoded private key
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenKey {
+    pub kid: String,
+    pub path: String
+}
+
+
+/// master key of the file store, `path` points to a file holding 32 random
+/// bytes, base64 encoded
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MasterKey {
+    pub id: String,
+    pub path: String
+}
+
+
+/// command line flags, the highest priority configuration layer
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct CliArgs {
+    /// path to a TOML configuration file
+    #[arg(long = "config", value_name = "FILE")]
+    pub config_file: Option<String>,
+
+    /// address the http server binds to
+    #[arg(long)]
+    pub bind_address: Option<String>,
+
+    /// port the http server listens on
+    #[arg(long)]
+    pub http_port: Option<u16>,
+
+    /// public base url used for links in mail
+    #[arg(long)]
+    pub public_url: Option<String>,
+
+    /// number of http worker threads
+    #[arg(long)]
+    pub workers: Option<usize>,
+
+    /// maximum number of connections per database pool
+    #[arg(long)]
+    pub pool_max_connections: Option<u32>,
+
+    /// allowed CORS origin, may be repeated
+    #[arg(long = "cors-origin", value_name = "ORIGIN")]
+    pub cors_origins: Vec<String>,
+
+    /// mail transport (log, smtp, sendmail, file)
+    #[arg(long)]
+    pub mail_transport: Option<String>,
+
+    /// root directory of the local file store
+    #[arg(long)]
+    pub file_store_root: Option<String>
+}
 
 
 #[derive(Debug, Deserialize)]
 struct EnvironmentConfig {
+    config_file: Option<String>,
+    bind_address: Option<String>,
     http_port: Option<u16>,
+    public_url: Option<String>,
+    workers: Option<usize>,
     cn: Option<String>,
-    token_secret: Option<String>
+    pool_max_connections: Option<u32>,
+    pool_min_connections: Option<u32>,
+    migrate_on_startup: Option<bool>,
+    cors_origins: Option<String>,
+    token_secret: Option<String>,
+    token_keys: Option<String>,
+    access_token_lifetime: Option<i64>,
+    refresh_token_lifetime: Option<i64>,
+    mail_transport: Option<String>,
+    mail_from: Option<String>,
+    smtp_host: Option<String>,
+    smtp_port: Option<u16>,
+    smtp_username: Option<String>,
+    smtp_password: Option<String>,
+    smtp_starttls: Option<bool>,
+    sendmail_path: Option<String>,
+    mail_file_path: Option<String>,
+    outbox_poll_interval: Option<i64>,
+    outbox_max_attempts: Option<i32>,
+    outbox_backoff: Option<i64>,
+    outbox_max_backoff: Option<i64>,
+    file_store_root: Option<String>,
+    file_store_max_size: Option<u64>,
+    file_store_default_quota: Option<u64>,
+    file_store_allowed_types: Option<String>,
+    file_store_master_keys: Option<String>,
+    preview_command: Option<String>,
+    preview_size: Option<u32>,
+    preview_timeout: Option<i64>,
+    preview_poll_interval: Option<i64>,
+    search_max_size: Option<u64>,
+    search_poll_interval: Option<i64>,
+    password_memory_cost: Option<u32>,
+    password_time_cost: Option<u32>,
+    password_parallelism: Option<u32>,
+    password_reset_lifetime: Option<i64>,
+    password_min_length: Option<usize>,
+    password_max_length: Option<usize>
+}
+
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    http: Option<FileHttpConfig>,
+    database: Option<FileDatabaseConfig>,
+    cors: Option<FileCorsConfig>,
+    token: Option<FileTokenConfig>,
+    mail: Option<FileMailConfig>,
+    outbox: Option<FileOutboxConfig>,
+    file_store: Option<FileStoreConfig>,
+    previews: Option<FilePreviewConfig>,
+    search: Option<FileSearchConfig>,
+    password: Option<FilePasswordConfig>
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileHttpConfig {
+    bind_address: Option<String>,
+    port: Option<u16>,
+    public_url: Option<String>,
+    workers: Option<usize>
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileDatabaseConfig {
+    max_connections: Option<u32>,
+    min_connections: Option<u32>,
+    migrate_on_startup: Option<bool>,
+    connections: Option<HashMap<String, String>>
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileCorsConfig {
+    origins: Option<Vec<String>>
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileTokenConfig {
+    secret: Option<String>,
+    keys: Option<Vec<TokenKey>>,
+    access_lifetime: Option<i64>,
+    refresh_lifetime: Option<i64>
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileMailConfig {
+    transport: Option<String>,
+    from: Option<String>,
+    smtp_host: Option<String>,
+    smtp_port: Option<u16>,
+    smtp_username: Option<String>,
+    smtp_password: Option<String>,
+    smtp_starttls: Option<bool>,
+    sendmail_path: Option<String>,
+    file_path: Option<String>
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileOutboxConfig {
+    poll_interval: Option<i64>,
+    max_attempts: Option<i32>,
+    backoff: Option<i64>,
+    max_backoff: Option<i64>
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileStoreConfig {
+    root: Option<String>,
+    max_size: Option<u64>,
+    default_quota: Option<u64>,
+    allowed_types: Option<Vec<String>>,
+    master_keys: Option<Vec<MasterKey>>
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FilePreviewConfig {
+    command: Option<String>,
+    size: Option<u32>,
+    timeout: Option<i64>,
+    poll_interval: Option<i64>
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileSearchConfig {
+    max_size: Option<u64>,
+    poll_interval: Option<i64>
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FilePasswordConfig {
+    memory_cost: Option<u32>,
+    time_cost: Option<u32>,
+    parallelism: Option<u32>,
+    reset_lifetime: Option<i64>,
+    min_length: Option<usize>,
+    max_length: Option<usize>
+}
+
+
+/// a single configuration source, values left as `None` fall through
+/// to the layer below
+#[derive(Debug, Default)]
+struct Layer {
+    bind_address: Option<String>,
+    http_port: Option<u16>,
+    public_url: Option<String>,
+    workers: Option<usize>,
+    connections: Option<HashMap<String, String>>,
+    pool_max_connections: Option<u32>,
+    pool_min_connections: Option<u32>,
+    migrate_on_startup: Option<bool>,
+    cors_origins: Option<Vec<String>>,
+    token_secret: Option<String>,
+    token_keys: Option<Vec<TokenKey>>,
+    access_token_lifetime: Option<i64>,
+    refresh_token_lifetime: Option<i64>,
+    mail_transport: Option<MailTransport>,
+    mail_from: Option<String>,
+    smtp_host: Option<String>,
+    smtp_port: Option<u16>,
+    smtp_username: Option<String>,
+    smtp_password: Option<String>,
+    smtp_starttls: Option<bool>,
+    sendmail_path: Option<String>,
+    mail_file_path: Option<String>,
+    outbox_poll_interval: Option<i64>,
+    outbox_max_attempts: Option<i32>,
+    outbox_backoff: Option<i64>,
+    outbox_max_backoff: Option<i64>,
+    file_store_root: Option<String>,
+    file_store_max_size: Option<u64>,
+    file_store_default_quota: Option<u64>,
+    file_store_allowed_types: Option<Vec<String>>,
+    file_store_master_keys: Option<Vec<MasterKey>>,
+    preview_command: Option<String>,
+    preview_size: Option<u32>,
+    preview_timeout: Option<i64>,
+    preview_poll_interval: Option<i64>,
+    search_max_size: Option<u64>,
+    search_poll_interval: Option<i64>,
+    password_memory_cost: Option<u32>,
+    password_time_cost: Option<u32>,
+    password_parallelism: Option<u32>,
+    password_reset_lifetime: Option<i64>,
+    password_min_length: Option<usize>,
+    password_max_length: Option<usize>
+}
+
+
+impl Layer {
+
+    fn from_file(path: &str) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::File(format!("{}: {}", path, e)))?;
+
+        return Self::from_toml(&contents)
+            .map_err(|e| match e {
+                ConfigError::File(e) => ConfigError::File(format!("{}: {}", path, e)),
+                e => e
+            });
+    }
+
+    fn from_toml(contents: &str) -> Result<Self, ConfigError> {
+        let file: FileConfig = toml::from_str(contents)
+            .map_err(|e| ConfigError::File(e.to_string()))?;
+
+        let http = file.http.unwrap_or_default();
+        let database = file.database.unwrap_or_default();
+        let cors = file.cors.unwrap_or_default();
+        let token = file.token.unwrap_or_default();
+        let mail = file.mail.unwrap_or_default();
+        let outbox = file.outbox.unwrap_or_default();
+        let file_store = file.file_store.unwrap_or_default();
+        let previews = file.previews.unwrap_or_default();
+        let search = file.search.unwrap_or_default();
+        let password = file.password.unwrap_or_default();
+
+        if let Some(connections) = &database.connections {
+            for (name, url) in connections.iter() {
+                check_connection(name, url)?;
+            }
+        }
+
+        return Ok(Self {
+            bind_address: http.bind_address,
+            http_port: http.port,
+            public_url: http.public_url,
+            workers: http.workers,
+            connections: database.connections,
+            pool_max_connections: database.max_connections,
+            pool_min_connections: database.min_connections,
+            migrate_on_startup: database.migrate_on_startup,
+            cors_origins: cors.origins,
+            token_secret: token.secret,
+            token_keys: token.keys,
+            access_token_lifetime: token.access_lifetime,
+            refresh_token_lifetime: token.refresh_lifetime,
+            mail_transport: mail.transport.map(|t| MailTransport::from_str(&t)).transpose()?,
+            mail_from: mail.from,
+            smtp_host: mail.smtp_host,
+            smtp_port: mail.smtp_port,
+            smtp_username: mail.smtp_username,
+            smtp_password: mail.smtp_password,
+            smtp_starttls: mail.smtp_starttls,
+            sendmail_path: mail.sendmail_path,
+            mail_file_path: mail.file_path,
+            outbox_poll_interval: outbox.poll_interval,
+            outbox_max_attempts: outbox.max_attempts,
+            outbox_backoff: outbox.backoff,
+            outbox_max_backoff: outbox.max_backoff,
+            file_store_root: file_store.root,
+            file_store_max_size: file_store.max_size,
+            file_store_default_quota: file_store.default_quota,
+            file_store_allowed_types: file_store.allowed_types,
+            file_store_master_keys: file_store.master_keys,
+            preview_command: previews.command,
+            preview_size: previews.size,
+            preview_timeout: previews.timeout,
+            preview_poll_interval: previews.poll_interval,
+            search_max_size: search.max_size,
+            search_poll_interval: search.poll_interval,
+            password_memory_cost: password.memory_cost,
+            password_time_cost: password.time_cost,
+            password_parallelism: password.parallelism,
+            password_reset_lifetime: password.reset_lifetime,
+            password_min_length: password.min_length,
+            password_max_length: password.max_length
+        });
+    }
+
+    fn from_environment(env: EnvironmentConfig) -> Result<Self, ConfigError> {
+        return Ok(Self {
+            bind_address: env.bind_address,
+            http_port: env.http_port,
+            public_url: env.public_url,
+            workers: env.workers,
+            connections: env.cn.map(|cn| parse_connections(&cn)).transpose()?,
+            pool_max_connections: env.pool_max_connections,
+            pool_min_connections: env.pool_min_connections,
+            migrate_on_startup: env.migrate_on_startup,
+            cors_origins: env.cors_origins.map(|origins| split_list(&origins)),
+            token_secret: env.token_secret,
+            token_keys: env.token_keys.map(|keys| parse_token_keys(&keys)).transpose()?,
+            access_token_lifetime: env.access_token_lifetime,
+            refresh_token_lifetime: env.refresh_token_lifetime,
+            mail_transport: env.mail_transport.map(|t| MailTransport::from_str(&t)).transpose()?,
+            mail_from: env.mail_from,
+            smtp_host: env.smtp_host,
+            smtp_port: env.smtp_port,
+            smtp_username: env.smtp_username,
+            smtp_password: env.smtp_password,
+            smtp_starttls: env.smtp_starttls,
+            sendmail_path: env.sendmail_path,
+            mail_file_path: env.mail_file_path,
+            outbox_poll_interval: env.outbox_poll_interval,
+            outbox_max_attempts: env.outbox_max_attempts,
+            outbox_backoff: env.outbox_backoff,
+            outbox_max_backoff: env.outbox_max_backoff,
+            file_store_root: env.file_store_root,
+            file_store_max_size: env.file_store_max_size,
+            file_store_default_quota: env.file_store_default_quota,
+            file_store_allowed_types: env.file_store_allowed_types.map(|types| split_list(&types)),
+            file_store_master_keys: env.file_store_master_keys.map(|keys| parse_master_keys(&keys)).transpose()?,
+            preview_command: env.preview_command,
+            preview_size: env.preview_size,
+            preview_timeout: env.preview_timeout,
+            preview_poll_interval: env.preview_poll_interval,
+            search_max_size: env.search_max_size,
+            search_poll_interval: env.search_poll_interval,
+            password_memory_cost: env.password_memory_cost,
+            password_time_cost: env.password_time_cost,
+            password_parallelism: env.password_parallelism,
+            password_reset_lifetime: env.password_reset_lifetime,
+            password_min_length: env.password_min_length,
+            password_max_length: env.password_max_length
+        });
+    }
+
+    fn from_cli(args: &CliArgs) -> Result<Self, ConfigError> {
+        return Ok(Self {
+            bind_address: args.bind_address.clone(),
+            http_port: args.http_port,
+            public_url: args.public_url.clone(),
+            workers: args.workers,
+            pool_max_connections: args.pool_max_connections,
+            cors_origins: if args.cors_origins.is_empty() { None } else { Some(args.cors_origins.clone()) },
+            mail_transport: args.mail_transport.as_deref().map(MailTransport::from_str).transpose()?,
+            file_store_root: args.file_store_root.clone(),
+            ..Default::default()
+        });
+    }
+
+    /// values set in `over` take precedence over values in `self`
+    fn merge(self, over: Layer) -> Layer {
+        return Layer {
+            bind_address: over.bind_address.or(self.bind_address),
+            http_port: over.http_port.or(self.http_port),
+            public_url: over.public_url.or(self.public_url),
+            workers: over.workers.or(self.workers),
+            connections: over.connections.or(self.connections),
+            pool_max_connections: over.pool_max_connections.or(self.pool_max_connections),
+            pool_min_connections: over.pool_min_connections.or(self.pool_min_connections),
+            migrate_on_startup: over.migrate_on_startup.or(self.migrate_on_startup),
+            cors_origins: over.cors_origins.or(self.cors_origins),
+            token_secret: over.token_secret.or(self.token_secret),
+            token_keys: over.token_keys.or(self.token_keys),
+            access_token_lifetime: over.access_token_lifetime.or(self.access_token_lifetime),
+            refresh_token_lifetime: over.refresh_token_lifetime.or(self.refresh_token_lifetime),
+            mail_transport: over.mail_transport.or(self.mail_transport),
+            mail_from: over.mail_from.or(self.mail_from),
+            smtp_host: over.smtp_host.or(self.smtp_host),
+            smtp_port: over.smtp_port.or(self.smtp_port),
+            smtp_username: over.smtp_username.or(self.smtp_username),
+            smtp_password: over.smtp_password.or(self.smtp_password),
+            smtp_starttls: over.smtp_starttls.or(self.smtp_starttls),
+            sendmail_path: over.sendmail_path.or(self.sendmail_path),
+            mail_file_path: over.mail_file_path.or(self.mail_file_path),
+            outbox_poll_interval: over.outbox_poll_interval.or(self.outbox_poll_interval),
+            outbox_max_attempts: over.outbox_max_attempts.or(self.outbox_max_attempts),
+            outbox_backoff: over.outbox_backoff.or(self.outbox_backoff),
+            outbox_max_backoff: over.outbox_max_backoff.or(self.outbox_max_backoff),
+            file_store_root: over.file_store_root.or(self.file_store_root),
+            file_store_max_size: over.file_store_max_size.or(self.file_store_max_size),
+            file_store_default_quota: over.file_store_default_quota.or(self.file_store_default_quota),
+            file_store_allowed_types: over.file_store_allowed_types.or(self.file_store_allowed_types),
+            file_store_master_keys: over.file_store_master_keys.or(self.file_store_master_keys),
+            preview_command: over.preview_command.or(self.preview_command),
+            preview_size: over.preview_size.or(self.preview_size),
+            preview_timeout: over.preview_timeout.or(self.preview_timeout),
+            preview_poll_interval: over.preview_poll_interval.or(self.preview_poll_interval),
+            search_max_size: over.search_max_size.or(self.search_max_size),
+            search_poll_interval: over.search_poll_interval.or(self.search_poll_interval),
+            password_memory_cost: over.password_memory_cost.or(self.password_memory_cost),
+            password_time_cost: over.password_time_cost.or(self.password_time_cost),
+            password_parallelism: over.password_parallelism.or(self.password_parallelism),
+            password_reset_lifetime: over.password_reset_lifetime.or(self.password_reset_lifetime),
+            password_min_length: over.password_min_length.or(self.password_min_length),
+            password_max_length: over.password_max_length.or(self.password_max_length)
+        };
+    }
+}
+
+
+/// parses the `CN` format: `name=url[,name=url...]`
+fn parse_connections(cn: &str) -> Result<HashMap<String, String>, ConfigError> {
+    let mut connection_strings: HashMap<String, String> = HashMap::new();
+
+    for kv in cn.split(",").map(str::trim).filter(|kv| !kv.is_empty()) {
+        let Some((name, url)) = kv.split_once("=") else {
+            return Err(ConfigError::MalformedConnection(format!("'{}' is not in the form name=url", redact_connection(kv))));
+        };
+
+        let name = name.trim();
+        let url = url.trim();
+        check_connection(name, url)?;
+
+        if connection_strings.insert(name.to_string(), url.to_string()).is_some() {
+            return Err(ConfigError::MalformedConnection(format!("connection '{}' is defined more than once", name)));
+        }
+    }
+
+    return Ok(connection_strings);
+}
+
+/// parses the `TOKEN_KEYS` format: `kid=path[,kid=path...]`, oldest first
+fn parse_token_keys(keys: &str) -> Result<Vec<TokenKey>, ConfigError> {
+    let mut token_keys: Vec<TokenKey> = Vec::new();
+
+    for kv in keys.split(",").map(str::trim).filter(|kv| !kv.is_empty()) {
+        let Some((kid, path)) = kv.split_once("=") else {
+            return Err(ConfigError::Invalid(format!("token key '{}' is not in the form kid=path", kv)));
+        };
+
+        token_keys.push(TokenKey {
+            kid: kid.trim().to_string(),
+            path: path.trim().to_string()
+        });
+    }
+
+    return Ok(token_keys);
+}
+
+/// parses the `FILE_STORE_MASTER_KEYS` format: `id=path[,id=path...]`,
+/// oldest first
+fn parse_master_keys(keys: &str) -> Result<Vec<MasterKey>, ConfigError> {
+    let mut master_keys: Vec<MasterKey> = Vec::new();
+
+    for kv in keys.split(",").map(str::trim).filter(|kv| !kv.is_empty()) {
+        let Some((id, path)) = kv.split_once("=") else {
+            return Err(ConfigError::Invalid(format!("master key '{}' is not in the form id=path", kv)));
+        };
+
+        master_keys.push(MasterKey {
+            id: id.trim().to_string(),
+            path: path.trim().to_string()
+        });
+    }
+
+    return Ok(master_keys);
+}
+
+fn check_connection(name: &str, url: &str) -> Result<(), ConfigError> {
+    if name.is_empty() {
+        return Err(ConfigError::MalformedConnection(String::from("connection name is empty")));
+    }
+    if url.is_empty() {
+        return Err(ConfigError::MalformedConnection(format!("connection '{}' has no url", name)));
+    }
+    if !url.starts_with("postgres") {
+        return Err(ConfigError::MalformedConnection(format!("connection '{}' uses an unsupported database type", name)));
+    }
+    return Ok(());
+}
+
+/// strips everything after the scheme so credentials never reach the logs
+fn redact_connection(value: &str) -> String {
+    return match value.split_once("://") {
+        Some((scheme, _)) => format!("{}://...", scheme),
+        None => String::from(value)
+    };
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    return value.split(",")
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(String::from)
+        .collect();
 }
 
 
 #[derive(Debug, Clone)]
 pub struct Config {
+    bind_address: String,
     http_port: u16,
+    public_url: String,
+    workers: usize,
     connections: HashMap<String, String>,
-    token_secret: String
+    pool_max_connections: u32,
+    pool_min_connections: u32,
+    migrate_on_startup: bool,
+    cors_origins: Vec<String>,
+    token_secret: Secret,
+    token_keys: Vec<TokenKey>,
+    access_token_lifetime: i64,
+    refresh_token_lifetime: i64,
+    mail: MailConfig,
+    outbox: OutboxConfig,
+    file_store_root: String,
+    file_store_master_keys: Vec<MasterKey>,
+    upload: UploadConfig,
+    previews: PreviewConfig,
+    search: SearchConfig,
+    password: PasswordConfig
 }
 
 
 
 impl Config {
+
+    /// loads configuration from the defaults, an optional TOML file
+    /// (`--config` or `CONFIG_FILE`), the environment and command line
+    /// flags, in increasing order of priority, then validates it
+    pub fn load(args: &CliArgs) -> Result<Self, ConfigError> {
+        let env = envy::from_env::<EnvironmentConfig>()
+            .map_err(|e| ConfigError::Environment(e.to_string()))?;
+
+        let mut layer = Layer::default();
+        if let Some(path) = args.config_file.clone().or(env.config_file.clone()) {
+            layer = layer.merge(Layer::from_file(&path)?);
+        }
+        layer = layer
+            .merge(Layer::from_environment(env)?)
+            .merge(Layer::from_cli(args)?);
+
+        let cfg = Self::from_layer(layer);
+        debug!("cfg: {:?}", cfg);
+
+        cfg.validate()?;
+        return Ok(cfg);
+    }
+
+    /// loads configuration from the environment (and `CONFIG_FILE` if set)
+    /// without validation, errors are logged and the affected layer is
+    /// skipped; intended for tests and tooling
     pub fn from_env() -> Self {
-        let cfg: Config = match envy::from_env::<EnvironmentConfig>() {
-            Ok(config) => {
-                debug!("Configuration: {:?}", config);
-                match config.cn {
-                    Some(cn) => {
-                        let mut connection_strings: HashMap<String, String> = HashMap::new();
-
-                        let kvs: Vec<&str> = cn.split(",").collect();
-                        for kv in kvs.iter() {
-                            let pair: Vec<&str> = kv.split("=").collect();
-                            connection_strings.insert(pair[0].to_string(), pair[1].to_string());
-                        }
+        let mut layer = Layer::default();
 
-                        let cfg = Config {
-                            http_port: config.http_port.unwrap_or(DEFAULT_HTTP_PORT),
-                            connections: connection_strings.clone(),
-                            token_secret: config.token_secret.unwrap_or(String::from(DEFAULT_TOKEN_SECRET))
-                        };
-
-                        debug!("cfg: {:?}", cfg);
-                        cfg
-                    },
-                    None => {
-                        error!("No CN provided in configuration");
-                        Config {
-                            http_port: DEFAULT_HTTP_PORT,
-                            connections: HashMap::new(),
-                            token_secret: String::from(DEFAULT_TOKEN_SECRET)
+        match envy::from_env::<EnvironmentConfig>() {
+            Err(e) => {
+                error!("Failed to load configuration from environment: {:?}", e);
+            }
+            Ok(env) => {
+                if let Some(path) = env.config_file.clone() {
+                    match Layer::from_file(&path) {
+                        Err(e) => {
+                            error!("{}", e);
+                        }
+                        Ok(l) => {
+                            layer = layer.merge(l);
                         }
                     }
                 }
-            }
-            Err(error) => {
-                error!("Failed to load configuration from environment: {:?}", error);
-                Config {
-                    http_port: DEFAULT_HTTP_PORT,
-                    connections: HashMap::new(),
-                    token_secret: String::from(DEFAULT_TOKEN_SECRET)
+
+                match Layer::from_environment(env) {
+                    Err(e) => {
+                        error!("{}", e);
+                    }
+                    Ok(l) => {
+                        layer = layer.merge(l);
+                    }
                 }
             }
-        };
+        }
 
+        if layer.connections.is_none() {
+            error!("No CN provided in configuration");
+        }
+
+        let cfg = Self::from_layer(layer);
+        debug!("cfg: {:?}", cfg);
         return cfg;
     }
 
+    fn from_layer(layer: Layer) -> Self {
+        return Self {
+            bind_address: layer.bind_address.unwrap_or(String::from(DEFAULT_BIND_ADDRESS)),
+            http_port: layer.http_port.unwrap_or(DEFAULT_HTTP_PORT),
+            public_url: layer.public_url
+                .unwrap_or(String::from(DEFAULT_PUBLIC_URL))
+                .trim()
+                .trim_end_matches('/')
+                .to_string(),
+            workers: layer.workers.unwrap_or(DEFAULT_WORKERS),
+            connections: layer.connections.unwrap_or_default(),
+            pool_max_connections: layer.pool_max_connections.unwrap_or(DEFAULT_POOL_MAX_CONNECTIONS),
+            pool_min_connections: layer.pool_min_connections.unwrap_or(DEFAULT_POOL_MIN_CONNECTIONS),
+            migrate_on_startup: layer.migrate_on_startup.unwrap_or(true),
+            cors_origins: layer.cors_origins.unwrap_or(vec![String::from(DEFAULT_CORS_ORIGIN)]),
+            token_secret: Secret(layer.token_secret.unwrap_or(String::from(DEFAULT_TOKEN_SECRET))),
+            token_keys: layer.token_keys.unwrap_or_default(),
+            access_token_lifetime: layer.access_token_lifetime.unwrap_or(DEFAULT_ACCESS_TOKEN_LIFETIME),
+            refresh_token_lifetime: layer.refresh_token_lifetime.unwrap_or(DEFAULT_REFRESH_TOKEN_LIFETIME),
+            mail: MailConfig {
+                transport: layer.mail_transport.unwrap_or(MailTransport::Log),
+                from: layer.mail_from.unwrap_or(String::from(DEFAULT_MAIL_FROM)),
+                smtp_host: layer.smtp_host.unwrap_or_default(),
+                smtp_port: layer.smtp_port.unwrap_or(DEFAULT_SMTP_PORT),
+                smtp_username: layer.smtp_username.unwrap_or_default(),
+                smtp_password: Secret(layer.smtp_password.unwrap_or_default()),
+                smtp_starttls: layer.smtp_starttls.unwrap_or(true),
+                sendmail_path: layer.sendmail_path.unwrap_or(String::from(DEFAULT_SENDMAIL_PATH)),
+                file_path: layer.mail_file_path.unwrap_or(String::from(DEFAULT_MAIL_FILE_PATH))
+            },
+            outbox: OutboxConfig {
+                poll_interval: layer.outbox_poll_interval.unwrap_or(DEFAULT_OUTBOX_POLL_INTERVAL),
+                max_attempts: layer.outbox_max_attempts.unwrap_or(DEFAULT_OUTBOX_MAX_ATTEMPTS),
+                backoff: layer.outbox_backoff.unwrap_or(DEFAULT_OUTBOX_BACKOFF),
+                max_backoff: layer.outbox_max_backoff.unwrap_or(DEFAULT_OUTBOX_MAX_BACKOFF)
+            },
+            file_store_root: layer.file_store_root.unwrap_or(String::from(DEFAULT_FILE_STORE_ROOT)),
+            file_store_master_keys: layer.file_store_master_keys.unwrap_or_default(),
+            upload: UploadConfig {
+                max_size: layer.file_store_max_size.unwrap_or(DEFAULT_FILE_MAX_SIZE),
+                default_quota: layer.file_store_default_quota.unwrap_or(0),
+                allowed_types: layer.file_store_allowed_types.unwrap_or_default()
+            },
+            previews: PreviewConfig {
+                command: layer.preview_command.unwrap_or_default().trim().to_string(),
+                size: layer.preview_size.unwrap_or(DEFAULT_PREVIEW_SIZE),
+                timeout: layer.preview_timeout.unwrap_or(DEFAULT_PREVIEW_TIMEOUT),
+                poll_interval: layer.preview_poll_interval.unwrap_or(DEFAULT_PREVIEW_POLL_INTERVAL)
+            },
+            search: SearchConfig {
+                max_size: layer.search_max_size.unwrap_or(DEFAULT_SEARCH_MAX_SIZE),
+                poll_interval: layer.search_poll_interval.unwrap_or(DEFAULT_SEARCH_POLL_INTERVAL)
+            },
+            password: PasswordConfig {
+                memory_cost: layer.password_memory_cost.unwrap_or(DEFAULT_PASSWORD_MEMORY_COST),
+                time_cost: layer.password_time_cost.unwrap_or(DEFAULT_PASSWORD_TIME_COST),
+                parallelism: layer.password_parallelism.unwrap_or(DEFAULT_PASSWORD_PARALLELISM),
+                reset_lifetime: layer.password_reset_lifetime.unwrap_or(DEFAULT_PASSWORD_RESET_LIFETIME),
+                min_length: layer.password_min_length.unwrap_or(DEFAULT_PASSWORD_MIN_LENGTH),
+                max_length: layer.password_max_length.unwrap_or(DEFAULT_PASSWORD_MAX_LENGTH)
+            }
+        };
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.token_keys.is_empty()
+            && (self.token_secret.0.trim().is_empty() || self.token_secret.0 == DEFAULT_TOKEN_SECRET)
+        {
+            return Err(ConfigError::DefaultSecret);
+        }
+
+        for (i, key) in self.token_keys.iter().enumerate() {
+            if key.kid.trim().is_empty() || key.path.trim().is_empty() {
+                return Err(ConfigError::Invalid(String::from("token keys need a kid and a path")));
+            }
+            if self.token_keys[..i].iter().any(|k| k.kid == key.kid) {
+                return Err(ConfigError::Invalid(format!("token key '{}' is defined more than once", key.kid)));
+            }
+        }
+
+        if !self.connections.contains_key(MAIN_CONNECTION) {
+            return Err(ConfigError::Invalid(format!("no '{}' database connection configured", MAIN_CONNECTION)));
+        }
+
+        if self.bind_address.trim().is_empty() {
+            return Err(ConfigError::Invalid(String::from("bind address is empty")));
+        }
+
+        if !(self.public_url.starts_with("http://") || self.public_url.starts_with("https://")) {
+            return Err(ConfigError::Invalid(format!("public url '{}' is not an http or https url", self.public_url)));
+        }
+
+        if self.workers == 0 {
+            return Err(ConfigError::Invalid(String::from("workers must be greater than 0")));
+        }
+
+        if self.pool_max_connections == 0 {
+            return Err(ConfigError::Invalid(String::from("pool max connections must be greater than 0")));
+        }
+
+        if self.pool_min_connections > self.pool_max_connections {
+            return Err(ConfigError::Invalid(String::from("pool min connections is greater than pool max connections")));
+        }
+
+        if self.access_token_lifetime <= 0 {
+            return Err(ConfigError::Invalid(String::from("access token lifetime must be greater than 0")));
+        }
+
+        if self.refresh_token_lifetime <= self.access_token_lifetime {
+            return Err(ConfigError::Invalid(String::from("refresh token lifetime must be greater than the access token lifetime")));
+        }
+
+        if !self.mail.from.contains("@") {
+            return Err(ConfigError::Invalid(format!("mail from address '{}' is not an email address", self.mail.from)));
+        }
+
+        if self.mail.transport == MailTransport::Smtp && self.mail.smtp_host.trim().is_empty() {
+            return Err(ConfigError::Invalid(String::from("smtp mail transport requires an smtp host")));
+        }
+
+        if self.outbox.poll_interval <= 0 || self.outbox.max_attempts <= 0 || self.outbox.backoff <= 0 {
+            return Err(ConfigError::Invalid(String::from("outbox poll interval, max attempts and backoff must be greater than 0")));
+        }
+
+        if self.outbox.max_backoff < self.outbox.backoff {
+            return Err(ConfigError::Invalid(String::from("outbox max backoff is less than the outbox backoff")));
+        }
+
+        if self.file_store_root.trim().is_empty() {
+            return Err(ConfigError::Invalid(String::from("file store root is empty")));
+        }
+
+        for (i, key) in self.file_store_master_keys.iter().enumerate() {
+            if key.id.trim().is_empty() || key.id.contains(':') || key.path.trim().is_empty() {
+                return Err(ConfigError::Invalid(String::from("master keys need an id without ':' and a path")));
+            }
+            if self.file_store_master_keys[..i].iter().any(|k| k.id == key.id) {
+                return Err(ConfigError::Invalid(format!("master key '{}' is defined more than once", key.id)));
+            }
+        }
+
+        if self.upload.max_size == 0 {
+            return Err(ConfigError::Invalid(String::from("file store max size must be greater than 0")));
+        }
+
+        if let Some(t) = self.upload.allowed_types.iter().find(|t| t.split('/').count() != 2) {
+            return Err(ConfigError::Invalid(format!("allowed type '{}' is not a content type", t)));
+        }
+
+        if !(16..=4096).contains(&self.previews.size) {
+            return Err(ConfigError::Invalid(String::from("preview size must be between 16 and 4096")));
+        }
+
+        if self.previews.timeout <= 0 || self.previews.poll_interval <= 0 {
+            return Err(ConfigError::Invalid(String::from("preview timeout and poll interval must be greater than 0")));
+        }
+
+        if self.search.max_size == 0 || self.search.poll_interval <= 0 {
+            return Err(ConfigError::Invalid(String::from("search max size and poll interval must be greater than 0")));
+        }
+
+        // the minimums of the argon2 specification
+        if self.password.time_cost == 0
+            || !(1..=255).contains(&self.password.parallelism)
+            || self.password.memory_cost < 8 * self.password.parallelism
+        {
+            return Err(ConfigError::Invalid(String::from(
+                "password time cost must be at least 1, parallelism between 1 and 255 and memory cost at least 8 KiB per lane"
+            )));
+        }
+
+        if self.password.reset_lifetime <= 0 {
+            return Err(ConfigError::Invalid(String::from("password reset lifetime must be greater than 0")));
+        }
+
+        if self.password.min_length == 0 || self.password.max_length < self.password.min_length {
+            return Err(ConfigError::Invalid(String::from(
+                "password min length must be at least 1 and max length at least the min length"
+            )));
+        }
+
+        return Ok(());
+    }
+
+    pub fn bind_address(&self) -> String {
+        return self.bind_address.clone();
+    }
+
+    pub fn http_port(&self) -> u16 {
+        return self.http_port;
+    }
+
+    /// base url the application is reachable at, without a trailing `/`
+    pub fn public_url(&self) -> String {
+        return self.public_url.clone();
+    }
+
+    pub fn workers(&self) -> usize {
+        return self.workers;
+    }
+
     pub fn connections(&self) -> HashMap<String, String> {
         return self.connections.clone();
     }
 
+    pub fn pool_max_connections(&self) -> u32 {
+        return self.pool_max_connections;
+    }
+
+    pub fn pool_min_connections(&self) -> u32 {
+        return self.pool_min_connections;
+    }
+
+    /// apply pending schema migrations before the server starts
+    pub fn migrate_on_startup(&self) -> bool {
+        return self.migrate_on_startup;
+    }
+
+    pub fn cors_origins(&self) -> Vec<String> {
+        return self.cors_origins.clone();
+    }
+
     pub fn token_secret(&self) -> String {
-        return self.token_secret.clone();
+        return self.token_secret.0.clone();
+    }
+
+    /// asymmetric signing keys, oldest first; tokens are signed with the last
+    /// key and the shared secret is only used when no keys are configured
+    pub fn token_keys(&self) -> Vec<TokenKey> {
+        return self.token_keys.clone();
+    }
+
+    /// access token lifetime in seconds
+    pub fn access_token_lifetime(&self) -> i64 {
+        return self.access_token_lifetime;
+    }
+
+    /// refresh token lifetime in seconds
+    pub fn refresh_token_lifetime(&self) -> i64 {
+        return self.refresh_token_lifetime;
+    }
+
+    pub fn mail(&self) -> MailConfig {
+        return self.mail.clone();
+    }
+
+    pub fn outbox(&self) -> OutboxConfig {
+        return self.outbox.clone();
+    }
+
+    pub fn file_store_root(&self) -> String {
+        return self.file_store_root.clone();
+    }
+
+    /// oldest first, content is stored unencrypted while there is none
+    pub fn file_store_master_keys(&self) -> Vec<MasterKey> {
+        return self.file_store_master_keys.clone();
+    }
+
+    pub fn upload(&self) -> UploadConfig {
+        return self.upload.clone();
+    }
+
+    pub fn previews(&self) -> PreviewConfig {
+        return self.previews.clone();
+    }
+
+    pub fn search(&self) -> SearchConfig {
+        return self.search.clone();
+    }
+
+    pub fn password(&self) -> PasswordConfig {
+        return self.password.clone();
     }
 }
 
@@ -95,9 +1134,304 @@ impl Config {
 mod tests {
     use super::*;
 
-    // #[test]
-    // fn it_works() {
-    //     let result = add(2, 2);
-    //     assert_eq!(result, 4);
-    // }
+    fn valid_layer() -> Layer {
+        return Layer {
+            connections: Some(parse_connections("main=postgres://app_user:pw@localhost:5432/eas").unwrap()),
+            token_secret: Some(String::from("a-real-secret")),
+            ..Default::default()
+        };
+    }
+
+    #[test]
+    fn test_parse_connections() {
+        let connections = parse_connections("main=postgres://u:p@h/db?sslmode=require, other=postgres://h/db2").unwrap();
+        assert_eq!(connections.len(), 2);
+        assert_eq!(connections["main"], "postgres://u:p@h/db?sslmode=require");
+        assert_eq!(connections["other"], "postgres://h/db2");
+
+        assert!(matches!(parse_connections("main"), Err(ConfigError::MalformedConnection(_))));
+        assert!(matches!(parse_connections("=postgres://h/db"), Err(ConfigError::MalformedConnection(_))));
+        assert!(matches!(parse_connections("main="), Err(ConfigError::MalformedConnection(_))));
+        assert!(matches!(parse_connections("main=mysql://h/db"), Err(ConfigError::MalformedConnection(_))));
+        assert!(matches!(parse_connections("main=postgres://a,main=postgres://b"), Err(ConfigError::MalformedConnection(_))));
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let file = Layer::from_toml(r#"
+            [http]
+            port = 9000
+            public_url = "https://app.example.com/"
+            workers = 8
+
+            [cors]
+            origins = ["https://app.example.com"]
+        "#).unwrap();
+        let env = Layer {
+            http_port: Some(9100),
+            ..Default::default()
+        };
+        let cli = Layer::from_cli(&CliArgs {
+            workers: Some(4),
+            ..Default::default()
+        }).unwrap();
+
+        let cfg = Config::from_layer(valid_layer().merge(file).merge(env).merge(cli));
+        assert_eq!(cfg.http_port(), 9100);
+        assert_eq!(cfg.workers(), 4);
+        assert_eq!(cfg.cors_origins(), vec![String::from("https://app.example.com")]);
+        assert_eq!(cfg.bind_address(), DEFAULT_BIND_ADDRESS);
+        assert_eq!(cfg.public_url(), "https://app.example.com");
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn test_unknown_file_keys_are_rejected() {
+        assert!(matches!(Layer::from_toml("[http]\nprot = 80"), Err(ConfigError::File(_))));
+        assert!(matches!(Layer::from_toml("[mail]\ntransport = \"pigeon\""), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_validate() {
+        let cfg = Config::from_layer(Layer {
+            token_secret: None,
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::DefaultSecret)));
+
+        let cfg = Config::from_layer(Layer {
+            connections: None,
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+
+        let cfg = Config::from_layer(Layer {
+            refresh_token_lifetime: Some(60),
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+
+        let cfg = Config::from_layer(Layer {
+            mail_transport: Some(MailTransport::Smtp),
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+
+        let cfg = Config::from_layer(Layer {
+            public_url: Some(String::from("app.example.com")),
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_outbox() {
+        let file = Layer::from_toml(r#"
+            [outbox]
+            poll_interval = 5
+            max_attempts = 3
+        "#).unwrap();
+
+        let cfg = Config::from_layer(valid_layer().merge(file));
+        assert_eq!(cfg.outbox().poll_interval, 5);
+        assert_eq!(cfg.outbox().max_attempts, 3);
+        assert_eq!(cfg.outbox().backoff, DEFAULT_OUTBOX_BACKOFF);
+        assert!(cfg.validate().is_ok());
+
+        let cfg = Config::from_layer(Layer {
+            outbox_max_attempts: Some(0),
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+
+        let cfg = Config::from_layer(Layer {
+            outbox_max_backoff: Some(1),
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_upload() {
+        let file = Layer::from_toml(r#"
+            [file_store]
+            max_size = 1048576
+            allowed_types = ["application/pdf", "image/*"]
+        "#).unwrap();
+
+        let cfg = Config::from_layer(valid_layer().merge(file));
+        assert_eq!(cfg.upload().max_size, 1048576);
+        assert_eq!(cfg.upload().default_quota, 0);
+        assert!(cfg.upload().allows("application/pdf"));
+        assert!(cfg.upload().allows("image/png"));
+        assert!(!cfg.upload().allows("text/html"));
+        assert!(cfg.validate().is_ok());
+
+        // no list allows everything
+        assert!(Config::from_layer(valid_layer()).upload().allows("application/x-msdownload"));
+
+        let cfg = Config::from_layer(Layer {
+            file_store_max_size: Some(0),
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+
+        let cfg = Config::from_layer(Layer {
+            file_store_allowed_types: Some(vec![String::from("pdf")]),
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_previews() {
+        let cfg = Config::from_layer(valid_layer());
+        assert!(cfg.previews().command.is_empty());
+        assert_eq!(cfg.previews().size, 256);
+
+        let file = Layer::from_toml(r#"
+            [previews]
+            command = "/usr/local/bin/nexus-preview"
+            size = 512
+        "#).unwrap();
+
+        let cfg = Config::from_layer(valid_layer().merge(file));
+        assert_eq!(cfg.previews().command, "/usr/local/bin/nexus-preview");
+        assert_eq!(cfg.previews().size, 512);
+        assert!(cfg.validate().is_ok());
+
+        let cfg = Config::from_layer(Layer {
+            preview_size: Some(0),
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+
+        let cfg = Config::from_layer(Layer {
+            preview_timeout: Some(0),
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_search() {
+        let cfg = Config::from_layer(valid_layer());
+        assert_eq!(cfg.search().max_size, 50 * 1024 * 1024);
+        assert_eq!(cfg.search().poll_interval, 10);
+
+        let file = Layer::from_toml(r#"
+            [search]
+            max_size = 1048576
+            poll_interval = 30
+        "#).unwrap();
+
+        let cfg = Config::from_layer(valid_layer().merge(file));
+        assert_eq!(cfg.search().max_size, 1024 * 1024);
+        assert_eq!(cfg.search().poll_interval, 30);
+        assert!(cfg.validate().is_ok());
+
+        let cfg = Config::from_layer(Layer {
+            search_max_size: Some(0),
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_password() {
+        let cfg = Config::from_layer(valid_layer());
+        assert_eq!(cfg.password().memory_cost, 19 * 1024);
+        assert_eq!(cfg.password().time_cost, 2);
+        assert_eq!(cfg.password().parallelism, 1);
+        assert_eq!(cfg.password().reset_lifetime, 60 * 60);
+        assert_eq!(cfg.password().min_length, 8);
+        assert_eq!(cfg.password().max_length, 128);
+
+        let file = Layer::from_toml(r#"
+            [password]
+            memory_cost = 65536
+            time_cost = 3
+            parallelism = 4
+            reset_lifetime = 900
+            min_length = 12
+        "#).unwrap();
+
+        let cfg = Config::from_layer(valid_layer().merge(file));
+        assert_eq!(cfg.password().memory_cost, 64 * 1024);
+        assert_eq!(cfg.password().parallelism, 4);
+        assert_eq!(cfg.password().reset_lifetime, 900);
+        assert_eq!(cfg.password().min_length, 12);
+        assert_eq!(cfg.password().max_length, 128);
+        assert!(cfg.validate().is_ok());
+
+        for layer in [
+            Layer { password_time_cost: Some(0), ..valid_layer() },
+            Layer { password_parallelism: Some(0), ..valid_layer() },
+            Layer { password_memory_cost: Some(16), password_parallelism: Some(4), ..valid_layer() },
+            Layer { password_reset_lifetime: Some(0), ..valid_layer() },
+            Layer { password_min_length: Some(0), ..valid_layer() },
+            Layer { password_min_length: Some(16), password_max_length: Some(12), ..valid_layer() }
+        ] {
+            let cfg = Config::from_layer(layer);
+            assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+        }
+    }
+
+    #[test]
+    fn test_token_keys() {
+        let keys = parse_token_keys("2026-01=/etc/nexus/a.pem, 2026-07=/etc/nexus/b.pem").unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[1], TokenKey { kid: String::from("2026-07"), path: String::from("/etc/nexus/b.pem") });
+        assert!(matches!(parse_token_keys("/etc/nexus/a.pem"), Err(ConfigError::Invalid(_))));
+
+        let file = Layer::from_toml(r#"
+            [[token.keys]]
+            kid = "2026-01"
+            path = "/etc/nexus/a.pem"
+        "#).unwrap();
+
+        // keys replace the shared secret
+        let cfg = Config::from_layer(Layer { token_secret: None, ..valid_layer() }.merge(file));
+        assert_eq!(cfg.token_keys().len(), 1);
+        assert!(cfg.validate().is_ok());
+
+        let cfg = Config::from_layer(Layer {
+            token_keys: Some(parse_token_keys("a=/a.pem,a=/b.pem").unwrap()),
+            ..valid_layer()
+        });
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_master_keys() {
+        let keys = parse_master_keys("2026-01=/etc/nexus/master-a.key, 2026-07=/etc/nexus/master-b.key").unwrap();
+        assert_eq!(keys[1], MasterKey { id: String::from("2026-07"), path: String::from("/etc/nexus/master-b.key") });
+        assert!(matches!(parse_master_keys("/etc/nexus/master-a.key"), Err(ConfigError::Invalid(_))));
+
+        let cfg = Config::from_layer(valid_layer());
+        assert!(cfg.file_store_master_keys().is_empty());
+
+        let file = Layer::from_toml(r#"
+            [[file_store.master_keys]]
+            id = "2026-01"
+            path = "/etc/nexus/master-a.key"
+        "#).unwrap();
+        let cfg = Config::from_layer(valid_layer().merge(file));
+        assert_eq!(cfg.file_store_master_keys().len(), 1);
+        assert!(cfg.validate().is_ok());
+
+        for keys in ["a=/a.key,a=/b.key", "a:b=/a.key"] {
+            let cfg = Config::from_layer(Layer {
+                file_store_master_keys: Some(parse_master_keys(keys).unwrap()),
+                ..valid_layer()
+            });
+            assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+        }
+    }
+
+    #[test]
+    fn test_secrets_are_not_logged() {
+        let cfg = Config::from_layer(valid_layer());
+        assert!(!format!("{:?}", cfg).contains("a-real-secret"));
+    }
 }
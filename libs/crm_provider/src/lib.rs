@@ -3,6 +3,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use provider_error::ProviderError;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Partner {
     pub partner_id: uuid::Uuid,
@@ -40,34 +42,34 @@ pub trait CrmProvider {
         &self,
         tenant_id: &uuid::Uuid,
         partner: &Partner,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     // fn business_save(
     // 	&self,
     // 	tenant_id: &uuid::Uuid,
     // 	business: &Business
-    // ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    // ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn partners_fetch(
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> impl Future<Output = Result<Vec<Partner>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Partner>, ProviderError>> + Send;
 
     fn partner_fetch_by_id(
         &self,
         partner_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Partner, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Partner, ProviderError>> + Send;
 
     fn partner_fetch_by_name(
         &self,
         tenant_id: &uuid::Uuid,
         name: &str,
-    ) -> impl Future<Output = Result<Partner, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Partner, ProviderError>> + Send;
 
     fn partners_set_active(
         &self,
         partner_ids: &Vec<uuid::Uuid>,
         active: bool,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 }
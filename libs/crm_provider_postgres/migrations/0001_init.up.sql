@@ -0,0 +1,191 @@
+-- business partners (customers, suppliers)
+
+create schema if not exists crm;
+
+
+create table crm.partners (
+    partner_id uuid not null,
+    tenant_id uuid not null,
+    active boolean not null default true,
+    created_ts timestamptz not null default now(),
+    updated_ts timestamptz not null default now(),
+    business_name text not null default '',
+    description text not null default '',
+    first_name text not null default '',
+    middle_name text not null default '',
+    last_name text not null default '',
+    prefix text not null default '',
+    suffix text not null default '',
+    constraint pk_partners primary key (partner_id),
+    constraint fk_partners_tenant foreign key (tenant_id) references tenants.tenants (tenant_id)
+);
+
+create index i_partners_tenant on crm.partners (tenant_id);
+
+
+create procedure crm.partner_save(
+    p_tenant_id uuid,
+    p_partner_id uuid,
+    p_business_name text,
+    p_description text,
+    p_first_name text,
+    p_middle_name text,
+    p_last_name text,
+    p_prefix text,
+    p_suffix text
+)
+language sql
+as $$
+    insert into crm.partners as p (
+        partner_id,
+        tenant_id,
+        business_name,
+        description,
+        first_name,
+        middle_name,
+        last_name,
+        prefix,
+        suffix
+    ) values (
+        p_partner_id,
+        p_tenant_id,
+        p_business_name,
+        p_description,
+        p_first_name,
+        p_middle_name,
+        p_last_name,
+        p_prefix,
+        p_suffix
+    )
+    on conflict (partner_id) do update set
+        business_name = excluded.business_name,
+        description = excluded.description,
+        first_name = excluded.first_name,
+        middle_name = excluded.middle_name,
+        last_name = excluded.last_name,
+        prefix = excluded.prefix,
+        suffix = excluded.suffix,
+        updated_ts = now()
+    where p.tenant_id = excluded.tenant_id;
+$$;
+
+create procedure crm.partners_set_active(
+    p_partner_ids uuid[],
+    p_active boolean
+)
+language sql
+as $$
+    update crm.partners set
+        active = p_active,
+        updated_ts = now()
+    where partner_id = any(p_partner_ids);
+$$;
+
+
+create function crm.partners_fetch(
+    p_tenant_id uuid,
+    p_filter text
+)
+returns table (
+    partner_id uuid,
+    active boolean,
+    created_ts timestamptz,
+    business_name text,
+    description text,
+    first_name text,
+    middle_name text,
+    last_name text,
+    prefix text,
+    suffix text
+)
+language sql
+stable
+as $$
+    select
+        p.partner_id,
+        p.active,
+        p.created_ts,
+        p.business_name,
+        p.description,
+        p.first_name,
+        p.middle_name,
+        p.last_name,
+        p.prefix,
+        p.suffix
+    from crm.partners p
+    where p.tenant_id = p_tenant_id
+        and (
+            p.business_name ilike '%' || p_filter || '%'
+            or p.first_name ilike '%' || p_filter || '%'
+            or p.last_name ilike '%' || p_filter || '%'
+        )
+    order by p.business_name;
+$$;
+
+create function crm.partner_fetch_by_id(
+    p_partner_id uuid
+)
+returns table (
+    partner_id uuid,
+    active boolean,
+    created_ts timestamptz,
+    business_name text,
+    description text,
+    first_name text,
+    middle_name text,
+    last_name text,
+    prefix text,
+    suffix text
+)
+language sql
+stable
+as $$
+    select
+        p.partner_id,
+        p.active,
+        p.created_ts,
+        p.business_name,
+        p.description,
+        p.first_name,
+        p.middle_name,
+        p.last_name,
+        p.prefix,
+        p.suffix
+    from crm.partners p
+    where p.partner_id = p_partner_id;
+$$;
+
+create function crm.partner_fetch_by_name(
+    p_tenant_id uuid,
+    p_name text
+)
+returns table (
+    partner_id uuid,
+    active boolean,
+    created_ts timestamptz,
+    business_name text,
+    description text,
+    first_name text,
+    middle_name text,
+    last_name text,
+    prefix text,
+    suffix text
+)
+language sql
+stable
+as $$
+    select
+        p.partner_id,
+        p.active,
+        p.created_ts,
+        p.business_name,
+        p.description,
+        p.first_name,
+        p.middle_name,
+        p.last_name,
+        p.prefix,
+        p.suffix
+    from crm.partners p
+    where p.tenant_id = p_tenant_id
+        and p.business_name = p_name;
+$$;
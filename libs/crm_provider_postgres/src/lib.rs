@@ -8,6 +8,22 @@ use tracing::{
 
 use sqlx::{Row, postgres::PgRow, prelude::FromRow};
 
+use provider_error::ProviderError;
+
+/// schema migrations for the `crm` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "crm",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        }
+    ]
+};
+
+
 struct Partnerdata(pub crm_provider::Partner);
 
 impl<'r> FromRow<'r, PgRow> for Partnerdata {
@@ -42,7 +58,7 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
         &self,
         tenant_id: &uuid::Uuid,
         partner: &crm_provider::Partner,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("partner_save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -61,7 +77,7 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
             {
                 Err(e) => {
                     error!("Error saving partner record: {:?}", e);
-                    return Err("Error saving partner record");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -69,7 +85,7 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -77,7 +93,7 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> Result<Vec<crm_provider::Partner>, &'static str> {
+    ) -> Result<Vec<crm_provider::Partner>, ProviderError> {
         info!("partners_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -89,7 +105,7 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching partners: {:?}", e);
-                    return Err("Error fetching partners");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let partners: Vec<crm_provider::Partner> =
@@ -100,14 +116,14 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn partner_fetch_by_id(
         &self,
         partner_id: &uuid::Uuid,
-    ) -> Result<crm_provider::Partner, &'static str> {
+    ) -> Result<crm_provider::Partner, ProviderError> {
         info!("partners_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -118,7 +134,7 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching partner: {:?}", e);
-                    return Err("Error fetching partner");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(r) => {
                     return Ok(r.0.clone());
@@ -126,7 +142,7 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -134,7 +150,7 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
         &self,
         tenant_id: &uuid::Uuid,
         name: &str,
-    ) -> Result<crm_provider::Partner, &'static str> {
+    ) -> Result<crm_provider::Partner, ProviderError> {
         info!("partner_fetch_by_name");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -148,7 +164,7 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching partner: {:?}", e);
-                    return Err("Error fetching partner");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(r) => {
                     return Ok(r.0.clone());
@@ -156,7 +172,7 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -164,7 +180,7 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
         &self,
         partner_ids: &Vec<uuid::Uuid>,
         active: bool,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("partners_set_active");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -179,12 +195,12 @@ impl crm_provider::CrmProvider for CrmProviderPostgres {
                 }
                 Err(e) => {
                     error!("Error setting partner active state: {:?}", e);
-                    return Err("Error setting partner active state");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
@@ -0,0 +1,109 @@
+use tracing::debug;
+
+use provider_error::ProviderError;
+
+/// maps a sqlx error onto a `ProviderError`, database errors are
+/// classified by their SQLSTATE code
+pub fn from_sqlx(e: &sqlx::Error) -> ProviderError {
+    match e {
+        sqlx::Error::RowNotFound => {
+            return ProviderError::NotFound;
+        }
+        sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::WorkerCrashed => {
+            return ProviderError::Unavailable;
+        }
+        sqlx::Error::Database(db) => {
+            let Some(code) = db.code() else {
+                return ProviderError::Internal;
+            };
+
+            let pg = db.try_downcast_ref::<sqlx::postgres::PgDatabaseError>();
+            let field = pg
+                .and_then(|pg| pg.column().or(pg.constraint()))
+                .or(db.constraint())
+                .unwrap_or_default();
+
+            // the message may quote the offending value, it is only logged
+            debug!("database error {}: {}", code, db.message());
+            return from_sqlstate(&code, field);
+        }
+        _ => {
+            return ProviderError::Internal;
+        }
+    }
+}
+
+fn from_sqlstate(code: &str, field: &str) -> ProviderError {
+    debug!("sqlstate: {} field: {}", code, field);
+
+    match code {
+        // no_data_found, raised by stored procedures for missing records
+        "P0002" => ProviderError::NotFound,
+        // unique_violation, exclusion_violation
+        "23505" | "23P01" => ProviderError::Conflict,
+        // serialization_failure, deadlock_detected
+        "40001" | "40P01" => ProviderError::Conflict,
+        "23503" => ProviderError::validation(field, "references a record that does not exist"),
+        "23502" => ProviderError::validation(field, "is required"),
+        "23514" => ProviderError::validation(field, "violates a check constraint"),
+        // data exceptions: invalid text representation, value too long,
+        // out of range, malformed dates ...
+        c if c.starts_with("22") => ProviderError::validation(field, "has an invalid value"),
+        // insufficient_privilege
+        "42501" => ProviderError::Forbidden,
+        // connection exceptions, insufficient resources, operator
+        // intervention (shutdown, cannot connect now)
+        c if c.starts_with("08") || c.starts_with("53") || c.starts_with("57P") => {
+            ProviderError::Unavailable
+        }
+        _ => ProviderError::Internal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_sqlstate() {
+        assert_eq!(from_sqlstate("P0002", ""), ProviderError::NotFound);
+        assert_eq!(
+            from_sqlstate("23505", "u_tenants_name"),
+            ProviderError::Conflict
+        );
+        assert_eq!(
+            from_sqlstate("23503", "fk_invoices_partner"),
+            ProviderError::validation(
+                "fk_invoices_partner",
+                "references a record that does not exist"
+            )
+        );
+        assert_eq!(
+            from_sqlstate("22P02", "amount"),
+            ProviderError::validation("amount", "has an invalid value")
+        );
+        assert_eq!(from_sqlstate("42501", ""), ProviderError::Forbidden);
+        assert_eq!(from_sqlstate("57P01", ""), ProviderError::Unavailable);
+        assert_eq!(from_sqlstate("42883", ""), ProviderError::Internal);
+    }
+
+    #[test]
+    fn test_from_sqlx() {
+        assert_eq!(
+            from_sqlx(&sqlx::Error::RowNotFound),
+            ProviderError::NotFound
+        );
+        assert_eq!(
+            from_sqlx(&sqlx::Error::PoolTimedOut),
+            ProviderError::Unavailable
+        );
+        assert_eq!(
+            from_sqlx(&sqlx::Error::Protocol(String::from("x"))),
+            ProviderError::Internal
+        );
+    }
+}
@@ -1,5 +1,10 @@
 #![allow(clippy::needless_return)]
 
+pub mod error;
+pub mod migrations;
+
+pub use error::from_sqlx;
+
 use tracing::{
     info,
     error
@@ -39,12 +44,18 @@ impl DatabaseProvider {
             match v {
                 s if s.starts_with("postgres") => {
                     info!("Creating Postgres pool for connection '{}'", k);
-                    let pool = PgPoolOptions::new()
-                        .max_connections(5)
+                    match PgPoolOptions::new()
+                        .max_connections(config.pool_max_connections())
+                        .min_connections(config.pool_min_connections())
                         .connect_lazy(s)
-                        .unwrap()
-                    ;
-                    pools.insert(k.clone(), DatabaseType::Postgres(pool));
+                    {
+                        Err(e) => {
+                            error!("Unable to create Postgres pool for connection '{}': {:?}", k, e);
+                        }
+                        Ok(pool) => {
+                            pools.insert(k.clone(), DatabaseType::Postgres(pool));
+                        }
+                    }
                 }
                 _ => {
                     error!("Unsupported database type for connection '{}'", k);
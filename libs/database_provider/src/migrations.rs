@@ -0,0 +1,398 @@
+use tracing::{
+    info,
+    error,
+    debug
+};
+
+use sqlx::{
+    Row,
+    PgConnection
+};
+
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::{
+    DatabaseProvider,
+    DatabaseType
+};
+
+
+// arbitrary key for pg_advisory_lock so concurrent instances do not
+// apply the same migrations at the same time
+const MIGRATION_LOCK_KEY: i64 = 0x6e65_7875_735f_6d67;
+
+
+/// a single versioned schema change, `up` and `down` are plain SQL scripts
+/// that may contain multiple statements
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str
+}
+
+
+impl Migration {
+
+    /// hex encoded SHA-256 of the `up` script, used to detect migrations
+    /// that were edited after being applied
+    pub fn checksum(&self) -> String {
+        return hex::encode(Sha256::digest(self.up.as_bytes()));
+    }
+}
+
+
+/// the migrations shipped by a single provider crate, versions are
+/// tracked per component
+#[derive(Debug, Clone, Copy)]
+pub struct MigrationSet {
+    pub component: &'static str,
+    pub migrations: &'static [Migration]
+}
+
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub component: String,
+    pub version: i64,
+    pub name: String,
+    pub applied: bool,
+    pub applied_ts: Option<chrono::DateTime<chrono::Utc>>,
+    pub checksum_matches: bool
+}
+
+
+struct AppliedMigration {
+    version: i64,
+    checksum: String,
+    applied_ts: chrono::DateTime<chrono::Utc>
+}
+
+
+pub struct Migrator {
+    dp: DatabaseProvider,
+    sets: Vec<MigrationSet>
+}
+
+
+impl Migrator {
+
+    /// `sets` must be ordered so that a component only depends on
+    /// components listed before it
+    pub fn new(
+        dp: &DatabaseProvider,
+        sets: Vec<MigrationSet>
+    ) -> Self {
+        return Self {
+            dp: dp.clone(),
+            sets
+        };
+    }
+
+    /// applies all pending migrations, returns the number applied
+    pub async fn up(&self) -> Result<usize, &'static str> {
+        info!("up");
+
+        let mut conn = self.lock().await?;
+        let result = self.up_locked(&mut conn).await;
+        Self::unlock(&mut conn).await;
+
+        return result;
+    }
+
+    /// reverts the last `steps` applied migrations of `component`,
+    /// returns the number reverted
+    pub async fn down(
+        &self,
+        component: &str,
+        steps: usize
+    ) -> Result<usize, &'static str> {
+        info!("down");
+
+        let Some(set) = self.sets.iter().find(|s| s.component == component) else {
+            error!("unknown migration component: {}", component);
+            return Err("unknown migration component");
+        };
+
+        let mut conn = self.lock().await?;
+        let result = Self::down_locked(&mut conn, set, steps).await;
+        Self::unlock(&mut conn).await;
+
+        return result;
+    }
+
+    pub async fn status(&self) -> Result<Vec<MigrationStatus>, &'static str> {
+        info!("status");
+
+        let mut conn = self.lock().await?;
+
+        let mut statuses: Vec<MigrationStatus> = Vec::new();
+        let mut result = Ok(());
+        for set in self.sets.iter() {
+            match Self::applied(&mut conn, set.component).await {
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+                Ok(applied) => {
+                    for m in set.migrations.iter() {
+                        let a = applied.iter().find(|a| a.version == m.version);
+                        statuses.push(MigrationStatus {
+                            component: String::from(set.component),
+                            version: m.version,
+                            name: String::from(m.name),
+                            applied: a.is_some(),
+                            applied_ts: a.map(|a| a.applied_ts),
+                            checksum_matches: a.is_none_or(|a| a.checksum == m.checksum())
+                        });
+                    }
+                }
+            }
+        }
+
+        Self::unlock(&mut conn).await;
+
+        return result.map(|_| statuses);
+    }
+
+    async fn up_locked(&self, conn: &mut PgConnection) -> Result<usize, &'static str> {
+        let mut count = 0;
+
+        for set in self.sets.iter() {
+            let applied = Self::applied(conn, set.component).await?;
+
+            for a in applied.iter() {
+                match set.migrations.iter().find(|m| m.version == a.version) {
+                    None => {
+                        error!("migration {} version {} is applied but not known", set.component, a.version);
+                        return Err("database has migrations that are not known to this build");
+                    }
+                    Some(m) if m.checksum() != a.checksum => {
+                        error!("migration {} version {} ({}) was modified after it was applied", set.component, m.version, m.name);
+                        return Err("applied migration checksum does not match");
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            let mut pending: Vec<&Migration> = set.migrations.iter()
+                .filter(|m| !applied.iter().any(|a| a.version == m.version))
+                .collect();
+            pending.sort_by_key(|m| m.version);
+
+            for m in pending {
+                info!("applying migration {} {} {}", set.component, m.version, m.name);
+                Self::apply(conn, set.component, m).await?;
+                count += 1;
+            }
+        }
+
+        return Ok(count);
+    }
+
+    async fn down_locked(
+        conn: &mut PgConnection,
+        set: &MigrationSet,
+        steps: usize
+    ) -> Result<usize, &'static str> {
+        let mut applied = Self::applied(conn, set.component).await?;
+        applied.sort_by_key(|a| std::cmp::Reverse(a.version));
+
+        let mut count = 0;
+        for a in applied.iter().take(steps) {
+            let Some(m) = set.migrations.iter().find(|m| m.version == a.version) else {
+                error!("migration {} version {} is applied but not known", set.component, a.version);
+                return Err("database has migrations that are not known to this build");
+            };
+
+            info!("reverting migration {} {} {}", set.component, m.version, m.name);
+            Self::revert(conn, set.component, m).await?;
+            count += 1;
+        }
+
+        return Ok(count);
+    }
+
+    async fn apply(
+        conn: &mut PgConnection,
+        component: &str,
+        migration: &Migration
+    ) -> Result<(), &'static str> {
+        let mut tx = match sqlx::Connection::begin(&mut *conn).await {
+            Err(e) => {
+                error!("unable to start transaction: {:?}", e);
+                return Err("unable to start transaction");
+            }
+            Ok(tx) => tx
+        };
+
+        if let Err(e) = sqlx::raw_sql(migration.up).execute(&mut *tx).await {
+            error!("unable to apply migration {} {}: {:?}", component, migration.version, e);
+            return Err("unable to apply migration");
+        }
+
+        if let Err(e) = sqlx::query("insert into public.schema_migrations (component, version, name, checksum) values ($1, $2, $3, $4);")
+            .bind(component)
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(migration.checksum())
+            .execute(&mut *tx)
+            .await
+        {
+            error!("unable to record migration {} {}: {:?}", component, migration.version, e);
+            return Err("unable to record migration");
+        }
+
+        if let Err(e) = tx.commit().await {
+            error!("unable to commit migration {} {}: {:?}", component, migration.version, e);
+            return Err("unable to commit migration");
+        }
+
+        return Ok(());
+    }
+
+    async fn revert(
+        conn: &mut PgConnection,
+        component: &str,
+        migration: &Migration
+    ) -> Result<(), &'static str> {
+        let mut tx = match sqlx::Connection::begin(&mut *conn).await {
+            Err(e) => {
+                error!("unable to start transaction: {:?}", e);
+                return Err("unable to start transaction");
+            }
+            Ok(tx) => tx
+        };
+
+        if let Err(e) = sqlx::raw_sql(migration.down).execute(&mut *tx).await {
+            error!("unable to revert migration {} {}: {:?}", component, migration.version, e);
+            return Err("unable to revert migration");
+        }
+
+        if let Err(e) = sqlx::query("delete from public.schema_migrations where component = $1 and version = $2;")
+            .bind(component)
+            .bind(migration.version)
+            .execute(&mut *tx)
+            .await
+        {
+            error!("unable to remove migration record {} {}: {:?}", component, migration.version, e);
+            return Err("unable to remove migration record");
+        }
+
+        if let Err(e) = tx.commit().await {
+            error!("unable to commit migration {} {}: {:?}", component, migration.version, e);
+            return Err("unable to commit migration");
+        }
+
+        return Ok(());
+    }
+
+    async fn applied(
+        conn: &mut PgConnection,
+        component: &str
+    ) -> Result<Vec<AppliedMigration>, &'static str> {
+        match sqlx::query("select version, checksum, applied_ts from public.schema_migrations where component = $1 order by version;")
+            .bind(component)
+            .fetch_all(&mut *conn)
+            .await
+        {
+            Err(e) => {
+                error!("unable to fetch applied migrations: {:?}", e);
+                return Err("unable to fetch applied migrations");
+            }
+            Ok(rows) => {
+                return Ok(rows.iter().map(|r| AppliedMigration {
+                    version: r.get("version"),
+                    checksum: r.get("checksum"),
+                    applied_ts: r.get("applied_ts")
+                }).collect());
+            }
+        }
+    }
+
+    /// acquires a dedicated connection holding the migration lock and makes
+    /// sure the tracking table exists
+    async fn lock(&self) -> Result<sqlx::pool::PoolConnection<sqlx::Postgres>, &'static str> {
+        let Some(DatabaseType::Postgres(pool)) = self.dp.get_pool("main") else {
+            error!("No Postgres pool found for 'main'");
+            return Err("Unable to get pool for 'main'");
+        };
+
+        let mut conn = match pool.acquire().await {
+            Err(e) => {
+                error!("unable to acquire connection: {:?}", e);
+                return Err("unable to acquire connection");
+            }
+            Ok(conn) => conn
+        };
+
+        if let Err(e) = sqlx::query("select pg_advisory_lock($1);")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *conn)
+            .await
+        {
+            error!("unable to acquire migration lock: {:?}", e);
+            return Err("unable to acquire migration lock");
+        }
+        debug!("migration lock acquired");
+
+        if let Err(e) = sqlx::raw_sql(
+            "create table if not exists public.schema_migrations (
+                component text not null,
+                version bigint not null,
+                name text not null,
+                checksum text not null,
+                applied_ts timestamptz not null default now(),
+                constraint pk_schema_migrations primary key (component, version)
+            );"
+        )
+            .execute(&mut *conn)
+            .await
+        {
+            error!("unable to create migrations table: {:?}", e);
+            Self::unlock(&mut conn).await;
+            return Err("unable to create migrations table");
+        }
+
+        return Ok(conn);
+    }
+
+    async fn unlock(conn: &mut PgConnection) {
+        if let Err(e) = sqlx::query("select pg_advisory_unlock($1);")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *conn)
+            .await
+        {
+            error!("unable to release migration lock: {:?}", e);
+        }
+        debug!("migration lock released");
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum() {
+        let m = Migration {
+            version: 1,
+            name: "init",
+            up: "create table t (id int);",
+            down: "drop table t;"
+        };
+        let edited = Migration {
+            up: "create table t (id bigint);",
+            ..m
+        };
+
+        assert_eq!(m.checksum().len(), 64);
+        assert_eq!(m.checksum(), m.checksum());
+        assert_ne!(m.checksum(), edited.checksum());
+    }
+}
@@ -1,13 +1,24 @@
+#![allow(clippy::needless_return)]
+
 use serde::{
     Serialize,
     Deserialize
 };
 
+use provider_error::ProviderError;
+
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Folder {
     pub folder_id: uuid::Uuid,
-    pub name: String
+    /// `None` for folders in the root
+    pub parent_folder_id: Option<uuid::Uuid>,
+    /// user who created the folder, `None` for folders created before
+    /// access control
+    pub owner_id: Option<uuid::Uuid>,
+    pub name: String,
+    /// set while the folder is in the trash
+    pub trashed: Option<chrono::DateTime<chrono::Utc>>
 }
 
 
@@ -19,16 +30,93 @@ impl Folder {
     ) -> Self {
         return Folder {
             folder_id,
-            name
+            parent_folder_id: None,
+            owner_id: None,
+            name,
+            trashed: None
+        };
+    }
+
+    pub fn with_parent(mut self, parent_folder_id: &uuid::Uuid) -> Self {
+        self.parent_folder_id = if parent_folder_id.is_nil() { None } else { Some(*parent_folder_id) };
+        return self;
+    }
+}
+
+
+/// access to a folder, granted levels include the lower ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLevel {
+    /// list, download and view sharing
+    Read,
+    /// upload, add versions, rename and delete files
+    Write,
+    /// move, trash and share
+    Manage
+}
+
+impl AccessLevel {
+
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            AccessLevel::Read => "read",
+            AccessLevel::Write => "write",
+            AccessLevel::Manage => "manage"
+        };
+    }
+}
+
+impl std::str::FromStr for AccessLevel {
+    type Err = ProviderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "read" => Ok(AccessLevel::Read),
+            "write" => Ok(AccessLevel::Write),
+            "manage" => Ok(AccessLevel::Manage),
+            _ => Err(ProviderError::validation("level", "unknown access level"))
         };
     }
 }
 
 
+/// who a folder is shared with, either a role or a user of the tenant
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grantee {
+    Role(uuid::Uuid),
+    User(uuid::Uuid)
+}
+
+
+/// access to a folder and everything below it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FolderGrant {
+    /// folder the grant was made on, an ancestor for inherited grants
+    pub folder_id: uuid::Uuid,
+    pub grantee: Grantee,
+    pub level: AccessLevel
+}
+
+
+/// content type used when the client did not send one
+pub const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct File {
     pub file_id: uuid::Uuid,
-    pub name: String
+    pub name: String,
+    pub content_type: String,
+    pub size: i64,
+    /// hex encoded sha-256 of the content
+    pub digest: Option<String>,
+    /// current version
+    pub version: i32,
+    /// state of the preview of the content, only set in folder listings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<PreviewState>
 }
 
 
@@ -40,44 +128,716 @@ impl File {
     ) -> Self {
         return File {
             file_id,
-            name
+            name,
+            content_type: String::from(DEFAULT_CONTENT_TYPE),
+            size: 0,
+            digest: None,
+            version: 1,
+            preview: None
+        };
+    }
+
+    pub fn with_content(mut self, content_type: &str, size: i64, digest: &str) -> Self {
+        self.content_type = String::from(content_type);
+        self.size = size;
+        self.digest = Some(String::from(digest));
+        return self;
+    }
+}
+
+
+/// content of a file as uploaded at one point in time
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileVersion {
+    pub file_id: uuid::Uuid,
+    pub version: i32,
+    pub created: chrono::DateTime<chrono::Utc>,
+    pub content_type: String,
+    pub size: i64,
+    pub digest: String
+}
+
+
+/// what a share link makes available, a folder includes everything below it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareTarget {
+    File(uuid::Uuid),
+    Folder(uuid::Uuid)
+}
+
+impl ShareTarget {
+
+    /// file and folder id columns of the target, one of them is `None`
+    pub fn ids(&self) -> (Option<uuid::Uuid>, Option<uuid::Uuid>) {
+        return match self {
+            ShareTarget::File(file_id) => (Some(*file_id), None),
+            ShareTarget::Folder(folder_id) => (None, Some(*folder_id))
+        };
+    }
+}
+
+
+/// link to a file or folder usable without signing in until it expires or
+/// is revoked; only the link is stored, the token handed out refers to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShareLink {
+    pub link_id: uuid::Uuid,
+    pub target: ShareTarget,
+    pub created_by: uuid::Uuid,
+    pub created: chrono::DateTime<chrono::Utc>,
+    pub expires: chrono::DateTime<chrono::Utc>,
+    pub password_protected: bool,
+    pub revoked: Option<chrono::DateTime<chrono::Utc>>
+}
+
+
+/// file available through a share link
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SharedFile {
+    /// relative to the shared folder, e.g. `reports/q1.pdf`
+    pub path: String,
+    #[serde(flatten)]
+    pub file: File
+}
+
+
+/// what came of a request with a share link
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareOutcome {
+    Listed,
+    Downloaded,
+    /// wrong password, or the link or file was not available
+    Denied
+}
+
+impl ShareOutcome {
+
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            ShareOutcome::Listed => "listed",
+            ShareOutcome::Downloaded => "downloaded",
+            ShareOutcome::Denied => "denied"
+        };
+    }
+}
+
+impl std::str::FromStr for ShareOutcome {
+    type Err = ProviderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "listed" => Ok(ShareOutcome::Listed),
+            "downloaded" => Ok(ShareOutcome::Downloaded),
+            "denied" => Ok(ShareOutcome::Denied),
+            _ => Err(ProviderError::validation("outcome", "unknown share outcome"))
+        };
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShareLinkAccess {
+    pub accessed: chrono::DateTime<chrono::Utc>,
+    /// file that was downloaded or denied, `None` for listings
+    pub file_id: Option<uuid::Uuid>,
+    pub outcome: ShareOutcome,
+    pub remote_addr: Option<String>,
+    pub user_agent: Option<String>
+}
+
+
+/// business records files can be attached to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityType {
+    Account,
+    Invoice,
+    Item,
+    Partner,
+    PurchaseOrder
+}
+
+impl EntityType {
+
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            EntityType::Account => "account",
+            EntityType::Invoice => "invoice",
+            EntityType::Item => "item",
+            EntityType::Partner => "partner",
+            EntityType::PurchaseOrder => "purchase_order"
+        };
+    }
+}
+
+
+/// file attached to a business record
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Attachment {
+    #[serde(flatten)]
+    pub file: File,
+    pub attached_by: Option<uuid::Uuid>,
+    pub attached: chrono::DateTime<chrono::Utc>
+}
+
+
+/// resumable upload of a new file, the content is kept by the file store
+/// until all `length` bytes are received; `folder_id` is nil for the root
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileUpload {
+    pub upload_id: uuid::Uuid,
+    pub file_id: uuid::Uuid,
+    pub folder_id: uuid::Uuid,
+    pub name: String,
+    pub content_type: String,
+    pub length: i64,
+    pub offset: i64,
+    pub expires: chrono::DateTime<chrono::Utc>
+}
+
+
+/// storage used by a tenant in bytes, `quota` is the tenant's own quota
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub used: i64,
+    pub files: i64,
+    pub quota: Option<i64>
+}
+
+
+/// previews are rendered per content, for images and pdf documents
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewState {
+    /// no preview is rendered for the content type
+    None,
+    Pending,
+    Ready,
+    /// rendering failed on every attempt
+    Failed
+}
+
+impl std::str::FromStr for PreviewState {
+    type Err = ProviderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "none" => Ok(PreviewState::None),
+            "pending" => Ok(PreviewState::Pending),
+            "ready" => Ok(PreviewState::Ready),
+            "failed" => Ok(PreviewState::Failed),
+            _ => Err(ProviderError::validation("state", "unknown preview state"))
         };
     }
 }
 
 
+/// preview of the content with `digest`, `content_type` is the type of
+/// the rendered image once it is ready
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preview {
+    pub digest: String,
+    pub state: PreviewState,
+    pub content_type: Option<String>,
+    pub updated: chrono::DateTime<chrono::Utc>
+}
+
+
+/// preview claimed for rendering, `source_type` is the content type of the
+/// content it is rendered from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreviewJob {
+    pub tenant_id: uuid::Uuid,
+    pub digest: String,
+    pub source_type: String,
+    /// failed attempts so far
+    pub attempts: i32
+}
+
+
+/// text claimed for extraction, `source_type` is the content type of the
+/// content it is extracted from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextJob {
+    pub tenant_id: uuid::Uuid,
+    pub digest: String,
+    pub source_type: String,
+    /// failed attempts so far
+    pub attempts: i32
+}
+
+
+/// marks the start of a match in a search snippet
+pub const MATCH_START: char = '\u{2}';
+/// marks the end of a match in a search snippet
+pub const MATCH_END: char = '\u{3}';
+
+/// file found by a search, `path` are the names of the folders from the
+/// root down to the file's folder, as far as the user can see them
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub file: File,
+    /// `None` for files in the root
+    pub folder_id: Option<uuid::Uuid>,
+    pub path: Vec<String>,
+    pub rank: f32,
+    /// text around the matches, which are enclosed in `MATCH_START` and
+    /// `MATCH_END`; `None` without extracted text
+    pub snippet: Option<String>
+}
+
+
+/// icon shown for files without a preview, by content type
+pub fn icon(content_type: &str) -> &'static str {
+    let content_type = content_type.to_ascii_lowercase();
+    let (kind, subtype) = content_type.split_once('/').unwrap_or((&content_type, ""));
+
+    let icons: &[(&str, &'static str)] = &[
+        ("pdf", "pdf"),
+        ("spreadsheet", "spreadsheet"),
+        ("ms-excel", "spreadsheet"),
+        ("csv", "spreadsheet"),
+        ("presentation", "presentation"),
+        ("powerpoint", "presentation"),
+        ("wordprocessing", "document"),
+        ("msword", "document"),
+        ("opendocument.text", "document"),
+        ("rtf", "document"),
+        ("zip", "archive"),
+        ("gzip", "archive"),
+        ("7z", "archive"),
+        ("rar", "archive"),
+        ("tar", "archive")
+    ];
+    if let Some((_, icon)) = icons.iter().find(|(part, _)| subtype.contains(part)) {
+        return icon;
+    }
+
+    return match kind {
+        "image" => "image",
+        "audio" => "audio",
+        "video" => "video",
+        "text" => "text",
+        _ => "file"
+    };
+}
+
+
+/// folders and files are only visible to the users they are shared with;
+/// whatever a user cannot see is `NotFound`, too little access is
+/// `Forbidden`
 pub trait FileProvider {
 
+    /// adds the folder below its parent, which has to be an active folder
+    /// of the same tenant
     fn folder_add(
         &self,
         tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
         folder: &Folder
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn folder_get(
         &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
         folder_id: &uuid::Uuid
-    ) -> impl Future<Output = Result<Folder, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Folder, ProviderError>> + Send;
 
+    /// subfolders of the folder, the nil uuid lists the root
     fn folder_list_folders(
         &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
         folder_id: &uuid::Uuid
-    ) -> impl Future<Output = Result<Vec<Folder>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Folder>, ProviderError>> + Send;
 
+    /// files in the folder, the nil uuid lists the root
     fn folder_list_files(
         &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<Vec<File>, ProviderError>> + Send;
+
+    fn folder_rename(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid,
+        name: &str
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// moves the folder below `parent_folder_id`, the nil uuid moves it to
+    /// the root; moving a folder below itself is a validation error
+    fn folder_move(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid,
+        parent_folder_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// moves the folder with its subfolders and files to the trash
+    fn folder_trash(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// restores a folder from the trash with everything trashed along with
+    /// it, the folder is restored to the root when its parent is gone
+    fn folder_restore(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// folders in the trash, most recently trashed first
+    fn folder_trash_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<Vec<Folder>, ProviderError>> + Send;
+
+    /// the folder and its ancestors, starting at the root
+    fn folder_path(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
         folder_id: &uuid::Uuid
-    ) -> impl Future<Output = Result<Vec<File>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Folder>, ProviderError>> + Send;
 
+    /// adds the file and a reference to its content
     fn file_add(
         &self,
         tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
         folder_id: &uuid::Uuid,
         file: &File
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// removes the file with all versions, returns the digests of content
+    /// no other file refers to any more and which can be removed
+    fn file_delete(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<Vec<String>, ProviderError>> + Send;
+
+    /// adds the content of `file` as new current version of the file,
+    /// returns the version number
+    fn file_version_add(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file: &File
+    ) -> impl Future<Output = Result<i32, ProviderError>> + Send;
+
+    /// versions of the file, newest first
+    fn file_versions_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<Vec<FileVersion>, ProviderError>> + Send;
 
+    fn file_version_get(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file_id: &uuid::Uuid,
+        version: i32
+    ) -> impl Future<Output = Result<FileVersion, ProviderError>> + Send;
+
+    /// adds the content of an older version as new current version,
+    /// returns the new version number
+    fn file_version_restore(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file_id: &uuid::Uuid,
+        version: i32
+    ) -> impl Future<Output = Result<i32, ProviderError>> + Send;
+
+    fn blob_is_referenced(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str
+    ) -> impl Future<Output = Result<bool, ProviderError>> + Send;
+
+    /// active file of the tenant, `NotFound` for files of other tenants
     fn file_get(
         &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<File, ProviderError>> + Send;
+
+    /// grants on the folder and the ones it inherits, closest first
+    fn folder_grants_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<Vec<FolderGrant>, ProviderError>> + Send;
+
+    /// adds or changes the grant on `grant.folder_id`, requires manage
+    /// access
+    fn folder_grant_save(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        grant: &FolderGrant
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    fn folder_grant_remove(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid,
+        grantee: &Grantee
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// adds a link to the target, requires manage access to it; an empty
+    /// password is no password, others are stored as argon2id hash
+    fn share_link_add(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        link_id: &uuid::Uuid,
+        target: &ShareTarget,
+        expires: &chrono::DateTime<chrono::Utc>,
+        password: Option<&str>
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// links to the target including revoked and expired ones, newest first
+    fn share_links_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        target: &ShareTarget
+    ) -> impl Future<Output = Result<Vec<ShareLink>, ProviderError>> + Send;
+
+    fn share_link_revoke(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        link_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// link that can be used without signing in, `NotFound` once it is
+    /// revoked or expired or its target is gone, `Forbidden` when the
+    /// password does not match and `Throttled` while the link takes no
+    /// password after too many wrong ones
+    fn share_link_open(
+        &self,
+        tenant_id: &uuid::Uuid,
+        link_id: &uuid::Uuid,
+        password: Option<&str>
+    ) -> impl Future<Output = Result<ShareLink, ProviderError>> + Send;
+
+    /// files the link makes available, by path; the link has to be opened
+    /// first
+    fn share_link_files(
+        &self,
+        tenant_id: &uuid::Uuid,
+        link_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<Vec<SharedFile>, ProviderError>> + Send;
+
+    /// file the link makes available, `NotFound` for any other file
+    fn share_link_file_get(
+        &self,
+        tenant_id: &uuid::Uuid,
+        link_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<File, ProviderError>> + Send;
+
+    fn share_link_access_add(
+        &self,
+        tenant_id: &uuid::Uuid,
+        link_id: &uuid::Uuid,
+        file_id: Option<&uuid::Uuid>,
+        outcome: ShareOutcome,
+        remote_addr: Option<&str>,
+        user_agent: Option<&str>
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// accesses of the link, newest first
+    fn share_link_accesses_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        link_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<Vec<ShareLinkAccess>, ProviderError>> + Send;
+
+    /// attaches the file to the record of the tenant, requires read access
+    /// to the file; records that do not exist are a validation error
+    fn attachment_add(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        entity_type: EntityType,
+        entity_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    fn attachment_remove(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        entity_type: EntityType,
+        entity_id: &uuid::Uuid,
         file_id: &uuid::Uuid
-    ) -> impl Future<Output = Result<File, &'static str>> + Send;
-}
\ No newline at end of file
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// files attached to the record the user can see, oldest attachment
+    /// first
+    fn attachments_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        entity_type: EntityType,
+        entity_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<Vec<Attachment>, ProviderError>> + Send;
+
+    /// number of the attachments `attachments_fetch` returns
+    fn attachment_count(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        entity_type: EntityType,
+        entity_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<i64, ProviderError>> + Send;
+
+    /// starts the upload of a new file, which requires write access to the
+    /// folder; the file id must not be in use
+    fn upload_add(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        upload: &FileUpload
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// an upload the user started that has not expired
+    fn upload_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        upload_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<FileUpload, ProviderError>> + Send;
+
+    /// moves the offset of the upload from `from` to `to`, an upload that
+    /// is no longer at `from` is a `Conflict`
+    fn upload_offset_save(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        upload_id: &uuid::Uuid,
+        from: i64,
+        to: i64
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    fn upload_remove(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        upload_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// removes the expired uploads of all tenants, returns their tenant and
+    /// upload ids so the partial uploads can be discarded
+    fn uploads_expire(
+        &self
+    ) -> impl Future<Output = Result<Vec<(uuid::Uuid, uuid::Uuid)>, ProviderError>> + Send;
+
+    /// content stored for the tenant, each content counted once
+    fn usage_fetch(
+        &self,
+        tenant_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<Usage, ProviderError>> + Send;
+
+    /// sets the tenant's own quota, `None` falls back to the default
+    fn quota_save(
+        &self,
+        tenant_id: &uuid::Uuid,
+        quota: Option<i64>
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// the preview of the current content of the file, `None` when its
+    /// content type has none
+    fn file_preview_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<Option<Preview>, ProviderError>> + Send;
+
+    /// claims up to `limit` pending previews of any tenant for `lease`
+    /// seconds, for the preview worker
+    fn previews_claim(
+        &self,
+        limit: i32,
+        lease: i32
+    ) -> impl Future<Output = Result<Vec<PreviewJob>, ProviderError>> + Send;
+
+    /// `content_type` is the type of the rendered image
+    fn preview_ready(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str,
+        content_type: &str
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// the preview is tried again at `retry_at`, without one it is given up
+    fn preview_failed(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str,
+        error: &str,
+        retry_at: Option<&chrono::DateTime<chrono::Utc>>
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// files of the tenant visible to the user whose name or text matches
+    /// the query, in web search syntax, best matches first
+    fn file_search(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        query: &str,
+        limit: i32,
+        offset: i32
+    ) -> impl Future<Output = Result<Vec<SearchResult>, ProviderError>> + Send;
+
+    /// claims up to `limit` pending texts of any tenant for `lease`
+    /// seconds, for the search worker
+    fn texts_claim(
+        &self,
+        limit: i32,
+        lease: i32
+    ) -> impl Future<Output = Result<Vec<TextJob>, ProviderError>> + Send;
+
+    /// stores the text extracted from the content and indexes it
+    fn text_ready(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str,
+        body: &str
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// the text is extracted again at `retry_at`, without one it is given
+    /// up
+    fn text_failed(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str,
+        error: &str,
+        retry_at: Option<&chrono::DateTime<chrono::Utc>>
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+}
@@ -0,0 +1,150 @@
+-- folders and files, a null folder or parent marks the root
+
+create schema if not exists files;
+
+
+create table files.folders (
+    folder_id uuid not null,
+    tenant_id uuid not null,
+    parent_folder_id uuid,
+    active boolean not null default true,
+    created_ts timestamptz not null default now(),
+    updated_ts timestamptz not null default now(),
+    name text not null,
+    constraint pk_folders primary key (folder_id),
+    constraint fk_folders_tenant foreign key (tenant_id) references tenants.tenants (tenant_id),
+    constraint fk_folders_parent foreign key (parent_folder_id) references files.folders (folder_id)
+);
+
+create index i_folders_tenant on files.folders (tenant_id);
+create index i_folders_parent on files.folders (parent_folder_id);
+
+create table files.files (
+    file_id uuid not null,
+    tenant_id uuid not null,
+    folder_id uuid,
+    active boolean not null default true,
+    created_ts timestamptz not null default now(),
+    updated_ts timestamptz not null default now(),
+    name text not null,
+    constraint pk_files primary key (file_id),
+    constraint fk_files_tenant foreign key (tenant_id) references tenants.tenants (tenant_id),
+    constraint fk_files_folder foreign key (folder_id) references files.folders (folder_id)
+);
+
+create index i_files_tenant on files.files (tenant_id);
+create index i_files_folder on files.files (folder_id);
+
+
+create procedure files.folder_add(
+    p_tenant_id uuid,
+    p_folder_id uuid,
+    p_name text
+)
+language sql
+as $$
+    insert into files.folders (
+        folder_id,
+        tenant_id,
+        name
+    ) values (
+        p_folder_id,
+        p_tenant_id,
+        p_name
+    );
+$$;
+
+-- the nil uuid as folder places the file in the root
+create procedure files.file_add(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text
+)
+language sql
+as $$
+    insert into files.files (
+        file_id,
+        tenant_id,
+        folder_id,
+        name
+    ) values (
+        p_file_id,
+        p_tenant_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_name
+    );
+$$;
+
+
+create function files.folder_get(
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.name
+    from files.folders f
+    where f.folder_id = p_folder_id;
+$$;
+
+create function files.folder_list_folders(
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.name
+    from files.folders f
+    where f.active
+        and f.parent_folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+create function files.folder_list_files(
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name
+    from files.files f
+    where f.active
+        and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+create function files.file_get(
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name
+    from files.files f
+    where f.file_id = p_file_id;
+$$;
@@ -0,0 +1,67 @@
+drop function files.file_get(uuid, uuid);
+
+create function files.file_get(
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name
+    from files.files f
+    where f.file_id = p_file_id;
+$$;
+
+drop function files.folder_list_files(uuid);
+
+create function files.folder_list_files(
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name
+    from files.files f
+    where f.active
+        and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+drop procedure files.file_add(uuid, uuid, uuid, text, text, bigint);
+
+-- the nil uuid as folder places the file in the root
+create procedure files.file_add(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text
+)
+language sql
+as $$
+    insert into files.files (
+        file_id,
+        tenant_id,
+        folder_id,
+        name
+    ) values (
+        p_file_id,
+        p_tenant_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_name
+    );
+$$;
+
+alter table files.files
+    drop column size,
+    drop column content_type;
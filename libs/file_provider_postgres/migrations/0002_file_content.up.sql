@@ -0,0 +1,90 @@
+-- content type and size of stored files, lookups of files are scoped to
+-- their tenant
+
+alter table files.files
+    add column content_type text not null default 'application/octet-stream',
+    add column size bigint not null default 0;
+
+
+drop procedure files.file_add(uuid, uuid, uuid, text);
+
+-- the nil uuid as folder places the file in the root
+create procedure files.file_add(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text,
+    p_content_type text,
+    p_size bigint
+)
+language sql
+as $$
+    insert into files.files (
+        file_id,
+        tenant_id,
+        folder_id,
+        name,
+        content_type,
+        size
+    ) values (
+        p_file_id,
+        p_tenant_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_name,
+        p_content_type,
+        p_size
+    );
+$$;
+
+
+drop function files.folder_list_files(uuid);
+
+create function files.folder_list_files(
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size
+    from files.files f
+    where f.active
+        and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+
+drop function files.file_get(uuid);
+
+create function files.file_get(
+    p_tenant_id uuid,
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size
+    from files.files f
+    where f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active;
+$$;
@@ -0,0 +1,182 @@
+-- content is stored once per tenant and sha-256 digest, files refer to it
+-- and the blob counts its references; files uploaded before have no digest
+
+create table files.blobs (
+    tenant_id uuid not null,
+    digest text not null,
+    size bigint not null,
+    ref_count int not null default 0,
+    created_ts timestamptz not null default now(),
+    constraint pk_blobs primary key (tenant_id, digest),
+    constraint fk_blobs_tenant foreign key (tenant_id) references tenants.tenants (tenant_id),
+    constraint c_blobs_ref_count check (ref_count >= 0)
+);
+
+alter table files.files
+    add column digest text,
+    add constraint fk_files_blob foreign key (tenant_id, digest) references files.blobs (tenant_id, digest);
+
+create index i_files_blob on files.files (tenant_id, digest);
+
+
+drop procedure files.file_add(uuid, uuid, uuid, text, text, bigint);
+
+-- the nil uuid as folder places the file in the root
+create procedure files.file_add(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+language sql
+as $$
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    insert into files.files (
+        file_id,
+        tenant_id,
+        folder_id,
+        name,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        p_tenant_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_name,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+$$;
+
+-- removes the file, returns the digest of its content once no other file
+-- refers to it and null otherwise
+create function files.file_delete(
+    p_tenant_id uuid,
+    p_file_id uuid
+)
+returns text
+language plpgsql
+as $$
+declare
+    v_digest text;
+begin
+    delete from files.files f
+    where
+        f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+    returning f.digest into v_digest;
+
+    if not found then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+
+    update files.blobs b set
+        ref_count = b.ref_count - 1
+    where
+        b.tenant_id = p_tenant_id
+        and b.digest = v_digest;
+
+    delete from files.blobs b
+    where
+        b.tenant_id = p_tenant_id
+        and b.digest = v_digest
+        and b.ref_count = 0;
+
+    if not found then
+        return null;
+    end if;
+    return v_digest;
+end;
+$$;
+
+create function files.blob_is_referenced(
+    p_tenant_id uuid,
+    p_digest text
+)
+returns boolean
+language sql
+stable
+as $$
+    select exists (
+        select 1
+        from files.blobs b
+        where
+            b.tenant_id = p_tenant_id
+            and b.digest = p_digest
+    );
+$$;
+
+
+drop function files.folder_list_files(uuid);
+
+create function files.folder_list_files(
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest
+    from files.files f
+    where f.active
+        and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+
+drop function files.file_get(uuid, uuid);
+
+create function files.file_get(
+    p_tenant_id uuid,
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest
+    from files.files f
+    where f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active;
+$$;
@@ -0,0 +1,180 @@
+drop function files.file_version_get(uuid, uuid, int);
+drop function files.file_versions_fetch(uuid, uuid);
+drop function files.file_version_restore(uuid, uuid, int);
+drop function files.file_version_add(uuid, uuid, text, bigint, text);
+
+drop function files.file_delete(uuid, uuid);
+
+-- removes the file, returns the digest of its content once no other file
+-- refers to it and null otherwise
+create function files.file_delete(
+    p_tenant_id uuid,
+    p_file_id uuid
+)
+returns text
+language plpgsql
+as $$
+declare
+    v_digest text;
+begin
+    delete from files.files f
+    where
+        f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+    returning f.digest into v_digest;
+
+    if not found then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+
+    update files.blobs b set
+        ref_count = b.ref_count - 1
+    where
+        b.tenant_id = p_tenant_id
+        and b.digest = v_digest;
+
+    delete from files.blobs b
+    where
+        b.tenant_id = p_tenant_id
+        and b.digest = v_digest
+        and b.ref_count = 0;
+
+    if not found then
+        return null;
+    end if;
+    return v_digest;
+end;
+$$;
+
+
+drop function files.folder_list_files(uuid);
+
+create function files.folder_list_files(
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest
+    from files.files f
+    where f.active
+        and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+
+drop function files.file_get(uuid, uuid);
+
+create function files.file_get(
+    p_tenant_id uuid,
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest
+    from files.files f
+    where f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active;
+$$;
+
+
+drop procedure files.file_add(uuid, uuid, uuid, text, text, bigint, text);
+
+-- the nil uuid as folder places the file in the root
+create procedure files.file_add(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+language sql
+as $$
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    insert into files.files (
+        file_id,
+        tenant_id,
+        folder_id,
+        name,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        p_tenant_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_name,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+$$;
+
+
+-- files refer to the content of their current version only
+update files.blobs b set
+    ref_count = x.refs
+from (
+    select f.tenant_id, f.digest, count(*) as refs
+    from files.files f
+    where f.digest is not null
+    group by f.tenant_id, f.digest
+) x
+where
+    b.tenant_id = x.tenant_id
+    and b.digest = x.digest;
+
+drop table files.file_versions;
+
+delete from files.blobs b
+where not exists (
+    select 1
+    from files.files f
+    where
+        f.tenant_id = b.tenant_id
+        and f.digest = b.digest
+);
+
+alter table files.files
+    drop column version;
@@ -0,0 +1,371 @@
+-- every upload of a file becomes a version, each version refers to its
+-- content; the file carries the content of its current version
+
+alter table files.files
+    add column version int not null default 1;
+
+create table files.file_versions (
+    file_id uuid not null,
+    version int not null,
+    tenant_id uuid not null,
+    created_ts timestamptz not null default now(),
+    content_type text not null,
+    size bigint not null,
+    digest text not null,
+    constraint pk_file_versions primary key (file_id, version),
+    constraint fk_file_versions_file foreign key (file_id) references files.files (file_id) on delete cascade,
+    constraint fk_file_versions_blob foreign key (tenant_id, digest) references files.blobs (tenant_id, digest)
+);
+
+create index i_file_versions_blob on files.file_versions (tenant_id, digest);
+
+-- references of existing files move to their first version
+insert into files.file_versions (
+    file_id,
+    version,
+    tenant_id,
+    created_ts,
+    content_type,
+    size,
+    digest
+)
+select
+    f.file_id,
+    1,
+    f.tenant_id,
+    f.created_ts,
+    f.content_type,
+    f.size,
+    f.digest
+from files.files f
+where f.digest is not null;
+
+
+drop procedure files.file_add(uuid, uuid, uuid, text, text, bigint, text);
+
+-- the nil uuid as folder places the file in the root
+create procedure files.file_add(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+language sql
+as $$
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    insert into files.files (
+        file_id,
+        tenant_id,
+        folder_id,
+        name,
+        content_type,
+        size,
+        digest,
+        version
+    ) values (
+        p_file_id,
+        p_tenant_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_name,
+        p_content_type,
+        p_size,
+        p_digest,
+        1
+    );
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        1,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+$$;
+
+-- adds the content as the next version of the file and makes it current,
+-- returns the new version number
+create function files.file_version_add(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+returns int
+language plpgsql
+as $$
+declare
+    v_version int;
+begin
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    update files.files f set
+        version = f.version + 1,
+        content_type = p_content_type,
+        size = p_size,
+        digest = p_digest,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active
+    returning f.version into v_version;
+
+    if not found then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        v_version,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+
+    return v_version;
+end;
+$$;
+
+-- restoring adds the content of an older version as new version, the
+-- history is kept
+create function files.file_version_restore(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_version int
+)
+returns int
+language plpgsql
+as $$
+declare
+    r files.file_versions%rowtype;
+begin
+    select v.* into r
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id
+        and v.version = p_version;
+
+    if not found then
+        raise exception 'no version % of file %', p_version, p_file_id using errcode = 'P0002';
+    end if;
+
+    return files.file_version_add(p_tenant_id, p_file_id, r.content_type, r.size, r.digest);
+end;
+$$;
+
+create function files.file_versions_fetch(
+    p_tenant_id uuid,
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    version int,
+    created_ts timestamptz,
+    content_type text,
+    size bigint,
+    digest text
+)
+language sql
+stable
+as $$
+    select
+        v.file_id,
+        v.version,
+        v.created_ts,
+        v.content_type,
+        v.size,
+        v.digest
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id
+    order by v.version desc;
+$$;
+
+create function files.file_version_get(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_version int
+)
+returns table (
+    file_id uuid,
+    version int,
+    created_ts timestamptz,
+    content_type text,
+    size bigint,
+    digest text
+)
+language sql
+stable
+as $$
+    select
+        v.file_id,
+        v.version,
+        v.created_ts,
+        v.content_type,
+        v.size,
+        v.digest
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id
+        and v.version = p_version;
+$$;
+
+
+drop function files.file_delete(uuid, uuid);
+
+-- removes the file with all its versions, returns the digests of content
+-- no other version refers to any more
+create function files.file_delete(
+    p_tenant_id uuid,
+    p_file_id uuid
+)
+returns setof text
+language plpgsql
+as $$
+declare
+    v_digests text[];
+begin
+    select array_agg(v.digest) into v_digests
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id;
+
+    delete from files.files f
+    where
+        f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id;
+
+    if not found then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+
+    update files.blobs b set
+        ref_count = b.ref_count - x.refs
+    from (
+        select d.digest, count(*) as refs
+        from unnest(v_digests) as d (digest)
+        group by d.digest
+    ) x
+    where
+        b.tenant_id = p_tenant_id
+        and b.digest = x.digest;
+
+    return query
+        delete from files.blobs b
+        where
+            b.tenant_id = p_tenant_id
+            and b.digest = any(v_digests)
+            and b.ref_count = 0
+        returning b.digest;
+end;
+$$;
+
+
+drop function files.folder_list_files(uuid);
+
+create function files.folder_list_files(
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest,
+        f.version
+    from files.files f
+    where f.active
+        and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+
+drop function files.file_get(uuid, uuid);
+
+create function files.file_get(
+    p_tenant_id uuid,
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest,
+        f.version
+    from files.files f
+    where f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active;
+$$;
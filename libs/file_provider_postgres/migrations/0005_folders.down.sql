@@ -0,0 +1,110 @@
+drop function files.folder_list_files(uuid, uuid);
+
+create function files.folder_list_files(
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest,
+        f.version
+    from files.files f
+    where f.active
+        and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+
+drop function files.folder_path(uuid, uuid);
+drop function files.folder_trash_fetch(uuid);
+
+drop function files.folder_list_folders(uuid, uuid);
+
+create function files.folder_list_folders(
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.name
+    from files.folders f
+    where f.active
+        and f.parent_folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+drop function files.folder_get(uuid, uuid);
+
+create function files.folder_get(
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    name text
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.name
+    from files.folders f
+    where f.folder_id = p_folder_id;
+$$;
+
+
+drop procedure files.folder_restore(uuid, uuid);
+drop procedure files.folder_trash(uuid, uuid);
+drop procedure files.folder_move(uuid, uuid, uuid);
+drop procedure files.folder_rename(uuid, uuid, text);
+
+drop procedure files.folder_add(uuid, uuid, uuid, text);
+
+create procedure files.folder_add(
+    p_tenant_id uuid,
+    p_folder_id uuid,
+    p_name text
+)
+language sql
+as $$
+    insert into files.folders (
+        folder_id,
+        tenant_id,
+        name
+    ) values (
+        p_folder_id,
+        p_tenant_id,
+        p_name
+    );
+$$;
+
+
+drop index files.i_files_trashed_with;
+drop index files.i_folders_trashed_with;
+
+alter table files.files
+    drop column trashed_with,
+    drop column trashed_ts;
+
+alter table files.folders
+    drop column trashed_with,
+    drop column trashed_ts;
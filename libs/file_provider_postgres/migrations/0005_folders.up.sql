@@ -0,0 +1,409 @@
+-- folder management: rename, move, trash and breadcrumbs; folders and
+-- files are looked up within their tenant. Deleting a folder moves it with
+-- everything below to the trash, `trashed_with` is the folder the deletion
+-- started from so that a restore brings back exactly what was trashed
+
+alter table files.folders
+    add column trashed_ts timestamptz,
+    add column trashed_with uuid;
+
+alter table files.files
+    add column trashed_ts timestamptz,
+    add column trashed_with uuid;
+
+create index i_folders_trashed_with on files.folders (tenant_id, trashed_with);
+create index i_files_trashed_with on files.files (tenant_id, trashed_with);
+
+
+drop procedure files.folder_add(uuid, uuid, text);
+
+-- the nil uuid as parent places the folder in the root
+create procedure files.folder_add(
+    p_tenant_id uuid,
+    p_folder_id uuid,
+    p_parent_folder_id uuid,
+    p_name text
+)
+language plpgsql
+as $$
+declare
+    v_parent_folder_id uuid := nullif(p_parent_folder_id, '00000000-0000-0000-0000-000000000000'::uuid);
+begin
+    if trim(p_name) = '' then
+        raise exception 'folder name is required' using errcode = '23502', column = 'name';
+    end if;
+
+    if v_parent_folder_id is not null and not exists (
+        select 1
+        from files.folders p
+        where
+            p.tenant_id = p_tenant_id
+            and p.folder_id = v_parent_folder_id
+            and p.active
+    ) then
+        raise exception 'no folder %', v_parent_folder_id using errcode = '23503', column = 'parent_folder_id';
+    end if;
+
+    insert into files.folders (
+        folder_id,
+        tenant_id,
+        parent_folder_id,
+        name
+    ) values (
+        p_folder_id,
+        p_tenant_id,
+        v_parent_folder_id,
+        trim(p_name)
+    );
+end;
+$$;
+
+create procedure files.folder_rename(
+    p_tenant_id uuid,
+    p_folder_id uuid,
+    p_name text
+)
+language plpgsql
+as $$
+begin
+    if trim(p_name) = '' then
+        raise exception 'folder name is required' using errcode = '23502', column = 'name';
+    end if;
+
+    update files.folders f set
+        name = trim(p_name),
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.active;
+
+    if not found then
+        raise exception 'no folder %', p_folder_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+-- the nil uuid as parent moves the folder to the root; moving a folder
+-- below itself is rejected
+create procedure files.folder_move(
+    p_tenant_id uuid,
+    p_folder_id uuid,
+    p_parent_folder_id uuid
+)
+language plpgsql
+as $$
+declare
+    v_parent_folder_id uuid := nullif(p_parent_folder_id, '00000000-0000-0000-0000-000000000000'::uuid);
+begin
+    -- concurrent moves could form a cycle that neither of them sees
+    perform pg_advisory_xact_lock(hashtextextended('files.folders:' || p_tenant_id::text, 0));
+
+    if v_parent_folder_id is not null then
+        if not exists (
+            select 1
+            from files.folders p
+            where
+                p.tenant_id = p_tenant_id
+                and p.folder_id = v_parent_folder_id
+                and p.active
+        ) then
+            raise exception 'no folder %', v_parent_folder_id using errcode = '23503', column = 'parent_folder_id';
+        end if;
+
+        if exists (
+            with recursive ancestors as (
+                select
+                    p.folder_id,
+                    p.parent_folder_id
+                from files.folders p
+                where p.folder_id = v_parent_folder_id
+                union all
+                select
+                    p.folder_id,
+                    p.parent_folder_id
+                from files.folders p
+                    join ancestors a on p.folder_id = a.parent_folder_id
+            )
+            select 1
+            from ancestors a
+            where a.folder_id = p_folder_id
+        ) then
+            raise exception 'a folder cannot be moved into itself or one of its subfolders' using errcode = '22023', column = 'parent_folder_id';
+        end if;
+    end if;
+
+    update files.folders f set
+        parent_folder_id = v_parent_folder_id,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.active;
+
+    if not found then
+        raise exception 'no folder %', p_folder_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+-- moves the folder, its subfolders and their files to the trash
+create procedure files.folder_trash(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+language plpgsql
+as $$
+begin
+    with recursive tree as (
+        select f.folder_id
+        from files.folders f
+        where
+            f.tenant_id = p_tenant_id
+            and f.folder_id = p_folder_id
+            and f.active
+        union all
+        select c.folder_id
+        from files.folders c
+            join tree t on c.parent_folder_id = t.folder_id
+        where c.active
+    )
+    update files.folders f set
+        active = false,
+        trashed_ts = now(),
+        trashed_with = p_folder_id,
+        updated_ts = now()
+    from tree t
+    where f.folder_id = t.folder_id;
+
+    if not found then
+        raise exception 'no folder %', p_folder_id using errcode = 'P0002';
+    end if;
+
+    update files.files f set
+        active = false,
+        trashed_ts = now(),
+        trashed_with = p_folder_id,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.active
+        and f.folder_id in (
+            select t.folder_id
+            from files.folders t
+            where
+                t.tenant_id = p_tenant_id
+                and t.trashed_with = p_folder_id
+        );
+end;
+$$;
+
+-- brings back what was trashed with the folder, the folder moves to the
+-- root when its former parent is gone
+create procedure files.folder_restore(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+language plpgsql
+as $$
+declare
+    v_parent_folder_id uuid;
+begin
+    select f.parent_folder_id into v_parent_folder_id
+    from files.folders f
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.trashed_with = p_folder_id;
+
+    if not found then
+        raise exception 'no folder % in the trash', p_folder_id using errcode = 'P0002';
+    end if;
+
+    if v_parent_folder_id is not null and not exists (
+        select 1
+        from files.folders p
+        where
+            p.folder_id = v_parent_folder_id
+            and p.active
+    ) then
+        update files.folders f set
+            parent_folder_id = null
+        where f.folder_id = p_folder_id;
+    end if;
+
+    update files.folders f set
+        active = true,
+        trashed_ts = null,
+        trashed_with = null,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.trashed_with = p_folder_id;
+
+    update files.files f set
+        active = true,
+        trashed_ts = null,
+        trashed_with = null,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.trashed_with = p_folder_id;
+end;
+$$;
+
+
+drop function files.folder_get(uuid);
+
+create function files.folder_get(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.parent_folder_id,
+        f.name,
+        f.trashed_ts
+    from files.folders f
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.active;
+$$;
+
+drop function files.folder_list_folders(uuid);
+
+create function files.folder_list_folders(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.parent_folder_id,
+        f.name,
+        f.trashed_ts
+    from files.folders f
+    where f.tenant_id = p_tenant_id
+        and f.active
+        and f.parent_folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+-- folders deleted by the user, without the subfolders trashed with them
+create function files.folder_trash_fetch(
+    p_tenant_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.parent_folder_id,
+        f.name,
+        f.trashed_ts
+    from files.folders f
+    where f.tenant_id = p_tenant_id
+        and f.trashed_with = f.folder_id
+    order by f.trashed_ts desc;
+$$;
+
+-- the folder and its ancestors, starting at the root
+create function files.folder_path(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language sql
+stable
+as $$
+    with recursive path as (
+        select
+            f.folder_id,
+            f.parent_folder_id,
+            f.name,
+            f.trashed_ts,
+            0 as depth
+        from files.folders f
+        where
+            f.tenant_id = p_tenant_id
+            and f.folder_id = p_folder_id
+            and f.active
+        union all
+        select
+            p.folder_id,
+            p.parent_folder_id,
+            p.name,
+            p.trashed_ts,
+            c.depth + 1
+        from files.folders p
+            join path c on p.folder_id = c.parent_folder_id
+    )
+    select
+        p.folder_id,
+        p.parent_folder_id,
+        p.name,
+        p.trashed_ts
+    from path p
+    order by p.depth desc;
+$$;
+
+
+drop function files.folder_list_files(uuid);
+
+create function files.folder_list_files(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest,
+        f.version
+    from files.files f
+    where f.tenant_id = p_tenant_id
+        and f.active
+        and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
@@ -0,0 +1,712 @@
+drop procedure files.folder_grant_remove(uuid, uuid, uuid, uuid, uuid);
+drop procedure files.folder_grant_save(uuid, uuid, uuid, uuid, uuid, text);
+drop function files.folder_grants_fetch(uuid, uuid, uuid);
+
+drop function files.file_get(uuid, uuid, uuid);
+drop function files.file_delete(uuid, uuid, uuid);
+drop function files.file_version_get(uuid, uuid, uuid, int);
+drop function files.file_versions_fetch(uuid, uuid, uuid);
+drop function files.file_version_restore(uuid, uuid, uuid, int);
+drop function files.file_version_add(uuid, uuid, uuid, text, bigint, text);
+drop procedure files.file_add(uuid, uuid, uuid, uuid, text, text, bigint, text);
+
+drop function files.folder_list_files(uuid, uuid, uuid);
+drop function files.folder_path(uuid, uuid, uuid);
+drop function files.folder_trash_fetch(uuid, uuid);
+drop function files.folder_list_folders(uuid, uuid, uuid);
+drop function files.folder_get(uuid, uuid, uuid);
+drop procedure files.folder_restore(uuid, uuid, uuid);
+drop procedure files.folder_trash(uuid, uuid, uuid);
+drop procedure files.folder_move(uuid, uuid, uuid, uuid);
+drop procedure files.folder_rename(uuid, uuid, uuid, text);
+drop procedure files.folder_add(uuid, uuid, uuid, uuid, text);
+
+drop procedure files.folder_require_parent(uuid, uuid, uuid);
+drop procedure files.file_require(uuid, uuid, uuid, text);
+drop procedure files.folder_require(uuid, uuid, uuid, text);
+drop function files.file_access(uuid, uuid, uuid);
+drop function files.folder_access(uuid, uuid, uuid);
+drop function files.has_all_access(uuid, uuid);
+drop function files.access_level(text);
+
+-- the nil uuid as parent places the folder in the root
+create procedure files.folder_add(
+    p_tenant_id uuid,
+    p_folder_id uuid,
+    p_parent_folder_id uuid,
+    p_name text
+)
+language plpgsql
+as $$
+declare
+    v_parent_folder_id uuid := nullif(p_parent_folder_id, '00000000-0000-0000-0000-000000000000'::uuid);
+begin
+    if trim(p_name) = '' then
+        raise exception 'folder name is required' using errcode = '23502', column = 'name';
+    end if;
+
+    if v_parent_folder_id is not null and not exists (
+        select 1
+        from files.folders p
+        where
+            p.tenant_id = p_tenant_id
+            and p.folder_id = v_parent_folder_id
+            and p.active
+    ) then
+        raise exception 'no folder %', v_parent_folder_id using errcode = '23503', column = 'parent_folder_id';
+    end if;
+
+    insert into files.folders (
+        folder_id,
+        tenant_id,
+        parent_folder_id,
+        name
+    ) values (
+        p_folder_id,
+        p_tenant_id,
+        v_parent_folder_id,
+        trim(p_name)
+    );
+end;
+$$;
+
+create procedure files.folder_rename(
+    p_tenant_id uuid,
+    p_folder_id uuid,
+    p_name text
+)
+language plpgsql
+as $$
+begin
+    if trim(p_name) = '' then
+        raise exception 'folder name is required' using errcode = '23502', column = 'name';
+    end if;
+
+    update files.folders f set
+        name = trim(p_name),
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.active;
+
+    if not found then
+        raise exception 'no folder %', p_folder_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+-- the nil uuid as parent moves the folder to the root; moving a folder
+-- below itself is rejected
+create procedure files.folder_move(
+    p_tenant_id uuid,
+    p_folder_id uuid,
+    p_parent_folder_id uuid
+)
+language plpgsql
+as $$
+declare
+    v_parent_folder_id uuid := nullif(p_parent_folder_id, '00000000-0000-0000-0000-000000000000'::uuid);
+begin
+    -- concurrent moves could form a cycle that neither of them sees
+    perform pg_advisory_xact_lock(hashtextextended('files.folders:' || p_tenant_id::text, 0));
+
+    if v_parent_folder_id is not null then
+        if not exists (
+            select 1
+            from files.folders p
+            where
+                p.tenant_id = p_tenant_id
+                and p.folder_id = v_parent_folder_id
+                and p.active
+        ) then
+            raise exception 'no folder %', v_parent_folder_id using errcode = '23503', column = 'parent_folder_id';
+        end if;
+
+        if exists (
+            with recursive ancestors as (
+                select
+                    p.folder_id,
+                    p.parent_folder_id
+                from files.folders p
+                where p.folder_id = v_parent_folder_id
+                union all
+                select
+                    p.folder_id,
+                    p.parent_folder_id
+                from files.folders p
+                    join ancestors a on p.folder_id = a.parent_folder_id
+            )
+            select 1
+            from ancestors a
+            where a.folder_id = p_folder_id
+        ) then
+            raise exception 'a folder cannot be moved into itself or one of its subfolders' using errcode = '22023', column = 'parent_folder_id';
+        end if;
+    end if;
+
+    update files.folders f set
+        parent_folder_id = v_parent_folder_id,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.active;
+
+    if not found then
+        raise exception 'no folder %', p_folder_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+-- moves the folder, its subfolders and their files to the trash
+create procedure files.folder_trash(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+language plpgsql
+as $$
+begin
+    with recursive tree as (
+        select f.folder_id
+        from files.folders f
+        where
+            f.tenant_id = p_tenant_id
+            and f.folder_id = p_folder_id
+            and f.active
+        union all
+        select c.folder_id
+        from files.folders c
+            join tree t on c.parent_folder_id = t.folder_id
+        where c.active
+    )
+    update files.folders f set
+        active = false,
+        trashed_ts = now(),
+        trashed_with = p_folder_id,
+        updated_ts = now()
+    from tree t
+    where f.folder_id = t.folder_id;
+
+    if not found then
+        raise exception 'no folder %', p_folder_id using errcode = 'P0002';
+    end if;
+
+    update files.files f set
+        active = false,
+        trashed_ts = now(),
+        trashed_with = p_folder_id,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.active
+        and f.folder_id in (
+            select t.folder_id
+            from files.folders t
+            where
+                t.tenant_id = p_tenant_id
+                and t.trashed_with = p_folder_id
+        );
+end;
+$$;
+
+-- brings back what was trashed with the folder, the folder moves to the
+-- root when its former parent is gone
+create procedure files.folder_restore(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+language plpgsql
+as $$
+declare
+    v_parent_folder_id uuid;
+begin
+    select f.parent_folder_id into v_parent_folder_id
+    from files.folders f
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.trashed_with = p_folder_id;
+
+    if not found then
+        raise exception 'no folder % in the trash', p_folder_id using errcode = 'P0002';
+    end if;
+
+    if v_parent_folder_id is not null and not exists (
+        select 1
+        from files.folders p
+        where
+            p.folder_id = v_parent_folder_id
+            and p.active
+    ) then
+        update files.folders f set
+            parent_folder_id = null
+        where f.folder_id = p_folder_id;
+    end if;
+
+    update files.folders f set
+        active = true,
+        trashed_ts = null,
+        trashed_with = null,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.trashed_with = p_folder_id;
+
+    update files.files f set
+        active = true,
+        trashed_ts = null,
+        trashed_with = null,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.trashed_with = p_folder_id;
+end;
+$$;
+
+create function files.folder_get(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.parent_folder_id,
+        f.name,
+        f.trashed_ts
+    from files.folders f
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.active;
+$$;
+
+create function files.folder_list_folders(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.parent_folder_id,
+        f.name,
+        f.trashed_ts
+    from files.folders f
+    where f.tenant_id = p_tenant_id
+        and f.active
+        and f.parent_folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+-- folders deleted by the user, without the subfolders trashed with them
+create function files.folder_trash_fetch(
+    p_tenant_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.parent_folder_id,
+        f.name,
+        f.trashed_ts
+    from files.folders f
+    where f.tenant_id = p_tenant_id
+        and f.trashed_with = f.folder_id
+    order by f.trashed_ts desc;
+$$;
+
+-- the folder and its ancestors, starting at the root
+create function files.folder_path(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language sql
+stable
+as $$
+    with recursive path as (
+        select
+            f.folder_id,
+            f.parent_folder_id,
+            f.name,
+            f.trashed_ts,
+            0 as depth
+        from files.folders f
+        where
+            f.tenant_id = p_tenant_id
+            and f.folder_id = p_folder_id
+            and f.active
+        union all
+        select
+            p.folder_id,
+            p.parent_folder_id,
+            p.name,
+            p.trashed_ts,
+            c.depth + 1
+        from files.folders p
+            join path c on p.folder_id = c.parent_folder_id
+    )
+    select
+        p.folder_id,
+        p.parent_folder_id,
+        p.name,
+        p.trashed_ts
+    from path p
+    order by p.depth desc;
+$$;
+
+create function files.folder_list_files(
+    p_tenant_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest,
+        f.version
+    from files.files f
+    where f.tenant_id = p_tenant_id
+        and f.active
+        and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+    order by f.name;
+$$;
+
+-- the nil uuid as folder places the file in the root
+create procedure files.file_add(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+language sql
+as $$
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    insert into files.files (
+        file_id,
+        tenant_id,
+        folder_id,
+        name,
+        content_type,
+        size,
+        digest,
+        version
+    ) values (
+        p_file_id,
+        p_tenant_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_name,
+        p_content_type,
+        p_size,
+        p_digest,
+        1
+    );
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        1,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+$$;
+
+-- adds the content as the next version of the file and makes it current,
+-- returns the new version number
+create function files.file_version_add(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+returns int
+language plpgsql
+as $$
+declare
+    v_version int;
+begin
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    update files.files f set
+        version = f.version + 1,
+        content_type = p_content_type,
+        size = p_size,
+        digest = p_digest,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active
+    returning f.version into v_version;
+
+    if not found then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        v_version,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+
+    return v_version;
+end;
+$$;
+
+-- restoring adds the content of an older version as new version, the
+-- history is kept
+create function files.file_version_restore(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_version int
+)
+returns int
+language plpgsql
+as $$
+declare
+    r files.file_versions%rowtype;
+begin
+    select v.* into r
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id
+        and v.version = p_version;
+
+    if not found then
+        raise exception 'no version % of file %', p_version, p_file_id using errcode = 'P0002';
+    end if;
+
+    return files.file_version_add(p_tenant_id, p_file_id, r.content_type, r.size, r.digest);
+end;
+$$;
+
+create function files.file_versions_fetch(
+    p_tenant_id uuid,
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    version int,
+    created_ts timestamptz,
+    content_type text,
+    size bigint,
+    digest text
+)
+language sql
+stable
+as $$
+    select
+        v.file_id,
+        v.version,
+        v.created_ts,
+        v.content_type,
+        v.size,
+        v.digest
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id
+    order by v.version desc;
+$$;
+
+create function files.file_version_get(
+    p_tenant_id uuid,
+    p_file_id uuid,
+    p_version int
+)
+returns table (
+    file_id uuid,
+    version int,
+    created_ts timestamptz,
+    content_type text,
+    size bigint,
+    digest text
+)
+language sql
+stable
+as $$
+    select
+        v.file_id,
+        v.version,
+        v.created_ts,
+        v.content_type,
+        v.size,
+        v.digest
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id
+        and v.version = p_version;
+$$;
+
+-- removes the file with all its versions, returns the digests of content
+-- no other version refers to any more
+create function files.file_delete(
+    p_tenant_id uuid,
+    p_file_id uuid
+)
+returns setof text
+language plpgsql
+as $$
+declare
+    v_digests text[];
+begin
+    select array_agg(v.digest) into v_digests
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id;
+
+    delete from files.files f
+    where
+        f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id;
+
+    if not found then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+
+    update files.blobs b set
+        ref_count = b.ref_count - x.refs
+    from (
+        select d.digest, count(*) as refs
+        from unnest(v_digests) as d (digest)
+        group by d.digest
+    ) x
+    where
+        b.tenant_id = p_tenant_id
+        and b.digest = x.digest;
+
+    return query
+        delete from files.blobs b
+        where
+            b.tenant_id = p_tenant_id
+            and b.digest = any(v_digests)
+            and b.ref_count = 0
+        returning b.digest;
+end;
+$$;
+
+create function files.file_get(
+    p_tenant_id uuid,
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest,
+        f.version
+    from files.files f
+    where f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active;
+$$;
+
+drop table files.folder_grants;
+
+alter table files.files
+    drop column owner_id;
+
+alter table files.folders
+    drop column owner_id;
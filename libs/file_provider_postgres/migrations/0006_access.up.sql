@@ -0,0 +1,1155 @@
+-- access control: a folder belongs to the user who created it and can be
+-- shared with roles and users of the tenant as read, write or manage;
+-- grants are inherited by subfolders and the files in them. Files in the
+-- root belong to the user who uploaded them. Holders of `files.folders.all`
+-- can access everything of the tenant, folders created before access
+-- control have no owner and are only reachable that way until shared
+
+alter table files.folders
+    add column owner_id uuid;
+
+alter table files.files
+    add column owner_id uuid;
+
+create table files.folder_grants (
+    folder_id uuid not null,
+    tenant_id uuid not null,
+    role_id uuid,
+    user_id uuid,
+    level text not null,
+    created_ts timestamptz not null default now(),
+    constraint fk_folder_grants_folder foreign key (folder_id) references files.folders (folder_id) on delete cascade,
+    constraint fk_folder_grants_role foreign key (role_id) references tenants.roles (role_id) on delete cascade,
+    constraint fk_folder_grants_user foreign key (user_id) references users.users (user_id) on delete cascade,
+    constraint c_folder_grants_grantee check ((role_id is null) <> (user_id is null)),
+    constraint c_folder_grants_level check (level in ('read', 'write', 'manage'))
+);
+
+create unique index u_folder_grants_role on files.folder_grants (folder_id, role_id) where role_id is not null;
+create unique index u_folder_grants_user on files.folder_grants (folder_id, user_id) where user_id is not null;
+create index i_folder_grants_user on files.folder_grants (tenant_id, user_id);
+
+
+create function files.access_level(
+    p_level text
+)
+returns int
+language sql
+immutable
+as $$
+    select case p_level
+        when 'read' then 1
+        when 'write' then 2
+        when 'manage' then 3
+        else 0
+    end;
+$$;
+
+create function files.has_all_access(
+    p_tenant_id uuid,
+    p_user_id uuid
+)
+returns boolean
+language sql
+stable
+as $$
+    select exists (
+        select 1
+        from tenants.tenant_user_fetch_permissions(p_user_id, p_tenant_id) p
+        where p.name = 'files.folders.all'
+    );
+$$;
+
+-- access of the user to the folder as returned by files.access_level, 0
+-- when the user cannot see the folder; everyone may add to the root
+create function files.folder_access(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+returns int
+language sql
+stable
+as $$
+    with recursive ancestors as (
+        select
+            f.folder_id,
+            f.parent_folder_id,
+            f.owner_id
+        from files.folders f
+        where
+            f.tenant_id = p_tenant_id
+            and f.folder_id = p_folder_id
+        union all
+        select
+            p.folder_id,
+            p.parent_folder_id,
+            p.owner_id
+        from files.folders p
+            join ancestors a on p.folder_id = a.parent_folder_id
+    ),
+    levels as (
+        select 3 as level
+        from ancestors a
+        where a.owner_id = p_user_id
+        union all
+        select files.access_level(g.level)
+        from files.folder_grants g
+            join ancestors a on a.folder_id = g.folder_id
+        where
+            g.user_id = p_user_id
+            or g.role_id in (
+                select ru.role_id
+                from tenants.role_users ru
+                    join tenants.roles r on r.role_id = ru.role_id
+                where
+                    ru.user_id = p_user_id
+                    and r.tenant_id = p_tenant_id
+                    and ru.active
+                    and r.active
+            )
+        union all
+        select 3
+        where
+            exists (select 1 from ancestors)
+            and files.has_all_access(p_tenant_id, p_user_id)
+        union all
+        select 2
+        where p_folder_id = '00000000-0000-0000-0000-000000000000'::uuid
+    )
+    select coalesce(max(l.level), 0)
+    from levels l;
+$$;
+
+-- files inherit the access to their folder, the user who uploaded a file
+-- may manage it
+create function files.file_access(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid
+)
+returns int
+language sql
+stable
+as $$
+    select coalesce((
+        select
+            case
+                when f.owner_id = p_user_id then 3
+                when files.has_all_access(p_tenant_id, p_user_id) then 3
+                when f.folder_id is null then 0
+                else files.folder_access(p_tenant_id, p_user_id, f.folder_id)
+            end
+        from files.files f
+        where
+            f.tenant_id = p_tenant_id
+            and f.file_id = p_file_id
+    ), 0);
+$$;
+
+-- folders and files the user cannot see are not found, too little access
+-- is an insufficient privilege
+create procedure files.folder_require(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid,
+    p_level text
+)
+language plpgsql
+as $$
+declare
+    v_access int := files.folder_access(p_tenant_id, p_user_id, p_folder_id);
+begin
+    if v_access = 0 then
+        raise exception 'no folder %', p_folder_id using errcode = 'P0002';
+    end if;
+    if v_access < files.access_level(p_level) then
+        raise exception '% access to folder % required', p_level, p_folder_id using errcode = '42501';
+    end if;
+end;
+$$;
+
+create procedure files.file_require(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_level text
+)
+language plpgsql
+as $$
+declare
+    v_access int := files.file_access(p_tenant_id, p_user_id, p_file_id);
+begin
+    if v_access = 0 then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+    if v_access < files.access_level(p_level) then
+        raise exception '% access to file % required', p_level, p_file_id using errcode = '42501';
+    end if;
+end;
+$$;
+
+-- a parent has to be an active folder the user may write to, parents the
+-- user cannot see are reported as missing
+create procedure files.folder_require_parent(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_parent_folder_id uuid
+)
+language plpgsql
+as $$
+declare
+    v_access int;
+begin
+    if p_parent_folder_id is null then
+        return;
+    end if;
+
+    v_access := files.folder_access(p_tenant_id, p_user_id, p_parent_folder_id);
+    if v_access = 0 or not exists (
+        select 1
+        from files.folders p
+        where
+            p.tenant_id = p_tenant_id
+            and p.folder_id = p_parent_folder_id
+            and p.active
+    ) then
+        raise exception 'no folder %', p_parent_folder_id using errcode = '23503', column = 'parent_folder_id';
+    end if;
+    if v_access < files.access_level('write') then
+        raise exception 'write access to folder % required', p_parent_folder_id using errcode = '42501';
+    end if;
+end;
+$$;
+
+
+drop procedure files.folder_add(uuid, uuid, uuid, text);
+
+-- the nil uuid as parent places the folder in the root
+create procedure files.folder_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid,
+    p_parent_folder_id uuid,
+    p_name text
+)
+language plpgsql
+as $$
+declare
+    v_parent_folder_id uuid := nullif(p_parent_folder_id, '00000000-0000-0000-0000-000000000000'::uuid);
+begin
+    if trim(p_name) = '' then
+        raise exception 'folder name is required' using errcode = '23502', column = 'name';
+    end if;
+
+    call files.folder_require_parent(p_tenant_id, p_user_id, v_parent_folder_id);
+
+    insert into files.folders (
+        folder_id,
+        tenant_id,
+        parent_folder_id,
+        owner_id,
+        name
+    ) values (
+        p_folder_id,
+        p_tenant_id,
+        v_parent_folder_id,
+        p_user_id,
+        trim(p_name)
+    );
+end;
+$$;
+
+drop procedure files.folder_rename(uuid, uuid, text);
+
+create procedure files.folder_rename(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid,
+    p_name text
+)
+language plpgsql
+as $$
+begin
+    if trim(p_name) = '' then
+        raise exception 'folder name is required' using errcode = '23502', column = 'name';
+    end if;
+
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'write');
+
+    update files.folders f set
+        name = trim(p_name),
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.active;
+
+    if not found then
+        raise exception 'no folder %', p_folder_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+drop procedure files.folder_move(uuid, uuid, uuid);
+
+-- the nil uuid as parent moves the folder to the root; moving a folder
+-- below itself is rejected
+create procedure files.folder_move(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid,
+    p_parent_folder_id uuid
+)
+language plpgsql
+as $$
+declare
+    v_parent_folder_id uuid := nullif(p_parent_folder_id, '00000000-0000-0000-0000-000000000000'::uuid);
+begin
+    -- concurrent moves could form a cycle that neither of them sees
+    perform pg_advisory_xact_lock(hashtextextended('files.folders:' || p_tenant_id::text, 0));
+
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'manage');
+    call files.folder_require_parent(p_tenant_id, p_user_id, v_parent_folder_id);
+
+    if v_parent_folder_id is not null and exists (
+        with recursive ancestors as (
+            select
+                p.folder_id,
+                p.parent_folder_id
+            from files.folders p
+            where p.folder_id = v_parent_folder_id
+            union all
+            select
+                p.folder_id,
+                p.parent_folder_id
+            from files.folders p
+                join ancestors a on p.folder_id = a.parent_folder_id
+        )
+        select 1
+        from ancestors a
+        where a.folder_id = p_folder_id
+    ) then
+        raise exception 'a folder cannot be moved into itself or one of its subfolders' using errcode = '22023', column = 'parent_folder_id';
+    end if;
+
+    update files.folders f set
+        parent_folder_id = v_parent_folder_id,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.active;
+
+    if not found then
+        raise exception 'no folder %', p_folder_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+drop procedure files.folder_trash(uuid, uuid);
+
+-- moves the folder, its subfolders and their files to the trash
+create procedure files.folder_trash(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'manage');
+
+    with recursive tree as (
+        select f.folder_id
+        from files.folders f
+        where
+            f.tenant_id = p_tenant_id
+            and f.folder_id = p_folder_id
+            and f.active
+        union all
+        select c.folder_id
+        from files.folders c
+            join tree t on c.parent_folder_id = t.folder_id
+        where c.active
+    )
+    update files.folders f set
+        active = false,
+        trashed_ts = now(),
+        trashed_with = p_folder_id,
+        updated_ts = now()
+    from tree t
+    where f.folder_id = t.folder_id;
+
+    if not found then
+        raise exception 'no folder %', p_folder_id using errcode = 'P0002';
+    end if;
+
+    update files.files f set
+        active = false,
+        trashed_ts = now(),
+        trashed_with = p_folder_id,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.active
+        and f.folder_id in (
+            select t.folder_id
+            from files.folders t
+            where
+                t.tenant_id = p_tenant_id
+                and t.trashed_with = p_folder_id
+        );
+end;
+$$;
+
+drop procedure files.folder_restore(uuid, uuid);
+
+-- brings back what was trashed with the folder, the folder moves to the
+-- root when its former parent is gone
+create procedure files.folder_restore(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+language plpgsql
+as $$
+declare
+    v_parent_folder_id uuid;
+begin
+    select f.parent_folder_id into v_parent_folder_id
+    from files.folders f
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.trashed_with = p_folder_id;
+
+    if not found then
+        raise exception 'no folder % in the trash', p_folder_id using errcode = 'P0002';
+    end if;
+
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'manage');
+
+    if v_parent_folder_id is not null and not exists (
+        select 1
+        from files.folders p
+        where
+            p.folder_id = v_parent_folder_id
+            and p.active
+    ) then
+        update files.folders f set
+            parent_folder_id = null
+        where f.folder_id = p_folder_id;
+    end if;
+
+    update files.folders f set
+        active = true,
+        trashed_ts = null,
+        trashed_with = null,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.trashed_with = p_folder_id;
+
+    update files.files f set
+        active = true,
+        trashed_ts = null,
+        trashed_with = null,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.trashed_with = p_folder_id;
+end;
+$$;
+
+
+drop function files.folder_get(uuid, uuid);
+
+create function files.folder_get(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    owner_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.parent_folder_id,
+        f.owner_id,
+        f.name,
+        f.trashed_ts
+    from files.folders f
+    where
+        f.tenant_id = p_tenant_id
+        and f.folder_id = p_folder_id
+        and f.active
+        and files.folder_access(p_tenant_id, p_user_id, f.folder_id) > 0;
+$$;
+
+drop function files.folder_list_folders(uuid, uuid);
+
+-- the root lists the top most folders the user can see: folders in the
+-- root and folders shared with the user below folders the user cannot see
+create function files.folder_list_folders(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    owner_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language plpgsql
+as $$
+begin
+    if p_folder_id = '00000000-0000-0000-0000-000000000000'::uuid then
+        return query
+            select
+                f.folder_id,
+                f.parent_folder_id,
+                f.owner_id,
+                f.name,
+                f.trashed_ts
+            from files.folders f
+            where
+                f.tenant_id = p_tenant_id
+                and f.active
+                and (
+                    (
+                        f.parent_folder_id is null
+                        and files.folder_access(p_tenant_id, p_user_id, f.folder_id) > 0
+                    )
+                    or (
+                        f.parent_folder_id is not null
+                        and (
+                            f.owner_id = p_user_id
+                            or exists (
+                                select 1
+                                from files.folder_grants g
+                                where g.folder_id = f.folder_id
+                            )
+                        )
+                        and files.folder_access(p_tenant_id, p_user_id, f.folder_id) > 0
+                        and files.folder_access(p_tenant_id, p_user_id, f.parent_folder_id) = 0
+                    )
+                )
+            order by f.name;
+        return;
+    end if;
+
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'read');
+
+    return query
+        select
+            f.folder_id,
+            f.parent_folder_id,
+            f.owner_id,
+            f.name,
+            f.trashed_ts
+        from files.folders f
+        where
+            f.tenant_id = p_tenant_id
+            and f.active
+            and f.parent_folder_id = p_folder_id
+        order by f.name;
+end;
+$$;
+
+drop function files.folder_trash_fetch(uuid);
+
+-- folders deleted by the user, without the subfolders trashed with them
+create function files.folder_trash_fetch(
+    p_tenant_id uuid,
+    p_user_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    owner_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language sql
+stable
+as $$
+    select
+        f.folder_id,
+        f.parent_folder_id,
+        f.owner_id,
+        f.name,
+        f.trashed_ts
+    from files.folders f
+    where f.tenant_id = p_tenant_id
+        and f.trashed_with = f.folder_id
+        and files.folder_access(p_tenant_id, p_user_id, f.folder_id) >= files.access_level('manage')
+    order by f.trashed_ts desc;
+$$;
+
+drop function files.folder_path(uuid, uuid);
+
+-- the folder and the ancestors the user can see, starting at the top most
+create function files.folder_path(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    parent_folder_id uuid,
+    owner_id uuid,
+    name text,
+    trashed_ts timestamptz
+)
+language sql
+stable
+as $$
+    with recursive path as (
+        select
+            f.folder_id,
+            f.parent_folder_id,
+            f.owner_id,
+            f.name,
+            f.trashed_ts,
+            0 as depth
+        from files.folders f
+        where
+            f.tenant_id = p_tenant_id
+            and f.folder_id = p_folder_id
+            and f.active
+        union all
+        select
+            p.folder_id,
+            p.parent_folder_id,
+            p.owner_id,
+            p.name,
+            p.trashed_ts,
+            c.depth + 1
+        from files.folders p
+            join path c on p.folder_id = c.parent_folder_id
+    )
+    select
+        p.folder_id,
+        p.parent_folder_id,
+        p.owner_id,
+        p.name,
+        p.trashed_ts
+    from path p
+    where files.folder_access(p_tenant_id, p_user_id, p.folder_id) > 0
+    order by p.depth desc;
+$$;
+
+drop function files.folder_list_files(uuid, uuid);
+
+-- files in the root are listed for the users who uploaded them
+create function files.folder_list_files(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'read');
+
+    return query
+        select
+            f.file_id,
+            f.name,
+            f.content_type,
+            f.size,
+            f.digest,
+            f.version
+        from files.files f
+        where f.tenant_id = p_tenant_id
+            and f.active
+            and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+            and (
+                f.folder_id is not null
+                or f.owner_id = p_user_id
+                or files.has_all_access(p_tenant_id, p_user_id)
+            )
+        order by f.name;
+end;
+$$;
+
+
+create function files.folder_grants_fetch(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    folder_id uuid,
+    role_id uuid,
+    user_id uuid,
+    level text
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'read');
+
+    return query
+        with recursive ancestors as (
+            select
+                f.folder_id,
+                f.parent_folder_id,
+                0 as depth
+            from files.folders f
+            where f.folder_id = p_folder_id
+            union all
+            select
+                p.folder_id,
+                p.parent_folder_id,
+                a.depth + 1
+            from files.folders p
+                join ancestors a on p.folder_id = a.parent_folder_id
+        )
+        select
+            g.folder_id,
+            g.role_id,
+            g.user_id,
+            g.level
+        from files.folder_grants g
+            join ancestors a on a.folder_id = g.folder_id
+        order by a.depth, g.created_ts;
+end;
+$$;
+
+-- grants the role or the user access to the folder and everything below,
+-- both have to belong to the tenant
+create procedure files.folder_grant_save(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid,
+    p_grantee_role_id uuid,
+    p_grantee_user_id uuid,
+    p_level text
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'manage');
+
+    if p_grantee_role_id is not null and not exists (
+        select 1
+        from tenants.roles r
+        where
+            r.tenant_id = p_tenant_id
+            and r.role_id = p_grantee_role_id
+    ) then
+        raise exception 'no role %', p_grantee_role_id using errcode = '23503', column = 'role_id';
+    end if;
+
+    if p_grantee_user_id is not null and not exists (
+        select 1
+        from tenants.tenant_users tu
+        where
+            tu.tenant_id = p_tenant_id
+            and tu.user_id = p_grantee_user_id
+            and tu.active
+    ) then
+        raise exception 'no user %', p_grantee_user_id using errcode = '23503', column = 'user_id';
+    end if;
+
+    if p_grantee_role_id is not null then
+        insert into files.folder_grants (
+            folder_id,
+            tenant_id,
+            role_id,
+            level
+        ) values (
+            p_folder_id,
+            p_tenant_id,
+            p_grantee_role_id,
+            p_level
+        )
+        on conflict (folder_id, role_id) where role_id is not null do update set
+            level = excluded.level;
+    else
+        insert into files.folder_grants (
+            folder_id,
+            tenant_id,
+            user_id,
+            level
+        ) values (
+            p_folder_id,
+            p_tenant_id,
+            p_grantee_user_id,
+            p_level
+        )
+        on conflict (folder_id, user_id) where user_id is not null do update set
+            level = excluded.level;
+    end if;
+end;
+$$;
+
+create procedure files.folder_grant_remove(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid,
+    p_grantee_role_id uuid,
+    p_grantee_user_id uuid
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'manage');
+
+    delete from files.folder_grants g
+    where
+        g.folder_id = p_folder_id
+        and g.role_id is not distinct from p_grantee_role_id
+        and g.user_id is not distinct from p_grantee_user_id;
+
+    if not found then
+        raise exception 'no grant on folder %', p_folder_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+
+drop procedure files.file_add(uuid, uuid, uuid, text, text, bigint, text);
+
+-- the nil uuid as folder places the file in the root
+create procedure files.file_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'write');
+
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    insert into files.files (
+        file_id,
+        tenant_id,
+        folder_id,
+        owner_id,
+        name,
+        content_type,
+        size,
+        digest,
+        version
+    ) values (
+        p_file_id,
+        p_tenant_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_user_id,
+        p_name,
+        p_content_type,
+        p_size,
+        p_digest,
+        1
+    );
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        1,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+end;
+$$;
+
+drop function files.file_version_restore(uuid, uuid, int);
+drop function files.file_version_add(uuid, uuid, text, bigint, text);
+
+-- adds the content as the next version of the file and makes it current,
+-- returns the new version number
+create function files.file_version_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+returns int
+language plpgsql
+as $$
+declare
+    v_version int;
+begin
+    call files.file_require(p_tenant_id, p_user_id, p_file_id, 'write');
+
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    update files.files f set
+        version = f.version + 1,
+        content_type = p_content_type,
+        size = p_size,
+        digest = p_digest,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active
+    returning f.version into v_version;
+
+    if not found then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        v_version,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+
+    return v_version;
+end;
+$$;
+
+-- restoring adds the content of an older version as new version, the
+-- history is kept
+create function files.file_version_restore(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_version int
+)
+returns int
+language plpgsql
+as $$
+declare
+    r files.file_versions%rowtype;
+begin
+    call files.file_require(p_tenant_id, p_user_id, p_file_id, 'write');
+
+    select v.* into r
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id
+        and v.version = p_version;
+
+    if not found then
+        raise exception 'no version % of file %', p_version, p_file_id using errcode = 'P0002';
+    end if;
+
+    return files.file_version_add(p_tenant_id, p_user_id, p_file_id, r.content_type, r.size, r.digest);
+end;
+$$;
+
+drop function files.file_versions_fetch(uuid, uuid);
+
+create function files.file_versions_fetch(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    version int,
+    created_ts timestamptz,
+    content_type text,
+    size bigint,
+    digest text
+)
+language sql
+stable
+as $$
+    select
+        v.file_id,
+        v.version,
+        v.created_ts,
+        v.content_type,
+        v.size,
+        v.digest
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id
+        and files.file_access(p_tenant_id, p_user_id, p_file_id) > 0
+    order by v.version desc;
+$$;
+
+drop function files.file_version_get(uuid, uuid, int);
+
+create function files.file_version_get(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_version int
+)
+returns table (
+    file_id uuid,
+    version int,
+    created_ts timestamptz,
+    content_type text,
+    size bigint,
+    digest text
+)
+language sql
+stable
+as $$
+    select
+        v.file_id,
+        v.version,
+        v.created_ts,
+        v.content_type,
+        v.size,
+        v.digest
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id
+        and v.version = p_version
+        and files.file_access(p_tenant_id, p_user_id, p_file_id) > 0;
+$$;
+
+drop function files.file_delete(uuid, uuid);
+
+-- removes the file with all its versions, returns the digests of content
+-- no other version refers to any more
+create function files.file_delete(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid
+)
+returns setof text
+language plpgsql
+as $$
+declare
+    v_digests text[];
+begin
+    call files.file_require(p_tenant_id, p_user_id, p_file_id, 'write');
+
+    select array_agg(v.digest) into v_digests
+    from files.file_versions v
+    where
+        v.tenant_id = p_tenant_id
+        and v.file_id = p_file_id;
+
+    delete from files.files f
+    where
+        f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id;
+
+    if not found then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+
+    update files.blobs b set
+        ref_count = b.ref_count - x.refs
+    from (
+        select d.digest, count(*) as refs
+        from unnest(v_digests) as d (digest)
+        group by d.digest
+    ) x
+    where
+        b.tenant_id = p_tenant_id
+        and b.digest = x.digest;
+
+    return query
+        delete from files.blobs b
+        where
+            b.tenant_id = p_tenant_id
+            and b.digest = any(v_digests)
+            and b.ref_count = 0
+        returning b.digest;
+end;
+$$;
+
+drop function files.file_get(uuid, uuid);
+
+create function files.file_get(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest,
+        f.version
+    from files.files f
+    where f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active
+        and files.file_access(p_tenant_id, p_user_id, p_file_id) > 0;
+$$;
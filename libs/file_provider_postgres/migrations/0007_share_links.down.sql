@@ -0,0 +1,12 @@
+drop function files.share_link_accesses_fetch(uuid, uuid, uuid);
+drop procedure files.share_link_access_add(uuid, uuid, uuid, text, text, text);
+drop function files.share_link_file_get(uuid, uuid, uuid);
+drop function files.share_link_files(uuid, uuid);
+drop function files.share_link_open(uuid, uuid, text);
+drop procedure files.share_link_revoke(uuid, uuid, uuid);
+drop function files.share_links_fetch(uuid, uuid, uuid, uuid);
+drop procedure files.share_link_add(uuid, uuid, uuid, uuid, uuid, timestamptz, text);
+drop procedure files.share_target_require(uuid, uuid, uuid, uuid);
+
+drop table files.share_link_accesses;
+drop table files.share_links;
@@ -0,0 +1,397 @@
+-- share links make a file or a folder with everything below it available
+-- without signing in until they expire or are revoked, optionally behind a
+-- password. The link is only referenced by a signed token, links stop
+-- working when the user who created them loses access to the target
+
+create table files.share_links (
+    link_id uuid not null,
+    tenant_id uuid not null,
+    file_id uuid,
+    folder_id uuid,
+    created_by uuid not null,
+    created_ts timestamptz not null default now(),
+    expires_ts timestamptz not null,
+    password_hash text,
+    revoked_ts timestamptz,
+    constraint pk_share_links primary key (link_id),
+    constraint fk_share_links_file foreign key (file_id) references files.files (file_id) on delete cascade,
+    constraint fk_share_links_folder foreign key (folder_id) references files.folders (folder_id) on delete cascade,
+    constraint c_share_links_target check ((file_id is null) <> (folder_id is null))
+);
+
+create index i_share_links_file on files.share_links (tenant_id, file_id);
+create index i_share_links_folder on files.share_links (tenant_id, folder_id);
+
+-- every request with a valid token, including the denied ones
+create table files.share_link_accesses (
+    link_id uuid not null,
+    tenant_id uuid not null,
+    accessed_ts timestamptz not null default now(),
+    file_id uuid,
+    outcome text not null,
+    remote_addr text,
+    user_agent text,
+    constraint fk_share_link_accesses_link foreign key (link_id) references files.share_links (link_id) on delete cascade,
+    constraint c_share_link_accesses_outcome check (outcome in ('listed', 'downloaded', 'denied'))
+);
+
+create index i_share_link_accesses_link on files.share_link_accesses (link_id, accessed_ts);
+
+
+-- managing the links of a target requires manage access to it
+create procedure files.share_target_require(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid
+)
+language plpgsql
+as $$
+begin
+    if (p_file_id is null) = (p_folder_id is null) then
+        raise exception 'either a file or a folder is shared' using errcode = '22023', column = 'target';
+    end if;
+
+    if p_file_id is not null then
+        call files.file_require(p_tenant_id, p_user_id, p_file_id, 'manage');
+    else
+        call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'manage');
+    end if;
+end;
+$$;
+
+create procedure files.share_link_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_link_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_expires_ts timestamptz,
+    p_password text
+)
+language plpgsql
+as $$
+begin
+    call files.share_target_require(p_tenant_id, p_user_id, p_file_id, p_folder_id);
+
+    if p_expires_ts <= now() then
+        raise exception 'share link expires in the past' using errcode = '22023', column = 'expires';
+    end if;
+
+    insert into files.share_links (
+        link_id,
+        tenant_id,
+        file_id,
+        folder_id,
+        created_by,
+        expires_ts,
+        password_hash
+    ) values (
+        p_link_id,
+        p_tenant_id,
+        p_file_id,
+        p_folder_id,
+        p_user_id,
+        p_expires_ts,
+        case
+            when coalesce(p_password, '') = '' then null
+            else public.crypt(p_password, public.gen_salt('bf'))
+        end
+    );
+end;
+$$;
+
+-- links of the file or the folder, newest first
+create function files.share_links_fetch(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    link_id uuid,
+    file_id uuid,
+    folder_id uuid,
+    created_by uuid,
+    created_ts timestamptz,
+    expires_ts timestamptz,
+    password_protected boolean,
+    revoked_ts timestamptz
+)
+language plpgsql
+as $$
+begin
+    call files.share_target_require(p_tenant_id, p_user_id, p_file_id, p_folder_id);
+
+    return query
+        select
+            l.link_id,
+            l.file_id,
+            l.folder_id,
+            l.created_by,
+            l.created_ts,
+            l.expires_ts,
+            l.password_hash is not null,
+            l.revoked_ts
+        from files.share_links l
+        where
+            l.tenant_id = p_tenant_id
+            and l.file_id is not distinct from p_file_id
+            and l.folder_id is not distinct from p_folder_id
+        order by l.created_ts desc;
+end;
+$$;
+
+create procedure files.share_link_revoke(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_link_id uuid
+)
+language plpgsql
+as $$
+declare
+    r files.share_links%rowtype;
+begin
+    select l.* into r
+    from files.share_links l
+    where
+        l.tenant_id = p_tenant_id
+        and l.link_id = p_link_id;
+
+    if not found then
+        raise exception 'no share link %', p_link_id using errcode = 'P0002';
+    end if;
+
+    call files.share_target_require(p_tenant_id, p_user_id, r.file_id, r.folder_id);
+
+    update files.share_links l set
+        revoked_ts = coalesce(l.revoked_ts, now())
+    where l.link_id = p_link_id;
+end;
+$$;
+
+-- a link that can be used: not revoked, not expired, its target is not
+-- trashed and its creator can still read it; `password_ok` is false when
+-- the link has a password and `p_password` does not match it
+create function files.share_link_open(
+    p_tenant_id uuid,
+    p_link_id uuid,
+    p_password text
+)
+returns table (
+    link_id uuid,
+    file_id uuid,
+    folder_id uuid,
+    created_by uuid,
+    created_ts timestamptz,
+    expires_ts timestamptz,
+    password_protected boolean,
+    revoked_ts timestamptz,
+    password_ok boolean
+)
+language plpgsql
+as $$
+begin
+    return query
+        select
+            l.link_id,
+            l.file_id,
+            l.folder_id,
+            l.created_by,
+            l.created_ts,
+            l.expires_ts,
+            l.password_hash is not null,
+            l.revoked_ts,
+            l.password_hash is null
+                or coalesce(l.password_hash = public.crypt(p_password, l.password_hash), false)
+        from files.share_links l
+        where
+            l.tenant_id = p_tenant_id
+            and l.link_id = p_link_id
+            and l.revoked_ts is null
+            and l.expires_ts > now()
+            and (
+                exists (select 1 from files.files f where f.file_id = l.file_id and f.active)
+                or exists (select 1 from files.folders f where f.folder_id = l.folder_id and f.active)
+            )
+            and case
+                when l.file_id is not null then files.file_access(p_tenant_id, l.created_by, l.file_id)
+                else files.folder_access(p_tenant_id, l.created_by, l.folder_id)
+            end > 0;
+
+    if not found then
+        raise exception 'no share link %', p_link_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+-- active files a link makes available with their path relative to the
+-- shared folder, the name alone for a shared file
+create function files.share_link_files(
+    p_tenant_id uuid,
+    p_link_id uuid
+)
+returns table (
+    path text,
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int
+)
+language sql
+stable
+as $$
+    with recursive link as (
+        select
+            l.file_id,
+            l.folder_id
+        from files.share_links l
+        where
+            l.tenant_id = p_tenant_id
+            and l.link_id = p_link_id
+    ),
+    folders as (
+        select
+            f.folder_id,
+            '' as path
+        from files.folders f
+            join link l on l.folder_id = f.folder_id
+        where f.active
+        union all
+        select
+            c.folder_id,
+            p.path || c.name || '/'
+        from files.folders c
+            join folders p on c.parent_folder_id = p.folder_id
+        where c.active
+    )
+    select
+        d.path || f.name,
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest,
+        f.version
+    from files.files f
+        join folders d on d.folder_id = f.folder_id
+    where f.active
+    union all
+    select
+        f.name,
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest,
+        f.version
+    from files.files f
+        join link l on l.file_id = f.file_id
+    where f.active
+    order by 1;
+$$;
+
+-- a file the link makes available, nothing for any other file
+create function files.share_link_file_get(
+    p_tenant_id uuid,
+    p_link_id uuid,
+    p_file_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int
+)
+language sql
+stable
+as $$
+    select
+        s.file_id,
+        s.name,
+        s.content_type,
+        s.size,
+        s.digest,
+        s.version
+    from files.share_link_files(p_tenant_id, p_link_id) s
+    where s.file_id = p_file_id;
+$$;
+
+-- accesses are only recorded for links that exist
+create procedure files.share_link_access_add(
+    p_tenant_id uuid,
+    p_link_id uuid,
+    p_file_id uuid,
+    p_outcome text,
+    p_remote_addr text,
+    p_user_agent text
+)
+language sql
+as $$
+    insert into files.share_link_accesses (
+        link_id,
+        tenant_id,
+        file_id,
+        outcome,
+        remote_addr,
+        user_agent
+    )
+    select
+        l.link_id,
+        l.tenant_id,
+        p_file_id,
+        p_outcome,
+        p_remote_addr,
+        left(p_user_agent, 512)
+    from files.share_links l
+    where
+        l.tenant_id = p_tenant_id
+        and l.link_id = p_link_id;
+$$;
+
+-- accesses of a link, newest first
+create function files.share_link_accesses_fetch(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_link_id uuid
+)
+returns table (
+    accessed_ts timestamptz,
+    file_id uuid,
+    outcome text,
+    remote_addr text,
+    user_agent text
+)
+language plpgsql
+as $$
+declare
+    r files.share_links%rowtype;
+begin
+    select l.* into r
+    from files.share_links l
+    where
+        l.tenant_id = p_tenant_id
+        and l.link_id = p_link_id;
+
+    if not found then
+        raise exception 'no share link %', p_link_id using errcode = 'P0002';
+    end if;
+
+    call files.share_target_require(p_tenant_id, p_user_id, r.file_id, r.folder_id);
+
+    return query
+        select
+            a.accessed_ts,
+            a.file_id,
+            a.outcome,
+            a.remote_addr,
+            a.user_agent
+        from files.share_link_accesses a
+        where a.link_id = p_link_id
+        order by a.accessed_ts desc;
+end;
+$$;
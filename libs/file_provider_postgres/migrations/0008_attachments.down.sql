@@ -0,0 +1,7 @@
+drop function files.attachment_count(uuid, uuid, text, uuid);
+drop function files.attachments_fetch(uuid, uuid, text, uuid);
+drop procedure files.attachment_remove(uuid, uuid, text, uuid, uuid);
+drop procedure files.attachment_add(uuid, uuid, text, uuid, uuid);
+drop function files.entity_exists(uuid, text, uuid);
+
+drop table files.attachments;
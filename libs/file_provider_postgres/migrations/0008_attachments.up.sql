@@ -0,0 +1,162 @@
+-- files attached to business records; the record is referenced by its type
+-- and id as records of different components cannot share a foreign key.
+-- Attachments are listed and counted for the files the user can see
+
+create table files.attachments (
+    tenant_id uuid not null,
+    entity_type text not null,
+    entity_id uuid not null,
+    file_id uuid not null,
+    created_by uuid,
+    created_ts timestamptz not null default now(),
+    constraint pk_attachments primary key (entity_type, entity_id, file_id),
+    constraint fk_attachments_file foreign key (file_id) references files.files (file_id) on delete cascade,
+    constraint c_attachments_entity_type check (entity_type in ('account', 'invoice', 'item', 'partner', 'purchase_order'))
+);
+
+create index i_attachments_file on files.attachments (file_id);
+
+
+create function files.entity_exists(
+    p_tenant_id uuid,
+    p_entity_type text,
+    p_entity_id uuid
+)
+returns boolean
+language sql
+stable
+as $$
+    select case p_entity_type
+        when 'account' then exists (
+            select 1 from acctg.accounts a where a.tenant_id = p_tenant_id and a.account_id = p_entity_id
+        )
+        when 'invoice' then exists (
+            select 1 from acctg.invoices i where i.tenant_id = p_tenant_id and i.invoice_id = p_entity_id
+        )
+        when 'item' then exists (
+            select 1 from mm.items i where i.tenant_id = p_tenant_id and i.item_id = p_entity_id
+        )
+        when 'partner' then exists (
+            select 1 from crm.partners p where p.tenant_id = p_tenant_id and p.partner_id = p_entity_id
+        )
+        when 'purchase_order' then exists (
+            select 1 from mm.purchase_orders po where po.tenant_id = p_tenant_id and po.po_id = p_entity_id
+        )
+        else false
+    end;
+$$;
+
+-- attaching requires read access to the file, attaching again is a no-op
+create procedure files.attachment_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_entity_type text,
+    p_entity_id uuid,
+    p_file_id uuid
+)
+language plpgsql
+as $$
+begin
+    call files.file_require(p_tenant_id, p_user_id, p_file_id, 'read');
+
+    if not files.entity_exists(p_tenant_id, p_entity_type, p_entity_id) then
+        raise exception 'no % %', p_entity_type, p_entity_id using errcode = '23503', column = 'entity_id';
+    end if;
+
+    insert into files.attachments (
+        tenant_id,
+        entity_type,
+        entity_id,
+        file_id,
+        created_by
+    ) values (
+        p_tenant_id,
+        p_entity_type,
+        p_entity_id,
+        p_file_id,
+        p_user_id
+    )
+    on conflict (entity_type, entity_id, file_id) do nothing;
+end;
+$$;
+
+-- detaching leaves the file as it is
+create procedure files.attachment_remove(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_entity_type text,
+    p_entity_id uuid,
+    p_file_id uuid
+)
+language plpgsql
+as $$
+begin
+    call files.file_require(p_tenant_id, p_user_id, p_file_id, 'read');
+
+    delete from files.attachments a
+    where
+        a.tenant_id = p_tenant_id
+        and a.entity_type = p_entity_type
+        and a.entity_id = p_entity_id
+        and a.file_id = p_file_id;
+
+    if not found then
+        raise exception 'file % is not attached to % %', p_file_id, p_entity_type, p_entity_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+-- active files attached to the record the user can see, in the order they
+-- were attached
+create function files.attachments_fetch(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_entity_type text,
+    p_entity_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int,
+    created_by uuid,
+    created_ts timestamptz
+)
+language sql
+stable
+as $$
+    select
+        f.file_id,
+        f.name,
+        f.content_type,
+        f.size,
+        f.digest,
+        f.version,
+        a.created_by,
+        a.created_ts
+    from files.attachments a
+        join files.files f on f.file_id = a.file_id
+    where
+        a.tenant_id = p_tenant_id
+        and a.entity_type = p_entity_type
+        and a.entity_id = p_entity_id
+        and f.active
+        and files.file_access(p_tenant_id, p_user_id, f.file_id) > 0
+    order by a.created_ts, f.name;
+$$;
+
+create function files.attachment_count(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_entity_type text,
+    p_entity_id uuid
+)
+returns bigint
+language sql
+stable
+as $$
+    select count(*)
+    from files.attachments_fetch(p_tenant_id, p_user_id, p_entity_type, p_entity_id);
+$$;
@@ -0,0 +1,4 @@
+drop function files.tenant_usage(uuid);
+drop procedure files.tenant_quota_save(uuid, bigint);
+
+drop table files.tenant_quotas;
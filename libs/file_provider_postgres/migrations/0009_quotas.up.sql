@@ -0,0 +1,82 @@
+-- storage used by a tenant is the size of its stored content, each content
+-- counted once however many files and versions refer to it. Tenants may be
+-- given a quota of their own, the others get the configured default
+
+create table files.tenant_quotas (
+    tenant_id uuid not null,
+    quota bigint not null,
+    updated_ts timestamptz not null default now(),
+    constraint pk_tenant_quotas primary key (tenant_id),
+    constraint fk_tenant_quotas_tenant foreign key (tenant_id) references tenants.tenants (tenant_id),
+    constraint c_tenant_quotas_quota check (quota >= 0)
+);
+
+
+-- a null quota removes the tenant's own quota
+create procedure files.tenant_quota_save(
+    p_tenant_id uuid,
+    p_quota bigint
+)
+language plpgsql
+as $$
+begin
+    if p_quota < 0 then
+        raise exception 'quota is negative' using errcode = '22023', column = 'quota';
+    end if;
+
+    if p_quota is null then
+        delete from files.tenant_quotas q
+        where q.tenant_id = p_tenant_id;
+        return;
+    end if;
+
+    insert into files.tenant_quotas (
+        tenant_id,
+        quota
+    ) values (
+        p_tenant_id,
+        p_quota
+    )
+    on conflict (tenant_id) do update set
+        quota = excluded.quota,
+        updated_ts = now();
+end;
+$$;
+
+-- bytes stored, number of files including trashed ones and the tenant's
+-- own quota, null when it has none; files uploaded before content was
+-- stored by digest count with their own size
+create function files.tenant_usage(
+    p_tenant_id uuid
+)
+returns table (
+    used bigint,
+    files bigint,
+    quota bigint
+)
+language sql
+stable
+as $$
+    select
+        (
+            select coalesce(sum(b.size), 0)
+            from files.blobs b
+            where b.tenant_id = p_tenant_id
+        )::bigint + (
+            select coalesce(sum(f.size), 0)
+            from files.files f
+            where
+                f.tenant_id = p_tenant_id
+                and f.digest is null
+        )::bigint,
+        (
+            select count(*)
+            from files.files f
+            where f.tenant_id = p_tenant_id
+        ),
+        (
+            select q.quota
+            from files.tenant_quotas q
+            where q.tenant_id = p_tenant_id
+        );
+$$;
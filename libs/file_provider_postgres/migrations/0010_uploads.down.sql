@@ -0,0 +1,7 @@
+drop function files.uploads_expire();
+drop procedure files.upload_remove(uuid, uuid, uuid);
+drop procedure files.upload_offset_save(uuid, uuid, uuid, bigint, bigint);
+drop function files.upload_get(uuid, uuid, uuid);
+drop procedure files.upload_add(uuid, uuid, uuid, uuid, uuid, text, text, bigint, timestamptz);
+
+drop table files.uploads;
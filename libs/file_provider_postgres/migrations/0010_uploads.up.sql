@@ -0,0 +1,175 @@
+-- resumable uploads of new files; the content is appended to a partial
+-- upload in the file store, the upload records how much of it was received
+-- and becomes a file once it is complete. Uploads are only visible to the
+-- user who started them and are removed when they expire
+
+create table files.uploads (
+    upload_id uuid not null,
+    tenant_id uuid not null,
+    created_by uuid not null,
+    created_ts timestamptz not null default now(),
+    expires_ts timestamptz not null,
+    file_id uuid not null,
+    folder_id uuid,
+    name text not null,
+    content_type text not null,
+    length bigint not null,
+    upload_offset bigint not null default 0,
+    constraint pk_uploads primary key (upload_id),
+    constraint fk_uploads_tenant foreign key (tenant_id) references tenants.tenants (tenant_id),
+    constraint u_uploads_file unique (file_id),
+    constraint c_uploads_length check (length >= 0),
+    constraint c_uploads_offset check (upload_offset between 0 and length)
+);
+
+create index i_uploads_expires on files.uploads (expires_ts);
+
+
+-- the file is added by the user to the folder, the nil uuid for the root,
+-- which requires write access to it
+create procedure files.upload_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_upload_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text,
+    p_content_type text,
+    p_length bigint,
+    p_expires_ts timestamptz
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'write');
+
+    if exists (select 1 from files.files f where f.file_id = p_file_id) then
+        raise exception 'file % already exists', p_file_id using errcode = '23505';
+    end if;
+
+    insert into files.uploads (
+        upload_id,
+        tenant_id,
+        created_by,
+        expires_ts,
+        file_id,
+        folder_id,
+        name,
+        content_type,
+        length
+    ) values (
+        p_upload_id,
+        p_tenant_id,
+        p_user_id,
+        p_expires_ts,
+        p_file_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_name,
+        p_content_type,
+        p_length
+    );
+end;
+$$;
+
+create function files.upload_get(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_upload_id uuid
+)
+returns table (
+    upload_id uuid,
+    file_id uuid,
+    folder_id uuid,
+    name text,
+    content_type text,
+    length bigint,
+    upload_offset bigint,
+    expires_ts timestamptz
+)
+language plpgsql
+as $$
+begin
+    return query
+        select
+            u.upload_id,
+            u.file_id,
+            coalesce(u.folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+            u.name,
+            u.content_type,
+            u.length,
+            u.upload_offset,
+            u.expires_ts
+        from files.uploads u
+        where
+            u.tenant_id = p_tenant_id
+            and u.created_by = p_user_id
+            and u.upload_id = p_upload_id
+            and u.expires_ts > now();
+
+    if not found then
+        raise exception 'no upload %', p_upload_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+-- moves the offset from where the append started to where it ended, an
+-- upload appended to in the meantime is a conflict
+create procedure files.upload_offset_save(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_upload_id uuid,
+    p_from bigint,
+    p_to bigint
+)
+language plpgsql
+as $$
+begin
+    perform 1 from files.upload_get(p_tenant_id, p_user_id, p_upload_id);
+
+    update files.uploads u set
+        upload_offset = p_to
+    where
+        u.upload_id = p_upload_id
+        and u.upload_offset = p_from;
+
+    if not found then
+        raise exception 'upload % moved on', p_upload_id using errcode = '40001';
+    end if;
+end;
+$$;
+
+create procedure files.upload_remove(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_upload_id uuid
+)
+language plpgsql
+as $$
+begin
+    delete from files.uploads u
+    where
+        u.tenant_id = p_tenant_id
+        and u.created_by = p_user_id
+        and u.upload_id = p_upload_id;
+
+    if not found then
+        raise exception 'no upload %', p_upload_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+-- removes the expired uploads of all tenants, the partial uploads they
+-- leave in the file store are discarded by the caller
+create function files.uploads_expire()
+returns table (
+    tenant_id uuid,
+    upload_id uuid
+)
+language sql
+as $$
+    delete from files.uploads u
+    where u.expires_ts <= now()
+    returning
+        u.tenant_id,
+        u.upload_id;
+$$;
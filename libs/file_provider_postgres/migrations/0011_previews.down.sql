@@ -0,0 +1,191 @@
+drop function files.folder_list_files(uuid, uuid, uuid);
+drop function files.file_version_add(uuid, uuid, uuid, text, bigint, text);
+drop procedure files.file_add(uuid, uuid, uuid, uuid, text, text, bigint, text);
+
+drop function files.file_preview_get(uuid, uuid, uuid);
+drop procedure files.preview_failed(uuid, text, text, timestamptz);
+drop procedure files.preview_ready(uuid, text, text);
+drop function files.previews_claim(int, int);
+drop procedure files.preview_request(uuid, text, text);
+drop function files.is_previewable(text);
+
+drop table files.previews;
+
+
+-- the nil uuid as folder places the file in the root
+create procedure files.file_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'write');
+
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    insert into files.files (
+        file_id,
+        tenant_id,
+        folder_id,
+        owner_id,
+        name,
+        content_type,
+        size,
+        digest,
+        version
+    ) values (
+        p_file_id,
+        p_tenant_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_user_id,
+        p_name,
+        p_content_type,
+        p_size,
+        p_digest,
+        1
+    );
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        1,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+end;
+$$;
+
+-- adds the content as the next version of the file and makes it current,
+-- returns the new version number
+create function files.file_version_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+returns int
+language plpgsql
+as $$
+declare
+    v_version int;
+begin
+    call files.file_require(p_tenant_id, p_user_id, p_file_id, 'write');
+
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    update files.files f set
+        version = f.version + 1,
+        content_type = p_content_type,
+        size = p_size,
+        digest = p_digest,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active
+    returning f.version into v_version;
+
+    if not found then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        v_version,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+
+    return v_version;
+end;
+$$;
+
+-- files in the root are listed for the users who uploaded them
+create function files.folder_list_files(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'read');
+
+    return query
+        select
+            f.file_id,
+            f.name,
+            f.content_type,
+            f.size,
+            f.digest,
+            f.version
+        from files.files f
+        where f.tenant_id = p_tenant_id
+            and f.active
+            and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+            and (
+                f.folder_id is not null
+                or f.owner_id = p_user_id
+                or files.has_all_access(p_tenant_id, p_user_id)
+            )
+        order by f.name;
+end;
+$$;
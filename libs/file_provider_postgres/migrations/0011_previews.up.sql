@@ -0,0 +1,365 @@
+-- previews are rendered per content by a background worker: adding content
+-- of a type a preview can be rendered for queues it, the worker claims
+-- pending previews, stores the rendered image next to the content and marks
+-- them ready, or failed once it runs out of attempts
+
+create table files.previews (
+    tenant_id uuid not null,
+    digest text not null,
+    source_type text not null,
+    state text not null default 'pending',
+    content_type text,
+    attempts int not null default 0,
+    next_attempt_ts timestamptz not null default now(),
+    last_error text,
+    updated_ts timestamptz not null default now(),
+    constraint pk_previews primary key (tenant_id, digest),
+    constraint fk_previews_blob foreign key (tenant_id, digest) references files.blobs (tenant_id, digest) on delete cascade,
+    constraint c_previews_state check (state in ('pending', 'ready', 'failed'))
+);
+
+create index i_previews_due on files.previews (next_attempt_ts) where state = 'pending';
+
+
+create function files.is_previewable(
+    p_content_type text
+)
+returns boolean
+language sql
+immutable
+as $$
+    select p_content_type like 'image/%' or p_content_type = 'application/pdf';
+$$;
+
+-- the preview is rendered from the content type the content was first
+-- added with
+create procedure files.preview_request(
+    p_tenant_id uuid,
+    p_digest text,
+    p_content_type text
+)
+language sql
+as $$
+    insert into files.previews (
+        tenant_id,
+        digest,
+        source_type
+    )
+    select
+        p_tenant_id,
+        p_digest,
+        p_content_type
+    where files.is_previewable(p_content_type)
+    on conflict (tenant_id, digest) do nothing;
+$$;
+
+-- content stored before previews existed
+insert into files.previews (
+    tenant_id,
+    digest,
+    source_type
+)
+select distinct on (v.tenant_id, v.digest)
+    v.tenant_id,
+    v.digest,
+    v.content_type
+from files.file_versions v
+where files.is_previewable(v.content_type)
+order by v.tenant_id, v.digest, v.created_ts;
+
+
+-- claimed previews are hidden from other workers for `p_lease` seconds
+create function files.previews_claim(
+    p_limit int,
+    p_lease int
+)
+returns table (
+    tenant_id uuid,
+    digest text,
+    source_type text,
+    attempts int
+)
+language sql
+as $$
+    update files.previews p set
+        next_attempt_ts = now() + make_interval(secs => p_lease)
+    where (p.tenant_id, p.digest) in (
+        select d.tenant_id, d.digest
+        from files.previews d
+        where
+            d.state = 'pending'
+            and d.next_attempt_ts <= now()
+        order by d.next_attempt_ts
+        limit p_limit
+        for update skip locked
+    )
+    returning
+        p.tenant_id,
+        p.digest,
+        p.source_type,
+        p.attempts;
+$$;
+
+create procedure files.preview_ready(
+    p_tenant_id uuid,
+    p_digest text,
+    p_content_type text
+)
+language sql
+as $$
+    update files.previews set
+        state = 'ready',
+        content_type = p_content_type,
+        attempts = attempts + 1,
+        last_error = null,
+        updated_ts = now()
+    where
+        tenant_id = p_tenant_id
+        and digest = p_digest;
+$$;
+
+-- a null `p_retry_at` gives up on the preview
+create procedure files.preview_failed(
+    p_tenant_id uuid,
+    p_digest text,
+    p_error text,
+    p_retry_at timestamptz
+)
+language sql
+as $$
+    update files.previews set
+        state = case when p_retry_at is null then 'failed' else 'pending' end,
+        attempts = attempts + 1,
+        next_attempt_ts = coalesce(p_retry_at, next_attempt_ts),
+        last_error = p_error,
+        updated_ts = now()
+    where
+        tenant_id = p_tenant_id
+        and digest = p_digest;
+$$;
+
+-- the preview of the current content of a file visible to the user, no
+-- row when the content has none
+create function files.file_preview_get(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid
+)
+returns table (
+    digest text,
+    state text,
+    content_type text,
+    updated_ts timestamptz
+)
+language plpgsql
+as $$
+begin
+    call files.file_require(p_tenant_id, p_user_id, p_file_id, 'read');
+
+    return query
+        select
+            p.digest,
+            p.state,
+            p.content_type,
+            p.updated_ts
+        from files.files f
+        join files.previews p on p.tenant_id = f.tenant_id and p.digest = f.digest
+        where
+            f.tenant_id = p_tenant_id
+            and f.file_id = p_file_id
+            and f.active;
+end;
+$$;
+
+
+drop procedure files.file_add(uuid, uuid, uuid, uuid, text, text, bigint, text);
+
+-- the nil uuid as folder places the file in the root
+create procedure files.file_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'write');
+
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    insert into files.files (
+        file_id,
+        tenant_id,
+        folder_id,
+        owner_id,
+        name,
+        content_type,
+        size,
+        digest,
+        version
+    ) values (
+        p_file_id,
+        p_tenant_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_user_id,
+        p_name,
+        p_content_type,
+        p_size,
+        p_digest,
+        1
+    );
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        1,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+
+    call files.preview_request(p_tenant_id, p_digest, p_content_type);
+end;
+$$;
+
+drop function files.file_version_add(uuid, uuid, uuid, text, bigint, text);
+
+-- adds the content as the next version of the file and makes it current,
+-- returns the new version number
+create function files.file_version_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+returns int
+language plpgsql
+as $$
+declare
+    v_version int;
+begin
+    call files.file_require(p_tenant_id, p_user_id, p_file_id, 'write');
+
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    update files.files f set
+        version = f.version + 1,
+        content_type = p_content_type,
+        size = p_size,
+        digest = p_digest,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active
+    returning f.version into v_version;
+
+    if not found then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        v_version,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+
+    call files.preview_request(p_tenant_id, p_digest, p_content_type);
+
+    return v_version;
+end;
+$$;
+
+drop function files.folder_list_files(uuid, uuid, uuid);
+
+-- files in the root are listed for the users who uploaded them, with the
+-- state of the preview of their content
+create function files.folder_list_files(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int,
+    preview text
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'read');
+
+    return query
+        select
+            f.file_id,
+            f.name,
+            f.content_type,
+            f.size,
+            f.digest,
+            f.version,
+            coalesce(p.state, 'none')
+        from files.files f
+        left join files.previews p on p.tenant_id = f.tenant_id and p.digest = f.digest
+        where f.tenant_id = p_tenant_id
+            and f.active
+            and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+            and (
+                f.folder_id is not null
+                or f.owner_id = p_user_id
+                or files.has_all_access(p_tenant_id, p_user_id)
+            )
+        order by f.name;
+end;
+$$;
@@ -0,0 +1,392 @@
+-- files are found by name and content: adding content text can be
+-- extracted from queues it like a preview, the search worker extracts the
+-- text and stores it with its search vector. Text is indexed with the
+-- `simple` configuration, tenants write in any language
+
+create table files.texts (
+    tenant_id uuid not null,
+    digest text not null,
+    source_type text not null,
+    state text not null default 'pending',
+    body text,
+    document tsvector,
+    attempts int not null default 0,
+    next_attempt_ts timestamptz not null default now(),
+    last_error text,
+    updated_ts timestamptz not null default now(),
+    constraint pk_texts primary key (tenant_id, digest),
+    constraint fk_texts_blob foreign key (tenant_id, digest) references files.blobs (tenant_id, digest) on delete cascade,
+    constraint c_texts_state check (state in ('pending', 'ready', 'failed'))
+);
+
+create index i_texts_due on files.texts (next_attempt_ts) where state = 'pending';
+create index i_texts_document on files.texts using gin (document);
+
+
+create function files.is_extractable(
+    p_content_type text
+)
+returns boolean
+language sql
+immutable
+as $$
+    select p_content_type like 'text/%'
+        or p_content_type in ('application/json', 'application/xml', 'application/pdf')
+        or p_content_type like 'application/vnd.openxmlformats-officedocument.wordprocessingml.%'
+        or p_content_type like 'application/vnd.openxmlformats-officedocument.spreadsheetml.%'
+        or p_content_type like 'application/vnd.openxmlformats-officedocument.presentationml.%'
+        or p_content_type like 'application/vnd.oasis.opendocument.%';
+$$;
+
+-- words of a file name, `q3_report.pdf` is found as `q3`, `report` and
+-- `pdf`
+create function files.name_document(
+    p_name text
+)
+returns tsvector
+language sql
+immutable
+as $$
+    select to_tsvector('simple', translate(p_name, '._-', '   '));
+$$;
+
+create procedure files.text_request(
+    p_tenant_id uuid,
+    p_digest text,
+    p_content_type text
+)
+language sql
+as $$
+    insert into files.texts (
+        tenant_id,
+        digest,
+        source_type
+    )
+    select
+        p_tenant_id,
+        p_digest,
+        p_content_type
+    where files.is_extractable(p_content_type)
+    on conflict (tenant_id, digest) do nothing;
+$$;
+
+-- content stored before search existed
+insert into files.texts (
+    tenant_id,
+    digest,
+    source_type
+)
+select distinct on (v.tenant_id, v.digest)
+    v.tenant_id,
+    v.digest,
+    v.content_type
+from files.file_versions v
+where files.is_extractable(v.content_type)
+order by v.tenant_id, v.digest, v.created_ts;
+
+
+-- claimed texts are hidden from other workers for `p_lease` seconds
+create function files.texts_claim(
+    p_limit int,
+    p_lease int
+)
+returns table (
+    tenant_id uuid,
+    digest text,
+    source_type text,
+    attempts int
+)
+language sql
+as $$
+    update files.texts t set
+        next_attempt_ts = now() + make_interval(secs => p_lease)
+    where (t.tenant_id, t.digest) in (
+        select d.tenant_id, d.digest
+        from files.texts d
+        where
+            d.state = 'pending'
+            and d.next_attempt_ts <= now()
+        order by d.next_attempt_ts
+        limit p_limit
+        for update skip locked
+    )
+    returning
+        t.tenant_id,
+        t.digest,
+        t.source_type,
+        t.attempts;
+$$;
+
+create procedure files.text_ready(
+    p_tenant_id uuid,
+    p_digest text,
+    p_body text
+)
+language sql
+as $$
+    update files.texts set
+        state = 'ready',
+        body = p_body,
+        document = to_tsvector('simple', p_body),
+        attempts = attempts + 1,
+        last_error = null,
+        updated_ts = now()
+    where
+        tenant_id = p_tenant_id
+        and digest = p_digest;
+$$;
+
+-- a null `p_retry_at` gives up on the text
+create procedure files.text_failed(
+    p_tenant_id uuid,
+    p_digest text,
+    p_error text,
+    p_retry_at timestamptz
+)
+language sql
+as $$
+    update files.texts set
+        state = case when p_retry_at is null then 'failed' else 'pending' end,
+        attempts = attempts + 1,
+        next_attempt_ts = coalesce(p_retry_at, next_attempt_ts),
+        last_error = p_error,
+        updated_ts = now()
+    where
+        tenant_id = p_tenant_id
+        and digest = p_digest;
+$$;
+
+-- files visible to the user whose name or text matches the web search
+-- style query, best matches first and matches in the name before those in
+-- the text; `path` are the names of the folders from the root down to the
+-- file's folder, as far as the user can see them, and `snippet` is the
+-- text around the matches, which are enclosed in chr(2) and chr(3)
+create function files.file_search(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_query text,
+    p_limit int,
+    p_offset int
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int,
+    folder_id uuid,
+    path text[],
+    rank real,
+    snippet text
+)
+language sql
+stable
+as $$
+    with query as (
+        select websearch_to_tsquery('simple', p_query) as q
+    ),
+    matches as (
+        select
+            f.file_id,
+            f.name,
+            f.content_type,
+            f.size,
+            f.digest,
+            f.version,
+            f.folder_id,
+            t.body,
+            ts_rank(
+                setweight(files.name_document(f.name), 'A') || coalesce(t.document, ''::tsvector),
+                query.q
+            ) as rank
+        from files.files f
+            cross join query
+            left join files.texts t on
+                t.tenant_id = f.tenant_id
+                and t.digest = f.digest
+                and t.state = 'ready'
+        where
+            f.tenant_id = p_tenant_id
+            and f.active
+            and (files.name_document(f.name) @@ query.q or t.document @@ query.q)
+            and files.file_access(p_tenant_id, p_user_id, f.file_id) > 0
+        order by rank desc, f.name, f.file_id
+        limit p_limit
+        offset p_offset
+    )
+    select
+        m.file_id,
+        m.name,
+        m.content_type,
+        m.size,
+        m.digest,
+        m.version,
+        m.folder_id,
+        coalesce((
+            select array_agg(p.name order by p.position)
+            from files.folder_path(p_tenant_id, p_user_id, m.folder_id)
+                with ordinality as p(folder_id, parent_folder_id, owner_id, name, trashed_ts, position)
+        ), '{}'),
+        m.rank,
+        case when m.body is not null then
+            ts_headline(
+                'simple',
+                m.body,
+                query.q,
+                'StartSel=' || chr(2) || ', StopSel=' || chr(3) || ', MaxWords=30, MinWords=12, MaxFragments=2, FragmentDelimiter=" … "'
+            )
+        end
+    from matches m
+        cross join query
+    order by m.rank desc, m.name, m.file_id;
+$$;
+
+
+drop procedure files.file_add(uuid, uuid, uuid, uuid, text, text, bigint, text);
+
+-- the nil uuid as folder places the file in the root
+create procedure files.file_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_name text,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'write');
+
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    insert into files.files (
+        file_id,
+        tenant_id,
+        folder_id,
+        owner_id,
+        name,
+        content_type,
+        size,
+        digest,
+        version
+    ) values (
+        p_file_id,
+        p_tenant_id,
+        nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid),
+        p_user_id,
+        p_name,
+        p_content_type,
+        p_size,
+        p_digest,
+        1
+    );
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        1,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+
+    call files.preview_request(p_tenant_id, p_digest, p_content_type);
+    call files.text_request(p_tenant_id, p_digest, p_content_type);
+end;
+$$;
+
+drop function files.file_version_add(uuid, uuid, uuid, text, bigint, text);
+
+-- adds the content as the next version of the file and makes it current,
+-- returns the new version number
+create function files.file_version_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid,
+    p_content_type text,
+    p_size bigint,
+    p_digest text
+)
+returns int
+language plpgsql
+as $$
+declare
+    v_version int;
+begin
+    call files.file_require(p_tenant_id, p_user_id, p_file_id, 'write');
+
+    insert into files.blobs (
+        tenant_id,
+        digest,
+        size,
+        ref_count
+    ) values (
+        p_tenant_id,
+        p_digest,
+        p_size,
+        1
+    )
+    on conflict (tenant_id, digest) do update set
+        ref_count = files.blobs.ref_count + 1;
+
+    update files.files f set
+        version = f.version + 1,
+        content_type = p_content_type,
+        size = p_size,
+        digest = p_digest,
+        updated_ts = now()
+    where
+        f.tenant_id = p_tenant_id
+        and f.file_id = p_file_id
+        and f.active
+    returning f.version into v_version;
+
+    if not found then
+        raise exception 'no file %', p_file_id using errcode = 'P0002';
+    end if;
+
+    insert into files.file_versions (
+        file_id,
+        version,
+        tenant_id,
+        content_type,
+        size,
+        digest
+    ) values (
+        p_file_id,
+        v_version,
+        p_tenant_id,
+        p_content_type,
+        p_size,
+        p_digest
+    );
+
+    call files.preview_request(p_tenant_id, p_digest, p_content_type);
+    call files.text_request(p_tenant_id, p_digest, p_content_type);
+
+    return v_version;
+end;
+$$;
@@ -0,0 +1,127 @@
+-- access control: everything without owner is reachable through
+-- `files.folders.all` only
+
+create or replace function files.folder_access(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+returns int
+language sql
+stable
+as $$
+    with recursive ancestors as (
+        select
+            f.folder_id,
+            f.parent_folder_id,
+            f.owner_id
+        from files.folders f
+        where
+            f.tenant_id = p_tenant_id
+            and f.folder_id = p_folder_id
+        union all
+        select
+            p.folder_id,
+            p.parent_folder_id,
+            p.owner_id
+        from files.folders p
+            join ancestors a on p.folder_id = a.parent_folder_id
+    ),
+    levels as (
+        select 3 as level
+        from ancestors a
+        where a.owner_id = p_user_id
+        union all
+        select files.access_level(g.level)
+        from files.folder_grants g
+            join ancestors a on a.folder_id = g.folder_id
+        where
+            g.user_id = p_user_id
+            or g.role_id in (
+                select ru.role_id
+                from tenants.role_users ru
+                    join tenants.roles r on r.role_id = ru.role_id
+                where
+                    ru.user_id = p_user_id
+                    and r.tenant_id = p_tenant_id
+                    and ru.active
+                    and r.active
+            )
+        union all
+        select 3
+        where
+            exists (select 1 from ancestors)
+            and files.has_all_access(p_tenant_id, p_user_id)
+        union all
+        select 2
+        where p_folder_id = '00000000-0000-0000-0000-000000000000'::uuid
+    )
+    select coalesce(max(l.level), 0)
+    from levels l;
+$$;
+
+create or replace function files.file_access(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_file_id uuid
+)
+returns int
+language sql
+stable
+as $$
+    select coalesce((
+        select
+            case
+                when f.owner_id = p_user_id then 3
+                when files.has_all_access(p_tenant_id, p_user_id) then 3
+                when f.folder_id is null then 0
+                else files.folder_access(p_tenant_id, p_user_id, f.folder_id)
+            end
+        from files.files f
+        where
+            f.tenant_id = p_tenant_id
+            and f.file_id = p_file_id
+    ), 0);
+$$;
+
+create or replace function files.folder_list_files(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_folder_id uuid
+)
+returns table (
+    file_id uuid,
+    name text,
+    content_type text,
+    size bigint,
+    digest text,
+    version int,
+    preview text
+)
+language plpgsql
+as $$
+begin
+    call files.folder_require(p_tenant_id, p_user_id, p_folder_id, 'read');
+
+    return query
+        select
+            f.file_id,
+            f.name,
+            f.content_type,
+            f.size,
+            f.digest,
+            f.version,
+            coalesce(p.state, 'none')
+        from files.files f
+        left join files.previews p on p.tenant_id = f.tenant_id and p.digest = f.digest
+        where f.tenant_id = p_tenant_id
+            and f.active
+            and f.folder_id is not distinct from nullif(p_folder_id, '00000000-0000-0000-0000-000000000000'::uuid)
+            and (
+                f.folder_id is not null
+                or f.owner_id = p_user_id
+                or files.has_all_access(p_tenant_id, p_user_id)
+            )
+        order by f.name;
+end;
+$$;
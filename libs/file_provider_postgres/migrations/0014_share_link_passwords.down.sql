@@ -0,0 +1,106 @@
+drop function files.share_link_password_legacy_verify(uuid, uuid, text);
+drop procedure files.share_link_password_rehash(uuid, uuid, text, text);
+drop procedure files.share_link_password_failed(uuid, uuid);
+drop function files.share_link_open(uuid, uuid);
+drop procedure files.share_link_add(uuid, uuid, uuid, uuid, uuid, timestamptz, text);
+
+alter table files.share_links
+    drop column failed_ts,
+    drop column failed_attempts;
+
+
+create procedure files.share_link_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_link_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_expires_ts timestamptz,
+    p_password text
+)
+language plpgsql
+as $$
+begin
+    call files.share_target_require(p_tenant_id, p_user_id, p_file_id, p_folder_id);
+
+    if p_expires_ts <= now() then
+        raise exception 'share link expires in the past' using errcode = '22023', column = 'expires';
+    end if;
+
+    insert into files.share_links (
+        link_id,
+        tenant_id,
+        file_id,
+        folder_id,
+        created_by,
+        expires_ts,
+        password_hash
+    ) values (
+        p_link_id,
+        p_tenant_id,
+        p_file_id,
+        p_folder_id,
+        p_user_id,
+        p_expires_ts,
+        case
+            when coalesce(p_password, '') = '' then null
+            else public.crypt(p_password, public.gen_salt('bf'))
+        end
+    );
+end;
+$$;
+
+-- a link that can be used: not revoked, not expired, its target is not
+-- trashed and its creator can still read it; `password_ok` is false when
+-- the link has a password and `p_password` does not match it
+create function files.share_link_open(
+    p_tenant_id uuid,
+    p_link_id uuid,
+    p_password text
+)
+returns table (
+    link_id uuid,
+    file_id uuid,
+    folder_id uuid,
+    created_by uuid,
+    created_ts timestamptz,
+    expires_ts timestamptz,
+    password_protected boolean,
+    revoked_ts timestamptz,
+    password_ok boolean
+)
+language plpgsql
+as $$
+begin
+    return query
+        select
+            l.link_id,
+            l.file_id,
+            l.folder_id,
+            l.created_by,
+            l.created_ts,
+            l.expires_ts,
+            l.password_hash is not null,
+            l.revoked_ts,
+            l.password_hash is null
+                or coalesce(l.password_hash = public.crypt(p_password, l.password_hash), false)
+        from files.share_links l
+        where
+            l.tenant_id = p_tenant_id
+            and l.link_id = p_link_id
+            and l.revoked_ts is null
+            and l.expires_ts > now()
+            and (
+                exists (select 1 from files.files f where f.file_id = l.file_id and f.active)
+                or exists (select 1 from files.folders f where f.folder_id = l.folder_id and f.active)
+            )
+            and case
+                when l.file_id is not null then files.file_access(p_tenant_id, l.created_by, l.file_id)
+                else files.folder_access(p_tenant_id, l.created_by, l.folder_id)
+            end > 0;
+
+    if not found then
+        raise exception 'no share link %', p_link_id using errcode = 'P0002';
+    end if;
+end;
+$$;
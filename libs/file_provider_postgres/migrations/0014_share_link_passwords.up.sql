@@ -0,0 +1,165 @@
+-- share link passwords are hashed and checked by the application like
+-- account passwords, they only reach the database once more to check a
+-- hash made by crypt() before, which is then replaced. Wrong passwords are
+-- counted per link: after five within 15 minutes of each other the link
+-- takes no password until 15 minutes after the last one
+
+alter table files.share_links
+    add column failed_attempts int not null default 0,
+    add column failed_ts timestamptz;
+
+
+drop procedure files.share_link_add(uuid, uuid, uuid, uuid, uuid, timestamptz, text);
+
+create procedure files.share_link_add(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_link_id uuid,
+    p_file_id uuid,
+    p_folder_id uuid,
+    p_expires_ts timestamptz,
+    p_password_hash text
+)
+language plpgsql
+as $$
+begin
+    call files.share_target_require(p_tenant_id, p_user_id, p_file_id, p_folder_id);
+
+    if p_expires_ts <= now() then
+        raise exception 'share link expires in the past' using errcode = '22023', column = 'expires';
+    end if;
+
+    insert into files.share_links (
+        link_id,
+        tenant_id,
+        file_id,
+        folder_id,
+        created_by,
+        expires_ts,
+        password_hash
+    ) values (
+        p_link_id,
+        p_tenant_id,
+        p_file_id,
+        p_folder_id,
+        p_user_id,
+        p_expires_ts,
+        p_password_hash
+    );
+end;
+$$;
+
+drop function files.share_link_open(uuid, uuid, text);
+
+-- a link that can be used: not revoked, not expired, its target is not
+-- trashed and its creator can still read it; `locked` while the link takes
+-- no password after too many wrong ones
+create function files.share_link_open(
+    p_tenant_id uuid,
+    p_link_id uuid
+)
+returns table (
+    link_id uuid,
+    file_id uuid,
+    folder_id uuid,
+    created_by uuid,
+    created_ts timestamptz,
+    expires_ts timestamptz,
+    password_protected boolean,
+    revoked_ts timestamptz,
+    password_hash text,
+    locked boolean
+)
+language plpgsql
+as $$
+begin
+    return query
+        select
+            l.link_id,
+            l.file_id,
+            l.folder_id,
+            l.created_by,
+            l.created_ts,
+            l.expires_ts,
+            l.password_hash is not null,
+            l.revoked_ts,
+            l.password_hash,
+            l.failed_attempts >= 5 and l.failed_ts > now() - interval '15 minutes'
+        from files.share_links l
+        where
+            l.tenant_id = p_tenant_id
+            and l.link_id = p_link_id
+            and l.revoked_ts is null
+            and l.expires_ts > now()
+            and (
+                exists (select 1 from files.files f where f.file_id = l.file_id and f.active)
+                or exists (select 1 from files.folders f where f.folder_id = l.folder_id and f.active)
+            )
+            and case
+                when l.file_id is not null then files.file_access(p_tenant_id, l.created_by, l.file_id)
+                else files.folder_access(p_tenant_id, l.created_by, l.folder_id)
+            end > 0;
+
+    if not found then
+        raise exception 'no share link %', p_link_id using errcode = 'P0002';
+    end if;
+end;
+$$;
+
+-- counts a wrong password, the count starts over 15 minutes after the last
+-- one
+create procedure files.share_link_password_failed(
+    p_tenant_id uuid,
+    p_link_id uuid
+)
+language sql
+as $$
+    update files.share_links l set
+        failed_attempts = case
+            when l.failed_ts > now() - interval '15 minutes' then l.failed_attempts + 1
+            else 1
+        end,
+        failed_ts = now()
+    where
+        l.tenant_id = p_tenant_id
+        and l.link_id = p_link_id;
+$$;
+
+-- replaces the hash after the password was checked, unless it was changed
+-- in the meantime
+create procedure files.share_link_password_rehash(
+    p_tenant_id uuid,
+    p_link_id uuid,
+    p_old_hash text,
+    p_new_hash text
+)
+language sql
+as $$
+    update files.share_links l set
+        password_hash = p_new_hash
+    where
+        l.tenant_id = p_tenant_id
+        and l.link_id = p_link_id
+        and l.password_hash = p_old_hash;
+$$;
+
+-- checks a password against a hash made by crypt()
+create function files.share_link_password_legacy_verify(
+    p_tenant_id uuid,
+    p_link_id uuid,
+    p_password text
+)
+returns boolean
+language sql
+stable
+as $$
+    select exists (
+        select 1
+        from files.share_links l
+        where
+            l.tenant_id = p_tenant_id
+            and l.link_id = p_link_id
+            and l.password_hash not like '$argon2%'
+            and l.password_hash = public.crypt(p_password, l.password_hash)
+    );
+$$;
@@ -1,3 +1,5 @@
+#![allow(clippy::needless_return)]
+
 use tracing::{
     info,
     error
@@ -5,6 +7,101 @@ use tracing::{
 
 use sqlx::Row;
 
+use auth_provider::password;
+use provider_error::ProviderError;
+
+
+/// schema migrations for the `files` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "files",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 2,
+            name: "file_content",
+            up: include_str!("../migrations/0002_file_content.up.sql"),
+            down: include_str!("../migrations/0002_file_content.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 3,
+            name: "blobs",
+            up: include_str!("../migrations/0003_blobs.up.sql"),
+            down: include_str!("../migrations/0003_blobs.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 4,
+            name: "file_versions",
+            up: include_str!("../migrations/0004_file_versions.up.sql"),
+            down: include_str!("../migrations/0004_file_versions.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 5,
+            name: "folders",
+            up: include_str!("../migrations/0005_folders.up.sql"),
+            down: include_str!("../migrations/0005_folders.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 6,
+            name: "access",
+            up: include_str!("../migrations/0006_access.up.sql"),
+            down: include_str!("../migrations/0006_access.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 7,
+            name: "share_links",
+            up: include_str!("../migrations/0007_share_links.up.sql"),
+            down: include_str!("../migrations/0007_share_links.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 8,
+            name: "attachments",
+            up: include_str!("../migrations/0008_attachments.up.sql"),
+            down: include_str!("../migrations/0008_attachments.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 9,
+            name: "quotas",
+            up: include_str!("../migrations/0009_quotas.up.sql"),
+            down: include_str!("../migrations/0009_quotas.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 10,
+            name: "uploads",
+            up: include_str!("../migrations/0010_uploads.up.sql"),
+            down: include_str!("../migrations/0010_uploads.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 11,
+            name: "previews",
+            up: include_str!("../migrations/0011_previews.up.sql"),
+            down: include_str!("../migrations/0011_previews.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 12,
+            name: "search",
+            up: include_str!("../migrations/0012_search.up.sql"),
+            down: include_str!("../migrations/0012_search.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 13,
+            name: "ownerless_access",
+            up: include_str!("../migrations/0013_ownerless_access.up.sql"),
+            down: include_str!("../migrations/0013_ownerless_access.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 14,
+            name: "share_link_passwords",
+            up: include_str!("../migrations/0014_share_link_passwords.up.sql"),
+            down: include_str!("../migrations/0014_share_link_passwords.down.sql")
+        }
+    ]
+};
+
 
 pub struct PostgresFileProvider {
     dp: database_provider::DatabaseProvider
@@ -19,6 +116,114 @@ impl PostgresFileProvider {
             dp: dp.clone()
         };
     }
+
+    /// checks a link password against a hash made by pgcrypto's `crypt()`
+    async fn share_link_password_legacy_verify(
+        &self,
+        tenant_id: &uuid::Uuid,
+        link_id: &uuid::Uuid,
+        password: &str
+    ) -> Result<bool, ProviderError> {
+        info!("share_link_password_legacy_verify");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.share_link_password_legacy_verify($1,$2,$3);")
+                .bind(tenant_id)
+                .bind(link_id)
+                .bind(password)
+                .fetch_one(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error verifying legacy share link password: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(r) => {
+                        return Ok(r.get("share_link_password_legacy_verify"));
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn share_link_password_rehash(
+        &self,
+        tenant_id: &uuid::Uuid,
+        link_id: &uuid::Uuid,
+        old_hash: &str,
+        new_hash: &str
+    ) -> Result<(), ProviderError> {
+        info!("share_link_password_rehash");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.share_link_password_rehash($1,$2,$3,$4);")
+                .bind(tenant_id)
+                .bind(link_id)
+                .bind(old_hash)
+                .bind(new_hash)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error replacing share link password hash: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn share_link_password_failed(
+        &self,
+        tenant_id: &uuid::Uuid,
+        link_id: &uuid::Uuid
+    ) -> Result<(), ProviderError> {
+        info!("share_link_password_failed");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.share_link_password_failed($1,$2);")
+                .bind(tenant_id)
+                .bind(link_id)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error counting wrong share link password: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+}
+
+
+/// hashing is slow by design and runs on the blocking thread pool
+async fn hash_password(pw: &str) -> Result<String, ProviderError> {
+    let pw = String::from(pw);
+    return tokio::task::spawn_blocking(move || password::hash(&password::Params::default(), &pw))
+        .await
+        .map_err(|e| {
+            error!("password hashing failed: {}", e);
+            return ProviderError::Internal;
+        })?;
+}
+
+async fn verify_password(pw: &str, pw_hash: &str) -> Result<bool, ProviderError> {
+    let pw = String::from(pw);
+    let pw_hash = String::from(pw_hash);
+    return tokio::task::spawn_blocking(move || password::verify(&pw, &pw_hash))
+        .await
+        .map_err(|e| {
+            error!("password verification failed: {}", e);
+            return ProviderError::Internal;
+        })?;
 }
 
 
@@ -27,20 +232,23 @@ impl file_provider::FileProvider for PostgresFileProvider {
     async fn folder_add(
         &self,
         tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
         folder: &file_provider::Folder
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("folder_add");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
-            match sqlx::query("call files.folder_add($1,$2,$3);")
+            match sqlx::query("call files.folder_add($1,$2,$3,$4,$5);")
                 .bind(tenant_id)
+                .bind(user_id)
                 .bind(folder.folder_id)
+                .bind(folder.parent_folder_id.unwrap_or_default())
                 .bind(folder.name.clone())
                 .execute(&pool)
                 .await {
                     Err(e) => {
                         error!("Error adding folder record: {:?}", e);
-                        return Err("Error adding folder record");
+                        return Err(database_provider::from_sqlx(&e));
                     }
                     Ok(_) => {
                         return Ok(());
@@ -48,125 +256,297 @@ impl file_provider::FileProvider for PostgresFileProvider {
                 }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
     async fn folder_get(
         &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
         folder_id: &uuid::Uuid
-    ) -> Result<file_provider::Folder, &'static str> {
+    ) -> Result<file_provider::Folder, ProviderError> {
         info!("folder_get");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
-            match sqlx::query("select * from fp.folder_get($1)")
+            match sqlx::query("select * from files.folder_get($1,$2,$3)")
+                .bind(tenant_id)
+                .bind(user_id)
                 .bind(folder_id)
                 .fetch_one(&pool)
                 .await {
                     Err(e) => {
                         error!("Error getting folder record: {:?}", e);
-                        return Err("Error getting folder record");
+                        return Err(database_provider::from_sqlx(&e));
                     }
                     Ok(r) => {
-                        let folder_id: uuid::Uuid = r.get("folder_id");
-                        let name: String = r.get("name");
-
-                        return Ok(file_provider::Folder {
-                            folder_id,
-                            name
-                        });
+                        return Ok(folder_from_row(&r));
                     }
                 }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
-
     async fn folder_list_folders(
         &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
         folder_id: &uuid::Uuid
-    ) -> Result<Vec<file_provider::Folder>, &'static str> {
+    ) -> Result<Vec<file_provider::Folder>, ProviderError> {
         info!("folder_list_folders");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
-            match sqlx::query("select * from files.folder_list_folders($1)")
+            match sqlx::query("select * from files.folder_list_folders($1,$2,$3)")
+                .bind(tenant_id)
+                .bind(user_id)
                 .bind(folder_id)
                 .fetch_all(&pool)
                 .await {
                     Err(e) => {
-                        error!("Error listing files in folder: {:?}", e);
-                        return Err("Error listing files in folder");
+                        error!("Error listing folders in folder: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
                     }
                     Ok(rows) => {
-                        let folders: Vec<file_provider::Folder> = rows.into_iter().map(|r| {
-                            let folder_id: uuid::Uuid = r.get("folder_id");
-                            let name: String = r.get("name");
-
-                            file_provider::Folder {
-                                folder_id,
-                                name
-                            }
-                        }).collect();
-                        return Ok(folders);
+                        return Ok(rows.iter().map(folder_from_row).collect());
                     }
                 }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
     async fn folder_list_files(
         &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
         folder_id: &uuid::Uuid
-    ) -> Result<Vec<file_provider::File>, &'static str> {
+    ) -> Result<Vec<file_provider::File>, ProviderError> {
         info!("folder_list_files");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
-            match sqlx::query("select * from files.folder_list_files($1)")
+            match sqlx::query("select * from files.folder_list_files($1,$2,$3)")
+                .bind(tenant_id)
+                .bind(user_id)
                 .bind(folder_id)
                 .fetch_all(&pool)
                 .await {
                     Err(e) => {
                         error!("Error listing files in folder: {:?}", e);
-                        return Err("Error listing files in folder");
+                        return Err(database_provider::from_sqlx(&e));
                     }
                     Ok(rows) => {
-                        let files: Vec<file_provider::File> = rows.into_iter().map(|r| {
-                            let file_id: uuid::Uuid = r.get("file_id");
-                            let name: String = r.get("name");
-
-                            file_provider::File {
-                                file_id,
-                                name
-                            }
-                        }).collect();
+                        let mut files: Vec<file_provider::File> = Vec::with_capacity(rows.len());
+                        for r in rows {
+                            let preview: String = r.get("preview");
+                            let mut file = file_from_row(&r);
+                            file.preview = Some(preview.parse()?);
+                            files.push(file);
+                        }
                         return Ok(files);
                     }
                 }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn folder_rename(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid,
+        name: &str
+    ) -> Result<(), ProviderError> {
+        info!("folder_rename");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.folder_rename($1,$2,$3,$4);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(folder_id)
+                .bind(name)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error renaming folder: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn folder_move(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid,
+        parent_folder_id: &uuid::Uuid
+    ) -> Result<(), ProviderError> {
+        info!("folder_move");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.folder_move($1,$2,$3,$4);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(folder_id)
+                .bind(parent_folder_id)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error moving folder: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn folder_trash(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid
+    ) -> Result<(), ProviderError> {
+        info!("folder_trash");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.folder_trash($1,$2,$3);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(folder_id)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error moving folder to the trash: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn folder_restore(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid
+    ) -> Result<(), ProviderError> {
+        info!("folder_restore");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.folder_restore($1,$2,$3);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(folder_id)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error restoring folder from the trash: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn folder_trash_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid
+    ) -> Result<Vec<file_provider::Folder>, ProviderError> {
+        info!("folder_trash_fetch");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.folder_trash_fetch($1,$2)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error fetching trashed folders: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) => {
+                        return Ok(rows.iter().map(folder_from_row).collect());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn folder_path(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid
+    ) -> Result<Vec<file_provider::Folder>, ProviderError> {
+        info!("folder_path");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.folder_path($1,$2,$3)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(folder_id)
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error fetching folder path: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) if rows.is_empty() => {
+                        return Err(ProviderError::NotFound);
+                    }
+                    Ok(rows) => {
+                        return Ok(rows.iter().map(folder_from_row).collect());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
     }
 
     async fn file_add(
         &self,
         tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
         folder_id: &uuid::Uuid,
         file: &file_provider::File
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("file_add");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
-            match sqlx::query("call files.file_add($1,$2,$3,$4);")
+            match sqlx::query("call files.file_add($1,$2,$3,$4,$5,$6,$7,$8);")
                 .bind(tenant_id)
+                .bind(user_id)
                 .bind(file.file_id)
                 .bind(folder_id)
                 .bind(file.name.clone())
+                .bind(file.content_type.clone())
+                .bind(file.size)
+                .bind(file.digest.clone())
                 .execute(&pool)
                 .await {
                     Err(e) => {
                         error!("Error adding file record: {:?}", e);
-                        return Err("Error adding file record");
+                        return Err(database_provider::from_sqlx(&e));
                     }
                     Ok(_) => {
                         return Ok(());
@@ -174,88 +554,2097 @@ impl file_provider::FileProvider for PostgresFileProvider {
                 }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
-    async fn file_get(
+    async fn file_delete(
         &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
         file_id: &uuid::Uuid
-    ) -> Result<file_provider::File, &'static str> {
-        info!("file_get");
+    ) -> Result<Vec<String>, ProviderError> {
+        info!("file_delete");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
-            match sqlx::query("select * from files.file_get($1)")
+            match sqlx::query("select digest from files.file_delete($1,$2,$3) as digest")
+                .bind(tenant_id)
+                .bind(user_id)
                 .bind(file_id)
-                .fetch_one(&pool)
+                .fetch_all(&pool)
                 .await {
                     Err(e) => {
-                        error!("Error getting file record: {:?}", e);
-                        return Err("Error getting file record");
+                        error!("Error deleting file record: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
                     }
-                    Ok(r) => {
-                        let file_id: uuid::Uuid = r.get("file_id");
-                        let name: String = r.get("name");
-
-                        return Ok(file_provider::File {
-                            file_id,
-                            name
-                        });
+                    Ok(rows) => {
+                        return Ok(rows.iter().map(|r| r.get("digest")).collect());
                     }
                 }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
-}
 
+    async fn file_version_add(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file: &file_provider::File
+    ) -> Result<i32, ProviderError> {
+        info!("file_version_add");
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select files.file_version_add($1,$2,$3,$4,$5,$6) as version")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(file.file_id)
+                .bind(file.content_type.clone())
+                .bind(file.size)
+                .bind(file.digest.clone())
+                .fetch_one(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error adding file version: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(r) => {
+                        return Ok(r.get("version"));
+                    }
+                }
+        }
 
-    use file_provider::FileProvider;
-    use tenants_provider::TenantsProvider;
+        return Err(ProviderError::Unavailable);
+    }
 
+    async fn file_versions_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> Result<Vec<file_provider::FileVersion>, ProviderError> {
+        info!("file_versions_fetch");
 
-    #[actix_web::test]
-    async fn test_postgres_file_provider() {
-        if let Err(e) = tracing_subscriber::fmt::try_init() {
-            println!("error: {:?}", e);
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.file_versions_fetch($1,$2,$3)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(file_id)
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error fetching file versions: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) => {
+                        return Ok(rows.iter().map(version_from_row).collect());
+                    }
+                }
         }
 
-        let cfg = config::Config::from_env();
-        let db_provider = database_provider::DatabaseProvider::new(&cfg);
-        let dp = actix_web::web::Data::new(std::sync::Arc::new(db_provider));
+        return Err(ProviderError::Unavailable);
+    }
 
-        let tp = tenants_provider_postgres::PostgresTenantsProvider::new(&dp);
-        let tenant = tp.tenant_fetch_by_name("default").await.unwrap();
-        let tenant_id = tenant.tenant_id();
+    async fn file_version_get(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file_id: &uuid::Uuid,
+        version: i32
+    ) -> Result<file_provider::FileVersion, ProviderError> {
+        info!("file_version_get");
 
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.file_version_get($1,$2,$3,$4)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(file_id)
+                .bind(version)
+                .fetch_one(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error getting file version: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(r) => {
+                        return Ok(version_from_row(&r));
+                    }
+                }
+        }
 
-        let fpp = PostgresFileProvider::new(&dp);
+        return Err(ProviderError::Unavailable);
+    }
 
-        let folder = file_provider::Folder {
-            folder_id: uuid::Uuid::new_v4(),
-            name: "Test Folder".to_string()
+    async fn file_version_restore(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file_id: &uuid::Uuid,
+        version: i32
+    ) -> Result<i32, ProviderError> {
+        info!("file_version_restore");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select files.file_version_restore($1,$2,$3,$4) as version")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(file_id)
+                .bind(version)
+                .fetch_one(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error restoring file version: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(r) => {
+                        return Ok(r.get("version"));
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn blob_is_referenced(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str
+    ) -> Result<bool, ProviderError> {
+        info!("blob_is_referenced");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select files.blob_is_referenced($1,$2) as referenced")
+                .bind(tenant_id)
+                .bind(digest)
+                .fetch_one(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error checking blob references: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(r) => {
+                        return Ok(r.get("referenced"));
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn file_get(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> Result<file_provider::File, ProviderError> {
+        info!("file_get");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.file_get($1,$2,$3)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(file_id)
+                .fetch_one(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error getting file record: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(r) => {
+                        return Ok(file_from_row(&r));
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn folder_grants_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid
+    ) -> Result<Vec<file_provider::FolderGrant>, ProviderError> {
+        info!("folder_grants_fetch");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.folder_grants_fetch($1,$2,$3)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(folder_id)
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error fetching folder grants: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) => {
+                        return rows.iter().map(grant_from_row).collect();
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn folder_grant_save(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        grant: &file_provider::FolderGrant
+    ) -> Result<(), ProviderError> {
+        info!("folder_grant_save");
+
+        let (role_id, grantee_user_id) = grantee_ids(&grant.grantee);
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.folder_grant_save($1,$2,$3,$4,$5,$6);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(grant.folder_id)
+                .bind(role_id)
+                .bind(grantee_user_id)
+                .bind(grant.level.as_str())
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error saving folder grant: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn folder_grant_remove(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        folder_id: &uuid::Uuid,
+        grantee: &file_provider::Grantee
+    ) -> Result<(), ProviderError> {
+        info!("folder_grant_remove");
+
+        let (role_id, grantee_user_id) = grantee_ids(grantee);
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.folder_grant_remove($1,$2,$3,$4,$5);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(folder_id)
+                .bind(role_id)
+                .bind(grantee_user_id)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error removing folder grant: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn share_link_add(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        link_id: &uuid::Uuid,
+        target: &file_provider::ShareTarget,
+        expires: &chrono::DateTime<chrono::Utc>,
+        password: Option<&str>
+    ) -> Result<(), ProviderError> {
+        info!("share_link_add");
+
+        let (file_id, folder_id) = target.ids();
+
+        let password_hash = match password.filter(|p| !p.is_empty()) {
+            None => None,
+            Some(password) => Some(hash_password(password).await?)
+        };
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.share_link_add($1,$2,$3,$4,$5,$6,$7);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(link_id)
+                .bind(file_id)
+                .bind(folder_id)
+                .bind(expires)
+                .bind(password_hash)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error adding share link: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn share_links_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        target: &file_provider::ShareTarget
+    ) -> Result<Vec<file_provider::ShareLink>, ProviderError> {
+        info!("share_links_fetch");
+
+        let (file_id, folder_id) = target.ids();
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.share_links_fetch($1,$2,$3,$4)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(file_id)
+                .bind(folder_id)
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error fetching share links: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) => {
+                        return rows.iter().map(share_link_from_row).collect();
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn share_link_revoke(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        link_id: &uuid::Uuid
+    ) -> Result<(), ProviderError> {
+        info!("share_link_revoke");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.share_link_revoke($1,$2,$3);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(link_id)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error revoking share link: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn share_link_open(
+        &self,
+        tenant_id: &uuid::Uuid,
+        link_id: &uuid::Uuid,
+        password: Option<&str>
+    ) -> Result<file_provider::ShareLink, ProviderError> {
+        info!("share_link_open");
+
+        let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") else {
+            return Err(ProviderError::Unavailable);
+        };
+
+        let r = match sqlx::query("select * from files.share_link_open($1,$2)")
+            .bind(tenant_id)
+            .bind(link_id)
+            .fetch_one(&pool)
+            .await {
+                Err(e) => {
+                    error!("Error opening share link: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+                Ok(r) => r
+            };
+
+        let link = share_link_from_row(&r)?;
+        let Some(password_hash) = r.get::<Option<String>, _>("password_hash") else {
+            return Ok(link);
+        };
+        if r.get::<bool, _>("locked") {
+            return Err(ProviderError::Throttled);
+        }
+        // a missing password is asked for, it is not a wrong one
+        let Some(password) = password.filter(|p| !p.is_empty()) else {
+            return Err(ProviderError::Forbidden);
+        };
+
+        let authentic = if password::is_legacy(&password_hash) {
+            self.share_link_password_legacy_verify(tenant_id, link_id, password).await?
+        } else {
+            verify_password(password, &password_hash).await?
         };
+
+        if !authentic {
+            self.share_link_password_failed(tenant_id, link_id).await?;
+            return Err(ProviderError::Forbidden);
+        }
+
+        if password::needs_rehash(&password::Params::default(), &password_hash) {
+            // the link opens even if the old hash stays
+            let rehashed = match hash_password(password).await {
+                Ok(new_hash) => self.share_link_password_rehash(tenant_id, link_id, &password_hash, &new_hash).await,
+                Err(e) => Err(e)
+            };
+            if let Err(e) = rehashed {
+                error!("unable to replace password hash of share link {}: {}", link_id, e);
+            }
+        }
+
+        return Ok(link);
+    }
+
+    async fn share_link_files(
+        &self,
+        tenant_id: &uuid::Uuid,
+        link_id: &uuid::Uuid
+    ) -> Result<Vec<file_provider::SharedFile>, ProviderError> {
+        info!("share_link_files");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.share_link_files($1,$2)")
+                .bind(tenant_id)
+                .bind(link_id)
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error fetching shared files: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) => {
+                        return Ok(rows.iter().map(|r| file_provider::SharedFile {
+                            path: r.get("path"),
+                            file: file_from_row(r)
+                        }).collect());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn share_link_file_get(
+        &self,
+        tenant_id: &uuid::Uuid,
+        link_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> Result<file_provider::File, ProviderError> {
+        info!("share_link_file_get");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.share_link_file_get($1,$2,$3)")
+                .bind(tenant_id)
+                .bind(link_id)
+                .bind(file_id)
+                .fetch_one(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error getting shared file: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(r) => {
+                        return Ok(file_from_row(&r));
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn share_link_access_add(
+        &self,
+        tenant_id: &uuid::Uuid,
+        link_id: &uuid::Uuid,
+        file_id: Option<&uuid::Uuid>,
+        outcome: file_provider::ShareOutcome,
+        remote_addr: Option<&str>,
+        user_agent: Option<&str>
+    ) -> Result<(), ProviderError> {
+        info!("share_link_access_add");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.share_link_access_add($1,$2,$3,$4,$5,$6);")
+                .bind(tenant_id)
+                .bind(link_id)
+                .bind(file_id)
+                .bind(outcome.as_str())
+                .bind(remote_addr)
+                .bind(user_agent)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error adding share link access: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn share_link_accesses_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        link_id: &uuid::Uuid
+    ) -> Result<Vec<file_provider::ShareLinkAccess>, ProviderError> {
+        info!("share_link_accesses_fetch");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.share_link_accesses_fetch($1,$2,$3)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(link_id)
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error fetching share link accesses: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) => {
+                        return rows.iter().map(|r| {
+                            let outcome: String = r.get("outcome");
+                            return Ok(file_provider::ShareLinkAccess {
+                                accessed: r.get("accessed_ts"),
+                                file_id: r.get("file_id"),
+                                outcome: outcome.parse()?,
+                                remote_addr: r.get("remote_addr"),
+                                user_agent: r.get("user_agent")
+                            });
+                        }).collect();
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn attachment_add(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        entity_type: file_provider::EntityType,
+        entity_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> Result<(), ProviderError> {
+        info!("attachment_add");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.attachment_add($1,$2,$3,$4,$5);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(entity_type.as_str())
+                .bind(entity_id)
+                .bind(file_id)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error adding attachment: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn attachment_remove(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        entity_type: file_provider::EntityType,
+        entity_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> Result<(), ProviderError> {
+        info!("attachment_remove");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.attachment_remove($1,$2,$3,$4,$5);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(entity_type.as_str())
+                .bind(entity_id)
+                .bind(file_id)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error removing attachment: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn attachments_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        entity_type: file_provider::EntityType,
+        entity_id: &uuid::Uuid
+    ) -> Result<Vec<file_provider::Attachment>, ProviderError> {
+        info!("attachments_fetch");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.attachments_fetch($1,$2,$3,$4)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(entity_type.as_str())
+                .bind(entity_id)
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error fetching attachments: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) => {
+                        return Ok(rows.iter().map(|r| file_provider::Attachment {
+                            file: file_from_row(r),
+                            attached_by: r.get("created_by"),
+                            attached: r.get("created_ts")
+                        }).collect());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn attachment_count(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        entity_type: file_provider::EntityType,
+        entity_id: &uuid::Uuid
+    ) -> Result<i64, ProviderError> {
+        info!("attachment_count");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select files.attachment_count($1,$2,$3,$4) as attachment_count")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(entity_type.as_str())
+                .bind(entity_id)
+                .fetch_one(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error counting attachments: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(r) => {
+                        return Ok(r.get("attachment_count"));
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+    async fn upload_add(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        upload: &file_provider::FileUpload
+    ) -> Result<(), ProviderError> {
+        info!("upload_add");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.upload_add($1,$2,$3,$4,$5,$6,$7,$8,$9);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(upload.upload_id)
+                .bind(upload.file_id)
+                .bind(upload.folder_id)
+                .bind(&upload.name)
+                .bind(&upload.content_type)
+                .bind(upload.length)
+                .bind(upload.expires)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error adding upload: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn upload_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        upload_id: &uuid::Uuid
+    ) -> Result<file_provider::FileUpload, ProviderError> {
+        info!("upload_fetch");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.upload_get($1,$2,$3)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(upload_id)
+                .fetch_one(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error fetching upload: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(r) => {
+                        return Ok(file_provider::FileUpload {
+                            upload_id: r.get("upload_id"),
+                            file_id: r.get("file_id"),
+                            folder_id: r.get("folder_id"),
+                            name: r.get("name"),
+                            content_type: r.get("content_type"),
+                            length: r.get("length"),
+                            offset: r.get("upload_offset"),
+                            expires: r.get("expires_ts")
+                        });
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn upload_offset_save(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        upload_id: &uuid::Uuid,
+        from: i64,
+        to: i64
+    ) -> Result<(), ProviderError> {
+        info!("upload_offset_save");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.upload_offset_save($1,$2,$3,$4,$5);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(upload_id)
+                .bind(from)
+                .bind(to)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error saving upload offset: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn upload_remove(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        upload_id: &uuid::Uuid
+    ) -> Result<(), ProviderError> {
+        info!("upload_remove");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.upload_remove($1,$2,$3);")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(upload_id)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error removing upload: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn uploads_expire(
+        &self
+    ) -> Result<Vec<(uuid::Uuid, uuid::Uuid)>, ProviderError> {
+        info!("uploads_expire");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.uploads_expire()")
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error expiring uploads: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) => {
+                        return Ok(rows.iter().map(|r| (r.get("tenant_id"), r.get("upload_id"))).collect());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn usage_fetch(
+        &self,
+        tenant_id: &uuid::Uuid
+    ) -> Result<file_provider::Usage, ProviderError> {
+        info!("usage_fetch");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.tenant_usage($1)")
+                .bind(tenant_id)
+                .fetch_one(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error fetching usage: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(r) => {
+                        return Ok(file_provider::Usage {
+                            used: r.get("used"),
+                            files: r.get("files"),
+                            quota: r.get("quota")
+                        });
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn quota_save(
+        &self,
+        tenant_id: &uuid::Uuid,
+        quota: Option<i64>
+    ) -> Result<(), ProviderError> {
+        info!("quota_save");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.tenant_quota_save($1,$2);")
+                .bind(tenant_id)
+                .bind(quota)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error saving quota: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn file_preview_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        file_id: &uuid::Uuid
+    ) -> Result<Option<file_provider::Preview>, ProviderError> {
+        info!("file_preview_fetch");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.file_preview_get($1,$2,$3)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(file_id)
+                .fetch_optional(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error fetching preview: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(r) => {
+                        return r.as_ref().map(preview_from_row).transpose();
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn previews_claim(
+        &self,
+        limit: i32,
+        lease: i32
+    ) -> Result<Vec<file_provider::PreviewJob>, ProviderError> {
+        info!("previews_claim");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.previews_claim($1,$2)")
+                .bind(limit)
+                .bind(lease)
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error claiming previews: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) => {
+                        return Ok(rows.iter().map(|r| file_provider::PreviewJob {
+                            tenant_id: r.get("tenant_id"),
+                            digest: r.get("digest"),
+                            source_type: r.get("source_type"),
+                            attempts: r.get("attempts")
+                        }).collect());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn preview_ready(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str,
+        content_type: &str
+    ) -> Result<(), ProviderError> {
+        info!("preview_ready");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.preview_ready($1,$2,$3);")
+                .bind(tenant_id)
+                .bind(digest)
+                .bind(content_type)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error saving preview: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn preview_failed(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str,
+        error: &str,
+        retry_at: Option<&chrono::DateTime<chrono::Utc>>
+    ) -> Result<(), ProviderError> {
+        info!("preview_failed");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.preview_failed($1,$2,$3,$4);")
+                .bind(tenant_id)
+                .bind(digest)
+                .bind(error)
+                .bind(retry_at)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error saving failed preview: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn file_search(
+        &self,
+        tenant_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        query: &str,
+        limit: i32,
+        offset: i32
+    ) -> Result<Vec<file_provider::SearchResult>, ProviderError> {
+        info!("file_search");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.file_search($1,$2,$3,$4,$5)")
+                .bind(tenant_id)
+                .bind(user_id)
+                .bind(query)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error searching files: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) => {
+                        return Ok(rows.iter().map(|r| file_provider::SearchResult {
+                            file: file_from_row(r),
+                            folder_id: r.get("folder_id"),
+                            path: r.get("path"),
+                            rank: r.get("rank"),
+                            snippet: r.get("snippet")
+                        }).collect());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn texts_claim(
+        &self,
+        limit: i32,
+        lease: i32
+    ) -> Result<Vec<file_provider::TextJob>, ProviderError> {
+        info!("texts_claim");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from files.texts_claim($1,$2)")
+                .bind(limit)
+                .bind(lease)
+                .fetch_all(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error claiming texts: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(rows) => {
+                        return Ok(rows.iter().map(|r| file_provider::TextJob {
+                            tenant_id: r.get("tenant_id"),
+                            digest: r.get("digest"),
+                            source_type: r.get("source_type"),
+                            attempts: r.get("attempts")
+                        }).collect());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn text_ready(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str,
+        body: &str
+    ) -> Result<(), ProviderError> {
+        info!("text_ready");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.text_ready($1,$2,$3);")
+                .bind(tenant_id)
+                .bind(digest)
+                .bind(body)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error saving text: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+
+    async fn text_failed(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str,
+        error: &str,
+        retry_at: Option<&chrono::DateTime<chrono::Utc>>
+    ) -> Result<(), ProviderError> {
+        info!("text_failed");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call files.text_failed($1,$2,$3,$4);")
+                .bind(tenant_id)
+                .bind(digest)
+                .bind(error)
+                .bind(retry_at)
+                .execute(&pool)
+                .await {
+                    Err(e) => {
+                        error!("Error saving failed text: {:?}", e);
+                        return Err(database_provider::from_sqlx(&e));
+                    }
+                    Ok(_) => {
+                        return Ok(());
+                    }
+                }
+        }
+
+        return Err(ProviderError::Unavailable);
+    }
+}
+
+
+fn folder_from_row(r: &sqlx::postgres::PgRow) -> file_provider::Folder {
+    return file_provider::Folder {
+        folder_id: r.get("folder_id"),
+        parent_folder_id: r.get("parent_folder_id"),
+        owner_id: r.get("owner_id"),
+        name: r.get("name"),
+        trashed: r.get("trashed_ts")
+    };
+}
+
+fn grant_from_row(r: &sqlx::postgres::PgRow) -> Result<file_provider::FolderGrant, ProviderError> {
+    let role_id: Option<uuid::Uuid> = r.get("role_id");
+    let user_id: Option<uuid::Uuid> = r.get("user_id");
+    let level: String = r.get("level");
+
+    let grantee = match (role_id, user_id) {
+        (Some(role_id), _) => file_provider::Grantee::Role(role_id),
+        (None, Some(user_id)) => file_provider::Grantee::User(user_id),
+        (None, None) => {
+            error!("folder grant without grantee");
+            return Err(ProviderError::Internal);
+        }
+    };
+
+    return Ok(file_provider::FolderGrant {
+        folder_id: r.get("folder_id"),
+        grantee,
+        level: level.parse()?
+    });
+}
+
+/// role and user id columns of a grantee, one of them is null
+fn grantee_ids(grantee: &file_provider::Grantee) -> (Option<uuid::Uuid>, Option<uuid::Uuid>) {
+    return match grantee {
+        file_provider::Grantee::Role(role_id) => (Some(*role_id), None),
+        file_provider::Grantee::User(user_id) => (None, Some(*user_id))
+    };
+}
+
+fn share_link_from_row(r: &sqlx::postgres::PgRow) -> Result<file_provider::ShareLink, ProviderError> {
+    let file_id: Option<uuid::Uuid> = r.get("file_id");
+    let folder_id: Option<uuid::Uuid> = r.get("folder_id");
+
+    let target = match (file_id, folder_id) {
+        (Some(file_id), _) => file_provider::ShareTarget::File(file_id),
+        (None, Some(folder_id)) => file_provider::ShareTarget::Folder(folder_id),
+        (None, None) => {
+            error!("share link without target");
+            return Err(ProviderError::Internal);
+        }
+    };
+
+    return Ok(file_provider::ShareLink {
+        link_id: r.get("link_id"),
+        target,
+        created_by: r.get("created_by"),
+        created: r.get("created_ts"),
+        expires: r.get("expires_ts"),
+        password_protected: r.get("password_protected"),
+        revoked: r.get("revoked_ts")
+    });
+}
+
+fn file_from_row(r: &sqlx::postgres::PgRow) -> file_provider::File {
+    let file_id: uuid::Uuid = r.get("file_id");
+    let name: String = r.get("name");
+    let content_type: String = r.get("content_type");
+    let size: i64 = r.get("size");
+    let digest: Option<String> = r.get("digest");
+    let version: i32 = r.get("version");
+
+    let mut file = file_provider::File::new(file_id, name);
+    file.content_type = content_type;
+    file.size = size;
+    file.digest = digest;
+    file.version = version;
+    return file;
+}
+
+fn preview_from_row(r: &sqlx::postgres::PgRow) -> Result<file_provider::Preview, ProviderError> {
+    let state: String = r.get("state");
+    return Ok(file_provider::Preview {
+        digest: r.get("digest"),
+        state: state.parse()?,
+        content_type: r.get("content_type"),
+        updated: r.get("updated_ts")
+    });
+}
+
+fn version_from_row(r: &sqlx::postgres::PgRow) -> file_provider::FileVersion {
+    return file_provider::FileVersion {
+        file_id: r.get("file_id"),
+        version: r.get("version"),
+        created: r.get("created_ts"),
+        content_type: r.get("content_type"),
+        size: r.get("size"),
+        digest: r.get("digest")
+    };
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    use file_provider::FileProvider;
+    use tenants_provider::TenantsProvider;
+
+
+    #[actix_web::test]
+    async fn test_postgres_file_provider() {
+        if let Err(e) = tracing_subscriber::fmt::try_init() {
+            println!("error: {:?}", e);
+        }
+
+        let cfg = config::Config::from_env();
+        let db_provider = database_provider::DatabaseProvider::new(&cfg);
+        let dp = actix_web::web::Data::new(std::sync::Arc::new(db_provider));
+
+        let tp = tenants_provider_postgres::PostgresTenantsProvider::new(&dp);
+        let tenant = tp.tenant_fetch_by_name("default").await.unwrap();
+        let tenant_id = tenant.tenant_id();
+
+
+        let fpp = PostgresFileProvider::new(&dp);
+        let user_id = uuid::Uuid::new_v4();
+
+        let folder = file_provider::Folder::new(uuid::Uuid::new_v4(), "Test Folder".to_string());
         let folder_id = folder.folder_id;
 
-        if let Err(e) = fpp.folder_add(&tenant_id, &folder).await {
+        if let Err(e) = fpp.folder_add(&tenant_id, &user_id, &folder).await {
             error!("error adding folder: {:?}", e);
             assert!(false, "error adding folder");
         };
 
-        let file = file_provider::File {
-            file_id: uuid::Uuid::new_v4(),
-            name: "Test File".to_string()
+        let digest = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+        let file = file_provider::File::new(uuid::Uuid::new_v4(), "Test File".to_string())
+            .with_content("text/plain", 11, &digest);
+
+        if let Err(e) = fpp.file_add(&tenant_id, &user_id, &folder_id, &file).await {
+            error!("error adding file: {:?}", e);
+            assert!(false, "error adding file");
+        }
+
+        match fpp.file_get(&tenant_id, &user_id, &file.file_id).await {
+            Err(e) => {
+                error!("error getting file: {:?}", e);
+                assert!(false, "error getting file");
+            }
+            Ok(f) => {
+                assert_eq!(f.content_type, "text/plain");
+                assert_eq!(f.size, 11);
+                assert_eq!(f.digest, Some(digest.clone()));
+            }
+        }
+
+        // files are not visible to other tenants
+        assert!(matches!(
+            fpp.file_get(&uuid::Uuid::new_v4(), &user_id, &file.file_id).await,
+            Err(ProviderError::NotFound)
+        ));
+
+        // a copy shares the content
+        let copy = file_provider::File::new(uuid::Uuid::new_v4(), "Test Copy".to_string())
+            .with_content("text/plain", 11, &digest);
+        if let Err(e) = fpp.file_add(&tenant_id, &user_id, &folder_id, &copy).await {
+            error!("error adding copy: {:?}", e);
+            assert!(false, "error adding copy");
+        }
+
+        assert_eq!(fpp.file_delete(&tenant_id, &user_id, &file.file_id).await, Ok(vec![]));
+        assert_eq!(fpp.blob_is_referenced(&tenant_id, &digest).await, Ok(true));
+
+        assert_eq!(fpp.file_delete(&tenant_id, &user_id, &copy.file_id).await, Ok(vec![digest.clone()]));
+        assert_eq!(fpp.blob_is_referenced(&tenant_id, &digest).await, Ok(false));
+
+        assert_eq!(fpp.file_delete(&tenant_id, &user_id, &copy.file_id).await, Err(ProviderError::NotFound));
+    }
+
+    #[actix_web::test]
+    async fn test_file_versions() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let tenant_id = uuid::Uuid::nil();
+        let user_id = uuid::Uuid::new_v4();
+        let fpp = PostgresFileProvider::new(&dp);
+
+        let digest = || format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+        let (first, second) = (digest(), digest());
+
+        let mut file = file_provider::File::new(uuid::Uuid::new_v4(), "contract.pdf".to_string())
+            .with_content("application/pdf", 100, &first);
+        if let Err(e) = fpp.file_add(&tenant_id, &user_id, &uuid::Uuid::nil(), &file).await {
+            error!("error adding file: {:?}", e);
+            assert!(false, "error adding file");
+        }
+
+        file = file.with_content("application/pdf", 120, &second);
+        assert_eq!(fpp.file_version_add(&tenant_id, &user_id, &file).await, Ok(2));
+
+        match fpp.file_versions_fetch(&tenant_id, &user_id, &file.file_id).await {
+            Err(e) => {
+                error!("error fetching versions: {:?}", e);
+                assert!(false, "error fetching versions");
+            }
+            Ok(versions) => {
+                let numbers: Vec<i32> = versions.iter().map(|v| v.version).collect();
+                assert_eq!(numbers, vec![2, 1]);
+            }
+        }
+
+        // restoring adds the old content as new version
+        assert_eq!(fpp.file_version_restore(&tenant_id, &user_id, &file.file_id, 1).await, Ok(3));
+        assert!(fpp.file_get(&tenant_id, &user_id, &file.file_id).await
+            .is_ok_and(|f| f.version == 3 && f.size == 100 && f.digest == Some(first.clone())));
+        assert!(fpp.file_version_get(&tenant_id, &user_id, &file.file_id, 2).await
+            .is_ok_and(|v| v.digest == second));
+
+        assert!(matches!(
+            fpp.file_version_get(&tenant_id, &user_id, &file.file_id, 9).await,
+            Err(ProviderError::NotFound)
+        ));
+        assert!(matches!(
+            fpp.file_version_restore(&uuid::Uuid::new_v4(), &user_id, &file.file_id, 1).await,
+            Err(ProviderError::NotFound)
+        ));
+
+        // content of all versions is released
+        match fpp.file_delete(&tenant_id, &user_id, &file.file_id).await {
+            Err(e) => {
+                error!("error deleting file: {:?}", e);
+                assert!(false, "error deleting file");
+            }
+            Ok(mut unused) => {
+                unused.sort();
+                let mut expected = vec![first, second];
+                expected.sort();
+                assert_eq!(unused, expected);
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_folders() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let tenant_id = uuid::Uuid::nil();
+        let user_id = uuid::Uuid::new_v4();
+        let fpp = PostgresFileProvider::new(&dp);
+
+        let projects = file_provider::Folder::new(uuid::Uuid::new_v4(), "Projects".to_string());
+        let alpha = file_provider::Folder::new(uuid::Uuid::new_v4(), "Alpha".to_string())
+            .with_parent(&projects.folder_id);
+        let drafts = file_provider::Folder::new(uuid::Uuid::new_v4(), "Drafts".to_string())
+            .with_parent(&alpha.folder_id);
+        for folder in [&projects, &alpha, &drafts] {
+            if let Err(e) = fpp.folder_add(&tenant_id, &user_id, folder).await {
+                error!("error adding folder: {:?}", e);
+                assert!(false, "error adding folder");
+            }
+        }
+
+        // parents have to belong to the tenant
+        assert!(matches!(
+            fpp.folder_add(&uuid::Uuid::new_v4(), &user_id, &file_provider::Folder::new(uuid::Uuid::new_v4(), "Other".to_string())
+                .with_parent(&projects.folder_id)).await,
+            Err(ProviderError::Validation { .. })
+        ));
+
+        let file = file_provider::File::new(uuid::Uuid::new_v4(), "draft.txt".to_string())
+            .with_content("text/plain", 5, &format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple()));
+        if let Err(e) = fpp.file_add(&tenant_id, &user_id, &drafts.folder_id, &file).await {
+            error!("error adding file: {:?}", e);
+            assert!(false, "error adding file");
+        }
+
+        let names = |folders: Vec<file_provider::Folder>| -> Vec<String> {
+            return folders.into_iter().map(|f| f.name).collect();
+        };
+
+        assert_eq!(fpp.folder_path(&tenant_id, &user_id, &drafts.folder_id).await.map(names),
+            Ok(vec!["Projects".to_string(), "Alpha".to_string(), "Drafts".to_string()]));
+        assert_eq!(fpp.folder_path(&uuid::Uuid::new_v4(), &user_id, &drafts.folder_id).await.map(names),
+            Err(ProviderError::NotFound));
+
+        assert_eq!(fpp.folder_rename(&tenant_id, &user_id, &alpha.folder_id, "Beta").await, Ok(()));
+        assert!(matches!(
+            fpp.folder_rename(&tenant_id, &user_id, &alpha.folder_id, " ").await,
+            Err(ProviderError::Validation { .. })
+        ));
+
+        // a folder cannot be moved below itself
+        assert!(matches!(
+            fpp.folder_move(&tenant_id, &user_id, &projects.folder_id, &drafts.folder_id).await,
+            Err(ProviderError::Validation { .. })
+        ));
+        assert!(matches!(
+            fpp.folder_move(&tenant_id, &user_id, &projects.folder_id, &projects.folder_id).await,
+            Err(ProviderError::Validation { .. })
+        ));
+        assert_eq!(fpp.folder_move(&tenant_id, &user_id, &drafts.folder_id, &uuid::Uuid::nil()).await, Ok(()));
+        assert_eq!(fpp.folder_path(&tenant_id, &user_id, &drafts.folder_id).await.map(names),
+            Ok(vec!["Drafts".to_string()]));
+        assert_eq!(fpp.folder_move(&tenant_id, &user_id, &drafts.folder_id, &alpha.folder_id).await, Ok(()));
+
+        // the whole tree goes to the trash
+        assert_eq!(fpp.folder_trash(&tenant_id, &user_id, &projects.folder_id).await, Ok(()));
+        assert_eq!(fpp.folder_get(&tenant_id, &user_id, &alpha.folder_id).await.map(|f| f.name), Err(ProviderError::NotFound));
+        assert_eq!(fpp.file_get(&tenant_id, &user_id, &file.file_id).await.map(|f| f.name), Err(ProviderError::NotFound));
+        assert!(fpp.folder_trash_fetch(&tenant_id, &user_id).await
+            .is_ok_and(|t| t.iter().any(|f| f.folder_id == projects.folder_id && f.trashed.is_some())
+                && !t.iter().any(|f| f.folder_id == alpha.folder_id)));
+
+        // subfolders are restored with the folder only
+        assert_eq!(fpp.folder_restore(&tenant_id, &user_id, &alpha.folder_id).await, Err(ProviderError::NotFound));
+        assert_eq!(fpp.folder_restore(&tenant_id, &user_id, &projects.folder_id).await, Ok(()));
+        assert!(fpp.file_get(&tenant_id, &user_id, &file.file_id).await.is_ok());
+        assert_eq!(fpp.folder_path(&tenant_id, &user_id, &drafts.folder_id).await.map(names),
+            Ok(vec!["Projects".to_string(), "Beta".to_string(), "Drafts".to_string()]));
+
+        // a folder whose parent is in the trash is restored to the root
+        assert_eq!(fpp.folder_trash(&tenant_id, &user_id, &drafts.folder_id).await, Ok(()));
+        assert_eq!(fpp.folder_trash(&tenant_id, &user_id, &projects.folder_id).await, Ok(()));
+        assert_eq!(fpp.folder_restore(&tenant_id, &user_id, &drafts.folder_id).await, Ok(()));
+        assert!(fpp.folder_get(&tenant_id, &user_id, &drafts.folder_id).await.is_ok_and(|f| f.parent_folder_id.is_none()));
+        assert!(fpp.folder_list_files(&tenant_id, &user_id, &drafts.folder_id).await
+            .is_ok_and(|files| files.iter().any(|f| f.file_id == file.file_id)));
+
+        assert!(fpp.file_delete(&tenant_id, &user_id, &file.file_id).await.is_ok());
+    }
+
+    /// user of the tenant, optionally in a new role
+    async fn tenant_user(dp: &database_provider::DatabaseProvider, tenant_id: &uuid::Uuid, role_id: Option<uuid::Uuid>) -> uuid::Uuid {
+        let Some(database_provider::DatabaseType::Postgres(pool)) = dp.get_pool("main") else {
+            panic!("no database");
+        };
+
+        let user_id = uuid::Uuid::new_v4();
+        sqlx::query("call users.user_save($1,'Test','','User','','',0);")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .expect("unable to add user");
+        sqlx::query("call tenants.tenant_user_save($1,$2);")
+            .bind(tenant_id)
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .expect("unable to add user to tenant");
+
+        if let Some(role_id) = role_id {
+            sqlx::query("call tenants.role_save($1,$2,$3,'file_provider_postgres_test');")
+                .bind(tenant_id)
+                .bind(role_id)
+                .bind(format!("test_{}", role_id.simple()))
+                .execute(&pool)
+                .await
+                .expect("unable to add role");
+            sqlx::query("call tenants.role_users_add($1,$2);")
+                .bind(vec![role_id])
+                .bind(vec![user_id])
+                .execute(&pool)
+                .await
+                .expect("unable to add user to role");
+        }
+        return user_id;
+    }
+
+    #[actix_web::test]
+    async fn test_folder_access() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let tenant_id = uuid::Uuid::nil();
+        let fpp = PostgresFileProvider::new(&dp);
+
+        let role_id = uuid::Uuid::new_v4();
+        let owner = tenant_user(&dp, &tenant_id, None).await;
+        let member = tenant_user(&dp, &tenant_id, Some(role_id)).await;
+
+        let shared = file_provider::Folder::new(uuid::Uuid::new_v4(), "Shared".to_string());
+        let reports = file_provider::Folder::new(uuid::Uuid::new_v4(), "Reports".to_string())
+            .with_parent(&shared.folder_id);
+        for folder in [&shared, &reports] {
+            if let Err(e) = fpp.folder_add(&tenant_id, &owner, folder).await {
+                error!("error adding folder: {:?}", e);
+                assert!(false, "error adding folder");
+            }
+        }
+        assert!(fpp.folder_get(&tenant_id, &owner, &shared.folder_id).await.is_ok_and(|f| f.owner_id == Some(owner)));
+
+        let file = file_provider::File::new(uuid::Uuid::new_v4(), "q1.pdf".to_string())
+            .with_content("application/pdf", 10, &format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple()));
+        if let Err(e) = fpp.file_add(&tenant_id, &owner, &reports.folder_id, &file).await {
+            error!("error adding file: {:?}", e);
+            assert!(false, "error adding file");
+        }
+
+        // nothing is visible before it is shared
+        assert_eq!(fpp.folder_get(&tenant_id, &member, &reports.folder_id).await.map(|f| f.name), Err(ProviderError::NotFound));
+        assert_eq!(fpp.file_get(&tenant_id, &member, &file.file_id).await.map(|f| f.name), Err(ProviderError::NotFound));
+        let mine = file_provider::Folder::new(uuid::Uuid::new_v4(), "Mine".to_string())
+            .with_parent(&reports.folder_id);
+        assert!(matches!(fpp.folder_add(&tenant_id, &member, &mine).await, Err(ProviderError::Validation { .. })));
+
+        let grant = file_provider::FolderGrant {
+            folder_id: reports.folder_id,
+            grantee: file_provider::Grantee::User(member),
+            level: file_provider::AccessLevel::Read
         };
+        assert_eq!(fpp.folder_grant_save(&tenant_id, &owner, &grant).await, Ok(()));
 
-        if let Err(e) = fpp.file_add(&tenant_id, &folder_id, &file).await {
+        // a folder shared below a folder the user cannot see shows in the root
+        assert!(fpp.folder_list_folders(&tenant_id, &member, &uuid::Uuid::nil()).await
+            .is_ok_and(|folders| folders.iter().any(|f| f.folder_id == reports.folder_id)
+                && !folders.iter().any(|f| f.folder_id == shared.folder_id)));
+        assert!(fpp.folder_path(&tenant_id, &member, &reports.folder_id).await
+            .is_ok_and(|path| path.len() == 1 && path[0].folder_id == reports.folder_id));
+        assert!(fpp.file_get(&tenant_id, &member, &file.file_id).await.is_ok());
+
+        // reading does not allow changes
+        assert_eq!(fpp.folder_rename(&tenant_id, &member, &reports.folder_id, "Mine").await, Err(ProviderError::Forbidden));
+        assert_eq!(fpp.file_delete(&tenant_id, &member, &file.file_id).await, Err(ProviderError::Forbidden));
+        assert_eq!(fpp.folder_grant_save(&tenant_id, &member, &grant).await, Err(ProviderError::Forbidden));
+
+        // grants through roles are inherited
+        let role_grant = file_provider::FolderGrant {
+            folder_id: shared.folder_id,
+            grantee: file_provider::Grantee::Role(role_id),
+            level: file_provider::AccessLevel::Write
+        };
+        assert_eq!(fpp.folder_grant_save(&tenant_id, &owner, &role_grant).await, Ok(()));
+        assert_eq!(fpp.folder_rename(&tenant_id, &member, &reports.folder_id, "Quarterly").await, Ok(()));
+        assert_eq!(fpp.folder_trash(&tenant_id, &member, &reports.folder_id).await, Err(ProviderError::Forbidden));
+        assert_eq!(fpp.folder_grants_fetch(&tenant_id, &member, &reports.folder_id).await,
+            Ok(vec![grant.clone(), role_grant.clone()]));
+
+        // only users and roles of the tenant can be granted access
+        let outsider = file_provider::FolderGrant {
+            folder_id: shared.folder_id,
+            grantee: file_provider::Grantee::User(uuid::Uuid::new_v4()),
+            level: file_provider::AccessLevel::Read
+        };
+        assert!(matches!(fpp.folder_grant_save(&tenant_id, &owner, &outsider).await, Err(ProviderError::Validation { .. })));
+
+        assert_eq!(fpp.folder_grant_remove(&tenant_id, &owner, &shared.folder_id, &role_grant.grantee).await, Ok(()));
+        assert_eq!(fpp.folder_grant_remove(&tenant_id, &owner, &reports.folder_id, &grant.grantee).await, Ok(()));
+        assert_eq!(fpp.folder_grant_remove(&tenant_id, &owner, &reports.folder_id, &grant.grantee).await, Err(ProviderError::NotFound));
+        assert_eq!(fpp.file_get(&tenant_id, &member, &file.file_id).await.map(|f| f.name), Err(ProviderError::NotFound));
+
+        assert!(fpp.file_delete(&tenant_id, &owner, &file.file_id).await.is_ok());
+    }
+
+    #[actix_web::test]
+    async fn test_ownerless_access() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+        let Some(database_provider::DatabaseType::Postgres(pool)) = dp.get_pool("main") else {
+            panic!("no database");
+        };
+
+        let tenant_id = uuid::Uuid::nil();
+        let fpp = PostgresFileProvider::new(&dp);
+
+        let creator = tenant_user(&dp, &tenant_id, None).await;
+        let member = tenant_user(&dp, &tenant_id, None).await;
+
+        let archive = file_provider::Folder::new(uuid::Uuid::new_v4(), "Archive".to_string());
+        let digest = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+        let file = file_provider::File::new(uuid::Uuid::new_v4(), "old.pdf".to_string())
+            .with_content("application/pdf", 10, &digest);
+        let loose = file_provider::File::new(uuid::Uuid::new_v4(), "loose.pdf".to_string())
+            .with_content("application/pdf", 10, &digest);
+        assert_eq!(fpp.folder_add(&tenant_id, &creator, &archive).await, Ok(()));
+        assert_eq!(fpp.file_add(&tenant_id, &creator, &archive.folder_id, &file).await, Ok(()));
+        assert_eq!(fpp.file_add(&tenant_id, &creator, &uuid::Uuid::nil(), &loose).await, Ok(()));
+
+        // as left by the migration to access control
+        sqlx::query("update files.folders set owner_id = null where folder_id = $1;")
+            .bind(archive.folder_id)
+            .execute(&pool)
+            .await
+            .expect("unable to clear folder owner");
+        sqlx::query("update files.files set owner_id = null where file_id = any($1);")
+            .bind(vec![file.file_id, loose.file_id])
+            .execute(&pool)
+            .await
+            .expect("unable to clear file owners");
+
+        assert!(fpp.folder_list_folders(&tenant_id, &member, &uuid::Uuid::nil()).await
+            .is_ok_and(|folders| folders.iter().any(|f| f.folder_id == archive.folder_id)));
+        assert!(fpp.folder_list_files(&tenant_id, &member, &uuid::Uuid::nil()).await
+            .is_ok_and(|files| files.iter().any(|f| f.file_id == loose.file_id)));
+        assert!(fpp.file_get(&tenant_id, &member, &file.file_id).await.is_ok());
+        assert!(fpp.file_get(&tenant_id, &member, &loose.file_id).await.is_ok());
+
+        // writing is open to the tenant, managing is not
+        assert_eq!(fpp.folder_rename(&tenant_id, &member, &archive.folder_id, "Old").await, Ok(()));
+        let below = file_provider::Folder::new(uuid::Uuid::new_v4(), "2019".to_string())
+            .with_parent(&archive.folder_id);
+        assert_eq!(fpp.folder_add(&tenant_id, &member, &below).await, Ok(()));
+        assert!(fpp.folder_get(&tenant_id, &creator, &below.folder_id).await.is_ok());
+        assert_eq!(fpp.folder_trash(&tenant_id, &member, &archive.folder_id).await, Err(ProviderError::Forbidden));
+
+        assert!(fpp.file_delete(&tenant_id, &member, &file.file_id).await.is_ok());
+        assert!(fpp.file_delete(&tenant_id, &member, &loose.file_id).await.is_ok());
+    }
+
+    #[actix_web::test]
+    async fn test_share_links() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let tenant_id = uuid::Uuid::nil();
+        let fpp = PostgresFileProvider::new(&dp);
+
+        let owner = tenant_user(&dp, &tenant_id, None).await;
+        let other = tenant_user(&dp, &tenant_id, None).await;
+
+        let shared = file_provider::Folder::new(uuid::Uuid::new_v4(), "Shared".to_string());
+        let reports = file_provider::Folder::new(uuid::Uuid::new_v4(), "Reports".to_string())
+            .with_parent(&shared.folder_id);
+        for folder in [&shared, &reports] {
+            if let Err(e) = fpp.folder_add(&tenant_id, &owner, folder).await {
+                error!("error adding folder: {:?}", e);
+                assert!(false, "error adding folder");
+            }
+        }
+
+        let file = file_provider::File::new(uuid::Uuid::new_v4(), "q1.pdf".to_string())
+            .with_content("application/pdf", 10, &format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple()));
+        if let Err(e) = fpp.file_add(&tenant_id, &owner, &reports.folder_id, &file).await {
+            error!("error adding file: {:?}", e);
+            assert!(false, "error adding file");
+        }
+
+        let expires = chrono::Utc::now() + chrono::TimeDelta::days(1);
+        let target = file_provider::ShareTarget::Folder(shared.folder_id);
+
+        // sharing requires manage access and an expiry in the future
+        let link_id = uuid::Uuid::new_v4();
+        assert_eq!(fpp.share_link_add(&tenant_id, &other, &link_id, &target, &expires, None).await, Err(ProviderError::NotFound));
+        assert!(matches!(
+            fpp.share_link_add(&tenant_id, &owner, &link_id, &target, &(chrono::Utc::now() - chrono::TimeDelta::hours(1)), None).await,
+            Err(ProviderError::Validation { .. })
+        ));
+        if let Err(e) = fpp.share_link_add(&tenant_id, &owner, &link_id, &target, &expires, Some("secret")).await {
+            error!("error adding share link: {:?}", e);
+            assert!(false, "error adding share link");
+        }
+        assert!(fpp.share_links_fetch(&tenant_id, &owner, &target).await
+            .is_ok_and(|links| links.len() == 1 && links[0].link_id == link_id && links[0].password_protected));
+
+        // the password has to match
+        assert_eq!(fpp.share_link_open(&tenant_id, &link_id, None).await.map(|l| l.link_id), Err(ProviderError::Forbidden));
+        assert_eq!(fpp.share_link_open(&tenant_id, &link_id, Some("wrong")).await.map(|l| l.link_id), Err(ProviderError::Forbidden));
+        assert_eq!(fpp.share_link_open(&tenant_id, &link_id, Some("secret")).await.map(|l| l.target), Ok(target));
+        assert_eq!(fpp.share_link_open(&uuid::Uuid::new_v4(), &link_id, Some("secret")).await.map(|l| l.link_id), Err(ProviderError::NotFound));
+
+        // files below the folder are listed by their path
+        assert!(fpp.share_link_files(&tenant_id, &link_id).await
+            .is_ok_and(|files| files.len() == 1 && files[0].path == "Reports/q1.pdf"));
+        assert!(fpp.share_link_file_get(&tenant_id, &link_id, &file.file_id).await.is_ok());
+        assert_eq!(fpp.share_link_file_get(&tenant_id, &link_id, &uuid::Uuid::new_v4()).await.map(|f| f.name), Err(ProviderError::NotFound));
+
+        assert_eq!(fpp.share_link_access_add(&tenant_id, &link_id, None, file_provider::ShareOutcome::Listed, Some("127.0.0.1"), Some("test")).await, Ok(()));
+        assert_eq!(fpp.share_link_access_add(&tenant_id, &link_id, Some(&file.file_id), file_provider::ShareOutcome::Downloaded, None, None).await, Ok(()));
+        assert!(fpp.share_link_accesses_fetch(&tenant_id, &owner, &link_id).await
+            .is_ok_and(|accesses| accesses.len() == 2
+                && accesses.iter().any(|a| a.outcome == file_provider::ShareOutcome::Downloaded && a.file_id == Some(file.file_id))));
+        assert_eq!(fpp.share_link_accesses_fetch(&tenant_id, &other, &link_id).await.map(|a| a.len()), Err(ProviderError::NotFound));
+
+        // revoked links cannot be used any more
+        assert_eq!(fpp.share_link_revoke(&tenant_id, &other, &link_id).await, Err(ProviderError::NotFound));
+        assert_eq!(fpp.share_link_revoke(&tenant_id, &owner, &link_id).await, Ok(()));
+        assert_eq!(fpp.share_link_open(&tenant_id, &link_id, Some("secret")).await.map(|l| l.link_id), Err(ProviderError::NotFound));
+
+        // a link takes no password after five wrong ones
+        let locked = uuid::Uuid::new_v4();
+        assert_eq!(fpp.share_link_add(&tenant_id, &owner, &locked, &target, &expires, Some("secret")).await, Ok(()));
+        for _ in 0..5 {
+            assert_eq!(fpp.share_link_open(&tenant_id, &locked, Some("wrong")).await.map(|l| l.link_id), Err(ProviderError::Forbidden));
+        }
+        assert_eq!(fpp.share_link_open(&tenant_id, &locked, Some("secret")).await.map(|l| l.link_id), Err(ProviderError::Throttled));
+
+        // a hash made by crypt() is checked once more and replaced
+        let Some(database_provider::DatabaseType::Postgres(pool)) = dp.get_pool("main") else {
+            panic!("no database");
+        };
+        let legacy = uuid::Uuid::new_v4();
+        assert_eq!(fpp.share_link_add(&tenant_id, &owner, &legacy, &target, &expires, None).await, Ok(()));
+        sqlx::query("update files.share_links set password_hash = public.crypt('secret', public.gen_salt('bf')) where link_id = $1;")
+            .bind(legacy)
+            .execute(&pool)
+            .await
+            .expect("unable to set legacy hash");
+        assert_eq!(fpp.share_link_open(&tenant_id, &legacy, Some("wrong")).await.map(|l| l.link_id), Err(ProviderError::Forbidden));
+        assert_eq!(fpp.share_link_open(&tenant_id, &legacy, Some("secret")).await.map(|l| l.link_id), Ok(legacy));
+        let stored: String = sqlx::query_scalar("select password_hash from files.share_links where link_id = $1;")
+            .bind(legacy)
+            .fetch_one(&pool)
+            .await
+            .expect("unable to fetch hash");
+        assert!(stored.starts_with("$argon2id$"));
+        assert_eq!(fpp.share_link_open(&tenant_id, &legacy, Some("secret")).await.map(|l| l.link_id), Ok(legacy));
+
+        // a link to a file only makes that file available
+        let file_link = uuid::Uuid::new_v4();
+        assert_eq!(fpp.share_link_add(&tenant_id, &owner, &file_link, &file_provider::ShareTarget::File(file.file_id), &expires, Some("")).await, Ok(()));
+        assert!(fpp.share_link_open(&tenant_id, &file_link, None).await.is_ok_and(|l| !l.password_protected));
+        assert!(fpp.share_link_files(&tenant_id, &file_link).await
+            .is_ok_and(|files| files.len() == 1 && files[0].path == "q1.pdf"));
+
+        assert!(fpp.file_delete(&tenant_id, &owner, &file.file_id).await.is_ok());
+        assert_eq!(fpp.share_link_open(&tenant_id, &file_link, None).await.map(|l| l.link_id), Err(ProviderError::NotFound));
+    }
+
+    #[actix_web::test]
+    async fn test_attachments() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let tenant_id = uuid::Uuid::nil();
+        let fpp = PostgresFileProvider::new(&dp);
+
+        let owner = tenant_user(&dp, &tenant_id, None).await;
+        let other = tenant_user(&dp, &tenant_id, None).await;
+
+        let Some(database_provider::DatabaseType::Postgres(pool)) = dp.get_pool("main") else {
+            panic!("no database");
+        };
+        let partner_id = uuid::Uuid::new_v4();
+        sqlx::query("call crm.partner_save($1,$2,$3,'','','','','','');")
+            .bind(tenant_id)
+            .bind(partner_id)
+            .bind(format!("partner_{}", partner_id.simple()))
+            .execute(&pool)
+            .await
+            .expect("unable to add partner");
+
+        let file = file_provider::File::new(uuid::Uuid::new_v4(), "contract.pdf".to_string())
+            .with_content("application/pdf", 10, &format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple()));
+        if let Err(e) = fpp.file_add(&tenant_id, &owner, &uuid::Uuid::nil(), &file).await {
             error!("error adding file: {:?}", e);
             assert!(false, "error adding file");
         }
 
+        let partner = file_provider::EntityType::Partner;
+        assert_eq!(fpp.attachment_add(&tenant_id, &owner, partner, &partner_id, &file.file_id).await, Ok(()));
+        assert_eq!(fpp.attachment_add(&tenant_id, &owner, partner, &partner_id, &file.file_id).await, Ok(()));
+        assert_eq!(fpp.attachment_count(&tenant_id, &owner, partner, &partner_id).await, Ok(1));
+        assert!(fpp.attachments_fetch(&tenant_id, &owner, partner, &partner_id).await
+            .is_ok_and(|a| a.len() == 1 && a[0].file.file_id == file.file_id && a[0].attached_by == Some(owner)));
+
+        // records have to exist, files have to be visible
+        assert!(matches!(
+            fpp.attachment_add(&tenant_id, &owner, file_provider::EntityType::Invoice, &partner_id, &file.file_id).await,
+            Err(ProviderError::Validation { .. })
+        ));
+        assert_eq!(fpp.attachment_add(&tenant_id, &other, partner, &partner_id, &file.file_id).await, Err(ProviderError::NotFound));
+        assert_eq!(fpp.attachment_count(&tenant_id, &other, partner, &partner_id).await, Ok(0));
+
+        assert_eq!(fpp.attachment_remove(&tenant_id, &owner, partner, &partner_id, &file.file_id).await, Ok(()));
+        assert_eq!(fpp.attachment_remove(&tenant_id, &owner, partner, &partner_id, &file.file_id).await, Err(ProviderError::NotFound));
+
+        // deleting the file removes its attachments
+        assert_eq!(fpp.attachment_add(&tenant_id, &owner, partner, &partner_id, &file.file_id).await, Ok(()));
+        assert!(fpp.file_delete(&tenant_id, &owner, &file.file_id).await.is_ok());
+        assert_eq!(fpp.attachment_count(&tenant_id, &owner, partner, &partner_id).await, Ok(0));
+    }
+
+    #[actix_web::test]
+    async fn test_uploads() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let tenant_id = uuid::Uuid::nil();
+        let fpp = PostgresFileProvider::new(&dp);
+
+        let owner = tenant_user(&dp, &tenant_id, None).await;
+        let other = tenant_user(&dp, &tenant_id, None).await;
+
+        let upload = file_provider::FileUpload {
+            upload_id: uuid::Uuid::new_v4(),
+            file_id: uuid::Uuid::new_v4(),
+            folder_id: uuid::Uuid::nil(),
+            name: String::from("scan.pdf"),
+            content_type: String::from("application/pdf"),
+            length: 100,
+            offset: 0,
+            expires: chrono::Utc::now() + chrono::Duration::hours(1)
+        };
+        if let Err(e) = fpp.upload_add(&tenant_id, &owner, &upload).await {
+            error!("error adding upload: {:?}", e);
+            assert!(false, "error adding upload");
+        }
+        assert!(fpp.upload_fetch(&tenant_id, &owner, &upload.upload_id).await.is_ok_and(|u| u.name == upload.name && u.offset == 0));
+
+        // uploads are only visible to the user who started them
+        assert_eq!(fpp.upload_fetch(&tenant_id, &other, &upload.upload_id).await, Err(ProviderError::NotFound));
+        assert_eq!(fpp.upload_offset_save(&tenant_id, &other, &upload.upload_id, 0, 10).await, Err(ProviderError::NotFound));
+
+        assert_eq!(fpp.upload_offset_save(&tenant_id, &owner, &upload.upload_id, 0, 60).await, Ok(()));
+        assert_eq!(fpp.upload_offset_save(&tenant_id, &owner, &upload.upload_id, 0, 60).await, Err(ProviderError::Conflict));
+        assert!(matches!(
+            fpp.upload_offset_save(&tenant_id, &owner, &upload.upload_id, 60, 101).await,
+            Err(ProviderError::Validation { .. })
+        ));
+        assert!(fpp.upload_fetch(&tenant_id, &owner, &upload.upload_id).await.is_ok_and(|u| u.offset == 60));
+
+        // the file id is taken
+        assert_eq!(fpp.upload_add(&tenant_id, &owner, &upload).await, Err(ProviderError::Conflict));
+
+        assert_eq!(fpp.upload_remove(&tenant_id, &owner, &upload.upload_id).await, Ok(()));
+        assert_eq!(fpp.upload_remove(&tenant_id, &owner, &upload.upload_id).await, Err(ProviderError::NotFound));
+
+        // expired uploads are gone
+        let expired = file_provider::FileUpload {
+            upload_id: uuid::Uuid::new_v4(),
+            file_id: uuid::Uuid::new_v4(),
+            expires: chrono::Utc::now() - chrono::Duration::seconds(1),
+            ..upload
+        };
+        assert_eq!(fpp.upload_add(&tenant_id, &owner, &expired).await, Ok(()));
+        assert_eq!(fpp.upload_fetch(&tenant_id, &owner, &expired.upload_id).await, Err(ProviderError::NotFound));
+        assert!(fpp.uploads_expire().await.is_ok_and(|u| u.contains(&(tenant_id, expired.upload_id))));
+    }
+
+    #[actix_web::test]
+    async fn test_previews() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let tenant_id = uuid::Uuid::nil();
+        let fpp = PostgresFileProvider::new(&dp);
+
+        let owner = tenant_user(&dp, &tenant_id, None).await;
+        let other = tenant_user(&dp, &tenant_id, None).await;
+
+        let mut files = vec![];
+        for (name, content_type) in [("photo.png", "image/png"), ("scan.pdf", "application/pdf"), ("notes.txt", "text/plain")] {
+            let digest = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+            let file = file_provider::File::new(uuid::Uuid::new_v4(), name.to_string())
+                .with_content(content_type, 10, &digest);
+            if let Err(e) = fpp.file_add(&tenant_id, &owner, &uuid::Uuid::nil(), &file).await {
+                error!("error adding file: {:?}", e);
+                assert!(false, "error adding file");
+            }
+            files.push(file);
+        }
+        let [photo, scan, notes] = &files[..] else {
+            panic!("files missing");
+        };
+
+        // the worker claims previews of any tenant, there may be more
+        let mut claimed = vec![];
+        loop {
+            match fpp.previews_claim(100, 600).await {
+                Err(e) => {
+                    error!("error claiming previews: {:?}", e);
+                    assert!(false, "error claiming previews");
+                    break;
+                }
+                Ok(jobs) if jobs.is_empty() => break,
+                Ok(jobs) => claimed.extend(jobs)
+            }
+        }
+        assert!(claimed.iter().any(|j| Some(&j.digest) == photo.digest.as_ref() && j.source_type == "image/png" && j.attempts == 0));
+        assert!(claimed.iter().any(|j| Some(&j.digest) == scan.digest.as_ref()));
+        assert!(!claimed.iter().any(|j| Some(&j.digest) == notes.digest.as_ref()));
+
+        let photo_digest = photo.digest.clone().unwrap_or_default();
+        let scan_digest = scan.digest.clone().unwrap_or_default();
+        assert_eq!(fpp.preview_ready(&tenant_id, &photo_digest, "image/webp").await, Ok(()));
+        assert_eq!(fpp.preview_failed(&tenant_id, &scan_digest, "no renderer", None).await, Ok(()));
+
+        let preview = fpp.file_preview_fetch(&tenant_id, &owner, &photo.file_id).await;
+        assert!(preview.is_ok_and(|p| p.is_some_and(|p| p.state == file_provider::PreviewState::Ready && p.content_type.as_deref() == Some("image/webp"))));
+        assert_eq!(fpp.file_preview_fetch(&tenant_id, &owner, &notes.file_id).await, Ok(None));
+
+        // previews are as visible as their files
+        assert_eq!(fpp.file_preview_fetch(&tenant_id, &other, &photo.file_id).await, Err(ProviderError::NotFound));
+
+        match fpp.folder_list_files(&tenant_id, &owner, &uuid::Uuid::nil()).await {
+            Err(e) => {
+                error!("error listing files: {:?}", e);
+                assert!(false, "error listing files");
+            }
+            Ok(listed) => {
+                let state = |file_id: &uuid::Uuid| listed.iter().find(|f| f.file_id == *file_id).and_then(|f| f.preview);
+                assert_eq!(state(&photo.file_id), Some(file_provider::PreviewState::Ready));
+                assert_eq!(state(&scan.file_id), Some(file_provider::PreviewState::Failed));
+                assert_eq!(state(&notes.file_id), Some(file_provider::PreviewState::None));
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_search() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let tenant_id = uuid::Uuid::nil();
+        let fpp = PostgresFileProvider::new(&dp);
+
+        let owner = tenant_user(&dp, &tenant_id, None).await;
+        let other = tenant_user(&dp, &tenant_id, None).await;
+
+        let folder = file_provider::Folder::new(uuid::Uuid::new_v4(), String::from("Reports"));
+        if let Err(e) = fpp.folder_add(&tenant_id, &owner, &folder).await {
+            error!("error adding folder: {:?}", e);
+            assert!(false, "error adding folder");
+        }
+
+        // a word no other test uses, in the name of one file and the text of
+        // another
+        let word = format!("w{}", uuid::Uuid::new_v4().simple());
+        let mut files = vec![];
+        for (folder_id, name, content_type) in [
+            (folder.folder_id, String::from("summary.pdf"), "application/pdf"),
+            (uuid::Uuid::nil(), format!("{word}_notes.txt"), "text/plain"),
+            (uuid::Uuid::nil(), String::from("photo.png"), "image/png")
+        ] {
+            let digest = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+            let file = file_provider::File::new(uuid::Uuid::new_v4(), name)
+                .with_content(content_type, 10, &digest);
+            if let Err(e) = fpp.file_add(&tenant_id, &owner, &folder_id, &file).await {
+                error!("error adding file: {:?}", e);
+                assert!(false, "error adding file");
+            }
+            files.push(file);
+        }
+        let [summary, notes, photo] = &files[..] else {
+            panic!("files missing");
+        };
+
+        let mut claimed = vec![];
+        loop {
+            match fpp.texts_claim(100, 600).await {
+                Err(e) => {
+                    error!("error claiming texts: {:?}", e);
+                    assert!(false, "error claiming texts");
+                    break;
+                }
+                Ok(jobs) if jobs.is_empty() => break,
+                Ok(jobs) => claimed.extend(jobs)
+            }
+        }
+        assert!(claimed.iter().any(|j| Some(&j.digest) == summary.digest.as_ref() && j.source_type == "application/pdf"));
+        assert!(claimed.iter().any(|j| Some(&j.digest) == notes.digest.as_ref()));
+        assert!(!claimed.iter().any(|j| Some(&j.digest) == photo.digest.as_ref()));
+
+        let summary_digest = summary.digest.clone().unwrap_or_default();
+        let notes_digest = notes.digest.clone().unwrap_or_default();
+        let body = format!("Quarterly summary\nRevenue grew, see {word} for details");
+        assert_eq!(fpp.text_ready(&tenant_id, &summary_digest, &body).await, Ok(()));
+        assert_eq!(fpp.text_failed(&tenant_id, &notes_digest, "unreadable", None).await, Ok(()));
+
+        // matches in the name rank before those in the text
+        match fpp.file_search(&tenant_id, &owner, &word, 10, 0).await {
+            Err(e) => {
+                error!("error searching files: {:?}", e);
+                assert!(false, "error searching files");
+            }
+            Ok(found) => {
+                assert_eq!(found.iter().map(|r| r.file.file_id).collect::<Vec<_>>(), vec![notes.file_id, summary.file_id]);
+                assert!(found[0].rank > found[1].rank);
+                assert_eq!((found[0].folder_id, found[0].path.len(), found[0].snippet.as_deref()), (None, 0, None));
+                assert_eq!(found[1].folder_id, Some(folder.folder_id));
+                assert_eq!(found[1].path, vec![String::from("Reports")]);
+                let highlighted = format!("{}{}{}", file_provider::MATCH_START, word, file_provider::MATCH_END);
+                assert!(found[1].snippet.as_ref().is_some_and(|s| s.contains(&highlighted)));
+            }
+        }
+        assert!(fpp.file_search(&tenant_id, &owner, &word, 1, 1).await.is_ok_and(|found| found.len() == 1 && found[0].file.file_id == summary.file_id));
+        assert!(fpp.file_search(&tenant_id, &owner, &format!("revenue {word} -quarterly"), 10, 0).await.is_ok_and(|found| found.is_empty()));
+
+        // files are only found by the users who can see them
+        assert!(fpp.file_search(&tenant_id, &other, &word, 10, 0).await.is_ok_and(|found| found.is_empty()));
+    }
+
+    #[actix_web::test]
+    async fn test_usage() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let Some(database_provider::DatabaseType::Postgres(pool)) = dp.get_pool("main") else {
+            panic!("no database");
+        };
+        let tenant_id = uuid::Uuid::new_v4();
+        sqlx::query("call tenants.tenant_save($1,$2,'',0);")
+            .bind(tenant_id)
+            .bind(format!("tenant_{}", tenant_id.simple()))
+            .execute(&pool)
+            .await
+            .expect("unable to add tenant");
+
+        let fpp = PostgresFileProvider::new(&dp);
+        let owner = tenant_user(&dp, &tenant_id, None).await;
+
+        assert_eq!(fpp.usage_fetch(&tenant_id).await, Ok(file_provider::Usage { used: 0, files: 0, quota: None }));
+
+        // the same content is counted once
+        let digest = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+        for name in ["a.pdf", "b.pdf"] {
+            let file = file_provider::File::new(uuid::Uuid::new_v4(), name.to_string())
+                .with_content("application/pdf", 10, &digest);
+            if let Err(e) = fpp.file_add(&tenant_id, &owner, &uuid::Uuid::nil(), &file).await {
+                error!("error adding file: {:?}", e);
+                assert!(false, "error adding file");
+            }
+        }
+        assert_eq!(fpp.usage_fetch(&tenant_id).await, Ok(file_provider::Usage { used: 10, files: 2, quota: None }));
+
+        assert_eq!(fpp.quota_save(&tenant_id, Some(100)).await, Ok(()));
+        assert!(fpp.usage_fetch(&tenant_id).await.is_ok_and(|u| u.quota == Some(100)));
+        assert_eq!(fpp.quota_save(&tenant_id, None).await, Ok(()));
+        assert!(fpp.usage_fetch(&tenant_id).await.is_ok_and(|u| u.quota.is_none()));
 
+        assert!(matches!(fpp.quota_save(&tenant_id, Some(-1)).await, Err(ProviderError::Validation { .. })));
+        assert!(matches!(fpp.quota_save(&uuid::Uuid::new_v4(), Some(1)).await, Err(ProviderError::Validation { .. })));
     }
 }
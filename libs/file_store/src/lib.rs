@@ -1,14 +1,122 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
+#![allow(clippy::needless_return)]
+
+use std::pin::Pin;
+
+use bytes::Bytes;
+use futures_core::Stream;
+use serde::Serialize;
+
+use provider_error::ProviderError;
+
+pub mod sniff;
+
+
+/// file content, read or written in chunks
+pub type ByteStream<'a> = Pin<Box<dyn Stream<Item = Result<Bytes, ProviderError>> + 'a>>;
+
+/// futures are not `Send`, uploads are streamed straight from the request
+/// payload which is bound to the worker thread
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, ProviderError>> + 'a>>;
+
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileStat {
+    /// hex encoded sha-256 of the content
+    pub digest: String,
+    pub size: u64,
+    pub modified: chrono::DateTime<chrono::Utc>
+}
+
+
+/// true for a hex encoded sha-256 digest, the only keys stores accept
+pub fn is_digest(value: &str) -> bool {
+    return value.len() == 64 && value.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
+/// content of files, stored once per tenant and addressed by its sha-256
+/// digest; metadata such as the name and folder is kept by the
+/// `file_provider`, which also counts references to the content
+pub trait FileStore: Send + Sync {
+
+    /// store `data` under the digest computed while it is written, content
+    /// that is already stored is kept as is
+    fn put<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        data: ByteStream<'a>
+    ) -> StoreFuture<'a, FileStat>;
+
+    fn get<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str
+    ) -> StoreFuture<'a, ByteStream<'static>>;
+
+    /// `length` bytes starting at `offset`, the stream ends early when the
+    /// content is shorter
+    fn get_range<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str,
+        offset: u64,
+        length: u64
+    ) -> StoreFuture<'a, ByteStream<'static>>;
+
+    /// removes the content together with its preview
+    fn delete<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str
+    ) -> StoreFuture<'a, ()>;
+
+    fn stat<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str
+    ) -> StoreFuture<'a, FileStat>;
+
+    /// appends `data` to the partial upload `upload_id` at `offset`, which
+    /// has to be where the last successful append ended, 0 for a new
+    /// upload; returns the length of the partial upload. Bytes past `offset`
+    /// left by a failed append are dropped, an upload that is appended to
+    /// or completed at the same time is a `Conflict`
+    fn append<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        upload_id: uuid::Uuid,
+        offset: u64,
+        data: ByteStream<'a>
+    ) -> StoreFuture<'a, u64>;
+
+    /// turns the partial upload into content stored as `put` would have
+    /// stored it
+    fn complete<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        upload_id: uuid::Uuid
+    ) -> StoreFuture<'a, FileStat>;
+
+    /// removes the partial upload, an upload nothing was appended to yet is
+    /// not an error
+    fn discard<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        upload_id: uuid::Uuid
+    ) -> StoreFuture<'a, ()>;
+
+    /// stores the image rendered as preview of the content with `digest`
+    /// next to it, replacing an earlier preview
+    fn put_preview<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str,
+        data: ByteStream<'a>
+    ) -> StoreFuture<'a, ()>;
+
+    fn get_preview<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str
+    ) -> StoreFuture<'a, ByteStream<'static>>;
 }
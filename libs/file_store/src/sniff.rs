@@ -0,0 +1,182 @@
+/// number of leading bytes the content type is sniffed from
+pub const HEAD_LENGTH: usize = 512;
+
+
+/// container formats and the more specific types a client may declare for
+/// them, e.g. an xlsx file is sniffed as zip; entries ending in `.` match
+/// as prefix, others exactly. Plain text is never refined to a type a
+/// browser renders as active content (html, xhtml, svg)
+const REFINEMENTS: &[(&str, &[&str])] = &[
+    ("application/zip", &[
+        "application/vnd.openxmlformats-officedocument.",
+        "application/vnd.oasis.opendocument.",
+        "application/epub+zip",
+        "application/java-archive"
+    ]),
+    ("application/x-cfb", &[
+        "application/msword",
+        "application/vnd.ms-excel",
+        "application/vnd.ms-powerpoint",
+        "application/vnd.ms-outlook"
+    ]),
+    ("text/plain", &[
+        "text/plain",
+        "text/csv",
+        "text/markdown",
+        "application/json"
+    ])
+];
+
+
+/// content type of the content starting with `head`, the type the client
+/// declared is only used where it refines the sniffed one
+pub fn content_type(head: &[u8], declared: &str) -> String {
+    let sniffed = sniff(head);
+    let declared = declared
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    let refines = REFINEMENTS.iter()
+        .find(|(container, _)| *container == sniffed)
+        .is_some_and(|(_, types)| types.iter().any(|t| {
+            return if t.ends_with('.') { declared.starts_with(t) } else { declared == *t };
+        }));
+
+    return if refines { declared } else { String::from(sniffed) };
+}
+
+
+/// content type recognised from the leading bytes, `text/plain` for utf-8
+/// text and `application/octet-stream` for anything else
+pub fn sniff(head: &[u8]) -> &'static str {
+    let at = |offset: usize, magic: &[u8]| head.get(offset..offset + magic.len()) == Some(magic);
+
+    let magic: &[(&[u8], &'static str)] = &[
+        (b"%PDF-", "application/pdf"),
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"II*\x00", "image/tiff"),
+        (b"MM\x00*", "image/tiff"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"PK\x05\x06", "application/zip"),
+        (b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", "application/x-cfb"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"Rar!\x1a\x07", "application/vnd.rar"),
+        (b"{\\rtf", "application/rtf"),
+        (b"%!PS", "application/postscript"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"ID3", "audio/mpeg"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+        (b"\x7fELF", "application/x-executable"),
+        (b"MZ", "application/x-msdownload")
+    ];
+    if let Some((_, content_type)) = magic.iter().find(|(m, _)| at(0, m)) {
+        return content_type;
+    }
+
+    if at(0, b"RIFF") {
+        if at(8, b"WEBP") {
+            return "image/webp";
+        }
+        if at(8, b"WAVE") {
+            return "audio/wav";
+        }
+        if at(8, b"AVI ") {
+            return "video/x-msvideo";
+        }
+    }
+
+    if at(4, b"ftyp") {
+        return match head.get(8..12) {
+            Some(b"heic") | Some(b"heix") | Some(b"mif1") => "image/heic",
+            Some(b"qt  ") => "video/quicktime",
+            _ => "video/mp4"
+        };
+    }
+
+    let text = head.strip_prefix(b"\xef\xbb\xbf").unwrap_or(head);
+    if is_text(text) {
+        let start = String::from_utf8_lossy(&text[..text.len().min(64)])
+            .trim_start()
+            .to_ascii_lowercase();
+        if start.starts_with("<!doctype html") || start.starts_with("<html") {
+            return "text/html";
+        }
+        if start.starts_with("<svg") {
+            return "image/svg+xml";
+        }
+        if start.starts_with("<?xml") {
+            return if String::from_utf8_lossy(text).contains("<svg") { "image/svg+xml" } else { "application/xml" };
+        }
+        return "text/plain";
+    }
+
+    return "application/octet-stream";
+}
+
+
+/// utf-8 without control characters other than whitespace, a character cut
+/// off at the end of `head` is allowed
+fn is_text(head: &[u8]) -> bool {
+    let valid = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            // the head ends within a character
+            std::str::from_utf8(&head[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return false
+    };
+
+    return !valid.chars().any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t' | '\x0c'));
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff(b"%PDF-1.7\n..."), "application/pdf");
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"), "image/png");
+        assert_eq!(sniff(b"RIFF\x00\x00\x00\x00WEBPVP8 "), "image/webp");
+        assert_eq!(sniff(b"\x00\x00\x00\x18ftypmp42"), "video/mp4");
+        assert_eq!(sniff(b"MZ\x90\x00"), "application/x-msdownload");
+        assert_eq!(sniff("name;amount\nCafé;3\n".as_bytes()), "text/plain");
+        assert_eq!(sniff(b"  <!DOCTYPE html><html>"), "text/html");
+        assert_eq!(sniff(b"<?xml version=\"1.0\"?>\n<svg xmlns="), "image/svg+xml");
+        assert_eq!(sniff(b"\x00\x01\x02\x03"), "application/octet-stream");
+
+        // a multibyte character cut off by the head is still text
+        assert_eq!(sniff(&"ü".repeat(10).as_bytes()[..19]), "text/plain");
+    }
+
+    #[test]
+    fn test_content_type() {
+        // declared types only refine the sniffed container
+        let xlsx = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+        assert_eq!(content_type(b"PK\x03\x04\x14\x00", xlsx), xlsx);
+        assert_eq!(content_type(b"a;b\n1;2\n", "text/csv; charset=utf-8"), "text/csv");
+        assert_eq!(content_type(b"\x89PNG\r\n\x1a\n", "application/pdf"), "image/png");
+        assert_eq!(content_type(b"MZ\x90\x00", "image/png"), "application/x-msdownload");
+        assert_eq!(content_type(b"<html><script>", "text/plain"), "text/html");
+        assert_eq!(content_type(b"# Notes\n", "text/markdown"), "text/markdown");
+        assert_eq!(content_type(b"{\"a\": 1}", "application/json"), "application/json");
+
+        // text is never refined to active content
+        assert_eq!(content_type(b"alert(document.cookie)", "text/html"), "text/plain");
+        assert_eq!(content_type(b"alert(document.cookie)", "text/html; charset=utf-8"), "text/plain");
+        assert_eq!(content_type(b"alert(document.cookie)", "application/xhtml+xml"), "text/plain");
+        assert_eq!(content_type(b"alert(document.cookie)", "image/svg+xml"), "text/plain");
+        assert_eq!(content_type(b"alert(document.cookie)", "text/javascript"), "text/plain");
+        assert_eq!(content_type(b"alert(document.cookie)", "text/csvx"), "text/plain");
+    }
+}
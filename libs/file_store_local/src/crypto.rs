@@ -0,0 +1,428 @@
+//! envelope encryption of stored content: every tenant has a data key of
+//! its own, kept wrapped by a master key in `<tenant_id>/.key`, so that
+//! replacing the master key only rewraps the data keys
+//!
+//! Content and previews are split into segments of `SEGMENT_SIZE` bytes,
+//! each encrypted with AES-256-GCM under a nonce made of a random prefix,
+//! the segment number and whether it is the last one; segments can be read
+//! on their own for ranges, and dropped or reordered segments fail to
+//! decrypt. Partial uploads, which are appended to at arbitrary offsets,
+//! are encrypted with AES-256-CTR instead.
+
+use base64::Engine;
+use openssl::symm::{Cipher, Crypter, Mode};
+
+
+pub const KEY_SIZE: usize = 32;
+
+/// plaintext bytes per segment of encrypted content
+pub(crate) const SEGMENT_SIZE: usize = 64 * 1024;
+const TAG_SIZE: usize = 16;
+const NONCE_SIZE: usize = 12;
+const PREFIX_SIZE: usize = 8;
+/// the segment number's highest bit marks the last segment
+const LAST_SEGMENT: u32 = 1 << 31;
+
+const CONTENT_MAGIC: &[u8; 8] = b"NXSENC01";
+/// magic followed by the nonce prefix
+pub(crate) const CONTENT_HEADER_SIZE: usize = CONTENT_MAGIC.len() + PREFIX_SIZE;
+
+const UPLOAD_MAGIC: &[u8; 8] = b"NXSUPL01";
+const IV_SIZE: usize = 16;
+/// magic followed by the counter block the keystream starts with
+pub(crate) const UPLOAD_HEADER_SIZE: usize = UPLOAD_MAGIC.len() + IV_SIZE;
+
+
+/// key wrapping the data keys of all tenants, identified by `id` in the
+/// wrapped keys so that older master keys can still unwrap them
+pub struct MasterKey {
+    id: String,
+    key: [u8; KEY_SIZE]
+}
+
+impl MasterKey {
+
+    pub fn new(id: &str, key: &[u8]) -> Result<Self, String> {
+        if id.is_empty() || id.contains(':') {
+            return Err(format!("master key id '{}' is empty or contains ':'", id));
+        }
+        let key: [u8; KEY_SIZE] = key.try_into()
+            .map_err(|_| format!("master key {} is not {} bytes long", id, KEY_SIZE))?;
+        return Ok(Self {
+            id: String::from(id),
+            key
+        });
+    }
+
+    /// reads a key of `KEY_SIZE` random bytes, base64 encoded, as written
+    /// by `openssl rand -base64 32`
+    pub fn from_file(id: &str, path: &str) -> Result<Self, String> {
+        let encoded = std::fs::read_to_string(path)
+            .map_err(|e| format!("unable to read {}: {}", path, e))?;
+        let key = base64::engine::general_purpose::STANDARD.decode(encoded.trim())
+            .map_err(|e| format!("{} is not base64 encoded: {}", path, e))?;
+        return Self::new(id, &key);
+    }
+
+    pub fn id(&self) -> &str {
+        return &self.id;
+    }
+}
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.debug_struct("MasterKey").field("id", &self.id).finish_non_exhaustive();
+    }
+}
+
+
+/// key encrypting the content of one tenant
+#[derive(Clone)]
+pub(crate) struct DataKey([u8; KEY_SIZE]);
+
+impl DataKey {
+
+    pub(crate) fn generate() -> Result<Self, String> {
+        let mut key = [0u8; KEY_SIZE];
+        random(&mut key)?;
+        return Ok(Self(key));
+    }
+}
+
+
+/// the data key encrypted with `master`, bound to the tenant, as stored in
+/// the tenant's key file: `<master key id>:<base64 of nonce, key and tag>`
+pub(crate) fn wrap(master: &MasterKey, tenant_id: &uuid::Uuid, key: &DataKey) -> Result<String, String> {
+    let mut nonce = [0u8; NONCE_SIZE];
+    random(&mut nonce)?;
+
+    let mut tag = [0u8; TAG_SIZE];
+    let wrapped = openssl::symm::encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &master.key,
+        Some(&nonce),
+        wrapping_aad(tenant_id, &master.id).as_bytes(),
+        &key.0,
+        &mut tag
+    ).map_err(|e| format!("unable to wrap data key: {}", e))?;
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode([nonce.as_slice(), &wrapped, &tag].concat());
+    return Ok(format!("{}:{}\n", master.id, encoded));
+}
+
+/// the data key in a key file, unwrapped with whichever of `masters` it was
+/// wrapped with
+pub(crate) fn unwrap(masters: &[MasterKey], tenant_id: &uuid::Uuid, stored: &str) -> Result<DataKey, String> {
+    let (master_id, encoded) = stored.trim().rsplit_once(':')
+        .ok_or_else(|| String::from("malformed key file"))?;
+    let master = masters.iter()
+        .find(|m| m.id == master_id)
+        .ok_or_else(|| format!("data key is wrapped with master key {}, which is not configured", master_id))?;
+
+    let wrapped = base64::engine::general_purpose::STANDARD.decode(encoded)
+        .map_err(|e| format!("malformed key file: {}", e))?;
+    if wrapped.len() != NONCE_SIZE + KEY_SIZE + TAG_SIZE {
+        return Err(String::from("malformed key file"));
+    }
+    let (nonce, rest) = wrapped.split_at(NONCE_SIZE);
+    let (key, tag) = rest.split_at(KEY_SIZE);
+
+    let key = openssl::symm::decrypt_aead(
+        Cipher::aes_256_gcm(),
+        &master.key,
+        Some(nonce),
+        wrapping_aad(tenant_id, master_id).as_bytes(),
+        key,
+        tag
+    ).map_err(|_| format!("unable to unwrap data key with master key {}", master_id))?;
+
+    return key.try_into()
+        .map(DataKey)
+        .map_err(|_| String::from("malformed key file"));
+}
+
+/// id of the master key the key file was wrapped with
+pub(crate) fn wrapped_with(stored: &str) -> Option<&str> {
+    return stored.trim().rsplit_once(':').map(|(id, _)| id);
+}
+
+fn wrapping_aad(tenant_id: &uuid::Uuid, master_id: &str) -> String {
+    return format!("{}:{}", tenant_id.simple(), master_id);
+}
+
+
+/// whether `header`, the start of a stored file, is that of encrypted
+/// content
+pub(crate) fn is_encrypted_content(header: &[u8]) -> bool {
+    return header.starts_with(CONTENT_MAGIC);
+}
+
+/// whether `header`, the start of a partial upload, is that of an
+/// encrypted one
+pub(crate) fn is_encrypted_upload(header: &[u8]) -> bool {
+    return header.starts_with(UPLOAD_MAGIC);
+}
+
+
+/// number of segments and plaintext size of encrypted content that is
+/// `length` bytes long, `None` for a length no encrypted content has
+pub(crate) fn content_layout(length: u64) -> Option<(u64, u64)> {
+    let body = length.checked_sub(CONTENT_HEADER_SIZE as u64)?;
+    let segment = (SEGMENT_SIZE + TAG_SIZE) as u64;
+    // empty content still has a last segment, holding just the tag
+    let count = body.div_ceil(segment).max(1);
+    let last = body.checked_sub((count - 1) * segment)?;
+    if last < TAG_SIZE as u64 {
+        return None;
+    }
+    return Some((count, body - count * TAG_SIZE as u64));
+}
+
+/// offset and length of segment `index` of `count` in the stored file
+pub(crate) fn segment_span(index: u64, count: u64, length: u64) -> (u64, usize) {
+    let segment = (SEGMENT_SIZE + TAG_SIZE) as u64;
+    let offset = CONTENT_HEADER_SIZE as u64 + index * segment;
+    if index + 1 == count {
+        return (offset, usize::try_from(length - offset).unwrap_or_default());
+    }
+    return (offset, SEGMENT_SIZE + TAG_SIZE);
+}
+
+
+/// encrypts content written in pieces of any size
+pub(crate) struct Encryptor {
+    key: DataKey,
+    prefix: [u8; PREFIX_SIZE],
+    index: u32,
+    /// plaintext not yet encrypted, a segment is only encrypted once it is
+    /// known whether it is the last one
+    pending: Vec<u8>
+}
+
+impl Encryptor {
+
+    /// the encryptor and the header the encrypted content starts with
+    pub(crate) fn new(key: &DataKey) -> Result<(Self, Vec<u8>), String> {
+        let mut prefix = [0u8; PREFIX_SIZE];
+        random(&mut prefix)?;
+
+        let encryptor = Self {
+            key: key.clone(),
+            prefix,
+            index: 0,
+            pending: Vec::with_capacity(SEGMENT_SIZE)
+        };
+        return Ok((encryptor, [CONTENT_MAGIC.as_slice(), &prefix].concat()));
+    }
+
+    /// encrypted segments completed by `data`
+    pub(crate) fn update(&mut self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut encrypted = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            if self.pending.len() == SEGMENT_SIZE {
+                let segment = std::mem::take(&mut self.pending);
+                encrypted.extend(self.seal(&segment, false)?);
+            }
+            let take = rest.len().min(SEGMENT_SIZE - self.pending.len());
+            self.pending.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+        }
+        return Ok(encrypted);
+    }
+
+    /// the last segment
+    pub(crate) fn finish(mut self) -> Result<Vec<u8>, String> {
+        let segment = std::mem::take(&mut self.pending);
+        return self.seal(&segment, true);
+    }
+
+    fn seal(&mut self, segment: &[u8], last: bool) -> Result<Vec<u8>, String> {
+        if self.index >= LAST_SEGMENT {
+            return Err(String::from("content is too large to encrypt"));
+        }
+        let nonce = segment_nonce(&self.prefix, self.index, last);
+        self.index += 1;
+
+        let mut tag = [0u8; TAG_SIZE];
+        let mut sealed = openssl::symm::encrypt_aead(Cipher::aes_256_gcm(), &self.key.0, Some(&nonce), &[], segment, &mut tag)
+            .map_err(|e| format!("unable to encrypt content: {}", e))?;
+        sealed.extend_from_slice(&tag);
+        return Ok(sealed);
+    }
+}
+
+
+/// plaintext of segment `index` of encrypted content starting with `header`
+pub(crate) fn open_segment(key: &DataKey, header: &[u8], index: u64, last: bool, segment: &[u8]) -> Result<Vec<u8>, String> {
+    let prefix = header.get(CONTENT_MAGIC.len()..CONTENT_HEADER_SIZE)
+        .and_then(|p| <[u8; PREFIX_SIZE]>::try_from(p).ok())
+        .ok_or_else(|| String::from("malformed content header"))?;
+    let index = u32::try_from(index)
+        .ok()
+        .filter(|i| *i < LAST_SEGMENT)
+        .ok_or_else(|| String::from("malformed content"))?;
+    let Some(split) = segment.len().checked_sub(TAG_SIZE) else {
+        return Err(String::from("truncated segment"));
+    };
+    let (data, tag) = segment.split_at(split);
+
+    return openssl::symm::decrypt_aead(Cipher::aes_256_gcm(), &key.0, Some(&segment_nonce(&prefix, index, last)), &[], data, tag)
+        .map_err(|_| format!("segment {} does not decrypt, the content was altered", index));
+}
+
+fn segment_nonce(prefix: &[u8; PREFIX_SIZE], index: u32, last: bool) -> [u8; NONCE_SIZE] {
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce[..PREFIX_SIZE].copy_from_slice(prefix);
+    let index = if last { index | LAST_SEGMENT } else { index };
+    nonce[PREFIX_SIZE..].copy_from_slice(&index.to_be_bytes());
+    return nonce;
+}
+
+
+/// header of a new encrypted partial upload
+pub(crate) fn upload_header() -> Result<Vec<u8>, String> {
+    let mut iv = [0u8; IV_SIZE];
+    random(&mut iv)?;
+    return Ok([UPLOAD_MAGIC.as_slice(), &iv].concat());
+}
+
+/// en- and decrypts a partial upload starting with `header` from byte
+/// `position` of its plaintext on; the keystream of a position is the same
+/// every time, so rewriting a range after a failed append reuses it
+pub(crate) struct UploadCipher {
+    crypter: Crypter
+}
+
+impl UploadCipher {
+
+    pub(crate) fn new(key: &DataKey, header: &[u8], position: u64) -> Result<Self, String> {
+        let iv = header.get(UPLOAD_MAGIC.len()..UPLOAD_HEADER_SIZE)
+            .and_then(|iv| <[u8; IV_SIZE]>::try_from(iv).ok())
+            .ok_or_else(|| String::from("malformed upload header"))?;
+
+        // the counter block of the position, the keystream of the bytes
+        // before it within the block is skipped
+        let block = u128::from_be_bytes(iv).wrapping_add(u128::from(position / 16));
+        let mut crypter = Crypter::new(Cipher::aes_256_ctr(), Mode::Encrypt, &key.0, Some(&block.to_be_bytes()))
+            .map_err(|e| format!("unable to set up cipher: {}", e))?;
+        let skip = usize::try_from(position % 16).unwrap_or_default();
+        if skip > 0 {
+            let mut discarded = [0u8; 32];
+            crypter.update(&[0u8; 16][..skip], &mut discarded)
+                .map_err(|e| format!("unable to set up cipher: {}", e))?;
+        }
+        return Ok(Self { crypter });
+    }
+
+    pub(crate) fn apply(&mut self, data: &[u8]) -> Result<Vec<u8>, String> {
+        // a stream cipher never holds data back, the block size is only
+        // reserved because openssl asks for it
+        let mut output = vec![0u8; data.len() + 16];
+        let length = self.crypter.update(data, &mut output)
+            .map_err(|e| format!("unable to apply cipher: {}", e))?;
+        output.truncate(length);
+        return Ok(output);
+    }
+}
+
+
+fn random(buf: &mut [u8]) -> Result<(), String> {
+    return openssl::rand::rand_bytes(buf).map_err(|e| format!("no random bytes: {}", e));
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypt(key: &DataKey, pieces: &[&[u8]]) -> Vec<u8> {
+        let (mut encryptor, mut encrypted) = Encryptor::new(key).unwrap();
+        for piece in pieces {
+            encrypted.extend(encryptor.update(piece).unwrap());
+        }
+        encrypted.extend(encryptor.finish().unwrap());
+        return encrypted;
+    }
+
+    fn decrypt(key: &DataKey, encrypted: &[u8]) -> Result<Vec<u8>, String> {
+        let (count, _) = content_layout(encrypted.len() as u64).ok_or("malformed")?;
+        let mut plain = Vec::new();
+        for index in 0..count {
+            let (offset, length) = segment_span(index, count, encrypted.len() as u64);
+            let offset = offset as usize;
+            plain.extend(open_segment(key, encrypted, index, index + 1 == count, &encrypted[offset..offset + length])?);
+        }
+        return Ok(plain);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let tenant_id = uuid::Uuid::new_v4();
+        let old = MasterKey::new("2026-01", &[1; KEY_SIZE]).unwrap();
+        let new = MasterKey::new("2026-07", &[2; KEY_SIZE]).unwrap();
+        let key = DataKey::generate().unwrap();
+
+        let stored = wrap(&old, &tenant_id, &key).unwrap();
+        assert_eq!(wrapped_with(&stored), Some("2026-01"));
+        assert!(unwrap(&[new], &tenant_id, &stored).is_err());
+
+        let masters = [old, MasterKey::new("2026-07", &[2; KEY_SIZE]).unwrap()];
+        assert!(unwrap(&masters, &tenant_id, &stored).is_ok_and(|k| k.0 == key.0));
+        // a key file is only valid for its tenant
+        assert!(unwrap(&masters, &uuid::Uuid::new_v4(), &stored).is_err());
+
+        let rewrapped = wrap(&masters[1], &tenant_id, &unwrap(&masters, &tenant_id, &stored).unwrap()).unwrap();
+        assert!(unwrap(&masters[1..], &tenant_id, &rewrapped).is_ok_and(|k| k.0 == key.0));
+
+        assert!(MasterKey::new("a:b", &[0; KEY_SIZE]).is_err());
+        assert!(MasterKey::new("short", &[0; 16]).is_err());
+    }
+
+    #[test]
+    fn test_content() {
+        let key = DataKey::generate().unwrap();
+
+        for length in [0, 1, SEGMENT_SIZE - 1, SEGMENT_SIZE, SEGMENT_SIZE + 1, 3 * SEGMENT_SIZE] {
+            let plain: Vec<u8> = (0..length).map(|i| (i % 251) as u8).collect();
+            let (head, tail) = plain.split_at(length / 3);
+            let encrypted = encrypt(&key, &[head, tail]);
+
+            assert!(is_encrypted_content(&encrypted));
+            assert_eq!(content_layout(encrypted.len() as u64).map(|(_, size)| size), Some(length as u64));
+            assert_eq!(decrypt(&key, &encrypted), Ok(plain));
+        }
+
+        // cutting off the last segment is noticed
+        let plain = vec![7u8; 2 * SEGMENT_SIZE + 5];
+        let encrypted = encrypt(&key, &[&plain]);
+        assert!(decrypt(&key, &encrypted[..CONTENT_HEADER_SIZE + 2 * (SEGMENT_SIZE + TAG_SIZE)]).is_err());
+
+        let mut altered = encrypted.clone();
+        altered[CONTENT_HEADER_SIZE + 3] ^= 1;
+        assert!(decrypt(&key, &altered).is_err());
+        assert!(decrypt(&DataKey::generate().unwrap(), &encrypted).is_err());
+
+        assert_eq!(content_layout(CONTENT_HEADER_SIZE as u64 + 3), None);
+    }
+
+    #[test]
+    fn test_upload_cipher() {
+        let key = DataKey::generate().unwrap();
+        let header = upload_header().unwrap();
+        assert!(is_encrypted_upload(&header));
+
+        let plain: Vec<u8> = (0..100u8).collect();
+        let encrypted = UploadCipher::new(&key, &header, 0).unwrap().apply(&plain).unwrap();
+        assert_ne!(encrypted, plain);
+
+        // appends at any offset continue the keystream
+        let mut pieces = Vec::new();
+        for (from, to) in [(0, 17), (17, 64), (64, 100)] {
+            pieces.extend(UploadCipher::new(&key, &header, from).unwrap().apply(&plain[from as usize..to]).unwrap());
+        }
+        assert_eq!(pieces, encrypted);
+        assert_eq!(UploadCipher::new(&key, &header, 0).unwrap().apply(&encrypted).unwrap(), plain);
+    }
+}
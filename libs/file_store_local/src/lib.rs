@@ -1,14 +1,1012 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
+#![allow(clippy::needless_return)]
+
+use tracing::{
+    info,
+    debug,
+    error
+};
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use futures::{StreamExt, TryStreamExt};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use file_store::{ByteStream, FileStat, FileStore, StoreFuture};
+use provider_error::ProviderError;
+
+mod crypto;
+
+pub use crypto::MasterKey;
+
+
+const TEMP_DIR: &str = ".tmp";
+const UPLOADS_DIR: &str = ".uploads";
+const KEY_FILE: &str = ".key";
+
+
+/// stores file content below `root` as `<tenant_id>/<xx>/<digest>`, where
+/// `xx` are the first two characters of the digest; paths are built from
+/// ids and digests only, names supplied by clients never reach the file
+/// system. Previews are kept next to the content as `<digest>.preview` and
+/// partial uploads as `<tenant_id>/.uploads/<upload_id>`.
+///
+/// With master keys, everything written is encrypted with the tenant's data
+/// key, kept wrapped in `<tenant_id>/.key`; content written before stays
+/// readable as it is
+pub struct LocalFileStore {
+    root: PathBuf,
+    /// partial uploads being appended to or completed
+    uploads: Mutex<HashSet<uuid::Uuid>>,
+    /// oldest first, the last one wraps new data keys
+    master_keys: Vec<MasterKey>,
+    /// unwrapped data keys by tenant
+    data_keys: Mutex<HashMap<uuid::Uuid, crypto::DataKey>>
+}
+
+
+/// marks a partial upload as in use until dropped
+struct UploadLock<'a> {
+    uploads: &'a Mutex<HashSet<uuid::Uuid>>,
+    upload_id: uuid::Uuid
+}
+
+impl Drop for UploadLock<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut uploads) = self.uploads.lock() {
+            uploads.remove(&self.upload_id);
+        }
+    }
+}
+
+
+impl LocalFileStore {
+
+    pub fn new(root: &str) -> Self {
+        return Self {
+            root: PathBuf::from(root),
+            uploads: Mutex::new(HashSet::new()),
+            master_keys: vec![],
+            data_keys: Mutex::new(HashMap::new())
+        };
+    }
+
+    /// encrypts content with data keys wrapped by the last of `master_keys`,
+    /// the others only unwrap keys wrapped before
+    pub fn with_master_keys(mut self, master_keys: Vec<MasterKey>) -> Self {
+        self.master_keys = master_keys;
+        return self;
+    }
+
+    /// rewraps the data keys of all tenants with the newest master key,
+    /// the content itself is left as it is; returns the number of keys
+    /// rewrapped
+    pub async fn rotate_keys(&self) -> Result<usize, ProviderError> {
+        info!("rotate_keys");
+
+        let Some(master) = self.master_keys.last() else {
+            return Err(ProviderError::validation("master_keys", "no master key configured"));
+        };
+
+        let mut entries = match tokio::fs::read_dir(&self.root).await {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(from_io(&e, "read directory")),
+            Ok(entries) => entries
+        };
+
+        let mut rotated = 0;
+        while let Some(entry) = entries.next_entry().await.map_err(|e| from_io(&e, "read directory"))? {
+            let Ok(tenant_id) = uuid::Uuid::try_parse(&entry.file_name().to_string_lossy()) else {
+                continue;
+            };
+            let stored = match tokio::fs::read_to_string(self.key_path(&tenant_id)).await {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(from_io(&e, "read key file")),
+                Ok(stored) => stored
+            };
+            if crypto::wrapped_with(&stored) == Some(master.id()) {
+                continue;
+            }
+
+            let key = crypto::unwrap(&self.master_keys, &tenant_id, &stored).map_err(|e| from_crypto(&e))?;
+            let rewrapped = crypto::wrap(master, &tenant_id, &key).map_err(|e| from_crypto(&e))?;
+            self.write_key_file(&tenant_id, &rewrapped, true).await?;
+
+            debug!("rewrapped data key of tenant {} with master key {}", tenant_id, master.id());
+            rotated += 1;
+        }
+        return Ok(rotated);
+    }
+
+    fn key_path(&self, tenant_id: &uuid::Uuid) -> PathBuf {
+        return self.root.join(tenant_id.simple().to_string()).join(KEY_FILE);
+    }
+
+    /// the tenant's data key, `None` while it has none
+    async fn data_key(&self, tenant_id: &uuid::Uuid) -> Result<Option<crypto::DataKey>, ProviderError> {
+        if let Some(key) = self.data_keys.lock().map_err(|_| ProviderError::Internal)?.get(tenant_id) {
+            return Ok(Some(key.clone()));
+        }
+
+        let stored = match tokio::fs::read_to_string(self.key_path(tenant_id)).await {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(from_io(&e, "read key file")),
+            Ok(stored) => stored
+        };
+        let key = crypto::unwrap(&self.master_keys, tenant_id, &stored)
+            .map_err(|e| from_crypto(&format!("tenant {}: {}", tenant_id, e)))?;
+
+        self.data_keys.lock().map_err(|_| ProviderError::Internal)?.insert(*tenant_id, key.clone());
+        return Ok(Some(key));
+    }
+
+    /// the data key to read encrypted content of the tenant with
+    async fn read_key(&self, tenant_id: &uuid::Uuid) -> Result<crypto::DataKey, ProviderError> {
+        return self.data_key(tenant_id).await?
+            .ok_or_else(|| from_crypto(&format!("content of tenant {} is encrypted but it has no data key", tenant_id)));
+    }
+
+    /// the data key to encrypt new content of the tenant with, created on
+    /// first use; `None` without master keys
+    async fn write_key(&self, tenant_id: &uuid::Uuid) -> Result<Option<crypto::DataKey>, ProviderError> {
+        let Some(master) = self.master_keys.last() else {
+            return Ok(None);
+        };
+        if let Some(key) = self.data_key(tenant_id).await? {
+            return Ok(Some(key));
+        }
+
+        let key = crypto::DataKey::generate().map_err(|e| from_crypto(&e))?;
+        let stored = crypto::wrap(master, tenant_id, &key).map_err(|e| from_crypto(&e))?;
+        if !self.write_key_file(tenant_id, &stored, false).await? {
+            // another instance created the key first
+            return self.read_key(tenant_id).await.map(Some);
+        }
+        info!("created data key of tenant {}", tenant_id);
+
+        self.data_keys.lock().map_err(|_| ProviderError::Internal)?.insert(*tenant_id, key.clone());
+        return Ok(Some(key));
+    }
+
+    /// writes the key file through a temporary file, an existing one is
+    /// only replaced with `replace`; false when it was kept
+    async fn write_key_file(&self, tenant_id: &uuid::Uuid, stored: &str, replace: bool) -> Result<bool, ProviderError> {
+        let temp_dir = self.root.join(tenant_id.simple().to_string()).join(TEMP_DIR);
+        let temp_path = temp_dir.join(uuid::Uuid::new_v4().simple().to_string());
+        let path = self.key_path(tenant_id);
+
+        tokio::fs::create_dir_all(&temp_dir).await.map_err(|e| from_io(&e, "create directory"))?;
+
+        let written: Result<bool, std::io::Error> = async {
+            let mut file = tokio::fs::File::create(&temp_path).await?;
+            file.write_all(stored.as_bytes()).await?;
+            file.sync_all().await?;
+            drop(file);
+
+            if replace {
+                tokio::fs::rename(&temp_path, &path).await?;
+                return Ok(true);
+            }
+            // unlike a rename, a link never replaces a key file another
+            // instance created in the meantime
+            return match tokio::fs::hard_link(&temp_path, &path).await {
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+                Err(e) => Err(e),
+                Ok(()) => Ok(true)
+            };
+        }.await;
+
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        return written.map_err(|e| from_io(&e, "write key file"));
+    }
+
+    fn path(&self, tenant_id: &uuid::Uuid, digest: &str) -> Result<PathBuf, ProviderError> {
+        if !file_store::is_digest(digest) {
+            return Err(ProviderError::validation("digest", "not a sha-256 digest"));
+        }
+        return Ok(self.root
+            .join(tenant_id.simple().to_string())
+            .join(&digest[..2])
+            .join(digest));
+    }
+
+    async fn write(
+        &self,
+        tenant_id: &uuid::Uuid,
+        mut data: ByteStream<'_>
+    ) -> Result<FileStat, ProviderError> {
+        let temp_dir = self.root.join(tenant_id.simple().to_string()).join(TEMP_DIR);
+        let temp_path = temp_dir.join(uuid::Uuid::new_v4().simple().to_string());
+
+        let key = self.write_key(tenant_id).await?;
+        tokio::fs::create_dir_all(&temp_dir).await.map_err(|e| from_io(&e, "create directory"))?;
+
+        let file = tokio::fs::File::create(&temp_path).await
+            .map_err(|e| from_io(&e, "create file"))?;
+
+        let mut hasher = Sha256::new();
+        let mut written: u64 = 0;
+        let result: Result<(), ProviderError> = async {
+            let mut writer = ContentWriter::new(file, key.as_ref()).await?;
+            while let Some(chunk) = data.next().await {
+                let chunk = chunk?;
+                writer.write(&chunk).await?;
+                hasher.update(&chunk);
+                written += chunk.len() as u64;
+            }
+            return writer.finish().await;
+        }.await;
+
+        if let Err(e) = result {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(e);
+        }
+
+        debug!("received {} bytes", written);
+        return self.keep(tenant_id, &temp_path, &hex::encode(hasher.finalize())).await;
+    }
+
+    /// moves the file at `temp_path` to where content with `digest` is kept
+    /// unless that content is already stored
+    async fn keep(
+        &self,
+        tenant_id: &uuid::Uuid,
+        temp_path: &Path,
+        digest: &str
+    ) -> Result<FileStat, ProviderError> {
+        let path = self.path(tenant_id, digest)?;
+
+        // the same digest means the same content, the stored copy is kept
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            debug!("content {} already stored", digest);
+            let _ = tokio::fs::remove_file(temp_path).await;
+            return self.metadata(tenant_id, digest).await;
+        }
+
+        let moved: Result<(), std::io::Error> = async {
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            return tokio::fs::rename(temp_path, &path).await;
+        }.await;
+        if let Err(e) = moved {
+            let _ = tokio::fs::remove_file(temp_path).await;
+            return Err(from_io(&e, "rename file"));
+        }
+        debug!("stored {}", path.display());
+
+        return self.metadata(tenant_id, digest).await;
+    }
+
+    fn preview_path(&self, tenant_id: &uuid::Uuid, digest: &str) -> Result<PathBuf, ProviderError> {
+        return Ok(self.path(tenant_id, digest)?.with_file_name(format!("{}.preview", digest)));
+    }
+
+    /// written to a temporary file first so a preview being read is never
+    /// seen half written
+    async fn write_preview(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str,
+        mut data: ByteStream<'_>
+    ) -> Result<(), ProviderError> {
+        let path = self.preview_path(tenant_id, digest)?;
+        let temp_dir = self.root.join(tenant_id.simple().to_string()).join(TEMP_DIR);
+        let temp_path = temp_dir.join(uuid::Uuid::new_v4().simple().to_string());
+
+        let key = self.write_key(tenant_id).await?;
+        tokio::fs::create_dir_all(&temp_dir).await.map_err(|e| from_io(&e, "create directory"))?;
+
+        let file = tokio::fs::File::create(&temp_path).await
+            .map_err(|e| from_io(&e, "create file"))?;
+
+        let result: Result<(), ProviderError> = async {
+            let mut writer = ContentWriter::new(file, key.as_ref()).await?;
+            while let Some(chunk) = data.next().await {
+                writer.write(&chunk?).await?;
+            }
+            writer.finish().await?;
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await.map_err(|e| from_io(&e, "create directory"))?;
+            }
+            return tokio::fs::rename(&temp_path, &path).await.map_err(|e| from_io(&e, "rename file"));
+        }.await;
+
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+        }
+        return result;
+    }
+
+    fn upload_path(&self, tenant_id: &uuid::Uuid, upload_id: &uuid::Uuid) -> PathBuf {
+        return self.root
+            .join(tenant_id.simple().to_string())
+            .join(UPLOADS_DIR)
+            .join(upload_id.simple().to_string());
+    }
+
+    fn lock(&self, upload_id: uuid::Uuid) -> Result<UploadLock<'_>, ProviderError> {
+        let mut uploads = self.uploads.lock().map_err(|_| ProviderError::Internal)?;
+        if !uploads.insert(upload_id) {
+            debug!("upload {} is in use", upload_id);
+            return Err(ProviderError::Conflict);
+        }
+        return Ok(UploadLock {
+            uploads: &self.uploads,
+            upload_id
+        });
+    }
+
+    async fn append_upload(
+        &self,
+        tenant_id: &uuid::Uuid,
+        upload_id: uuid::Uuid,
+        offset: u64,
+        mut data: ByteStream<'_>
+    ) -> Result<u64, ProviderError> {
+        let _lock = self.lock(upload_id)?;
+        let path = self.upload_path(tenant_id, &upload_id);
+
+        if offset == 0
+            && let Some(dir) = path.parent()
+        {
+            tokio::fs::create_dir_all(dir).await.map_err(|e| from_io(&e, "create directory"))?;
+        }
+
+        let mut file = match tokio::fs::OpenOptions::new().read(true).write(true).create(offset == 0).open(&path).await {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(ProviderError::Conflict),
+            Err(e) => return Err(from_io(&e, "open file")),
+            Ok(file) => file
+        };
+
+        // an upload started over gets a new header, one being continued is
+        // encrypted as it was started
+        let encryption = if offset == 0 {
+            match self.write_key(tenant_id).await? {
+                None => None,
+                Some(key) => Some((key, crypto::upload_header().map_err(|e| from_crypto(&e))?))
+            }
+        } else {
+            let header = read_header(&mut file, crypto::UPLOAD_HEADER_SIZE).await?;
+            if crypto::is_encrypted_upload(&header) {
+                Some((self.read_key(tenant_id).await?, header))
+            } else {
+                None
+            }
+        };
+        let start = match encryption {
+            Some(_) => crypto::UPLOAD_HEADER_SIZE as u64 + offset,
+            None => offset
+        };
+
+        let length = file.metadata().await.map_err(|e| from_io(&e, "stat file"))?.len();
+        if offset > 0 && length < start {
+            debug!("upload {} holds {} bytes, not {}", upload_id, length, start);
+            return Err(ProviderError::Conflict);
+        }
+
+        let mut written: u64 = 0;
+        let result: Result<(), ProviderError> = async {
+            file.set_len(start).await.map_err(|e| from_io(&e, "truncate file"))?;
+            let mut cipher = None;
+            if let Some((key, header)) = &encryption {
+                if offset == 0 {
+                    file.seek(std::io::SeekFrom::Start(0)).await.map_err(|e| from_io(&e, "seek file"))?;
+                    file.write_all(header).await.map_err(|e| from_io(&e, "write file"))?;
+                }
+                cipher = Some(crypto::UploadCipher::new(key, header, offset).map_err(|e| from_crypto(&e))?);
+            }
+            file.seek(std::io::SeekFrom::Start(start)).await.map_err(|e| from_io(&e, "seek file"))?;
+            while let Some(chunk) = data.next().await {
+                let chunk = chunk?;
+                match &mut cipher {
+                    Some(cipher) => file.write_all(&cipher.apply(&chunk).map_err(|e| from_crypto(&e))?).await,
+                    None => file.write_all(&chunk).await
+                }.map_err(|e| from_io(&e, "write file"))?;
+                written += chunk.len() as u64;
+            }
+            file.sync_all().await.map_err(|e| from_io(&e, "write file"))?;
+            return Ok(());
+        }.await;
+
+        if let Err(e) = result {
+            let _ = file.set_len(start).await;
+            return Err(e);
+        }
+
+        debug!("appended {} bytes to upload {}", written, upload_id);
+        return Ok(offset + written);
+    }
+
+    async fn complete_upload(
+        &self,
+        tenant_id: &uuid::Uuid,
+        upload_id: uuid::Uuid
+    ) -> Result<FileStat, ProviderError> {
+        let _lock = self.lock(upload_id)?;
+        let path = self.upload_path(tenant_id, &upload_id);
+
+        let mut file = tokio::fs::File::open(&path).await.map_err(|e| from_io(&e, "open file"))?;
+        let header = read_header(&mut file, crypto::UPLOAD_HEADER_SIZE).await?;
+        let mut cipher = if crypto::is_encrypted_upload(&header) {
+            Some(crypto::UploadCipher::new(&self.read_key(tenant_id).await?, &header, 0).map_err(|e| from_crypto(&e))?)
+        } else {
+            file.seek(std::io::SeekFrom::Start(0)).await.map_err(|e| from_io(&e, "seek file"))?;
+            None
+        };
+        let key = self.write_key(tenant_id).await?;
+
+        let mut hasher = Sha256::new();
+        let mut content = tokio_util::io::ReaderStream::new(file);
+
+        if cipher.is_none() && key.is_none() {
+            while let Some(chunk) = content.next().await {
+                hasher.update(&chunk.map_err(|e| from_io(&e, "read file"))?);
+            }
+            return self.keep(tenant_id, &path, &hex::encode(hasher.finalize())).await;
+        }
+
+        // decrypted and encrypted again as content
+        let temp_dir = self.root.join(tenant_id.simple().to_string()).join(TEMP_DIR);
+        let temp_path = temp_dir.join(uuid::Uuid::new_v4().simple().to_string());
+        tokio::fs::create_dir_all(&temp_dir).await.map_err(|e| from_io(&e, "create directory"))?;
+        let temp = tokio::fs::File::create(&temp_path).await
+            .map_err(|e| from_io(&e, "create file"))?;
+
+        let result: Result<(), ProviderError> = async {
+            let mut writer = ContentWriter::new(temp, key.as_ref()).await?;
+            while let Some(chunk) = content.next().await {
+                let chunk = chunk.map_err(|e| from_io(&e, "read file"))?;
+                let chunk = match &mut cipher {
+                    Some(cipher) => cipher.apply(&chunk).map_err(|e| from_crypto(&e))?,
+                    None => chunk.to_vec()
+                };
+                hasher.update(&chunk);
+                writer.write(&chunk).await?;
+            }
+            return writer.finish().await;
+        }.await;
+
+        if let Err(e) = result {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(e);
+        }
+        let stat = self.keep(tenant_id, &temp_path, &hex::encode(hasher.finalize())).await?;
+        tokio::fs::remove_file(&path).await.map_err(|e| from_io(&e, "delete file"))?;
+        return Ok(stat);
+    }
+
+    async fn metadata(
+        &self,
+        tenant_id: &uuid::Uuid,
+        digest: &str
+    ) -> Result<FileStat, ProviderError> {
+        let mut file = tokio::fs::File::open(self.path(tenant_id, digest)?).await
+            .map_err(|e| from_io(&e, "open file"))?;
+        let metadata = file.metadata().await
+            .map_err(|e| from_io(&e, "stat file"))?;
+
+        let mut size = metadata.len();
+        if crypto::is_encrypted_content(&read_header(&mut file, crypto::CONTENT_HEADER_SIZE).await?) {
+            size = crypto::content_layout(size)
+                .map(|(_, size)| size)
+                .ok_or_else(|| from_crypto(&format!("content {} is malformed", digest)))?;
+        }
+
+        return Ok(FileStat {
+            digest: String::from(digest),
+            size,
+            modified: metadata.modified()
+                .map(chrono::DateTime::<chrono::Utc>::from)
+                .unwrap_or(chrono::Utc::now())
+        });
+    }
+
+    /// `length` bytes of the file at `path` starting at `offset`, decrypted
+    /// when it is encrypted
+    async fn read(
+        &self,
+        tenant_id: &uuid::Uuid,
+        path: &Path,
+        offset: u64,
+        length: u64
+    ) -> Result<ByteStream<'static>, ProviderError> {
+        let mut file = tokio::fs::File::open(path).await
+            .map_err(|e| from_io(&e, "open file"))?;
+        let header = read_header(&mut file, crypto::CONTENT_HEADER_SIZE).await?;
+
+        if !crypto::is_encrypted_content(&header) {
+            file.seek(std::io::SeekFrom::Start(offset)).await
+                .map_err(|e| from_io(&e, "seek file"))?;
+            let stream: ByteStream<'static> = Box::pin(
+                tokio_util::io::ReaderStream::new(file.take(length))
+                    .map_err(|e| from_io(&e, "read file"))
+            );
+            return Ok(stream);
+        }
+
+        let key = self.read_key(tenant_id).await?;
+        let file_length = file.metadata().await.map_err(|e| from_io(&e, "stat file"))?.len();
+        let (count, size) = crypto::content_layout(file_length)
+            .ok_or_else(|| from_crypto(&format!("{} is malformed", path.display())))?;
+
+        let segment_size = crypto::SEGMENT_SIZE as u64;
+        let first = offset / segment_size;
+        if first < count {
+            let (start, _) = crypto::segment_span(first, count, file_length);
+            file.seek(std::io::SeekFrom::Start(start)).await
+                .map_err(|e| from_io(&e, "seek file"))?;
+        }
+
+        let segments = Segments {
+            file,
+            key,
+            header,
+            index: first,
+            count,
+            file_length,
+            skip: usize::try_from(offset % segment_size).unwrap_or_default(),
+            remaining: length.min(size.saturating_sub(offset))
+        };
+        let stream: ByteStream<'static> = Box::pin(futures::stream::try_unfold(segments, |mut s| async move {
+            if s.remaining == 0 || s.index >= s.count {
+                return Ok(None);
+            }
+            let (_, sealed_length) = crypto::segment_span(s.index, s.count, s.file_length);
+            let mut sealed = vec![0u8; sealed_length];
+            s.file.read_exact(&mut sealed).await.map_err(|e| from_io(&e, "read file"))?;
+            let plain = crypto::open_segment(&s.key, &s.header, s.index, s.index + 1 == s.count, &sealed)
+                .map_err(|e| from_crypto(&e))?;
+
+            let start = s.skip.min(plain.len());
+            let end = plain.len().min(start.saturating_add(usize::try_from(s.remaining).unwrap_or(usize::MAX)));
+            s.remaining -= (end - start) as u64;
+            s.skip = 0;
+            s.index += 1;
+            return Ok(Some((bytes::Bytes::from(plain).slice(start..end), s)));
+        }));
+        return Ok(stream);
+    }
+}
+
+
+/// state of a stream of decrypted segments
+struct Segments {
+    file: tokio::fs::File,
+    key: crypto::DataKey,
+    header: Vec<u8>,
+    index: u64,
+    count: u64,
+    file_length: u64,
+    /// bytes of the next segment before the range
+    skip: usize,
+    /// bytes of the range still to return
+    remaining: u64
+}
+
+
+/// writes content to a file, encrypted when there is a data key
+struct ContentWriter {
+    file: tokio::fs::File,
+    encryptor: Option<crypto::Encryptor>
+}
+
+impl ContentWriter {
+
+    async fn new(mut file: tokio::fs::File, key: Option<&crypto::DataKey>) -> Result<Self, ProviderError> {
+        let mut encryptor = None;
+        if let Some(key) = key {
+            let (e, header) = crypto::Encryptor::new(key).map_err(|e| from_crypto(&e))?;
+            file.write_all(&header).await.map_err(|e| from_io(&e, "write file"))?;
+            encryptor = Some(e);
+        }
+        return Ok(Self {
+            file,
+            encryptor
+        });
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<(), ProviderError> {
+        return match &mut self.encryptor {
+            Some(encryptor) => self.file.write_all(&encryptor.update(data).map_err(|e| from_crypto(&e))?).await,
+            None => self.file.write_all(data).await
+        }.map_err(|e| from_io(&e, "write file"));
+    }
+
+    async fn finish(mut self) -> Result<(), ProviderError> {
+        if let Some(encryptor) = self.encryptor {
+            let last = encryptor.finish().map_err(|e| from_crypto(&e))?;
+            self.file.write_all(&last).await.map_err(|e| from_io(&e, "write file"))?;
+        }
+        return self.file.sync_all().await.map_err(|e| from_io(&e, "write file"));
+    }
+}
+
+
+/// the first `size` bytes of the file, fewer when it is shorter
+async fn read_header(file: &mut tokio::fs::File, size: usize) -> Result<Vec<u8>, ProviderError> {
+    let mut header = Vec::with_capacity(size);
+    (&mut *file).take(size as u64).read_to_end(&mut header).await
+        .map_err(|e| from_io(&e, "read file"))?;
+    return Ok(header);
+}
+
+
+impl FileStore for LocalFileStore {
+
+    fn put<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        data: ByteStream<'a>
+    ) -> StoreFuture<'a, FileStat> {
+        info!("put");
+        return Box::pin(async move {
+            return self.write(&tenant_id, data).await;
+        });
+    }
+
+    fn get<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str
+    ) -> StoreFuture<'a, ByteStream<'static>> {
+        info!("get");
+        return Box::pin(async move {
+            return self.read(&tenant_id, &self.path(&tenant_id, digest)?, 0, u64::MAX).await;
+        });
+    }
+
+    fn get_range<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str,
+        offset: u64,
+        length: u64
+    ) -> StoreFuture<'a, ByteStream<'static>> {
+        info!("get_range");
+        return Box::pin(async move {
+            return self.read(&tenant_id, &self.path(&tenant_id, digest)?, offset, length).await;
+        });
+    }
+
+    fn delete<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str
+    ) -> StoreFuture<'a, ()> {
+        info!("delete");
+        return Box::pin(async move {
+            tokio::fs::remove_file(self.path(&tenant_id, digest)?).await
+                .map_err(|e| from_io(&e, "delete file"))?;
+            // content without a preview
+            return match tokio::fs::remove_file(self.preview_path(&tenant_id, digest)?).await {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(from_io(&e, "delete file")),
+                Ok(()) => Ok(())
+            };
+        });
+    }
+
+    fn stat<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str
+    ) -> StoreFuture<'a, FileStat> {
+        info!("stat");
+        return Box::pin(async move {
+            return self.metadata(&tenant_id, digest).await;
+        });
+    }
+
+    fn append<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        upload_id: uuid::Uuid,
+        offset: u64,
+        data: ByteStream<'a>
+    ) -> StoreFuture<'a, u64> {
+        info!("append");
+        return Box::pin(async move {
+            return self.append_upload(&tenant_id, upload_id, offset, data).await;
+        });
+    }
+
+    fn complete<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        upload_id: uuid::Uuid
+    ) -> StoreFuture<'a, FileStat> {
+        info!("complete");
+        return Box::pin(async move {
+            return self.complete_upload(&tenant_id, upload_id).await;
+        });
+    }
+
+    fn discard<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        upload_id: uuid::Uuid
+    ) -> StoreFuture<'a, ()> {
+        info!("discard");
+        return Box::pin(async move {
+            let _lock = self.lock(upload_id)?;
+            return match tokio::fs::remove_file(self.upload_path(&tenant_id, &upload_id)).await {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(from_io(&e, "delete file")),
+                Ok(()) => Ok(())
+            };
+        });
+    }
+
+    fn put_preview<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str,
+        data: ByteStream<'a>
+    ) -> StoreFuture<'a, ()> {
+        info!("put_preview");
+        return Box::pin(async move {
+            return self.write_preview(&tenant_id, digest, data).await;
+        });
+    }
+
+    fn get_preview<'a>(
+        &'a self,
+        tenant_id: uuid::Uuid,
+        digest: &'a str
+    ) -> StoreFuture<'a, ByteStream<'static>> {
+        info!("get_preview");
+        return Box::pin(async move {
+            return self.read(&tenant_id, &self.preview_path(&tenant_id, digest)?, 0, u64::MAX).await;
+        });
+    }
 }
 
+
+fn from_io(e: &std::io::Error, operation: &str) -> ProviderError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        return ProviderError::NotFound;
+    }
+    error!("unable to {}: {}", operation, e);
+    return ProviderError::Internal;
+}
+
+fn from_crypto(e: &str) -> ProviderError {
+    error!("{}", e);
+    return ProviderError::Internal;
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    // sha-256 of "hello world"
+    const HELLO_WORLD: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    fn store_root() -> PathBuf {
+        return std::env::temp_dir().join(format!("file_store_test_{}", uuid::Uuid::new_v4().simple()));
+    }
+
+    fn stream(chunks: Vec<&'static [u8]>) -> ByteStream<'static> {
+        return Box::pin(futures::stream::iter(
+            chunks.into_iter().map(|c| Ok(bytes::Bytes::from_static(c)))
+        ));
+    }
+
+    async fn collect(mut data: ByteStream<'static>) -> Result<Vec<u8>, ProviderError> {
+        let mut content = vec![];
+        while let Some(chunk) = data.next().await {
+            content.extend_from_slice(&chunk?);
+        }
+        return Ok(content);
+    }
+
+    async fn read(store: &LocalFileStore, tenant_id: uuid::Uuid, digest: &str) -> Result<Vec<u8>, ProviderError> {
+        return collect(store.get(tenant_id, digest).await?).await;
+    }
+
+    #[tokio::test]
+    async fn test_put_get_delete() {
+        let root = store_root();
+        let store = LocalFileStore::new(&root.to_string_lossy());
+
+        let tenant_id = uuid::Uuid::new_v4();
+
+        let stat = store.put(tenant_id, stream(vec![b"hello ", b"world"])).await;
+        assert!(stat.is_ok_and(|s| s.size == 11 && s.digest == HELLO_WORLD));
+        assert_eq!(read(&store, tenant_id, HELLO_WORLD).await, Ok(b"hello world".to_vec()));
+
+        // the same content is stored once
+        let stat = store.put(tenant_id, stream(vec![b"hello world"])).await;
+        assert!(stat.is_ok_and(|s| s.digest == HELLO_WORLD));
+        let dir = root.join(tenant_id.simple().to_string()).join(&HELLO_WORLD[..2]);
+        assert_eq!(std::fs::read_dir(dir).map(|d| d.count()).unwrap_or_default(), 1);
+
+        // another tenant does not see the content
+        assert_eq!(read(&store, uuid::Uuid::new_v4(), HELLO_WORLD).await, Err(ProviderError::NotFound));
+
+        assert!(store.delete(tenant_id, HELLO_WORLD).await.is_ok());
+        assert_eq!(store.stat(tenant_id, HELLO_WORLD).await, Err(ProviderError::NotFound));
+        assert_eq!(store.delete(tenant_id, HELLO_WORLD).await, Err(ProviderError::NotFound));
+
+        // keys are digests only
+        assert!(matches!(store.stat(tenant_id, "../../etc/passwd").await, Err(ProviderError::Validation { .. })));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_get_range() {
+        let root = store_root();
+        let store = LocalFileStore::new(&root.to_string_lossy());
+
+        let tenant_id = uuid::Uuid::new_v4();
+
+        assert!(store.put(tenant_id, stream(vec![b"hello world"])).await.is_ok());
+
+        let range = store.get_range(tenant_id, HELLO_WORLD, 2, 3).await;
+        assert_eq!(collect(range.unwrap()).await, Ok(b"llo".to_vec()));
+
+        // ranges past the end are cut short
+        let range = store.get_range(tenant_id, HELLO_WORLD, 9, 10).await;
+        assert_eq!(collect(range.unwrap()).await, Ok(b"ld".to_vec()));
+
+        assert!(matches!(
+            store.get_range(uuid::Uuid::new_v4(), HELLO_WORLD, 0, 1).await,
+            Err(ProviderError::NotFound)
+        ));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_failed_put() {
+        let root = store_root();
+        let store = LocalFileStore::new(&root.to_string_lossy());
+
+        let tenant_id = uuid::Uuid::new_v4();
+
+        let failing: ByteStream<'static> = Box::pin(futures::stream::iter(vec![
+            Ok(bytes::Bytes::from_static(b"partial")),
+            Err(ProviderError::Internal)
+        ]));
+        assert_eq!(store.put(tenant_id, failing).await, Err(ProviderError::Internal));
+
+        // no temporary files are left behind
+        let temp_dir = root.join(tenant_id.simple().to_string()).join(TEMP_DIR);
+        assert_eq!(std::fs::read_dir(temp_dir).map(|d| d.count()).unwrap_or_default(), 0);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_partial_upload() {
+        let root = store_root();
+        let store = LocalFileStore::new(&root.to_string_lossy());
+
+        let tenant_id = uuid::Uuid::new_v4();
+        let upload_id = uuid::Uuid::new_v4();
+
+        // appends continue where the last one ended
+        assert_eq!(store.append(tenant_id, upload_id, 3, stream(vec![b"lo "])).await, Err(ProviderError::Conflict));
+        assert_eq!(store.append(tenant_id, upload_id, 0, stream(vec![b"hel"])).await, Ok(3));
+        assert_eq!(store.append(tenant_id, upload_id, 5, stream(vec![b"world"])).await, Err(ProviderError::Conflict));
+
+        // a failed append leaves the upload as it was
+        let failing: ByteStream<'static> = Box::pin(futures::stream::iter(vec![
+            Ok(bytes::Bytes::from_static(b"lo wor")),
+            Err(ProviderError::Internal)
+        ]));
+        assert_eq!(store.append(tenant_id, upload_id, 3, failing).await, Err(ProviderError::Internal));
+        assert_eq!(store.append(tenant_id, upload_id, 3, stream(vec![b"lo ", b"world"])).await, Ok(11));
+
+        let stat = store.complete(tenant_id, upload_id).await;
+        assert!(stat.is_ok_and(|s| s.size == 11 && s.digest == HELLO_WORLD));
+        assert_eq!(read(&store, tenant_id, HELLO_WORLD).await, Ok(b"hello world".to_vec()));
+        assert_eq!(store.complete(tenant_id, upload_id).await, Err(ProviderError::NotFound));
+
+        let upload_id = uuid::Uuid::new_v4();
+        assert_eq!(store.discard(tenant_id, upload_id).await, Ok(()));
+        assert_eq!(store.append(tenant_id, upload_id, 0, stream(vec![b"partial"])).await, Ok(7));
+        assert_eq!(store.discard(tenant_id, upload_id).await, Ok(()));
+        assert_eq!(store.append(tenant_id, upload_id, 7, stream(vec![b"!"])).await, Err(ProviderError::Conflict));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_preview() {
+        let root = store_root();
+        let store = LocalFileStore::new(&root.to_string_lossy());
+
+        let tenant_id = uuid::Uuid::new_v4();
+
+        assert!(store.put(tenant_id, stream(vec![b"hello world"])).await.is_ok());
+        assert!(matches!(store.get_preview(tenant_id, HELLO_WORLD).await, Err(ProviderError::NotFound)));
+
+        assert_eq!(store.put_preview(tenant_id, HELLO_WORLD, stream(vec![b"\x89PNG", b"first"])).await, Ok(()));
+        assert_eq!(store.put_preview(tenant_id, HELLO_WORLD, stream(vec![b"\x89PNG", b"second"])).await, Ok(()));
+        let preview = store.get_preview(tenant_id, HELLO_WORLD).await;
+        assert_eq!(collect(preview.unwrap()).await, Ok(b"\x89PNGsecond".to_vec()));
+
+        // the content is unchanged and removed together with its preview
+        assert_eq!(read(&store, tenant_id, HELLO_WORLD).await, Ok(b"hello world".to_vec()));
+        assert!(store.delete(tenant_id, HELLO_WORLD).await.is_ok());
+        assert!(matches!(store.get_preview(tenant_id, HELLO_WORLD).await, Err(ProviderError::NotFound)));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_encryption() {
+        let root = store_root();
+        let master = |id: &str, byte: u8| MasterKey::new(id, &[byte; crypto::KEY_SIZE]).unwrap();
+        let tenant_id = uuid::Uuid::new_v4();
+
+        // content stored before encryption stays readable
+        let plain_store = LocalFileStore::new(&root.to_string_lossy());
+        assert!(plain_store.put(tenant_id, stream(vec![b"hello world"])).await.is_ok());
+
+        let store = LocalFileStore::new(&root.to_string_lossy()).with_master_keys(vec![master("2026-01", 1)]);
+        assert_eq!(read(&store, tenant_id, HELLO_WORLD).await, Ok(b"hello world".to_vec()));
+
+        let content: Vec<u8> = (0..crypto::SEGMENT_SIZE * 2 + 100).map(|i| (i % 253) as u8).collect();
+        let chunks: ByteStream<'static> = Box::pin(futures::stream::iter(
+            content.chunks(1000).map(|c| Ok(bytes::Bytes::copy_from_slice(c))).collect::<Vec<_>>()
+        ));
+        let stat = store.put(tenant_id, chunks).await.unwrap();
+        assert_eq!(stat.size, content.len() as u64);
+        assert_eq!(store.stat(tenant_id, &stat.digest).await.map(|s| s.size), Ok(content.len() as u64));
+        assert_eq!(read(&store, tenant_id, &stat.digest).await, Ok(content.clone()));
+
+        // nothing readable is left on disk
+        let on_disk = std::fs::read(store.path(&tenant_id, &stat.digest).unwrap()).unwrap();
+        assert!(!on_disk.windows(64).any(|w| w == &content[5000..5064]));
+
+        // ranges across segments
+        let offset = crypto::SEGMENT_SIZE as u64 - 10;
+        let range = store.get_range(tenant_id, &stat.digest, offset, 30).await;
+        assert_eq!(collect(range.unwrap()).await, Ok(content[offset as usize..offset as usize + 30].to_vec()));
+        let range = store.get_range(tenant_id, &stat.digest, content.len() as u64 - 5, 10).await;
+        assert_eq!(collect(range.unwrap()).await, Ok(content[content.len() - 5..].to_vec()));
+        let range = store.get_range(tenant_id, &stat.digest, content.len() as u64 + 5, 10).await;
+        assert_eq!(collect(range.unwrap()).await, Ok(vec![]));
+
+        assert_eq!(store.put_preview(tenant_id, &stat.digest, stream(vec![b"\x89PNG", b"preview"])).await, Ok(()));
+        let preview = store.get_preview(tenant_id, &stat.digest).await;
+        assert_eq!(collect(preview.unwrap()).await, Ok(b"\x89PNGpreview".to_vec()));
+
+        // partial uploads are encrypted as well
+        let upload_id = uuid::Uuid::new_v4();
+        assert_eq!(store.append(tenant_id, upload_id, 0, stream(vec![b"secret "])).await, Ok(7));
+        let failing: ByteStream<'static> = Box::pin(futures::stream::iter(vec![
+            Ok(bytes::Bytes::from_static(b"par")),
+            Err(ProviderError::Internal)
+        ]));
+        assert_eq!(store.append(tenant_id, upload_id, 7, failing).await, Err(ProviderError::Internal));
+        assert_eq!(store.append(tenant_id, upload_id, 7, stream(vec![b"plans"])).await, Ok(12));
+        let partial = std::fs::read(store.upload_path(&tenant_id, &upload_id)).unwrap();
+        assert!(!partial.windows(6).any(|w| w == b"secret"));
+        let stat = store.complete(tenant_id, upload_id).await.unwrap();
+        assert_eq!(read(&store, tenant_id, &stat.digest).await, Ok(b"secret plans".to_vec()));
+        assert!(!store.upload_path(&tenant_id, &upload_id).exists());
+
+        // rotation only rewraps the data key
+        let key_file = std::fs::read_to_string(store.key_path(&tenant_id)).unwrap();
+        let rotated = LocalFileStore::new(&root.to_string_lossy()).with_master_keys(vec![master("2026-01", 1), master("2026-07", 2)]);
+        assert_eq!(rotated.rotate_keys().await, Ok(1));
+        assert_eq!(rotated.rotate_keys().await, Ok(0));
+        assert_ne!(std::fs::read_to_string(store.key_path(&tenant_id)).unwrap(), key_file);
+
+        let store = LocalFileStore::new(&root.to_string_lossy()).with_master_keys(vec![master("2026-07", 2)]);
+        assert_eq!(read(&store, tenant_id, &stat.digest).await, Ok(b"secret plans".to_vec()));
+        let store = LocalFileStore::new(&root.to_string_lossy()).with_master_keys(vec![master("2026-01", 1)]);
+        assert_eq!(read(&store, tenant_id, &stat.digest).await, Err(ProviderError::Internal));
+        assert_eq!(read(&plain_store, tenant_id, &stat.digest).await, Err(ProviderError::Internal));
+
+        let _ = std::fs::remove_dir_all(&root);
     }
 }
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use provider_error::ProviderError;
+
 pub mod transactions;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,24 +83,24 @@ pub struct ItemLocation {
 //     //     &self,
 //     //     tenant_id: &uuid::Uuid,
 //     //     item: &Item,
-//     // ) -> impl Future<Output = Result<(), &'static str>> + Send;
+//     // ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
 //     fn item_set_active(
 //         &self,
 //         item_id: &uuid::Uuid,
 //         active: &bool,
-//     ) -> impl Future<Output = Result<(), &'static str>> + Send;
+//     ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
 //     // fn items_fetch(
 //     //     &self,
 //     //     tenant_id: &uuid::Uuid,
 //     //     filter: &str,
-//     // ) -> impl Future<Output = Result<Vec<Item>, &'static str>> + Send;
+//     // ) -> impl Future<Output = Result<Vec<Item>, ProviderError>> + Send;
 
 //     fn item_fetch_by_id(
 //         &self,
 //         item_id: &uuid::Uuid,
-//     ) -> impl Future<Output = Result<Item, &'static str>> + Send;
+//     ) -> impl Future<Output = Result<Item, ProviderError>> + Send;
 // }
 
 pub trait WarehouseProvider {
@@ -106,30 +108,30 @@ pub trait WarehouseProvider {
         &self,
         tenant_id: &uuid::Uuid,
         warehouse: &Warehouse,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn warehouse_set_active(
         &self,
         warehouse_id: &uuid::Uuid,
         active: &bool,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn warehouses_fetch(
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> impl Future<Output = Result<Vec<Warehouse>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Warehouse>, ProviderError>> + Send;
 
     fn fetch_by_name(
         &self,
         tenant_id: &uuid::Uuid,
         name: &str,
-    ) -> impl Future<Output = Result<Warehouse, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Warehouse, ProviderError>> + Send;
 
     fn fetch_by_id(
         &self,
         warehouse_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Warehouse, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Warehouse, ProviderError>> + Send;
 }
 
 pub trait LocationsProvider {
@@ -138,27 +140,27 @@ pub trait LocationsProvider {
         tenant_id: &uuid::Uuid,
         warehouse_id: &uuid::Uuid,
         location: &Location,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn location_set_active(
         &self,
         location_id: &uuid::Uuid,
         active: &bool,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn fetch(
         &self,
         tenant_id: &uuid::Uuid,
         warehouse_id: &uuid::Uuid,
         filter: &str,
-    ) -> impl Future<Output = Result<Vec<Location>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Location>, ProviderError>> + Send;
 
     fn fetch_by_name(
         &self,
         tenant_id: &uuid::Uuid,
         warehouse_id: &uuid::Uuid,
         name: &str,
-    ) -> impl Future<Output = Result<Location, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Location, ProviderError>> + Send;
 }
 
 pub trait ItemProvider {
@@ -166,13 +168,13 @@ pub trait ItemProvider {
         &self,
         tenant_id: &uuid::Uuid,
         item: &Item,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn items_fetch(
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> impl Future<Output = Result<Vec<Item>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Item>, ProviderError>> + Send;
 
     fn location_save(
         &self,
@@ -185,10 +187,10 @@ pub trait ItemProvider {
         dimension_id: &i32,
         uom_id: &i64,
         expiry: Option<chrono::DateTime<chrono::Utc>>,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn locations_fetch(
         &self,
         item_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Vec<ItemLocation>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<ItemLocation>, ProviderError>> + Send;
 }
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use provider_error::ProviderError;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PurchaseOrderItem {
     pub item_id: Uuid,
@@ -24,10 +26,11 @@ pub trait PurchaseOrderProvider {
         &self,
         tenant_id: &Uuid,
         purchase_order: &PurchaseOrder,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn fetch_by_id(
         &self,
-        po_id: Uuid,
-    ) -> impl Future<Output = Result<PurchaseOrder, &'static str>> + Send;
+        tenant_id: &Uuid,
+        po_id: &Uuid,
+    ) -> impl Future<Output = Result<PurchaseOrder, ProviderError>> + Send;
 }
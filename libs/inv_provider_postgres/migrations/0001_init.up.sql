@@ -0,0 +1,724 @@
+-- inventory: items, warehouses, locations and purchase orders
+
+create schema if not exists mm;
+
+
+create table mm.items (
+    item_id uuid not null,
+    tenant_id uuid not null,
+    active boolean not null default true,
+    version int not null default 0,
+    created timestamptz not null default now(),
+    updated timestamptz not null default now(),
+    name text not null,
+    description text not null default '',
+    sku text not null default '',
+    upc text not null default '',
+    perishable boolean not null default false,
+    hazardous boolean not null default false,
+    flammable boolean not null default false,
+    esd_sensitive boolean not null default false,
+    constraint pk_items primary key (item_id),
+    constraint fk_items_tenant foreign key (tenant_id) references tenants.tenants (tenant_id)
+);
+
+create index i_items_tenant on mm.items (tenant_id);
+
+create table mm.warehouses (
+    warehouse_id uuid not null,
+    tenant_id uuid not null,
+    active boolean not null default true,
+    version int not null default 0,
+    created timestamptz not null default now(),
+    updated timestamptz not null default now(),
+    name text not null,
+    description text not null default '',
+    street text not null default '',
+    city text not null default '',
+    state text not null default '',
+    zip_code text not null default '',
+    country_id int not null,
+    constraint pk_warehouses primary key (warehouse_id),
+    constraint fk_warehouses_tenant foreign key (tenant_id) references tenants.tenants (tenant_id),
+    constraint fk_warehouses_country foreign key (country_id) references common.countries (iso_3166_1_numeric)
+);
+
+create unique index u_warehouses_name on mm.warehouses (tenant_id, name);
+
+create table mm.locations (
+    location_id uuid not null,
+    tenant_id uuid not null,
+    warehouse_id uuid not null,
+    active boolean not null default true,
+    version int not null default 0,
+    created timestamptz not null default now(),
+    updated timestamptz not null default now(),
+    name text not null,
+    description text not null default '',
+    floor text not null default '',
+    level text not null default '',
+    section text not null default '',
+    aisle text not null default '',
+    "row" text not null default '',
+    rack text not null default '',
+    shelf text not null default '',
+    bin text not null default '',
+    pallet text not null default '',
+    constraint pk_locations primary key (location_id),
+    constraint fk_locations_tenant foreign key (tenant_id) references tenants.tenants (tenant_id),
+    constraint fk_locations_warehouse foreign key (warehouse_id) references mm.warehouses (warehouse_id)
+);
+
+create unique index u_locations_name on mm.locations (warehouse_id, name);
+
+create table mm.item_locations (
+    item_id uuid not null,
+    location_id uuid not null,
+    active boolean not null default true,
+    version int not null default 0,
+    created timestamptz not null default now(),
+    updated timestamptz not null default now(),
+    batch text not null default '',
+    lot text not null default '',
+    quantity numeric not null default 0,
+    dimension_id smallint not null,
+    uom_id bigint not null,
+    expiry timestamptz,
+    constraint pk_item_locations primary key (item_id, location_id),
+    constraint fk_item_locations_item foreign key (item_id) references mm.items (item_id),
+    constraint fk_item_locations_location foreign key (location_id) references mm.locations (location_id),
+    constraint fk_item_locations_dimension foreign key (dimension_id) references common.dimensions (dimension_id),
+    constraint fk_item_locations_uom foreign key (uom_id) references common.uom (uom_id)
+);
+
+-- org_id is not constrained, a tenant without organizations orders as itself
+create table mm.purchase_orders (
+    po_id uuid not null,
+    tenant_id uuid not null,
+    active boolean not null default true,
+    version int not null default 0,
+    created timestamptz not null default now(),
+    updated timestamptz not null default now(),
+    description text not null default '',
+    org_id uuid not null,
+    partner_id uuid not null,
+    constraint pk_purchase_orders primary key (po_id),
+    constraint fk_purchase_orders_tenant foreign key (tenant_id) references tenants.tenants (tenant_id),
+    constraint fk_purchase_orders_partner foreign key (partner_id) references crm.partners (partner_id)
+);
+
+create index i_purchase_orders_tenant on mm.purchase_orders (tenant_id);
+
+create table mm.purchase_order_items (
+    po_id uuid not null,
+    item_id uuid not null,
+    quantity numeric not null,
+    uom_id bigint not null,
+    constraint pk_purchase_order_items primary key (po_id, item_id),
+    constraint fk_purchase_order_items_po foreign key (po_id) references mm.purchase_orders (po_id) on delete cascade,
+    constraint fk_purchase_order_items_item foreign key (item_id) references mm.items (item_id),
+    constraint fk_purchase_order_items_uom foreign key (uom_id) references common.uom (uom_id)
+);
+
+create type mm.purchase_order_item_type as (
+    item_id uuid,
+    quantity numeric,
+    uom_id int
+);
+
+
+create procedure mm.item_save(
+    p_tenant_id uuid,
+    p_item_id uuid,
+    p_version int,
+    p_name text,
+    p_description text,
+    p_sku text,
+    p_upc text
+)
+language sql
+as $$
+    insert into mm.items as i (
+        item_id,
+        tenant_id,
+        version,
+        name,
+        description,
+        sku,
+        upc
+    ) values (
+        p_item_id,
+        p_tenant_id,
+        p_version,
+        p_name,
+        p_description,
+        p_sku,
+        p_upc
+    )
+    on conflict (item_id) do update set
+        name = excluded.name,
+        description = excluded.description,
+        sku = excluded.sku,
+        upc = excluded.upc,
+        version = i.version + 1,
+        updated = now()
+    where i.tenant_id = excluded.tenant_id;
+$$;
+
+create procedure mm.item_location_save(
+    p_item_id uuid,
+    p_location_id uuid,
+    p_version int,
+    p_batch text,
+    p_lot text,
+    p_quantity numeric,
+    p_dimension_id smallint,
+    p_uom_id bigint,
+    p_expiry timestamptz
+)
+language sql
+as $$
+    insert into mm.item_locations as il (
+        item_id,
+        location_id,
+        version,
+        batch,
+        lot,
+        quantity,
+        dimension_id,
+        uom_id,
+        expiry
+    ) values (
+        p_item_id,
+        p_location_id,
+        p_version,
+        p_batch,
+        p_lot,
+        p_quantity,
+        p_dimension_id,
+        p_uom_id,
+        p_expiry
+    )
+    on conflict (item_id, location_id) do update set
+        batch = excluded.batch,
+        lot = excluded.lot,
+        quantity = excluded.quantity,
+        dimension_id = excluded.dimension_id,
+        uom_id = excluded.uom_id,
+        expiry = excluded.expiry,
+        version = il.version + 1,
+        updated = now();
+$$;
+
+create procedure mm.warehouse_save(
+    p_tenant_id uuid,
+    p_warehouse_id uuid,
+    p_name text,
+    p_description text,
+    p_street text,
+    p_city text,
+    p_state text,
+    p_zip_code text,
+    p_country_id int,
+    p_version int
+)
+language sql
+as $$
+    insert into mm.warehouses as w (
+        warehouse_id,
+        tenant_id,
+        name,
+        description,
+        street,
+        city,
+        state,
+        zip_code,
+        country_id,
+        version
+    ) values (
+        p_warehouse_id,
+        p_tenant_id,
+        p_name,
+        p_description,
+        p_street,
+        p_city,
+        p_state,
+        p_zip_code,
+        p_country_id,
+        p_version
+    )
+    on conflict (warehouse_id) do update set
+        name = excluded.name,
+        description = excluded.description,
+        street = excluded.street,
+        city = excluded.city,
+        state = excluded.state,
+        zip_code = excluded.zip_code,
+        country_id = excluded.country_id,
+        version = w.version + 1,
+        updated = now()
+    where w.tenant_id = excluded.tenant_id;
+$$;
+
+create procedure mm.warehouse_set_active(
+    p_warehouse_id uuid,
+    p_active boolean
+)
+language sql
+as $$
+    update mm.warehouses set
+        active = p_active,
+        updated = now()
+    where warehouse_id = p_warehouse_id;
+$$;
+
+create procedure mm.location_save(
+    p_tenant_id uuid,
+    p_location_id uuid,
+    p_warehouse_id uuid,
+    p_version int,
+    p_name text,
+    p_description text,
+    p_floor text,
+    p_level text,
+    p_section text,
+    p_row text,
+    p_rack text,
+    p_aisle text,
+    p_shelf text,
+    p_bin text,
+    p_pallet text
+)
+language sql
+as $$
+    insert into mm.locations as l (
+        location_id,
+        tenant_id,
+        warehouse_id,
+        version,
+        name,
+        description,
+        floor,
+        level,
+        section,
+        "row",
+        rack,
+        aisle,
+        shelf,
+        bin,
+        pallet
+    ) values (
+        p_location_id,
+        p_tenant_id,
+        p_warehouse_id,
+        p_version,
+        p_name,
+        p_description,
+        p_floor,
+        p_level,
+        p_section,
+        p_row,
+        p_rack,
+        p_aisle,
+        p_shelf,
+        p_bin,
+        p_pallet
+    )
+    on conflict (location_id) do update set
+        warehouse_id = excluded.warehouse_id,
+        name = excluded.name,
+        description = excluded.description,
+        floor = excluded.floor,
+        level = excluded.level,
+        section = excluded.section,
+        "row" = excluded."row",
+        rack = excluded.rack,
+        aisle = excluded.aisle,
+        shelf = excluded.shelf,
+        bin = excluded.bin,
+        pallet = excluded.pallet,
+        version = l.version + 1,
+        updated = now()
+    where l.tenant_id = excluded.tenant_id;
+$$;
+
+create procedure mm.location_set_active(
+    p_location_id uuid,
+    p_active boolean
+)
+language sql
+as $$
+    update mm.locations set
+        active = p_active,
+        updated = now()
+    where location_id = p_location_id;
+$$;
+
+create procedure mm.purchase_order_save(
+    p_tenant_id uuid,
+    p_po_id uuid,
+    p_version int,
+    p_description text,
+    p_org_id uuid,
+    p_partner_id uuid
+)
+language sql
+as $$
+    insert into mm.purchase_orders as po (
+        po_id,
+        tenant_id,
+        version,
+        description,
+        org_id,
+        partner_id
+    ) values (
+        p_po_id,
+        p_tenant_id,
+        p_version,
+        p_description,
+        p_org_id,
+        p_partner_id
+    )
+    on conflict (po_id) do update set
+        description = excluded.description,
+        org_id = excluded.org_id,
+        partner_id = excluded.partner_id,
+        version = po.version + 1,
+        updated = now()
+    where po.tenant_id = excluded.tenant_id;
+$$;
+
+-- replaces the items of a purchase order with the given items
+create procedure mm.purchase_order_items_save(
+    p_tenant_id uuid,
+    p_po_id uuid,
+    p_items mm.purchase_order_item_type[]
+)
+language sql
+as $$
+    delete from mm.purchase_order_items poi
+    using mm.purchase_orders po
+    where po.po_id = poi.po_id
+        and po.tenant_id = p_tenant_id
+        and poi.po_id = p_po_id
+        and poi.item_id not in (
+            select t.item_id from unnest(p_items) t
+        );
+
+    insert into mm.purchase_order_items as poi (
+        po_id,
+        item_id,
+        quantity,
+        uom_id
+    )
+    select
+        po.po_id,
+        t.item_id,
+        t.quantity,
+        t.uom_id
+    from unnest(p_items) t
+        join mm.purchase_orders po on po.po_id = p_po_id and po.tenant_id = p_tenant_id
+    on conflict (po_id, item_id) do update set
+        quantity = excluded.quantity,
+        uom_id = excluded.uom_id;
+$$;
+
+
+create function mm.items_fetch(
+    p_tenant_id uuid,
+    p_filter text
+)
+returns table (
+    item_id uuid,
+    active boolean,
+    version int,
+    created timestamptz,
+    updated timestamptz,
+    name text,
+    description text,
+    sku text,
+    upc text,
+    perishable boolean,
+    hazardous boolean,
+    flammable boolean,
+    esd_sensitive boolean
+)
+language sql
+stable
+as $$
+    select
+        i.item_id,
+        i.active,
+        i.version,
+        i.created,
+        i.updated,
+        i.name,
+        i.description,
+        i.sku,
+        i.upc,
+        i.perishable,
+        i.hazardous,
+        i.flammable,
+        i.esd_sensitive
+    from mm.items i
+    where i.tenant_id = p_tenant_id
+        and (
+            i.name ilike '%' || p_filter || '%'
+            or i.sku ilike '%' || p_filter || '%'
+            or i.upc ilike '%' || p_filter || '%'
+        )
+    order by i.name;
+$$;
+
+create function mm.item_locations_fetch(
+    p_item_id uuid
+)
+returns table (
+    location_id uuid,
+    item_id uuid,
+    active boolean,
+    version int,
+    created timestamptz,
+    updated timestamptz,
+    batch text,
+    lot text,
+    quantity numeric,
+    dimension_id int,
+    uom_id int,
+    expiry timestamptz
+)
+language sql
+stable
+as $$
+    select
+        il.location_id,
+        il.item_id,
+        il.active,
+        il.version,
+        il.created,
+        il.updated,
+        il.batch,
+        il.lot,
+        il.quantity,
+        il.dimension_id::int,
+        il.uom_id::int,
+        il.expiry
+    from mm.item_locations il
+    where il.item_id = p_item_id;
+$$;
+
+create function mm.warehouses_fetch(
+    p_tenant_id uuid,
+    p_filter text
+)
+returns table (
+    warehouse_id uuid,
+    active boolean,
+    version int,
+    name text,
+    description text,
+    street text,
+    city text,
+    state text,
+    zip_code text,
+    country_id int
+)
+language sql
+stable
+as $$
+    select
+        w.warehouse_id,
+        w.active,
+        w.version,
+        w.name,
+        w.description,
+        w.street,
+        w.city,
+        w.state,
+        w.zip_code,
+        w.country_id
+    from mm.warehouses w
+    where w.tenant_id = p_tenant_id
+        and w.name ilike '%' || p_filter || '%'
+    order by w.name;
+$$;
+
+create function mm.warehouse_fetch_by_name(
+    p_tenant_id uuid,
+    p_name text
+)
+returns table (
+    warehouse_id uuid,
+    active boolean,
+    version int,
+    name text,
+    description text,
+    street text,
+    city text,
+    state text,
+    zip_code text,
+    country_id int
+)
+language sql
+stable
+as $$
+    select
+        w.warehouse_id,
+        w.active,
+        w.version,
+        w.name,
+        w.description,
+        w.street,
+        w.city,
+        w.state,
+        w.zip_code,
+        w.country_id
+    from mm.warehouses w
+    where w.tenant_id = p_tenant_id
+        and w.name = p_name;
+$$;
+
+create function mm.warehouse_fetch_by_id(
+    p_warehouse_id uuid
+)
+returns table (
+    warehouse_id uuid,
+    active boolean,
+    version int,
+    name text,
+    description text,
+    street text,
+    city text,
+    state text,
+    zip_code text,
+    country_id int
+)
+language sql
+stable
+as $$
+    select
+        w.warehouse_id,
+        w.active,
+        w.version,
+        w.name,
+        w.description,
+        w.street,
+        w.city,
+        w.state,
+        w.zip_code,
+        w.country_id
+    from mm.warehouses w
+    where w.warehouse_id = p_warehouse_id;
+$$;
+
+create function mm.locations_fetch(
+    p_tenant_id uuid,
+    p_warehouse_id uuid,
+    p_filter text
+)
+returns table (
+    location_id uuid,
+    version int,
+    name text,
+    description text,
+    floor text,
+    level text,
+    section text,
+    aisle text,
+    "row" text,
+    rack text,
+    shelf text,
+    bin text,
+    pallet text
+)
+language sql
+stable
+as $$
+    select
+        l.location_id,
+        l.version,
+        l.name,
+        l.description,
+        l.floor,
+        l.level,
+        l.section,
+        l.aisle,
+        l."row",
+        l.rack,
+        l.shelf,
+        l.bin,
+        l.pallet
+    from mm.locations l
+    where l.tenant_id = p_tenant_id
+        and l.warehouse_id = p_warehouse_id
+        and l.name ilike '%' || p_filter || '%'
+    order by l.name;
+$$;
+
+create function mm.location_fetch_by_name(
+    p_tenant_id uuid,
+    p_warehouse_id uuid,
+    p_name text
+)
+returns table (
+    location_id uuid,
+    version int,
+    name text,
+    description text,
+    floor text,
+    level text,
+    section text,
+    aisle text,
+    "row" text,
+    rack text,
+    shelf text,
+    bin text,
+    pallet text
+)
+language sql
+stable
+as $$
+    select
+        l.location_id,
+        l.version,
+        l.name,
+        l.description,
+        l.floor,
+        l.level,
+        l.section,
+        l.aisle,
+        l."row",
+        l.rack,
+        l.shelf,
+        l.bin,
+        l.pallet
+    from mm.locations l
+    where l.tenant_id = p_tenant_id
+        and l.warehouse_id = p_warehouse_id
+        and l.name = p_name;
+$$;
+
+create function mm.purchase_order_fetch_by_id(
+    p_po_id uuid
+)
+returns table (
+    po_id uuid,
+    active boolean,
+    version int,
+    description text,
+    org_id uuid,
+    partner_id uuid
+)
+language sql
+stable
+as $$
+    select
+        po.po_id,
+        po.active,
+        po.version,
+        po.description,
+        po.org_id,
+        po.partner_id
+    from mm.purchase_orders po
+    where po.po_id = p_po_id;
+$$;
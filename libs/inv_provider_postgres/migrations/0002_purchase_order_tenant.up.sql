@@ -0,0 +1,30 @@
+-- purchase orders are only fetched within the tenant that owns them
+
+drop function if exists mm.purchase_order_fetch_by_id(uuid);
+
+create function mm.purchase_order_fetch_by_id(
+    p_tenant_id uuid,
+    p_po_id uuid
+)
+returns table (
+    po_id uuid,
+    active boolean,
+    version int,
+    description text,
+    org_id uuid,
+    partner_id uuid
+)
+language sql
+stable
+as $$
+    select
+        po.po_id,
+        po.active,
+        po.version,
+        po.description,
+        po.org_id,
+        po.partner_id
+    from mm.purchase_orders po
+    where po.tenant_id = p_tenant_id
+        and po.po_id = p_po_id;
+$$;
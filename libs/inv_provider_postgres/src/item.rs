@@ -5,6 +5,8 @@ use tracing::{debug, error, info};
 
 use sqlx::{Row, postgres::PgRow, prelude::FromRow};
 
+use provider_error::ProviderError;
+
 #[derive(Debug)]
 struct ItemRow(pub Item);
 
@@ -64,7 +66,7 @@ impl inv_provider::ItemProvider for ItemProviderPostgres {
         &self,
         tenant_id: &uuid::Uuid,
         item: &inv_provider::Item,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("item_save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -81,7 +83,7 @@ impl inv_provider::ItemProvider for ItemProviderPostgres {
             {
                 Err(e) => {
                     error!("Error saving inventory item record: {:?}", e);
-                    return Err("Error saving inventory item record");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -89,7 +91,7 @@ impl inv_provider::ItemProvider for ItemProviderPostgres {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -97,7 +99,7 @@ impl inv_provider::ItemProvider for ItemProviderPostgres {
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> Result<Vec<Item>, &'static str> {
+    ) -> Result<Vec<Item>, ProviderError> {
         info!("items_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -111,7 +113,7 @@ impl inv_provider::ItemProvider for ItemProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching inventory items: {:?}", e);
-                    return Err("Error fetching inventory items");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let items = rows.iter().map(|r| r.0.clone()).collect::<Vec<Item>>();
@@ -120,7 +122,7 @@ impl inv_provider::ItemProvider for ItemProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
     async fn location_save(
@@ -134,7 +136,7 @@ impl inv_provider::ItemProvider for ItemProviderPostgres {
         dimension_id: &i32,
         uom_id: &i64,
         expiry: Option<chrono::DateTime<chrono::Utc>>,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("location_save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -153,7 +155,7 @@ impl inv_provider::ItemProvider for ItemProviderPostgres {
             {
                 Err(e) => {
                     error!("Error saving location: {:?}", e);
-                    return Err("Error saving location");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -161,13 +163,13 @@ impl inv_provider::ItemProvider for ItemProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
     async fn locations_fetch(
         &self,
         item_id: &uuid::Uuid,
-    ) -> Result<Vec<ItemLocation>, &'static str> {
+    ) -> Result<Vec<ItemLocation>, ProviderError> {
         info!("locations_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -178,7 +180,7 @@ impl inv_provider::ItemProvider for ItemProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching location: {:?}", e);
-                    return Err("Error fetching location");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let results = rows
@@ -190,7 +192,7 @@ impl inv_provider::ItemProvider for ItemProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 }
 
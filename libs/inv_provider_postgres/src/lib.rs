@@ -5,6 +5,26 @@ pub mod location;
 pub mod transactions;
 pub mod warehouse;
 
+/// schema migrations for the `inv` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "inv",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 2,
+            name: "purchase_order_tenant",
+            up: include_str!("../migrations/0002_purchase_order_tenant.up.sql"),
+            down: include_str!("../migrations/0002_purchase_order_tenant.down.sql")
+        }
+    ]
+};
+
+
 // use tracing::{debug, error, info};
 
 // use sqlx::Row;
@@ -24,7 +44,7 @@ pub mod warehouse;
 //     &self,
 //     tenant_id: &uuid::Uuid,
 //     item: &inv_provider::Item,
-// ) -> Result<(), &'static str> {
+// ) -> Result<(), ProviderError> {
 //     info!("item_save");
 
 //     if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -40,7 +60,7 @@ pub mod warehouse;
 //         {
 //             Err(e) => {
 //                 error!("Error saving inventory item record: {:?}", e);
-//                 return Err("Error saving inventory item record");
+//                 return Err(database_provider::from_sqlx(&e));
 //             }
 //             Ok(_) => {
 //                 return Ok(());
@@ -48,7 +68,7 @@ pub mod warehouse;
 //         }
 //     } else {
 //         error!("No Postgres pool found for 'main'");
-//         return Err("Unable to get pool for 'main'");
+//         return Err(ProviderError::Unavailable);
 //     }
 // }
 
@@ -56,7 +76,7 @@ pub mod warehouse;
 //     &self,
 //     item_id: &uuid::Uuid,
 //     active: &bool,
-// ) -> Result<(), &'static str> {
+// ) -> Result<(), ProviderError> {
 //     info!("item_set_active");
 
 //     if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -68,7 +88,7 @@ pub mod warehouse;
 //         {
 //             Err(e) => {
 //                 error!("Error setting inventory item active state: {:?}", e);
-//                 return Err("Error setting inventory item active state");
+//                 return Err(database_provider::from_sqlx(&e));
 //             }
 //             Ok(_) => {
 //                 return Ok(());
@@ -76,7 +96,7 @@ pub mod warehouse;
 //         }
 //     } else {
 //         error!("No Postgres pool found for 'main'");
-//         return Err("Unable to get pool for 'main'");
+//         return Err(ProviderError::Unavailable);
 //     }
 // }
 
@@ -84,7 +104,7 @@ pub mod warehouse;
 //     &self,
 //     tenant_id: &uuid::Uuid,
 //     filter: &str,
-// ) -> Result<Vec<inv_provider::Item>, &'static str> {
+// ) -> Result<Vec<inv_provider::Item>, ProviderError> {
 //     info!("items_fetch");
 
 //     if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -96,7 +116,7 @@ pub mod warehouse;
 //         {
 //             Err(e) => {
 //                 error!("Error fetching inventory item records: {:?}", e);
-//                 return Err("Error fetching inventory item records");
+//                 return Err(database_provider::from_sqlx(&e));
 //             }
 //             Ok(rows) => {
 //                 let items = rows
@@ -126,14 +146,14 @@ pub mod warehouse;
 //         }
 //     } else {
 //         error!("No Postgres pool found for 'main'");
-//         return Err("Unable to get pool for 'main'");
+//         return Err(ProviderError::Unavailable);
 //     }
 // }
 
 // async fn item_fetch_by_id(
 //     &self,
 //     item_id: &uuid::Uuid,
-// ) -> Result<inv_provider::Item, &'static str> {
+// ) -> Result<inv_provider::Item, ProviderError> {
 //     info!("item_fetch_by_id");
 
 //     if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -144,7 +164,7 @@ pub mod warehouse;
 //         {
 //             Err(e) => {
 //                 error!("Error fetching inventory item record: {:?}", e);
-//                 return Err("Error fetching inventory item record");
+//                 return Err(database_provider::from_sqlx(&e));
 //             }
 //             Ok(r) => {
 //                 let id: uuid::Uuid = r.get("item_id");
@@ -168,7 +188,7 @@ pub mod warehouse;
 //         }
 //     } else {
 //         error!("No Postgres pool found for 'main'");
-//         return Err("Unable to get pool for 'main'");
+//         return Err(ProviderError::Unavailable);
 //     }
 // }
 // Other methods would be implemented similarly...
@@ -5,6 +5,8 @@ use tracing::{debug, error, info};
 use inv_provider::Location;
 use sqlx::{Row, postgres::PgRow, prelude::FromRow};
 
+use provider_error::ProviderError;
+
 #[derive(Debug, Clone)]
 struct LocationData(pub Location);
 
@@ -44,7 +46,7 @@ impl inv_provider::LocationsProvider for LocationsProviderPostgres {
         tenant_id: &uuid::Uuid,
         warehouse_id: &uuid::Uuid,
         location: &inv_provider::Location,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("location_save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -71,7 +73,7 @@ impl inv_provider::LocationsProvider for LocationsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error saving location record: {:?}", e);
-                    return Err("Error saving location record");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -79,14 +81,14 @@ impl inv_provider::LocationsProvider for LocationsProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
     async fn location_set_active(
         &self,
         location_id: &uuid::Uuid,
         active: &bool,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("location_set_active");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -98,7 +100,7 @@ impl inv_provider::LocationsProvider for LocationsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error setting location active: {:?}", e);
-                    return Err("Error setting location active");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -106,7 +108,7 @@ impl inv_provider::LocationsProvider for LocationsProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
     async fn fetch(
@@ -114,7 +116,7 @@ impl inv_provider::LocationsProvider for LocationsProviderPostgres {
         tenant_id: &uuid::Uuid,
         warehouse_id: &uuid::Uuid,
         filter: &str,
-    ) -> Result<Vec<Location>, &'static str> {
+    ) -> Result<Vec<Location>, ProviderError> {
         info!("fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -129,7 +131,7 @@ impl inv_provider::LocationsProvider for LocationsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error setting location active: {:?}", e);
-                    return Err("Error setting location active");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let locations = rows.iter().map(|r| r.0.clone()).collect();
@@ -138,7 +140,7 @@ impl inv_provider::LocationsProvider for LocationsProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
     async fn fetch_by_name(
@@ -146,7 +148,7 @@ impl inv_provider::LocationsProvider for LocationsProviderPostgres {
         tenant_id: &uuid::Uuid,
         warehouse_id: &uuid::Uuid,
         name: &str,
-    ) -> Result<Location, &'static str> {
+    ) -> Result<Location, ProviderError> {
         info!("fetch_by_name");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -161,7 +163,7 @@ impl inv_provider::LocationsProvider for LocationsProviderPostgres {
             {
                 Err(e) => {
                     error!("Error setting location active: {:?}", e);
-                    return Err("Error setting location active");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(row) => {
                     return Ok(row.0.clone());
@@ -169,7 +171,7 @@ impl inv_provider::LocationsProvider for LocationsProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 }
 
@@ -204,7 +206,7 @@ mod tests {
         let warehouse_id = uuid::Uuid::new_v4();
 
         let wh = inv_provider::Warehouse {
-            warehouse_id: uuid::Uuid::new_v4(),
+            warehouse_id: warehouse_id,
             active: true,
             version: 0,
             name: format!("Main Warehouse {}", offset),
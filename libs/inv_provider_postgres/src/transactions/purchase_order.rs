@@ -14,6 +14,8 @@ use inv_provider::{
     transactions::purchase_order::{PurchaseOrder, PurchaseOrderItem},
 };
 
+use provider_error::ProviderError;
+
 #[derive(Debug, Serialize, Deserialize, Type)]
 #[sqlx(type_name = "mm.purchase_order_item_type")]
 struct PurchaseOrderItemDerived {
@@ -56,7 +58,7 @@ impl inv_provider::transactions::purchase_order::PurchaseOrderProvider
         &self,
         tenant_id: &uuid::Uuid,
         purchase_order: &inv_provider::transactions::purchase_order::PurchaseOrder,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -73,7 +75,7 @@ impl inv_provider::transactions::purchase_order::PurchaseOrderProvider
             match pool.begin().await {
                 Err(e) => {
                     error!("Error starting transaction: {:?}", e);
-                    return Err("Error starting transaction");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(mut tx) => {
                     match sqlx::query("call mm.purchase_order_save($1,$2,$3,$4,$5,$6);")
@@ -88,7 +90,7 @@ impl inv_provider::transactions::purchase_order::PurchaseOrderProvider
                     {
                         Err(e) => {
                             error!("Error saving purchase order: {:?}", e);
-                            return Err("Error saving purchase order");
+                            return Err(database_provider::from_sqlx(&e));
                         }
                         Ok(_) => {
                             match sqlx::query("call mm.purchase_order_items_save($1,$2,$3);")
@@ -100,12 +102,12 @@ impl inv_provider::transactions::purchase_order::PurchaseOrderProvider
                             {
                                 Err(e) => {
                                     error!("Error saving purchase order items: {:?}", e);
-                                    return Err("Error saving purchase order items");
+                                    return Err(database_provider::from_sqlx(&e));
                                 }
                                 Ok(_) => {
                                     if let Err(e) = tx.commit().await {
                                         error!("Error committing transaction: {:?}", e);
-                                        return Err("Error committing transaction");
+                                        return Err(database_provider::from_sqlx(&e));
                                     }
                                     return Ok(());
                                 }
@@ -116,23 +118,28 @@ impl inv_provider::transactions::purchase_order::PurchaseOrderProvider
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
-    async fn fetch_by_id(&self, po_id: uuid::Uuid) -> Result<PurchaseOrder, &'static str> {
-        info!("save");
+    async fn fetch_by_id(
+        &self,
+        tenant_id: &uuid::Uuid,
+        po_id: &uuid::Uuid,
+    ) -> Result<PurchaseOrder, ProviderError> {
+        info!("fetch_by_id");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
             match sqlx::query_as::<_, PurchaseOrderDerived>(
-                "select * from mm.purchase_order_fetch_by_id($1);",
+                "select * from mm.purchase_order_fetch_by_id($1,$2);",
             )
+            .bind(tenant_id)
             .bind(po_id)
             .fetch_one(&pool)
             .await
             {
                 Err(e) => {
                     error!("Error fetching purchase order: {:?}", e);
-                    return Err("Error fetching purchase order");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(row) => {
                     return Ok(row.0.clone());
@@ -140,7 +147,7 @@ impl inv_provider::transactions::purchase_order::PurchaseOrderProvider
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 }
 
@@ -266,5 +273,19 @@ mod tests {
             error!("unable to update purchase order: {:?}", e);
             assert!(false, "unable to update purchase order");
         }
+
+        if let Err(e) = ppp.fetch_by_id(&tenant_id, &po_id).await {
+            error!("unable to fetch purchase order: {:?}", e);
+            assert!(false, "unable to fetch purchase order");
+        }
+
+        // purchase orders of another tenant are not found
+        match ppp.fetch_by_id(&uuid::Uuid::new_v4(), &po_id).await {
+            Err(ProviderError::NotFound) => {}
+            other => {
+                error!("unexpected result: {:?}", other);
+                assert!(false, "purchase order fetched from another tenant");
+            }
+        }
     }
 }
@@ -4,6 +4,8 @@ use tracing::{debug, error, info};
 
 use sqlx::{Row, postgres::PgRow, prelude::FromRow};
 
+use provider_error::ProviderError;
+
 struct WarehouseDataItem(pub inv_provider::Warehouse);
 
 impl<'r> FromRow<'r, PgRow> for WarehouseDataItem {
@@ -40,7 +42,7 @@ impl inv_provider::WarehouseProvider for WarehouseProviderPostgres {
         &self,
         tenant_id: &uuid::Uuid,
         warehouse: &inv_provider::Warehouse,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("warehouse_save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -60,7 +62,7 @@ impl inv_provider::WarehouseProvider for WarehouseProviderPostgres {
             {
                 Err(e) => {
                     error!("Error saving warehouse record: {:?}", e);
-                    return Err("Error saving warehouse record");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -68,14 +70,14 @@ impl inv_provider::WarehouseProvider for WarehouseProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
     async fn warehouse_set_active(
         &self,
         warehouse_id: &uuid::Uuid,
         active: &bool,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("warehouse_set_active");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -87,7 +89,7 @@ impl inv_provider::WarehouseProvider for WarehouseProviderPostgres {
             {
                 Err(e) => {
                     error!("Error setting warehouse active status: {:?}", e);
-                    return Err("Error setting warehouse active status");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -95,14 +97,14 @@ impl inv_provider::WarehouseProvider for WarehouseProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
     async fn warehouses_fetch(
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> Result<Vec<inv_provider::Warehouse>, &'static str> {
+    ) -> Result<Vec<inv_provider::Warehouse>, ProviderError> {
         info!("warehouses_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -116,7 +118,7 @@ impl inv_provider::WarehouseProvider for WarehouseProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching warehouse records: {:?}", e);
-                    return Err("Error fetching warehouse records");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let warehouses: Vec<inv_provider::Warehouse> =
@@ -126,14 +128,14 @@ impl inv_provider::WarehouseProvider for WarehouseProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
     async fn fetch_by_name(
         &self,
         tenant_id: &uuid::Uuid,
         name: &str,
-    ) -> Result<inv_provider::Warehouse, &'static str> {
+    ) -> Result<inv_provider::Warehouse, ProviderError> {
         info!("warehouses_fetch_by_name");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -147,7 +149,7 @@ impl inv_provider::WarehouseProvider for WarehouseProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching warehouse record by name: {:?}", e);
-                    return Err("Error fetching warehouse record by name");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(row) => {
                     let warehouse: inv_provider::Warehouse = row.0.clone();
@@ -156,13 +158,13 @@ impl inv_provider::WarehouseProvider for WarehouseProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 
     async fn fetch_by_id(
         &self,
         warehouse_id: &uuid::Uuid,
-    ) -> Result<inv_provider::Warehouse, &'static str> {
+    ) -> Result<inv_provider::Warehouse, ProviderError> {
         info!("warehouses_fetch_by_name");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -175,7 +177,7 @@ impl inv_provider::WarehouseProvider for WarehouseProviderPostgres {
             {
                 Err(e) => {
                     error!("Error fetching warehouse record by id: {:?}", e);
-                    return Err("Error fetching warehouse record by id");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(row) => {
                     let warehouse: inv_provider::Warehouse = row.0.clone();
@@ -184,7 +186,7 @@ impl inv_provider::WarehouseProvider for WarehouseProviderPostgres {
             }
         }
 
-        return Err("No database pool found");
+        return Err(ProviderError::Unavailable);
     }
 }
 
@@ -1,34 +1,111 @@
 #![allow(clippy::needless_return)]
 
+pub mod message;
+pub mod template;
+pub mod transport;
+
+pub use message::{Attachment, Message};
+pub use template::{Template, TemplateName};
+pub use transport::MailTransport;
+
 use tracing::{
     info,
-    debug,
     error
 };
 
+use std::fmt::Display;
+use std::sync::Arc;
 
 
+const DEFAULT_FROM: &str = "nexus@localhost";
 
-pub struct Mailer {
 
+#[derive(Debug)]
+pub enum MailError {
+    Address(String),
+    Message(String),
+    Template(String),
+    Transport(String)
+}
+
+impl Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MailError::Address(e) => write!(f, "invalid mail address {}", e),
+            MailError::Message(e) => write!(f, "unable to build mail message: {}", e),
+            MailError::Template(e) => write!(f, "invalid mail template: {}", e),
+            MailError::Transport(e) => write!(f, "unable to deliver mail: {}", e)
+        }
+    }
+}
+
+impl std::error::Error for MailError {}
+
+
+
+#[derive(Clone)]
+pub struct Mailer {
+    from: String,
+    transport: Arc<dyn MailTransport>
 }
 
 
 impl Mailer {
 
+    /// mailer that only logs messages
     pub fn new() -> Self {
         return Self {
+            from: String::from(DEFAULT_FROM),
+            transport: Arc::new(transport::LogTransport)
+        };
+    }
+
+    pub fn with_transport(
+        from: &str,
+        transport: Arc<dyn MailTransport>
+    ) -> Self {
+        return Self {
+            from: String::from(from),
+            transport
+        };
+    }
 
+    /// mailer using the transport selected in the `[mail]` configuration
+    pub fn from_config(cfg: &config::MailConfig) -> Result<Self, MailError> {
+        let transport: Arc<dyn MailTransport> = match cfg.transport {
+            config::MailTransport::Log => Arc::new(transport::LogTransport),
+            config::MailTransport::Smtp => Arc::new(transport::SmtpTransport::new(
+                &cfg.smtp_host,
+                cfg.smtp_port,
+                cfg.smtp_starttls,
+                &cfg.smtp_username,
+                &cfg.smtp_password()
+            )?),
+            config::MailTransport::Sendmail => Arc::new(transport::SendmailTransport::new(&cfg.sendmail_path)),
+            config::MailTransport::File => Arc::new(transport::FileTransport::new(&cfg.file_path)),
         };
+
+        return Ok(Self::with_transport(&cfg.from, transport));
     }
 
+    pub async fn send(&self, message: &Message) -> Result<(), MailError> {
+        info!("send");
 
-    pub fn send(&self, msg: String) -> Result<(), &'static str> {
-        info!("Sending mail: {}", msg);
+        let built = match message.build(&self.from) {
+            Err(e) => {
+                error!("{}", e);
+                return Err(e);
+            }
+            Ok(built) => built,
+        };
 
-        debug!("Message: {}", msg);
+        return self.transport.send(built).await;
+    }
+}
 
-        return Ok(());
+impl Default for Mailer {
+    fn default() -> Self {
+        return Self::new();
     }
 }
 
@@ -37,9 +114,66 @@ impl Mailer {
 mod tests {
     use super::*;
 
-    // #[test]
-    // fn it_works() {
-    //     let result = add(2, 2);
-    //     assert_eq!(result, 4);
-    // }
+    fn mail_dir() -> std::path::PathBuf {
+        return std::env::temp_dir().join(format!("mailer_test_{}", uuid::Uuid::new_v4().simple()));
+    }
+
+    fn read_mails(dir: &std::path::Path) -> Vec<String> {
+        let mut mails = vec![];
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                if entry.path().extension().is_some_and(|e| e == "eml") {
+                    mails.push(std::fs::read_to_string(entry.path()).unwrap_or_default());
+                }
+            }
+        }
+        return mails;
+    }
+
+    #[tokio::test]
+    async fn test_file_transport() {
+        let dir = mail_dir();
+        let mailer = Mailer::with_transport(
+            "nexus@example.com",
+            Arc::new(transport::FileTransport::new(&dir.to_string_lossy()))
+        );
+
+        let message = Message::new("user@example.com", "Verify your email address", "plain body")
+            .with_html("<p>html body</p>")
+            .with_attachment("invoice.pdf", "application/pdf", b"%PDF-1.4");
+
+        assert!(mailer.send(&message).await.is_ok());
+
+        let mails = read_mails(&dir);
+        assert_eq!(mails.len(), 1);
+        assert!(mails[0].contains("From: nexus@example.com"));
+        assert!(mails[0].contains("To: user@example.com"));
+        assert!(mails[0].contains("Subject: Verify your email address"));
+        assert!(mails[0].contains("multipart/alternative"));
+        assert!(mails[0].contains("filename=\"invoice.pdf\""));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_build_message() {
+        let plain = Message::new("user@example.com", "subject", "text").build(DEFAULT_FROM);
+        assert!(plain.is_ok_and(|m| !String::from_utf8_lossy(&m.formatted()).contains("multipart")));
+
+        let no_recipient = Message { to: vec![], ..Message::new("user@example.com", "subject", "text") };
+        assert!(matches!(no_recipient.build(DEFAULT_FROM), Err(MailError::Message(_))));
+
+        let bad_address = Message::new("not an address", "subject", "text");
+        assert!(matches!(bad_address.build(DEFAULT_FROM), Err(MailError::Address(_))));
+
+        let bad_type = Message::new("user@example.com", "subject", "text")
+            .with_attachment("a.bin", "not a type", b"");
+        assert!(matches!(bad_type.build(DEFAULT_FROM), Err(MailError::Message(_))));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let cfg = config::Config::from_env().mail();
+        assert!(Mailer::from_config(&cfg).is_ok());
+    }
 }
@@ -0,0 +1,126 @@
+use lettre::message::{
+    Mailbox,
+    MultiPart,
+    SinglePart,
+    header::ContentType
+};
+
+use crate::MailError;
+
+
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>
+}
+
+
+/// mail message independent of the transport, `from` falls back to the
+/// configured sender of the mailer
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub from: Option<String>,
+    pub to: Vec<String>,
+    pub subject: String,
+    pub text: String,
+    pub html: Option<String>,
+    pub attachments: Vec<Attachment>
+}
+
+
+impl Message {
+
+    pub fn new(
+        to: &str,
+        subject: &str,
+        text: &str
+    ) -> Self {
+        return Self {
+            from: None,
+            to: vec![String::from(to)],
+            subject: String::from(subject),
+            text: String::from(text),
+            html: None,
+            attachments: vec![]
+        };
+    }
+
+    pub fn with_from(mut self, from: &str) -> Self {
+        self.from = Some(String::from(from));
+        return self;
+    }
+
+    pub fn with_to(mut self, to: &str) -> Self {
+        self.to.push(String::from(to));
+        return self;
+    }
+
+    pub fn with_html(mut self, html: &str) -> Self {
+        self.html = Some(String::from(html));
+        return self;
+    }
+
+    pub fn with_attachment(
+        mut self,
+        filename: &str,
+        content_type: &str,
+        data: &[u8]
+    ) -> Self {
+        self.attachments.push(Attachment {
+            filename: String::from(filename),
+            content_type: String::from(content_type),
+            data: data.to_vec()
+        });
+        return self;
+    }
+
+    /// build the MIME message: plain text, text and html as alternatives,
+    /// and attachments wrapped in a mixed multipart
+    pub fn build(&self, default_from: &str) -> Result<lettre::Message, MailError> {
+        let from = self.from.as_deref().unwrap_or(default_from);
+
+        if self.to.is_empty() {
+            return Err(MailError::Message(String::from("message has no recipients")));
+        }
+
+        let mut builder = lettre::Message::builder()
+            .from(parse_mailbox(from)?)
+            .subject(self.subject.clone());
+        for to in self.to.iter() {
+            builder = builder.to(parse_mailbox(to)?);
+        }
+
+        let result = match (&self.html, self.attachments.is_empty()) {
+            (None, true) => builder.singlepart(SinglePart::plain(self.text.clone())),
+            (Some(html), true) => builder.multipart(
+                MultiPart::alternative_plain_html(self.text.clone(), html.clone())
+            ),
+            (html, false) => {
+                let mut mixed = match html {
+                    Some(html) => MultiPart::mixed().multipart(
+                        MultiPart::alternative_plain_html(self.text.clone(), html.clone())
+                    ),
+                    None => MultiPart::mixed().singlepart(SinglePart::plain(self.text.clone())),
+                };
+                for attachment in self.attachments.iter() {
+                    let content_type = ContentType::parse(&attachment.content_type)
+                        .map_err(|e| MailError::Message(format!("invalid content type '{}': {}", attachment.content_type, e)))?;
+                    mixed = mixed.singlepart(
+                        lettre::message::Attachment::new(attachment.filename.clone())
+                            .body(attachment.data.clone(), content_type)
+                    );
+                }
+                builder.multipart(mixed)
+            }
+        };
+
+        return result.map_err(|e| MailError::Message(e.to_string()));
+    }
+}
+
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, MailError> {
+    return address.parse::<Mailbox>()
+        .map_err(|e| MailError::Address(format!("'{}': {}", address, e)));
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::MailError;
+
+
+/// locale of the built-in templates used when no better match exists
+pub const DEFAULT_LOCALE: &str = "en";
+
+
+/// system messages sent by the application
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TemplateName {
+    Verification,
+    PasswordReset,
+    Invitation,
+    Invoice
+}
+
+impl TemplateName {
+
+    pub const ALL: [TemplateName; 4] = [
+        TemplateName::Verification,
+        TemplateName::PasswordReset,
+        TemplateName::Invitation,
+        TemplateName::Invoice
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            TemplateName::Verification => "verification",
+            TemplateName::PasswordReset => "password_reset",
+            TemplateName::Invitation => "invitation",
+            TemplateName::Invoice => "invoice"
+        };
+    }
+
+    /// variables passed when the template is rendered, templates may not
+    /// reference any other variable
+    pub fn variables(&self) -> &'static [&'static str] {
+        return match self {
+            TemplateName::Verification => &["email", "link"],
+            TemplateName::PasswordReset => &["email", "link"],
+            TemplateName::Invitation => &["inviter", "tenant", "link"],
+            TemplateName::Invoice => &["invoice_number", "tenant", "link"]
+        };
+    }
+}
+
+impl FromStr for TemplateName {
+    type Err = MailError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return TemplateName::ALL.into_iter()
+            .find(|name| name.as_str() == s)
+            .ok_or(MailError::Template(format!("unknown template '{}'", s)));
+    }
+}
+
+
+/// subject, text and optional html variant of a message in one locale;
+/// `{{ variable }}` placeholders are replaced when rendering, values are
+/// escaped in the html variant
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pub locale: String,
+    pub subject: String,
+    pub text: String,
+    pub html: Option<String>
+}
+
+#[derive(Debug, Clone)]
+pub struct Rendered {
+    pub subject: String,
+    pub text: String,
+    pub html: Option<String>
+}
+
+
+macro_rules! builtin {
+    ($locale:literal, $name:literal) => {
+        Some((
+            include_str!(concat!("../templates/", $locale, "/", $name, ".subject")),
+            include_str!(concat!("../templates/", $locale, "/", $name, ".txt")),
+            include_str!(concat!("../templates/", $locale, "/", $name, ".html"))
+        ))
+    };
+}
+
+
+impl Template {
+
+    pub fn new(
+        locale: &str,
+        subject: &str,
+        text: &str
+    ) -> Self {
+        return Self {
+            locale: normalize_locale(locale),
+            subject: String::from(subject),
+            text: String::from(text),
+            html: None
+        };
+    }
+
+    pub fn with_html(mut self, html: &str) -> Self {
+        self.html = Some(String::from(html));
+        return self;
+    }
+
+    /// template shipped with the application
+    pub fn builtin(name: TemplateName, locale: &str) -> Option<Self> {
+        let parts = match (name, normalize_locale(locale).as_str()) {
+            (TemplateName::Verification, "en") => builtin!("en", "verification"),
+            (TemplateName::PasswordReset, "en") => builtin!("en", "password_reset"),
+            (TemplateName::Invitation, "en") => builtin!("en", "invitation"),
+            (TemplateName::Invoice, "en") => builtin!("en", "invoice"),
+            (TemplateName::Verification, "de") => builtin!("de", "verification"),
+            (TemplateName::PasswordReset, "de") => builtin!("de", "password_reset"),
+            (TemplateName::Invitation, "de") => builtin!("de", "invitation"),
+            (TemplateName::Invoice, "de") => builtin!("de", "invoice"),
+            _ => None
+        };
+
+        return parts.map(|(subject, text, html)| Self::new(locale, subject.trim(), text).with_html(html));
+    }
+
+    /// best template for `locale`: the exact locale, then its language and
+    /// finally the default locale, `overrides` win over built-in templates
+    /// of the same locale
+    pub fn resolve(
+        name: TemplateName,
+        locale: &str,
+        overrides: &[Template]
+    ) -> Result<Self, MailError> {
+        for candidate in locale_candidates(locale) {
+            if let Some(template) = overrides.iter().find(|t| t.locale == candidate) {
+                return Ok(template.clone());
+            }
+            if let Some(template) = Self::builtin(name, &candidate) {
+                return Ok(template);
+            }
+        }
+
+        return Err(MailError::Template(format!("no {} template for locale '{}'", name.as_str(), locale)));
+    }
+
+    /// check that every placeholder is terminated and refers to a variable
+    /// of `name`
+    pub fn validate(&self, name: TemplateName) -> Result<(), MailError> {
+        if self.locale.is_empty() || !self.locale.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(MailError::Template(format!("invalid locale '{}'", self.locale)));
+        }
+        if self.subject.trim().is_empty() {
+            return Err(MailError::Template(String::from("subject is empty")));
+        }
+
+        let variables: HashMap<&str, String> = name.variables().iter()
+            .map(|v| (*v, String::new()))
+            .collect();
+        let _ = self.render(&variables)?;
+        return Ok(());
+    }
+
+    pub fn render(&self, variables: &HashMap<&str, String>) -> Result<Rendered, MailError> {
+        return Ok(Rendered {
+            subject: render_part(&self.subject, variables, false)?,
+            text: render_part(&self.text, variables, false)?,
+            html: self.html.as_deref()
+                .map(|html| render_part(html, variables, true))
+                .transpose()?
+        });
+    }
+}
+
+
+/// lower case with `-` as separator, `de_AT` becomes `de-at`
+pub fn normalize_locale(locale: &str) -> String {
+    return locale.trim().replace('_', "-").to_lowercase();
+}
+
+fn locale_candidates(locale: &str) -> Vec<String> {
+    let locale = normalize_locale(locale);
+    let mut candidates: Vec<String> = vec![];
+
+    if !locale.is_empty() {
+        candidates.push(locale.clone());
+    }
+    if let Some((language, _)) = locale.split_once('-') {
+        candidates.push(String::from(language));
+    }
+    if !candidates.iter().any(|c| c == DEFAULT_LOCALE) {
+        candidates.push(String::from(DEFAULT_LOCALE));
+    }
+
+    return candidates;
+}
+
+fn render_part(
+    source: &str,
+    variables: &HashMap<&str, String>,
+    escape: bool
+) -> Result<String, MailError> {
+    let mut rendered = String::with_capacity(source.len());
+    let mut rest = source;
+
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+
+        let placeholder = &rest[start + 2..];
+        let Some(end) = placeholder.find("}}") else {
+            return Err(MailError::Template(String::from("unterminated placeholder")));
+        };
+
+        let variable = placeholder[..end].trim();
+        let Some(value) = variables.get(variable) else {
+            return Err(MailError::Template(format!("unknown variable '{}'", variable)));
+        };
+
+        if escape {
+            rendered.push_str(&escape_html(value));
+        } else {
+            rendered.push_str(value);
+        }
+        rest = &placeholder[end + 2..];
+    }
+    rendered.push_str(rest);
+
+    return Ok(rendered);
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c)
+        }
+    }
+    return escaped;
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(name: TemplateName) -> HashMap<&'static str, String> {
+        return name.variables().iter()
+            .map(|v| (*v, format!("<{}>", v)))
+            .collect();
+    }
+
+    #[test]
+    fn test_builtin_templates() {
+        for locale in ["en", "de"] {
+            for name in TemplateName::ALL {
+                let Some(template) = Template::builtin(name, locale) else {
+                    assert!(false, "missing built-in {} template for {}", name.as_str(), locale);
+                    return;
+                };
+                assert!(template.validate(name).is_ok(), "invalid built-in {} template for {}", name.as_str(), locale);
+            }
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let template = Template::new("en", "Hi {{email}}", "Open {{ link }}")
+            .with_html("<a href=\"{{ link }}\">{{ email }}</a>");
+
+        let rendered = template.render(&variables(TemplateName::Verification));
+        assert!(rendered.as_ref().is_ok_and(|r| r.subject == "Hi <email>"));
+        assert!(rendered.as_ref().is_ok_and(|r| r.text == "Open <link>"));
+        assert!(rendered.is_ok_and(|r| r.html.as_deref() == Some("<a href=\"&lt;link&gt;\">&lt;email&gt;</a>")));
+
+        let unknown = Template::new("en", "subject", "{{ tenant }}");
+        assert!(matches!(unknown.validate(TemplateName::Verification), Err(MailError::Template(_))));
+
+        let unterminated = Template::new("en", "subject", "{{ link");
+        assert!(matches!(unterminated.validate(TemplateName::Verification), Err(MailError::Template(_))));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let resolved = Template::resolve(TemplateName::Verification, "de-AT", &[]);
+        assert!(resolved.is_ok_and(|t| t.locale == "de"));
+
+        let resolved = Template::resolve(TemplateName::Verification, "fr", &[]);
+        assert!(resolved.is_ok_and(|t| t.locale == DEFAULT_LOCALE));
+
+        let overrides = vec![
+            Template::new("de-at", "Servus", "{{ link }}"),
+            Template::new("en", "Welcome", "{{ link }}")
+        ];
+        let resolved = Template::resolve(TemplateName::Verification, "de_AT", &overrides);
+        assert!(resolved.is_ok_and(|t| t.subject == "Servus"));
+
+        // a built-in template in the requested language beats an override
+        // in the default locale
+        let resolved = Template::resolve(TemplateName::Verification, "de", &overrides);
+        assert!(resolved.is_ok_and(|t| t.locale == "de" && t.subject != "Welcome"));
+    }
+}
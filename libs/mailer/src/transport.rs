@@ -0,0 +1,164 @@
+use tracing::{
+    info,
+    debug,
+    error
+};
+
+use std::pin::Pin;
+
+use lettre::{
+    AsyncFileTransport,
+    AsyncSendmailTransport,
+    AsyncSmtpTransport,
+    AsyncTransport,
+    Tokio1Executor,
+    transport::smtp::authentication::Credentials
+};
+
+use crate::MailError;
+
+
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<(), MailError>> + Send + 'a>>;
+
+
+/// delivers a built message, transports are selected by configuration
+pub trait MailTransport: Send + Sync {
+
+    fn send(&self, message: lettre::Message) -> SendFuture<'_>;
+}
+
+
+
+/// only logs messages, for development without a mail server
+pub struct LogTransport;
+
+impl MailTransport for LogTransport {
+
+    fn send(&self, message: lettre::Message) -> SendFuture<'_> {
+        return Box::pin(async move {
+            info!("mail to {:?} not delivered by the log transport", message.envelope().to());
+            debug!("message: {}", String::from_utf8_lossy(&message.formatted()));
+            return Ok(());
+        });
+    }
+}
+
+
+
+pub struct SmtpTransport {
+    transport: AsyncSmtpTransport<Tokio1Executor>
+}
+
+impl SmtpTransport {
+
+    /// `starttls` upgrades the connection before authenticating, without
+    /// it the connection is unencrypted; credentials are only used when
+    /// `username` is set
+    pub fn new(
+        host: &str,
+        port: u16,
+        starttls: bool,
+        username: &str,
+        password: &str
+    ) -> Result<Self, MailError> {
+        let mut builder = if starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                .map_err(|e| MailError::Transport(format!("smtp relay {}: {}", host, e)))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        };
+
+        builder = builder.port(port);
+        if !username.is_empty() {
+            builder = builder.credentials(Credentials::new(String::from(username), String::from(password)));
+        }
+
+        return Ok(Self {
+            transport: builder.build()
+        });
+    }
+}
+
+impl MailTransport for SmtpTransport {
+
+    fn send(&self, message: lettre::Message) -> SendFuture<'_> {
+        return Box::pin(async move {
+            if let Err(e) = self.transport.send(message).await {
+                error!("unable to send mail using smtp: {}", e);
+                return Err(MailError::Transport(e.to_string()));
+            }
+            return Ok(());
+        });
+    }
+}
+
+
+
+/// pipes messages to a local sendmail compatible binary
+pub struct SendmailTransport {
+    transport: AsyncSendmailTransport<Tokio1Executor>
+}
+
+impl SendmailTransport {
+
+    pub fn new(path: &str) -> Self {
+        return Self {
+            transport: AsyncSendmailTransport::<Tokio1Executor>::new_with_command(path)
+        };
+    }
+}
+
+impl MailTransport for SendmailTransport {
+
+    fn send(&self, message: lettre::Message) -> SendFuture<'_> {
+        return Box::pin(async move {
+            if let Err(e) = self.transport.send(message).await {
+                error!("unable to send mail using sendmail: {}", e);
+                return Err(MailError::Transport(e.to_string()));
+            }
+            return Ok(());
+        });
+    }
+}
+
+
+
+/// writes every message to `<id>.eml` in a directory, used for tests and
+/// to inspect mail during development
+pub struct FileTransport {
+    path: String,
+    transport: AsyncFileTransport<Tokio1Executor>
+}
+
+impl FileTransport {
+
+    pub fn new(path: &str) -> Self {
+        return Self {
+            path: String::from(path),
+            transport: AsyncFileTransport::<Tokio1Executor>::new(path)
+        };
+    }
+}
+
+impl MailTransport for FileTransport {
+
+    fn send(&self, message: lettre::Message) -> SendFuture<'_> {
+        return Box::pin(async move {
+            if let Err(e) = tokio::fs::create_dir_all(&self.path).await {
+                error!("unable to create mail directory {}: {}", self.path, e);
+                return Err(MailError::Transport(e.to_string()));
+            }
+
+            match self.transport.send(message).await {
+                Err(e) => {
+                    error!("unable to write mail to {}: {}", self.path, e);
+                    return Err(MailError::Transport(e.to_string()));
+                }
+                Ok(id) => {
+                    debug!("mail written to {}/{}.eml", self.path, id);
+                    return Ok(());
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,115 @@
+#![allow(clippy::needless_return)]
+
+use serde::Serialize;
+
+use provider_error::ProviderError;
+
+
+/// mail queued for delivery, mirrors the fields of `mailer::Message`
+/// without attachments
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxMessage {
+    pub to: Vec<String>,
+    pub from: Option<String>,
+    pub subject: String,
+    pub text: String,
+    pub html: Option<String>
+}
+
+impl OutboxMessage {
+
+    pub fn new(
+        to: &str,
+        subject: &str,
+        text: &str
+    ) -> Self {
+        return Self {
+            to: vec![String::from(to)],
+            from: None,
+            subject: String::from(subject),
+            text: String::from(text),
+            html: None
+        };
+    }
+
+    pub fn with_html(mut self, html: &str) -> Self {
+        self.html = Some(String::from(html));
+        return self;
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutboxStatus {
+    Pending,
+    Sent,
+    /// dead-lettered after running out of attempts
+    Failed
+}
+
+impl From<&str> for OutboxStatus {
+
+    fn from(status: &str) -> Self {
+        return match status {
+            "sent" => OutboxStatus::Sent,
+            "failed" => OutboxStatus::Failed,
+            _ => OutboxStatus::Pending
+        };
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxEntry {
+    pub message_id: uuid::Uuid,
+    pub message: OutboxMessage,
+    pub status: OutboxStatus,
+    pub attempts: i32,
+    pub next_attempt: chrono::DateTime<chrono::Utc>,
+    pub last_error: Option<String>,
+    pub created: chrono::DateTime<chrono::Utc>,
+    pub sent: Option<chrono::DateTime<chrono::Utc>>
+}
+
+
+
+pub trait OutboxProvider {
+
+    fn enqueue(
+        &self,
+        message: &OutboxMessage
+    ) -> impl Future<Output = Result<uuid::Uuid, ProviderError>> + Send;
+
+    /// lease up to `limit` pending messages that are due for `lease` seconds,
+    /// messages leased by another worker are skipped
+    fn claim_due(
+        &self,
+        limit: i32,
+        lease: i32
+    ) -> impl Future<Output = Result<Vec<OutboxEntry>, ProviderError>> + Send;
+
+    fn mark_sent(
+        &self,
+        message_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    /// record a failed attempt, the message is retried at `retry_at` or
+    /// dead-lettered when it is `None`
+    fn mark_failed(
+        &self,
+        message_id: &uuid::Uuid,
+        error: &str,
+        retry_at: Option<&chrono::DateTime<chrono::Utc>>
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    fn fetch_failed(
+        &self
+    ) -> impl Future<Output = Result<Vec<OutboxEntry>, ProviderError>> + Send;
+
+    /// queue a dead-lettered message again with a fresh set of attempts
+    fn retry(
+        &self,
+        message_id: &uuid::Uuid
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+}
@@ -0,0 +1,187 @@
+-- outbound mail queue, drained by the ws outbox worker
+
+create schema if not exists outbox;
+
+
+create table outbox.messages (
+    message_id uuid not null,
+    created timestamptz not null default now(),
+    status text not null default 'pending',
+    mail_to text[] not null,
+    mail_from text,
+    subject text not null,
+    body_text text not null,
+    body_html text,
+    attempts int not null default 0,
+    next_attempt timestamptz not null default now(),
+    locked_until timestamptz,
+    last_error text,
+    sent timestamptz,
+    constraint pk_outbox_messages primary key (message_id),
+    constraint chk_outbox_messages_status check (status in ('pending', 'sent', 'failed'))
+);
+
+create index ix_outbox_messages_due on outbox.messages (next_attempt) where status = 'pending';
+create index ix_outbox_messages_failed on outbox.messages (created) where status = 'failed';
+
+
+create procedure outbox.message_add(
+    p_message_id uuid,
+    p_to text[],
+    p_from text,
+    p_subject text,
+    p_text text,
+    p_html text
+)
+language sql
+as $$
+    insert into outbox.messages (
+        message_id,
+        mail_to,
+        mail_from,
+        subject,
+        body_text,
+        body_html
+    ) values (
+        p_message_id,
+        p_to,
+        p_from,
+        p_subject,
+        p_text,
+        p_html
+    );
+$$;
+
+create function outbox.messages_claim(
+    p_limit int,
+    p_lease int
+)
+returns table (
+    message_id uuid,
+    created timestamptz,
+    status text,
+    mail_to text[],
+    mail_from text,
+    subject text,
+    body_text text,
+    body_html text,
+    attempts int,
+    next_attempt timestamptz,
+    last_error text,
+    sent timestamptz
+)
+language sql
+as $$
+    update outbox.messages m set
+        locked_until = now() + make_interval(secs => p_lease)
+    where m.message_id in (
+        select d.message_id
+        from outbox.messages d
+        where
+            d.status = 'pending'
+            and d.next_attempt <= now()
+            and (d.locked_until is null or d.locked_until < now())
+        order by d.next_attempt
+        limit p_limit
+        for update skip locked
+    )
+    returning
+        m.message_id,
+        m.created,
+        m.status,
+        m.mail_to,
+        m.mail_from,
+        m.subject,
+        m.body_text,
+        m.body_html,
+        m.attempts,
+        m.next_attempt,
+        m.last_error,
+        m.sent;
+$$;
+
+create procedure outbox.message_sent(
+    p_message_id uuid
+)
+language sql
+as $$
+    update outbox.messages set
+        status = 'sent',
+        attempts = attempts + 1,
+        locked_until = null,
+        sent = now()
+    where message_id = p_message_id;
+$$;
+
+create procedure outbox.message_failed(
+    p_message_id uuid,
+    p_error text,
+    p_retry_at timestamptz
+)
+language sql
+as $$
+    update outbox.messages set
+        status = case when p_retry_at is null then 'failed' else 'pending' end,
+        attempts = attempts + 1,
+        next_attempt = coalesce(p_retry_at, next_attempt),
+        locked_until = null,
+        last_error = p_error
+    where message_id = p_message_id;
+$$;
+
+create function outbox.messages_fetch_failed()
+returns table (
+    message_id uuid,
+    created timestamptz,
+    status text,
+    mail_to text[],
+    mail_from text,
+    subject text,
+    body_text text,
+    body_html text,
+    attempts int,
+    next_attempt timestamptz,
+    last_error text,
+    sent timestamptz
+)
+language sql
+stable
+as $$
+    select
+        m.message_id,
+        m.created,
+        m.status,
+        m.mail_to,
+        m.mail_from,
+        m.subject,
+        m.body_text,
+        m.body_html,
+        m.attempts,
+        m.next_attempt,
+        m.last_error,
+        m.sent
+    from outbox.messages m
+    where m.status = 'failed'
+    order by m.created desc;
+$$;
+
+create procedure outbox.message_retry(
+    p_message_id uuid
+)
+language plpgsql
+as $$
+begin
+    update outbox.messages set
+        status = 'pending',
+        attempts = 0,
+        next_attempt = now(),
+        locked_until = null
+    where
+        message_id = p_message_id
+        and status = 'failed';
+
+    if not found then
+        raise exception 'no failed outbox message %', p_message_id using errcode = 'P0002';
+    end if;
+end;
+$$;
@@ -0,0 +1,311 @@
+#![allow(clippy::needless_return)]
+
+use tracing::{debug, error, info};
+
+use sqlx::{Row, postgres::PgRow};
+
+use outbox_provider::{OutboxEntry, OutboxMessage, OutboxProvider, OutboxStatus};
+
+use provider_error::ProviderError;
+
+/// schema migrations for the `outbox` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "outbox",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        }
+    ]
+};
+
+
+/// queue a message using `executor`, pass a transaction to enqueue the
+/// message atomically with the change that caused it
+pub async fn enqueue_with<'c, E>(executor: E, message: &OutboxMessage) -> Result<uuid::Uuid, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'c>,
+{
+    let message_id = uuid::Uuid::new_v4();
+
+    sqlx::query("call outbox.message_add($1,$2,$3,$4,$5,$6);")
+        .bind(message_id)
+        .bind(&message.to)
+        .bind(&message.from)
+        .bind(&message.subject)
+        .bind(&message.text)
+        .bind(&message.html)
+        .execute(executor)
+        .await?;
+
+    return Ok(message_id);
+}
+
+fn entry_from_row(row: &PgRow) -> OutboxEntry {
+    let status: String = row.get("status");
+
+    return OutboxEntry {
+        message_id: row.get("message_id"),
+        message: OutboxMessage {
+            to: row.get("mail_to"),
+            from: row.get("mail_from"),
+            subject: row.get("subject"),
+            text: row.get("body_text"),
+            html: row.get("body_html"),
+        },
+        status: OutboxStatus::from(status.as_str()),
+        attempts: row.get("attempts"),
+        next_attempt: row.get("next_attempt"),
+        last_error: row.get("last_error"),
+        created: row.get("created"),
+        sent: row.get("sent"),
+    };
+}
+
+
+pub struct PostgresOutboxProvider {
+    dp: database_provider::DatabaseProvider,
+}
+
+impl PostgresOutboxProvider {
+    pub fn new(dp: &database_provider::DatabaseProvider) -> Self {
+        return Self { dp: dp.clone() };
+    }
+}
+
+impl OutboxProvider for PostgresOutboxProvider {
+    async fn enqueue(&self, message: &OutboxMessage) -> Result<uuid::Uuid, ProviderError> {
+        info!("enqueue");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match enqueue_with(&pool, message).await {
+                Ok(message_id) => {
+                    return Ok(message_id);
+                }
+                Err(e) => {
+                    error!("Error queueing outbox message: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn claim_due(&self, limit: i32, lease: i32) -> Result<Vec<OutboxEntry>, ProviderError> {
+        info!("claim_due");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from outbox.messages_claim($1,$2);")
+                .bind(limit)
+                .bind(lease)
+                .fetch_all(&pool)
+                .await
+            {
+                Ok(rows) => {
+                    debug!("claimed {} outbox message(s)", rows.len());
+                    return Ok(rows.iter().map(entry_from_row).collect());
+                }
+                Err(e) => {
+                    error!("Error claiming outbox messages: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn mark_sent(&self, message_id: &uuid::Uuid) -> Result<(), ProviderError> {
+        info!("mark_sent");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call outbox.message_sent($1);")
+                .bind(message_id)
+                .execute(&pool)
+                .await
+            {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("Error marking outbox message as sent: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn mark_failed(
+        &self,
+        message_id: &uuid::Uuid,
+        error: &str,
+        retry_at: Option<&chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), ProviderError> {
+        info!("mark_failed");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call outbox.message_failed($1,$2,$3);")
+                .bind(message_id)
+                .bind(error)
+                .bind(retry_at)
+                .execute(&pool)
+                .await
+            {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("Error marking outbox message as failed: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn fetch_failed(&self) -> Result<Vec<OutboxEntry>, ProviderError> {
+        info!("fetch_failed");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("select * from outbox.messages_fetch_failed();")
+                .fetch_all(&pool)
+                .await
+            {
+                Ok(rows) => {
+                    return Ok(rows.iter().map(entry_from_row).collect());
+                }
+                Err(e) => {
+                    error!("Error fetching failed outbox messages: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn retry(&self, message_id: &uuid::Uuid) -> Result<(), ProviderError> {
+        info!("retry");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call outbox.message_retry($1);")
+                .bind(message_id)
+                .execute(&pool)
+                .await
+            {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("Error retrying outbox message: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_outbox_dead_letter_and_retry() {
+        if let Err(e) = tracing_subscriber::fmt::try_init() {
+            println!("error: {:?}", e);
+        }
+
+        let cfg = config::Config::from_env();
+        let db_provider = database_provider::DatabaseProvider::new(&cfg);
+        let dp = actix_web::web::Data::new(std::sync::Arc::new(db_provider));
+
+        let op = PostgresOutboxProvider::new(&dp);
+
+        let subject = format!("outbox test {}", uuid::Uuid::new_v4());
+        let message = OutboxMessage::new("user@example.com", &subject, "text").with_html("<p>html</p>");
+
+        let message_id = match op.enqueue(&message).await {
+            Err(e) => {
+                error!("{}", e);
+                assert!(false, "unable to queue message");
+                return;
+            }
+            Ok(message_id) => message_id,
+        };
+
+        // other tests may have queued messages, claim until ours shows up
+        let mut claimed = None;
+        for _ in 0..10 {
+            match op.claim_due(100, 60).await {
+                Err(e) => {
+                    error!("{}", e);
+                    assert!(false, "unable to claim messages");
+                }
+                Ok(entries) => {
+                    claimed = entries.into_iter().find(|e| e.message_id == message_id);
+                    if claimed.is_some() {
+                        break;
+                    }
+                }
+            }
+        }
+        let Some(entry) = claimed else {
+            assert!(false, "queued message was not claimed");
+            return;
+        };
+        assert_eq!(entry.message.subject, subject);
+        assert_eq!(entry.message.html.as_deref(), Some("<p>html</p>"));
+        assert_eq!(entry.status, OutboxStatus::Pending);
+
+        // a leased message is not handed out twice
+        if let Ok(entries) = op.claim_due(100, 60).await {
+            assert!(!entries.iter().any(|e| e.message_id == message_id));
+        }
+
+        if let Err(e) = op.mark_failed(&message_id, "connection refused", None).await {
+            error!("{}", e);
+            assert!(false, "unable to dead-letter message");
+        }
+
+        match op.fetch_failed().await {
+            Err(e) => {
+                error!("{}", e);
+                assert!(false, "unable to fetch failed messages");
+            }
+            Ok(entries) => {
+                let Some(failed) = entries.iter().find(|e| e.message_id == message_id) else {
+                    assert!(false, "dead-lettered message is not listed");
+                    return;
+                };
+                assert_eq!(failed.status, OutboxStatus::Failed);
+                assert_eq!(failed.attempts, 1);
+                assert_eq!(failed.last_error.as_deref(), Some("connection refused"));
+            }
+        }
+
+        if let Err(e) = op.retry(&message_id).await {
+            error!("{}", e);
+            assert!(false, "unable to retry message");
+        }
+        assert_eq!(op.retry(&message_id).await, Err(ProviderError::NotFound));
+
+        if let Err(e) = op.mark_sent(&message_id).await {
+            error!("{}", e);
+            assert!(false, "unable to mark message as sent");
+        }
+    }
+}
@@ -3,6 +3,8 @@ use serde::{
     Serialize
 };
 
+use provider_error::ProviderError;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Permission {
     pub id: i32,
@@ -17,15 +19,15 @@ pub trait PermissionsProvider {
     fn fetch(
         &self,
         filter: &str
-    ) -> impl Future<Output = Result<Vec<Permission>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Permission>, ProviderError>> + Send;
 
     fn fetch_by_id(
         &self,
         id: &i32
-    ) -> impl Future<Output = Result<Permission, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Permission, ProviderError>> + Send;
 
     fn fetch_by_name(
         &self,
         name: &str
-    ) -> impl Future<Output = Result<Permission, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Permission, ProviderError>> + Send;
 }
\ No newline at end of file
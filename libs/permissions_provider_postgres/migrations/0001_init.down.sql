@@ -0,0 +1 @@
+drop schema if exists permissions cascade;
@@ -0,0 +1,94 @@
+-- permission catalog, permissions are granted to roles in the tenants schema
+
+create schema if not exists permissions;
+
+
+create table permissions.permissions (
+    id int not null,
+    active boolean not null default true,
+    name text not null,
+    description text not null default '',
+    constraint pk_permissions primary key (id),
+    constraint u_permissions_name unique (name)
+);
+
+
+insert into permissions.permissions (id, name, description) values
+    (1, 'tenant.fetch', 'fetch a tenant'),
+    (2, 'tenant.save', 'create or update a tenant'),
+    (3, 'tenant.list', 'list tenants'),
+    (4, 'tenant.users.list', 'list the users of a tenant'),
+    (5, 'tenant.set.active', 'activate or deactivate a tenant'),
+    (6, 'tenant.roles.save', 'create or update a role'),
+    (7, 'tenant.roles.fetch', 'fetch a role'),
+    (8, 'tenant.roles.list', 'list roles'),
+    (9, 'tenant.role.assign.users', 'assign users to a role'),
+    (10, 'tenant.role.assign.permission', 'assign permissions to a role'),
+    (11, 'tenant.role.set.active', 'activate or deactivate a role'),
+    (100, 'files.upload', 'upload files'),
+    (101, 'files.folders.create', 'create folders'),
+    (102, 'files.folders.list.folders', 'list folder contents');
+
+
+create function permissions.permissions_fetch(
+    p_filter text
+)
+returns table (
+    id int,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        p.id,
+        p.name,
+        p.description
+    from permissions.permissions p
+    where
+        p.active
+        and (
+            p.name ilike '%' || p_filter || '%'
+            or p.description ilike '%' || p_filter || '%'
+        )
+    order by p.name;
+$$;
+
+create function permissions.permission_fetch_by_id(
+    p_id int
+)
+returns table (
+    id int,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        p.id,
+        p.name,
+        p.description
+    from permissions.permissions p
+    where p.id = p_id;
+$$;
+
+create function permissions.permission_fetch_by_name(
+    p_name text
+)
+returns table (
+    id int,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        p.id,
+        p.name,
+        p.description
+    from permissions.permissions p
+    where p.name = p_name;
+$$;
@@ -0,0 +1,5 @@
+-- permissions for the mail outbox administration endpoints
+
+insert into permissions.permissions (id, name, description) values
+    (200, 'outbox.list', 'list undeliverable mail'),
+    (201, 'outbox.retry', 'queue undeliverable mail again');
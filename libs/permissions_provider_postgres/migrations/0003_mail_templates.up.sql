@@ -0,0 +1,5 @@
+-- permissions for managing the mail templates of a tenant
+
+insert into permissions.permissions (id, name, description) values
+    (210, 'templates.list', 'list mail templates'),
+    (211, 'templates.save', 'override or reset mail templates');
@@ -0,0 +1,4 @@
+-- permission to download file content
+
+insert into permissions.permissions (id, name, description) values
+    (103, 'files.download', 'download files');
@@ -0,0 +1 @@
+delete from permissions.permissions where id = 104;
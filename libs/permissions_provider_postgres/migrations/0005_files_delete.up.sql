@@ -0,0 +1,4 @@
+-- permission to delete files
+
+insert into permissions.permissions (id, name, description) values
+    (104, 'files.delete', 'delete files');
@@ -0,0 +1 @@
+delete from permissions.permissions where id in (105, 106);
@@ -0,0 +1,5 @@
+-- permissions for file versions, uploading a version requires files.upload
+
+insert into permissions.permissions (id, name, description) values
+    (105, 'files.versions.list', 'list versions of files'),
+    (106, 'files.versions.restore', 'restore older versions of files');
@@ -0,0 +1,5 @@
+-- permissions for managing folders, the trash is covered by files.folders.delete
+
+insert into permissions.permissions (id, name, description) values
+    (107, 'files.folders.update', 'rename and move folders'),
+    (108, 'files.folders.delete', 'move folders to the trash and restore them');
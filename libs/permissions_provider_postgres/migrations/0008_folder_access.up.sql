@@ -0,0 +1,6 @@
+-- sharing folders requires manage access to the folder as well,
+-- files.folders.all bypasses folder access control within the tenant
+
+insert into permissions.permissions (id, name, description) values
+    (109, 'files.folders.share', 'view and change who has access to folders'),
+    (110, 'files.folders.all', 'access all folders and files of the tenant regardless of sharing');
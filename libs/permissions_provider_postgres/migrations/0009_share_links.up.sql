@@ -0,0 +1,5 @@
+-- share links are managed by users with manage access to the shared file
+-- or folder
+
+insert into permissions.permissions (id, name, description) values
+    (111, 'files.share.links', 'create, list and revoke share links to files and folders and view their accesses');
@@ -0,0 +1,5 @@
+-- attachments are only listed and changed for files the user can see
+
+insert into permissions.permissions (id, name, description) values
+    (112, 'files.attachments.list', 'list files attached to business records'),
+    (113, 'files.attachments.update', 'attach files to and detach them from business records');
@@ -0,0 +1,5 @@
+-- usage is shown to tenant admins, quotas are set by whoever manages tenants
+
+insert into permissions.permissions (id, name, description) values
+    (12, 'tenant.quota.save', 'set or remove the storage quota of a tenant'),
+    (114, 'files.usage', 'show the storage used by the tenant and its limits');
@@ -0,0 +1,4 @@
+-- permission to search files by name and content
+
+insert into permissions.permissions (id, name, description) values
+    (115, 'files.search', 'search files by name and content');
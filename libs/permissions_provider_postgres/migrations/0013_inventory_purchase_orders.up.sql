@@ -0,0 +1,4 @@
+-- permission to fetch purchase orders of the tenant
+
+insert into permissions.permissions (id, name, description) values
+    (300, 'inventory.purchase_orders.fetch', 'fetch purchase orders');
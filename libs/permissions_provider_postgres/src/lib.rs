@@ -6,6 +6,94 @@ use tracing::{
 
 use sqlx::Row;
 
+use provider_error::ProviderError;
+
+
+/// schema migrations for the `permissions` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "permissions",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 2,
+            name: "outbox",
+            up: include_str!("../migrations/0002_outbox.up.sql"),
+            down: include_str!("../migrations/0002_outbox.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 3,
+            name: "mail_templates",
+            up: include_str!("../migrations/0003_mail_templates.up.sql"),
+            down: include_str!("../migrations/0003_mail_templates.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 4,
+            name: "files_download",
+            up: include_str!("../migrations/0004_files_download.up.sql"),
+            down: include_str!("../migrations/0004_files_download.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 5,
+            name: "files_delete",
+            up: include_str!("../migrations/0005_files_delete.up.sql"),
+            down: include_str!("../migrations/0005_files_delete.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 6,
+            name: "file_versions",
+            up: include_str!("../migrations/0006_file_versions.up.sql"),
+            down: include_str!("../migrations/0006_file_versions.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 7,
+            name: "folders",
+            up: include_str!("../migrations/0007_folders.up.sql"),
+            down: include_str!("../migrations/0007_folders.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 8,
+            name: "folder_access",
+            up: include_str!("../migrations/0008_folder_access.up.sql"),
+            down: include_str!("../migrations/0008_folder_access.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 9,
+            name: "share_links",
+            up: include_str!("../migrations/0009_share_links.up.sql"),
+            down: include_str!("../migrations/0009_share_links.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 10,
+            name: "attachments",
+            up: include_str!("../migrations/0010_attachments.up.sql"),
+            down: include_str!("../migrations/0010_attachments.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 11,
+            name: "quotas",
+            up: include_str!("../migrations/0011_quotas.up.sql"),
+            down: include_str!("../migrations/0011_quotas.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 12,
+            name: "files_search",
+            up: include_str!("../migrations/0012_files_search.up.sql"),
+            down: include_str!("../migrations/0012_files_search.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 13,
+            name: "inventory_purchase_orders",
+            up: include_str!("../migrations/0013_inventory_purchase_orders.up.sql"),
+            down: include_str!("../migrations/0013_inventory_purchase_orders.down.sql")
+        }
+    ]
+};
+
 
 pub struct PostgresPermissionsProvider {
     dp: database_provider::DatabaseProvider
@@ -29,7 +117,7 @@ impl permissions_provider::PermissionsProvider for PostgresPermissionsProvider {
     async fn fetch(
         &self,
         filter: &str
-    ) -> Result<Vec<permissions_provider::Permission>, &'static str> {
+    ) -> Result<Vec<permissions_provider::Permission>, ProviderError> {
         info!("fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -55,19 +143,19 @@ impl permissions_provider::PermissionsProvider for PostgresPermissionsProvider {
                     }
                     Err(e) => {
                         error!("Error fetching permissions: {:?}", e);
-                        return Err("Error fetching permissions");
+                        return Err(database_provider::from_sqlx(&e));
                     }
                 }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn fetch_by_id(
         &self,
         id: &i32
-    ) -> Result<permissions_provider::Permission, &'static str> {
+    ) -> Result<permissions_provider::Permission, ProviderError> {
         info!("fetch_by_id");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -88,19 +176,19 @@ impl permissions_provider::PermissionsProvider for PostgresPermissionsProvider {
                     }
                     Err(e) => {
                         error!("Error fetching permissions: {:?}", e);
-                        return Err("Error fetching permissions");
+                        return Err(database_provider::from_sqlx(&e));
                     }
                 }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn fetch_by_name(
         &self,
         name: &str
-    ) -> Result<permissions_provider::Permission, &'static str> {
+    ) -> Result<permissions_provider::Permission, ProviderError> {
         info!("fetch_by_id");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -121,12 +209,12 @@ impl permissions_provider::PermissionsProvider for PostgresPermissionsProvider {
                     }
                     Err(e) => {
                         error!("Error fetching permissions: {:?}", e);
-                        return Err("Error fetching permissions");
+                        return Err(database_provider::from_sqlx(&e));
                     }
                 }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
@@ -153,17 +241,17 @@ mod tests {
         let pp = PostgresPermissionsProvider::new(&dp);
 
         if let Err(e) = pp.fetch("%").await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch permissions");
         }
 
         if let Err(e) = pp.fetch_by_id(&1).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch permission by id");
         }
 
         if let Err(e) = pp.fetch_by_name(&"tenant.save").await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch permission by name");
         }
     }
@@ -0,0 +1,66 @@
+#![allow(clippy::needless_return)]
+
+use std::fmt::Display;
+
+/// error returned by all providers, backends map their native errors onto
+/// these variants so callers can react without knowing the backend
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderError {
+    /// the requested record does not exist
+    NotFound,
+    /// the change collides with existing data (unique constraint,
+    /// concurrent update)
+    Conflict,
+    /// an input value was rejected, `field` names the offending column or
+    /// constraint when the backend reports one
+    Validation { field: String, reason: String },
+    /// the caller is not allowed to perform the operation
+    Forbidden,
+    /// the caller tried too often, retrying later may succeed
+    Throttled,
+    /// the backend cannot be reached or is not configured, retrying later
+    /// may succeed
+    Unavailable,
+    /// anything else, details are logged where the error occurs
+    Internal,
+}
+
+impl ProviderError {
+    pub fn validation(field: &str, reason: &str) -> Self {
+        return ProviderError::Validation {
+            field: String::from(field),
+            reason: String::from(reason),
+        };
+    }
+
+    /// stable machine-readable identifier of the variant
+    pub fn code(&self) -> &'static str {
+        return match self {
+            ProviderError::NotFound => "not_found",
+            ProviderError::Conflict => "conflict",
+            ProviderError::Validation { .. } => "validation",
+            ProviderError::Forbidden => "forbidden",
+            ProviderError::Throttled => "throttled",
+            ProviderError::Unavailable => "unavailable",
+            ProviderError::Internal => "internal",
+        };
+    }
+}
+
+impl Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProviderError::NotFound => write!(f, "record not found"),
+            ProviderError::Conflict => write!(f, "record conflicts with existing data"),
+            ProviderError::Validation { field, reason } => {
+                write!(f, "invalid value for '{}': {}", field, reason)
+            }
+            ProviderError::Forbidden => write!(f, "operation not permitted"),
+            ProviderError::Throttled => write!(f, "too many attempts"),
+            ProviderError::Unavailable => write!(f, "backend unavailable"),
+            ProviderError::Internal => write!(f, "internal error"),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
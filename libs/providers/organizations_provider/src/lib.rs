@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use provider_error::ProviderError;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Organization {
     pub org_id: uuid::Uuid,
@@ -27,10 +29,10 @@ pub trait OrganizationsProvider {
         &self,
         tenant_id: &uuid::Uuid,
         organization: &Organization,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn organizations_fetch_tree(
         &self,
         tenant_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Vec<OrganizationTreeItem>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<OrganizationTreeItem>, ProviderError>> + Send;
 }
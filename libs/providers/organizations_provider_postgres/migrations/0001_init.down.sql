@@ -0,0 +1 @@
+drop procedure if exists organizations.organization_save(uuid, uuid, text, text, uuid);
@@ -0,0 +1,32 @@
+-- the organizations table and tree query are created by the tenants
+-- component, this adds the save overload used by this provider
+
+create procedure organizations.organization_save(
+    p_tenant_id uuid,
+    p_org_id uuid,
+    p_name text,
+    p_description text,
+    p_parent_org_id uuid
+)
+language sql
+as $$
+    insert into organizations.organizations as o (
+        org_id,
+        tenant_id,
+        parent_org_id,
+        name,
+        description
+    ) values (
+        p_org_id,
+        p_tenant_id,
+        nullif(p_parent_org_id, '00000000-0000-0000-0000-000000000000'),
+        p_name,
+        p_description
+    )
+    on conflict (org_id) do update set
+        parent_org_id = excluded.parent_org_id,
+        name = excluded.name,
+        description = excluded.description,
+        version = o.version + 1,
+        updated = now();
+$$;
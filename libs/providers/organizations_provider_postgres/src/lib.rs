@@ -6,6 +6,22 @@ use sqlx::{Row, postgres::PgRow, prelude::FromRow};
 use std::collections::HashMap;
 use tracing::{debug, error, info};
 
+use provider_error::ProviderError;
+
+/// schema migrations for the `organizations` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "organizations",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        }
+    ]
+};
+
+
 pub struct OrganizationTreeItemImpl(pub OrganizationTreeItem);
 
 impl<'r> FromRow<'r, PgRow> for OrganizationTreeItemImpl {
@@ -38,7 +54,7 @@ impl OrganizationsProvider for OrganizationsProviderPostgres {
         &self,
         tenant_id: &uuid::Uuid,
         organization: &organizations_provider::Organization,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("organizations_save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -56,19 +72,19 @@ impl OrganizationsProvider for OrganizationsProviderPostgres {
                 }
                 Err(e) => {
                     error!("Error saving organization: {:?}", e);
-                    return Err("Error saving organization");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn organizations_fetch_tree(
         &self,
         tenant_id: &uuid::Uuid,
-    ) -> Result<Vec<OrganizationTreeItem>, &'static str> {
+    ) -> Result<Vec<OrganizationTreeItem>, ProviderError> {
         info!("organizations_fetch_tree");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -86,12 +102,12 @@ impl OrganizationsProvider for OrganizationsProviderPostgres {
                 }
                 Err(e) => {
                     error!("Error fetching organizations: {:?}", e);
-                    return Err("Error fetching organizations");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
@@ -132,12 +148,12 @@ mod tests {
         };
 
         if let Err(e) = opp.organizations_save(&tenant_id, &org_01).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to save organizations");
         }
 
         if let Err(e) = opp.organizations_fetch_tree(&tenant_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch tree of organizations");
         }
     }
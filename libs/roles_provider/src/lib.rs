@@ -3,6 +3,8 @@ use serde::{
     Deserialize
 };
 
+use provider_error::ProviderError;
+
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Role {
@@ -20,73 +22,73 @@ pub trait RolesProvider {
         &self,
         tenant_id: &uuid::Uuid,
         role: &Role
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn set_active(
         &self,
         role_id: &uuid::Uuid,
         active: &bool
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn set_active_multiple(
         &self,
         role_ids: &Vec<uuid::Uuid>,
         active: &bool
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn fetch(
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str
-    ) -> impl Future<Output = Result<Vec<Role>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Role>, ProviderError>> + Send;
 
     fn fetch_by_id(
         &self,
         role_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Role, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Role, ProviderError>> + Send;
 
     fn assign_users(
         &self,
         role_ids: &Vec<uuid::Uuid>,
         user_ids: &Vec<uuid::Uuid>
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn revoke_users(
         &self,
         role_ids: &Vec<uuid::Uuid>,
         user_ids: &Vec<uuid::Uuid>
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn role_user_set_active(
         &self,
         role_id: &uuid::Uuid,
         user_id: &uuid::Uuid,
         active: &bool
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn tenant_user_set_active(
         &self,
         tenant_id: &uuid::Uuid,
         user_id: &uuid::Uuid,
         active: &bool
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn assign_permissions(
         &self,
         role_ids: &Vec<uuid::Uuid>,
         permission_ids: &Vec<i32>
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn revoke_permissions(
         &self,
         role_ids: &Vec<uuid::Uuid>,
         permission_ids: &Vec<i32>
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn role_permission_set_active(
         &self,
         role_id: &uuid::Uuid,
         permission_ids: &Vec<i32>,
         active: bool
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 }
@@ -0,0 +1,11 @@
+drop function if exists tenants.role_fetch(uuid);
+drop function if exists tenants.roles_fetch(uuid, text);
+drop procedure if exists tenants.role_permission_set_active(uuid, int[], boolean);
+drop procedure if exists tenants.role_permissions_remove(uuid[], int[]);
+drop procedure if exists tenants.role_permissions_add(uuid[], int[]);
+drop procedure if exists tenants.role_user_set_active(uuid, uuid, boolean);
+drop procedure if exists tenants.role_users_remove(uuid[], uuid[]);
+drop procedure if exists tenants.role_users_add(uuid[], uuid[]);
+drop procedure if exists tenants.roles_set_active(uuid[], boolean);
+drop procedure if exists tenants.role_set_active(uuid, boolean);
+drop procedure if exists tenants.role_save(uuid, uuid, text, text);
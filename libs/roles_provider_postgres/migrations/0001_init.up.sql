@@ -0,0 +1,190 @@
+-- role management, the role tables are created by the tenants component
+
+create procedure tenants.role_save(
+    p_tenant_id uuid,
+    p_role_id uuid,
+    p_name text,
+    p_description text
+)
+language sql
+as $$
+    insert into tenants.roles (
+        role_id,
+        tenant_id,
+        name,
+        description
+    ) values (
+        p_role_id,
+        p_tenant_id,
+        p_name,
+        p_description
+    )
+    on conflict (role_id) do update set
+        name = excluded.name,
+        description = excluded.description,
+        updated = now();
+$$;
+
+create procedure tenants.role_set_active(
+    p_role_id uuid,
+    p_active boolean
+)
+language sql
+as $$
+    update tenants.roles set
+        active = p_active,
+        updated = now()
+    where role_id = p_role_id;
+$$;
+
+create procedure tenants.roles_set_active(
+    p_role_ids uuid[],
+    p_active boolean
+)
+language sql
+as $$
+    update tenants.roles set
+        active = p_active,
+        updated = now()
+    where role_id = any(p_role_ids);
+$$;
+
+create procedure tenants.role_users_add(
+    p_role_ids uuid[],
+    p_user_ids uuid[]
+)
+language sql
+as $$
+    insert into tenants.role_users (
+        role_id,
+        user_id
+    )
+    select r.role_id, u.user_id
+    from unnest(p_role_ids) as r (role_id)
+        cross join unnest(p_user_ids) as u (user_id)
+    on conflict (role_id, user_id) do update set
+        active = true;
+$$;
+
+create procedure tenants.role_users_remove(
+    p_role_ids uuid[],
+    p_user_ids uuid[]
+)
+language sql
+as $$
+    delete from tenants.role_users
+    where
+        role_id = any(p_role_ids)
+        and user_id = any(p_user_ids);
+$$;
+
+create procedure tenants.role_user_set_active(
+    p_role_id uuid,
+    p_user_id uuid,
+    p_active boolean
+)
+language sql
+as $$
+    update tenants.role_users set
+        active = p_active
+    where
+        role_id = p_role_id
+        and user_id = p_user_id;
+$$;
+
+create procedure tenants.role_permissions_add(
+    p_role_ids uuid[],
+    p_permission_ids int[]
+)
+language sql
+as $$
+    insert into tenants.role_permissions (
+        role_id,
+        permission_id
+    )
+    select r.role_id, p.permission_id
+    from unnest(p_role_ids) as r (role_id)
+        cross join unnest(p_permission_ids) as p (permission_id)
+    on conflict (role_id, permission_id) do update set
+        active = true;
+$$;
+
+create procedure tenants.role_permissions_remove(
+    p_role_ids uuid[],
+    p_permission_ids int[]
+)
+language sql
+as $$
+    delete from tenants.role_permissions
+    where
+        role_id = any(p_role_ids)
+        and permission_id = any(p_permission_ids);
+$$;
+
+create procedure tenants.role_permission_set_active(
+    p_role_id uuid,
+    p_permission_ids int[],
+    p_active boolean
+)
+language sql
+as $$
+    update tenants.role_permissions set
+        active = p_active
+    where
+        role_id = p_role_id
+        and permission_id = any(p_permission_ids);
+$$;
+
+
+create function tenants.roles_fetch(
+    p_tenant_id uuid,
+    p_filter text
+)
+returns table (
+    role_id uuid,
+    active boolean,
+    created timestamptz,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        r.role_id,
+        r.active,
+        r.created,
+        r.name,
+        r.description
+    from tenants.roles r
+    where
+        r.tenant_id = p_tenant_id
+        and (
+            r.name ilike '%' || p_filter || '%'
+            or r.description ilike '%' || p_filter || '%'
+        )
+    order by r.name;
+$$;
+
+create function tenants.role_fetch(
+    p_role_id uuid
+)
+returns table (
+    role_id uuid,
+    active boolean,
+    created timestamptz,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        r.role_id,
+        r.active,
+        r.created,
+        r.name,
+        r.description
+    from tenants.roles r
+    where r.role_id = p_role_id;
+$$;
@@ -4,6 +4,22 @@ use tracing::{debug, error, info};
 
 use sqlx::Row;
 
+use provider_error::ProviderError;
+
+/// schema migrations for the `roles` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "roles",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        }
+    ]
+};
+
+
 pub struct PostgresRolesProvider {
     dp: database_provider::DatabaseProvider,
 }
@@ -19,7 +35,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
         &self,
         tenant_id: &uuid::Uuid,
         role: &roles_provider::Role,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -36,16 +52,16 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
                 }
                 Err(e) => {
                     error!("Error saving role record: {:?}", e);
-                    return Err("Error saving role record");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn set_active(&self, role_id: &uuid::Uuid, active: &bool) -> Result<(), &'static str> {
+    async fn set_active(&self, role_id: &uuid::Uuid, active: &bool) -> Result<(), ProviderError> {
         info!("save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -60,12 +76,12 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
                 }
                 Err(e) => {
                     error!("Error setting active state of role: {:?}", e);
-                    return Err("Error setting active state of role");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -73,7 +89,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
         &self,
         role_ids: &Vec<uuid::Uuid>,
         active: &bool,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("set_active_multiple");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -88,12 +104,12 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
                 }
                 Err(e) => {
                     error!("Error setting active state of roles: {:?}", e);
-                    return Err("Error setting active state of roles");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -101,7 +117,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> Result<Vec<roles_provider::Role>, &'static str> {
+    ) -> Result<Vec<roles_provider::Role>, ProviderError> {
         info!("fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -134,19 +150,19 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
                 }
                 Err(e) => {
                     error!("Error fetching role records: {:?}", e);
-                    return Err("Error fetching role records");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn fetch_by_id(
         &self,
         role_id: &uuid::Uuid,
-    ) -> Result<roles_provider::Role, &'static str> {
+    ) -> Result<roles_provider::Role, ProviderError> {
         info!("fetch_by_id");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -172,12 +188,12 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
                 }
                 Err(e) => {
                     error!("Error fetching role record: {:?}", e);
-                    return Err("Error fetching role record");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -185,7 +201,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
         &self,
         role_ids: &Vec<uuid::Uuid>,
         user_ids: &Vec<uuid::Uuid>,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("assign_users");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -197,7 +213,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             {
                 Err(e) => {
                     error!("Error assigning users to role: {:?}", e);
-                    return Err("Error assigning users to role");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -205,7 +221,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -213,7 +229,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
         &self,
         role_ids: &Vec<uuid::Uuid>,
         user_ids: &Vec<uuid::Uuid>,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("revoke_users");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -225,7 +241,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             {
                 Err(e) => {
                     error!("Error revoking users from role: {:?}", e);
-                    return Err("Error revoking users from role");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -233,7 +249,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -242,7 +258,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
         role_id: &uuid::Uuid,
         user_id: &uuid::Uuid,
         active: &bool,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("revoke_users");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -255,7 +271,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             {
                 Err(e) => {
                     error!("Error setting active state of role: {:?}", e);
-                    return Err("Error setting active state of role");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -263,7 +279,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -272,7 +288,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
         tenant_id: &uuid::Uuid,
         user_id: &uuid::Uuid,
         active: &bool,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("revoke_users");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -285,7 +301,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             {
                 Err(e) => {
                     error!("Error setting active state of tenant users: {:?}", e);
-                    return Err("Error setting active state of tenant users");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -293,7 +309,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -301,7 +317,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
         &self,
         role_ids: &Vec<uuid::Uuid>,
         permission_ids: &Vec<i32>,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("assign_permissions");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -313,7 +329,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             {
                 Err(e) => {
                     error!("Error assigning permissions for role: {:?}", e);
-                    return Err("Error assigning permissions for role");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -321,7 +337,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -329,7 +345,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
         &self,
         role_ids: &Vec<uuid::Uuid>,
         permission_ids: &Vec<i32>,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("revoke_permissions");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -341,7 +357,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             {
                 Err(e) => {
                     error!("Error revoking permissions from role: {:?}", e);
-                    return Err("Error revoking permissions from role");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -349,7 +365,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -358,7 +374,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
         role_id: &uuid::Uuid,
         permission_ids: &Vec<i32>,
         active: bool,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("revoke_permissions");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -371,7 +387,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             {
                 Err(e) => {
                     error!("Error setting active state of role permission: {:?}", e);
-                    return Err("Error setting active state of role permission");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -379,7 +395,7 @@ impl roles_provider::RolesProvider for PostgresRolesProvider {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
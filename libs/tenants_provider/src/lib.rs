@@ -1,4 +1,5 @@
 pub mod organizations;
+pub mod templates;
 
 use tracing::{debug, error, info};
 
@@ -7,6 +8,8 @@ use serde::Serialize;
 use std::vec::Vec;
 use uuid::Uuid;
 
+use provider_error::ProviderError;
+
 #[derive(Debug, Serialize, Clone)]
 pub struct Tenant {
     pub id: uuid::Uuid,
@@ -91,12 +94,12 @@ pub trait TenantsProvider {
     fn tenants_fetch_by_id(
         &self,
         tenant_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Tenant, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Tenant, ProviderError>> + Send;
 
     fn tenant_fetch_by_name(
         &self,
         name: &str,
-    ) -> impl Future<Output = Result<Tenant, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Tenant, ProviderError>> + Send;
 
     fn tenant_save(
         &self,
@@ -104,33 +107,33 @@ pub trait TenantsProvider {
         name: &str,
         description: &str,
         version: &i32,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn tenant_set_active(
         &self,
         tenant_id: &uuid::Uuid,
         active: &bool,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn tenants_set_active(
         &self,
         tenant_ids: &Vec<uuid::Uuid>,
         active: &bool,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn tenants_fetch(
         &self,
         filter: &str,
-    ) -> impl Future<Output = Result<Vec<Tenant>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Tenant>, ProviderError>> + Send;
 
     fn tenant_user_tenants_fetch(
         &self,
         user_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Vec<Tenant>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Tenant>, ProviderError>> + Send;
 
     fn tenant_user_permissions_fetch(
         &self,
         user_id: &uuid::Uuid,
         tenant_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Vec<Permission>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<Permission>, ProviderError>> + Send;
 }
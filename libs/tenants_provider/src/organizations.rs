@@ -5,6 +5,8 @@ use serde::Serialize;
 use std::vec::Vec;
 use uuid::Uuid;
 
+use provider_error::ProviderError;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct OrganizationData {
     pub org_id: Uuid,
@@ -33,27 +35,27 @@ pub trait OrganizationsProvider {
         name: &str,
         description: &str,
         version: &i32,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn fetch(
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> impl Future<Output = Result<Vec<OrganizationData>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<OrganizationData>, ProviderError>> + Send;
 
     fn fetch_tree(
         &self,
         tenant_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<Vec<OrganizationNodeData>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<Vec<OrganizationNodeData>, ProviderError>> + Send;
 
     fn fetch_by_id(
         &self,
         org_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<OrganizationData, &'static str>> + Send;
+    ) -> impl Future<Output = Result<OrganizationData, ProviderError>> + Send;
 
     fn fetch_by_name(
         &self,
         tenant_id: &uuid::Uuid,
         name: &str,
-    ) -> impl Future<Output = Result<OrganizationData, &'static str>> + Send;
+    ) -> impl Future<Output = Result<OrganizationData, ProviderError>> + Send;
 }
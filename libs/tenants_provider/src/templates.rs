@@ -0,0 +1,43 @@
+use core::future::Future;
+use serde::Serialize;
+use std::vec::Vec;
+
+use provider_error::ProviderError;
+
+/// tenant override of a built-in mail template in one locale
+#[derive(Debug, Clone, Serialize)]
+pub struct MailTemplate {
+    pub tenant_id: uuid::Uuid,
+    pub name: String,
+    pub locale: String,
+    pub updated: chrono::DateTime<chrono::Utc>,
+    pub subject: String,
+    pub text: String,
+    pub html: Option<String>,
+}
+
+pub trait MailTemplatesProvider {
+    /// overrides of a tenant, of every template when `name` is `None`
+    fn templates_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        name: Option<&str>,
+    ) -> impl Future<Output = Result<Vec<MailTemplate>, ProviderError>> + Send;
+
+    fn template_save(
+        &self,
+        tenant_id: &uuid::Uuid,
+        name: &str,
+        locale: &str,
+        subject: &str,
+        text: &str,
+        html: Option<&str>,
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+
+    fn template_delete(
+        &self,
+        tenant_id: &uuid::Uuid,
+        name: &str,
+        locale: &str,
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
+}
@@ -0,0 +1,2 @@
+drop schema if exists organizations cascade;
+drop schema if exists tenants cascade;
@@ -0,0 +1,549 @@
+-- tenants, tenant membership and tenant scoped roles
+--
+-- role tables live here rather than in the roles component because
+-- tenant_user_fetch_permissions resolves a user's permissions through them
+
+create schema if not exists tenants;
+
+
+create table tenants.tenants (
+    tenant_id uuid not null,
+    active boolean not null default true,
+    version int not null default 0,
+    created timestamptz not null default now(),
+    updated timestamptz not null default now(),
+    name text not null,
+    description text not null default '',
+    constraint pk_tenants primary key (tenant_id),
+    constraint u_tenants_name unique (name)
+);
+
+create table tenants.tenant_users (
+    tenant_id uuid not null,
+    user_id uuid not null,
+    active boolean not null default true,
+    created timestamptz not null default now(),
+    constraint pk_tenant_users primary key (tenant_id, user_id),
+    constraint fk_tenant_users_tenant foreign key (tenant_id) references tenants.tenants (tenant_id) on delete cascade,
+    constraint fk_tenant_users_user foreign key (user_id) references users.users (user_id) on delete cascade
+);
+
+create index ix_tenant_users_user on tenants.tenant_users (user_id);
+
+create table tenants.roles (
+    role_id uuid not null,
+    tenant_id uuid not null,
+    active boolean not null default true,
+    created timestamptz not null default now(),
+    updated timestamptz not null default now(),
+    name text not null,
+    description text not null default '',
+    constraint pk_roles primary key (role_id),
+    constraint fk_roles_tenant foreign key (tenant_id) references tenants.tenants (tenant_id) on delete cascade,
+    constraint u_roles_name unique (tenant_id, name)
+);
+
+create table tenants.role_users (
+    role_id uuid not null,
+    user_id uuid not null,
+    active boolean not null default true,
+    created timestamptz not null default now(),
+    constraint pk_role_users primary key (role_id, user_id),
+    constraint fk_role_users_role foreign key (role_id) references tenants.roles (role_id) on delete cascade,
+    constraint fk_role_users_user foreign key (user_id) references users.users (user_id) on delete cascade
+);
+
+create index ix_role_users_user on tenants.role_users (user_id);
+
+create table tenants.role_permissions (
+    role_id uuid not null,
+    permission_id int not null,
+    active boolean not null default true,
+    created timestamptz not null default now(),
+    constraint pk_role_permissions primary key (role_id, permission_id),
+    constraint fk_role_permissions_role foreign key (role_id) references tenants.roles (role_id) on delete cascade,
+    constraint fk_role_permissions_permission foreign key (permission_id) references permissions.permissions (id) on delete cascade
+);
+
+
+-- the nil tenant is the tenant of a freshly signed in user
+insert into tenants.tenants (tenant_id, name, description) values
+    ('00000000-0000-0000-0000-000000000000', 'default', 'default tenant');
+
+-- users still have to be assigned to this role
+insert into tenants.roles (role_id, tenant_id, name, description) values
+    ('00000000-0000-0000-0000-000000000001', '00000000-0000-0000-0000-000000000000', 'administrator', 'all permissions');
+
+insert into tenants.role_permissions (role_id, permission_id)
+select '00000000-0000-0000-0000-000000000001', p.id
+from permissions.permissions p;
+
+
+create procedure tenants.tenant_save(
+    p_tenant_id uuid,
+    p_name text,
+    p_description text,
+    p_version int
+)
+language sql
+as $$
+    insert into tenants.tenants as t (
+        tenant_id,
+        name,
+        description,
+        version
+    ) values (
+        p_tenant_id,
+        p_name,
+        p_description,
+        p_version
+    )
+    on conflict (tenant_id) do update set
+        name = excluded.name,
+        description = excluded.description,
+        version = t.version + 1,
+        updated = now();
+$$;
+
+create procedure tenants.tenant_set_active(
+    p_tenant_id uuid,
+    p_active boolean
+)
+language sql
+as $$
+    update tenants.tenants set
+        active = p_active,
+        updated = now()
+    where tenant_id = p_tenant_id;
+$$;
+
+create procedure tenants.tenants_set_active(
+    p_tenant_ids uuid[],
+    p_active boolean
+)
+language sql
+as $$
+    update tenants.tenants set
+        active = p_active,
+        updated = now()
+    where tenant_id = any(p_tenant_ids);
+$$;
+
+create procedure tenants.tenant_user_save(
+    p_tenant_id uuid,
+    p_user_id uuid
+)
+language sql
+as $$
+    insert into tenants.tenant_users (
+        tenant_id,
+        user_id
+    ) values (
+        p_tenant_id,
+        p_user_id
+    )
+    on conflict (tenant_id, user_id) do update set
+        active = true;
+$$;
+
+create procedure tenants.tenant_users_assign(
+    p_tenant_ids uuid[],
+    p_user_ids uuid[]
+)
+language sql
+as $$
+    insert into tenants.tenant_users (
+        tenant_id,
+        user_id
+    )
+    select t.tenant_id, u.user_id
+    from unnest(p_tenant_ids) as t (tenant_id)
+        cross join unnest(p_user_ids) as u (user_id)
+    on conflict (tenant_id, user_id) do update set
+        active = true;
+$$;
+
+create procedure tenants.tenant_user_set_active(
+    p_tenant_id uuid,
+    p_user_id uuid,
+    p_active boolean
+)
+language sql
+as $$
+    update tenants.tenant_users set
+        active = p_active
+    where
+        tenant_id = p_tenant_id
+        and user_id = p_user_id;
+$$;
+
+
+create function tenants.tenants_fetch_by_id(
+    p_tenant_id uuid
+)
+returns table (
+    tenant_id uuid,
+    active boolean,
+    version int,
+    created timestamptz,
+    updated timestamptz,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        t.tenant_id,
+        t.active,
+        t.version,
+        t.created,
+        t.updated,
+        t.name,
+        t.description
+    from tenants.tenants t
+    where t.tenant_id = p_tenant_id;
+$$;
+
+create function tenants.tenant_fetch_by_name(
+    p_name text
+)
+returns table (
+    tenant_id uuid,
+    active boolean,
+    version int,
+    created timestamptz,
+    updated timestamptz,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        t.tenant_id,
+        t.active,
+        t.version,
+        t.created,
+        t.updated,
+        t.name,
+        t.description
+    from tenants.tenants t
+    where t.name = p_name;
+$$;
+
+create function tenants.tenants_fetch(
+    p_filter text
+)
+returns table (
+    tenant_id uuid,
+    active boolean,
+    version int,
+    created timestamptz,
+    updated timestamptz,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        t.tenant_id,
+        t.active,
+        t.version,
+        t.created,
+        t.updated,
+        t.name,
+        t.description
+    from tenants.tenants t
+    where
+        t.name ilike '%' || p_filter || '%'
+        or t.description ilike '%' || p_filter || '%'
+    order by t.name;
+$$;
+
+create function tenants.tenant_user_fetch_tenants(
+    p_user_id uuid
+)
+returns table (
+    tenant_id uuid,
+    active boolean,
+    version int,
+    created timestamptz,
+    updated timestamptz,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        t.tenant_id,
+        t.active,
+        t.version,
+        t.created,
+        t.updated,
+        t.name,
+        t.description
+    from tenants.tenants t
+        join tenants.tenant_users tu on tu.tenant_id = t.tenant_id
+    where
+        tu.user_id = p_user_id
+        and tu.active
+        and t.active
+    order by t.name;
+$$;
+
+create function tenants.tenant_user_fetch_permissions(
+    p_user_id uuid,
+    p_tenant_id uuid
+)
+returns table (
+    permission_id int,
+    name text
+)
+language sql
+stable
+as $$
+    select distinct
+        p.id as permission_id,
+        p.name
+    from tenants.role_users ru
+        join tenants.roles r on r.role_id = ru.role_id
+        join tenants.role_permissions rp on rp.role_id = r.role_id
+        join permissions.permissions p on p.id = rp.permission_id
+    where
+        ru.user_id = p_user_id
+        and r.tenant_id = p_tenant_id
+        and ru.active
+        and r.active
+        and rp.active
+        and p.active
+    order by p.name;
+$$;
+
+create function tenants.tenant_users_fetch(
+    p_tenant_id uuid,
+    p_filter text
+)
+returns table (
+    user_id uuid,
+    active boolean,
+    created timestamptz,
+    first_name text,
+    middle_name text,
+    last_name text,
+    prefix text,
+    suffix text,
+    email text
+)
+language sql
+stable
+as $$
+    select
+        v.user_id,
+        v.active and tu.active,
+        v.created,
+        v.first_name,
+        v.middle_name,
+        v.last_name,
+        v.prefix,
+        v.suffix,
+        v.email
+    from users.v_users v
+        join tenants.tenant_users tu on tu.user_id = v.user_id
+    where
+        tu.tenant_id = p_tenant_id
+        and (
+            v.email ilike '%' || p_filter || '%'
+            or v.first_name ilike '%' || p_filter || '%'
+            or v.last_name ilike '%' || p_filter || '%'
+        )
+    order by v.email;
+$$;
+
+
+-- organizations within a tenant
+
+create schema if not exists organizations;
+
+
+create table organizations.organizations (
+    org_id uuid not null,
+    tenant_id uuid not null,
+    parent_org_id uuid,
+    active boolean not null default true,
+    version int not null default 0,
+    created timestamptz not null default now(),
+    updated timestamptz not null default now(),
+    name text not null,
+    description text not null default '',
+    constraint pk_organizations primary key (org_id),
+    constraint fk_organizations_tenant foreign key (tenant_id) references tenants.tenants (tenant_id) on delete cascade,
+    constraint fk_organizations_parent foreign key (parent_org_id) references organizations.organizations (org_id),
+    constraint u_organizations_name unique (tenant_id, name)
+);
+
+create index ix_organizations_parent on organizations.organizations (parent_org_id);
+
+
+-- a nil parent makes the organization a root
+create procedure organizations.organization_save(
+    p_tenant_id uuid,
+    p_org_id uuid,
+    p_parent_org_id uuid,
+    p_name text,
+    p_description text,
+    p_version int
+)
+language sql
+as $$
+    insert into organizations.organizations as o (
+        org_id,
+        tenant_id,
+        parent_org_id,
+        name,
+        description,
+        version
+    ) values (
+        p_org_id,
+        p_tenant_id,
+        nullif(p_parent_org_id, '00000000-0000-0000-0000-000000000000'),
+        p_name,
+        p_description,
+        p_version
+    )
+    on conflict (org_id) do update set
+        parent_org_id = excluded.parent_org_id,
+        name = excluded.name,
+        description = excluded.description,
+        version = o.version + 1,
+        updated = now();
+$$;
+
+create function organizations.organizations_fetch(
+    p_tenant_id uuid,
+    p_filter text
+)
+returns table (
+    org_id uuid,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        o.org_id,
+        o.name,
+        o.description
+    from organizations.organizations o
+    where
+        o.tenant_id = p_tenant_id
+        and (
+            o.name ilike '%' || p_filter || '%'
+            or o.description ilike '%' || p_filter || '%'
+        )
+    order by o.name;
+$$;
+
+create function organizations.organizations_fetch_tree(
+    p_tenant_id uuid
+)
+returns table (
+    org_id uuid,
+    parent_org_id uuid,
+    active boolean,
+    created timestamptz,
+    updated timestamptz,
+    name text,
+    description text,
+    level int,
+    path text
+)
+language sql
+stable
+as $$
+    with recursive tree as (
+        select
+            o.org_id,
+            o.parent_org_id,
+            o.active,
+            o.created,
+            o.updated,
+            o.name,
+            o.description,
+            0 as level,
+            o.name as path
+        from organizations.organizations o
+        where
+            o.tenant_id = p_tenant_id
+            and o.parent_org_id is null
+        union all
+        select
+            o.org_id,
+            o.parent_org_id,
+            o.active,
+            o.created,
+            o.updated,
+            o.name,
+            o.description,
+            t.level + 1,
+            t.path || '/' || o.name
+        from organizations.organizations o
+            join tree t on t.org_id = o.parent_org_id
+    )
+    select
+        tree.org_id,
+        tree.parent_org_id,
+        tree.active,
+        tree.created,
+        tree.updated,
+        tree.name,
+        tree.description,
+        tree.level,
+        tree.path
+    from tree
+    order by tree.path;
+$$;
+
+create function organizations.organization_fetch_by_id(
+    p_org_id uuid
+)
+returns table (
+    org_id uuid,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        o.org_id,
+        o.name,
+        o.description
+    from organizations.organizations o
+    where o.org_id = p_org_id;
+$$;
+
+create function organizations.organization_fetch_by_name(
+    p_tenant_id uuid,
+    p_name text
+)
+returns table (
+    org_id uuid,
+    name text,
+    description text
+)
+language sql
+stable
+as $$
+    select
+        o.org_id,
+        o.name,
+        o.description
+    from organizations.organizations o
+    where
+        o.tenant_id = p_tenant_id
+        and o.name = p_name;
+$$;
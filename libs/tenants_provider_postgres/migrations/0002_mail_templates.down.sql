@@ -0,0 +1,4 @@
+drop procedure if exists tenants.mail_template_delete(uuid, text, text);
+drop procedure if exists tenants.mail_template_save(uuid, text, text, text, text, text);
+drop function if exists tenants.mail_templates_fetch(uuid, text);
+drop table if exists tenants.mail_templates;
@@ -0,0 +1,97 @@
+-- tenant overrides of the built-in mail templates, one row per template and locale
+
+create table tenants.mail_templates (
+    tenant_id uuid not null,
+    name text not null,
+    locale text not null,
+    updated timestamptz not null default now(),
+    subject text not null,
+    body_text text not null,
+    body_html text,
+    constraint pk_mail_templates primary key (tenant_id, name, locale),
+    constraint fk_mail_templates_tenant foreign key (tenant_id) references tenants.tenants (tenant_id) on delete cascade
+);
+
+
+create function tenants.mail_templates_fetch(
+    p_tenant_id uuid,
+    p_name text
+)
+returns table (
+    tenant_id uuid,
+    name text,
+    locale text,
+    updated timestamptz,
+    subject text,
+    body_text text,
+    body_html text
+)
+language sql
+stable
+as $$
+    select
+        t.tenant_id,
+        t.name,
+        t.locale,
+        t.updated,
+        t.subject,
+        t.body_text,
+        t.body_html
+    from tenants.mail_templates t
+    where
+        t.tenant_id = p_tenant_id
+        and (p_name is null or t.name = p_name)
+    order by t.name, t.locale;
+$$;
+
+create procedure tenants.mail_template_save(
+    p_tenant_id uuid,
+    p_name text,
+    p_locale text,
+    p_subject text,
+    p_text text,
+    p_html text
+)
+language sql
+as $$
+    insert into tenants.mail_templates (
+        tenant_id,
+        name,
+        locale,
+        subject,
+        body_text,
+        body_html
+    ) values (
+        p_tenant_id,
+        p_name,
+        p_locale,
+        p_subject,
+        p_text,
+        p_html
+    )
+    on conflict (tenant_id, name, locale) do update set
+        subject = excluded.subject,
+        body_text = excluded.body_text,
+        body_html = excluded.body_html,
+        updated = now();
+$$;
+
+create procedure tenants.mail_template_delete(
+    p_tenant_id uuid,
+    p_name text,
+    p_locale text
+)
+language plpgsql
+as $$
+begin
+    delete from tenants.mail_templates
+    where
+        tenant_id = p_tenant_id
+        and name = p_name
+        and locale = p_locale;
+
+    if not found then
+        raise exception 'no % template for locale %', p_name, p_locale using errcode = 'P0002';
+    end if;
+end;
+$$;
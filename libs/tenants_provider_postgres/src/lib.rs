@@ -1,6 +1,7 @@
 #![allow(clippy::needless_return)]
 
 pub mod organizations;
+pub mod templates;
 
 use tracing::{debug, error, info};
 
@@ -8,6 +9,28 @@ use sqlx::{Row, postgres::PgRow, prelude::FromRow, types::chrono};
 use std::vec::Vec;
 use uuid::Uuid;
 
+use provider_error::ProviderError;
+
+/// schema migrations for the `tenants` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "tenants",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        },
+        database_provider::migrations::Migration {
+            version: 2,
+            name: "mail_templates",
+            up: include_str!("../migrations/0002_mail_templates.up.sql"),
+            down: include_str!("../migrations/0002_mail_templates.down.sql")
+        }
+    ]
+};
+
+
 struct TenantItem(pub tenants_provider::Tenant);
 
 impl<'r> FromRow<'r, PgRow> for TenantItem {
@@ -49,7 +72,7 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
     async fn tenants_fetch_by_id(
         &self,
         tenant_id: &uuid::Uuid,
-    ) -> Result<tenants_provider::Tenant, &'static str> {
+    ) -> Result<tenants_provider::Tenant, ProviderError> {
         info!("tenants_fetch_by_id");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -60,7 +83,7 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
             {
                 Err(e) => {
                     error!("Error fetching tenant record: {:?}", e);
-                    return Err("Error fetching tenant record");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(r) => {
                     return Ok(r.0);
@@ -68,14 +91,14 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn tenant_fetch_by_name(
         &self,
         name: &str,
-    ) -> Result<tenants_provider::Tenant, &'static str> {
+    ) -> Result<tenants_provider::Tenant, ProviderError> {
         info!("tenant_fetch_by_name");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -86,7 +109,7 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
             {
                 Err(e) => {
                     error!("Error fetching tenant record by name: {:?}", e);
-                    return Err("Error fetching tenant record by name");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(r) => {
                     return Ok(r.0);
@@ -94,7 +117,7 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -104,7 +127,7 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
         name: &str,
         description: &str,
         version: &i32,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("tenant_save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -121,12 +144,12 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
                 }
                 Err(e) => {
                     error!("Error saving tenant record: {:?}", e);
-                    return Err("Error saving tenant record");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -134,7 +157,7 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
         &self,
         tenant_id: &uuid::Uuid,
         active: &bool,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("tenant_set_active");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -149,12 +172,12 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
                 }
                 Err(e) => {
                     error!("Error setting tenant active state: {:?}", e);
-                    return Err("Error setting tenant active state");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -162,7 +185,7 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
         &self,
         tenant_ids: &Vec<uuid::Uuid>,
         active: &bool,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("tenants_set_active");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -177,19 +200,19 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
                 }
                 Err(e) => {
                     error!("Error setting tenants active state: {:?}", e);
-                    return Err("Error setting tenants active state");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn tenants_fetch(
         &self,
         filter: &str,
-    ) -> Result<Vec<tenants_provider::Tenant>, &'static str> {
+    ) -> Result<Vec<tenants_provider::Tenant>, ProviderError> {
         info!("tenants_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -204,19 +227,19 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
                 }
                 Err(e) => {
                     error!("Error fetching tenant records: {:?}", e);
-                    return Err("Error fetching tenant records");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn tenant_user_tenants_fetch(
         &self,
         user_id: &uuid::Uuid,
-    ) -> Result<Vec<tenants_provider::Tenant>, &'static str> {
+    ) -> Result<Vec<tenants_provider::Tenant>, ProviderError> {
         info!("tenant_user_fetch_tenants");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -234,12 +257,12 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
                 }
                 Err(e) => {
                     error!("Error fetching tenant records: {:?}", e);
-                    return Err("Error fetching tenant records");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -247,7 +270,7 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
         &self,
         user_id: &uuid::Uuid,
         tenant_id: &uuid::Uuid,
-    ) -> Result<Vec<tenants_provider::Permission>, &'static str> {
+    ) -> Result<Vec<tenants_provider::Permission>, ProviderError> {
         info!("tenant_user_permissions_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -266,12 +289,12 @@ impl tenants_provider::TenantsProvider for PostgresTenantsProvider {
                 }
                 Err(e) => {
                     error!("Error fetching permissions: {:?}", e);
-                    return Err("Error fetching permissions");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
@@ -298,33 +321,33 @@ mod tests {
         let tp = PostgresTenantsProvider::new(&dp);
 
         if let Err(e) = tp.tenant_save(&tenant_id, &name, &description, &0).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to save tenant record");
         }
 
         if let Err(e) = tp.tenant_fetch_by_name(&name).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch tenant record by name");
         }
 
         if let Err(e) = tp.tenant_set_active(&tenant_id, &true).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to set tenant active state");
         }
 
         let tenant_ids = vec![tenant_id];
         if let Err(e) = tp.tenants_set_active(&tenant_ids, &true).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to set tenant active state");
         }
 
         if let Err(e) = tp.tenants_fetch_by_id(&tenant_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch tenant");
         }
 
         if let Err(e) = tp.tenants_fetch("%test%").await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch tenants");
         }
     }
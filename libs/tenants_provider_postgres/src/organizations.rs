@@ -4,6 +4,8 @@ use tracing::{debug, error, info};
 
 use sqlx::{Row, postgres::PgRow, prelude::FromRow};
 
+use provider_error::ProviderError;
+
 struct OrganizationDataItem(pub tenants_provider::organizations::OrganizationData);
 
 impl<'r> FromRow<'r, PgRow> for OrganizationDataItem {
@@ -54,7 +56,7 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
         name: &str,
         description: &str,
         version: &i32,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -70,7 +72,7 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
             {
                 Err(e) => {
                     error!("Error saving organization: {:?}", e);
-                    return Err("Error saving organization");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(_) => {
                     return Ok(());
@@ -78,7 +80,7 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -86,7 +88,7 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> Result<Vec<tenants_provider::organizations::OrganizationData>, &'static str> {
+    ) -> Result<Vec<tenants_provider::organizations::OrganizationData>, ProviderError> {
         info!("fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -100,7 +102,7 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
             {
                 Err(e) => {
                     error!("Error fetching organizations: {:?}", e);
-                    return Err("Error fetching organizations");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let organizations = rows.iter().map(|r| r.0.clone()).collect();
@@ -109,14 +111,14 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn fetch_tree(
         &self,
         tenant_id: &uuid::Uuid,
-    ) -> Result<Vec<tenants_provider::organizations::OrganizationNodeData>, &'static str> {
+    ) -> Result<Vec<tenants_provider::organizations::OrganizationNodeData>, ProviderError> {
         info!("fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -129,7 +131,7 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
             {
                 Err(e) => {
                     error!("Error fetching organizations: {:?}", e);
-                    return Err("Error fetching organizations");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(rows) => {
                     let organizations = rows.iter().map(|r| r.0.clone()).collect();
@@ -138,14 +140,14 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn fetch_by_id(
         &self,
         org_id: &uuid::Uuid,
-    ) -> Result<tenants_provider::organizations::OrganizationData, &'static str> {
+    ) -> Result<tenants_provider::organizations::OrganizationData, ProviderError> {
         info!("fetch_by_id");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -158,7 +160,7 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
             {
                 Err(e) => {
                     error!("Error fetching organizations: {:?}", e);
-                    return Err("Error fetching organizations");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(row) => {
                     return Ok(row.0.clone());
@@ -166,7 +168,7 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -174,7 +176,7 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
         &self,
         tenant_id: &uuid::Uuid,
         name: &str,
-    ) -> Result<tenants_provider::organizations::OrganizationData, &'static str> {
+    ) -> Result<tenants_provider::organizations::OrganizationData, ProviderError> {
         info!("fetch_by_name");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -188,7 +190,7 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
             {
                 Err(e) => {
                     error!("Error fetching organizations: {:?}", e);
-                    return Err("Error fetching organizations");
+                    return Err(database_provider::from_sqlx(&e));
                 }
                 Ok(row) => {
                     return Ok(row.0.clone());
@@ -196,7 +198,7 @@ impl tenants_provider::organizations::OrganizationsProvider for OrganizationsPro
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
@@ -243,17 +245,17 @@ mod tests {
             )
             .await
         {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to save organization");
         }
 
         if let Err(e) = opp.fetch(&tenant_id, &"%").await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch organizations");
         }
 
         if let Err(e) = opp.fetch_tree(&tenant_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch organization tree");
         }
     }
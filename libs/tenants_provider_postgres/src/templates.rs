@@ -0,0 +1,208 @@
+#![allow(clippy::needless_return)]
+
+use tracing::{error, info};
+
+use sqlx::{Row, postgres::PgRow, prelude::FromRow};
+
+use provider_error::ProviderError;
+
+struct MailTemplateItem(pub tenants_provider::templates::MailTemplate);
+
+impl<'r> FromRow<'r, PgRow> for MailTemplateItem {
+    fn from_row(row: &'r PgRow) -> sqlx::Result<Self> {
+        return Ok(Self(tenants_provider::templates::MailTemplate {
+            tenant_id: row.get("tenant_id"),
+            name: row.get("name"),
+            locale: row.get("locale"),
+            updated: row.get("updated"),
+            subject: row.get("subject"),
+            text: row.get("body_text"),
+            html: row.get("body_html"),
+        }));
+    }
+}
+
+pub struct MailTemplatesProviderPostgres {
+    dp: database_provider::DatabaseProvider,
+}
+
+impl MailTemplatesProviderPostgres {
+    pub fn new(dp: &database_provider::DatabaseProvider) -> Self {
+        return Self { dp: dp.clone() };
+    }
+}
+
+impl tenants_provider::templates::MailTemplatesProvider for MailTemplatesProviderPostgres {
+    async fn templates_fetch(
+        &self,
+        tenant_id: &uuid::Uuid,
+        name: Option<&str>,
+    ) -> Result<Vec<tenants_provider::templates::MailTemplate>, ProviderError> {
+        info!("templates_fetch");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query_as::<_, MailTemplateItem>(
+                "select * from tenants.mail_templates_fetch($1,$2);",
+            )
+            .bind(tenant_id)
+            .bind(name)
+            .fetch_all(&pool)
+            .await
+            {
+                Err(e) => {
+                    error!("Error fetching mail templates: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+                Ok(rows) => {
+                    return Ok(rows.into_iter().map(|r| r.0).collect());
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn template_save(
+        &self,
+        tenant_id: &uuid::Uuid,
+        name: &str,
+        locale: &str,
+        subject: &str,
+        text: &str,
+        html: Option<&str>,
+    ) -> Result<(), ProviderError> {
+        info!("template_save");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call tenants.mail_template_save($1,$2,$3,$4,$5,$6);")
+                .bind(tenant_id)
+                .bind(name)
+                .bind(locale)
+                .bind(subject)
+                .bind(text)
+                .bind(html)
+                .execute(&pool)
+                .await
+            {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("Error saving mail template: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+
+    async fn template_delete(
+        &self,
+        tenant_id: &uuid::Uuid,
+        name: &str,
+        locale: &str,
+    ) -> Result<(), ProviderError> {
+        info!("template_delete");
+
+        if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
+            match sqlx::query("call tenants.mail_template_delete($1,$2,$3);")
+                .bind(tenant_id)
+                .bind(name)
+                .bind(locale)
+                .execute(&pool)
+                .await
+            {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("Error deleting mail template: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            }
+        } else {
+            error!("No Postgres pool found for 'main'");
+            return Err(ProviderError::Unavailable);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PostgresTenantsProvider;
+    use tenants_provider::{TenantsProvider, templates::MailTemplatesProvider};
+
+    #[actix_web::test]
+    async fn test_mail_templates() {
+        if let Err(e) = tracing_subscriber::fmt::try_init() {
+            println!("error: {:?}", e);
+        }
+
+        let cfg = config::Config::from_env();
+        let db_provider = database_provider::DatabaseProvider::new(&cfg);
+        let dp = actix_web::web::Data::new(std::sync::Arc::new(db_provider));
+
+        let tp = PostgresTenantsProvider::new(&dp);
+        let mtp = MailTemplatesProviderPostgres::new(&dp);
+
+        let tenant_id = uuid::Uuid::new_v4();
+        let name = format!("test_{}", tenant_id.simple());
+        if let Err(e) = tp.tenant_save(&tenant_id, &name, "mail template test", &0).await {
+            error!("{}", e);
+            assert!(false, "unable to save tenant");
+        }
+
+        for subject in ["Welcome", "Welcome aboard"] {
+            if let Err(e) = mtp
+                .template_save(&tenant_id, "verification", "de", subject, "{{ link }}", None)
+                .await
+            {
+                error!("{}", e);
+                assert!(false, "unable to save mail template");
+            }
+        }
+        if let Err(e) = mtp
+            .template_save(&tenant_id, "invoice", "en", "Invoice", "{{ link }}", Some("<p>{{ link }}</p>"))
+            .await
+        {
+            error!("{}", e);
+            assert!(false, "unable to save mail template");
+        }
+
+        match mtp.templates_fetch(&tenant_id, Some("verification")).await {
+            Err(e) => {
+                error!("{}", e);
+                assert!(false, "unable to fetch mail templates");
+            }
+            Ok(templates) => {
+                assert_eq!(templates.len(), 1);
+                assert_eq!(templates[0].subject, "Welcome aboard");
+                assert_eq!(templates[0].html, None);
+            }
+        }
+
+        if let Err(e) = mtp.template_delete(&tenant_id, "verification", "de").await {
+            error!("{}", e);
+            assert!(false, "unable to delete mail template");
+        }
+        assert_eq!(
+            mtp.template_delete(&tenant_id, "verification", "de").await,
+            Err(ProviderError::NotFound)
+        );
+
+        match mtp.templates_fetch(&tenant_id, None).await {
+            Err(e) => {
+                error!("{}", e);
+                assert!(false, "unable to fetch mail templates");
+            }
+            Ok(templates) => {
+                assert_eq!(templates.len(), 1);
+                assert_eq!(templates[0].name, "invoice");
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+#![allow(clippy::needless_return)]
+
+//! text of stored documents for the search index: plain text, html, pdf
+//! and the office open xml and opendocument formats
+
+pub mod xml;
+
+use std::fmt::Display;
+use std::io::Read;
+
+
+/// extracted text is cut off after this many bytes
+pub const MAX_TEXT: usize = 512 * 1024;
+/// parts of office documents are read up to this many bytes
+pub const MAX_PART_SIZE: u64 = 64 * 1024 * 1024;
+
+/// office documents, their parts holding text and the rules to read them
+const OFFICE: &[(&str, &[&str], &xml::Rules)] = &[
+    ("application/vnd.openxmlformats-officedocument.wordprocessingml.", &[
+        "word/document.xml",
+        "word/header",
+        "word/footer",
+        "word/footnotes.xml",
+        "word/endnotes.xml"
+    ], &xml::OOXML),
+    ("application/vnd.openxmlformats-officedocument.spreadsheetml.", &[
+        "xl/sharedStrings.xml",
+        "xl/worksheets/sheet"
+    ], &xml::OOXML),
+    ("application/vnd.openxmlformats-officedocument.presentationml.", &[
+        "ppt/slides/slide",
+        "ppt/notesSlides/notesSlide"
+    ], &xml::OOXML),
+    ("application/vnd.oasis.opendocument.", &[
+        "content.xml"
+    ], &xml::ODF)
+];
+
+
+#[derive(Debug, PartialEq)]
+pub enum ExtractError {
+    /// no text is extracted from the content type
+    Unsupported(String),
+    /// the content is not what its content type says
+    Invalid(String),
+    Encrypted
+}
+
+impl Display for ExtractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractError::Unsupported(t) => write!(f, "no text extraction for {}", t),
+            ExtractError::Invalid(e) => write!(f, "invalid document: {}", e),
+            ExtractError::Encrypted => write!(f, "document is encrypted")
+        }
+    }
+}
+
+
+/// whether text is extracted from content of the type
+pub fn supports(content_type: &str) -> bool {
+    let content_type = content_type.to_ascii_lowercase();
+    return content_type.starts_with("text/")
+        || content_type == "application/json"
+        || content_type == "application/xml"
+        || content_type == "application/pdf"
+        || OFFICE.iter().any(|(prefix, _, _)| content_type.starts_with(prefix));
+}
+
+
+/// text of the content, with whitespace collapsed and lines separated by
+/// `\n`, at most `MAX_TEXT` bytes
+pub fn extract(content_type: &str, data: &[u8]) -> Result<String, ExtractError> {
+    let content_type = content_type.to_ascii_lowercase();
+
+    let text = if content_type == "text/html" || content_type == "application/xhtml+xml" {
+        xml::text(&decode_text(data), &xml::HTML)
+    } else if content_type == "application/xml" {
+        xml::text(&decode_text(data), &xml::XML)
+    } else if content_type.starts_with("text/") || content_type == "application/json" {
+        decode_text(data)
+    } else if content_type == "application/pdf" {
+        pdf_text(data)?
+    } else if let Some((_, parts, rules)) = OFFICE.iter().find(|(prefix, _, _)| content_type.starts_with(prefix)) {
+        office_text(data, parts, rules)?
+    } else {
+        return Err(ExtractError::Unsupported(content_type));
+    };
+
+    return Ok(normalize(&text));
+}
+
+
+/// text shown by the pages of a pdf document, pages are separated by lines;
+/// documents needing a password to open are not read
+fn pdf_text(data: &[u8]) -> Result<String, ExtractError> {
+    if !data.starts_with(b"%PDF") {
+        return Err(ExtractError::Invalid(String::from("not a pdf document")));
+    }
+
+    // pdf-extract panics on some malformed documents
+    let text = std::panic::catch_unwind(|| {
+        // loading decrypts what opens without a password
+        let document = pdf_extract::Document::load_mem(data).map_err(|e| ExtractError::Invalid(e.to_string()))?;
+        if document.is_encrypted() && document.encryption_state.is_none() {
+            return Err(ExtractError::Encrypted);
+        }
+
+        let mut text = String::new();
+        for page in document.get_pages().into_keys() {
+            let mut output = pdf_extract::PlainTextOutput::new(&mut text);
+            pdf_extract::output_doc_page(&document, &mut output, page).map_err(|e| ExtractError::Invalid(e.to_string()))?;
+            text.push('\n');
+            if text.len() > MAX_TEXT {
+                break;
+            }
+        }
+        return Ok(text);
+    });
+    return text.unwrap_or_else(|_| Err(ExtractError::Invalid(String::from("unreadable pdf document"))));
+}
+
+
+/// text of the parts of an office document whose names start with one of
+/// `parts`, in the order of `parts` and then by name
+fn office_text(data: &[u8], parts: &[&str], rules: &xml::Rules) -> Result<String, ExtractError> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data))
+        .map_err(|e| ExtractError::Invalid(e.to_string()))?;
+    let names: Vec<String> = archive.file_names().map(String::from).collect();
+
+    let mut text = String::new();
+    for part in parts {
+        let mut matching: Vec<&String> = names.iter()
+            .filter(|n| n.starts_with(part) && n.ends_with(".xml"))
+            .collect();
+        // slide10 after slide9
+        matching.sort_by_key(|n| (n.len(), *n));
+
+        for name in matching {
+            let mut content = Vec::new();
+            archive.by_name(name)
+                .map_err(|e| ExtractError::Invalid(e.to_string()))?
+                .take(MAX_PART_SIZE)
+                .read_to_end(&mut content)
+                .map_err(|e| ExtractError::Invalid(e.to_string()))?;
+            text.push_str(&xml::text(&String::from_utf8_lossy(&content), rules));
+            text.push('\n');
+            if text.len() > MAX_TEXT {
+                return Ok(text);
+            }
+        }
+    }
+    return Ok(text);
+}
+
+
+/// utf-8 or, with a byte order mark, utf-16 text; invalid sequences are
+/// replaced
+fn decode_text(data: &[u8]) -> String {
+    let utf16 = |bytes: &[u8], from: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|c| from([c[0], c[1]])).collect();
+        return String::from_utf16_lossy(&units);
+    };
+
+    if let Some(rest) = data.strip_prefix(b"\xff\xfe") {
+        return utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = data.strip_prefix(b"\xfe\xff") {
+        return utf16(rest, u16::from_be_bytes);
+    }
+    let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+    return String::from_utf8_lossy(&data[..data.len().min(MAX_TEXT * 4)]).into_owned();
+}
+
+
+/// collapses whitespace within lines, drops empty lines and control
+/// characters and cuts the text off at `MAX_TEXT` bytes
+fn normalize(text: &str) -> String {
+    let mut normalized = String::new();
+
+    for line in text.lines() {
+        let mut words = line
+            .split(|c: char| c.is_whitespace() || c.is_control())
+            .filter(|w| !w.is_empty())
+            .peekable();
+        if words.peek().is_none() {
+            continue;
+        }
+
+        if !normalized.is_empty() {
+            normalized.push('\n');
+        }
+        for (i, word) in words.enumerate() {
+            if i > 0 {
+                normalized.push(' ');
+            }
+            normalized.push_str(word);
+        }
+
+        if normalized.len() > MAX_TEXT {
+            break;
+        }
+    }
+
+    if normalized.len() > MAX_TEXT {
+        let mut end = MAX_TEXT;
+        while !normalized.is_char_boundary(end) {
+            end -= 1;
+        }
+        normalized.truncate(end);
+    }
+    return normalized;
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    /// archive of deflated entries
+    fn archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            let written = writer.start_file(*name, zip::write::SimpleFileOptions::default())
+                .map_err(std::io::Error::other)
+                .and_then(|()| writer.write_all(content));
+            assert!(written.is_ok(), "unable to write {name}");
+        }
+        return writer.finish().map(std::io::Cursor::into_inner).unwrap_or_default();
+    }
+
+    #[test]
+    fn test_supports() {
+        assert!(supports("text/plain"));
+        assert!(supports("text/csv"));
+        assert!(supports("application/PDF"));
+        assert!(supports("application/vnd.openxmlformats-officedocument.wordprocessingml.document"));
+        assert!(supports("application/vnd.oasis.opendocument.spreadsheet"));
+        assert!(!supports("application/msword"));
+        assert!(!supports("image/png"));
+    }
+
+    #[test]
+    fn test_extract() {
+        assert_eq!(extract("text/plain", b"\xef\xbb\xbfhello\r\n\r\n  big\t world \x00\n"), Ok(String::from("hello\nbig world")));
+        assert_eq!(extract("text/plain", b"\xff\xfeh\x00i\x00"), Ok(String::from("hi")));
+        assert_eq!(
+            extract("text/html", b"<html><head><title>Report</title><style>p { color: red }</style></head><body><p>Q3&nbsp;sales</p><p>up</p></body></html>"),
+            Ok(String::from("Report\nQ3 sales\nup"))
+        );
+        assert_eq!(extract("image/png", b"\x89PNG"), Err(ExtractError::Unsupported(String::from("image/png"))));
+        assert!(matches!(
+            extract("application/vnd.oasis.opendocument.text", b"not a zip"),
+            Err(ExtractError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_office() {
+        let docx = archive(&[
+            ("[Content_Types].xml", b"<Types/>".as_slice()),
+            ("word/document.xml", b"<w:document><w:body><w:p><w:r><w:t>Quar</w:t></w:r><w:r><w:t xml:space=\"preserve\">terly </w:t></w:r><w:r><w:t>report</w:t></w:r></w:p><w:p><w:r><w:t>Revenue</w:t><w:tab/><w:t>1&amp;2</w:t></w:r></w:p></w:body></w:document>".as_slice()),
+            ("word/footer1.xml", b"<w:ftr><w:p><w:r><w:t>Confidential</w:t></w:r></w:p></w:ftr>".as_slice())
+        ]);
+        assert_eq!(
+            extract("application/vnd.openxmlformats-officedocument.wordprocessingml.document", &docx),
+            Ok(String::from("Quarterly report\nRevenue 1&2\nConfidential"))
+        );
+
+        let pptx = archive(&[
+            ("ppt/slides/slide10.xml", b"<p:sld><a:p><a:r><a:t>last</a:t></a:r></a:p></p:sld>".as_slice()),
+            ("ppt/slides/slide9.xml", b"<p:sld><a:p><a:r><a:t>first</a:t></a:r></a:p></p:sld>".as_slice()),
+            ("ppt/slides/_rels/slide9.xml.rels", b"<Relationships/>".as_slice())
+        ]);
+        assert_eq!(
+            extract("application/vnd.openxmlformats-officedocument.presentationml.presentation", &pptx),
+            Ok(String::from("first\nlast"))
+        );
+
+        let odt = archive(&[
+            ("mimetype", b"application/vnd.oasis.opendocument.text".as_slice()),
+            ("content.xml", b"<office:document-content><office:automatic-styles><style:style style:name=\"P1\"/></office:automatic-styles><office:body><office:text><text:h>Minutes</text:h><text:p>Budget<text:s/>approved<text:line-break/>unanimously</text:p></office:text></office:body></office:document-content>".as_slice())
+        ]);
+        assert_eq!(
+            extract("application/vnd.oasis.opendocument.text", &odt),
+            Ok(String::from("Minutes\nBudget approved unanimously"))
+        );
+    }
+
+    /// document of one page per text, shown in helvetica, encrypted when
+    /// there is a password to open it
+    fn pdf(pages: &[&str], password: Option<&str>) -> Vec<u8> {
+        use pdf_extract::content::{Content, Operation};
+        use pdf_extract::{dictionary, Document, EncryptionState, EncryptionVersion, Object, Permissions, Stream};
+
+        let mut document = Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+        let font_id = document.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+
+        let mut kids = Vec::new();
+        for text in pages {
+            let content = Content {
+                operations: vec![
+                    Operation::new("BT", vec![]),
+                    Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                    Operation::new("Td", vec![72.into(), 720.into()]),
+                    Operation::new("Tj", vec![Object::string_literal(*text)]),
+                    Operation::new("ET", vec![]),
+                ],
+            };
+            let content_id = document.add_object(Stream::new(dictionary! {}, content.encode().unwrap_or_default()));
+            kids.push(Object::from(document.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            })));
+        }
+
+        let count = i64::try_from(kids.len()).unwrap_or_default();
+        document.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => count,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }));
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+
+        if let Some(password) = password {
+            // the document id is part of the key
+            let id = Object::string_literal("0123456789abcdef");
+            document.trailer.set("ID", vec![id.clone(), id]);
+            let version = EncryptionVersion::V2 {
+                document: &document,
+                owner_password: "owner",
+                user_password: password,
+                key_length: 128,
+                permissions: Permissions::all(),
+            };
+            let encrypted = EncryptionState::try_from(version).and_then(|state| document.encrypt(&state));
+            assert!(encrypted.is_ok(), "unable to encrypt pdf");
+        }
+
+        let mut data = Vec::new();
+        assert!(document.save_to(&mut data).is_ok(), "unable to write pdf");
+        return data;
+    }
+
+    #[test]
+    fn test_pdf() {
+        assert_eq!(
+            extract("application/pdf", &pdf(&["Quarterly (Q3) report", "Revenue up"], None)),
+            Ok(String::from("Quarterly (Q3) report\nRevenue up"))
+        );
+        // documents only restricted by an owner password open without one
+        assert_eq!(extract("application/pdf", &pdf(&["Restricted"], Some(""))), Ok(String::from("Restricted")));
+        assert_eq!(extract("application/pdf", &pdf(&["Secret"], Some("secret"))), Err(ExtractError::Encrypted));
+        assert_eq!(extract("application/pdf", b"PK\x03\x04"), Err(ExtractError::Invalid(String::from("not a pdf document"))));
+        assert!(matches!(extract("application/pdf", b"%PDF-1.7\ntruncated"), Err(ExtractError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(" a  b \n\n\n c\u{7}d "), "a b\nc d");
+
+        let long = "ä".repeat(MAX_TEXT);
+        let normalized = normalize(&long);
+        assert!(normalized.len() <= MAX_TEXT && normalized.len() > MAX_TEXT - 2);
+    }
+}
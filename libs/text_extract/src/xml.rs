@@ -0,0 +1,165 @@
+//! character data of xml and html documents, read with quick-xml set up to
+//! tolerate the unmatched and unclosed tags html usually has
+
+use quick_xml::events::Event;
+use quick_xml::name::QName;
+use quick_xml::Reader;
+
+/// which elements of a document hold text, elements are matched by their
+/// local name, without namespace prefix and ignoring case
+pub struct Rules {
+    /// text is only taken from within these elements, from anywhere when
+    /// empty
+    pub text: &'static [&'static str],
+    /// elements starting or ending a line
+    pub breaks: &'static [&'static str],
+    /// elements standing for a space
+    pub spaces: &'static [&'static str],
+    /// elements whose content is left out
+    pub skip: &'static [&'static str]
+}
+
+/// wordprocessing, spreadsheet and presentation documents keep their text
+/// in `t` elements, split into runs
+pub const OOXML: Rules = Rules {
+    text: &["t"],
+    breaks: &["p", "si", "row", "tr"],
+    spaces: &["tab", "br", "cr", "c", "tc"],
+    skip: &["instrText", "delText"]
+};
+
+/// opendocument text is everything in the body
+pub const ODF: Rules = Rules {
+    text: &["body"],
+    breaks: &["p", "h", "table-row", "list-item"],
+    spaces: &["s", "tab", "line-break", "table-cell"],
+    skip: &["annotation", "tracked-changes"]
+};
+
+pub const HTML: Rules = Rules {
+    text: &[],
+    breaks: &[
+        "p", "div", "li", "tr", "table", "section", "article", "header", "footer", "title",
+        "h1", "h2", "h3", "h4", "h5", "h6", "pre", "blockquote", "dt", "dd"
+    ],
+    spaces: &["br", "td", "th", "img", "hr"],
+    skip: &["script", "style", "noscript", "template", "svg"]
+};
+
+pub const XML: Rules = Rules {
+    text: &[],
+    breaks: &[],
+    spaces: &[],
+    skip: &[]
+};
+
+
+/// text of the document, elements are separated by the rules' breaks and
+/// spaces only, so that runs of a word are joined again; markup that cannot
+/// be read ends the text
+pub fn text(document: &str, rules: &Rules) -> String {
+    let matches = |names: &[&str], name: &str| names.iter().any(|n| n.eq_ignore_ascii_case(name));
+
+    let mut reader = Reader::from_str(document);
+    let config = reader.config_mut();
+    config.check_end_names = false;
+    config.allow_unmatched_ends = true;
+    config.allow_dangling_amp = true;
+
+    let mut text = String::new();
+    let mut depth = 0usize;
+
+    loop {
+        let inside = rules.text.is_empty() || depth > 0;
+
+        match reader.read_event() {
+            Err(_) | Ok(Event::Eof) => break,
+            Ok(Event::Start(tag)) => {
+                let local = tag.local_name().as_ref().to_string();
+                if matches(rules.skip, &local) {
+                    // the content is not parsed, scripts may hold anything
+                    let name = tag.name().as_ref().to_string();
+                    if reader.read_text(QName(&name)).is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                if matches(rules.text, &local) {
+                    depth += 1;
+                }
+                separate(&mut text, rules, &local);
+            }
+            Ok(Event::End(tag)) => {
+                let local = tag.local_name().as_ref().to_string();
+                if matches(rules.text, &local) {
+                    depth = depth.saturating_sub(1);
+                }
+                separate(&mut text, rules, &local);
+            }
+            Ok(Event::Empty(tag)) => {
+                separate(&mut text, rules, tag.local_name().as_ref());
+            }
+            Ok(Event::Text(content)) if inside => {
+                text.push_str(&content.xml10_content());
+            }
+            Ok(Event::CData(content)) if inside => {
+                text.push_str(&content.xml10_content());
+            }
+            Ok(Event::GeneralRef(reference)) if inside => {
+                let name = reference.xml10_content();
+                match reference.resolve_char_ref() {
+                    Ok(Some(c)) => text.push(c),
+                    _ => match quick_xml::escape::resolve_html5_entity(&name) {
+                        Some(resolved) => text.push_str(resolved),
+                        // unknown entities are kept as written
+                        None => {
+                            text.push('&');
+                            text.push_str(&name);
+                            text.push(';');
+                        }
+                    },
+                }
+            }
+            Ok(_) => {}
+        }
+    }
+
+    return text;
+}
+
+
+/// separates the text at an element that breaks a line or stands for a
+/// space
+fn separate(text: &mut String, rules: &Rules, local: &str) {
+    if rules.breaks.iter().any(|n| n.eq_ignore_ascii_case(local)) {
+        text.push('\n');
+    } else if rules.spaces.iter().any(|n| n.eq_ignore_ascii_case(local)) {
+        text.push(' ');
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text() {
+        assert_eq!(
+            text("<?xml version=\"1.0\"?><a:p><a:r><a:t>Hel</a:t></a:r><a:r><a:t>lo</a:t></a:r></a:p><a:p><a:t>a &lt; b&#x21;</a:t></a:p>", &OOXML),
+            "\nHello\n\na < b!\n"
+        );
+        // only text elements count
+        assert_eq!(text("<w:p><w:pPr>style</w:pPr><w:t>x</w:t><w:instrText>PAGE</w:instrText></w:p>", &OOXML), "\nx\n");
+
+        // unclosed and unmatched tags are tolerated, scripts are not parsed
+        assert_eq!(
+            text("<p title=\"a > b\">1 &lt; 2 &amp;&nbsp;&bogus; <!-- note --><br>3<script>if (a </p> b) {}</script></p><pre>x</pre></div>", &HTML),
+            "\n1 < 2 &\u{a0}&bogus;  3\n\nx\n\n"
+        );
+        assert_eq!(text("<a><![CDATA[<raw>]]></a>", &XML), "<raw>");
+
+        // the text read up to malformed markup is kept
+        assert_eq!(text("<a>kept</a><b attr=\"unterminated>lost", &XML), "kept");
+    }
+}
@@ -0,0 +1,171 @@
+use tracing::{
+    info,
+    error
+};
+
+use base64::Engine;
+use ed25519_dalek::pkcs8::DecodePrivateKey;
+use jsonwebtoken::{
+    Algorithm,
+    DecodingKey,
+    EncodingKey,
+    jwk::{
+        AlgorithmParameters,
+        CommonParameters,
+        EllipticCurve,
+        Jwk,
+        KeyAlgorithm,
+        OctetKeyPairParameters,
+        OctetKeyPairType,
+        PublicKeyUse
+    }
+};
+
+
+/// asymmetric key used to sign and verify tokens, identified by the `kid`
+/// placed in the token header
+#[derive(Clone)]
+pub struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    jwk: Jwk
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.debug_struct("SigningKey")
+            .field("kid", &self.kid)
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive();
+    }
+}
+
+
+impl SigningKey {
+
+    /// load a PKCS#8 private key, Ed25519 keys sign with EdDSA and RSA keys
+    /// (PKCS#8 or PKCS#1) with RS256
+    pub fn from_pem(kid: &str, pem: &[u8]) -> Result<Self, &'static str> {
+        info!("from_pem");
+
+        if kid.trim().is_empty() {
+            error!("key id is empty");
+            return Err("key id is empty");
+        }
+
+        let Ok(pem_str) = std::str::from_utf8(pem) else {
+            error!("key {} is not a PEM file", kid);
+            return Err("key is not a PEM file");
+        };
+
+        if let Ok(ed_key) = ed25519_dalek::SigningKey::from_pkcs8_pem(pem_str) {
+            let x = base64::engine::general_purpose::URL_SAFE_NO_PAD
+                .encode(ed_key.verifying_key().as_bytes());
+
+            let (Ok(encoding_key), Ok(decoding_key)) = (
+                EncodingKey::from_ed_pem(pem),
+                DecodingKey::from_ed_components(&x)
+            ) else {
+                error!("unable to load Ed25519 key {}", kid);
+                return Err("unable to load Ed25519 key");
+            };
+
+            let jwk = Jwk {
+                common: common_parameters(kid, KeyAlgorithm::EdDSA),
+                algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x
+                })
+            };
+
+            return Ok(Self {
+                kid: String::from(kid),
+                algorithm: Algorithm::EdDSA,
+                encoding_key,
+                decoding_key,
+                jwk
+            });
+        }
+
+        let encoding_key = match EncodingKey::from_rsa_pem(pem) {
+            Err(e) => {
+                error!("key {} is neither an Ed25519 nor an RSA private key: {}", kid, e);
+                return Err("unsupported key type, expected an Ed25519 or RSA private key");
+            }
+            Ok(key) => key,
+        };
+
+        let mut jwk = match Jwk::from_encoding_key(&encoding_key, Algorithm::RS256) {
+            Err(e) => {
+                error!("unable to extract public key of {}: {}", kid, e);
+                return Err("unable to extract RSA public key");
+            }
+            Ok(jwk) => jwk,
+        };
+        jwk.common = common_parameters(kid, KeyAlgorithm::RS256);
+
+        let decoding_key = match DecodingKey::from_jwk(&jwk) {
+            Err(e) => {
+                error!("unable to create decoding key for {}: {}", kid, e);
+                return Err("unable to extract RSA public key");
+            }
+            Ok(key) => key,
+        };
+
+        return Ok(Self {
+            kid: String::from(kid),
+            algorithm: Algorithm::RS256,
+            encoding_key,
+            decoding_key,
+            jwk
+        });
+    }
+
+    pub fn from_pem_file(kid: &str, path: &str) -> Result<Self, &'static str> {
+        info!("from_pem_file");
+
+        let pem = match std::fs::read(path) {
+            Err(e) => {
+                error!("unable to read key file {}: {}", path, e);
+                return Err("unable to read key file");
+            }
+            Ok(pem) => pem,
+        };
+
+        return Self::from_pem(kid, &pem);
+    }
+
+    pub fn kid(&self) -> String {
+        return self.kid.clone();
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        return self.algorithm;
+    }
+
+    pub(crate) fn encoding_key(&self) -> &EncodingKey {
+        return &self.encoding_key;
+    }
+
+    pub(crate) fn decoding_key(&self) -> &DecodingKey {
+        return &self.decoding_key;
+    }
+
+    /// public part of the key as a JWK
+    pub fn jwk(&self) -> Jwk {
+        return self.jwk.clone();
+    }
+}
+
+
+fn common_parameters(kid: &str, algorithm: KeyAlgorithm) -> CommonParameters {
+    return CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_algorithm: Some(algorithm),
+        key_id: Some(String::from(kid)),
+        ..Default::default()
+    };
+}
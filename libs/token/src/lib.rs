@@ -1,5 +1,9 @@
 #![allow(clippy::needless_return)]
 
+pub mod keys;
+
+pub use keys::SigningKey;
+
 use std::str::FromStr;
 
 use tracing::{
@@ -9,7 +13,7 @@ use tracing::{
 };
 
 use serde::{Serialize, Deserialize};
-use jsonwebtoken::{encode, decode, Header, Algorithm, Validation, EncodingKey, DecodingKey};
+use jsonwebtoken::{encode, decode, decode_header, Header, Algorithm, Validation, EncodingKey, DecodingKey, jwk::JwkSet};
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +22,12 @@ pub struct AuthData {
     pub tenant_id: uuid::Uuid,
     pub email: String,
     pub username: String,
+    /// `jti` of the access token, used to revoke it before it expires
+    pub token_id: uuid::Uuid,
+    /// expiry of the access token as a unix timestamp
+    pub expires: i64,
+    /// issue time of the access token as a unix timestamp
+    pub issued: i64,
 }
 
 impl AuthData {
@@ -28,6 +38,9 @@ impl AuthData {
             tenant_id: uuid::Uuid::nil(),
             email: String::new(),
             username: String::new(),
+            token_id: uuid::Uuid::nil(),
+            expires: 0,
+            issued: 0,
         }
     }
 
@@ -47,29 +60,147 @@ struct Claim {
 	pub email: String,
 	pub preferred_username: String,
 
+    pub jti: String,
     pub iat: usize,
     pub exp: usize,
     pub nbf: usize
 }
 
 
+/// audience of share link tokens, access tokens carry none
+const SHARE_AUDIENCE: &str = "share";
+
+
+/// claims of a share link token, kept apart from access tokens by the
+/// audience and by lacking the user claims
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ShareClaim {
+    /// id of the share link
+    pub sub: String,
+    pub client_id: String,
+    pub aud: String,
+    pub iat: usize,
+    pub exp: usize,
+    pub nbf: usize
+}
+
+
+/// content of a verified share link token
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShareData {
+    pub link_id: uuid::Uuid,
+    pub tenant_id: uuid::Uuid,
+    /// expiry as a unix timestamp
+    pub expires: i64
+}
+
+
+/// access token together with the refresh token that can be exchanged
+/// for the next pair once the access token expires
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    /// seconds until the access token expires
+    pub expires_in: i64,
+    pub refresh_token: String,
+    pub refresh_expires: chrono::DateTime<chrono::Utc>,
+}
+
 
+
+/// signs tokens with the newest key of `keys` and verifies them with any
+/// of the keys, falls back to HS512 with `secret` when no keys are set
 #[derive(Debug, Clone)]
 pub struct TokenGenerator {
-    secret: String
+    secret: String,
+    keys: Vec<SigningKey>,
+    lifetime: chrono::TimeDelta,
+    refresh_lifetime: chrono::TimeDelta
 }
 
 
 impl TokenGenerator {
 
+    /// `lifetime` and `refresh_lifetime` are the number of seconds a
+    /// generated access token and refresh token stay valid
     pub fn new(
-        secret: &str
+        secret: &str,
+        lifetime: i64,
+        refresh_lifetime: i64
     ) -> Self {
         return Self {
-            secret: String::from(secret)
+            secret: String::from(secret),
+            keys: Vec::new(),
+            lifetime: chrono::TimeDelta::seconds(lifetime),
+            refresh_lifetime: chrono::TimeDelta::seconds(refresh_lifetime)
+        };
+    }
+
+    /// asymmetric keys ordered oldest first, tokens are signed with the last
+    /// key and verified with whichever key the `kid` header names
+    pub fn with_keys(mut self, keys: Vec<SigningKey>) -> Self {
+        self.keys = keys;
+        return self;
+    }
+
+    /// public keys tokens can be verified with, empty when tokens are
+    /// signed with the shared secret
+    pub fn jwks(&self) -> JwkSet {
+        return JwkSet {
+            keys: self.keys.iter().map(|k| k.jwk()).collect()
         };
     }
 
+    /// access token lifetime in seconds
+    pub fn lifetime(&self) -> i64 {
+        return self.lifetime.num_seconds();
+    }
+
+    /// generate a new opaque refresh token and its expiry, the token is not
+    /// signed and has to be persisted by the caller
+    pub fn generate_refresh_token(
+        &self
+    ) -> Result<(String, chrono::DateTime<chrono::Utc>), &'static str> {
+        info!("generate_refresh_token");
+
+        let Some(expiry) = chrono::Utc::now().checked_add_signed(self.refresh_lifetime) else {
+            error!("unable to compute refresh token expiry");
+            return Err("unable to compute refresh token expiry");
+        };
+
+        // two v4 uuids give 244 random bits, enough for an opaque token
+        let token = format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+
+        return Ok((token, expiry));
+    }
+
+    /// generate an access token issued at `issued` together with a new
+    /// refresh token
+    pub fn generate_pair(
+        &self,
+        user_id: &uuid::Uuid,
+        tenant_id: &uuid::Uuid,
+        user_name: &str,
+        email: &str,
+        issued: &chrono::DateTime<chrono::Utc>
+    ) -> Result<TokenPair, &'static str> {
+        info!("generate_pair");
+
+        let access_token = self.generate_issued(user_id, tenant_id, user_name, email, issued)?;
+        let (refresh_token, refresh_expires) = self.generate_refresh_token()?;
+
+        return Ok(TokenPair {
+            access_token,
+            expires_in: self.lifetime(),
+            refresh_token,
+            refresh_expires
+        });
+    }
+
     pub fn generate(
         &self,
         user_id: &uuid::Uuid,
@@ -77,23 +208,37 @@ impl TokenGenerator {
         user_name: &str,
         email: &str
     ) -> Result<String, &'static str> {
-        info!("generate");
+        return self.generate_issued(user_id, tenant_id, user_name, email, &chrono::Utc::now());
+    }
 
-        let now = chrono::Utc::now();
-        let expiry = now.checked_add_signed(chrono::TimeDelta::hours(1)).unwrap();
+    /// generate an access token that claims to be issued at `issued`, e.g.
+    /// the time the database revoked the earlier tokens of the user. Expiry
+    /// and validity still start now
+    pub fn generate_issued(
+        &self,
+        user_id: &uuid::Uuid,
+        tenant_id: &uuid::Uuid,
+        user_name: &str,
+        email: &str,
+        issued: &chrono::DateTime<chrono::Utc>
+    ) -> Result<String, &'static str> {
+        info!("generate_issued");
 
-        let header = Header {
-            alg: Algorithm::HS512,
-            kid: Some(String::from("todo")),
-            ..Default::default()
+        let now = chrono::Utc::now();
+        let Some(expiry) = now.checked_add_signed(self.lifetime) else {
+            error!("unable to compute token expiry");
+            return Err("unable to compute token expiry");
         };
 
+        let (header, encoding_key) = self.signing_key();
+
         let claims = Claim {
             sub: user_id.to_string(),
             client_id: tenant_id.to_string(),
             email: String::from(email),
             preferred_username: String::from(user_name),
-            iat: now.timestamp() as usize,
+            jti: uuid::Uuid::new_v4().to_string(),
+            iat: issued.timestamp() as usize,
             exp: expiry.timestamp() as usize,
             nbf: now.timestamp() as usize
         };
@@ -101,7 +246,7 @@ impl TokenGenerator {
         let token = match encode(
             &header,
             &claims,
-            &EncodingKey::from_secret(self.secret.as_bytes()),
+            &encoding_key,
         ) {
             Err(e) => {
                 error!("unable to encode token: {}", e);
@@ -121,10 +266,12 @@ impl TokenGenerator {
         debug!("token: [{}]", token);
 
 
+        let (decoding_key, algorithm) = self.verifying_key(token)?;
+
         let tokens = match decode::<Claim>(
             &token,
-            &DecodingKey::from_secret(self.secret.as_bytes()),
-            &Validation::new(Algorithm::HS512),
+            &decoding_key,
+            &Validation::new(algorithm),
         ) {
             Err(e) => {
                 error!("unable to decode token: {}", e);
@@ -148,14 +295,126 @@ impl TokenGenerator {
             }
             Ok(client_id) => client_id,
         };
+        let token_id = match uuid::Uuid::from_str(claim.jti.as_str()) {
+            Err(e) => {
+                error!("unable to parse jti: {}", e);
+                uuid::Uuid::nil()
+            }
+            Ok(token_id) => token_id,
+        };
 
         return Ok(AuthData {
             user_id: user_id,
             tenant_id: tenant_id,
             email: claim.email,
             username: claim.preferred_username,
+            token_id,
+            expires: claim.exp as i64,
+            issued: claim.iat as i64,
         });
     }
+
+    /// share link token for `link_id`, valid until `expires`; the link
+    /// itself has to be kept by the caller to allow revoking it
+    pub fn generate_share_token(
+        &self,
+        link_id: &uuid::Uuid,
+        tenant_id: &uuid::Uuid,
+        expires: &chrono::DateTime<chrono::Utc>
+    ) -> Result<String, &'static str> {
+        info!("generate_share_token");
+
+        let now = chrono::Utc::now();
+        if *expires <= now {
+            return Err("share link expiry is in the past");
+        }
+
+        let (header, encoding_key) = self.signing_key();
+        let claims = ShareClaim {
+            sub: link_id.to_string(),
+            client_id: tenant_id.to_string(),
+            aud: String::from(SHARE_AUDIENCE),
+            iat: now.timestamp() as usize,
+            exp: expires.timestamp() as usize,
+            nbf: now.timestamp() as usize
+        };
+
+        return encode(&header, &claims, &encoding_key).map_err(|e| {
+            error!("unable to encode share token: {}", e);
+            return "unable to encode token";
+        });
+    }
+
+    pub fn parse_share_token(&self, token: &str) -> Result<ShareData, &'static str> {
+        info!("parse_share_token");
+
+        let (decoding_key, algorithm) = self.verifying_key(token)?;
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_audience(&[SHARE_AUDIENCE]);
+
+        let claim = match decode::<ShareClaim>(token, &decoding_key, &validation) {
+            Err(e) => {
+                debug!("unable to decode share token: {}", e);
+                return Err("unable to decode token");
+            }
+            Ok(data) => data.claims
+        };
+
+        let (Ok(link_id), Ok(tenant_id)) = (
+            uuid::Uuid::from_str(&claim.sub),
+            uuid::Uuid::from_str(&claim.client_id)
+        ) else {
+            error!("share token with malformed ids");
+            return Err("unable to decode token");
+        };
+
+        return Ok(ShareData {
+            link_id,
+            tenant_id,
+            expires: claim.exp as i64
+        });
+    }
+
+    /// header and key new tokens are signed with
+    fn signing_key(&self) -> (Header, EncodingKey) {
+        return match self.keys.last() {
+            Some(key) => (
+                Header {
+                    alg: key.algorithm(),
+                    kid: Some(key.kid()),
+                    ..Default::default()
+                },
+                key.encoding_key().clone()
+            ),
+            None => (
+                Header::new(Algorithm::HS512),
+                EncodingKey::from_secret(self.secret.as_bytes())
+            )
+        };
+    }
+
+    /// key and algorithm `token` has to be signed with, chosen by its `kid`
+    /// header when keys are configured
+    fn verifying_key(&self, token: &str) -> Result<(DecodingKey, Algorithm), &'static str> {
+        if self.keys.is_empty() {
+            return Ok((DecodingKey::from_secret(self.secret.as_bytes()), Algorithm::HS512));
+        }
+
+        let kid = match decode_header(token) {
+            Err(e) => {
+                error!("unable to decode token header: {}", e);
+                return Err("unable to decode token");
+            }
+            Ok(header) => header.kid.unwrap_or_default(),
+        };
+
+        let Some(key) = self.keys.iter().find(|k| k.kid() == kid) else {
+            error!("token signed with unknown key: [{}]", kid);
+            return Err("token signed with unknown key");
+        };
+        return Ok((key.decoding_key().clone(), key.algorithm()));
+    }
 }
 
 
@@ -165,9 +424,132 @@ impl TokenGenerator {
 mod tests {
     use super::*;
 
-    // #[test]
-    // fn it_works() {
-    //     let result = add(2, 2);
-    //     assert_eq!(result, 4);
-    // }
+    #[test]
+    fn test_generate_pair() {
+        let tg = TokenGenerator::new("test-secret", 60, 3600);
+
+        let user_id = uuid::Uuid::new_v4();
+        let tenant_id = uuid::Uuid::new_v4();
+
+        let now = chrono::Utc::now();
+        let first = tg.generate_pair(&user_id, &tenant_id, "user", "user@test.com", &now).unwrap();
+        let second = tg.generate_pair(&user_id, &tenant_id, "user", "user@test.com", &now).unwrap();
+
+        assert_eq!(first.refresh_token.len(), 64);
+        assert_ne!(first.refresh_token, second.refresh_token);
+        assert_eq!(first.expires_in, 60);
+        assert!(first.refresh_expires > chrono::Utc::now() + chrono::TimeDelta::seconds(60));
+
+        let a = tg.parse_token(&first.access_token).unwrap();
+        let b = tg.parse_token(&second.access_token).unwrap();
+
+        assert_eq!(a.user_id, user_id);
+        assert_eq!(a.tenant_id, tenant_id);
+        assert!(!a.token_id.is_nil());
+        assert_ne!(a.token_id, b.token_id);
+        assert!(a.expires > chrono::Utc::now().timestamp());
+        assert_eq!(a.expires - a.issued, 60);
+        assert_eq!(a.issued, now.timestamp());
+
+        // the issue time can differ from the time the token is made
+        let revoked = now + chrono::TimeDelta::seconds(2);
+        let third = tg.generate_pair(&user_id, &tenant_id, "user", "user@test.com", &revoked).unwrap();
+        let c = tg.parse_token(&third.access_token).unwrap();
+        assert_eq!(c.issued, revoked.timestamp());
+        assert!(c.expires - c.issued <= 60);
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let rsa = SigningKey::from_pem("rsa-1", include_bytes!("../testdata/rsa.pem")).unwrap();
+        let ed = SigningKey::from_pem("ed-2", include_bytes!("../testdata/ed25519.pem")).unwrap();
+
+        assert_eq!(rsa.algorithm(), Algorithm::RS256);
+        assert_eq!(ed.algorithm(), Algorithm::EdDSA);
+
+        let user_id = uuid::Uuid::new_v4();
+
+        let old = TokenGenerator::new("", 60, 3600).with_keys(vec![rsa.clone()]);
+        let new = TokenGenerator::new("", 60, 3600).with_keys(vec![rsa.clone(), ed.clone()]);
+        let retired = TokenGenerator::new("", 60, 3600).with_keys(vec![ed]);
+
+        let old_token = old.generate(&user_id, &uuid::Uuid::nil(), "user", "user@test.com").unwrap();
+        let new_token = new.generate(&user_id, &uuid::Uuid::nil(), "user", "user@test.com").unwrap();
+
+        assert_eq!(decode_header(&old_token).unwrap().kid.as_deref(), Some("rsa-1"));
+        assert_eq!(decode_header(&new_token).unwrap().kid.as_deref(), Some("ed-2"));
+
+        // tokens signed with the previous key stay valid during the overlap
+        assert_eq!(new.parse_token(&old_token).unwrap().user_id, user_id);
+        assert_eq!(new.parse_token(&new_token).unwrap().user_id, user_id);
+
+        // once the key is removed its tokens are rejected
+        assert!(retired.parse_token(&old_token).is_err());
+
+        // symmetric tokens are not accepted once keys are configured
+        let hmac = TokenGenerator::new("test-secret", 60, 3600);
+        let hmac_token = hmac.generate(&user_id, &uuid::Uuid::nil(), "user", "user@test.com").unwrap();
+        assert!(new.parse_token(&hmac_token).is_err());
+        assert!(hmac.jwks().keys.is_empty());
+    }
+
+    #[test]
+    fn test_jwks() {
+        let rsa = SigningKey::from_pem("rsa-1", include_bytes!("../testdata/rsa.pem")).unwrap();
+        let ed = SigningKey::from_pem("ed-2", include_bytes!("../testdata/ed25519.pem")).unwrap();
+
+        let tg = TokenGenerator::new("", 60, 3600).with_keys(vec![rsa, ed]);
+        let jwks = serde_json::to_value(tg.jwks()).unwrap();
+
+        assert_eq!(jwks["keys"][0]["kid"], "rsa-1");
+        assert_eq!(jwks["keys"][0]["kty"], "RSA");
+        assert_eq!(jwks["keys"][0]["alg"], "RS256");
+        assert!(jwks["keys"][0]["n"].is_string());
+        assert!(jwks["keys"][0].get("d").is_none());
+
+        assert_eq!(jwks["keys"][1]["kid"], "ed-2");
+        assert_eq!(jwks["keys"][1]["kty"], "OKP");
+        assert_eq!(jwks["keys"][1]["crv"], "Ed25519");
+        assert_eq!(jwks["keys"][1]["use"], "sig");
+
+        // keys published in the set verify the tokens
+        let token = tg.generate(&uuid::Uuid::new_v4(), &uuid::Uuid::nil(), "user", "user@test.com").unwrap();
+        let jwk = tg.jwks().find("ed-2").cloned().unwrap();
+        let key = DecodingKey::from_jwk(&jwk).unwrap();
+        assert!(decode::<Claim>(&token, &key, &Validation::new(Algorithm::EdDSA)).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        assert!(SigningKey::from_pem("bad", b"not a key").is_err());
+        assert!(SigningKey::from_pem("", include_bytes!("../testdata/ed25519.pem")).is_err());
+    }
+
+    #[test]
+    fn test_share_token() {
+        let tg = TokenGenerator::new("test-secret", 60, 3600);
+
+        let link_id = uuid::Uuid::new_v4();
+        let tenant_id = uuid::Uuid::new_v4();
+        let expires = chrono::Utc::now() + chrono::TimeDelta::days(7);
+
+        let token = tg.generate_share_token(&link_id, &tenant_id, &expires).unwrap();
+        let share = tg.parse_share_token(&token).unwrap();
+        assert_eq!(share.link_id, link_id);
+        assert_eq!(share.tenant_id, tenant_id);
+        assert_eq!(share.expires, expires.timestamp());
+
+        // share tokens and access tokens cannot be used for each other
+        assert!(tg.parse_token(&token).is_err());
+        let access_token = tg.generate(&uuid::Uuid::new_v4(), &tenant_id, "user", "user@test.com").unwrap();
+        assert!(tg.parse_share_token(&access_token).is_err());
+
+        assert!(TokenGenerator::new("other-secret", 60, 3600).parse_share_token(&token).is_err());
+        assert!(tg.generate_share_token(&link_id, &tenant_id, &(chrono::Utc::now() - chrono::TimeDelta::seconds(1))).is_err());
+
+        let ed = SigningKey::from_pem("ed-2", include_bytes!("../testdata/ed25519.pem")).unwrap();
+        let signed = TokenGenerator::new("", 60, 3600).with_keys(vec![ed]);
+        let token = signed.generate_share_token(&link_id, &tenant_id, &expires).unwrap();
+        assert_eq!(signed.parse_share_token(&token).map(|s| s.link_id), Ok(link_id));
+    }
 }
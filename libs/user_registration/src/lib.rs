@@ -10,31 +10,37 @@ use uuid::Uuid;
 use core::future::Future;
 use serde::Serialize;
 
+use provider_error::ProviderError;
+
 
 pub trait UserRegistrationProvider {
 
+    /// store the registration and queue `notification` in the same
+    /// transaction, so the verification mail is never lost or sent for a
+    /// registration that was rolled back
     fn register_user(
         &self,
         register_id: &uuid::Uuid,
         email: &str,
-        token: &str
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+        token: &str,
+        notification: &outbox_provider::OutboxMessage
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn fetch_registration_details_by_token(
         &self,
         token: &str
-    ) -> impl Future<Output = Result<UserRegistrationDetails, &'static str>> + Send;
+    ) -> impl Future<Output = Result<UserRegistrationDetails, ProviderError>> + Send;
 
     fn fetch_registration_details_by_id(
         &self,
         register_id: &uuid::Uuid
-    ) -> impl Future<Output = Result<UserRegistrationDetails, &'static str>> + Send;
+    ) -> impl Future<Output = Result<UserRegistrationDetails, ProviderError>> + Send;
 
     fn verify_registration(
         &self,
         register_id: &uuid::Uuid,
         token: &str
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 }
 
 
@@ -0,0 +1 @@
+drop schema if exists user_registration cascade;
@@ -0,0 +1,94 @@
+-- pending self-service registrations
+
+create schema if not exists user_registration;
+
+
+create table user_registration.registrations (
+    id uuid not null,
+    email text not null,
+    token text not null,
+    created timestamptz not null default now(),
+    verified timestamptz,
+    constraint pk_registrations primary key (id),
+    constraint u_registrations_token unique (token)
+);
+
+create index ix_registrations_email on user_registration.registrations (lower(email));
+
+
+create procedure user_registration.register_user(
+    p_id uuid,
+    p_email text,
+    p_token text
+)
+language sql
+as $$
+    insert into user_registration.registrations (
+        id,
+        email,
+        token
+    ) values (
+        p_id,
+        p_email,
+        p_token
+    );
+$$;
+
+create procedure user_registration.verify_registration(
+    p_id uuid,
+    p_token text
+)
+language plpgsql
+as $$
+begin
+    update user_registration.registrations set
+        verified = now()
+    where
+        id = p_id
+        and token = p_token
+        and verified is null;
+
+    if not found then
+        raise exception 'registration not found or already verified'
+            using errcode = 'no_data_found';
+    end if;
+end;
+$$;
+
+create function user_registration.fetch_registration_details_by_token(
+    p_token text
+)
+returns table (
+    id uuid,
+    email text,
+    token text
+)
+language sql
+stable
+as $$
+    select
+        r.id,
+        r.email,
+        r.token
+    from user_registration.registrations r
+    where r.token = p_token;
+$$;
+
+create function user_registration.fetch_registration_details_by_id(
+    p_id uuid
+)
+returns table (
+    id uuid,
+    email text,
+    token text
+)
+language sql
+stable
+as $$
+    select
+        r.id,
+        r.email,
+        r.token
+    from user_registration.registrations r
+    where r.id = p_id;
+$$;
@@ -4,6 +4,22 @@ use tracing::{debug, error, info};
 
 use sqlx::Row;
 
+use provider_error::ProviderError;
+
+/// schema migrations for the `user_registration` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "user_registration",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        }
+    ]
+};
+
+
 pub struct PostgresUserRegistrationProvider {
     dp: database_provider::DatabaseProvider,
 }
@@ -20,35 +36,54 @@ impl user_registration::UserRegistrationProvider for PostgresUserRegistrationPro
         register_id: &uuid::Uuid,
         email: &str,
         token: &str,
-    ) -> Result<(), &'static str> {
+        notification: &outbox_provider::OutboxMessage,
+    ) -> Result<(), ProviderError> {
         info!("register_user");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
-            match sqlx::query("call user_registration.register_user($1, $2, $3);")
+            let mut tx = match pool.begin().await {
+                Ok(tx) => tx,
+                Err(e) => {
+                    error!("Error starting transaction: {:?}", e);
+                    return Err(database_provider::from_sqlx(&e));
+                }
+            };
+
+            if let Err(e) = sqlx::query("call user_registration.register_user($1, $2, $3);")
                 .bind(register_id)
                 .bind(email)
                 .bind(token)
-                .execute(&pool)
+                .execute(&mut *tx)
                 .await
             {
+                error!("Error registering user: {:?}", e);
+                return Err(database_provider::from_sqlx(&e));
+            }
+
+            if let Err(e) = outbox_provider_postgres::enqueue_with(&mut *tx, notification).await {
+                error!("Error queueing registration mail: {:?}", e);
+                return Err(database_provider::from_sqlx(&e));
+            }
+
+            match tx.commit().await {
                 Ok(_) => {
                     return Ok(());
                 }
                 Err(e) => {
                     error!("Error registering user: {:?}", e);
-                    return Err("Error registering user");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn fetch_registration_details_by_token(
         &self,
         token: &str,
-    ) -> Result<user_registration::UserRegistrationDetails, &'static str> {
+    ) -> Result<user_registration::UserRegistrationDetails, ProviderError> {
         info!("fetch_registration_details_by_token");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -74,19 +109,19 @@ impl user_registration::UserRegistrationProvider for PostgresUserRegistrationPro
                 }
                 Err(e) => {
                     error!("Error verifying user registration: {:?}", e);
-                    return Err("Error verifying user registration");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn fetch_registration_details_by_id(
         &self,
         register_id: &uuid::Uuid,
-    ) -> Result<user_registration::UserRegistrationDetails, &'static str> {
+    ) -> Result<user_registration::UserRegistrationDetails, ProviderError> {
         info!("fetch_registration_details_by_id");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -112,12 +147,12 @@ impl user_registration::UserRegistrationProvider for PostgresUserRegistrationPro
                 }
                 Err(e) => {
                     error!("Error verifying user registration: {:?}", e);
-                    return Err("Error verifying user registration");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -125,7 +160,7 @@ impl user_registration::UserRegistrationProvider for PostgresUserRegistrationPro
         &self,
         register_id: &uuid::Uuid,
         token: &str,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("verify_registration");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -140,12 +175,12 @@ impl user_registration::UserRegistrationProvider for PostgresUserRegistrationPro
                 }
                 Err(e) => {
                     error!("Error verifying user registration: {:?}", e);
-                    return Err("Error verifying user registration");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
@@ -177,8 +212,9 @@ mod tests {
         let token: String = (0..50).map(|_| rng.sample(Alphanumeric) as char).collect();
 
         let ur = PostgresUserRegistrationProvider::new(&dp);
+        let notification = outbox_provider::OutboxMessage::new(&email, "Verify your email address", &token);
 
-        if let Err(e) = ur.register_user(&register_id, &email, &token).await {
+        if let Err(e) = ur.register_user(&register_id, &email, &token, &notification).await {
             assert!(false, "error registering user: {}", e);
         }
     }
@@ -199,8 +235,9 @@ mod tests {
         let token: String = (0..50).map(|_| rng.sample(Alphanumeric) as char).collect();
 
         let ur = PostgresUserRegistrationProvider::new(&dp);
+        let notification = outbox_provider::OutboxMessage::new(&email, "Verify your email address", &token);
 
-        if let Err(e) = ur.register_user(&register_id, &email, &token).await {
+        if let Err(e) = ur.register_user(&register_id, &email, &token, &notification).await {
             assert!(false, "error registering user: {}", e);
         }
 
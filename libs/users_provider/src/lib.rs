@@ -1,5 +1,7 @@
 use serde::Serialize;
 
+use provider_error::ProviderError;
+
 #[derive(Debug, Serialize)]
 pub struct User {
     pub user_id: uuid::Uuid,
@@ -67,53 +69,53 @@ pub trait UsersProvider {
         prefix: &str,
         suffix: &str,
         version: &i32,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn set_active(
         &self,
         user_id: &uuid::Uuid,
         active: &bool,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn set_active_multiple(
         &self,
         user_id: &Vec<uuid::Uuid>,
         active: &bool,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn add_email(
         &self,
         user_id: &uuid::Uuid,
         email: &str,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn fetch_by_id(
         &self,
         user_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<User, &'static str>> + Send;
+    ) -> impl Future<Output = Result<User, ProviderError>> + Send;
 
     fn fetch_by_email(
         &self,
         email: &str,
-    ) -> impl Future<Output = Result<User, &'static str>> + Send;
+    ) -> impl Future<Output = Result<User, ProviderError>> + Send;
 
-    fn fetch(&self, filter: &str) -> impl Future<Output = Result<Vec<User>, &'static str>> + Send;
+    fn fetch(&self, filter: &str) -> impl Future<Output = Result<Vec<User>, ProviderError>> + Send;
 
     fn tenant_users_fetch(
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> impl Future<Output = Result<std::vec::Vec<User>, &'static str>> + Send;
+    ) -> impl Future<Output = Result<std::vec::Vec<User>, ProviderError>> + Send;
 
     fn tenant_user_save(
         &self,
         tenant_id: &uuid::Uuid,
         user_id: &uuid::Uuid,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 
     fn tenant_assign(
         &self,
         user_ids: &Vec<uuid::Uuid>,
         tenant_ids: &Vec<uuid::Uuid>,
-    ) -> impl Future<Output = Result<(), &'static str>> + Send;
+    ) -> impl Future<Output = Result<(), ProviderError>> + Send;
 }
@@ -0,0 +1,228 @@
+-- user records and email addresses
+
+create schema if not exists users;
+
+
+create table users.users (
+    user_id uuid not null,
+    active boolean not null default true,
+    version int not null default 0,
+    created timestamptz not null default now(),
+    updated timestamptz not null default now(),
+    first_name text not null default '',
+    middle_name text not null default '',
+    last_name text not null default '',
+    prefix text not null default '',
+    suffix text not null default '',
+    constraint pk_users primary key (user_id)
+);
+
+create table users.user_emails (
+    user_id uuid not null,
+    email text not null,
+    created timestamptz not null default now(),
+    constraint pk_user_emails primary key (user_id, email),
+    constraint fk_user_emails_user foreign key (user_id) references users.users (user_id) on delete cascade
+);
+
+create unique index u_user_emails_email on users.user_emails (lower(email));
+
+
+create procedure users.user_save(
+    p_user_id uuid,
+    p_first_name text,
+    p_middle_name text,
+    p_last_name text,
+    p_prefix text,
+    p_suffix text,
+    p_version int
+)
+language sql
+as $$
+    insert into users.users as u (
+        user_id,
+        first_name,
+        middle_name,
+        last_name,
+        prefix,
+        suffix,
+        version
+    ) values (
+        p_user_id,
+        p_first_name,
+        p_middle_name,
+        p_last_name,
+        p_prefix,
+        p_suffix,
+        p_version
+    )
+    on conflict (user_id) do update set
+        first_name = excluded.first_name,
+        middle_name = excluded.middle_name,
+        last_name = excluded.last_name,
+        prefix = excluded.prefix,
+        suffix = excluded.suffix,
+        version = u.version + 1,
+        updated = now();
+$$;
+
+create procedure users.users_set_active(
+    p_user_id uuid,
+    p_active boolean
+)
+language sql
+as $$
+    update users.users set
+        active = p_active,
+        updated = now()
+    where user_id = p_user_id;
+$$;
+
+create procedure users.users_set_active_multiple(
+    p_user_ids uuid[],
+    p_active boolean
+)
+language sql
+as $$
+    update users.users set
+        active = p_active,
+        updated = now()
+    where user_id = any(p_user_ids);
+$$;
+
+create procedure users.user_emails_add(
+    p_user_id uuid,
+    p_email text
+)
+language sql
+as $$
+    insert into users.user_emails (
+        user_id,
+        email
+    ) values (
+        p_user_id,
+        p_email
+    )
+    on conflict (user_id, email) do nothing;
+$$;
+
+
+-- returns the user with the first email address registered, if any
+create view users.v_users as
+select
+    u.user_id,
+    u.active,
+    u.created,
+    u.first_name,
+    u.middle_name,
+    u.last_name,
+    u.prefix,
+    u.suffix,
+    coalesce(e.email, '') as email
+from users.users u
+    left join lateral (
+        select ue.email
+        from users.user_emails ue
+        where ue.user_id = u.user_id
+        order by ue.created
+        limit 1
+    ) e on true;
+
+
+create function users.users_fetch_by_id(
+    p_user_id uuid
+)
+returns table (
+    user_id uuid,
+    active boolean,
+    created timestamptz,
+    first_name text,
+    middle_name text,
+    last_name text,
+    prefix text,
+    suffix text,
+    email text
+)
+language sql
+stable
+as $$
+    select
+        v.user_id,
+        v.active,
+        v.created,
+        v.first_name,
+        v.middle_name,
+        v.last_name,
+        v.prefix,
+        v.suffix,
+        v.email
+    from users.v_users v
+    where v.user_id = p_user_id;
+$$;
+
+create function users.users_fetch_by_email(
+    p_email text
+)
+returns table (
+    user_id uuid,
+    active boolean,
+    created timestamptz,
+    first_name text,
+    middle_name text,
+    last_name text,
+    prefix text,
+    suffix text,
+    email text
+)
+language sql
+stable
+as $$
+    select
+        u.user_id,
+        u.active,
+        u.created,
+        u.first_name,
+        u.middle_name,
+        u.last_name,
+        u.prefix,
+        u.suffix,
+        ue.email
+    from users.users u
+        join users.user_emails ue on ue.user_id = u.user_id
+    where lower(ue.email) = lower(p_email);
+$$;
+
+create function users.users_fetch(
+    p_filter text
+)
+returns table (
+    user_id uuid,
+    active boolean,
+    created timestamptz,
+    first_name text,
+    middle_name text,
+    last_name text,
+    prefix text,
+    suffix text,
+    email text
+)
+language sql
+stable
+as $$
+    select
+        v.user_id,
+        v.active,
+        v.created,
+        v.first_name,
+        v.middle_name,
+        v.last_name,
+        v.prefix,
+        v.suffix,
+        v.email
+    from users.v_users v
+    where
+        v.email ilike '%' || p_filter || '%'
+        or v.first_name ilike '%' || p_filter || '%'
+        or v.last_name ilike '%' || p_filter || '%'
+    order by v.email;
+$$;
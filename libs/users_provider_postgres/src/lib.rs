@@ -6,6 +6,22 @@ use sqlx::Row;
 
 use chrono::{DateTime, NaiveDateTime};
 
+use provider_error::ProviderError;
+
+/// schema migrations for the `users` component
+pub static MIGRATIONS: database_provider::migrations::MigrationSet = database_provider::migrations::MigrationSet {
+    component: "users",
+    migrations: &[
+        database_provider::migrations::Migration {
+            version: 1,
+            name: "init",
+            up: include_str!("../migrations/0001_init.up.sql"),
+            down: include_str!("../migrations/0001_init.down.sql")
+        }
+    ]
+};
+
+
 pub struct PostgresUsersProvider {
     dp: database_provider::DatabaseProvider,
 }
@@ -26,7 +42,7 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
         prefix: &str,
         suffix: &str,
         version: &i32,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -46,16 +62,16 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
                 }
                 Err(e) => {
                     error!("Error registering user: {:?}", e);
-                    return Err("Error registering user");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn set_active(&self, user_id: &uuid::Uuid, active: &bool) -> Result<(), &'static str> {
+    async fn set_active(&self, user_id: &uuid::Uuid, active: &bool) -> Result<(), ProviderError> {
         info!("set_active");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -70,12 +86,12 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
                 }
                 Err(e) => {
                     error!("Error setting user active status: {:?}", e);
-                    return Err("Error setting user active status");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -83,7 +99,7 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
         &self,
         user_ids: &Vec<uuid::Uuid>,
         active: &bool,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("set_active_multiple");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -98,16 +114,16 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
                 }
                 Err(e) => {
                     error!("Error setting user active status: {:?}", e);
-                    return Err("Error setting user active status");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn add_email(&self, user_id: &uuid::Uuid, email: &str) -> Result<(), &'static str> {
+    async fn add_email(&self, user_id: &uuid::Uuid, email: &str) -> Result<(), ProviderError> {
         info!("add_email");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -122,19 +138,19 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
                 }
                 Err(e) => {
                     error!("Error adding user email: {:?}", e);
-                    return Err("Error adding user email");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
     async fn fetch_by_id(
         &self,
         user_id: &uuid::Uuid,
-    ) -> Result<users_provider::User, &'static str> {
+    ) -> Result<users_provider::User, ProviderError> {
         info!("fetch_by_id");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -170,16 +186,16 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
                 }
                 Err(e) => {
                     error!("Error fetching user details using id: {:?}", e);
-                    return Err("Error fetching user details using id");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn fetch_by_email(&self, email: &str) -> Result<users_provider::User, &'static str> {
+    async fn fetch_by_email(&self, email: &str) -> Result<users_provider::User, ProviderError> {
         info!("fetch_by_email");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -215,16 +231,16 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
                 }
                 Err(e) => {
                     error!("Error fetching user details using email: {:?}", e);
-                    return Err("Error fetching user details using email");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
-    async fn fetch(&self, filter: &str) -> Result<Vec<users_provider::User>, &'static str> {
+    async fn fetch(&self, filter: &str) -> Result<Vec<users_provider::User>, ProviderError> {
         info!("fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -267,12 +283,12 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
                 }
                 Err(e) => {
                     error!("Error fetching users: {:?}", e);
-                    return Err("Error fetching users");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -280,7 +296,7 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
         &self,
         tenant_id: &uuid::Uuid,
         filter: &str,
-    ) -> Result<std::vec::Vec<users_provider::User>, &'static str> {
+    ) -> Result<std::vec::Vec<users_provider::User>, ProviderError> {
         info!("tenant_users_fetch");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -321,12 +337,12 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
                 }
                 Err(e) => {
                     error!("Error fetching tenant users records: {:?}", e);
-                    return Err("Error fetching tenant users records");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -334,7 +350,7 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
         &self,
         tenant_id: &uuid::Uuid,
         user_id: &uuid::Uuid,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("tenant_user_save");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -349,12 +365,12 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
                 }
                 Err(e) => {
                     error!("Error adding tenant user: {:?}", e);
-                    return Err("Error adding tenant user");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 
@@ -362,7 +378,7 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
         &self,
         user_ids: &Vec<uuid::Uuid>,
         tenant_ids: &Vec<uuid::Uuid>,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), ProviderError> {
         info!("tenant_assign");
 
         if let Some(database_provider::DatabaseType::Postgres(pool)) = self.dp.get_pool("main") {
@@ -377,12 +393,12 @@ impl users_provider::UsersProvider for PostgresUsersProvider {
                 }
                 Err(e) => {
                     error!("Error assigning user to tenant: {:?}", e);
-                    return Err("Error assigning user to tenant");
+                    return Err(database_provider::from_sqlx(&e));
                 }
             }
         } else {
             error!("No Postgres pool found for 'main'");
-            return Err("Unable to get pool for 'main'");
+            return Err(ProviderError::Unavailable);
         }
     }
 }
@@ -426,37 +442,37 @@ mod tests {
             )
             .await
         {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to save user");
         }
 
         if let Err(e) = up.set_active(&user_id, &true).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to set user active state");
         }
 
         if let Err(e) = up.set_active_multiple(&vec![user_id], &true).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to set multiple user active state");
         }
 
         if let Err(e) = up.add_email(&user_id, &email).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to add user email");
         }
 
         if let Err(e) = up.fetch_by_id(&user_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch user by id");
         }
 
         if let Err(e) = up.fetch_by_email(&email).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch user by email");
         }
 
         if let Err(e) = up.fetch("%").await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch users");
         }
 
@@ -466,7 +482,7 @@ mod tests {
 
         let tp = tenants_provider_postgres::PostgresTenantsProvider::new(&dp);
         if let Err(e) = tp.tenant_save(&tenant_id, &name, &description, &0).await {
-            error!(e);
+            error!("{}", e);
             assert!(
                 false,
                 "unable to save tenant into in users_provider_postgres_test"
@@ -474,17 +490,17 @@ mod tests {
         }
 
         if let Err(e) = up.tenant_user_save(&tenant_id, &user_id).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to save tenant user");
         }
 
         if let Err(e) = up.tenant_users_fetch(&tenant_id, &"%").await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch tenant users");
         }
 
         if let Err(e) = up.tenant_assign(&vec![user_id], &vec![tenant_id]).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch assign users to tenants");
         }
     }
@@ -0,0 +1,35 @@
+-- development fixtures used by the provider integration tests, apply after
+-- `ws migrate up`:
+--
+--   psql "$DATABASE_URL" -f tests/sql/fixtures.sql
+--
+-- safe to apply more than once
+
+insert into tenants.tenants (tenant_id, name, description) values
+    ('00000000-0000-0000-0000-0000000000a1', 'tenant_01', 'test tenant 01')
+on conflict do nothing;
+
+-- every tenant has a root organization sharing the tenant id
+insert into organizations.organizations (org_id, tenant_id, parent_org_id, name, description) values
+    ('00000000-0000-0000-0000-0000000000a1', '00000000-0000-0000-0000-0000000000a1', null, 'tenant_01', 'root organization'),
+    ('00000000-0000-0000-0000-0000000001a1', '00000000-0000-0000-0000-0000000000a1', '00000000-0000-0000-0000-0000000000a1', 'child_01', 'child organization 01')
+on conflict do nothing;
+
+insert into crm.partners (partner_id, tenant_id, business_name, description) values
+    ('00000000-0000-0000-0000-0000000002a1', '00000000-0000-0000-0000-0000000000a1', 'partner_01', 'test partner 01')
+on conflict do nothing;
+
+insert into acctg.accounts (account_id, tenant_id, account_type_id, account_category_id, code, name, description) values
+    ('00000000-0000-0000-0000-0000000003a1', '00000000-0000-0000-0000-0000000000a1', 1, 1, '1000', 'asset', 'assets'),
+    ('00000000-0000-0000-0000-0000000003a2', '00000000-0000-0000-0000-0000000000a1', 2, 3, '2000', 'liability', 'liabilities'),
+    ('00000000-0000-0000-0000-0000000003a3', '00000000-0000-0000-0000-0000000000a1', 3, 5, '3000', 'equity', 'equity'),
+    ('00000000-0000-0000-0000-0000000003a4', '00000000-0000-0000-0000-0000000000a1', 4, 6, '4000', 'revenue', 'revenue'),
+    ('00000000-0000-0000-0000-0000000003a5', '00000000-0000-0000-0000-0000000000a1', 5, 8, '5000', 'expense', 'expenses')
+on conflict do nothing;
+
+insert into acctg.account_hierarchy (account_id, tenant_id, parent_account_id)
+select a.account_id, a.tenant_id, null
+from acctg.accounts a
+where a.tenant_id = '00000000-0000-0000-0000-0000000000a1'
+    and a.code in ('1000', '2000', '3000', '4000', '5000')
+on conflict do nothing;
@@ -32,7 +32,9 @@ pub struct User {
     name: String,
     email: String,
     tenants: Vec<tenant::Tenant>,
-    permissions: Vec<permission::Permission>
+    permissions: Vec<permission::Permission>,
+    token_id: uuid::Uuid,
+    token_expires: i64
 }
 
 
@@ -60,10 +62,24 @@ impl User {
             name: String::from(name),
             email: String::from(email),
             tenants: tenants.clone(),
-            permissions: permissions.clone()
+            permissions: permissions.clone(),
+            token_id: uuid::Uuid::nil(),
+            token_expires: 0
         };
     }
 
+    /// access token the user was authenticated with, `expires` is a unix
+    /// timestamp
+    pub fn with_token(
+        mut self,
+        token_id: &uuid::Uuid,
+        expires: i64
+    ) -> Self {
+        self.token_id = *token_id;
+        self.token_expires = expires;
+        return self;
+    }
+
     pub fn anonymous() -> Self {
         return Self {
             user_id: uuid::Uuid::nil(),
@@ -71,7 +87,9 @@ impl User {
             name: String::from(""),
             email: String::from(""),
             tenants: vec![],
-            permissions: vec![]
+            permissions: vec![],
+            token_id: uuid::Uuid::nil(),
+            token_expires: 0
         };
     }
 
@@ -106,6 +124,14 @@ impl User {
     pub fn permissions(&self) -> Vec<permission::Permission> {
         return self.permissions.clone();
     }
+
+    pub fn token_id(&self) -> uuid::Uuid {
+        return self.token_id;
+    }
+
+    pub fn token_expires(&self) -> i64 {
+        return self.token_expires;
+    }
 }
 
 
@@ -133,7 +159,7 @@ impl FromRequest for User {
     fn from_request(req: &actix_web::HttpRequest, payload: &mut actix_http::Payload) -> Self::Future {
         info!("from_request");
 
-        if req.method() == Method::POST {
+        if req.method() != Method::OPTIONS {
             // check if already have value
             if let Some(u) = req.extensions().get::<user::User>() {
                 let cloned = u.clone();
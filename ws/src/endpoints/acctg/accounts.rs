@@ -10,7 +10,7 @@ use actix_web::{HttpResponse, Responder, guard, http, web};
 
 use crate::{
     classes::user,
-    endpoints::{ApiResponse, default_option_response},
+    endpoints::{ApiError, ApiResponse, default_option_response},
 };
 
 use acctg_provider::accounts::AccountsProvider;
@@ -101,8 +101,7 @@ async fn account_types_fetch_post(
     match app.account_types_fetch().await {
         Err(e) => {
             error!("unable to fetch account types: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch account types"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch account types"));
         }
         Ok(account_types) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -127,8 +126,7 @@ async fn account_categories_fetch_post(
     match app.account_categories_fetch().await {
         Err(e) => {
             error!("unable to fetch account categories: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch account categories"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch account categories"));
         }
         Ok(account_categories) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -155,8 +153,7 @@ async fn accounts_fetch_all_post(
     match app.accounts_fetch_all(&tenant_id).await {
         Err(e) => {
             error!("unable to fetch accounts: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch accounts"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch accounts"));
         }
         Ok(accounts) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -183,8 +180,7 @@ async fn accounts_fetch_tree_post(
     match app.accounts_fetch_tree(&tenant_id).await {
         Err(e) => {
             error!("unable to fetch accounts: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch accounts"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch accounts"));
         }
         Ok(accounts) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -220,8 +216,7 @@ async fn accounts_fetch_by_type_post(
     {
         Err(e) => {
             error!("unable to fetch accounts by type: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch accounts by type"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch accounts by type"));
         }
         Ok(accounts) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -262,8 +257,7 @@ async fn accounts_fetch_post(
     {
         Err(e) => {
             error!("unable to fetch accounts: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch accounts"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch accounts"));
         }
         Ok(accounts) => {
             // debug!("accounts: {:?}", accounts);
@@ -295,8 +289,7 @@ async fn account_fetch_post(
     match app.account_fetch(&params.account_id).await {
         Err(e) => {
             error!("unable to fetch account: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch account"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch account"));
         }
         Ok(account) => {
             // debug!("account: {:?}", account);
@@ -335,8 +328,7 @@ async fn account_save_post(
     {
         Err(e) => {
             error!("unable to save account: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to save account"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to save account"));
         }
         Ok(_) => {
             return HttpResponse::Ok().json(ApiResponse::new(
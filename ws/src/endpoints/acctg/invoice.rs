@@ -14,10 +14,11 @@ use crate::{
         // tenant,
         // permission
     },
-    endpoints::{ApiResponse, default_option_response},
+    endpoints::{ApiError, ApiResponse, default_option_response},
 };
 
 use acctg_provider::invoice::{Invoice, InvoiceItem, InvoiceProvider};
+use file_provider::FileProvider;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -69,8 +70,7 @@ async fn invoice_types_fetch_post(
     match ipp.invoice_types_fetch().await {
         Err(e) => {
             error!("unable to fetch invoice types: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch invoice types"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch invoice types"));
         }
         Ok(invoice_types) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -106,8 +106,7 @@ async fn invoices_fetch_post(
     {
         Err(e) => {
             error!("unable to fetch invoices: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch invoices"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch invoices"));
         }
         Ok(invoices) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -134,19 +133,31 @@ async fn invoice_fetch_post(
     info!("invoices_fetch_post");
 
     let ipp = acctg_provider_postgres::invoice::InvoiceProviderPostgres::new(&dp);
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
 
-    match ipp.invoice_fetch(&params.invoice_id).await {
+    let tenant_id = user.tenant().tenant_id();
+    let user_id = user.user_id();
+
+    let f1 = ipp.invoice_fetch(&params.invoice_id);
+    let f2 = fp.attachment_count(
+        &tenant_id,
+        &user_id,
+        file_provider::EntityType::Invoice,
+        &params.invoice_id,
+    );
+
+    match futures::try_join!(f1, f2) {
         Err(e) => {
             error!("unable to fetch invoice: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch invoice"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch invoice"));
         }
-        Ok(invoice) => {
+        Ok((invoice, attachment_count)) => {
             return HttpResponse::Ok().json(ApiResponse::new(
                 true,
                 "successfully fetched invoice",
                 Some(json!({
-                    "invoice": invoice
+                    "invoice": invoice,
+                    "attachment_count": attachment_count
                 })),
             ));
         }
@@ -204,8 +215,7 @@ async fn invoice_save_post(
 
     if let Err(e) = ipp.invoice_save(&user.tenant().tenant_id(), &invoice).await {
         error!("unable to save invoice: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::error("unable to save invoice"));
+        return HttpResponse::from_error(ApiError::new(e, "unable to save invoice"));
     }
 
     return HttpResponse::Ok().json(ApiResponse::ok("successfully saved invoice"));
@@ -1,2 +1,4 @@
+pub mod outbox;
+pub mod templates;
 pub mod tenants;
 pub mod users;
\ No newline at end of file
@@ -0,0 +1,95 @@
+use actix_web::{HttpResponse, Responder, guard, http, web};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::endpoints::{ApiError, ApiResponse, default_option_response};
+use crate::middleware::permissions::Permission;
+
+use outbox_provider::OutboxProvider;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg
+        .service(
+            web::resource("failed")
+                .wrap(Permission::new("outbox.list"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(admin_outbox_failed))
+        )
+        .service(
+            web::resource("retry")
+                .wrap(Permission::new("outbox.retry"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(admin_outbox_retry))
+        );
+}
+
+async fn admin_outbox_failed(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+) -> impl Responder {
+    info!("admin_outbox_failed");
+
+    let op = outbox_provider_postgres::PostgresOutboxProvider::new(&dp);
+
+    match op.fetch_failed().await {
+        Err(e) => {
+            error!("unable to fetch failed outbox messages: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch failed messages"));
+        }
+        Ok(messages) => {
+            return HttpResponse::Ok().json(ApiResponse::new(
+                true,
+                "successfully retrieved failed messages",
+                Some(json!({
+                    "messages": messages
+                })),
+            ));
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AdminOutboxRetryPost {
+    message_id: uuid::Uuid,
+}
+
+async fn admin_outbox_retry(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    params: web::Json<AdminOutboxRetryPost>,
+) -> impl Responder {
+    info!("admin_outbox_retry");
+
+    let op = outbox_provider_postgres::PostgresOutboxProvider::new(&dp);
+
+    if let Err(e) = op.retry(&params.message_id).await {
+        error!("unable to retry outbox message: {}", e);
+        return HttpResponse::from_error(ApiError::new(e, "unable to retry message"));
+    }
+
+    return HttpResponse::Ok().json(ApiResponse::ok("message queued for delivery"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_retry_unknown_message() {
+        if let Err(e) = tracing_subscriber::fmt::try_init() {
+            println!("error: {:?}", e);
+        }
+
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let params = web::Json(AdminOutboxRetryPost {
+            message_id: uuid::Uuid::new_v4(),
+        });
+
+        let response = admin_outbox_retry(web::Data::new(Arc::new(dp)), params).await;
+        let request = actix_web::test::TestRequest::default().to_http_request();
+        let response = response.respond_to(&request);
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,195 @@
+use actix_web::{HttpResponse, Responder, guard, http, web};
+use serde::Deserialize;
+use serde_json::json;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::classes::user;
+use crate::endpoints::{ApiError, ApiResponse, default_option_response};
+use crate::middleware::permissions::Permission;
+
+use provider_error::ProviderError;
+use tenants_provider::templates::MailTemplatesProvider;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg
+        .service(
+            web::resource("fetch")
+                .wrap(Permission::new("templates.list"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(admin_templates_fetch))
+        )
+        .service(
+            web::resource("save")
+                .wrap(Permission::new("templates.save"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(admin_template_save))
+        )
+        .service(
+            web::resource("delete")
+                .wrap(Permission::new("templates.save"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(admin_template_delete))
+        );
+}
+
+/// overrides of the current tenant together with the templates that can be
+/// overridden and the variables available to them
+async fn admin_templates_fetch(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+) -> impl Responder {
+    info!("admin_templates_fetch");
+
+    let mtp = tenants_provider_postgres::templates::MailTemplatesProviderPostgres::new(&dp);
+
+    match mtp.templates_fetch(&user.tenant().tenant_id(), None).await {
+        Err(e) => {
+            error!("unable to fetch mail templates: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch mail templates"));
+        }
+        Ok(templates) => {
+            let names: Vec<serde_json::Value> = mailer::TemplateName::ALL
+                .iter()
+                .map(|name| json!({
+                    "name": name.as_str(),
+                    "variables": name.variables()
+                }))
+                .collect();
+
+            return HttpResponse::Ok().json(ApiResponse::new(
+                true,
+                "successfully retrieved mail templates",
+                Some(json!({
+                    "names": names,
+                    "templates": templates
+                })),
+            ));
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AdminTemplateSavePost {
+    name: String,
+    locale: String,
+    subject: String,
+    text: String,
+    html: Option<String>,
+}
+
+/// parse and check a template before it is stored, so that rendering can
+/// only fail on broken built-in templates
+fn validate_template(params: &AdminTemplateSavePost) -> Result<mailer::Template, ProviderError> {
+    let Ok(name) = mailer::TemplateName::from_str(&params.name) else {
+        return Err(ProviderError::validation("name", "unknown template"));
+    };
+
+    let mut template = mailer::Template::new(&params.locale, &params.subject, &params.text);
+    if let Some(html) = &params.html {
+        template = template.with_html(html);
+    }
+
+    if let Err(e) = template.validate(name) {
+        return Err(ProviderError::validation("template", &e.to_string()));
+    }
+
+    return Ok(template);
+}
+
+async fn admin_template_save(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<AdminTemplateSavePost>,
+) -> impl Responder {
+    info!("admin_template_save");
+
+    let template = match validate_template(&params) {
+        Err(e) => {
+            error!("rejected mail template: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "invalid mail template"));
+        }
+        Ok(template) => template,
+    };
+
+    let mtp = tenants_provider_postgres::templates::MailTemplatesProviderPostgres::new(&dp);
+
+    if let Err(e) = mtp
+        .template_save(
+            &user.tenant().tenant_id(),
+            &params.name,
+            &template.locale,
+            &template.subject,
+            &template.text,
+            template.html.as_deref(),
+        )
+        .await
+    {
+        error!("unable to save mail template: {}", e);
+        return HttpResponse::from_error(ApiError::new(e, "unable to save mail template"));
+    }
+
+    return HttpResponse::Ok().json(ApiResponse::ok("successfully saved mail template"));
+}
+
+#[derive(Debug, Deserialize)]
+struct AdminTemplateDeletePost {
+    name: String,
+    locale: String,
+}
+
+/// remove an override, the built-in template is used again
+async fn admin_template_delete(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<AdminTemplateDeletePost>,
+) -> impl Responder {
+    info!("admin_template_delete");
+
+    let mtp = tenants_provider_postgres::templates::MailTemplatesProviderPostgres::new(&dp);
+
+    if let Err(e) = mtp
+        .template_delete(
+            &user.tenant().tenant_id(),
+            &params.name,
+            &mailer::template::normalize_locale(&params.locale),
+        )
+        .await
+    {
+        error!("unable to delete mail template: {}", e);
+        return HttpResponse::from_error(ApiError::new(e, "unable to delete mail template"));
+    }
+
+    return HttpResponse::Ok().json(ApiResponse::ok("successfully deleted mail template"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn save_post(name: &str, text: &str) -> AdminTemplateSavePost {
+        return AdminTemplateSavePost {
+            name: String::from(name),
+            locale: String::from("de_AT"),
+            subject: String::from("Willkommen"),
+            text: String::from(text),
+            html: None,
+        };
+    }
+
+    #[test]
+    fn test_validate_template() {
+        let template = validate_template(&save_post("verification", "{{ link }}"));
+        assert!(template.is_ok_and(|t| t.locale == "de-at"));
+
+        assert!(matches!(
+            validate_template(&save_post("newsletter", "{{ link }}")),
+            Err(ProviderError::Validation { field, .. }) if field == "name"
+        ));
+        assert!(matches!(
+            validate_template(&save_post("verification", "{{ invoice_number }}")),
+            Err(ProviderError::Validation { field, .. }) if field == "template"
+        ));
+    }
+}
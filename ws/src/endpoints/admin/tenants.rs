@@ -4,9 +4,10 @@ use serde_json::json;
 use std::sync::Arc;
 use tracing::{debug, error, info};
 
-use crate::endpoints::{ApiResponse, default_option_response};
+use crate::endpoints::{ApiError, ApiResponse, default_option_response};
 use crate::middleware::permissions::Permission;
 
+use file_provider::FileProvider;
 use roles_provider::{Role, RolesProvider};
 use tenants_provider::TenantsProvider;
 use users_provider::UsersProvider;
@@ -44,6 +45,12 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                 .route(web::method(http::Method::OPTIONS).to(default_option_response))
                 .route(web::post().guard(guard::Header("content-type", "application/json")).to(admin_tenants_set_active))
         )
+        .service(
+            web::resource("quota/save")
+                .wrap(Permission::new("tenant.quota.save"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(admin_tenants_quota_save))
+        )
         .service(
             web::resource("role/save")
                 .wrap(Permission::new("tenant.roles.save"))
@@ -123,8 +130,7 @@ async fn admin_tenants_fetch_id(
     match tp.tenants_fetch_by_id(&params.tenant_id).await {
         Err(e) => {
             error!("unable to fetch tenant by id: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch tenant by id"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch tenant by id"));
         }
         Ok(tenant) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -164,8 +170,7 @@ async fn admin_tenants_save(
         .await
     {
         error!("unable to save tenant: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::error("unable to save tenant"));
+        return HttpResponse::from_error(ApiError::new(e, "unable to save tenant"));
     }
 
     return HttpResponse::Ok().json(ApiResponse::ok("success"));
@@ -189,8 +194,7 @@ async fn admin_tenants_fetch(
     match atp.tenants_fetch(filter.as_str()).await {
         Err(e) => {
             error!("unable to fetch tenant records: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch tenant records"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch tenant records"));
         }
         Ok(tenants) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -224,8 +228,7 @@ async fn admin_tenants_fetch_users(
     {
         Err(e) => {
             error!("unable to fetch tenant users: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch tenant users"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch tenant users"));
         }
         Ok(users) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -266,8 +269,7 @@ async fn admin_role_save_post(
     match rp.save(&params.tenant_id, &role).await {
         Err(e) => {
             error!("unable to add role: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to add role"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to add role"));
         }
         Ok(_) => {
             return HttpResponse::Ok().json(ApiResponse::ok("successfully added role"));
@@ -289,8 +291,7 @@ async fn admin_role_fetch_post(
     match rp.fetch_by_id(&params.role_id).await {
         Err(e) => {
             error!("unable to fetch role: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch role"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch role"));
         }
         Ok(role) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -328,8 +329,7 @@ async fn admin_roles_fetch_post(
     {
         Err(e) => {
             error!("unable to fetch roles: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch roles"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch roles"));
         }
         Ok(roles) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -359,8 +359,7 @@ async fn admin_roles_fetch_id_post(
     match rp.fetch_by_id(&params.role_id).await {
         Err(e) => {
             error!("unable to fetch role: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch role"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch role"));
         }
         Ok(role) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -391,8 +390,7 @@ async fn admin_role_assign_users_post(
     match rp.assign_users(&params.role_ids, &params.user_ids).await {
         Err(e) => {
             error!("unable to assign role to users: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to assign role to users"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to assign role to users"));
         }
         Ok(_) => {
             return HttpResponse::Ok().json(ApiResponse::ok("successfully assigned role to users"));
@@ -411,8 +409,7 @@ async fn admin_role_revoke_users_post(
     match rp.revoke_users(&params.role_ids, &params.user_ids).await {
         Err(e) => {
             error!("unable to revoke role from users: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to revoke role from users"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to revoke role from users"));
         }
         Ok(_) => {
             return HttpResponse::Ok()
@@ -441,8 +438,7 @@ async fn admin_role_assign_permissions_post(
     {
         Err(e) => {
             error!("unable to assign permissions to role: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to assign permissions to role"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to assign permissions to role"));
         }
         Ok(_) => {
             return HttpResponse::Ok()
@@ -465,8 +461,7 @@ async fn admin_role_revoke_permissions_post(
     {
         Err(e) => {
             error!("unable to revoke permissions from role: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to revoke permissions from role"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to revoke permissions from role"));
         }
         Ok(_) => {
             return HttpResponse::Ok()
@@ -495,8 +490,7 @@ async fn admin_tenants_set_active(
     {
         Err(e) => {
             error!("unable to set tenants active state: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to set tenants active state"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to set tenants active state"));
         }
         Ok(_) => {
             return HttpResponse::Ok()
@@ -505,6 +499,33 @@ async fn admin_tenants_set_active(
     }
 }
 
+/// `quota` in bytes, `null` gives the tenant the default quota again
+#[derive(Debug, Deserialize)]
+struct AdminTenantQuotaSave {
+    tenant_id: uuid::Uuid,
+    quota: Option<i64>,
+}
+
+async fn admin_tenants_quota_save(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    params: web::Json<AdminTenantQuotaSave>,
+) -> impl Responder {
+    info!("admin_tenants_quota_save");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+
+    match fp.quota_save(&params.tenant_id, params.quota).await {
+        Err(e) => {
+            error!("unable to save tenant quota: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to save tenant quota"));
+        }
+        Ok(()) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("successfully saved tenant quota"));
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct AdminRolesSetActivePost {
     role_ids: Vec<uuid::Uuid>,
@@ -525,8 +546,7 @@ async fn admin_roles_set_active_post(
     {
         Err(e) => {
             error!("unable to set active state for roles: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to set active state for roles"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to set active state for roles"));
         }
         Ok(_) => {
             return HttpResponse::Ok()
@@ -558,8 +578,7 @@ async fn tenant_users_fetch_post(
     ).await {
         Err(e) => {
             error!("unable to fetch tenant users: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch tenant users"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch tenant users"));
         }
         Ok(users) => {
             return HttpResponse::Ok()
@@ -603,12 +622,12 @@ mod tests {
             .tenant_save(&tenant_id, &tenant_name, &tenant_description, &0)
             .await
         {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to save tenant record");
         }
 
         if let Err(e) = tp.tenant_set_active(&tenant_id, &true).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to set tenant active state");
         }
 
@@ -636,23 +655,23 @@ mod tests {
             )
             .await
         {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to save user");
         }
 
         if let Err(e) = up.set_active(&user_id, &true).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to set user active state");
         }
 
         if let Err(e) = up.add_email(&user_id, &user_email).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to add user email");
         }
 
         // assign user to tenant
         if let Err(e) = up.tenant_assign(&vec![user_id], &vec![tenant_id]).await {
-            error!(e);
+            error!("{}", e);
             assert!(false, "unable to fetch assign users to tenants");
         }
 
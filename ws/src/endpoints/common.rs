@@ -8,7 +8,7 @@ use actix_web::{HttpResponse, Responder, guard, http, web};
 
 use crate::{
     classes::{tenant, user},
-    endpoints::{ApiResponse, default_option_response},
+    endpoints::{ApiError, ApiResponse, default_option_response},
 };
 
 use commons_provider::CommonsProvider;
@@ -50,8 +50,7 @@ async fn countries_fetch_post(
     match cp.fetch_countries().await {
         Err(e) => {
             error!("unable to fetch countries: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch countries"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch countries"));
         }
         Ok(countries) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -74,8 +73,7 @@ async fn currencies_fetch_post(
     match cp.fetch_currencies().await {
         Err(e) => {
             error!("unable to fetch currencies: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch currencies"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch currencies"));
         }
         Ok(currencies) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -98,8 +96,7 @@ async fn dimensions_fetch_post(
     match cp.fetch_dimensions().await {
         Err(e) => {
             error!("unable to fetch dimensions: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch dimensions"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch dimensions"));
         }
         Ok(dimensions) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -122,8 +119,7 @@ async fn uoms_fetch_post(
     match cp.fetch_uoms().await {
         Err(e) => {
             error!("unable to fetch uoms: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch uoms"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch uoms"));
         }
         Ok(uoms) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -153,8 +149,7 @@ async fn uoms_dimension_fetch_post(
     match cp.fetch_uoms_by_dimension_id(&params.dimension_id).await {
         Err(e) => {
             error!("unable to fetch uoms: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch uoms"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch uoms"));
         }
         Ok(uoms) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -13,10 +13,11 @@ use crate::{
         // tenant,
         // permission
     },
-    endpoints::{ApiResponse, default_option_response},
+    endpoints::{ApiError, ApiResponse, default_option_response},
 };
 
 use crm_provider::CrmProvider;
+use file_provider::FileProvider;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -102,8 +103,7 @@ async fn partner_save_post(
     match crm_provider.partner_save(&params.tenant_id, &partner).await {
         Err(e) => {
             error!("unable to save partner record: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to save partner record"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to save partner record"));
         }
         Ok(_) => {
             return HttpResponse::Ok().json(ApiResponse::ok("successfully saved partner record"));
@@ -131,8 +131,7 @@ async fn partners_fetch_post(
     match crm_provider.partners_fetch(&tenant_id, &filter).await {
         Err(e) => {
             error!("unable to fetch permissions: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch permissions"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch permissions"));
         }
         Ok(partners) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -159,19 +158,31 @@ async fn partner_fetch_id_post(
     info!("partner_fetch_id_post");
 
     let crm_provider = crm_provider_postgres::CrmProviderPostgres::new(&dp);
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
 
-    match crm_provider.partner_fetch_by_id(&params.partner_id).await {
+    let tenant_id = user.tenant().tenant_id();
+    let user_id = user.user_id();
+
+    let f1 = crm_provider.partner_fetch_by_id(&params.partner_id);
+    let f2 = fp.attachment_count(
+        &tenant_id,
+        &user_id,
+        file_provider::EntityType::Partner,
+        &params.partner_id,
+    );
+
+    match futures::try_join!(f1, f2) {
         Err(e) => {
             error!("unable to fetch partner: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch partner"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch partner"));
         }
-        Ok(partner) => {
+        Ok((partner, attachment_count)) => {
             return HttpResponse::Ok().json(ApiResponse::new(
                 true,
                 "successfully fetched partner",
                 Some(json!({
-                    "partner": partner
+                    "partner": partner,
+                    "attachment_count": attachment_count
                 })),
             ));
         }
@@ -198,8 +209,7 @@ async fn partners_set_active_post(
     {
         Err(e) => {
             error!("unable to set partner active state: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to save partner record"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to save partner record"));
         }
         Ok(_) => {
             return HttpResponse::Ok()
@@ -4,7 +4,11 @@ use tracing::{
     debug
 };
 
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::vec::Vec;
 
 use serde::{
@@ -13,15 +17,15 @@ use serde::{
 };
 use serde_json::json;
 
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 
 use actix_web::{
+    body::SizedStream,
+    guard,
     http,
-    web::{
-        self,
-        Bytes
-    },
-    dev::Payload,
+    http::header,
+    web,
+    HttpRequest,
     HttpResponse,
     Responder
 };
@@ -30,12 +34,12 @@ use actix_multipart::{
     Multipart
 };
 
-use tokio::{io::AsyncWriteExt};
-
 
 use file_provider::FileProvider;
+use provider_error::ProviderError;
 use crate::{
     endpoints::{
+        ApiError,
         ApiResponse,
         default_option_response
     },
@@ -54,6 +58,64 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                 .route(web::method(http::Method::OPTIONS).to(default_option_response))
                 .route(web::post().to(file_upload_post))
         )
+        .service(
+            web::resource("version/upload")
+                .wrap(Permission::new("files.upload"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().to(file_version_upload_post))
+        )
+        .service(
+            web::resource("versions")
+                .wrap(Permission::new("files.versions.list"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(file_versions_post))
+        )
+        .service(
+            web::resource("version/restore")
+                .wrap(Permission::new("files.versions.restore"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(file_version_restore_post))
+        )
+        .service(
+            web::resource("usage")
+                .wrap(Permission::new("files.usage"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(file_usage_post))
+        )
+        .service(
+            web::resource("download/{file_id}")
+                .wrap(Permission::new("files.download"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::get().to(file_download_get))
+        )
+        .service(
+            web::resource("preview/{file_id}")
+                .wrap(Permission::new("files.download"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::get().to(file_preview_get))
+        )
+        .service(
+            web::resource("delete")
+                .wrap(Permission::new("files.delete"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(file_delete_post))
+        )
+        .service(
+            web::resource("search")
+                .wrap(Permission::new("files.search"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(file_search_post))
+        )
+        .configure(folder_config)
+        .configure(share_config)
+        .configure(attachment_config)
+        .service(web::scope("tus").configure(crate::endpoints::tus::config))
+    ;
+}
+
+
+fn folder_config(cfg: &mut web::ServiceConfig) {
+    cfg
         .service(
             web::resource("folder/create")
                 .wrap(Permission::new("files.folders.create"))
@@ -66,112 +128,486 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                 .route(web::method(http::Method::OPTIONS).to(default_option_response))
                 .route(web::post().to(folder_list_folders_post))
         )
+        .service(
+            web::resource("folder/path")
+                .wrap(Permission::new("files.folders.list.folders"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(folder_path_post))
+        )
+        .service(
+            web::resource("folder/download/{folder_id}")
+                .wrap(Permission::new("files.download"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::get().to(folder_download_get))
+        )
+        .service(
+            web::resource("folder/rename")
+                .wrap(Permission::new("files.folders.update"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(folder_rename_post))
+        )
+        .service(
+            web::resource("folder/move")
+                .wrap(Permission::new("files.folders.update"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(folder_move_post))
+        )
+        .service(
+            web::resource("folder/delete")
+                .wrap(Permission::new("files.folders.delete"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(folder_delete_post))
+        )
+        .service(
+            web::resource("folder/restore")
+                .wrap(Permission::new("files.folders.delete"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(folder_restore_post))
+        )
+        .service(
+            web::resource("folder/trash")
+                .wrap(Permission::new("files.folders.delete"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().to(folder_trash_post))
+        )
+        .service(
+            web::resource("folder/sharing")
+                .wrap(Permission::new("files.folders.list.folders"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(folder_sharing_post))
+        )
+        .service(
+            web::resource("folder/share")
+                .wrap(Permission::new("files.folders.share"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(folder_share_post))
+        )
+        .service(
+            web::resource("folder/unshare")
+                .wrap(Permission::new("files.folders.share"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(folder_unshare_post))
+        )
+    ;
+}
+
+
+
+fn share_config(cfg: &mut web::ServiceConfig) {
+    cfg
+        .service(
+            web::resource("share/create")
+                .wrap(Permission::new("files.share.links"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(share_create_post))
+        )
+        .service(
+            web::resource("share/list")
+                .wrap(Permission::new("files.share.links"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(share_list_post))
+        )
+        .service(
+            web::resource("share/revoke")
+                .wrap(Permission::new("files.share.links"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(share_revoke_post))
+        )
+        .service(
+            web::resource("share/accesses")
+                .wrap(Permission::new("files.share.links"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(share_accesses_post))
+        )
+    ;
+}
+
+
+
+fn attachment_config(cfg: &mut web::ServiceConfig) {
+    cfg
+        .service(
+            web::resource("attachments")
+                .wrap(Permission::new("files.attachments.list"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(attachments_post))
+        )
+        .service(
+            web::resource("attachment/add")
+                .wrap(Permission::new("files.attachments.update"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(attachment_add_post))
+        )
+        .service(
+            web::resource("attachment/remove")
+                .wrap(Permission::new("files.attachments.update"))
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(attachment_remove_post))
+        )
     ;
 }
 
 
 
 async fn file_upload_post(
+    cfg: web::Data<Arc<config::Config>>,
     dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    store: web::Data<Arc<dyn file_store::FileStore>>,
     user: user::User,
-    mut payload: Multipart
+    payload: Multipart
 ) -> impl Responder {
     info!("file_upload_post");
 
-    // debug!("payload: {:?}", payload);
+    let tenant_id = user.tenant().tenant_id();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let limits = match upload_limits(&fp, &cfg, tenant_id).await {
+        Err(response) => return response,
+        Ok(limits) => limits
+    };
+
+    let upload = match read_upload(&store, &limits, tenant_id, payload).await {
+        Err(response) => return response,
+        Ok(upload) => upload
+    };
+
+    // the client may choose the id of the file
+    let file_id = if upload.file_id.is_nil() { uuid::Uuid::new_v4() } else { upload.file_id };
+    let file = upload.file(file_id);
+
+    if let Err(e) = fp.file_add(&tenant_id, &user.user_id(), &upload.folder_id, &file).await {
+        error!("error adding file to provider: {:?}", e);
+        release_content(&fp, &store, tenant_id, &upload.stat.digest).await;
+        return HttpResponse::from_error(ApiError::new(e, "Error saving file"));
+    }
+
+    return HttpResponse::Ok()
+        .json(ApiResponse::new(
+            true,
+            "File uploaded successfully",
+            Some(json!({
+                "file_id": file_id,
+                "version": file.version,
+                "size": upload.stat.size,
+                "digest": upload.stat.digest
+            }))
+        ));
+}
+
+
+/// uploads new content for the file `file_id`, which becomes its current
+/// version
+async fn file_version_upload_post(
+    cfg: web::Data<Arc<config::Config>>,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    store: web::Data<Arc<dyn file_store::FileStore>>,
+    user: user::User,
+    payload: Multipart
+) -> impl Responder {
+    info!("file_version_upload_post");
+
+    let tenant_id = user.tenant().tenant_id();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let limits = match upload_limits(&fp, &cfg, tenant_id).await {
+        Err(response) => return response,
+        Ok(limits) => limits
+    };
+
+    let upload = match read_upload(&store, &limits, tenant_id, payload).await {
+        Err(response) => return response,
+        Ok(upload) => upload
+    };
+
+    if upload.file_id.is_nil() {
+        release_content(&fp, &store, tenant_id, &upload.stat.digest).await;
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::error("Missing file_id"));
+    }
+
+    match fp.file_version_add(&tenant_id, &user.user_id(), &upload.file(upload.file_id)).await {
+        Err(e) => {
+            error!("error adding version of file {}: {:?}", upload.file_id, e);
+            release_content(&fp, &store, tenant_id, &upload.stat.digest).await;
+            return HttpResponse::from_error(ApiError::new(e, "Error saving file version"));
+        }
+        Ok(version) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::new(
+                    true,
+                    "File version uploaded successfully",
+                    Some(json!({
+                        "file_id": upload.file_id,
+                        "version": version,
+                        "size": upload.stat.size,
+                        "digest": upload.stat.digest
+                    }))
+                ));
+        }
+    }
+}
+
+
+/// fields of a multipart upload, the content is already in the store
+struct Upload {
+    folder_id: uuid::Uuid,
+    /// nil unless sent by the client
+    file_id: uuid::Uuid,
+    file_name: String,
+    content_type: String,
+    stat: file_store::FileStat
+}
+
+impl Upload {
+
+    fn file(&self, file_id: uuid::Uuid) -> file_provider::File {
+        return file_provider::File::new(file_id, self.file_name.clone())
+            .with_content(
+                &self.content_type,
+                i64::try_from(self.stat.size).unwrap_or(i64::MAX),
+                &self.stat.digest
+            );
+    }
+}
+
+/// limits an upload has to stay within, `remaining` is what is left of the
+/// tenant's quota and `None` without a quota
+pub(crate) struct UploadLimits {
+    pub(crate) config: config::UploadConfig,
+    remaining: Option<u64>
+}
+
+impl UploadLimits {
+
+    /// the limit `size` bytes break, if any
+    pub(crate) fn exceeded(&self, size: u64) -> Option<Rejection> {
+        if size > self.config.max_size {
+            return Some(Rejection::TooLarge);
+        }
+        if self.remaining.is_some_and(|remaining| size > remaining) {
+            return Some(Rejection::OverQuota);
+        }
+        return None;
+    }
+}
+
+/// the tenant's own quota takes precedence over the configured default,
+/// uploads have to fit in what is left of it; content the tenant already
+/// stored counts like any other upload
+pub(crate) async fn upload_limits(
+    fp: &file_provider_postgres::PostgresFileProvider,
+    cfg: &config::Config,
+    tenant_id: uuid::Uuid
+) -> Result<UploadLimits, HttpResponse> {
+    let config = cfg.upload();
+    match fp.usage_fetch(&tenant_id).await {
+        Err(e) => {
+            error!("unable to fetch storage usage of tenant {}: {:?}", tenant_id, e);
+            return Err(HttpResponse::from_error(ApiError::new(e, "Error checking storage quota")));
+        }
+        Ok(usage) => {
+            let remaining = effective_quota(&usage, &config)
+                .map(|quota| quota.saturating_sub(u64::try_from(usage.used).unwrap_or_default()));
+            return Ok(UploadLimits {
+                config,
+                remaining
+            });
+        }
+    }
+}
+
+fn effective_quota(usage: &file_provider::Usage, config: &config::UploadConfig) -> Option<u64> {
+    return match usage.quota {
+        Some(quota) => Some(u64::try_from(quota).unwrap_or_default()),
+        None if config.default_quota > 0 => Some(config.default_quota),
+        None => None
+    };
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Rejection {
+    TooLarge,
+    OverQuota,
+    Type
+}
+
+impl Rejection {
+
+    pub(crate) fn response(self) -> HttpResponse {
+        return match self {
+            Rejection::TooLarge => HttpResponse::PayloadTooLarge()
+                .json(ApiResponse::error("File exceeds the maximum file size")),
+            Rejection::OverQuota => HttpResponse::PayloadTooLarge()
+                .json(ApiResponse::error("Storage quota exceeded")),
+            Rejection::Type => HttpResponse::UnsupportedMediaType()
+                .json(ApiResponse::error("File type not allowed"))
+        };
+    }
+}
+
+
+/// outcome of the checks, the content type is set once it is sniffed
+#[derive(Debug, Default)]
+struct UploadCheck {
+    content_type: Option<String>,
+    rejection: Option<Rejection>
+}
+
+/// passes the content of the `file` field on to the store and cuts it off
+/// as soon as it breaks a limit; the content type is sniffed from the first
+/// bytes, the type the client declared only refines it
+struct CheckedUpload<'a> {
+    data: file_store::ByteStream<'a>,
+    limits: &'a UploadLimits,
+    declared: String,
+    read: u64,
+    head: Vec<u8>,
+    check: &'a RefCell<UploadCheck>
+}
+
+impl CheckedUpload<'_> {
+
+    fn reject(&self, rejection: Rejection) -> ProviderError {
+        debug!("upload rejected after {} bytes: {:?}", self.read, rejection);
+        self.check.borrow_mut().rejection = Some(rejection);
+        return ProviderError::validation("file", "upload rejected");
+    }
+
+    fn check_type(&self) -> Result<(), ProviderError> {
+        let content_type = file_store::sniff::content_type(&self.head, &self.declared);
+        if !self.limits.config.allows(&content_type) {
+            debug!("content type {} not allowed", content_type);
+            return Err(self.reject(Rejection::Type));
+        }
+        self.check.borrow_mut().content_type = Some(content_type);
+        return Ok(());
+    }
+}
+
+impl Stream for CheckedUpload<'_> {
+    type Item = Result<web::Bytes, ProviderError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        match this.data.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                this.read += chunk.len() as u64;
+                if let Some(rejection) = this.limits.exceeded(this.read) {
+                    return Poll::Ready(Some(Err(this.reject(rejection))));
+                }
+
+                let missing = file_store::sniff::HEAD_LENGTH - this.head.len();
+                if missing > 0 {
+                    this.head.extend_from_slice(&chunk[..missing.min(chunk.len())]);
+                    if this.head.len() == file_store::sniff::HEAD_LENGTH
+                        && let Err(e) = this.check_type()
+                    {
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
+                return Poll::Ready(Some(Ok(chunk)));
+            }
+            Poll::Ready(None) => {
+                // content shorter than the head is checked at its end
+                let sniffed = this.check.borrow().content_type.is_some();
+                if !sniffed
+                    && let Err(e) = this.check_type()
+                {
+                    return Poll::Ready(Some(Err(e)));
+                }
+                return Poll::Ready(None);
+            }
+            polled => {
+                return polled;
+            }
+        }
+    }
+}
+
 
+/// stores the single `file` field of the payload, `folder_id` and `file_id`
+/// fields may accompany it
+async fn read_upload(
+    store: &Arc<dyn file_store::FileStore>,
+    limits: &UploadLimits,
+    tenant_id: uuid::Uuid,
+    mut payload: Multipart
+) -> Result<Upload, HttpResponse> {
     let mut folder_id: uuid::Uuid = uuid::Uuid::nil();
     let mut file_id: uuid::Uuid = uuid::Uuid::nil();
-    let mut file_name: String = String::new();
-    let mut file_uploaded = false;
+    let mut uploaded: Option<(String, String, file_store::FileStat)> = None;
 
     while let Some(p) = payload.next().await {
-        let mut field = p.unwrap();
-        let cd = field.content_disposition().unwrap();
-        let field_name = cd.get_name().unwrap();
-        match field_name {
+        let field = match p {
+            Err(e) => {
+                error!("error reading multipart field: {:?}", e);
+                return Err(HttpResponse::BadRequest()
+                    .json(ApiResponse::error("Invalid multipart payload")));
+            }
+            Ok(field) => field
+        };
+
+        let field_name = field.content_disposition()
+            .and_then(|cd| cd.get_name())
+            .map(String::from)
+            .unwrap_or_default();
+        match field_name.as_str() {
             "file" => {
-                file_name = cd.get_filename().map(String::from).unwrap();
-                // debug!("Receiving file: {}", file_name);
+                if uploaded.is_some() {
+                    return Err(HttpResponse::BadRequest()
+                        .json(ApiResponse::error("Only one file can be uploaded at a time")));
+                }
 
-                let mut file = match tokio::fs::File::create(format!("/var/tmp/{}", file_name)).await {
-                    Err(e) => {
-                        error!("error creating file: {:?}", e);
-                        return HttpResponse::InternalServerError()
-                            .json(ApiResponse::error("Error creating file"));
-                    }
-                    Ok(f) => f
+                let Some(file_name) = field.content_disposition()
+                    .and_then(|cd| cd.get_filename())
+                    .map(sanitize_file_name)
+                    .filter(|name| !name.is_empty())
+                else {
+                    return Err(HttpResponse::BadRequest()
+                        .json(ApiResponse::error("Missing file name")));
                 };
 
-                while let Some(next_chunk) = field.next().await {
-                    let chunk = match next_chunk {
-                        Err(e) => {
-                            error!("error reading chunk: {:?}", e);
-                            return HttpResponse::InternalServerError()
-                                .json(ApiResponse::error("Error reading file chunk"));
-                        }
-                        Ok(c) => {
-                            debug!("read chunk of size: {}", c.len());
-                            c
-                        }
-                    };
+                let declared = field.content_type()
+                    .map_or(String::from(file_provider::DEFAULT_CONTENT_TYPE), ToString::to_string);
 
-                    if let Err(e) = file.write_all(&chunk).await {
-                        error!("error writing chunk to file: {:?}", e);
-                        return HttpResponse::InternalServerError()
-                            .json(ApiResponse::error("Error writing file chunk"));
-                    }
-                }
+                // the digest is computed by the store while the field is written
+                let check = RefCell::new(UploadCheck::default());
+                let data: file_store::ByteStream = Box::pin(CheckedUpload {
+                    data: Box::pin(field.map(|chunk| chunk.map_err(|e| {
+                        error!("error reading chunk: {:?}", e);
+                        return ProviderError::validation("file", "unable to read upload");
+                    }))),
+                    limits,
+                    declared,
+                    read: 0,
+                    head: Vec::with_capacity(file_store::sniff::HEAD_LENGTH),
+                    check: &check
+                });
 
-                file_uploaded = true;
-            },
-            "folder_id" => {
-                let mut data = Vec::new();
-                while let Some(bytes) = field.next().await {
-                    match bytes {
-                        Err(e) => {
-                            error!("error reading folder_id bytes: {:?}", e);
-                            return HttpResponse::InternalServerError()
-                                .json(ApiResponse::error("Error reading folder_id"));
-                        }
-                        Ok(b) => {
-                            data.extend_from_slice(&b);
+                let stored = store.put(tenant_id, data).await;
+                let check = check.into_inner();
+                match stored {
+                    Err(e) => {
+                        if let Some(rejection) = check.rejection {
+                            return Err(rejection.response());
                         }
+                        error!("error storing file: {}", e);
+                        return Err(HttpResponse::from_error(ApiError::new(e, "Error storing file")));
                     }
-                }
-
-                if let Ok(id_str) = String::from_utf8(data.clone()) {
-                    if let Ok(id) = uuid::Uuid::parse_str(&id_str) {
-                        folder_id = id;
-                    } else {
-                        return HttpResponse::BadRequest()
-                            .json(ApiResponse::error("Invalid folder_id"));
+                    Ok(stat) => {
+                        debug!("stored {} bytes as {}", stat.size, stat.digest);
+                        let content_type = check.content_type
+                            .unwrap_or(String::from(file_provider::DEFAULT_CONTENT_TYPE));
+                        uploaded = Some((file_name, content_type, stat));
                     }
                 }
             },
+            "folder_id" => {
+                folder_id = read_uuid_field(field).await?;
+            },
             "file_id" => {
-                let mut data = Vec::new();
-                while let Some(bytes) = field.next().await {
-                    match bytes {
-                        Err(e) => {
-                            error!("error reading file_id bytes: {:?}", e);
-                            return HttpResponse::InternalServerError()
-                                .json(ApiResponse::error("Error reading file_id"));
-                        }
-                        Ok(b) => {
-                            data.extend_from_slice(&b);
-                        }
-                    }
-                }
-
-                if let Ok(id_str) = String::from_utf8(data.clone()) {
-                    if let Ok(id) = uuid::Uuid::parse_str(&id_str) {
-                        file_id = id;
-                    } else {
-                        return HttpResponse::BadRequest()
-                            .json(ApiResponse::error("Invalid file_id"));
-                    }
-                }
+                file_id = read_uuid_field(field).await?;
             }
             _ => {
                 debug!("unhandled field: {}", field_name);
@@ -179,86 +615,690 @@ async fn file_upload_post(
         }
     }
 
-    if file_uploaded {
-        let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
-        if let Err(e) = fp.file_add(
-            &user.tenant().tenant_id(),
-            &folder_id,
-            &file_provider::File::new(
-                file_id,
-                file_name.clone()
-            )
-        ).await {
-            error!("error adding file to provider: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("Error saving file"));
+    let Some((file_name, content_type, stat)) = uploaded else {
+        return Err(HttpResponse::BadRequest()
+            .json(ApiResponse::error("No file uploaded")));
+    };
+
+    return Ok(Upload {
+        folder_id,
+        file_id,
+        file_name,
+        content_type,
+        stat
+    });
+}
+
+
+/// removes stored content that no file refers to, used when the metadata of
+/// an upload was rejected; content shared with other files stays
+pub(crate) async fn release_content(
+    fp: &file_provider_postgres::PostgresFileProvider,
+    store: &Arc<dyn file_store::FileStore>,
+    tenant_id: uuid::Uuid,
+    digest: &str
+) {
+    if let Ok(false) = fp.blob_is_referenced(&tenant_id, digest).await
+        && let Err(e) = store.delete(tenant_id, digest).await
+    {
+        error!("unable to remove unreferenced content {}: {}", digest, e);
+    }
+}
+
+
+/// reads a small text field holding a uuid
+async fn read_uuid_field(mut field: Field) -> Result<uuid::Uuid, HttpResponse> {
+    let mut data = Vec::new();
+    while let Some(bytes) = field.next().await {
+        match bytes {
+            Err(e) => {
+                error!("error reading field bytes: {:?}", e);
+                return Err(HttpResponse::BadRequest()
+                    .json(ApiResponse::error("Error reading field")));
+            }
+            Ok(b) => {
+                data.extend_from_slice(&b);
+            }
         }
     }
 
-    return HttpResponse::Ok()
-        .json(ApiResponse::ok("File uploaded successfully"));
+    return String::from_utf8(data).ok()
+        .and_then(|id| uuid::Uuid::parse_str(id.trim()).ok())
+        .ok_or(HttpResponse::BadRequest()
+            .json(ApiResponse::error("Invalid uuid")));
 }
 
 
-
-#[derive(Debug, Deserialize)]
-struct FolderCreatePost {
-    folder_id: uuid::Uuid,
-    name: String
+/// name shown to users, only the last path component of what the client
+/// sent and without control characters
+pub(crate) fn sanitize_file_name(name: &str) -> String {
+    return name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .to_string();
 }
 
-async fn folder_create_post(
+
+
+/// storage used by the tenant and the limits uploads are checked against,
+/// `quota` and `available` are null without a quota
+async fn file_usage_post(
+    cfg: web::Data<Arc<config::Config>>,
     dp: web::Data<Arc<database_provider::DatabaseProvider>>,
-    user: user::User,
-    params: web::Json<FolderCreatePost>
+    user: user::User
 ) -> impl Responder {
-    info!("folder_create_post");
+    info!("file_usage_post");
 
+    let config = cfg.upload();
     let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
-    match fp.folder_add(
-        &user.tenant().tenant_id(),
-        &file_provider::Folder::new(
-            params.folder_id,
-            params.name.clone()
-        )
-    ).await {
+    match fp.usage_fetch(&user.tenant().tenant_id()).await {
         Err(e) => {
-            error!("error creating folder: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("Error creating folder"));
+            error!("unable to fetch storage usage: {:?}", e);
+            return HttpResponse::from_error(ApiError::new(e, "Error fetching storage usage"));
         }
-        Ok(_) => {
+        Ok(usage) => {
+            let quota = effective_quota(&usage, &config);
             return HttpResponse::Ok()
-                .json(ApiResponse::ok("Folder created successfully"));
+                .json(ApiResponse::new(
+                    true,
+                    "successfully fetched storage usage",
+                    Some(json!({
+                        "used": usage.used,
+                        "files": usage.files,
+                        "quota": quota,
+                        "available": quota.map(|q| q.saturating_sub(u64::try_from(usage.used).unwrap_or_default())),
+                        "max_size": config.max_size,
+                        "allowed_types": config.allowed_types
+                    }))
+                ));
         }
     }
 }
 
 
 #[derive(Debug, Deserialize)]
-struct FolderListFoldersPost {
-    folder_id: uuid::Uuid
+struct FileDownloadQuery {
+    inline: Option<bool>,
+    /// the current version when not set
+    version: Option<i32>
 }
 
-async fn folder_list_folders_post(
+/// content of a file of the current tenant, `?version=` selects an older
+/// version; `If-None-Match` is answered with
+/// 304 while the content is unchanged and a single byte range may be
+/// requested through `Range`, multiple ranges are served as the whole file
+async fn file_download_get(
+    req: HttpRequest,
     dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    store: web::Data<Arc<dyn file_store::FileStore>>,
     user: user::User,
-    params: web::Json<FolderListFoldersPost>
+    path: web::Path<uuid::Uuid>,
+    query: web::Query<FileDownloadQuery>
 ) -> impl Responder {
-    info!("folder_list_folders_post");
+    info!("file_download_get");
 
-    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let tenant_id = user.tenant().tenant_id();
+    let file_id = path.into_inner();
 
-    let f1 = fp.folder_list_folders(&params.folder_id);
-    let f2 = fp.folder_list_files(&params.folder_id);
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let file = match file_version(&fp, &tenant_id, &user.user_id(), &file_id, query.version).await {
+        Err(e) => {
+            error!("unable to fetch file {}: {:?}", file_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error fetching file"));
+        }
+        Ok(file) => file
+    };
+
+    return serve_file(&req, store.get_ref().as_ref(), tenant_id, &file, query.inline.unwrap_or(false)).await;
+}
+
+
+/// the preview image of the current version of a file; without one the
+/// state of the preview and an icon to show instead are returned with 404
+async fn file_preview_get(
+    req: HttpRequest,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    store: web::Data<Arc<dyn file_store::FileStore>>,
+    user: user::User,
+    path: web::Path<uuid::Uuid>
+) -> impl Responder {
+    info!("file_preview_get");
+
+    let tenant_id = user.tenant().tenant_id();
+    let user_id = user.user_id();
+    let file_id = path.into_inner();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let f1 = fp.file_get(&tenant_id, &user_id, &file_id);
+    let f2 = fp.file_preview_fetch(&tenant_id, &user_id, &file_id);
+    let (file, preview) = match futures::try_join!(f1, f2) {
+        Err(e) => {
+            error!("unable to fetch preview of file {}: {:?}", file_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error fetching preview"));
+        }
+        Ok(result) => result
+    };
+
+    let (Some(preview), Some(content_type)) = (
+        preview.as_ref().filter(|p| p.state == file_provider::PreviewState::Ready),
+        preview.as_ref().and_then(|p| p.content_type.as_deref())
+    ) else {
+        let state = preview.map_or(file_provider::PreviewState::None, |p| p.state);
+        return HttpResponse::NotFound()
+            .json(ApiResponse::new(
+                false,
+                "No preview available",
+                Some(json!({
+                    "preview": state,
+                    "icon": file_provider::icon(&file.content_type)
+                }))
+            ));
+    };
+
+    let etag = format!("\"{}.preview\"", preview.digest);
+    if req.headers().get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| etag_matches(v, &etag))
+    {
+        return HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .finish();
+    }
+
+    match store.get_preview(tenant_id, &preview.digest).await {
+        Err(e) => {
+            error!("unable to read preview of file {}: {}", file_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error reading preview"));
+        }
+        Ok(data) => {
+            return HttpResponse::Ok()
+                .content_type(content_type)
+                .insert_header((header::ETAG, etag))
+                .insert_header(header::LastModified(std::time::SystemTime::from(preview.updated).into()))
+                .insert_header((header::CACHE_CONTROL, "private, no-cache"))
+                .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+                .insert_header((header::CONTENT_SECURITY_POLICY, FILE_CONTENT_SECURITY_POLICY))
+                .streaming(data);
+        }
+    }
+}
+
+
+/// stored content never runs scripts or loads anything on the api origin,
+/// whatever its type
+const FILE_CONTENT_SECURITY_POLICY: &str = "default-src 'none'; sandbox";
+
+
+/// content of the file as response to `req`, honoring `If-None-Match`,
+/// `Range` and `If-Range`
+pub(crate) async fn serve_file(
+    req: &HttpRequest,
+    store: &dyn file_store::FileStore,
+    tenant_id: uuid::Uuid,
+    file: &file_provider::File,
+    inline: bool
+) -> HttpResponse {
+    let file_id = file.file_id;
+
+    let Some(digest) = file.digest.as_deref() else {
+        error!("file {} has no content", file_id);
+        return HttpResponse::from_error(ApiError::new(ProviderError::NotFound, "Error reading file"));
+    };
+
+    let stat = match store.stat(tenant_id, digest).await {
+        Err(e) => {
+            error!("unable to stat content of file {}: {}", file_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error reading file"));
+        }
+        Ok(stat) => stat
+    };
+
+    let etag = format!("\"{}\"", stat.digest);
+    let header_value = |name: header::HeaderName| req.headers().get(name).and_then(|v| v.to_str().ok());
+
+    if header_value(header::IF_NONE_MATCH).is_some_and(|v| etag_matches(v, &etag)) {
+        return HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .finish();
+    }
+
+    // a range only applies to the version the client already has a part of
+    let range = header_value(header::RANGE)
+        .filter(|_| header_value(header::IF_RANGE).is_none_or(|v| v == etag));
+
+    let (mut response, offset, length) = match byte_range(range, stat.size) {
+        ByteRange::Full => (HttpResponse::Ok(), 0, stat.size),
+        ByteRange::Partial(first, last) => {
+            let mut response = HttpResponse::PartialContent();
+            response.insert_header((header::CONTENT_RANGE, format!("bytes {}-{}/{}", first, last, stat.size)));
+            (response, first, last - first + 1)
+        }
+        ByteRange::Unsatisfiable => {
+            return HttpResponse::RangeNotSatisfiable()
+                .insert_header((header::CONTENT_RANGE, format!("bytes */{}", stat.size)))
+                .finish();
+        }
+    };
+
+    let data = match store.get_range(tenant_id, digest, offset, length).await {
+        Err(e) => {
+            error!("unable to read content of file {}: {}", file_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error reading file"));
+        }
+        Ok(data) => data
+    };
+
+    return response
+        .content_type(file.content_type.as_str())
+        .insert_header(content_disposition(&file.name, inline && inline_allowed(&file.content_type)))
+        .insert_header((header::ETAG, etag))
+        .insert_header(header::LastModified(std::time::SystemTime::from(stat.modified).into()))
+        .insert_header((header::ACCEPT_RANGES, "bytes"))
+        .insert_header((header::CACHE_CONTROL, "private, no-cache"))
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .insert_header((header::CONTENT_SECURITY_POLICY, FILE_CONTENT_SECURITY_POLICY))
+        .body(SizedStream::new(length, data));
+}
+
+
+/// the file with the content of `version`, of the current version when no
+/// version is given
+async fn file_version(
+    fp: &file_provider_postgres::PostgresFileProvider,
+    tenant_id: &uuid::Uuid,
+    user_id: &uuid::Uuid,
+    file_id: &uuid::Uuid,
+    version: Option<i32>
+) -> Result<file_provider::File, ProviderError> {
+    let file = fp.file_get(tenant_id, user_id, file_id).await?;
+    let Some(version) = version else {
+        return Ok(file);
+    };
+
+    let v = fp.file_version_get(tenant_id, user_id, file_id, version).await?;
+    let mut file = file_provider::File::new(file.file_id, file.name)
+        .with_content(&v.content_type, v.size, &v.digest);
+    file.version = v.version;
+    return Ok(file);
+}
+
+
+#[derive(Debug, Deserialize)]
+struct FileVersionsPost {
+    file_id: uuid::Uuid
+}
+
+/// the file and its versions, newest first
+async fn file_versions_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FileVersionsPost>
+) -> impl Responder {
+    info!("file_versions_post");
+
+    let tenant_id = user.tenant().tenant_id();
+    let user_id = user.user_id();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+
+    let f1 = fp.file_get(&tenant_id, &user_id, &params.file_id);
+    let f2 = fp.file_versions_fetch(&tenant_id, &user_id, &params.file_id);
+
+    match futures::try_join!(f1, f2) {
+        Err(e) => {
+            error!("unable to fetch versions of file {}: {:?}", params.file_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch file versions"));
+        }
+        Ok((file, versions)) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::new(
+                    true,
+                    "successfully fetched file versions",
+                    Some(json!({
+                        "file": file,
+                        "versions": versions
+                    }))
+                ));
+        }
+    }
+}
+
+
+#[derive(Debug, Deserialize)]
+struct FileVersionRestorePost {
+    file_id: uuid::Uuid,
+    version: i32
+}
+
+/// makes the content of an older version current again by adding it as new
+/// version
+async fn file_version_restore_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FileVersionRestorePost>
+) -> impl Responder {
+    info!("file_version_restore_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.file_version_restore(&user.tenant().tenant_id(), &user.user_id(), &params.file_id, params.version).await {
+        Err(e) => {
+            error!("unable to restore version {} of file {}: {:?}", params.version, params.file_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error restoring file version"));
+        }
+        Ok(version) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::new(
+                    true,
+                    "File version restored successfully",
+                    Some(json!({
+                        "file_id": params.file_id,
+                        "version": version
+                    }))
+                ));
+        }
+    }
+}
+
+
+#[derive(Debug, Deserialize)]
+struct FileDeletePost {
+    file_id: uuid::Uuid
+}
+
+/// removes the file with all versions, content only once no other file
+/// refers to it
+async fn file_delete_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    store: web::Data<Arc<dyn file_store::FileStore>>,
+    user: user::User,
+    params: web::Json<FileDeletePost>
+) -> impl Responder {
+    info!("file_delete_post");
+
+    let tenant_id = user.tenant().tenant_id();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.file_delete(&tenant_id, &user.user_id(), &params.file_id).await {
+        Err(e) => {
+            error!("error deleting file {}: {:?}", params.file_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error deleting file"));
+        }
+        Ok(unused) => {
+            // the file is gone either way, content left behind only uses space
+            for digest in unused {
+                if let Err(e) = store.delete(tenant_id, &digest).await {
+                    error!("unable to remove unused content {}: {}", digest, e);
+                }
+            }
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("File deleted successfully"));
+        }
+    }
+}
+
+
+/// results returned when the request does not say
+const SEARCH_DEFAULT_LIMIT: i32 = 20;
+const SEARCH_MAX_LIMIT: i32 = 100;
+
+/// file found by a search, the snippet is html with matches in `<mark>`
+#[derive(Debug, Serialize)]
+struct FoundFile {
+    #[serde(flatten)]
+    result: file_provider::SearchResult,
+    icon: &'static str
+}
+
+#[derive(Debug, Deserialize)]
+struct FileSearchPost {
+    query: String,
+    limit: Option<i32>,
+    offset: Option<i32>
+}
+
+/// files whose name or text matches the query, best matches first; the
+/// query takes the syntax of web search engines
+async fn file_search_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FileSearchPost>
+) -> impl Responder {
+    info!("file_search_post");
+
+    let query = params.query.trim();
+    if query.is_empty() {
+        return HttpResponse::from_error(ApiError::new(
+            ProviderError::validation("query", "is required"),
+            "unable to search files"
+        ));
+    }
+    let limit = params.limit.unwrap_or(SEARCH_DEFAULT_LIMIT).clamp(1, SEARCH_MAX_LIMIT);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.file_search(&user.tenant().tenant_id(), &user.user_id(), query, limit, offset).await {
+        Err(e) => {
+            error!("unable to search files for '{}': {:?}", query, e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to search files"));
+        }
+        Ok(results) => {
+            let files: Vec<FoundFile> = results.into_iter()
+                .map(|mut result| {
+                    result.snippet = result.snippet.as_deref().map(snippet_html);
+                    return FoundFile {
+                        icon: file_provider::icon(&result.file.content_type),
+                        result
+                    };
+                })
+                .collect();
+            return HttpResponse::Ok()
+                .json(ApiResponse::new(
+                    true,
+                    "successfully searched files",
+                    Some(json!({
+                        "files": files,
+                        "limit": limit,
+                        "offset": offset
+                    }))
+                ));
+        }
+    }
+}
+
+/// the snippet escaped as html, with the matches marked by the provider
+/// wrapped in `<mark>`
+fn snippet_html(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            file_provider::MATCH_START => html.push_str("<mark>"),
+            file_provider::MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c)
+        }
+    }
+    return html;
+}
+
+
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    Full,
+    /// first and last byte, both inclusive
+    Partial(u64, u64),
+    Unsatisfiable
+}
+
+/// single range of a `Range` header for content of `size` bytes; headers
+/// that cannot be parsed are ignored as RFC 9110 allows
+fn byte_range(range: Option<&str>, size: u64) -> ByteRange {
+    let Some(spec) = range.and_then(|r| r.trim().strip_prefix("bytes=")) else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((first, last)) = spec.split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    let (first, last) = (first.trim(), last.trim());
+    if first.is_empty() {
+        // suffix range, the last n bytes
+        return match last.parse::<u64>() {
+            Err(_) => ByteRange::Full,
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if size == 0 => ByteRange::Unsatisfiable,
+            Ok(n) => ByteRange::Partial(size.saturating_sub(n), size - 1)
+        };
+    }
+
+    let Ok(start) = first.parse::<u64>() else {
+        return ByteRange::Full;
+    };
+    let end = match last {
+        "" => size.saturating_sub(1),
+        last => match last.parse::<u64>() {
+            Err(_) => return ByteRange::Full,
+            Ok(end) if end < start => return ByteRange::Full,
+            Ok(end) => end.min(size.saturating_sub(1))
+        }
+    };
+
+    if start >= size {
+        return ByteRange::Unsatisfiable;
+    }
+    return ByteRange::Partial(start, end);
+}
+
+/// weak comparison as required for `If-None-Match`
+fn etag_matches(header: &str, etag: &str) -> bool {
+    return header
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+}
+
+/// whether content of the type is passive enough to be shown inline on the
+/// api origin, anything else is always downloaded
+fn inline_allowed(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    return matches!(
+        essence.as_str(),
+        "image/png" | "image/jpeg" | "image/gif" | "image/webp" | "image/bmp" | "image/tiff"
+            | "application/pdf" | "text/plain"
+    );
+}
+
+/// attachment or inline disposition, names outside of ascii are sent as
+/// `filename*` with an ascii fallback
+fn content_disposition(name: &str, inline: bool) -> header::ContentDisposition {
+    let fallback: String = name.chars()
+        .map(|c| if c.is_ascii() { c } else { '_' })
+        .collect();
+
+    let mut parameters = vec![header::DispositionParam::Filename(fallback)];
+    if !name.is_ascii() {
+        parameters.push(header::DispositionParam::FilenameExt(header::ExtendedValue {
+            charset: header::Charset::Ext(String::from("UTF-8")),
+            language_tag: None,
+            value: name.as_bytes().to_vec()
+        }));
+    }
+
+    return header::ContentDisposition {
+        disposition: if inline { header::DispositionType::Inline } else { header::DispositionType::Attachment },
+        parameters
+    };
+}
+
+
+
+#[derive(Debug, Deserialize)]
+struct FolderCreatePost {
+    folder_id: uuid::Uuid,
+    /// folders without a parent are created in the root
+    #[serde(default)]
+    parent_folder_id: uuid::Uuid,
+    name: String
+}
+
+async fn folder_create_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FolderCreatePost>
+) -> impl Responder {
+    info!("folder_create_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.folder_add(
+        &user.tenant().tenant_id(),
+        &user.user_id(),
+        &file_provider::Folder::new(
+            params.folder_id,
+            params.name.clone()
+        ).with_parent(&params.parent_folder_id)
+    ).await {
+        Err(e) => {
+            error!("error creating folder: {:?}", e);
+            return HttpResponse::from_error(ApiError::new(e, "Error creating folder"));
+        }
+        Ok(_) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("Folder created successfully"));
+        }
+    }
+}
+
+
+/// file in a folder listing, with the icon shown while it has no preview
+#[derive(Debug, Serialize)]
+struct ListedFile {
+    #[serde(flatten)]
+    file: file_provider::File,
+    icon: &'static str
+}
+
+#[derive(Debug, Deserialize)]
+struct FolderListFoldersPost {
+    folder_id: uuid::Uuid
+}
+
+async fn folder_list_folders_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FolderListFoldersPost>
+) -> impl Responder {
+    info!("folder_list_folders_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+
+    let tenant_id = user.tenant().tenant_id();
+    let user_id = user.user_id();
+    let f1 = fp.folder_list_folders(&tenant_id, &user_id, &params.folder_id);
+    let f2 = fp.folder_list_files(&tenant_id, &user_id, &params.folder_id);
 
     match futures::try_join!(f1, f2) {
         Err(e) => {
             error!("unable to fetch files and folders: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch files and folders"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch files and folders"));
         }
         Ok((folders, files)) => {
+            let files: Vec<ListedFile> = files.into_iter()
+                .map(|file| ListedFile {
+                    icon: file_provider::icon(&file.content_type),
+                    file
+                })
+                .collect();
             return HttpResponse::Ok()
                 .json(ApiResponse::new(
                     true,
@@ -271,3 +1311,1013 @@ async fn folder_list_folders_post(
         }
     }
 }
+
+
+#[derive(Debug, Deserialize)]
+struct FolderPost {
+    folder_id: uuid::Uuid
+}
+
+/// breadcrumbs of the folder, from the root down to the folder itself
+async fn folder_path_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FolderPost>
+) -> impl Responder {
+    info!("folder_path_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.folder_path(&user.tenant().tenant_id(), &user.user_id(), &params.folder_id).await {
+        Err(e) => {
+            error!("unable to fetch path of folder {}: {:?}", params.folder_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch folder path"));
+        }
+        Ok(folders) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::new(
+                    true,
+                    "successfully fetched folder path",
+                    Some(json!({
+                        "folders": folders
+                    }))
+                ));
+        }
+    }
+}
+
+
+
+/// bytes of the archive sent in one chunk
+const ARCHIVE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// why writing an archive stopped
+enum ArchiveError {
+    /// the client went away
+    Closed,
+    Failed(ProviderError)
+}
+
+impl From<ProviderError> for ArchiveError {
+    fn from(e: ProviderError) -> Self {
+        return ArchiveError::Failed(e);
+    }
+}
+
+impl From<std::io::Error> for ArchiveError {
+    fn from(e: std::io::Error) -> Self {
+        error!("unable to compress archive: {}", e);
+        return ArchiveError::Failed(ProviderError::Internal);
+    }
+}
+
+impl From<zip::result::ZipError> for ArchiveError {
+    fn from(e: zip::result::ZipError) -> Self {
+        error!("unable to write archive: {}", e);
+        return ArchiveError::Failed(ProviderError::Internal);
+    }
+}
+
+/// archive bytes the zip writer wrote and that are not sent yet
+#[derive(Clone, Default)]
+struct ArchiveBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl std::io::Write for ArchiveBuffer {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(data);
+        return Ok(data.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return Ok(());
+    }
+}
+
+type ArchiveWriter = zip::ZipWriter<zip::write::StreamWriter<ArchiveBuffer>>;
+
+/// archive bytes collected into chunks of about `ARCHIVE_CHUNK_SIZE`
+struct ArchiveOutput {
+    sender: futures::channel::mpsc::Sender<Result<web::Bytes, ProviderError>>,
+    buffer: ArchiveBuffer
+}
+
+impl ArchiveOutput {
+
+    /// a zip writer writing into the buffer, which the sent chunks are
+    /// taken from
+    fn writer(&self) -> ArchiveWriter {
+        return zip::ZipWriter::new_stream(self.buffer.clone());
+    }
+
+    /// sends what was written once it makes up a chunk
+    async fn send(&mut self) -> Result<(), ArchiveError> {
+        if self.buffer.0.borrow().len() >= ARCHIVE_CHUNK_SIZE {
+            self.flush().await?;
+        }
+        return Ok(());
+    }
+
+    async fn flush(&mut self) -> Result<(), ArchiveError> {
+        use futures::SinkExt;
+
+        let chunk = web::Bytes::from(std::mem::take(&mut *self.buffer.0.borrow_mut()));
+        return self.sender.send(Ok(chunk)).await
+            .map_err(|_| ArchiveError::Closed);
+    }
+}
+
+/// the folder and everything below it the user may see as a zip archive,
+/// written while it is sent; files without content are left out
+async fn folder_download_get(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    store: web::Data<Arc<dyn file_store::FileStore>>,
+    user: user::User,
+    path: web::Path<uuid::Uuid>
+) -> impl Responder {
+    info!("folder_download_get");
+
+    let tenant_id = user.tenant().tenant_id();
+    let user_id = user.user_id();
+    let folder_id = path.into_inner();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let folder = match fp.folder_get(&tenant_id, &user_id, &folder_id).await {
+        Err(e) => {
+            error!("unable to fetch folder {}: {:?}", folder_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error fetching folder"));
+        }
+        Ok(folder) => folder
+    };
+
+    let name = archive_name(&folder.name);
+    let (sender, receiver) = futures::channel::mpsc::channel(4);
+    let store = store.get_ref().clone();
+    actix_web::rt::spawn(async move {
+        let mut output = ArchiveOutput { sender, buffer: ArchiveBuffer::default() };
+        match write_archive(&fp, store.as_ref(), &tenant_id, &user_id, &folder, &mut output).await {
+            Ok(()) => {}
+            Err(ArchiveError::Closed) => {
+                debug!("download of folder {} cancelled", folder.folder_id);
+            }
+            Err(ArchiveError::Failed(e)) => {
+                use futures::SinkExt;
+
+                error!("unable to write archive of folder {}: {}", folder.folder_id, e);
+                // ends the response without the central directory, so that
+                // the archive is recognizably incomplete
+                let _ = output.sender.send(Err(e)).await;
+            }
+        }
+    });
+
+    return HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header(content_disposition(&format!("{name}.zip"), false))
+        .insert_header((header::CACHE_CONTROL, "private, no-store"))
+        .insert_header((header::CONTENT_SECURITY_POLICY, FILE_CONTENT_SECURITY_POLICY))
+        .streaming(receiver);
+}
+
+/// walks the folder depth first, subfolders that became inaccessible in the
+/// meantime are left out
+async fn write_archive(
+    fp: &file_provider_postgres::PostgresFileProvider,
+    store: &dyn file_store::FileStore,
+    tenant_id: &uuid::Uuid,
+    user_id: &uuid::Uuid,
+    folder: &file_provider::Folder,
+    output: &mut ArchiveOutput
+) -> Result<(), ArchiveError> {
+    let mut zip = output.writer();
+    let now = archive_time(&chrono::Utc::now());
+    let mut visited = std::collections::HashSet::new();
+    let mut pending = vec![(folder.folder_id, format!("{}/", archive_name(&folder.name)))];
+
+    while let Some((folder_id, prefix)) = pending.pop() {
+        if !visited.insert(folder_id) {
+            continue;
+        }
+
+        let listed = futures::try_join!(
+            fp.folder_list_folders(tenant_id, user_id, &folder_id),
+            fp.folder_list_files(tenant_id, user_id, &folder_id)
+        );
+        let (folders, files) = match listed {
+            Err(ProviderError::NotFound | ProviderError::Forbidden) if folder_id != folder.folder_id => {
+                debug!("folder {} is no longer accessible", folder_id);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+            Ok(listed) => listed
+        };
+
+        write_directory(&mut zip, &prefix, now)?;
+        output.send().await?;
+
+        let mut names = std::collections::HashSet::new();
+        for file in &files {
+            let Some(digest) = file.digest.as_deref() else {
+                continue;
+            };
+            let name = unique_name(&mut names, &archive_name(&file.name));
+            let stat = store.stat(*tenant_id, digest).await?;
+            let content = store.get(*tenant_id, digest).await?;
+            write_file(&mut zip, output, &format!("{prefix}{name}"), &file.content_type, &stat, content).await?;
+        }
+
+        // reversed, so that subfolders are written in the order listed
+        for subfolder in folders.iter().rev() {
+            let name = unique_name(&mut names, &archive_name(&subfolder.name));
+            pending.push((subfolder.folder_id, format!("{prefix}{name}/")));
+        }
+    }
+
+    zip.finish()?;
+    return output.flush().await;
+}
+
+/// adds an empty entry named `name`, ending with `/`, which readers take
+/// for a directory; `add_directory` of the streaming writer announces a data
+/// descriptor it never writes, so that strict readers see overlapping
+/// entries
+fn write_directory(zip: &mut ArchiveWriter, name: &str, modified: zip::DateTime) -> Result<(), ArchiveError> {
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored)
+        .last_modified_time(modified)
+        .unix_permissions(0o755);
+    zip.start_file(name, options)?;
+    return Ok(());
+}
+
+/// adds the content as file to the archive while sending it, content that
+/// is compressed already is stored
+async fn write_file(
+    zip: &mut ArchiveWriter,
+    output: &mut ArchiveOutput,
+    name: &str,
+    content_type: &str,
+    stat: &file_store::FileStat,
+    mut content: file_store::ByteStream<'_>
+) -> Result<(), ArchiveError> {
+    use std::io::Write;
+
+    let (method, level) = if compressible(content_type) {
+        (zip::CompressionMethod::Deflated, Some(1))
+    } else {
+        (zip::CompressionMethod::Stored, None)
+    };
+    // deflated content grows by a few bytes per block at most
+    let large = stat.size.saturating_add(stat.size / 128).saturating_add(1024) >= u64::from(u32::MAX);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(method)
+        .compression_level(level)
+        .last_modified_time(archive_time(&stat.modified))
+        .large_file(large);
+
+    zip.start_file(name, options)?;
+    output.send().await?;
+    while let Some(chunk) = content.next().await {
+        zip.write_all(&chunk?)?;
+        output.send().await?;
+    }
+    return Ok(());
+}
+
+/// modification time of an entry, archives cannot tell times before 1980
+fn archive_time(time: &chrono::DateTime<chrono::Utc>) -> zip::DateTime {
+    return zip::DateTime::try_from(time.naive_utc()).unwrap_or_default();
+}
+
+/// whether content of `content_type` is worth compressing, formats that
+/// are compressed already (images, media, archives, office documents) are
+/// stored
+fn compressible(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    return essence.starts_with("text/")
+        || essence.ends_with("+xml")
+        || essence.ends_with("+json")
+        || matches!(
+            essence.as_str(),
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/x-ndjson"
+                | "application/msword"
+                | "application/vnd.ms-excel"
+                | "application/vnd.ms-powerpoint"
+                | "application/rtf"
+                | "application/x-tar"
+        );
+}
+
+/// a name usable as path component in an archive, without separators and
+/// control characters
+fn archive_name(name: &str) -> String {
+    let name: String = name.chars()
+        .map(|c| if c == '/' || c == '\\' || c.is_control() { '_' } else { c })
+        .collect();
+    let name = name.trim();
+    return match name {
+        "" | "." | ".." => String::from("_"),
+        _ => String::from(name)
+    };
+}
+
+/// `name`, or `name (2).ext` and so on when a name of the folder differs
+/// from it only in case, since not every file system tells them apart
+fn unique_name(names: &mut std::collections::HashSet<String>, name: &str) -> String {
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > 0 => name.split_at(dot),
+        _ => (name, "")
+    };
+
+    let mut candidate = String::from(name);
+    let mut counter = 2;
+    while !names.insert(candidate.to_lowercase()) {
+        candidate = format!("{stem} ({counter}){extension}");
+        counter += 1;
+    }
+    return candidate;
+}
+
+#[derive(Debug, Deserialize)]
+struct FolderRenamePost {
+    folder_id: uuid::Uuid,
+    name: String
+}
+
+async fn folder_rename_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FolderRenamePost>
+) -> impl Responder {
+    info!("folder_rename_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.folder_rename(&user.tenant().tenant_id(), &user.user_id(), &params.folder_id, &params.name).await {
+        Err(e) => {
+            error!("error renaming folder {}: {:?}", params.folder_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error renaming folder"));
+        }
+        Ok(()) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("Folder renamed successfully"));
+        }
+    }
+}
+
+
+#[derive(Debug, Deserialize)]
+struct FolderMovePost {
+    folder_id: uuid::Uuid,
+    /// the nil uuid moves the folder to the root
+    parent_folder_id: uuid::Uuid
+}
+
+async fn folder_move_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FolderMovePost>
+) -> impl Responder {
+    info!("folder_move_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.folder_move(&user.tenant().tenant_id(), &user.user_id(), &params.folder_id, &params.parent_folder_id).await {
+        Err(e) => {
+            error!("error moving folder {}: {:?}", params.folder_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error moving folder"));
+        }
+        Ok(()) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("Folder moved successfully"));
+        }
+    }
+}
+
+
+/// moves the folder with everything in it to the trash, content stays
+/// stored until the files are deleted
+async fn folder_delete_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FolderPost>
+) -> impl Responder {
+    info!("folder_delete_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.folder_trash(&user.tenant().tenant_id(), &user.user_id(), &params.folder_id).await {
+        Err(e) => {
+            error!("error moving folder {} to the trash: {:?}", params.folder_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error deleting folder"));
+        }
+        Ok(()) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("Folder moved to the trash"));
+        }
+    }
+}
+
+
+async fn folder_restore_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FolderPost>
+) -> impl Responder {
+    info!("folder_restore_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.folder_restore(&user.tenant().tenant_id(), &user.user_id(), &params.folder_id).await {
+        Err(e) => {
+            error!("error restoring folder {}: {:?}", params.folder_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error restoring folder"));
+        }
+        Ok(()) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("Folder restored successfully"));
+        }
+    }
+}
+
+
+async fn folder_trash_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User
+) -> impl Responder {
+    info!("folder_trash_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.folder_trash_fetch(&user.tenant().tenant_id(), &user.user_id()).await {
+        Err(e) => {
+            error!("unable to fetch trashed folders: {:?}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch trashed folders"));
+        }
+        Ok(folders) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::new(
+                    true,
+                    "successfully fetched trashed folders",
+                    Some(json!({
+                        "folders": folders
+                    }))
+                ));
+        }
+    }
+}
+
+
+/// owner of the folder and who else it is shared with, grants inherited
+/// from parent folders carry the id of the folder they were made on
+async fn folder_sharing_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FolderPost>
+) -> impl Responder {
+    info!("folder_sharing_post");
+
+    let tenant_id = user.tenant().tenant_id();
+    let user_id = user.user_id();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+
+    let f1 = fp.folder_get(&tenant_id, &user_id, &params.folder_id);
+    let f2 = fp.folder_grants_fetch(&tenant_id, &user_id, &params.folder_id);
+
+    match futures::try_join!(f1, f2) {
+        Err(e) => {
+            error!("unable to fetch sharing of folder {}: {:?}", params.folder_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch folder sharing"));
+        }
+        Ok((folder, grants)) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::new(
+                    true,
+                    "successfully fetched folder sharing",
+                    Some(json!({
+                        "folder": folder,
+                        "grants": grants
+                    }))
+                ));
+        }
+    }
+}
+
+
+/// shares the folder with a role or a user, `{"folder_id": "..",
+/// "grantee": {"role": ".."}, "level": "read"}`; sharing again changes the
+/// level
+async fn folder_share_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<file_provider::FolderGrant>
+) -> impl Responder {
+    info!("folder_share_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.folder_grant_save(&user.tenant().tenant_id(), &user.user_id(), &params).await {
+        Err(e) => {
+            error!("error sharing folder {}: {:?}", params.folder_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error sharing folder"));
+        }
+        Ok(()) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("Folder shared successfully"));
+        }
+    }
+}
+
+
+#[derive(Debug, Deserialize)]
+struct FolderUnsharePost {
+    folder_id: uuid::Uuid,
+    grantee: file_provider::Grantee
+}
+
+async fn folder_unshare_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<FolderUnsharePost>
+) -> impl Responder {
+    info!("folder_unshare_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.folder_grant_remove(&user.tenant().tenant_id(), &user.user_id(), &params.folder_id, &params.grantee).await {
+        Err(e) => {
+            error!("error removing grant on folder {}: {:?}", params.folder_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error removing folder grant"));
+        }
+        Ok(()) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("Folder grant removed successfully"));
+        }
+    }
+}
+
+
+#[derive(Debug, Deserialize)]
+struct ShareCreatePost {
+    target: file_provider::ShareTarget,
+    expires: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    password: Option<String>
+}
+
+/// link to a file or folder that works without signing in until it expires
+/// or is revoked, `{"target": {"folder": ".."}, "expires": "..",
+/// "password": ".."}`; the token is only returned here
+async fn share_create_post(
+    cfg: web::Data<Arc<config::Config>>,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    tg: web::Data<Arc<token::TokenGenerator>>,
+    user: user::User,
+    params: web::Json<ShareCreatePost>
+) -> impl Responder {
+    info!("share_create_post");
+
+    let tenant_id = user.tenant().tenant_id();
+    let link_id = uuid::Uuid::new_v4();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    if let Err(e) = fp.share_link_add(&tenant_id, &user.user_id(), &link_id, &params.target, &params.expires, params.password.as_deref()).await {
+        error!("error adding share link: {:?}", e);
+        return HttpResponse::from_error(ApiError::new(e, "Error creating share link"));
+    }
+
+    let token = match tg.generate_share_token(&link_id, &tenant_id, &params.expires) {
+        Err(e) => {
+            error!("unable to generate share token: {}", e);
+            return HttpResponse::from_error(ApiError::new(ProviderError::Internal, "Error creating share link"));
+        }
+        Ok(token) => token
+    };
+
+    return HttpResponse::Ok()
+        .json(ApiResponse::new(
+            true,
+            "Share link created successfully",
+            Some(json!({
+                "link_id": link_id,
+                "expires": params.expires,
+                "url": crate::mail::link(&cfg, &format!("api/v1/share/{token}")),
+                "token": token
+            }))
+        ));
+}
+
+
+#[derive(Debug, Deserialize)]
+struct ShareListPost {
+    target: file_provider::ShareTarget
+}
+
+async fn share_list_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<ShareListPost>
+) -> impl Responder {
+    info!("share_list_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.share_links_fetch(&user.tenant().tenant_id(), &user.user_id(), &params.target).await {
+        Err(e) => {
+            error!("unable to fetch share links: {:?}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch share links"));
+        }
+        Ok(links) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::new(
+                    true,
+                    "successfully fetched share links",
+                    Some(json!({
+                        "links": links
+                    }))
+                ));
+        }
+    }
+}
+
+
+#[derive(Debug, Deserialize)]
+struct SharePost {
+    link_id: uuid::Uuid
+}
+
+async fn share_revoke_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<SharePost>
+) -> impl Responder {
+    info!("share_revoke_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.share_link_revoke(&user.tenant().tenant_id(), &user.user_id(), &params.link_id).await {
+        Err(e) => {
+            error!("error revoking share link {}: {:?}", params.link_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error revoking share link"));
+        }
+        Ok(()) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("Share link revoked successfully"));
+        }
+    }
+}
+
+
+/// who used the link and what came of it, newest first
+async fn share_accesses_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<SharePost>
+) -> impl Responder {
+    info!("share_accesses_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.share_link_accesses_fetch(&user.tenant().tenant_id(), &user.user_id(), &params.link_id).await {
+        Err(e) => {
+            error!("unable to fetch accesses of share link {}: {:?}", params.link_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch share link accesses"));
+        }
+        Ok(accesses) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::new(
+                    true,
+                    "successfully fetched share link accesses",
+                    Some(json!({
+                        "accesses": accesses
+                    }))
+                ));
+        }
+    }
+}
+
+
+#[derive(Debug, Deserialize)]
+struct AttachmentsPost {
+    entity_type: file_provider::EntityType,
+    entity_id: uuid::Uuid
+}
+
+/// files attached to a business record, e.g. `{"entity_type": "invoice",
+/// "entity_id": ".."}`
+async fn attachments_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<AttachmentsPost>
+) -> impl Responder {
+    info!("attachments_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.attachments_fetch(&user.tenant().tenant_id(), &user.user_id(), params.entity_type, &params.entity_id).await {
+        Err(e) => {
+            error!("unable to fetch attachments of {} {}: {:?}", params.entity_type.as_str(), params.entity_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch attachments"));
+        }
+        Ok(attachments) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::new(
+                    true,
+                    "successfully fetched attachments",
+                    Some(json!({
+                        "attachments": attachments
+                    }))
+                ));
+        }
+    }
+}
+
+
+#[derive(Debug, Deserialize)]
+struct AttachmentPost {
+    entity_type: file_provider::EntityType,
+    entity_id: uuid::Uuid,
+    file_id: uuid::Uuid
+}
+
+async fn attachment_add_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<AttachmentPost>
+) -> impl Responder {
+    info!("attachment_add_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.attachment_add(&user.tenant().tenant_id(), &user.user_id(), params.entity_type, &params.entity_id, &params.file_id).await {
+        Err(e) => {
+            error!("error attaching file {} to {} {}: {:?}", params.file_id, params.entity_type.as_str(), params.entity_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error attaching file"));
+        }
+        Ok(()) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("File attached successfully"));
+        }
+    }
+}
+
+
+async fn attachment_remove_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<AttachmentPost>
+) -> impl Responder {
+    info!("attachment_remove_post");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.attachment_remove(&user.tenant().tenant_id(), &user.user_id(), params.entity_type, &params.entity_id, &params.file_id).await {
+        Err(e) => {
+            error!("error detaching file {} from {} {}: {:?}", params.file_id, params.entity_type.as_str(), params.entity_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error detaching file"));
+        }
+        Ok(()) => {
+            return HttpResponse::Ok()
+                .json(ApiResponse::ok("File detached successfully"));
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the content type the checked upload ends with, or why it was rejected
+    async fn check_upload(
+        limits: &UploadLimits,
+        chunks: Vec<&'static [u8]>,
+        declared: &str
+    ) -> Result<String, Rejection> {
+        let check = RefCell::new(UploadCheck::default());
+        let mut upload = CheckedUpload {
+            data: Box::pin(futures::stream::iter(chunks.into_iter().map(|c| Ok(web::Bytes::from_static(c))))),
+            limits,
+            declared: String::from(declared),
+            read: 0,
+            head: vec![],
+            check: &check
+        };
+        while let Some(chunk) = upload.next().await {
+            if chunk.is_err() {
+                break;
+            }
+        }
+        drop(upload);
+
+        let check = check.into_inner();
+        return match check.rejection {
+            Some(rejection) => Err(rejection),
+            None => Ok(check.content_type.unwrap_or_default())
+        };
+    }
+
+    #[actix_web::test]
+    async fn test_checked_upload() {
+        let limits = UploadLimits {
+            config: config::UploadConfig {
+                max_size: 1024,
+                default_quota: 0,
+                allowed_types: vec![String::from("application/pdf"), String::from("text/*")]
+            },
+            remaining: Some(600)
+        };
+
+        assert_eq!(check_upload(&limits, vec![b"%PDF-", b"1.7"], "application/octet-stream").await, Ok(String::from("application/pdf")));
+        assert_eq!(check_upload(&limits, vec![b"a;b\n"], "text/csv").await, Ok(String::from("text/csv")));
+        assert_eq!(check_upload(&limits, vec![], "application/pdf").await, Ok(String::from("text/plain")));
+
+        // the type is sniffed, whatever the client declares
+        assert_eq!(check_upload(&limits, vec![b"MZ\x90\x00"], "application/pdf").await, Err(Rejection::Type));
+
+        // the type is checked as soon as the head is complete
+        assert_eq!(check_upload(&limits, vec![&[0; 512], &[0; 1024]], "application/pdf").await, Err(Rejection::Type));
+
+        assert_eq!(check_upload(&limits, vec![b"%PDF-", &[b'a'; 600]], "").await, Err(Rejection::OverQuota));
+        let limits = UploadLimits { remaining: None, ..limits };
+        assert_eq!(check_upload(&limits, vec![b"%PDF-", &[b'a'; 1020]], "").await, Err(Rejection::TooLarge));
+        assert!(check_upload(&limits, vec![b"%PDF-", &[b'a'; 1019]], "").await.is_ok());
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("report.pdf"), "report.pdf");
+        assert_eq!(sanitize_file_name("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_file_name("C:\\Users\\me\\notes.txt"), "notes.txt");
+        assert_eq!(sanitize_file_name("bad\nname.txt"), "badname.txt");
+        assert_eq!(sanitize_file_name("dir/"), "");
+    }
+
+    #[test]
+    fn test_byte_range() {
+        assert_eq!(byte_range(None, 100), ByteRange::Full);
+        assert_eq!(byte_range(Some("bytes=0-9"), 100), ByteRange::Partial(0, 9));
+        assert_eq!(byte_range(Some("bytes=90-"), 100), ByteRange::Partial(90, 99));
+        assert_eq!(byte_range(Some("bytes=-10"), 100), ByteRange::Partial(90, 99));
+        assert_eq!(byte_range(Some("bytes=-500"), 100), ByteRange::Partial(0, 99));
+        assert_eq!(byte_range(Some("bytes=50-500"), 100), ByteRange::Partial(50, 99));
+
+        assert_eq!(byte_range(Some("bytes=100-"), 100), ByteRange::Unsatisfiable);
+        assert_eq!(byte_range(Some("bytes=-0"), 100), ByteRange::Unsatisfiable);
+        assert_eq!(byte_range(Some("bytes=0-"), 0), ByteRange::Unsatisfiable);
+
+        // ignored
+        assert_eq!(byte_range(Some("bytes=0-1,5-6"), 100), ByteRange::Full);
+        assert_eq!(byte_range(Some("bytes=9-0"), 100), ByteRange::Full);
+        assert_eq!(byte_range(Some("items=0-9"), 100), ByteRange::Full);
+        assert_eq!(byte_range(Some("bytes=a-b"), 100), ByteRange::Full);
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = "\"b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\"";
+
+        assert!(etag_matches(etag, etag));
+        assert!(etag_matches(&format!("\"other\", W/{etag}"), etag));
+        assert!(etag_matches("*", etag));
+        assert!(!etag_matches("\"other\"", etag));
+    }
+
+    #[test]
+    fn test_archive_name() {
+        assert_eq!(archive_name("Q3/Q4 report.pdf"), "Q3_Q4 report.pdf");
+        assert_eq!(archive_name("..\\evil\n"), ".._evil_");
+        assert_eq!(archive_name(" .. "), "_");
+        assert_eq!(archive_name(""), "_");
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let mut names = std::collections::HashSet::new();
+        assert_eq!(unique_name(&mut names, "Report.pdf"), "Report.pdf");
+        assert_eq!(unique_name(&mut names, "report.PDF"), "report (2).PDF");
+        assert_eq!(unique_name(&mut names, "Report.pdf"), "Report (3).pdf");
+        assert_eq!(unique_name(&mut names, ".profile"), ".profile");
+        assert_eq!(unique_name(&mut names, ".profile"), ".profile (2)");
+        assert_eq!(unique_name(&mut names, "Photos"), "Photos");
+        assert_eq!(unique_name(&mut names, "photos"), "photos (2)");
+    }
+
+    #[test]
+    fn test_compressible() {
+        assert!(compressible("text/plain; charset=utf-8"));
+        assert!(compressible("image/svg+xml"));
+        assert!(compressible("application/msword"));
+        assert!(!compressible("image/jpeg"));
+        assert!(!compressible("application/vnd.openxmlformats-officedocument.wordprocessingml.document"));
+        assert!(!compressible("application/zip"));
+    }
+
+    #[actix_web::test]
+    async fn test_write_file() {
+        use std::io::Read;
+
+        let (sender, receiver) = futures::channel::mpsc::channel(64);
+        let mut output = ArchiveOutput { sender, buffer: ArchiveBuffer::default() };
+        let mut zip = output.writer();
+
+        let modified = chrono::DateTime::parse_from_rfc3339("2026-10-18T12:34:56Z").unwrap_or_default().to_utc();
+        assert!(write_directory(&mut zip, "Reports/", archive_time(&modified)).is_ok());
+
+        let text = [b"quarterly ".as_slice(), &b"numbers ".repeat(100)].concat();
+        let files = [
+            ("Reports/summary.txt", "text/plain", text),
+            ("Reports/photo \u{2013} \u{fc}.jpg", "image/jpeg", [b"\xff\xd8\xff\xe0".as_slice(), &vec![7; 100 * 1024]].concat()),
+            ("Reports/empty.txt", "text/plain", Vec::new()),
+        ];
+        for (name, content_type, content) in &files {
+            let stat = file_store::FileStat { digest: String::new(), size: content.len() as u64, modified };
+            let data = web::Bytes::from(content.clone());
+            let content: file_store::ByteStream = Box::pin(futures::stream::once(async move {
+                return Ok(data);
+            }));
+            assert!(write_file(&mut zip, &mut output, name, content_type, &stat, content).await.is_ok());
+        }
+        assert!(zip.finish().is_ok());
+        assert!(output.flush().await.is_ok());
+        drop(output);
+
+        // the archive is sent in chunks while it is written
+        let chunks: Vec<_> = receiver.collect().await;
+        assert!(chunks.len() > 1);
+        let archive: Vec<u8> = chunks.into_iter().flat_map(|c| c.unwrap_or_default().to_vec()).collect();
+
+        let archive = zip::ZipArchive::new(std::io::Cursor::new(archive));
+        assert!(archive.is_ok(), "unable to read archive");
+        let Ok(mut archive) = archive else {
+            return;
+        };
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names, vec!["Reports/", "Reports/summary.txt", "Reports/photo \u{2013} \u{fc}.jpg", "Reports/empty.txt"]);
+        for (name, content_type, content) in &files {
+            let mut read = Vec::new();
+            let entry = archive.by_name(name);
+            assert!(entry.is_ok_and(|mut entry| {
+                let method = if *content_type == "text/plain" { zip::CompressionMethod::Deflated } else { zip::CompressionMethod::Stored };
+                return entry.compression() == method
+                    && entry.last_modified().is_some_and(|m| (m.year(), m.second()) == (2026, 56))
+                    && entry.read_to_end(&mut read).is_ok();
+            }), "{name}");
+            assert_eq!(&read, content, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_content_disposition() {
+        assert_eq!(
+            content_disposition("report.pdf", false).to_string(),
+            "attachment; filename=\"report.pdf\""
+        );
+        assert_eq!(
+            content_disposition("\u{fc}bersicht.pdf", true).to_string(),
+            "inline; filename=\"_bersicht.pdf\"; filename*=UTF-8''%C3%BCbersicht.pdf"
+        );
+    }
+
+    #[test]
+    fn test_inline_allowed() {
+        assert!(inline_allowed("image/png"));
+        assert!(inline_allowed("application/pdf"));
+        assert!(inline_allowed("Text/Plain; charset=utf-8"));
+        assert!(!inline_allowed("text/html"));
+        assert!(!inline_allowed("image/svg+xml"));
+        assert!(!inline_allowed("application/xhtml+xml"));
+        assert!(!inline_allowed(""));
+    }
+
+    #[actix_web::test]
+    async fn test_serve_file() {
+        use file_store::FileStore;
+
+        let dir = std::env::temp_dir().join(format!("serve_test_{}", uuid::Uuid::new_v4().simple()));
+        let store = file_store_local::LocalFileStore::new(&dir.to_string_lossy());
+        let tenant_id = uuid::Uuid::new_v4();
+
+        let content: file_store::ByteStream = Box::pin(futures::stream::once(async {
+            return Ok(web::Bytes::from_static(b"<script>alert(document.cookie)</script>"));
+        }));
+        let stat = store.put(tenant_id, content).await;
+        assert!(stat.is_ok(), "unable to store content");
+        let digest = stat.map(|s| s.digest).unwrap_or_default();
+
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        for (content_type, disposition) in [("text/html", "attachment"), ("text/plain", "inline")] {
+            let file = file_provider::File::new(uuid::Uuid::new_v4(), String::from("page.html"))
+                .with_content(content_type, 39, &digest);
+            let response = serve_file(&req, &store, tenant_id, &file, true).await;
+            assert_eq!(response.status(), http::StatusCode::OK);
+
+            let header = |name: header::HeaderName| {
+                return response.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+            };
+            assert!(header(header::CONTENT_DISPOSITION).starts_with(disposition), "{content_type}");
+            assert_eq!(header(header::CONTENT_SECURITY_POLICY), FILE_CONTENT_SECURITY_POLICY);
+            assert_eq!(header(header::X_CONTENT_TYPE_OPTIONS), "nosniff");
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_snippet_html() {
+        assert_eq!(
+            snippet_html("Q3 \u{2}sales\u{3} <up> & \"rising\""),
+            "Q3 <mark>sales</mark> &lt;up&gt; &amp; &quot;rising&quot;"
+        );
+        assert_eq!(snippet_html(""), "");
+    }
+}
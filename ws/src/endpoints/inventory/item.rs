@@ -9,7 +9,7 @@ use actix_web::{HttpResponse, Responder, http, web};
 
 use crate::{
     classes::user,
-    endpoints::{ApiResponse, default_option_response},
+    endpoints::{ApiError, ApiResponse, default_option_response},
 };
 
 use inv_provider::ItemProvider;
@@ -77,8 +77,7 @@ async fn item_save_post(
     match ipp.item_save(&tenant_id, &item).await {
         Err(e) => {
             error!("unable to save item: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("Unable to save item"));
+            return HttpResponse::from_error(ApiError::new(e, "Unable to save item"));
         }
         Ok(_) => {
             return HttpResponse::Ok().json(ApiResponse::ok("Item saved successfully"));
@@ -106,8 +105,7 @@ async fn items_fetch_post(
     match ipp.items_fetch(&tenant_id, &filter).await {
         Err(e) => {
             error!("unable to fetch items: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("Unable to fetch items"));
+            return HttpResponse::from_error(ApiError::new(e, "Unable to fetch items"));
         }
         Ok(items) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -9,7 +9,7 @@ use actix_web::{HttpResponse, Responder, http, web};
 
 use crate::{
     classes::user,
-    endpoints::{ApiResponse, default_option_response},
+    endpoints::{ApiError, ApiResponse, default_option_response},
 };
 
 use inv_provider::{Location, LocationsProvider};
@@ -50,8 +50,7 @@ async fn location_save_post(
     {
         Err(e) => {
             error!("unable to save warehouse: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("Unable to save location"));
+            return HttpResponse::from_error(ApiError::new(e, "Unable to save location"));
         }
         Ok(_) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -87,8 +86,7 @@ async fn locations_fetch_post(
     {
         Err(e) => {
             error!("unable to fetch locations: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("Unable to fetch locations"));
+            return HttpResponse::from_error(ApiError::new(e, "Unable to fetch locations"));
         }
         Ok(locations) => {
             return HttpResponse::Ok().json(ApiResponse::new(
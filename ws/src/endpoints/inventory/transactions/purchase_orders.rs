@@ -9,9 +9,11 @@ use actix_web::{HttpResponse, Responder, http, web};
 
 use crate::{
     classes::user,
-    endpoints::{ApiResponse, default_option_response},
+    endpoints::{ApiError, ApiResponse, default_option_response},
+    middleware::permissions::Permission,
 };
 
+use file_provider::FileProvider;
 use inv_provider::transactions::purchase_order::PurchaseOrderProvider;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -19,6 +21,12 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         web::resource("save")
             .route(web::method(http::Method::OPTIONS).to(default_option_response))
             .route(web::post().to(purchase_order_save_post)),
+    )
+    .service(
+        web::resource("fetch/id")
+            .wrap(Permission::new("inventory.purchase_orders.fetch"))
+            .route(web::method(http::Method::OPTIONS).to(default_option_response))
+            .route(web::post().to(purchase_order_fetch_id_post)),
     );
 }
 
@@ -43,8 +51,55 @@ async fn purchase_order_save_post(
     match ppp.save(&tenant_id, &params.purchase_order).await {
         Err(e) => {
             error!("unable to save purchase order: {}", e);
-            return HttpResponse::InternalServerError().json(ApiResponse::error(e));
+            return HttpResponse::from_error(ApiError::new(e, "unable to save purchase order"));
         }
         Ok(_) => HttpResponse::Ok().json(ApiResponse::ok("purchase order saved successfully")),
     }
 }
+
+#[derive(Debug, Deserialize)]
+struct PurchaseOrderFetchIdPost {
+    po_id: uuid::Uuid,
+}
+
+async fn purchase_order_fetch_id_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<PurchaseOrderFetchIdPost>,
+) -> impl Responder {
+    info!("purchase_order_fetch_id_post");
+
+    let ppp =
+        inv_provider_postgres::transactions::purchase_order::PurchaseOrderProviderPostgres::new(
+            &dp,
+        );
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+
+    let tenant_id = user.tenant().tenant_id();
+    let user_id = user.user_id();
+
+    let f1 = ppp.fetch_by_id(&tenant_id, &params.po_id);
+    let f2 = fp.attachment_count(
+        &tenant_id,
+        &user_id,
+        file_provider::EntityType::PurchaseOrder,
+        &params.po_id,
+    );
+
+    match futures::try_join!(f1, f2) {
+        Err(e) => {
+            error!("unable to fetch purchase order: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch purchase order"));
+        }
+        Ok((purchase_order, attachment_count)) => {
+            return HttpResponse::Ok().json(ApiResponse::new(
+                true,
+                "successfully fetched purchase order",
+                Some(json!({
+                    "purchase_order": purchase_order,
+                    "attachment_count": attachment_count
+                })),
+            ));
+        }
+    }
+}
@@ -9,7 +9,7 @@ use actix_web::{HttpResponse, Responder, http, web};
 
 use crate::{
     classes::user,
-    endpoints::{ApiResponse, default_option_response},
+    endpoints::{ApiError, ApiResponse, default_option_response},
 };
 
 use inv_provider::WarehouseProvider;
@@ -80,8 +80,7 @@ async fn warehouse_save_post(
     {
         Err(e) => {
             error!("unable to save warehouse: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("Unable to save warehouse"));
+            return HttpResponse::from_error(ApiError::new(e, "Unable to save warehouse"));
         }
         Ok(_) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -113,8 +112,7 @@ async fn warehouses_fetch_post(
     match provider.warehouses_fetch(&tenant_id, &filter).await {
         Err(e) => {
             error!("unable to fetch warehouses: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("Unable to fetch warehouses"));
+            return HttpResponse::from_error(ApiError::new(e, "Unable to fetch warehouses"));
         }
         Ok(warehouses) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -7,19 +7,26 @@ pub mod inventory;
 pub mod organizations;
 pub mod permissions;
 pub mod session;
+pub mod share;
+pub mod tus;
 pub mod user;
+pub mod well_known;
 
 use tracing::info;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use actix_web::{HttpResponse, Responder, http};
+use actix_web::{HttpResponse, Responder, ResponseError, http};
+
+use provider_error::ProviderError;
 
 #[derive(Serialize, Deserialize)]
 pub struct ApiResponse {
     success: bool,
     message: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
     data: Option<Value>,
 }
 
@@ -28,6 +35,7 @@ impl ApiResponse {
         return Self {
             success,
             message,
+            code: None,
             data: data.clone(),
         };
     }
@@ -36,6 +44,7 @@ impl ApiResponse {
         return Self {
             success: true,
             message,
+            code: None,
             data: None,
         };
     }
@@ -44,9 +53,68 @@ impl ApiResponse {
         return Self {
             success: false,
             message,
+            code: None,
             data: None,
         };
     }
+
+    /// error response carrying the provider error code, validation errors
+    /// include the offending field and the reason in `data`
+    pub fn provider_error(error: &ProviderError, message: &'static str) -> Self {
+        let data = match error {
+            ProviderError::Validation { field, reason } => Some(serde_json::json!({
+                "field": field,
+                "reason": reason
+            })),
+            _ => None,
+        };
+
+        return Self {
+            success: false,
+            message,
+            code: Some(error.code()),
+            data,
+        };
+    }
+}
+
+/// provider error paired with the message shown to the client, responds
+/// with the status matching the error
+#[derive(Debug)]
+pub struct ApiError {
+    error: ProviderError,
+    message: &'static str,
+}
+
+impl ApiError {
+    pub fn new(error: ProviderError, message: &'static str) -> Self {
+        return Self { error, message };
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}: {}", self.message, self.error);
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> http::StatusCode {
+        return match self.error {
+            ProviderError::NotFound => http::StatusCode::NOT_FOUND,
+            ProviderError::Conflict => http::StatusCode::CONFLICT,
+            ProviderError::Validation { .. } => http::StatusCode::BAD_REQUEST,
+            ProviderError::Forbidden => http::StatusCode::FORBIDDEN,
+            ProviderError::Throttled => http::StatusCode::TOO_MANY_REQUESTS,
+            ProviderError::Unavailable => http::StatusCode::SERVICE_UNAVAILABLE,
+            ProviderError::Internal => http::StatusCode::INTERNAL_SERVER_ERROR,
+        };
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        return HttpResponse::build(self.status_code())
+            .json(ApiResponse::provider_error(&self.error, self.message));
+    }
 }
 
 pub async fn default_option_response() -> impl Responder {
@@ -60,3 +128,46 @@ pub async fn default_option_response() -> impl Responder {
         // ))
         .finish();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_error_status() {
+        let cases = vec![
+            (ProviderError::NotFound, http::StatusCode::NOT_FOUND),
+            (ProviderError::Conflict, http::StatusCode::CONFLICT),
+            (
+                ProviderError::validation("email", "is required"),
+                http::StatusCode::BAD_REQUEST,
+            ),
+            (ProviderError::Forbidden, http::StatusCode::FORBIDDEN),
+            (ProviderError::Throttled, http::StatusCode::TOO_MANY_REQUESTS),
+            (ProviderError::Unavailable, http::StatusCode::SERVICE_UNAVAILABLE),
+            (ProviderError::Internal, http::StatusCode::INTERNAL_SERVER_ERROR),
+        ];
+
+        for (error, status) in cases {
+            assert_eq!(ApiError::new(error, "failed").status_code(), status);
+        }
+    }
+
+    #[test]
+    fn test_provider_error_response_body() {
+        let response =
+            ApiResponse::provider_error(&ProviderError::validation("email", "is required"), "failed");
+        let value = serde_json::to_value(&response);
+        assert!(value.is_ok(), "unable to serialize response");
+        let value = value.unwrap_or_default();
+
+        assert_eq!(value["code"], "validation");
+        assert_eq!(value["data"]["field"], "email");
+        assert_eq!(value["data"]["reason"], "is required");
+
+        let value = serde_json::to_value(ApiResponse::error("failed"));
+        assert!(value.is_ok(), "unable to serialize response");
+        let value = value.unwrap_or_default();
+        assert!(value.get("code").is_none());
+    }
+}
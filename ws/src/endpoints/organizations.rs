@@ -10,7 +10,7 @@ use actix_web::{HttpResponse, Responder, http, web};
 
 use crate::{
     classes::user,
-    endpoints::{ApiResponse, default_option_response},
+    endpoints::{ApiError, ApiResponse, default_option_response},
 };
 
 use tenants_provider::organizations::OrganizationsProvider;
@@ -58,8 +58,7 @@ async fn organizations_fetch_post(
     match opp.fetch(&tenant_id, &filter).await {
         Err(e) => {
             error!("unable to fetch organizations: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch organizations"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch organizations"));
         }
         Ok(organizations) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -86,8 +85,7 @@ async fn organizations_fetch_tree_post(
     match opp.fetch_tree(&tenant_id).await {
         Err(e) => {
             error!("unable to fetch organizations: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch organizations"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch organizations"));
         }
         Ok(organizations) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -120,8 +118,7 @@ async fn organizations_fetch_id_post(
     match opp.fetch_by_id(&params.org_id).await {
         Err(e) => {
             error!("unable to fetch organizations: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch organizations"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch organizations"));
         }
         Ok(organization) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -168,8 +165,7 @@ async fn organization_save_post(
     {
         Err(e) => {
             error!("unable to save organization: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to save organization"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to save organization"));
         }
         Ok(_) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -23,6 +23,7 @@ use actix_web::{
 
 
 use crate::endpoints::{
+    ApiError,
     ApiResponse,
     default_option_response
 };
@@ -64,8 +65,7 @@ async fn permissions_fetch_post(
     ).await {
         Err(e) => {
             error!("unable to fetch permissions: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch permissions"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch permissions"));
         }
         Ok(permissions) => {
             return HttpResponse::Ok()
@@ -4,7 +4,10 @@ use tracing::{
     debug
 };
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::Arc
+};
 use serde::{
     Serialize,
     Deserialize
@@ -17,9 +20,11 @@ use actix_web::{
     dev::ConnectionInfo,
     http,
     web,
+    HttpRequest,
     HttpResponse,
     Responder
 };
+use rand::RngExt;
 
 
 use crate::{
@@ -29,17 +34,19 @@ use crate::{
         permission
     },
     endpoints::{
+        ApiError,
         ApiResponse,
         default_option_response
     }
 };
 
-use auth_provider::AuthProvider;
+use auth_provider::{AuthProvider, reset::PasswordResetProvider, tokens::TokensProvider};
 use users_provider::UsersProvider;
 use tenants_provider::TenantsProvider;
 
 
 
+const RESET_TOKEN_LENGTH: usize = 32;
 
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -64,11 +71,97 @@ pub fn config(cfg: &mut web::ServiceConfig) {
          .route(web::method(http::Method::OPTIONS).to(default_option_response))
          .route(web::post().guard(guard::Header("content-type", "application/json")).to(user_session_tenants_fetch_post))
         )
+        .service(
+            web::resource("refresh")
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(user_session_refresh_post))
+        )
+        .service(
+            web::resource("sign-out")
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(user_session_signout_post))
+        )
+        .service(
+            web::resource("password/reset")
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(user_session_password_reset_post))
+        )
+        .service(
+            web::resource("password/reset/confirm")
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(user_session_password_reset_confirm_post))
+        )
+        .service(
+            web::resource("password/change")
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::post().guard(guard::Header("content-type", "application/json")).to(user_session_password_change_post))
+        )
     ;
 }
 
 
 
+/// the configured rules for new passwords
+fn password_policy(cfg: &config::Config) -> auth_provider::password::Policy {
+    let password = cfg.password();
+    return auth_provider::password::Policy {
+        min_length: password.min_length,
+        max_length: password.max_length
+    };
+}
+
+/// the auth provider, hashing passwords with the configured parameters
+pub(crate) fn auth_provider(
+    dp: &database_provider::DatabaseProvider,
+    cfg: &config::Config
+) -> auth_provider_postgres::PostgresAuthProvider {
+    let password = cfg.password();
+    return auth_provider_postgres::PostgresAuthProvider::new(dp)
+        .with_password_params(auth_provider::password::Params {
+            memory_cost: password.memory_cost,
+            time_cost: password.time_cost,
+            parallelism: password.parallelism
+        })
+        .with_password_policy(password_policy(cfg));
+}
+
+
+/// generate an access token issued at `issued` and start a new refresh
+/// token family for it
+async fn issue_tokens(
+    dp: &database_provider::DatabaseProvider,
+    tg: &token::TokenGenerator,
+    user_id: &uuid::Uuid,
+    tenant_id: &uuid::Uuid,
+    user_name: &str,
+    email: &str,
+    issued: &chrono::DateTime<chrono::Utc>
+) -> Option<token::TokenPair> {
+    let pair = match tg.generate_pair(user_id, tenant_id, user_name, email, issued) {
+        Err(e) => {
+            error!("unable to generate token: {}", e);
+            return None;
+        }
+        Ok(pair) => pair,
+    };
+
+    let tkp = auth_provider_postgres::tokens::PostgresTokensProvider::new(dp);
+    if let Err(e) = tkp.refresh_token_add(
+        &uuid::Uuid::new_v4(),
+        user_id,
+        tenant_id,
+        &pair.refresh_token,
+        &pair.refresh_expires
+    ).await {
+        error!("unable to save refresh token: {}", e);
+        return None;
+    }
+
+    return Some(pair);
+}
+
+
+
 #[derive(Debug, Deserialize)]
 struct UserSessionSignInPost {
     email: String,
@@ -79,14 +172,14 @@ struct UserSessionSignInPost {
 
 async fn user_session_signin_post(
     info: ConnectionInfo,
-    // config: web::Data<Arc<config::Config>>,
+    cfg: web::Data<Arc<config::Config>>,
     dp: web::Data<Arc<database_provider::DatabaseProvider>>,
     tg: web::Data<Arc<token::TokenGenerator>>,
     params: web::Json<UserSessionSignInPost>
 ) -> impl Responder {
     info!("user_session_signin_post");
 
-    let ap = auth_provider_postgres::PostgresAuthProvider::new(&dp);
+    let ap = auth_provider(&dp, &cfg);
     let authentic = match ap.authenticate_by_password(
         &params.email,
         &params.pw
@@ -101,6 +194,7 @@ async fn user_session_signin_post(
     };
 
     let mut rb = HttpResponse::Ok();
+    let mut tokens = None;
 
     if authentic {
         let up = users_provider_postgres::PostgresUsersProvider::new(&dp);
@@ -126,18 +220,17 @@ async fn user_session_signin_post(
             //     &params.email
             // );
 
-            match tg.generate(
+            if let Some(pair) = issue_tokens(
+                &dp,
+                &tg,
                 &user.user_id,
                 &uuid::Uuid::nil(),
                 &params.email,
-                &params.email
-            ) {
-                Err(e) => {
-                    error!("unable to generate token: {}", e);
-                }
-                Ok(token) => {
-                    rb.append_header((http::header::AUTHORIZATION, format!("Bearer {}", token)));
-                }
+                &params.email,
+                &chrono::Utc::now()
+            ).await {
+                rb.append_header((http::header::AUTHORIZATION, format!("Bearer {}", pair.access_token)));
+                tokens = Some(json!(pair));
             }
         }
     }
@@ -146,7 +239,7 @@ async fn user_session_signin_post(
     let response = rb.json(ApiResponse::new(
         authentic,
         if authentic { "user is authentic" } else { "user/password is not correct" },
-        None
+        tokens
     ));
 
     return response;
@@ -186,8 +279,8 @@ async fn user_session_user_post(
 
     match futures::future::try_join4(f1, f2, f3, f4).await {
     	Err(e) => {
-    		return HttpResponse::InternalServerError()
-    			.json(ApiResponse::error(e));
+    		error!("unable to fetch session details: {}", e);
+    		return HttpResponse::from_error(ApiError::new(e, "unable to fetch session details"));
     	}
     	Ok((user, tenant, tenants, permissions)) => {
      		let t = tenant::Tenant::new(
@@ -262,6 +355,7 @@ async fn user_session_tenant_set_post(
     info!("user_session_tenant_set_post");
 
     let mut rb = HttpResponse::Ok();
+    let mut tokens = None;
 
     if user.is_authenticated() {
         let tp = tenants_provider_postgres::PostgresTenantsProvider::new(&dp);
@@ -274,18 +368,17 @@ async fn user_session_tenant_set_post(
             //     &user.email()
             // );
 
-            match tg.generate(
+            if let Some(pair) = issue_tokens(
+                &dp,
+                &tg,
                 &user.user_id(),
                 &new_tenant.tenant_id(),
                 &user.name(),
-                &user.email()
-            ) {
-                Err(e) => {
-                    error!("unable to generate token: {}", e);
-                }
-                Ok(token) => {
-                    rb.append_header((http::header::AUTHORIZATION, format!("Bearer {}", token)));
-                }
+                &user.email(),
+                &chrono::Utc::now()
+            ).await {
+                rb.append_header((http::header::AUTHORIZATION, format!("Bearer {}", pair.access_token)));
+                tokens = Some(json!(pair));
             }
         }
     }
@@ -293,7 +386,7 @@ async fn user_session_tenant_set_post(
     let response = rb.json(ApiResponse::new(
         true,
         "switched to tenant",
-        None
+        tokens
     ));
 
     return response;
@@ -312,8 +405,7 @@ async fn user_session_tenants_fetch_post(
     match tp.tenant_user_tenants_fetch(&user_id).await {
 	    Err(e) => {
 	        error!("unable to fetch tenants: {}", e);
-			return HttpResponse::InternalServerError()
-				.json(ApiResponse::error("unable to fetch tenants"));
+			return HttpResponse::from_error(ApiError::new(e, "unable to fetch tenants"));
 	    }
         Ok(tenants) => {
             return HttpResponse::Ok()
@@ -327,3 +419,640 @@ async fn user_session_tenants_fetch_post(
         }
     }
 }
+
+
+
+#[derive(Debug, Deserialize)]
+struct UserSessionRefreshPost {
+    refresh_token: String
+}
+
+async fn user_session_refresh_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    tg: web::Data<Arc<token::TokenGenerator>>,
+    params: web::Json<UserSessionRefreshPost>
+) -> impl Responder {
+    info!("user_session_refresh_post");
+
+    let (refresh_token, refresh_expires) = match tg.generate_refresh_token() {
+        Err(e) => {
+            error!("unable to generate refresh token: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::error("unable to refresh session"));
+        }
+        Ok(r) => r,
+    };
+
+    let tkp = auth_provider_postgres::tokens::PostgresTokensProvider::new(&dp);
+    let session = match tkp.refresh_token_rotate(
+        &params.refresh_token,
+        &refresh_token,
+        &refresh_expires
+    ).await {
+        Err(provider_error::ProviderError::NotFound) => {
+            debug!("refresh token is invalid, expired or already used");
+            return HttpResponse::Unauthorized()
+                .json(ApiResponse::error("refresh token is invalid or expired"));
+        }
+        Err(e) => {
+            error!("unable to rotate refresh token: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to refresh session"));
+        }
+        Ok(session) => session,
+    };
+
+    let up = users_provider_postgres::PostgresUsersProvider::new(&dp);
+    let user = match up.fetch_by_id(&session.user_id).await {
+        Err(e) => {
+            error!("unable to fetch user: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to refresh session"));
+        }
+        Ok(user) => user,
+    };
+
+    let access_token = match tg.generate(
+        &session.user_id,
+        &session.tenant_id,
+        &user.email,
+        &user.email
+    ) {
+        Err(e) => {
+            error!("unable to generate token: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::error("unable to refresh session"));
+        }
+        Ok(token) => token,
+    };
+
+    let pair = token::TokenPair {
+        access_token,
+        expires_in: tg.lifetime(),
+        refresh_token,
+        refresh_expires
+    };
+
+    return HttpResponse::Ok()
+        .append_header((http::header::AUTHORIZATION, format!("Bearer {}", pair.access_token)))
+        .json(ApiResponse::new(
+            true,
+            "session refreshed",
+            Some(json!(pair))
+        ));
+}
+
+
+
+#[derive(Debug, Deserialize)]
+struct UserSessionSignOutPost {
+    refresh_token: String
+}
+
+/// revoke the refresh token family of the session and deny the access
+/// token used for the request until it expires
+async fn user_session_signout_post(
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    params: web::Json<UserSessionSignOutPost>
+) -> impl Responder {
+    info!("user_session_signout_post");
+
+    if !user.is_authenticated() {
+        return HttpResponse::Unauthorized()
+            .json(ApiResponse::error("not signed in"));
+    }
+
+    let Some(token_expires) = chrono::DateTime::from_timestamp(user.token_expires(), 0) else {
+        error!("invalid access token expiry: {}", user.token_expires());
+        return HttpResponse::InternalServerError()
+            .json(ApiResponse::error("unable to sign out"));
+    };
+
+    let user_id = user.user_id();
+    let token_id = user.token_id();
+    let tkp = auth_provider_postgres::tokens::PostgresTokensProvider::new(&dp);
+
+    let f1 = tkp.refresh_family_revoke(&user_id, &params.refresh_token);
+    let f2 = tkp.access_token_deny(&token_id, &token_expires);
+
+    if let Err(e) = futures::try_join!(f1, f2) {
+        error!("unable to sign out: {}", e);
+        return HttpResponse::from_error(ApiError::new(e, "unable to sign out"));
+    }
+
+    return HttpResponse::Ok()
+        .json(ApiResponse::ok("signed out"));
+}
+
+
+
+#[derive(Debug, Deserialize)]
+struct UserSessionPasswordResetPost {
+    email: String,
+    /// locale of the reset mail, defaults to the `Accept-Language` of the
+    /// request
+    locale: Option<String>
+}
+
+/// mail a reset link if the address belongs to an active account, the
+/// response does not tell whether it does. Addresses and clients asking too
+/// often get 429, known or not
+async fn user_session_password_reset_post(
+    req: HttpRequest,
+    cfg: web::Data<Arc<config::Config>>,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    params: web::Json<UserSessionPasswordResetPost>
+) -> impl Responder {
+    info!("user_session_password_reset_post");
+
+    let token: String = rand::rng()
+        .sample_iter(rand::distr::Alphanumeric)
+        .take(RESET_TOKEN_LENGTH)
+        .map(char::from)
+        .collect();
+    let expires = chrono::Utc::now() + chrono::TimeDelta::seconds(cfg.password().reset_lifetime);
+
+    let locale = params.locale.clone().unwrap_or(crate::mail::request_locale(&req));
+    let variables = HashMap::from([
+        ("email", params.email.clone()),
+        ("link", crate::mail::link(&cfg, &format!("/user/password/reset/{token}"))),
+    ]);
+
+    // the token is stored and the mail queued in one transaction, the mail
+    // is sent by the outbox. both are skipped for unknown addresses, the
+    // answer is the same
+    let notification = match crate::mail::compose(
+        &dp,
+        None,
+        mailer::TemplateName::PasswordReset,
+        &locale,
+        &params.email,
+        &variables
+    ).await {
+        Ok(notification) => notification,
+        Err(e) => {
+            error!("unable to request password reset: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to request password reset"));
+        }
+    };
+
+    let connection = req.connection_info().clone();
+    let client = connection.realip_remote_addr().unwrap_or_default();
+
+    let rp = auth_provider_postgres::reset::PostgresPasswordResetProvider::new(&dp);
+    match rp.password_reset_request(&params.email, client, &token, &expires, &notification).await {
+        Ok(stored) => debug!("password reset token stored: {}", stored),
+        Err(e) => {
+            error!("unable to request password reset: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to request password reset"));
+        }
+    }
+
+    return HttpResponse::Ok()
+        .json(ApiResponse::ok("password reset requested"));
+}
+
+
+
+#[derive(Debug, Deserialize)]
+struct UserSessionPasswordResetConfirmPost {
+    token: String,
+    pw: String
+}
+
+/// set the password of the account the reset token was issued for and end
+/// every session of it
+async fn user_session_password_reset_confirm_post(
+    cfg: web::Data<Arc<config::Config>>,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    params: web::Json<UserSessionPasswordResetConfirmPost>
+) -> impl Responder {
+    info!("user_session_password_reset_confirm_post");
+
+    // a password the policy rejects does not use up the token
+    if let Err(e) = password_policy(&cfg).check(&params.pw) {
+        debug!("new password rejected: {}", e);
+        return HttpResponse::from_error(ApiError::new(e, "unable to reset password"));
+    }
+
+    let rp = auth_provider_postgres::reset::PostgresPasswordResetProvider::new(&dp);
+    let user_id = match rp.password_reset_consume(&params.token).await {
+        Err(provider_error::ProviderError::NotFound) => {
+            debug!("password reset token is invalid, expired or already used");
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::error("password reset link is invalid or expired"));
+        }
+        Err(e) => {
+            error!("unable to use password reset token: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to reset password"));
+        }
+        Ok(user_id) => user_id,
+    };
+
+    let ap = auth_provider(&dp, &cfg);
+    if let Err(e) = ap.user_auth_password_set(&user_id, &params.pw).await {
+        error!("unable to set password of user {}: {}", user_id, e);
+        return HttpResponse::from_error(ApiError::new(e, "unable to reset password"));
+    }
+
+    let tkp = auth_provider_postgres::tokens::PostgresTokensProvider::new(&dp);
+    if let Err(e) = tkp.user_tokens_revoke(&user_id).await {
+        error!("unable to revoke sessions of user {}: {}", user_id, e);
+        return HttpResponse::from_error(ApiError::new(e, "unable to reset password"));
+    }
+
+    return HttpResponse::Ok()
+        .json(ApiResponse::ok("password reset"));
+}
+
+
+
+#[derive(Debug, Deserialize)]
+struct UserSessionPasswordChangePost {
+    current_pw: String,
+    pw: String
+}
+
+/// change the password of the signed in user, every other session of the
+/// user ends and the response carries a new token pair like a sign-in
+async fn user_session_password_change_post(
+    cfg: web::Data<Arc<config::Config>>,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    tg: web::Data<Arc<token::TokenGenerator>>,
+    user: user::User,
+    params: web::Json<UserSessionPasswordChangePost>
+) -> impl Responder {
+    info!("user_session_password_change_post");
+
+    if !user.is_authenticated() {
+        return HttpResponse::Unauthorized()
+            .json(ApiResponse::error("not signed in"));
+    }
+
+    if let Err(e) = password_policy(&cfg).check(&params.pw) {
+        debug!("new password rejected: {}", e);
+        return HttpResponse::from_error(ApiError::new(e, "unable to change password"));
+    }
+
+    let user_id = user.user_id();
+    let ap = auth_provider(&dp, &cfg);
+
+    let auth = match ap.fetch_user_by_id(&user_id).await {
+        Err(e) => {
+            error!("unable to fetch password sign in of user {}: {}", user_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to change password"));
+        }
+        Ok(auth) => auth,
+    };
+
+    match ap.authenticate_by_password(&auth.email, &params.current_pw).await {
+        Err(e) => {
+            error!("unable to authenticate user: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to change password"));
+        }
+        Ok(false) => {
+            return HttpResponse::Forbidden()
+                .json(ApiResponse::error("current password is not correct"));
+        }
+        Ok(true) => {}
+    }
+
+    if let Err(e) = ap.user_auth_password_set(&user_id, &params.pw).await {
+        error!("unable to set password of user {}: {}", user_id, e);
+        return HttpResponse::from_error(ApiError::new(e, "unable to change password"));
+    }
+
+    // tokens issued before are revoked, including the one of this request
+    let Some(token_expires) = chrono::DateTime::from_timestamp(user.token_expires(), 0) else {
+        error!("invalid access token expiry: {}", user.token_expires());
+        return HttpResponse::InternalServerError()
+            .json(ApiResponse::error("unable to change password"));
+    };
+
+    let token_id = user.token_id();
+    let tkp = auth_provider_postgres::tokens::PostgresTokensProvider::new(&dp);
+    let f1 = tkp.user_tokens_revoke(&user_id);
+    let f2 = tkp.access_token_deny(&token_id, &token_expires);
+
+    let revoked = match futures::try_join!(f1, f2) {
+        Err(e) => {
+            error!("unable to revoke sessions of user {}: {}", user_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to change password"));
+        }
+        Ok((revoked, ())) => revoked,
+    };
+
+    // the new token is issued at the revocation time of the database, a
+    // clock of this host behind it would deny the token otherwise
+    let Some(pair) = issue_tokens(
+        &dp,
+        &tg,
+        &user_id,
+        &user.tenant().tenant_id(),
+        &user.name(),
+        &user.email(),
+        &revoked
+    ).await else {
+        return HttpResponse::InternalServerError()
+            .json(ApiResponse::error("password changed, unable to issue a new token"));
+    };
+
+    return HttpResponse::Ok()
+        .append_header((http::header::AUTHORIZATION, format!("Bearer {}", pair.access_token)))
+        .json(ApiResponse::new(
+            true,
+            "password changed",
+            Some(json!(pair))
+        ));
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::{body, test};
+
+    async fn response_json(
+        responder: impl Responder,
+        req: &actix_web::HttpRequest
+    ) -> (http::StatusCode, serde_json::Value) {
+        let response = responder.respond_to(req).map_into_boxed_body();
+        let status = response.status();
+        let bytes = body::to_bytes(response.into_body()).await.unwrap_or_default();
+        let value = serde_json::from_slice(&bytes).unwrap_or_default();
+        return (status, value);
+    }
+
+    #[actix_web::test]
+    async fn test_session_refresh_rotation() {
+        if let Err(e) = tracing_subscriber::fmt::try_init() {
+            println!("error: {:?}", e);
+        }
+
+        let cfg = config::Config::from_env();
+        let db_provider = database_provider::DatabaseProvider::new(&cfg);
+        let dp = web::Data::new(Arc::new(db_provider));
+        let tg = web::Data::new(Arc::new(token::TokenGenerator::new("test-secret", 60, 3600)));
+        let cfg = web::Data::new(Arc::new(cfg));
+
+        let user_id = uuid::Uuid::new_v4();
+        let email = format!("test_{}@test.com", user_id.simple());
+        let pw = "test1test";
+
+        let up = users_provider_postgres::PostgresUsersProvider::new(&dp);
+        if let Err(e) = up.save(&user_id, &"", &"", &"", &"", &"", &0).await {
+            error!("{}", e);
+            assert!(false, "unable to add user");
+        }
+        if let Err(e) = up.add_email(&user_id, &email).await {
+            error!("{}", e);
+            assert!(false, "unable to add user email");
+        }
+
+        let ap = auth_provider(&dp, &cfg);
+        if let Err(e) = ap.add_user_auth_password(&user_id, &email, pw).await {
+            error!("{}", e);
+            assert!(false, "unable to add user authentication using password");
+        }
+
+        let req = test::TestRequest::default().to_http_request();
+        let info = req.connection_info().clone();
+
+        let (status, value) = response_json(
+            user_session_signin_post(
+                info,
+                cfg.clone(),
+                dp.clone(),
+                tg.clone(),
+                web::Json(UserSessionSignInPost { email: email.clone(), pw: String::from(pw) })
+            ).await,
+            &req
+        ).await;
+        assert_eq!(status, http::StatusCode::OK);
+
+        let first = value["data"]["refresh_token"].as_str().unwrap_or_default().to_string();
+        assert!(!first.is_empty(), "sign-in did not return a refresh token");
+
+        let (status, value) = response_json(
+            user_session_refresh_post(
+                dp.clone(),
+                tg.clone(),
+                web::Json(UserSessionRefreshPost { refresh_token: first.clone() })
+            ).await,
+            &req
+        ).await;
+        assert_eq!(status, http::StatusCode::OK);
+
+        let second = value["data"]["refresh_token"].as_str().unwrap_or_default().to_string();
+        assert!(!second.is_empty());
+        assert_ne!(first, second);
+
+        // a refresh token can only be used once
+        let (status, _) = response_json(
+            user_session_refresh_post(
+                dp.clone(),
+                tg.clone(),
+                web::Json(UserSessionRefreshPost { refresh_token: first })
+            ).await,
+            &req
+        ).await;
+        assert_eq!(status, http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_password_reset() {
+        let cfg = config::Config::from_env();
+        let db_provider = database_provider::DatabaseProvider::new(&cfg);
+        let dp = web::Data::new(Arc::new(db_provider));
+        let tg = web::Data::new(Arc::new(token::TokenGenerator::new("test-secret", 60, 3600)));
+        let cfg = web::Data::new(Arc::new(cfg));
+
+        let user_id = uuid::Uuid::new_v4();
+        let email = format!("test_{}@test.com", user_id.simple());
+
+        let up = users_provider_postgres::PostgresUsersProvider::new(&dp);
+        assert!(up.save(&user_id, "", "", "", "", "", &0).await.is_ok());
+        assert!(up.add_email(&user_id, &email).await.is_ok());
+        assert!(auth_provider(&dp, &cfg).add_user_auth_password(&user_id, &email, "test1test").await.is_ok());
+
+        // reset requests are limited per client, every run asks as a new one
+        let req = test::TestRequest::default()
+            .insert_header(("x-forwarded-for", format!("test_{}", user_id.simple())))
+            .to_http_request();
+        let info = req.connection_info().clone();
+        let signin = async |pw: &str| {
+            return response_json(
+                user_session_signin_post(
+                    info.clone(),
+                    cfg.clone(),
+                    dp.clone(),
+                    tg.clone(),
+                    web::Json(UserSessionSignInPost { email: email.clone(), pw: String::from(pw) })
+                ).await,
+                &req
+            ).await;
+        };
+
+        let (_, value) = signin("test1test").await;
+        let refresh_token = value["data"]["refresh_token"].as_str().unwrap_or_default().to_string();
+        assert!(!refresh_token.is_empty());
+
+        let reset = async |address: &str| {
+            return response_json(
+                user_session_password_reset_post(
+                    req.clone(),
+                    cfg.clone(),
+                    dp.clone(),
+                    web::Json(UserSessionPasswordResetPost { email: String::from(address), locale: None })
+                ).await,
+                &req
+            ).await;
+        };
+
+        // known and unknown addresses get the same answer
+        let unknown = format!("unknown_{}@test.com", user_id.simple());
+        for address in [&email, &unknown] {
+            let (status, value) = reset(address).await;
+            assert_eq!(status, http::StatusCode::OK);
+            assert_eq!(value["message"], "password reset requested");
+        }
+
+        // and are throttled alike
+        for _ in 0..4 {
+            assert_eq!(reset(&unknown).await.0, http::StatusCode::OK);
+        }
+        let (status, value) = reset(&unknown).await;
+        assert_eq!(status, http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(value["code"], "throttled");
+
+        // the token of the mail is not known here, a second request replaces it
+        let token = uuid::Uuid::new_v4().to_string();
+        let expires = chrono::Utc::now() + chrono::TimeDelta::hours(1);
+        let notification = outbox_provider::OutboxMessage::new(&email, "Reset your password", &token);
+        let rp = auth_provider_postgres::reset::PostgresPasswordResetProvider::new(&dp);
+        let client = format!("test_{}", user_id.simple());
+        assert_eq!(rp.password_reset_request(&email, &client, &token, &expires, &notification).await, Ok(true));
+
+        let confirm = async |token: &str, pw: &str| {
+            return response_json(
+                user_session_password_reset_confirm_post(
+                    cfg.clone(),
+                    dp.clone(),
+                    web::Json(UserSessionPasswordResetConfirmPost { token: String::from(token), pw: String::from(pw) })
+                ).await,
+                &req
+            ).await;
+        };
+        // a password the policy rejects leaves the token usable
+        let (status, value) = confirm(&token, "short").await;
+        assert_eq!(status, http::StatusCode::BAD_REQUEST);
+        assert_eq!(value["code"], "validation");
+        assert_eq!(confirm(&token, "test2test").await.0, http::StatusCode::OK);
+        assert_eq!(confirm(&token, "test2test").await.0, http::StatusCode::BAD_REQUEST);
+
+        // the old password and the sessions started with it are gone
+        assert_eq!(signin("test1test").await.1["success"], false);
+        assert_eq!(signin("test2test").await.1["success"], true);
+
+        let (status, _) = response_json(
+            user_session_refresh_post(
+                dp.clone(),
+                tg.clone(),
+                web::Json(UserSessionRefreshPost { refresh_token })
+            ).await,
+            &req
+        ).await;
+        assert_eq!(status, http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_password_change() {
+        let cfg = config::Config::from_env();
+        let db_provider = database_provider::DatabaseProvider::new(&cfg);
+        let dp = web::Data::new(Arc::new(db_provider));
+        let tg = web::Data::new(Arc::new(token::TokenGenerator::new("test-secret", 60, 3600)));
+        let cfg = web::Data::new(Arc::new(cfg));
+
+        let user_id = uuid::Uuid::new_v4();
+        let email = format!("test_{}@test.com", user_id.simple());
+
+        let up = users_provider_postgres::PostgresUsersProvider::new(&dp);
+        assert!(up.save(&user_id, "", "", "", "", "", &0).await.is_ok());
+        assert!(up.add_email(&user_id, &email).await.is_ok());
+        assert!(auth_provider(&dp, &cfg).add_user_auth_password(&user_id, &email, "test1test").await.is_ok());
+
+        let req = test::TestRequest::default().to_http_request();
+        let info = req.connection_info().clone();
+        let (_, value) = response_json(
+            user_session_signin_post(
+                info,
+                cfg.clone(),
+                dp.clone(),
+                tg.clone(),
+                web::Json(UserSessionSignInPost { email: email.clone(), pw: String::from("test1test") })
+            ).await,
+            &req
+        ).await;
+        let refresh_token = value["data"]["refresh_token"].as_str().unwrap_or_default().to_string();
+        let claim = tg.parse_token(value["data"]["access_token"].as_str().unwrap_or_default())
+            .unwrap_or(token::AuthData::default());
+        assert!(!claim.is_empty(), "sign-in did not return a valid access token");
+
+        let change = async |current_pw: &str, pw: &str| {
+            return response_json(
+                user_session_password_change_post(
+                    cfg.clone(),
+                    dp.clone(),
+                    tg.clone(),
+                    user::User::new(&user_id, &tenant::Tenant::default(), &email, &email, &vec![], &vec![])
+                        .with_token(&claim.token_id, claim.expires),
+                    web::Json(UserSessionPasswordChangePost {
+                        current_pw: String::from(current_pw),
+                        pw: String::from(pw)
+                    })
+                ).await,
+                &req
+            ).await;
+        };
+
+        assert_eq!(change("wrong-password", "test2test").await.0, http::StatusCode::FORBIDDEN);
+        assert_eq!(change("test1test", "short").await.0, http::StatusCode::BAD_REQUEST);
+
+        let (status, value) = change("test1test", "test2test").await;
+        assert_eq!(status, http::StatusCode::OK);
+        let next = value["data"]["refresh_token"].as_str().unwrap_or_default().to_string();
+        assert!(!next.is_empty());
+        assert_ne!(next, refresh_token);
+
+        let ap = auth_provider(&dp, &cfg);
+        assert_eq!(ap.authenticate_by_password(&email, "test1test").await, Ok(false));
+        assert_eq!(ap.authenticate_by_password(&email, "test2test").await, Ok(true));
+
+        // the sessions before the change end, the new one continues
+        let tkp = auth_provider_postgres::tokens::PostgresTokensProvider::new(&dp);
+        let issued = chrono::DateTime::from_timestamp(claim.issued, 0).unwrap_or_default();
+        assert_eq!(tkp.access_token_is_denied(&claim.token_id, &user_id, &issued).await, Ok(true));
+
+        // the new access token is issued at the revocation, whatever the
+        // clock of this host says
+        let renewed = tg.parse_token(value["data"]["access_token"].as_str().unwrap_or_default())
+            .unwrap_or(token::AuthData::default());
+        assert!(!renewed.is_empty(), "password change did not return a valid access token");
+        let renewed_issued = chrono::DateTime::from_timestamp(renewed.issued, 0).unwrap_or_default();
+        assert_eq!(tkp.access_token_is_denied(&renewed.token_id, &user_id, &renewed_issued).await, Ok(false));
+
+        let refresh = async |refresh_token: String| {
+            return response_json(
+                user_session_refresh_post(
+                    dp.clone(),
+                    tg.clone(),
+                    web::Json(UserSessionRefreshPost { refresh_token })
+                ).await,
+                &req
+            ).await.0;
+        };
+        assert_eq!(refresh(refresh_token).await, http::StatusCode::UNAUTHORIZED);
+        assert_eq!(refresh(next).await, http::StatusCode::OK);
+    }
+}
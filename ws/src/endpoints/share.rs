@@ -0,0 +1,206 @@
+use tracing::{
+    info,
+    error,
+    debug
+};
+
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use actix_web::{
+    http,
+    web,
+    HttpRequest,
+    HttpResponse,
+    Responder
+};
+
+
+use file_provider::FileProvider;
+use provider_error::ProviderError;
+use crate::endpoints::{
+    ApiError,
+    ApiResponse,
+    default_option_response
+};
+
+
+/// header carrying the password of a password protected link
+const PASSWORD_HEADER: &str = "x-share-password";
+
+
+
+/// share links are used without signing in, the token in the path is all
+/// the authorization there is
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg
+        .service(
+            web::resource("{token}")
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::get().to(share_get))
+        )
+        .service(
+            web::resource("{token}/{file_id}")
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::get().to(share_file_get))
+        )
+    ;
+}
+
+
+
+#[derive(Debug, Deserialize)]
+struct ShareDownloadQuery {
+    inline: Option<bool>
+}
+
+/// the file of a file link, the files available through a folder link
+async fn share_get(
+    req: HttpRequest,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    store: web::Data<Arc<dyn file_store::FileStore>>,
+    tg: web::Data<Arc<token::TokenGenerator>>,
+    path: web::Path<String>,
+    query: web::Query<ShareDownloadQuery>
+) -> impl Responder {
+    info!("share_get");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let (share, link) = match open_link(&req, &fp, &tg, &path.into_inner()).await {
+        Err(response) => return response,
+        Ok(opened) => opened
+    };
+
+    let folder_id = match link.target {
+        file_provider::ShareTarget::File(file_id) => {
+            return download(&req, &fp, store.get_ref().as_ref(), &share, &file_id, query.inline.unwrap_or(false)).await;
+        }
+        file_provider::ShareTarget::Folder(folder_id) => folder_id
+    };
+
+    match fp.share_link_files(&share.tenant_id, &share.link_id).await {
+        Err(e) => {
+            error!("unable to fetch files of share link {}: {:?}", share.link_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch shared files"));
+        }
+        Ok(files) => {
+            log_access(&req, &fp, &share, None, file_provider::ShareOutcome::Listed).await;
+            return HttpResponse::Ok()
+                .json(ApiResponse::new(
+                    true,
+                    "successfully fetched shared files",
+                    Some(json!({
+                        "folder_id": folder_id,
+                        "expires": link.expires,
+                        "files": files
+                    }))
+                ));
+        }
+    }
+}
+
+
+/// a file of a folder link
+async fn share_file_get(
+    req: HttpRequest,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    store: web::Data<Arc<dyn file_store::FileStore>>,
+    tg: web::Data<Arc<token::TokenGenerator>>,
+    path: web::Path<(String, uuid::Uuid)>,
+    query: web::Query<ShareDownloadQuery>
+) -> impl Responder {
+    info!("share_file_get");
+
+    let (token, file_id) = path.into_inner();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let (share, _) = match open_link(&req, &fp, &tg, &token).await {
+        Err(response) => return response,
+        Ok(opened) => opened
+    };
+
+    return download(&req, &fp, store.get_ref().as_ref(), &share, &file_id, query.inline.unwrap_or(false)).await;
+}
+
+
+/// the link the token refers to if it can be used with the password sent;
+/// tokens that do not verify are not found, every other attempt is logged
+async fn open_link(
+    req: &HttpRequest,
+    fp: &file_provider_postgres::PostgresFileProvider,
+    tg: &token::TokenGenerator,
+    token: &str
+) -> Result<(token::ShareData, file_provider::ShareLink), HttpResponse> {
+    let share = match tg.parse_share_token(token) {
+        Err(e) => {
+            debug!("invalid share token: {}", e);
+            return Err(HttpResponse::from_error(ApiError::new(ProviderError::NotFound, "Share link not found")));
+        }
+        Ok(share) => share
+    };
+
+    let password = req.headers().get(PASSWORD_HEADER).and_then(|v| v.to_str().ok());
+    match fp.share_link_open(&share.tenant_id, &share.link_id, password).await {
+        Err(e) => {
+            debug!("unable to open share link {}: {:?}", share.link_id, e);
+            log_access(req, fp, &share, None, file_provider::ShareOutcome::Denied).await;
+            let message = match e {
+                ProviderError::Forbidden => "Share link password required",
+                ProviderError::Throttled => "Too many wrong passwords, try again later",
+                _ => "Share link not found"
+            };
+            return Err(HttpResponse::from_error(ApiError::new(e, message)));
+        }
+        Ok(link) => {
+            return Ok((share, link));
+        }
+    }
+}
+
+
+async fn download(
+    req: &HttpRequest,
+    fp: &file_provider_postgres::PostgresFileProvider,
+    store: &dyn file_store::FileStore,
+    share: &token::ShareData,
+    file_id: &uuid::Uuid,
+    inline: bool
+) -> HttpResponse {
+    match fp.share_link_file_get(&share.tenant_id, &share.link_id, file_id).await {
+        Err(e) => {
+            error!("unable to fetch file {} of share link {}: {:?}", file_id, share.link_id, e);
+            log_access(req, fp, share, Some(file_id), file_provider::ShareOutcome::Denied).await;
+            return HttpResponse::from_error(ApiError::new(e, "Error fetching file"));
+        }
+        Ok(file) => {
+            log_access(req, fp, share, Some(file_id), file_provider::ShareOutcome::Downloaded).await;
+            return crate::endpoints::file::serve_file(req, store, share.tenant_id, &file, inline).await;
+        }
+    }
+}
+
+
+/// the response does not depend on the access being logged
+async fn log_access(
+    req: &HttpRequest,
+    fp: &file_provider_postgres::PostgresFileProvider,
+    share: &token::ShareData,
+    file_id: Option<&uuid::Uuid>,
+    outcome: file_provider::ShareOutcome
+) {
+    let connection = req.connection_info().clone();
+    let user_agent = req.headers().get(http::header::USER_AGENT).and_then(|v| v.to_str().ok());
+
+    if let Err(e) = fp.share_link_access_add(
+        &share.tenant_id,
+        &share.link_id,
+        file_id,
+        outcome,
+        connection.realip_remote_addr(),
+        user_agent
+    ).await {
+        error!("unable to log access to share link {}: {:?}", share.link_id, e);
+    }
+}
@@ -0,0 +1,632 @@
+use tracing::{
+    info,
+    error,
+    debug
+};
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use base64::Engine;
+use futures::{Stream, StreamExt};
+
+use actix_web::{
+    http,
+    http::header,
+    http::StatusCode,
+    web,
+    HttpRequest,
+    HttpResponse,
+    HttpResponseBuilder,
+    Responder
+};
+
+
+use file_provider::FileProvider;
+use provider_error::ProviderError;
+use crate::{
+    endpoints::{
+        ApiError,
+        ApiResponse,
+        file
+    },
+    classes::user
+};
+use crate::middleware::permissions::Permission;
+
+
+const TUS_VERSION: &str = "1.0.0";
+const TUS_EXTENSIONS: &str = "creation,expiration,termination";
+const OFFSET_CONTENT_TYPE: &str = "application/offset+octet-stream";
+
+const TUS_RESUMABLE: &str = "tus-resumable";
+const TUS_VERSION_HEADER: &str = "tus-version";
+const TUS_EXTENSION: &str = "tus-extension";
+const TUS_MAX_SIZE: &str = "tus-max-size";
+const UPLOAD_LENGTH: &str = "upload-length";
+const UPLOAD_OFFSET: &str = "upload-offset";
+const UPLOAD_METADATA: &str = "upload-metadata";
+const UPLOAD_EXPIRES: &str = "upload-expires";
+/// id of the file an upload turns into
+const FILE_ID: &str = "x-file-id";
+
+/// uploads that are not completed within this time are removed
+const UPLOAD_LIFETIME_HOURS: i64 = 24;
+
+
+
+/// resumable uploads following the tus 1.0 protocol with the creation,
+/// expiration and termination extensions
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg
+        .service(
+            web::resource("")
+                .wrap(Permission::new("files.upload"))
+                .route(web::method(http::Method::OPTIONS).to(tus_options))
+                .route(web::post().to(tus_create))
+        )
+        .service(
+            web::resource("{upload_id}")
+                .wrap(Permission::new("files.upload"))
+                .route(web::method(http::Method::OPTIONS).to(tus_options))
+                .route(web::head().to(tus_head))
+                .route(web::patch().to(tus_patch))
+                .route(web::delete().to(tus_delete))
+        )
+    ;
+}
+
+
+
+async fn tus_options(
+    cfg: web::Data<Arc<config::Config>>
+) -> impl Responder {
+    info!("tus_options");
+
+    return tus_response(StatusCode::NO_CONTENT)
+        .insert_header((TUS_VERSION_HEADER, TUS_VERSION))
+        .insert_header((TUS_EXTENSION, TUS_EXTENSIONS))
+        .insert_header((TUS_MAX_SIZE, cfg.upload().max_size.to_string()))
+        .finish();
+}
+
+
+/// starts an upload of `Upload-Length` bytes; `Upload-Metadata` names the
+/// file with `filename` and may add `filetype`, `folder_id` and `file_id`
+async fn tus_create(
+    req: HttpRequest,
+    cfg: web::Data<Arc<config::Config>>,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    store: web::Data<Arc<dyn file_store::FileStore>>,
+    user: user::User
+) -> impl Responder {
+    info!("tus_create");
+
+    if let Some(response) = unsupported_version(&req) {
+        return response;
+    }
+
+    let Some(length) = header_u64(&req, UPLOAD_LENGTH) else {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::error("Missing Upload-Length"));
+    };
+
+    let Some(metadata) = req.headers().get(UPLOAD_METADATA)
+        .map_or(Some(HashMap::new()), |v| v.to_str().ok().and_then(parse_metadata))
+    else {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::error("Invalid Upload-Metadata"));
+    };
+
+    let Some(name) = metadata.get("filename").or(metadata.get("name"))
+        .map(|name| file::sanitize_file_name(name))
+        .filter(|name| !name.is_empty())
+    else {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::error("Missing file name"));
+    };
+
+    let ids = (
+        metadata.get("folder_id").map(|id| uuid::Uuid::parse_str(id)).transpose(),
+        metadata.get("file_id").map(|id| uuid::Uuid::parse_str(id)).transpose()
+    );
+    let (Ok(folder_id), Ok(file_id)) = ids else {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::error("Invalid uuid"));
+    };
+
+    let tenant_id = user.tenant().tenant_id();
+    let user_id = user.user_id();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let limits = match file::upload_limits(&fp, &cfg, tenant_id).await {
+        Err(response) => return response,
+        Ok(limits) => limits
+    };
+    if let Some(rejection) = limits.exceeded(length) {
+        return rejection.response();
+    }
+
+    expire_uploads(&fp, &store).await;
+
+    let upload = file_provider::FileUpload {
+        upload_id: uuid::Uuid::new_v4(),
+        file_id: file_id.unwrap_or(uuid::Uuid::new_v4()),
+        folder_id: folder_id.unwrap_or(uuid::Uuid::nil()),
+        name,
+        content_type: metadata.get("filetype").or(metadata.get("type"))
+            .cloned()
+            .unwrap_or(String::from(file_provider::DEFAULT_CONTENT_TYPE)),
+        length: i64::try_from(length).unwrap_or(i64::MAX),
+        offset: 0,
+        expires: chrono::Utc::now() + chrono::Duration::hours(UPLOAD_LIFETIME_HOURS)
+    };
+    if let Err(e) = fp.upload_add(&tenant_id, &user_id, &upload).await {
+        error!("unable to add upload: {:?}", e);
+        return HttpResponse::from_error(ApiError::new(e, "Error creating upload"));
+    }
+    debug!("upload {} of {} bytes created", upload.upload_id, length);
+
+    let mut response = tus_response(StatusCode::CREATED);
+    response
+        .insert_header((header::LOCATION, upload_location(&req, &upload.upload_id)))
+        .insert_header((UPLOAD_EXPIRES, http_date(&upload.expires)));
+
+    // there is nothing to send for an empty file
+    if length == 0 {
+        let empty: file_store::ByteStream = Box::pin(futures::stream::empty());
+        if let Err(e) = store.append(tenant_id, upload.upload_id, 0, empty).await {
+            error!("unable to store upload {}: {:?}", upload.upload_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error storing upload"));
+        }
+        if let Err(response) = finalize(&cfg, &fp, &store, tenant_id, user_id, &upload).await {
+            return response;
+        }
+        response.insert_header((FILE_ID, upload.file_id.to_string()));
+    }
+
+    return response.finish();
+}
+
+
+async fn tus_head(
+    req: HttpRequest,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    user: user::User,
+    path: web::Path<uuid::Uuid>
+) -> impl Responder {
+    info!("tus_head");
+
+    if let Some(response) = unsupported_version(&req) {
+        return response;
+    }
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    match fp.upload_fetch(&user.tenant().tenant_id(), &user.user_id(), &path.into_inner()).await {
+        Err(e) => {
+            debug!("unable to fetch upload: {:?}", e);
+            return HttpResponse::from_error(ApiError::new(e, "Upload not found"));
+        }
+        Ok(upload) => {
+            return tus_response(StatusCode::OK)
+                .insert_header((UPLOAD_OFFSET, upload.offset.to_string()))
+                .insert_header((UPLOAD_LENGTH, upload.length.to_string()))
+                .insert_header((UPLOAD_EXPIRES, http_date(&upload.expires)))
+                .insert_header((header::CACHE_CONTROL, "no-store"))
+                .finish();
+        }
+    }
+}
+
+
+/// appends the body at `Upload-Offset`, the upload becomes a file once all
+/// of it is received
+async fn tus_patch(
+    req: HttpRequest,
+    cfg: web::Data<Arc<config::Config>>,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    store: web::Data<Arc<dyn file_store::FileStore>>,
+    user: user::User,
+    path: web::Path<uuid::Uuid>,
+    payload: web::Payload
+) -> impl Responder {
+    info!("tus_patch");
+
+    if let Some(response) = unsupported_version(&req) {
+        return response;
+    }
+
+    let content_type = req.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+    if content_type != Some(OFFSET_CONTENT_TYPE) {
+        return HttpResponse::UnsupportedMediaType()
+            .json(ApiResponse::error("Content type has to be application/offset+octet-stream"));
+    }
+
+    let Some(offset) = header_u64(&req, UPLOAD_OFFSET) else {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::error("Missing Upload-Offset"));
+    };
+
+    let tenant_id = user.tenant().tenant_id();
+    let user_id = user.user_id();
+    let upload_id = path.into_inner();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let upload = match fp.upload_fetch(&tenant_id, &user_id, &upload_id).await {
+        Err(e) => {
+            debug!("unable to fetch upload: {:?}", e);
+            return HttpResponse::from_error(ApiError::new(e, "Upload not found"));
+        }
+        Ok(upload) => upload
+    };
+
+    let length = u64::try_from(upload.length).unwrap_or_default();
+    if u64::try_from(upload.offset).ok() != Some(offset) {
+        return HttpResponse::Conflict()
+            .json(ApiResponse::error("Upload-Offset does not match the upload"));
+    }
+
+    let state = Cell::new(BodyState::Reading);
+    let body = PatchBody {
+        payload,
+        remaining: length - offset,
+        state: &state
+    };
+
+    let end = match store.append(tenant_id, upload_id, offset, Box::pin(body)).await {
+        Err(e) => {
+            if state.get() == BodyState::TooLong {
+                return HttpResponse::PayloadTooLarge()
+                    .json(ApiResponse::error("Body exceeds the Upload-Length"));
+            }
+            error!("unable to append to upload {}: {:?}", upload_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error storing upload"));
+        }
+        Ok(end) => end
+    };
+    if state.get() == BodyState::Interrupted {
+        debug!("upload {} interrupted at {}", upload_id, end);
+    }
+
+    // bytes the store holds past the saved offset are dropped by the next
+    // append
+    if end != offset
+        && let Err(e) = fp.upload_offset_save(
+            &tenant_id,
+            &user_id,
+            &upload_id,
+            upload.offset,
+            i64::try_from(end).unwrap_or(i64::MAX)
+        ).await
+    {
+        error!("unable to save offset of upload {}: {:?}", upload_id, e);
+        return HttpResponse::from_error(ApiError::new(e, "Error saving upload offset"));
+    }
+
+    let mut response = tus_response(StatusCode::NO_CONTENT);
+    response
+        .insert_header((UPLOAD_OFFSET, end.to_string()))
+        .insert_header((UPLOAD_EXPIRES, http_date(&upload.expires)));
+
+    if end == length {
+        if let Err(response) = finalize(&cfg, &fp, &store, tenant_id, user_id, &upload).await {
+            return response;
+        }
+        response.insert_header((FILE_ID, upload.file_id.to_string()));
+    }
+
+    return response.finish();
+}
+
+
+/// abandons the upload and discards what was received
+async fn tus_delete(
+    req: HttpRequest,
+    dp: web::Data<Arc<database_provider::DatabaseProvider>>,
+    store: web::Data<Arc<dyn file_store::FileStore>>,
+    user: user::User,
+    path: web::Path<uuid::Uuid>
+) -> impl Responder {
+    info!("tus_delete");
+
+    if let Some(response) = unsupported_version(&req) {
+        return response;
+    }
+
+    let tenant_id = user.tenant().tenant_id();
+    let user_id = user.user_id();
+    let upload_id = path.into_inner();
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    if let Err(e) = fp.upload_fetch(&tenant_id, &user_id, &upload_id).await {
+        debug!("unable to fetch upload: {:?}", e);
+        return HttpResponse::from_error(ApiError::new(e, "Upload not found"));
+    }
+
+    if let Err(e) = store.discard(tenant_id, upload_id).await {
+        error!("unable to discard upload {}: {:?}", upload_id, e);
+        return HttpResponse::from_error(ApiError::new(e, "Error removing upload"));
+    }
+
+    match fp.upload_remove(&tenant_id, &user_id, &upload_id).await {
+        Err(e) => {
+            error!("unable to remove upload {}: {:?}", upload_id, e);
+            return HttpResponse::from_error(ApiError::new(e, "Error removing upload"));
+        }
+        Ok(()) => {
+            return tus_response(StatusCode::NO_CONTENT).finish();
+        }
+    }
+}
+
+
+/// turns the complete upload into a file; the upload is removed either way
+/// and its content released again when it does not become a file
+async fn finalize(
+    cfg: &config::Config,
+    fp: &file_provider_postgres::PostgresFileProvider,
+    store: &Arc<dyn file_store::FileStore>,
+    tenant_id: uuid::Uuid,
+    user_id: uuid::Uuid,
+    upload: &file_provider::FileUpload
+) -> Result<(), HttpResponse> {
+    let stat = match store.complete(tenant_id, upload.upload_id).await {
+        Err(e) => {
+            error!("unable to complete upload {}: {:?}", upload.upload_id, e);
+            return Err(HttpResponse::from_error(ApiError::new(e, "Error storing upload")));
+        }
+        Ok(stat) => stat
+    };
+
+    let added = add_file(cfg, fp, store, tenant_id, user_id, upload, &stat).await;
+
+    if let Err(e) = fp.upload_remove(&tenant_id, &user_id, &upload.upload_id).await {
+        error!("unable to remove upload {}: {:?}", upload.upload_id, e);
+    }
+    if added.is_err() {
+        file::release_content(fp, store, tenant_id, &stat.digest).await;
+    }
+    return added;
+}
+
+
+/// the content type and the quota are checked as for other uploads, the
+/// quota again now that the whole content is stored
+async fn add_file(
+    cfg: &config::Config,
+    fp: &file_provider_postgres::PostgresFileProvider,
+    store: &Arc<dyn file_store::FileStore>,
+    tenant_id: uuid::Uuid,
+    user_id: uuid::Uuid,
+    upload: &file_provider::FileUpload,
+    stat: &file_store::FileStat
+) -> Result<(), HttpResponse> {
+    let head = match read_head(store.as_ref(), tenant_id, &stat.digest).await {
+        Err(e) => {
+            error!("unable to read upload {}: {:?}", upload.upload_id, e);
+            return Err(HttpResponse::from_error(ApiError::new(e, "Error reading upload")));
+        }
+        Ok(head) => head
+    };
+    let content_type = file_store::sniff::content_type(&head, &upload.content_type);
+
+    let limits = file::upload_limits(fp, cfg, tenant_id).await?;
+    if !limits.config.allows(&content_type) {
+        debug!("content type {} not allowed", content_type);
+        return Err(file::Rejection::Type.response());
+    }
+    if let Some(rejection) = limits.exceeded(stat.size) {
+        return Err(rejection.response());
+    }
+
+    let file = file_provider::File::new(upload.file_id, upload.name.clone())
+        .with_content(
+            &content_type,
+            i64::try_from(stat.size).unwrap_or(i64::MAX),
+            &stat.digest
+        );
+    if let Err(e) = fp.file_add(&tenant_id, &user_id, &upload.folder_id, &file).await {
+        error!("error adding file to provider: {:?}", e);
+        return Err(HttpResponse::from_error(ApiError::new(e, "Error saving file")));
+    }
+    return Ok(());
+}
+
+
+async fn read_head(
+    store: &dyn file_store::FileStore,
+    tenant_id: uuid::Uuid,
+    digest: &str
+) -> Result<Vec<u8>, ProviderError> {
+    let mut data = store.get_range(tenant_id, digest, 0, file_store::sniff::HEAD_LENGTH as u64).await?;
+    let mut head = Vec::with_capacity(file_store::sniff::HEAD_LENGTH);
+    while let Some(chunk) = data.next().await {
+        head.extend_from_slice(&chunk?);
+    }
+    return Ok(head);
+}
+
+
+/// the response does not depend on expired uploads being removed
+async fn expire_uploads(
+    fp: &file_provider_postgres::PostgresFileProvider,
+    store: &Arc<dyn file_store::FileStore>
+) {
+    match fp.uploads_expire().await {
+        Err(e) => {
+            error!("unable to expire uploads: {:?}", e);
+        }
+        Ok(expired) => {
+            for (tenant_id, upload_id) in expired {
+                debug!("discarding expired upload {}", upload_id);
+                if let Err(e) = store.discard(tenant_id, upload_id).await {
+                    error!("unable to discard upload {}: {:?}", upload_id, e);
+                }
+            }
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BodyState {
+    Reading,
+    /// the client went away, what was received is kept
+    Interrupted,
+    /// the body is longer than what is left of the upload
+    TooLong
+}
+
+/// the body of a PATCH request, cut off where the upload ends
+struct PatchBody<'a> {
+    payload: web::Payload,
+    remaining: u64,
+    state: &'a Cell<BodyState>
+}
+
+impl Stream for PatchBody<'_> {
+    type Item = Result<web::Bytes, ProviderError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        match this.payload.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                let length = chunk.len() as u64;
+                if length > this.remaining {
+                    this.state.set(BodyState::TooLong);
+                    return Poll::Ready(Some(Err(ProviderError::validation("body", "exceeds the upload length"))));
+                }
+                this.remaining -= length;
+                return Poll::Ready(Some(Ok(chunk)));
+            }
+            Poll::Ready(Some(Err(e))) => {
+                debug!("error reading body: {:?}", e);
+                this.state.set(BodyState::Interrupted);
+                return Poll::Ready(None);
+            }
+            Poll::Ready(None) => {
+                return Poll::Ready(None);
+            }
+            Poll::Pending => {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+
+fn tus_response(status: StatusCode) -> HttpResponseBuilder {
+    let mut response = HttpResponse::build(status);
+    response.insert_header((TUS_RESUMABLE, TUS_VERSION));
+    return response;
+}
+
+
+/// requests other than OPTIONS have to name the protocol version they use
+fn unsupported_version(req: &HttpRequest) -> Option<HttpResponse> {
+    if req.headers().get(TUS_RESUMABLE).and_then(|v| v.to_str().ok()) == Some(TUS_VERSION) {
+        return None;
+    }
+    return Some(tus_response(StatusCode::PRECONDITION_FAILED)
+        .insert_header((TUS_VERSION_HEADER, TUS_VERSION))
+        .json(ApiResponse::error("Unsupported tus version")));
+}
+
+
+/// absolute url of an upload below the url the creation request was sent
+/// to, scheme and host follow the `Forwarded` headers of a proxy
+fn upload_location(req: &HttpRequest, upload_id: &uuid::Uuid) -> String {
+    let info = req.connection_info();
+    return format!(
+        "{}://{}{}/{}",
+        info.scheme(),
+        info.host(),
+        req.path().trim_end_matches('/'),
+        upload_id
+    );
+}
+
+fn header_u64(req: &HttpRequest, name: &str) -> Option<u64> {
+    return req.headers().get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+}
+
+
+fn http_date(date: &chrono::DateTime<chrono::Utc>) -> String {
+    return header::HttpDate::from(std::time::SystemTime::from(*date)).to_string();
+}
+
+
+/// `Upload-Metadata`, comma separated keys each followed by an optional
+/// base64 encoded value
+fn parse_metadata(value: &str) -> Option<HashMap<String, String>> {
+    let mut metadata = HashMap::new();
+    for pair in value.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+        let mut parts = pair.split(' ').filter(|part| !part.is_empty());
+        let key = parts.next()?;
+        let value = match parts.next() {
+            None => String::new(),
+            Some(encoded) => {
+                let decoded = base64::engine::general_purpose::STANDARD.decode(encoded).ok()?;
+                String::from_utf8(decoded).ok()?
+            }
+        };
+        if parts.next().is_some() || metadata.insert(String::from(key), value).is_some() {
+            return None;
+        }
+    }
+    return Some(metadata);
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metadata() {
+        let expected = HashMap::from([
+            (String::from("filename"), String::from("scan.pdf")),
+            (String::from("filetype"), String::from("application/pdf")),
+            (String::from("is_confidential"), String::new())
+        ]);
+        assert_eq!(
+            parse_metadata("filename c2Nhbi5wZGY=, filetype YXBwbGljYXRpb24vcGRm,is_confidential"),
+            Some(expected)
+        );
+
+        assert_eq!(parse_metadata(""), Some(HashMap::new()));
+        assert_eq!(parse_metadata("filename not-base64!"), None);
+        assert_eq!(parse_metadata("filename YQ== YQ=="), None);
+        assert_eq!(parse_metadata("filename YQ==,filename Yg=="), None);
+    }
+
+    #[test]
+    fn test_upload_location() {
+        let upload_id = uuid::Uuid::nil();
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/api/v1/file/tus/")
+            .insert_header((header::HOST, "files.example.com"))
+            .to_http_request();
+        assert_eq!(
+            upload_location(&req, &upload_id),
+            format!("http://files.example.com/api/v1/file/tus/{upload_id}")
+        );
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/api/v1/file/tus")
+            .insert_header((header::HOST, "internal:8080"))
+            .insert_header((header::FORWARDED, "proto=https;host=files.example.com"))
+            .to_http_request();
+        assert_eq!(
+            upload_location(&req, &upload_id),
+            format!("https://files.example.com/api/v1/file/tus/{upload_id}")
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, error, info};
 
@@ -6,9 +7,9 @@ use serde_json::json;
 
 use rand::{Rng, distr::Alphanumeric, prelude::*};
 
-use actix_web::{HttpResponse, Responder, http, web};
+use actix_web::{HttpRequest, HttpResponse, Responder, http, web};
 
-use crate::endpoints::{ApiResponse, default_option_response};
+use crate::endpoints::{ApiError, ApiResponse, default_option_response};
 
 use auth_provider::AuthProvider;
 use user_registration::UserRegistrationProvider;
@@ -38,10 +39,14 @@ pub fn config(cfg: &mut web::ServiceConfig) {
 struct UserRegistrationSignUpPost {
     id: uuid::Uuid,
     email: String,
+    /// locale of the verification mail, defaults to the `Accept-Language`
+    /// of the request
+    locale: Option<String>,
 }
 
 async fn user_registration_signup_post(
-    mailer: web::Data<Arc<mailer::Mailer>>,
+    req: HttpRequest,
+    cfg: web::Data<Arc<config::Config>>,
     dp: web::Data<Arc<database_provider::DatabaseProvider>>,
     params: web::Json<UserRegistrationSignUpPost>,
 ) -> impl Responder {
@@ -55,24 +60,34 @@ async fn user_registration_signup_post(
         .map(|_| rng.sample(rand::distr::Alphanumeric) as char)
         .collect();
 
-    match ur.register_user(&params.id, &params.email, &token).await {
-        Ok(_) => {
-            info!("User registered successfully");
-
-            // send email with link to verify email address
-            if let Err(result) = mailer.send(format!("Please verify your email address by clicking the following link: /user/sign-up/verified/{}", token)) {
-                error!("Error sending verification email: {}", result);
-                return HttpResponse::InternalServerError()
-                    .json(ApiResponse::error("email_sending_failed"))
-                    ;
-            }
-        }
+    // email with link to verify email address, delivered by the outbox worker
+    let locale = params.locale.clone().unwrap_or(crate::mail::request_locale(&req));
+    let variables = HashMap::from([
+        ("email", params.email.clone()),
+        ("link", crate::mail::link(&cfg, &format!("/user/sign-up/verified/{}", token))),
+    ]);
+    let notification = match crate::mail::compose(
+        &dp,
+        None,
+        mailer::TemplateName::Verification,
+        &locale,
+        &params.email,
+        &variables,
+    )
+    .await
+    {
         Err(e) => {
-            error!("Error registering user: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("registration failed"));
+            error!("Error composing verification email: {}", e);
+            return HttpResponse::from_error(ApiError::new(e, "registration failed"));
         }
+        Ok(notification) => notification,
+    };
+
+    if let Err(e) = ur.register_user(&params.id, &params.email, &token, &notification).await {
+        error!("Error registering user: {}", e);
+        return HttpResponse::from_error(ApiError::new(e, "registration failed"));
     }
+    info!("User registered successfully");
 
     return HttpResponse::Ok().json(ApiResponse::ok("success"));
 }
@@ -85,6 +100,7 @@ struct UserRegistrationSignUpVerifiedPost {
 }
 
 async fn user_registration_signup_verified_post(
+    cfg: web::Data<Arc<config::Config>>,
     dp: web::Data<Arc<database_provider::DatabaseProvider>>,
     params: web::Json<UserRegistrationSignUpVerifiedPost>,
 ) -> impl Responder {
@@ -98,8 +114,7 @@ async fn user_registration_signup_verified_post(
         .await
     {
         error!("unable to verify user registration: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::error("error while verifying registration"));
+        return HttpResponse::from_error(ApiError::new(e, "error while verifying registration"));
     }
 
     if let Err(e) = ur
@@ -107,8 +122,7 @@ async fn user_registration_signup_verified_post(
         .await
     {
         error!("unable to fetch user registration details: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::error("error while verifying registration"));
+        return HttpResponse::from_error(ApiError::new(e, "error while verifying registration"));
     }
     let urd = match ur
         .fetch_registration_details_by_id(&params.register_id)
@@ -126,8 +140,7 @@ async fn user_registration_signup_verified_post(
     // save initial user details
     if let Err(e) = up.save(&params.register_id, "", "", "", "", "", &0).await {
         error!("unable to save user details: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::error("error while verifying registration"));
+        return HttpResponse::from_error(ApiError::new(e, "error while verifying registration"));
     }
 
     // save user email address
@@ -136,11 +149,10 @@ async fn user_registration_signup_verified_post(
         .await
     {
         error!("unable to save user email: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::error("error while verifying registration"));
+        return HttpResponse::from_error(ApiError::new(e, "error while verifying registration"));
     }
 
-    let ap = auth_provider_postgres::PostgresAuthProvider::new(&dp);
+    let ap = crate::endpoints::session::auth_provider(&dp, &cfg);
 
     // add user authentication using email and password
     if let Err(e) = ap
@@ -148,8 +160,7 @@ async fn user_registration_signup_verified_post(
         .await
     {
         error!("unable to add user authentication via password: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::error("error while verifying registration"));
+        return HttpResponse::from_error(ApiError::new(e, "error while verifying registration"));
     }
 
     return HttpResponse::Ok().json(ApiResponse::ok("success"));
@@ -203,15 +214,16 @@ mod tests {
         let ursp = UserRegistrationSignUpPost {
             id: uuid::Uuid::new_v4(),
             email: format!("test_{}@test.com", rand::random::<u16>()),
+            locale: None,
         };
         let params = web::Json(ursp);
 
         let cfg = config::Config::from_env();
-        let mailer = mailer::Mailer::new();
         let dp = database_provider::DatabaseProvider::new(&cfg);
 
         let _r = user_registration_signup_post(
-            web::Data::new(std::sync::Arc::new(mailer)),
+            actix_web::test::TestRequest::default().to_http_request(),
+            web::Data::new(std::sync::Arc::new(cfg)),
             web::Data::new(std::sync::Arc::new(dp)),
             params,
         )
@@ -6,7 +6,7 @@ use serde_json::json;
 
 use actix_web::{HttpResponse, Responder, http, web};
 
-use crate::endpoints::{ApiResponse, default_option_response};
+use crate::endpoints::{ApiError, ApiResponse, default_option_response};
 
 use auth_provider::AuthProvider;
 use users_provider::UsersProvider;
@@ -52,24 +52,23 @@ struct UsersCreatePost {
 }
 
 async fn users_create_post(
+    cfg: web::Data<Arc<config::Config>>,
     dp: web::Data<Arc<database_provider::DatabaseProvider>>,
     params: web::Json<UsersCreatePost>,
 ) -> impl Responder {
     info!("users_create_post");
 
-    let authp = auth_provider_postgres::PostgresAuthProvider::new(&dp);
+    let authp = crate::endpoints::session::auth_provider(&dp, &cfg);
     let up = users_provider_postgres::PostgresUsersProvider::new(&dp);
 
     if let Err(e) = up.save(&params.user_id, &"", &"", &"", &"", &"", &0).await {
         error!("unable to save user data: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::error("unable to save user data"));
+        return HttpResponse::from_error(ApiError::new(e, "unable to save user data"));
     }
 
     if let Err(e) = up.add_email(&params.user_id, &params.email).await {
         error!("unable to save user email: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::error("unable to save user email"));
+        return HttpResponse::from_error(ApiError::new(e, "unable to save user email"));
     }
 
     if let Err(e) = authp
@@ -77,8 +76,7 @@ async fn users_create_post(
         .await
     {
         error!("unable to add user account: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::error("unable to add user account"));
+        return HttpResponse::from_error(ApiError::new(e, "unable to add user account"));
     }
 
     return HttpResponse::Ok().json(ApiResponse::ok("successfully create user account"));
@@ -101,8 +99,7 @@ async fn users_set_active_post(
 
     if let Err(e) = up.set_active(&params.user_id, &params.active).await {
         error!("unable to set user active status: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::error("unable to set user active status"));
+        return HttpResponse::from_error(ApiError::new(e, "unable to set user active status"));
     }
 
     return HttpResponse::Ok().json(ApiResponse::ok("success"));
@@ -127,9 +124,7 @@ async fn users_set_active_multiple_post(
         .await
     {
         error!("unable to set multiple user active status: {}", e);
-        return HttpResponse::InternalServerError().json(ApiResponse::error(
-            "unable to set multiple user active status",
-        ));
+        return HttpResponse::from_error(ApiError::new(e, "unable to set multiple user active status"));
     }
 
     return HttpResponse::Ok().json(ApiResponse::ok("success"));
@@ -167,8 +162,7 @@ async fn users_fetch_post(
     match up.fetch(&params.filter).await {
         Err(e) => {
             error!("unable to fetch users: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to fetch users"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to fetch users"));
         }
         Ok(users) => {
             return HttpResponse::Ok().json(ApiResponse::new(
@@ -199,8 +193,7 @@ async fn users_tenants_assign_post(
     match up.tenant_assign(&params.user_ids, &params.tenant_ids).await {
         Err(e) => {
             error!("unable to assign users to tenants: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::error("unable to assign users to tenants"));
+            return HttpResponse::from_error(ApiError::new(e, "unable to assign users to tenants"));
         }
         Ok(_) => {
             return HttpResponse::Ok().json(ApiResponse::ok("//todo users_tenants_assign_post"));
@@ -0,0 +1,73 @@
+use tracing::info;
+
+use std::sync::Arc;
+
+use actix_web::{
+    http,
+    web,
+    HttpResponse,
+    Responder
+};
+
+
+use crate::endpoints::default_option_response;
+
+
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg
+        .service(
+            web::resource("jwks.json")
+                .route(web::method(http::Method::OPTIONS).to(default_option_response))
+                .route(web::get().to(jwks_get))
+        )
+    ;
+}
+
+
+
+/// public keys access tokens can be verified with, as a plain JWK set so
+/// other services can consume it with standard JWT libraries
+async fn jwks_get(
+    tg: web::Data<Arc<token::TokenGenerator>>
+) -> impl Responder {
+    info!("jwks_get");
+
+    return HttpResponse::Ok()
+        .append_header((http::header::CACHE_CONTROL, "public, max-age=300"))
+        .json(tg.jwks());
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::{App, test};
+
+    #[actix_web::test]
+    async fn test_jwks_endpoint() {
+        let Ok(key) = token::SigningKey::from_pem(
+            "ed-1",
+            include_bytes!("../../../libs/token/testdata/ed25519.pem")
+        ) else {
+            assert!(false, "unable to load test key");
+            return;
+        };
+        let tg = token::TokenGenerator::new("", 60, 3600).with_keys(vec![key]);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(tg)))
+                .service(web::scope("/.well-known").configure(config))
+        ).await;
+
+        let req = test::TestRequest::get().uri("/.well-known/jwks.json").to_request();
+        let value: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(value["keys"][0]["kid"], "ed-1");
+        assert_eq!(value["keys"][0]["kty"], "OKP");
+        assert!(value["keys"][0].get("d").is_none());
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use tracing::{error, info};
+
+use actix_web::{HttpRequest, http};
+
+use provider_error::ProviderError;
+use tenants_provider::templates::MailTemplatesProvider;
+
+/// render the system message `name` for `to` in `locale` and return it ready
+/// to be queued; overrides of the tenant are used when `tenant_id` is set,
+/// built-in templates otherwise
+pub async fn compose(
+    dp: &database_provider::DatabaseProvider,
+    tenant_id: Option<&uuid::Uuid>,
+    name: mailer::TemplateName,
+    locale: &str,
+    to: &str,
+    variables: &HashMap<&str, String>,
+) -> Result<outbox_provider::OutboxMessage, ProviderError> {
+    info!("compose");
+
+    let mut overrides = vec![];
+    if let Some(tenant_id) = tenant_id {
+        let mtp = tenants_provider_postgres::templates::MailTemplatesProviderPostgres::new(dp);
+        overrides = mtp
+            .templates_fetch(tenant_id, Some(name.as_str()))
+            .await?
+            .into_iter()
+            .map(|t| {
+                let template = mailer::Template::new(&t.locale, &t.subject, &t.text);
+                match t.html {
+                    Some(html) => template.with_html(&html),
+                    None => template,
+                }
+            })
+            .collect();
+    }
+
+    let rendered = mailer::Template::resolve(name, locale, &overrides)
+        .and_then(|template| template.render(variables))
+        .map_err(|e| {
+            error!("unable to render {} mail: {}", name.as_str(), e);
+            ProviderError::Internal
+        })?;
+
+    let message = outbox_provider::OutboxMessage::new(to, &rendered.subject, &rendered.text);
+    return Ok(match rendered.html {
+        Some(html) => message.with_html(&html),
+        None => message,
+    });
+}
+
+/// absolute link to `path` below the configured public url
+pub fn link(cfg: &config::Config, path: &str) -> String {
+    return format!("{}/{}", cfg.public_url(), path.trim_start_matches('/'));
+}
+
+/// first language of the `Accept-Language` header, the default locale when
+/// the header is missing
+pub fn request_locale(req: &HttpRequest) -> String {
+    return req
+        .headers()
+        .get(http::header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|tag| tag.split(';').next().unwrap_or_default().trim())
+        .filter(|tag| !tag.is_empty() && *tag != "*")
+        .map_or(String::from(mailer::template::DEFAULT_LOCALE), String::from);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_locale() {
+        let req = actix_web::test::TestRequest::default()
+            .insert_header((http::header::ACCEPT_LANGUAGE, "de-AT,de;q=0.9,en;q=0.8"))
+            .to_http_request();
+        assert_eq!(request_locale(&req), "de-AT");
+
+        let req = actix_web::test::TestRequest::default()
+            .insert_header((http::header::ACCEPT_LANGUAGE, "*"))
+            .to_http_request();
+        assert_eq!(request_locale(&req), mailer::template::DEFAULT_LOCALE);
+
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        assert_eq!(request_locale(&req), mailer::template::DEFAULT_LOCALE);
+    }
+
+    #[actix_web::test]
+    async fn test_compose() {
+        let cfg = config::Config::from_env();
+        let dp = database_provider::DatabaseProvider::new(&cfg);
+
+        let link = link(&cfg, "/user/sign-up/verified/abc");
+        assert_eq!(link, format!("{}/user/sign-up/verified/abc", cfg.public_url()));
+
+        let variables = HashMap::from([
+            ("email", String::from("user@example.com")),
+            ("link", link.clone()),
+        ]);
+        match compose(&dp, None, mailer::TemplateName::Verification, "de-DE", "user@example.com", &variables).await {
+            Err(e) => {
+                error!("{}", e);
+                assert!(false, "unable to compose verification mail");
+            }
+            Ok(message) => {
+                assert_eq!(message.to, vec![String::from("user@example.com")]);
+                assert!(message.subject.contains("E-Mail-Adresse"));
+                assert!(message.text.contains(&link));
+                assert!(message.html.is_some_and(|html| html.contains(&link)));
+            }
+        }
+    }
+}
@@ -7,7 +7,11 @@ mod classes;
 mod endpoints;
 mod extractors;
 mod guards;
+mod mail;
 mod middleware;
+mod migrations;
+mod store;
+mod workers;
 
 use tracing::{debug, error, info};
 use tracing_subscriber::FmtSubscriber;
@@ -18,8 +22,34 @@ use std::{collections::HashMap, hash::Hash};
 
 use actix_web::{App, HttpResponse, HttpServer, error, web};
 
+use clap::Parser;
+
 use database_provider::DatabaseProvider;
 
+#[derive(Debug, Parser)]
+#[command(name = "ws", about = "Nexus web service")]
+struct Cli {
+    #[command(flatten)]
+    config: config::CliArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// manage database schema migrations
+    Migrate {
+        #[command(subcommand)]
+        command: migrations::MigrateCommand,
+    },
+    /// manage the file store
+    Store {
+        #[command(subcommand)]
+        command: store::StoreCommand,
+    },
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // env_logger::init();
@@ -35,12 +65,98 @@ async fn main() -> std::io::Result<()> {
 
     info!("Starting up ...");
 
-    let cfg = config::Config::from_env();
+    let cli = Cli::parse();
+
+    let cfg = match config::Config::load(&cli.config) {
+        Err(e) => {
+            error!("refusing to start: {}", e);
+            return Err(std::io::Error::other(e));
+        }
+        Ok(cfg) => cfg,
+    };
     debug!("config: {:?}", cfg);
 
     let db_provider = database_provider::DatabaseProvider::new(&cfg);
 
-    let token_generator = token::TokenGenerator::new(&cfg.token_secret());
+    if let Some(Command::Migrate { command }) = &cli.command {
+        return migrations::run(&db_provider, command)
+            .await
+            .map_err(std::io::Error::other);
+    }
+
+    let file_store = match store::open(&cfg) {
+        Err(e) => {
+            error!("refusing to start: {}", e);
+            return Err(std::io::Error::other(e));
+        }
+        Ok(file_store) => file_store,
+    };
+
+    if let Some(Command::Store { command }) = &cli.command {
+        return store::run(&file_store, command)
+            .await
+            .map_err(std::io::Error::other);
+    }
+
+    if cfg.migrate_on_startup()
+        && let Err(e) = migrations::migrate_on_startup(&db_provider).await
+    {
+        error!("refusing to start: {}", e);
+        return Err(std::io::Error::other(e));
+    }
+
+    let mut signing_keys = Vec::new();
+    for key in cfg.token_keys() {
+        match token::SigningKey::from_pem_file(&key.kid, &key.path) {
+            Err(e) => {
+                error!("refusing to start: token key {}: {}", key.kid, e);
+                return Err(std::io::Error::other(e));
+            }
+            Ok(signing_key) => {
+                info!("loaded {:?} token key {}", signing_key.algorithm(), key.kid);
+                signing_keys.push(signing_key);
+            }
+        }
+    }
+
+    let token_generator = token::TokenGenerator::new(
+        &cfg.token_secret(),
+        cfg.access_token_lifetime(),
+        cfg.refresh_token_lifetime(),
+    )
+    .with_keys(signing_keys);
+
+    let mailer = match mailer::Mailer::from_config(&cfg.mail()) {
+        Err(e) => {
+            error!("refusing to start: {}", e);
+            return Err(std::io::Error::other(e));
+        }
+        Ok(mailer) => mailer,
+    };
+
+    let file_store: Arc<dyn file_store::FileStore> = Arc::new(file_store);
+
+    actix_web::rt::spawn(crate::workers::outbox::run(
+        db_provider.clone(),
+        Arc::new(mailer.clone()),
+        cfg.outbox(),
+    ));
+
+    actix_web::rt::spawn(crate::workers::previews::run(
+        db_provider.clone(),
+        file_store.clone(),
+        cfg.previews(),
+    ));
+
+    actix_web::rt::spawn(crate::workers::search::run(
+        db_provider.clone(),
+        file_store.clone(),
+        cfg.search(),
+    ));
+
+    let bind_address = cfg.bind_address();
+    let http_port = cfg.http_port();
+    let workers = cfg.workers();
 
     let mut http_server = HttpServer::new(move || {
         let app = App::new()
@@ -51,8 +167,9 @@ async fn main() -> std::io::Result<()> {
                 crate::middleware::auth::auth_middleware,
             ))
             .app_data(web::Data::new(Arc::new(cfg.clone())))
-            .app_data(web::Data::new(Arc::new(mailer::Mailer::new())))
+            .app_data(web::Data::new(Arc::new(mailer.clone())))
             .app_data(web::Data::new(Arc::new(db_provider.clone())))
+            .app_data(web::Data::new(file_store.clone()))
             .app_data(web::Data::new(Arc::new(token_generator.clone())))
             .app_data(web::JsonConfig::default().error_handler(|err, _req| {
                 error!("JSON PARSE ERROR: {}", err);
@@ -71,6 +188,9 @@ async fn main() -> std::io::Result<()> {
             //     debug!("PATH ERROR: {}", err);
             //     return actix_web::error::ErrorBadRequest(err);
             // }))
+            .service(
+                web::scope("/.well-known").configure(crate::endpoints::well_known::config),
+            )
             .service(web::scope("/api/v1/common").configure(crate::endpoints::common::config))
             .service(web::scope("/api/v1/session").configure(crate::endpoints::session::config))
             .service(
@@ -92,8 +212,17 @@ async fn main() -> std::io::Result<()> {
             .service(
                 web::scope("/api/v1/admin/users").configure(crate::endpoints::admin::users::config),
             )
+            .service(
+                web::scope("/api/v1/admin/outbox")
+                    .configure(crate::endpoints::admin::outbox::config),
+            )
+            .service(
+                web::scope("/api/v1/admin/templates")
+                    .configure(crate::endpoints::admin::templates::config),
+            )
             // .service(web::scope("/documents").configure(crate::endpoints::documents::config))
             .service(web::scope("/api/v1/file").configure(crate::endpoints::file::config))
+            .service(web::scope("/api/v1/share").configure(crate::endpoints::share::config))
             .service(
                 web::scope("/api/v1/acctg/accounts")
                     .configure(crate::endpoints::acctg::accounts::config),
@@ -122,11 +251,11 @@ async fn main() -> std::io::Result<()> {
 
         return app;
     })
-    .workers(2);
+    .workers(workers);
 
-    http_server = http_server.bind("localhost:8080")?;
+    http_server = http_server.bind((bind_address.as_str(), http_port))?;
 
-    info!("Starting server...");
+    info!("Starting server on {}:{} ...", bind_address, http_port);
 
     let server = http_server.run();
     return server.await;
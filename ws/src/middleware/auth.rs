@@ -22,6 +22,7 @@ use actix_http::{
 use futures::try_join;
 
 
+use auth_provider::tokens::TokensProvider;
 use users_provider::UsersProvider;
 use tenants_provider::TenantsProvider;
 
@@ -55,7 +56,8 @@ async fn get_user_from_request(
 ) -> user::User {
     info!("get_user_from_request");
 
-    if req.method() != Method::POST {
+    // preflight requests carry no credentials
+    if req.method() == Method::OPTIONS {
         return user::User::anonymous();
     }
 
@@ -68,6 +70,9 @@ async fn get_user_from_request(
 
         let mut user_id = uuid::Uuid::nil();
         let mut tenant_id = uuid::Uuid::nil();
+        let mut token_id = uuid::Uuid::nil();
+        let mut token_expires = 0;
+        let mut token_issued = 0;
 
         if let Some(tg) = req.app_data::<web::Data<Arc<token::TokenGenerator>>>() {
             let claim = match tg.parse_token(&token) {
@@ -77,6 +82,9 @@ async fn get_user_from_request(
             if !claim.is_empty() {
                 user_id = claim.user_id;
                 tenant_id = claim.tenant_id;
+                token_id = claim.token_id;
+                token_expires = claim.expires;
+                token_issued = claim.issued;
             }
         }
 
@@ -84,17 +92,23 @@ async fn get_user_from_request(
             let dp = dp_ref.get_ref();
             let up = users_provider_postgres::PostgresUsersProvider::new(&dp);
             let tp = tenants_provider_postgres::PostgresTenantsProvider::new(&dp);
+            let tkp = auth_provider_postgres::tokens::PostgresTokensProvider::new(dp);
 
             let f1 = up.fetch_by_id(&user_id);
             let f2 = tp.tenant_user_tenants_fetch(&user_id);
             let f3 = tp.tenants_fetch_by_id(&tenant_id);
             let f4 = tp.tenant_user_permissions_fetch(&user_id, &tenant_id);
+            let issued = chrono::DateTime::from_timestamp(token_issued, 0).unwrap_or_default();
+            let f5 = tkp.access_token_is_denied(&token_id, &user_id, &issued);
 
-            match try_join!(f1, f2, f3, f4) {
+            match try_join!(f1, f2, f3, f4, f5) {
                 Err(e) => {
                     error!("unable to fetch user or tenant data for user: {:?}", e);
                 }
-                Ok((user, tenants, tenant, permissions)) => {
+                Ok((_, _, _, _, true)) => {
+                    debug!("access token has been revoked");
+                }
+                Ok((user, tenants, tenant, permissions, false)) => {
                     let ts: Vec<tenant::Tenant> = tenants.iter().map(|t| {
                         let tenant_id = t.tenant_id();
                         let name = t.name();
@@ -130,7 +144,7 @@ async fn get_user_from_request(
                         &user.email,
                         &ts,
                         &ps
-                    );
+                    ).with_token(&token_id, token_expires);
 
                     debug!("returning authenticated user: {:?}", u);
                     return u;
@@ -1,4 +1,5 @@
 use actix_http::header::HeaderValue;
+use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
 use actix_web::{
@@ -17,22 +18,26 @@ pub async fn cors_middleware(
     // pre-processing
     debug!("method: {:?}", req.method());
 
+    let allowed_origin = allowed_origin(&req);
+
     // invoke the wrapped middleware or service
     let mut res = next.call(req).await?;
     // debug!("res: {:?}", res.status());
 
     // post-processing
-    res.headers_mut().append(
-        header::ACCESS_CONTROL_ALLOW_ORIGIN,
-        HeaderValue::from_static("*"),
-    );
+    if let Some(origin) = allowed_origin {
+        res.headers_mut()
+            .append(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        res.headers_mut()
+            .append(header::VARY, HeaderValue::from_static("origin"));
+    }
     res.headers_mut().append(
         header::ACCESS_CONTROL_ALLOW_METHODS,
-        HeaderValue::from_static("POST, OPTIONS"),
+        HeaderValue::from_static("GET, POST, HEAD, PATCH, DELETE, OPTIONS"),
     );
     res.headers_mut().append(
         header::ACCESS_CONTROL_ALLOW_HEADERS,
-        HeaderValue::from_static("content-type, authorization"),
+        HeaderValue::from_static("content-type, authorization, range, if-none-match, if-range, x-share-password, tus-resumable, upload-length, upload-offset, upload-metadata"),
     );
     res.headers_mut().append(
         header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
@@ -40,10 +45,35 @@ pub async fn cors_middleware(
     );
     res.headers_mut().append(
         header::ACCESS_CONTROL_EXPOSE_HEADERS,
-        HeaderValue::from_static("authorization"),
+        HeaderValue::from_static("authorization, content-disposition, content-range, accept-ranges, etag, location, tus-resumable, tus-version, tus-extension, tus-max-size, upload-offset, upload-length, upload-expires, x-file-id"),
     );
 
     // debug!("post: cors_middleware");
 
     Ok(res)
 }
+
+/// value for the `Access-Control-Allow-Origin` header, `*` in the configured
+/// origins allows any origin
+fn allowed_origin(req: &ServiceRequest) -> Option<HeaderValue> {
+    let origins = match req.app_data::<web::Data<Arc<config::Config>>>() {
+        None => vec![String::from("*")],
+        Some(cfg) => cfg.cors_origins(),
+    };
+
+    if origins.iter().any(|o| o == "*") {
+        return Some(HeaderValue::from_static("*"));
+    }
+
+    let origin = req.headers().get(header::ORIGIN)?;
+    let matched = origin
+        .to_str()
+        .is_ok_and(|o| origins.iter().any(|allowed| allowed.eq_ignore_ascii_case(o)));
+
+    if matched {
+        return Some(origin.clone());
+    }
+
+    debug!("origin not allowed: {:?}", origin);
+    return None;
+}
@@ -102,7 +102,7 @@ where
 
         // if the endpoint is protected by a permission
         if !requested_permission.is_empty()
-            && req.method() == Method::POST
+            && req.method() != Method::OPTIONS
         {
             if user.is_anonymous() {
                 debug!("user is anonymous");
@@ -0,0 +1,100 @@
+use tracing::{error, info};
+
+use database_provider::{
+    DatabaseProvider,
+    migrations::{MigrationSet, Migrator},
+};
+
+/// migration sets of all provider crates, ordered so that a component
+/// only references schemas created by components listed before it
+pub fn sets() -> Vec<MigrationSet> {
+    return vec![
+        commons_provider_postgres::MIGRATIONS,
+        outbox_provider_postgres::MIGRATIONS,
+        users_provider_postgres::MIGRATIONS,
+        auth_provider_postgres::MIGRATIONS,
+        user_registration_postgres::MIGRATIONS,
+        permissions_provider_postgres::MIGRATIONS,
+        tenants_provider_postgres::MIGRATIONS,
+        roles_provider_postgres::MIGRATIONS,
+        organizations_provider_postgres::MIGRATIONS,
+        crm_provider_postgres::MIGRATIONS,
+        acctg_provider_postgres::MIGRATIONS,
+        inv_provider_postgres::MIGRATIONS,
+        file_provider_postgres::MIGRATIONS,
+    ];
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum MigrateCommand {
+    /// apply all pending migrations
+    Up,
+    /// revert the most recent migrations of a component
+    Down {
+        /// component to revert, e.g. `acctg`
+        #[arg(long)]
+        component: String,
+
+        /// number of migrations to revert
+        #[arg(long, default_value_t = 1)]
+        steps: usize,
+    },
+    /// list known migrations and whether they are applied
+    Status,
+}
+
+pub async fn run(dp: &DatabaseProvider, command: &MigrateCommand) -> Result<(), &'static str> {
+    info!("run");
+
+    let migrator = Migrator::new(dp, sets());
+
+    match command {
+        MigrateCommand::Up => {
+            let count = migrator.up().await?;
+            info!("applied {} migration(s)", count);
+        }
+        MigrateCommand::Down { component, steps } => {
+            let count = migrator.down(component, *steps).await?;
+            info!("reverted {} migration(s) of {}", count, component);
+        }
+        MigrateCommand::Status => {
+            let statuses = migrator.status().await?;
+            print_status(&statuses);
+        }
+    }
+
+    return Ok(());
+}
+
+/// applies pending migrations before the server starts
+pub async fn migrate_on_startup(dp: &DatabaseProvider) -> Result<(), &'static str> {
+    info!("migrate_on_startup");
+
+    match Migrator::new(dp, sets()).up().await {
+        Err(e) => {
+            error!("unable to apply migrations: {}", e);
+            return Err(e);
+        }
+        Ok(count) => {
+            info!("applied {} migration(s)", count);
+            return Ok(());
+        }
+    }
+}
+
+#[allow(clippy::print_stdout)]
+fn print_status(statuses: &[database_provider::migrations::MigrationStatus]) {
+    println!("{:<20} {:>7}  {:<24} {:<10} applied", "component", "version", "name", "state");
+    for s in statuses {
+        let state = match (s.applied, s.checksum_matches) {
+            (false, _) => "pending",
+            (true, true) => "applied",
+            (true, false) => "modified",
+        };
+        let applied_ts = s.applied_ts.map(|ts| ts.to_rfc3339()).unwrap_or_default();
+        println!(
+            "{:<20} {:>7}  {:<24} {:<10} {}",
+            s.component, s.version, s.name, state, applied_ts
+        );
+    }
+}
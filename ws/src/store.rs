@@ -0,0 +1,48 @@
+use tracing::{error, info};
+
+use file_store_local::{LocalFileStore, MasterKey};
+
+#[derive(Debug, clap::Subcommand)]
+pub enum StoreCommand {
+    /// rewrap the data keys of all tenants with the newest master key
+    RotateKeys,
+}
+
+/// the local file store, encrypting content when master keys are
+/// configured
+pub fn open(cfg: &config::Config) -> Result<LocalFileStore, String> {
+    info!("open");
+
+    let mut master_keys = Vec::new();
+    for key in cfg.file_store_master_keys() {
+        match MasterKey::from_file(&key.id, &key.path) {
+            Err(e) => {
+                error!("unable to load master key {}: {}", key.id, e);
+                return Err(format!("master key {}: {}", key.id, e));
+            }
+            Ok(master_key) => {
+                info!("loaded master key {}", master_key.id());
+                master_keys.push(master_key);
+            }
+        }
+    }
+    if master_keys.is_empty() {
+        info!("no master key configured, files are stored unencrypted");
+    }
+
+    return Ok(LocalFileStore::new(&cfg.file_store_root()).with_master_keys(master_keys));
+}
+
+pub async fn run(store: &LocalFileStore, command: &StoreCommand) -> Result<(), String> {
+    info!("run");
+
+    match command {
+        StoreCommand::RotateKeys => {
+            let count = store.rotate_keys().await
+                .map_err(|e| format!("unable to rotate keys: {e}"))?;
+            info!("rewrapped {} data key(s)", count);
+        }
+    }
+
+    return Ok(());
+}
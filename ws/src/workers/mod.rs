@@ -0,0 +1,3 @@
+pub mod outbox;
+pub mod previews;
+pub mod search;
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{debug, error, info};
+
+use outbox_provider::{OutboxEntry, OutboxProvider};
+
+/// messages claimed per poll
+const BATCH_SIZE: i32 = 20;
+/// seconds a claimed message stays hidden from other workers, long enough
+/// to cover a slow smtp server
+const LEASE: i32 = 300;
+
+/// delivers queued mail until the process exits, every instance may run a
+/// worker since claimed messages are leased
+pub async fn run(
+    dp: database_provider::DatabaseProvider,
+    mailer: Arc<mailer::Mailer>,
+    cfg: config::OutboxConfig,
+) {
+    info!("run");
+
+    let op = outbox_provider_postgres::PostgresOutboxProvider::new(&dp);
+    let poll_interval = Duration::from_secs(cfg.poll_interval.unsigned_abs());
+
+    loop {
+        match op.claim_due(BATCH_SIZE, LEASE).await {
+            Err(e) => {
+                error!("unable to claim outbox messages: {}", e);
+            }
+            Ok(entries) => {
+                for entry in &entries {
+                    deliver(&op, &mailer, &cfg, entry).await;
+                }
+
+                // a full batch means there may be more messages due
+                if usize::try_from(BATCH_SIZE).is_ok_and(|size| entries.len() >= size) {
+                    continue;
+                }
+            }
+        }
+
+        actix_web::rt::time::sleep(poll_interval).await;
+    }
+}
+
+async fn deliver(
+    op: &outbox_provider_postgres::PostgresOutboxProvider,
+    mailer: &mailer::Mailer,
+    cfg: &config::OutboxConfig,
+    entry: &OutboxEntry,
+) {
+    debug!("delivering outbox message {}", entry.message_id);
+
+    let message = mailer::Message {
+        from: entry.message.from.clone(),
+        to: entry.message.to.clone(),
+        subject: entry.message.subject.clone(),
+        text: entry.message.text.clone(),
+        html: entry.message.html.clone(),
+        attachments: vec![],
+    };
+
+    let result = match mailer.send(&message).await {
+        Ok(()) => op.mark_sent(&entry.message_id).await,
+        Err(e) => {
+            let attempts = entry.attempts + 1;
+            if attempts >= cfg.max_attempts {
+                error!(
+                    "giving up on outbox message {} after {} attempts: {}",
+                    entry.message_id, attempts, e
+                );
+                op.mark_failed(&entry.message_id, &e.to_string(), None).await
+            } else {
+                let retry_at = chrono::Utc::now() + chrono::Duration::seconds(retry_delay(cfg, attempts));
+                info!(
+                    "outbox message {} failed ({}), retrying at {}",
+                    entry.message_id, e, retry_at
+                );
+                op.mark_failed(&entry.message_id, &e.to_string(), Some(&retry_at)).await
+            }
+        }
+    };
+
+    if let Err(e) = result {
+        error!("unable to update outbox message {}: {}", entry.message_id, e);
+    }
+}
+
+/// seconds to wait after the `attempts`th failed attempt, doubling from
+/// `backoff` up to `max_backoff`
+fn retry_delay(cfg: &config::OutboxConfig, attempts: i32) -> i64 {
+    let exponent = u32::try_from(attempts.max(1) - 1).unwrap_or(0).min(32);
+    return cfg
+        .backoff
+        .saturating_mul(1_i64 << exponent)
+        .min(cfg.max_backoff);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        let cfg = config::OutboxConfig {
+            poll_interval: 10,
+            max_attempts: 8,
+            backoff: 30,
+            max_backoff: 600,
+        };
+
+        assert_eq!(retry_delay(&cfg, 1), 30);
+        assert_eq!(retry_delay(&cfg, 2), 60);
+        assert_eq!(retry_delay(&cfg, 3), 120);
+        assert_eq!(retry_delay(&cfg, 6), 600);
+        assert_eq!(retry_delay(&cfg, 100), 600);
+    }
+}
@@ -0,0 +1,395 @@
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{debug, error, info};
+
+use file_provider::{FileProvider, PreviewJob};
+
+/// previews claimed per poll
+const BATCH_SIZE: i32 = 5;
+/// attempts before a preview is given up
+const MAX_ATTEMPTS: i32 = 3;
+/// seconds before a failed preview is tried again
+const RETRY_DELAY: i64 = 300;
+/// largest image accepted from the preview command
+const MAX_PREVIEW_SIZE: u64 = 4 * 1024 * 1024;
+/// most of the preview command's error output kept
+const MAX_ERROR_SIZE: u64 = 64 * 1024;
+/// image types the preview command may write
+const PREVIEW_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/webp", "image/gif"];
+/// image types scaled down in process, everything else is left to the
+/// preview command
+const THUMBNAIL_TYPES: [&str; 6] = ["image/png", "image/jpeg", "image/gif", "image/webp", "image/bmp", "image/tiff"];
+/// largest image scaled down in process
+const MAX_IMAGE_SIZE: u64 = 64 * 1024 * 1024;
+/// largest width and height of an image scaled down in process
+const MAX_IMAGE_DIMENSION: u32 = 16384;
+/// most memory allocated while decoding an image
+const MAX_IMAGE_ALLOC: u64 = 512 * 1024 * 1024;
+
+/// renders previews until the process exits, every instance may run a
+/// worker since claimed previews are leased; images are scaled down in
+/// process, pdf documents need a preview command
+pub async fn run(
+    dp: database_provider::DatabaseProvider,
+    store: Arc<dyn file_store::FileStore>,
+    cfg: config::PreviewConfig,
+) {
+    info!("run");
+
+    if cfg.command.is_empty() {
+        info!("no preview command configured, only images get a preview");
+    }
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let poll_interval = Duration::from_secs(cfg.poll_interval.unsigned_abs());
+    // long enough for every preview of a batch to time out
+    let lease = i32::try_from(cfg.timeout.saturating_mul(i64::from(BATCH_SIZE) + 1)).unwrap_or(i32::MAX);
+
+    loop {
+        match fp.previews_claim(BATCH_SIZE, lease).await {
+            Err(e) => {
+                error!("unable to claim previews: {}", e);
+            }
+            Ok(jobs) => {
+                for job in &jobs {
+                    process(&fp, store.as_ref(), &cfg, job).await;
+                }
+
+                // a full batch means there may be more previews pending
+                if usize::try_from(BATCH_SIZE).is_ok_and(|size| jobs.len() >= size) {
+                    continue;
+                }
+            }
+        }
+
+        actix_web::rt::time::sleep(poll_interval).await;
+    }
+}
+
+async fn process(
+    fp: &file_provider_postgres::PostgresFileProvider,
+    store: &dyn file_store::FileStore,
+    cfg: &config::PreviewConfig,
+    job: &PreviewJob,
+) {
+    debug!("rendering preview of {}", job.digest);
+
+    let rendered = match render(store, cfg, job).await {
+        Err(e) => Err(e),
+        Ok((image, content_type)) => {
+            let data: file_store::ByteStream = Box::pin(futures::stream::once(async move {
+                return Ok(actix_web::web::Bytes::from(image));
+            }));
+            match store.put_preview(job.tenant_id, &job.digest, data).await {
+                Err(e) => Err(format!("unable to store preview: {e}")),
+                Ok(()) => Ok(content_type),
+            }
+        }
+    };
+
+    let result = match rendered {
+        Ok(content_type) => fp.preview_ready(&job.tenant_id, &job.digest, content_type).await,
+        Err(e) => {
+            let attempts = job.attempts + 1;
+            if attempts >= MAX_ATTEMPTS {
+                error!("giving up on preview of {} after {} attempts: {}", job.digest, attempts, e);
+                fp.preview_failed(&job.tenant_id, &job.digest, &e, None).await
+            } else {
+                let retry_at = chrono::Utc::now() + chrono::Duration::seconds(RETRY_DELAY);
+                info!("preview of {} failed ({}), retrying at {}", job.digest, e, retry_at);
+                fp.preview_failed(&job.tenant_id, &job.digest, &e, Some(&retry_at)).await
+            }
+        }
+    };
+
+    if let Err(e) = result {
+        error!("unable to update preview of {}: {}", job.digest, e);
+    }
+}
+
+/// renders the preview of a job; returns the image and its content type
+async fn render(
+    store: &dyn file_store::FileStore,
+    cfg: &config::PreviewConfig,
+    job: &PreviewJob,
+) -> Result<(Vec<u8>, &'static str), String> {
+    if THUMBNAIL_TYPES.contains(&job.source_type.as_str()) {
+        return thumbnail(store, cfg.size, job).await;
+    }
+    if cfg.command.is_empty() {
+        return Err(format!("no preview command configured for {}", job.source_type));
+    }
+    return render_command(store, cfg, job).await;
+}
+
+/// reads the image into memory and scales it down to fit `size` on a
+/// blocking thread, the preview is always a png
+async fn thumbnail(
+    store: &dyn file_store::FileStore,
+    size: u32,
+    job: &PreviewJob,
+) -> Result<(Vec<u8>, &'static str), String> {
+    let stat = store.stat(job.tenant_id, &job.digest).await
+        .map_err(|e| format!("unable to read content: {e}"))?;
+    if stat.size > MAX_IMAGE_SIZE {
+        return Err(format!("image of {} bytes is too large", stat.size));
+    }
+
+    let mut data = Vec::with_capacity(usize::try_from(stat.size).unwrap_or_default());
+    let mut stream = store.get(job.tenant_id, &job.digest).await
+        .map_err(|e| format!("unable to read content: {e}"))?;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("unable to read content: {e}"))?;
+        data.extend_from_slice(&chunk);
+        if data.len() as u64 > MAX_IMAGE_SIZE {
+            return Err(String::from("image is too large"));
+        }
+    }
+
+    return match actix_web::rt::task::spawn_blocking(move || scale(&data, size)).await {
+        Err(e) => Err(format!("scaling the image panicked: {e}")),
+        Ok(Err(e)) => Err(format!("unable to scale image: {e}")),
+        Ok(Ok(image)) => Ok((image, "image/png")),
+    };
+}
+
+/// decodes an image within the size limits, turns it upright and scales it
+/// down to fit `size`; smaller images keep their size
+fn scale(data: &[u8], size: u32) -> Result<Vec<u8>, image::ImageError> {
+    use image::ImageDecoder;
+
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+    limits.max_alloc = Some(MAX_IMAGE_ALLOC);
+
+    let mut reader = image::ImageReader::new(std::io::Cursor::new(data)).with_guessed_format()?;
+    reader.limits(limits);
+    let mut decoder = reader.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = image::DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    if image.width() > size || image.height() > size {
+        image = image.thumbnail(size, size);
+    }
+    // png takes 8 bit channels, deeper ones are not worth keeping in a preview
+    if !matches!(image.color(), image::ColorType::L8 | image::ColorType::La8 | image::ColorType::Rgb8 | image::ColorType::Rgba8) {
+        image = image::DynamicImage::ImageRgba8(image.to_rgba8());
+    }
+
+    let mut png = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+    return Ok(png);
+}
+
+/// runs the preview command with the content on stdin and reads the image
+/// from stdout, the decrypted content is never written to disk; returns the
+/// image and its content type
+async fn render_command(
+    store: &dyn file_store::FileStore,
+    cfg: &config::PreviewConfig,
+    job: &PreviewJob,
+) -> Result<(Vec<u8>, &'static str), String> {
+    let mut data = store.get(job.tenant_id, &job.digest).await
+        .map_err(|e| format!("unable to read content: {e}"))?;
+
+    let mut child = tokio::process::Command::new(&cfg.command)
+        .arg(cfg.size.to_string())
+        .arg(&job.source_type)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("unable to run {}: {}", cfg.command, e))?;
+    let (Some(mut stdin), Some(stdout), Some(stderr)) = (child.stdin.take(), child.stdout.take(), child.stderr.take()) else {
+        return Err(format!("unable to connect to {}", cfg.command));
+    };
+
+    // the content is written while the image is read, a command may stop
+    // reading once it has seen enough of the content
+    let write = async move {
+        while let Some(chunk) = data.next().await {
+            let chunk = chunk.map_err(|e| format!("unable to read content: {e}"))?;
+            match stdin.write_all(&chunk).await {
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+                Err(e) => return Err(format!("unable to write to {}: {}", cfg.command, e)),
+                Ok(()) => {}
+            }
+        }
+        return Ok(());
+    };
+    let read = async move {
+        let mut image = Vec::new();
+        stdout.take(MAX_PREVIEW_SIZE + 1).read_to_end(&mut image).await
+            .map_err(|e| format!("unable to read from {}: {}", cfg.command, e))?;
+        return Ok::<_, String>(image);
+    };
+    let read_stderr = async move {
+        let mut message = Vec::new();
+        let _ = stderr.take(MAX_ERROR_SIZE).read_to_end(&mut message).await;
+        return message;
+    };
+    let run = async {
+        let (written, image, message) = tokio::join!(write, read, read_stderr);
+        let status = child.wait().await.map_err(|e| format!("unable to run {}: {}", cfg.command, e))?;
+        return Ok::<_, String>((written, image, message, status));
+    };
+
+    let timeout = Duration::from_secs(cfg.timeout.unsigned_abs());
+    let (written, image, message, status) = match actix_web::rt::time::timeout(timeout, run).await {
+        Err(_) => return Err(format!("preview command timed out after {} seconds", cfg.timeout)),
+        Ok(finished) => finished?,
+    };
+    if !status.success() {
+        let message = String::from_utf8_lossy(&message);
+        return Err(format!(
+            "preview command failed ({}): {}",
+            status,
+            message.lines().last().unwrap_or_default()
+        ));
+    }
+    written?;
+    let image = image?;
+
+    if image.is_empty() {
+        return Err(String::from("no preview written"));
+    }
+    if image.len() as u64 > MAX_PREVIEW_SIZE {
+        return Err(String::from("preview is too large"));
+    }
+
+    let content_type = file_store::sniff::sniff(&image);
+    if !PREVIEW_TYPES.contains(&content_type) {
+        return Err(format!("preview command wrote {content_type} instead of an image"));
+    }
+    return Ok((image, content_type));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::fs::PermissionsExt;
+
+    use file_store::FileStore;
+
+    /// a preview command that runs `script` with the arguments in `$1` and `$2`
+    fn command(dir: &std::path::Path, script: &str) -> config::PreviewConfig {
+        let path = dir.join("preview.sh");
+        let written = std::fs::write(&path, format!("#!/bin/sh\n{script}\n"))
+            .and_then(|()| std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)));
+        assert!(written.is_ok(), "unable to write preview command");
+
+        return config::PreviewConfig {
+            command: path.to_string_lossy().to_string(),
+            size: 64,
+            timeout: 5,
+            poll_interval: 10,
+        };
+    }
+
+    #[actix_web::test]
+    async fn test_render() {
+        let dir = std::env::temp_dir().join(format!("preview_test_{}", uuid::Uuid::new_v4().simple()));
+        assert!(std::fs::create_dir_all(&dir).is_ok());
+        let store = file_store_local::LocalFileStore::new(&dir.join("store").to_string_lossy());
+
+        let tenant_id = uuid::Uuid::new_v4();
+        let content: file_store::ByteStream = Box::pin(futures::stream::once(async {
+            return Ok(actix_web::web::Bytes::from_static(b"\x89PNG\r\n\x1a\n-image-"));
+        }));
+        let stat = store.put(tenant_id, content).await;
+        assert!(stat.is_ok(), "unable to store content");
+        let job = PreviewJob {
+            tenant_id,
+            digest: stat.map(|s| s.digest).unwrap_or_default(),
+            source_type: String::from("application/pdf"),
+            attempts: 0,
+        };
+
+        // the command gets the size and the content type, the content on
+        // stdin and writes the preview to stdout
+        let cfg = command(&dir, "[ \"$1 $2\" = \"64 application/pdf\" ] && cat");
+        assert_eq!(
+            render(&store, &cfg, &job).await,
+            Ok((b"\x89PNG\r\n\x1a\n-image-".to_vec(), "image/png"))
+        );
+
+        let cfg = command(&dir, "echo 'no decoder' >&2; exit 3");
+        assert!(render(&store, &cfg, &job).await.is_err_and(|e| e.contains("no decoder")));
+
+        // only images are accepted as preview
+        let cfg = command(&dir, "echo '<html>'");
+        assert!(render(&store, &cfg, &job).await.is_err_and(|e| e.contains("text/html")));
+
+        let cfg = command(&dir, "exit 0");
+        assert!(render(&store, &cfg, &job).await.is_err_and(|e| e.contains("no preview written")));
+
+        // a command need not read all of the content
+        let content: file_store::ByteStream = Box::pin(futures::stream::once(async {
+            return Ok(actix_web::web::Bytes::from(vec![b'%'; 1024 * 1024]));
+        }));
+        let stat = store.put(tenant_id, content).await;
+        let large = PreviewJob { digest: stat.map(|s| s.digest).unwrap_or_default(), ..job.clone() };
+        let cfg = command(&dir, "head -c 4 > /dev/null; printf '\\211PNG\\r\\n\\032\\n'");
+        assert_eq!(render(&store, &cfg, &large).await, Ok((b"\x89PNG\r\n\x1a\n".to_vec(), "image/png")));
+
+        let cfg = config::PreviewConfig { command: String::new(), ..cfg };
+        assert!(render(&store, &cfg, &job).await.is_err_and(|e| e.contains("no preview command")));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[actix_web::test]
+    async fn test_thumbnail() {
+        let dir = std::env::temp_dir().join(format!("preview_test_{}", uuid::Uuid::new_v4().simple()));
+        let store = file_store_local::LocalFileStore::new(&dir.to_string_lossy());
+        let tenant_id = uuid::Uuid::new_v4();
+
+        let mut png = Vec::new();
+        let image = image::DynamicImage::ImageRgb8(image::RgbImage::new(300, 150));
+        assert!(image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).is_ok());
+
+        let mut jobs = Vec::new();
+        for content in [png, b"\x89PNG\r\n\x1a\n-broken-".to_vec()] {
+            let data: file_store::ByteStream = Box::pin(futures::stream::once(async move {
+                return Ok(actix_web::web::Bytes::from(content));
+            }));
+            let stat = store.put(tenant_id, data).await;
+            assert!(stat.is_ok(), "unable to store content");
+            jobs.push(PreviewJob {
+                tenant_id,
+                digest: stat.map(|s| s.digest).unwrap_or_default(),
+                source_type: String::from("image/png"),
+                attempts: 0,
+            });
+        }
+
+        // images are scaled down without a preview command
+        let cfg = config::PreviewConfig {
+            command: String::new(),
+            size: 64,
+            timeout: 5,
+            poll_interval: 10,
+        };
+        let rendered = render(&store, &cfg, &jobs[0]).await;
+        assert!(rendered.as_ref().is_ok_and(|(_, content_type)| *content_type == "image/png"));
+        let preview = rendered.ok().and_then(|(preview, _)| image::load_from_memory(&preview).ok());
+        assert_eq!(preview.map(|p| (p.width(), p.height())), Some((64, 32)));
+
+        assert!(render(&store, &cfg, &jobs[1]).await.is_err_and(|e| e.contains("unable to scale image")));
+
+        // smaller images keep their size
+        let cfg = config::PreviewConfig { size: 512, ..cfg };
+        let preview = render(&store, &cfg, &jobs[0]).await.ok().and_then(|(preview, _)| image::load_from_memory(&preview).ok());
+        assert_eq!(preview.map(|p| (p.width(), p.height())), Some((300, 150)));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,173 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use tracing::{debug, error, info};
+
+use file_provider::{FileProvider, TextJob};
+
+/// texts claimed per poll
+const BATCH_SIZE: i32 = 10;
+/// attempts before a text is given up
+const MAX_ATTEMPTS: i32 = 3;
+/// seconds before a failed text is tried again
+const RETRY_DELAY: i64 = 300;
+/// seconds a claimed batch is leased to this worker
+const LEASE: i32 = 600;
+
+/// why no text was extracted
+#[derive(Debug, PartialEq)]
+enum Failure {
+    /// the content could not be read, it is tried again
+    Retry(String),
+    /// no text will ever be extracted from the content
+    GiveUp(String),
+}
+
+/// extracts the text of stored documents for the search index until the
+/// process exits, every instance may run a worker since claimed texts are
+/// leased
+pub async fn run(
+    dp: database_provider::DatabaseProvider,
+    store: Arc<dyn file_store::FileStore>,
+    cfg: config::SearchConfig,
+) {
+    info!("run");
+
+    let fp = file_provider_postgres::PostgresFileProvider::new(&dp);
+    let poll_interval = Duration::from_secs(cfg.poll_interval.unsigned_abs());
+
+    loop {
+        match fp.texts_claim(BATCH_SIZE, LEASE).await {
+            Err(e) => {
+                error!("unable to claim texts: {}", e);
+            }
+            Ok(jobs) => {
+                for job in &jobs {
+                    process(&fp, store.as_ref(), &cfg, job).await;
+                }
+
+                // a full batch means there may be more texts pending
+                if usize::try_from(BATCH_SIZE).is_ok_and(|size| jobs.len() >= size) {
+                    continue;
+                }
+            }
+        }
+
+        actix_web::rt::time::sleep(poll_interval).await;
+    }
+}
+
+async fn process(
+    fp: &file_provider_postgres::PostgresFileProvider,
+    store: &dyn file_store::FileStore,
+    cfg: &config::SearchConfig,
+    job: &TextJob,
+) {
+    debug!("extracting text of {}", job.digest);
+
+    let result = match extract(store, cfg, job).await {
+        Ok(text) => fp.text_ready(&job.tenant_id, &job.digest, &text).await,
+        Err(Failure::GiveUp(e)) => {
+            info!("no text extracted from {}: {}", job.digest, e);
+            fp.text_failed(&job.tenant_id, &job.digest, &e, None).await
+        }
+        Err(Failure::Retry(e)) => {
+            let attempts = job.attempts + 1;
+            if attempts >= MAX_ATTEMPTS {
+                error!("giving up on text of {} after {} attempts: {}", job.digest, attempts, e);
+                fp.text_failed(&job.tenant_id, &job.digest, &e, None).await
+            } else {
+                let retry_at = chrono::Utc::now() + chrono::Duration::seconds(RETRY_DELAY);
+                info!("text of {} failed ({}), retrying at {}", job.digest, e, retry_at);
+                fp.text_failed(&job.tenant_id, &job.digest, &e, Some(&retry_at)).await
+            }
+        }
+    };
+
+    if let Err(e) = result {
+        error!("unable to update text of {}: {}", job.digest, e);
+    }
+}
+
+/// reads the content into memory and extracts its text on a blocking
+/// thread, content larger than the configured maximum is not read
+async fn extract(
+    store: &dyn file_store::FileStore,
+    cfg: &config::SearchConfig,
+    job: &TextJob,
+) -> Result<String, Failure> {
+    let stat = store.stat(job.tenant_id, &job.digest).await
+        .map_err(|e| Failure::Retry(format!("unable to read content: {e}")))?;
+    if stat.size > cfg.max_size {
+        return Err(Failure::GiveUp(format!("content of {} bytes is too large", stat.size)));
+    }
+
+    let mut data = Vec::with_capacity(usize::try_from(stat.size).unwrap_or_default());
+    let mut stream = store.get(job.tenant_id, &job.digest).await
+        .map_err(|e| Failure::Retry(format!("unable to read content: {e}")))?;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| Failure::Retry(format!("unable to read content: {e}")))?;
+        data.extend_from_slice(&chunk);
+        if data.len() as u64 > cfg.max_size {
+            return Err(Failure::GiveUp(String::from("content is too large")));
+        }
+    }
+
+    let content_type = job.source_type.clone();
+    return match actix_web::rt::task::spawn_blocking(move || text_extract::extract(&content_type, &data)).await {
+        Err(e) => Err(Failure::Retry(format!("text extraction panicked: {e}"))),
+        Ok(Err(e)) => Err(Failure::GiveUp(e.to_string())),
+        Ok(Ok(text)) => Ok(text),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use file_store::FileStore;
+
+    #[actix_web::test]
+    async fn test_extract() {
+        let dir = std::env::temp_dir().join(format!("search_test_{}", uuid::Uuid::new_v4().simple()));
+        let store = file_store_local::LocalFileStore::new(&dir.to_string_lossy());
+        let cfg = config::SearchConfig {
+            max_size: 64,
+            poll_interval: 10,
+        };
+
+        let tenant_id = uuid::Uuid::new_v4();
+        let mut jobs = Vec::new();
+        for (content, source_type) in [
+            (b"Quarterly  report\r\n\r\nrevenue".as_slice(), "text/plain"),
+            (b"%PDF-1.4 broken".as_slice(), "application/pdf"),
+            (&[b'x'; 65], "text/plain"),
+        ] {
+            let content: file_store::ByteStream = Box::pin(futures::stream::once(async move {
+                return Ok(actix_web::web::Bytes::copy_from_slice(content));
+            }));
+            let stat = store.put(tenant_id, content).await;
+            assert!(stat.is_ok(), "unable to store content");
+            jobs.push(TextJob {
+                tenant_id,
+                digest: stat.map(|s| s.digest).unwrap_or_default(),
+                source_type: String::from(source_type),
+                attempts: 0,
+            });
+        }
+
+        assert_eq!(extract(&store, &cfg, &jobs[0]).await, Ok(String::from("Quarterly report\nrevenue")));
+        // neither invalid nor too large content is tried again
+        assert!(matches!(extract(&store, &cfg, &jobs[1]).await, Err(Failure::GiveUp(_))));
+        assert!(matches!(extract(&store, &cfg, &jobs[2]).await, Err(Failure::GiveUp(_))));
+
+        let missing = TextJob {
+            digest: "0".repeat(64),
+            ..jobs.swap_remove(0)
+        };
+        assert!(matches!(extract(&store, &cfg, &missing).await, Err(Failure::Retry(_))));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}